@@ -0,0 +1,321 @@
+//! Run checkpointing — persist in-flight agent runs so they can be resumed.
+//!
+//! A [`RunCheckpoint`] captures everything a ReAct run needs to continue
+//! after a restart: the conversation so far, working memory (including the
+//! iteration counter), recalled memories, knowledge chunks and any tool
+//! calls the model requested that have not been executed yet.
+//!
+//! Checkpoints are written to a pluggable [`CheckpointStore`] after every
+//! iteration and after each tool execution. Implementations:
+//!
+//! - [`InMemoryCheckpointStore`] — process-local, for tests and ephemeral use
+//! - [`FileCheckpointStore`] — one JSON file per run under a directory
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rustedclaw_core::memory::MemoryEntry;
+use rustedclaw_core::message::{Conversation, MessageToolCall};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+
+use crate::context::assembler::KnowledgeChunk;
+use crate::context::working_memory::WorkingMemory;
+
+// ── Data Structures ───────────────────────────────────────────────────────
+
+/// Serializable snapshot of an in-flight agent run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunCheckpoint {
+    /// Unique run ID.
+    pub run_id: String,
+    /// The user message that started the run.
+    pub user_message: String,
+    /// Pattern name of the strategy that started the run ("react", "loop",
+    /// "rag"), so it resumes the same way. Older checkpoints were ReAct.
+    #[serde(default = "default_strategy")]
+    pub strategy: String,
+    /// Conversation state, including assistant tool-call messages and
+    /// tool results produced so far.
+    pub conversation: Conversation,
+    /// Working memory (trace, tool results, iteration counter).
+    pub working_memory: WorkingMemory,
+    /// Long-term memories injected into context for this run.
+    #[serde(default)]
+    pub memories: Vec<MemoryEntry>,
+    /// Knowledge chunks injected into context for this run.
+    #[serde(default)]
+    pub knowledge_chunks: Vec<KnowledgeChunk>,
+    /// Total tool calls executed so far.
+    pub tool_calls_made: usize,
+    /// Tool calls requested by the model that have not been executed yet.
+    #[serde(default)]
+    pub pending_tool_calls: Vec<MessageToolCall>,
+    /// When the run started.
+    pub started_at: DateTime<Utc>,
+    /// When this checkpoint was written.
+    pub updated_at: DateTime<Utc>,
+}
+
+fn default_strategy() -> String {
+    "react".into()
+}
+
+impl RunCheckpoint {
+    /// Create a checkpoint for a fresh run.
+    pub fn new(
+        run_id: impl Into<String>,
+        user_message: impl Into<String>,
+        conversation: Conversation,
+        working_memory: WorkingMemory,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            run_id: run_id.into(),
+            user_message: user_message.into(),
+            strategy: default_strategy(),
+            conversation,
+            working_memory,
+            memories: Vec::new(),
+            knowledge_chunks: Vec::new(),
+            tool_calls_made: 0,
+            pending_tool_calls: Vec::new(),
+            started_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Number of iterations completed so far.
+    pub fn iterations(&self) -> usize {
+        self.working_memory.iterations
+    }
+}
+
+// ── Store trait ───────────────────────────────────────────────────────────
+
+/// Persistent storage for run checkpoints.
+#[async_trait]
+pub trait CheckpointStore: Send + Sync {
+    /// Insert or replace the checkpoint for `checkpoint.run_id`.
+    async fn save(&self, checkpoint: &RunCheckpoint) -> Result<(), rustedclaw_core::Error>;
+
+    /// Load a checkpoint by run ID.
+    async fn load(&self, run_id: &str) -> Result<Option<RunCheckpoint>, rustedclaw_core::Error>;
+
+    /// Delete a checkpoint. Returns `true` if it existed.
+    async fn delete(&self, run_id: &str) -> Result<bool, rustedclaw_core::Error>;
+
+    /// List the IDs of all stored checkpoints.
+    async fn list(&self) -> Result<Vec<String>, rustedclaw_core::Error>;
+}
+
+// ── In-memory store ───────────────────────────────────────────────────────
+
+/// Process-local checkpoint store. Checkpoints do not survive restarts.
+#[derive(Default)]
+pub struct InMemoryCheckpointStore {
+    checkpoints: RwLock<HashMap<String, RunCheckpoint>>,
+}
+
+impl InMemoryCheckpointStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for InMemoryCheckpointStore {
+    async fn save(&self, checkpoint: &RunCheckpoint) -> Result<(), rustedclaw_core::Error> {
+        self.checkpoints
+            .write()
+            .await
+            .insert(checkpoint.run_id.clone(), checkpoint.clone());
+        Ok(())
+    }
+
+    async fn load(&self, run_id: &str) -> Result<Option<RunCheckpoint>, rustedclaw_core::Error> {
+        Ok(self.checkpoints.read().await.get(run_id).cloned())
+    }
+
+    async fn delete(&self, run_id: &str) -> Result<bool, rustedclaw_core::Error> {
+        Ok(self.checkpoints.write().await.remove(run_id).is_some())
+    }
+
+    async fn list(&self) -> Result<Vec<String>, rustedclaw_core::Error> {
+        Ok(self.checkpoints.read().await.keys().cloned().collect())
+    }
+}
+
+// ── File store ────────────────────────────────────────────────────────────
+
+/// File-backed checkpoint store: one `<run_id>.json` file per run.
+///
+/// Files are written to a temporary path and renamed into place, so a
+/// crash mid-write never leaves a truncated checkpoint behind.
+pub struct FileCheckpointStore {
+    dir: PathBuf,
+}
+
+impl FileCheckpointStore {
+    /// Create a store rooted at `dir` (created on first write).
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Default directory: `~/.rustedclaw/runs`
+    pub fn default_path() -> PathBuf {
+        let home = std::env::var("HOME")
+            .or_else(|_| std::env::var("USERPROFILE"))
+            .unwrap_or_else(|_| ".".to_string());
+        PathBuf::from(home).join(".rustedclaw").join("runs")
+    }
+
+    fn path_for(&self, run_id: &str) -> Result<PathBuf, rustedclaw_core::Error> {
+        validate_run_id(run_id)?;
+        Ok(self.dir.join(format!("{}.json", run_id)))
+    }
+}
+
+/// Longest run ID accepted by [`validate_run_id`].
+pub const MAX_RUN_ID_LEN: usize = 128;
+
+/// Check that `run_id` can key a checkpoint: 1 to [`MAX_RUN_ID_LEN`] ASCII
+/// letters, digits, `-` or `_`. Run IDs become file names, so anything that
+/// could escape the checkpoint directory is rejected.
+pub fn validate_run_id(run_id: &str) -> Result<(), rustedclaw_core::Error> {
+    if run_id.is_empty()
+        || run_id.len() > MAX_RUN_ID_LEN
+        || !run_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(rustedclaw_core::Error::Internal(format!(
+            "Invalid run ID: '{}'",
+            run_id
+        )));
+    }
+    Ok(())
+}
+
+fn io_error(e: std::io::Error) -> rustedclaw_core::Error {
+    rustedclaw_core::Error::Internal(format!("Checkpoint I/O failed: {}", e))
+}
+
+#[async_trait]
+impl CheckpointStore for FileCheckpointStore {
+    async fn save(&self, checkpoint: &RunCheckpoint) -> Result<(), rustedclaw_core::Error> {
+        let path = self.path_for(&checkpoint.run_id)?;
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(io_error)?;
+
+        let json = serde_json::to_vec(checkpoint)?;
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, json).await.map_err(io_error)?;
+        tokio::fs::rename(&tmp, &path).await.map_err(io_error)?;
+
+        debug!(run_id = %checkpoint.run_id, "Checkpoint saved");
+        Ok(())
+    }
+
+    async fn load(&self, run_id: &str) -> Result<Option<RunCheckpoint>, rustedclaw_core::Error> {
+        let path = self.path_for(run_id)?;
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error(e)),
+        }
+    }
+
+    async fn delete(&self, run_id: &str) -> Result<bool, rustedclaw_core::Error> {
+        let path = self.path_for(run_id)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(io_error(e)),
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<String>, rustedclaw_core::Error> {
+        let mut dir = match tokio::fs::read_dir(&self.dir).await {
+            Ok(d) => d,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(io_error(e)),
+        };
+
+        let mut ids = Vec::new();
+        while let Some(entry) = dir.next_entry().await.map_err(io_error)? {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json")
+                && let Some(stem) = path.file_stem().and_then(|s| s.to_str())
+            {
+                ids.push(stem.to_string());
+            } else {
+                warn!(path = %path.display(), "Ignoring non-checkpoint file");
+            }
+        }
+        Ok(ids)
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustedclaw_core::message::Message;
+
+    fn sample_checkpoint(run_id: &str) -> RunCheckpoint {
+        let mut conv = Conversation::new();
+        conv.push(Message::user("What is 2+3?"));
+        let mut wm = WorkingMemory::new(5);
+        wm.tick();
+        wm.add_thought("Need the calculator");
+
+        let mut cp = RunCheckpoint::new(run_id, "What is 2+3?", conv, wm);
+        cp.pending_tool_calls.push(MessageToolCall {
+            id: "call_1".into(),
+            name: "calculator".into(),
+            arguments: r#"{"expression":"2+3"}"#.into(),
+        });
+        cp
+    }
+
+    #[tokio::test]
+    async fn in_memory_roundtrip() {
+        let store = InMemoryCheckpointStore::new();
+        store.save(&sample_checkpoint("run-1")).await.unwrap();
+
+        let loaded = store.load("run-1").await.unwrap().unwrap();
+        assert_eq!(loaded.iterations(), 1);
+        assert_eq!(loaded.pending_tool_calls.len(), 1);
+        assert_eq!(store.list().await.unwrap(), vec!["run-1".to_string()]);
+
+        assert!(store.delete("run-1").await.unwrap());
+        assert!(store.load("run-1").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn file_store_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("rc-ckpt-{}", uuid::Uuid::new_v4()));
+        let store = FileCheckpointStore::new(dir.clone());
+
+        store.save(&sample_checkpoint("run-2")).await.unwrap();
+        let loaded = store.load("run-2").await.unwrap().unwrap();
+        assert_eq!(loaded.conversation.messages.len(), 1);
+        assert_eq!(loaded.working_memory.trace.len(), 1);
+        assert_eq!(store.list().await.unwrap(), vec!["run-2".to_string()]);
+
+        assert!(store.delete("run-2").await.unwrap());
+        assert!(!store.delete("run-2").await.unwrap());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn file_store_rejects_path_traversal() {
+        let store = FileCheckpointStore::new(std::env::temp_dir());
+        assert!(store.load("../etc/passwd").await.is_err());
+    }
+}
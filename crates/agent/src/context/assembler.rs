@@ -7,7 +7,7 @@
 //! 3. **Working Memory** (current plan, traces) — oldest dropped, plan kept
 //! 4. **Knowledge / RAG** (retrieved chunks) — lowest-similarity dropped
 //! 5. **Tool Schemas** (tool definitions) — least relevant dropped
-//! 6. **Conversation History** (recent turns) — oldest turns dropped
+//! 6. **Conversation History** (rolling summary + recent turns) — oldest turns dropped
 //!
 //! Implements FR-2 from the specification.
 //!
@@ -17,13 +17,19 @@
 //! identical outputs (FR-2.5). No random or time-dependent logic is
 //! used during assembly.
 
+use crate::context::layer::{ContextLayer, priority};
 use crate::context::token;
+use crate::context::tool_ranker::ToolRanker;
 use crate::context::working_memory::WorkingMemory;
+use rustedclaw_config::{ContextConfig, LayerBudgetConfig};
 use rustedclaw_core::identity::Identity;
 use rustedclaw_core::memory::MemoryEntry;
-use rustedclaw_core::message::{Conversation, Message};
+use rustedclaw_core::message::{Conversation, Message, Role};
+use rustedclaw_core::model::ModelCapabilities;
 use rustedclaw_core::provider::ToolDefinition;
+use rustedclaw_core::tokens::{HeuristicCounter, TokenCounter};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 // ── Types ─────────────────────────────────────────────────────────────────
 
@@ -59,6 +65,9 @@ pub struct PerLayerBudget {
     pub knowledge: Option<usize>,
     pub tool_schemas: Option<usize>,
     pub conversation_history: Option<usize>,
+    /// Cap on a single tool result in the history. Longer outputs are cut
+    /// to this many tokens instead of pushing their turn out of the window.
+    pub tool_output: Option<usize>,
 }
 
 impl Default for TokenBudget {
@@ -70,6 +79,59 @@ impl Default for TokenBudget {
     }
 }
 
+impl TokenBudget {
+    /// Budget for a model: its context window minus the tokens reserved
+    /// for the reply, with per-layer caps taken as percentages of that.
+    ///
+    /// The reply reservation is `max_output` (or the model's maximum if
+    /// unset), capped at half the window so small local models keep room
+    /// for the prompt. Unknown models get 8192 − 4096 = 4096 tokens, the
+    /// same as [`TokenBudget::default`].
+    pub fn for_model(
+        capabilities: &ModelCapabilities,
+        max_output: Option<u32>,
+        layers: &LayerBudgetConfig,
+    ) -> Self {
+        let window = capabilities.context_window;
+        let reserved = max_output
+            .map_or(capabilities.max_output, |m| {
+                (m as usize).min(capabilities.max_output)
+            })
+            .min(window / 2);
+        Self::from_percentages(window - reserved, layers)
+    }
+
+    /// [`for_model`](Self::for_model) with the `[context]` config section's
+    /// window override and layer percentages applied.
+    pub fn from_config(
+        capabilities: &ModelCapabilities,
+        max_output: Option<u32>,
+        config: &ContextConfig,
+    ) -> Self {
+        let capabilities = match config.context_window {
+            Some(window) => capabilities.with_context_window(window),
+            None => *capabilities,
+        };
+        Self::for_model(&capabilities, max_output, &config.layers)
+    }
+
+    /// Budget of `total` tokens with per-layer caps as percentages of it.
+    pub fn from_percentages(total: usize, layers: &LayerBudgetConfig) -> Self {
+        let share = |pct: Option<f32>| pct.map(|p| (total as f64 * p as f64 / 100.0) as usize);
+        Self {
+            total,
+            per_layer: PerLayerBudget {
+                long_term_memory: share(layers.long_term_memory),
+                working_memory: share(layers.working_memory),
+                knowledge: share(layers.knowledge),
+                tool_schemas: share(layers.tool_schemas),
+                conversation_history: share(layers.conversation_history),
+                tool_output: share(layers.tool_output),
+            },
+        }
+    }
+}
+
 /// All inputs required by the assembler for a single LLM call.
 pub struct AssemblyInput<'a> {
     /// Agent identity (system prompt source).
@@ -114,6 +176,9 @@ pub struct AssemblyMetadata {
     pub per_layer: Vec<LayerStats>,
     /// Items dropped from each layer.
     pub drops: Vec<DropInfo>,
+    /// Tools left out of the request, most relevant first.
+    #[serde(default)]
+    pub hidden_tools: Vec<String>,
 }
 
 /// Statistics for a single context layer.
@@ -142,6 +207,13 @@ pub struct DropInfo {
     pub reason: String,
 }
 
+/// [`DropInfo::reason`] for history turns that fell out of the sliding window.
+pub const HISTORY_WINDOW_DROP: &str = "Oldest turns dropped (sliding window)";
+/// [`DropInfo::reason`] for tool results whose tool call is no longer in history.
+pub const HISTORY_ORPHAN_DROP: &str = "Orphaned tool results dropped";
+/// [`DropInfo::reason`] for tool results cut to `PerLayerBudget::tool_output`.
+pub const HISTORY_TOOL_OUTPUT_TRUNCATED: &str = "Tool outputs truncated";
+
 /// Errors from context assembly.
 #[derive(Debug, Clone)]
 pub enum AssemblyError {
@@ -176,12 +248,52 @@ impl std::error::Error for AssemblyError {}
 /// The context assembler. Stateless — create one and reuse it.
 pub struct ContextAssembler {
     budget: TokenBudget,
+    counter: Arc<dyn TokenCounter>,
+    tool_ranker: ToolRanker,
+    layers: Vec<Arc<dyn ContextLayer>>,
+}
+
+/// One step of the fill order.
+enum Slot<'a> {
+    LongTermMemory,
+    WorkingMemory,
+    Knowledge,
+    ToolSchemas,
+    ConversationHistory,
+    Custom(&'a dyn ContextLayer),
 }
 
 impl ContextAssembler {
     /// Create a new assembler with the given token budget.
+    ///
+    /// Counts tokens with the heuristic until [`with_counter`](Self::with_counter)
+    /// installs the model's tokenizer.
     pub fn new(budget: TokenBudget) -> Self {
-        Self { budget }
+        Self {
+            budget,
+            counter: Arc::new(HeuristicCounter),
+            tool_ranker: ToolRanker::default(),
+            layers: Vec::new(),
+        }
+    }
+
+    /// Count tokens with a model-specific counter.
+    pub fn with_counter(mut self, counter: Arc<dyn TokenCounter>) -> Self {
+        self.counter = counter;
+        self
+    }
+
+    /// Rank tool schemas with `ranker` (pinned tools, embeddings) instead of
+    /// keyword relevance alone.
+    pub fn with_tool_ranker(mut self, ranker: ToolRanker) -> Self {
+        self.tool_ranker = ranker;
+        self
+    }
+
+    /// Register a custom layer, filled at its [`ContextLayer::priority`].
+    pub fn with_layer(mut self, layer: Arc<dyn ContextLayer>) -> Self {
+        self.layers.push(layer);
+        self
     }
 
     /// Create an assembler with the default budget (4096 tokens).
@@ -189,14 +301,15 @@ impl ContextAssembler {
         Self::new(TokenBudget::default())
     }
 
-    /// Assemble context from all six layers.
+    /// Assemble context from the six built-in layers and any custom ones.
     ///
     /// # Algorithm
     ///
     /// 1. Compute tokens for system prompt + user message (always included)
     /// 2. If those exceed the budget → return error
     /// 3. Fill remaining budget in priority order:
-    ///    Long-Term Memory → Working Memory → Knowledge/RAG → Tool Schemas → Conversation History
+    ///    Long-Term Memory → Working Memory → Knowledge/RAG → Tool Schemas → Conversation History,
+    ///    with custom layers slotted in by their priority
     /// 4. Return assembled context + metadata
     pub fn assemble(&self, input: &AssemblyInput<'_>) -> Result<AssembledContext, AssemblyError> {
         let mut stats: Vec<LayerStats> = Vec::new();
@@ -204,7 +317,7 @@ impl ContextAssembler {
 
         // ── Layer 1: System prompt (always included, never trimmed) ────────
         let system_prompt = &input.identity.system_prompt;
-        let system_tokens = token::estimate_tokens(system_prompt);
+        let system_tokens = self.counter.count(system_prompt);
         stats.push(LayerStats {
             name: "system".into(),
             tokens: system_tokens,
@@ -213,7 +326,7 @@ impl ContextAssembler {
         });
 
         // User message tokens (always included)
-        let user_tokens = token::estimate_tokens(input.user_message) + 4; // +4 message overhead
+        let user_tokens = self.counter.count(input.user_message) + 4; // +4 message overhead
 
         // Guard: system + user must fit
         let reserved = system_tokens + user_tokens;
@@ -227,69 +340,123 @@ impl ContextAssembler {
 
         let mut remaining = self.budget.total - reserved;
         let mut context_sections: Vec<String> = Vec::new();
-
-        // ── Layer 2: Long-Term Memory ──────────────────────────────────────
-        let (mem_section, mem_stats, mem_drop) = Self::render_memory_layer(
-            input.memories,
-            self.effective_budget(self.budget.per_layer.long_term_memory, remaining),
-        );
-        remaining -= mem_stats.tokens;
-        if !mem_section.is_empty() {
-            context_sections.push(mem_section);
-        }
-        stats.push(mem_stats);
-        if let Some(d) = mem_drop {
-            drops.push(d);
-        }
-
-        // ── Layer 3: Working Memory ────────────────────────────────────────
-        let (wm_section, wm_stats, wm_drop) = Self::render_working_memory_layer(
-            input.working_memory,
-            self.effective_budget(self.budget.per_layer.working_memory, remaining),
-        );
-        remaining -= wm_stats.tokens;
-        if !wm_section.is_empty() {
-            context_sections.push(wm_section);
-        }
-        stats.push(wm_stats);
-        if let Some(d) = wm_drop {
-            drops.push(d);
-        }
-
-        // ── Layer 4: Knowledge / RAG ───────────────────────────────────────
-        let (rag_section, rag_stats, rag_drop) = Self::render_knowledge_layer(
-            input.knowledge_chunks,
-            self.effective_budget(self.budget.per_layer.knowledge, remaining),
-        );
-        remaining -= rag_stats.tokens;
-        if !rag_section.is_empty() {
-            context_sections.push(rag_section);
-        }
-        stats.push(rag_stats);
-        if let Some(d) = rag_drop {
-            drops.push(d);
-        }
-
-        // ── Layer 5: Tool Schemas ──────────────────────────────────────────
-        let (tools_included, tool_stats, tool_drop) = Self::render_tool_layer(
-            input.tool_definitions,
-            self.effective_budget(self.budget.per_layer.tool_schemas, remaining),
-        );
-        remaining -= tool_stats.tokens;
-        stats.push(tool_stats);
-        if let Some(d) = tool_drop {
-            drops.push(d);
-        }
-
-        // ── Layer 6: Conversation History ──────────────────────────────────
-        let (history_messages, hist_stats, hist_drop) = Self::render_history_layer(
-            input.conversation,
-            self.effective_budget(self.budget.per_layer.conversation_history, remaining),
+        let mut tools_included = Vec::new();
+        let mut hidden_tools = Vec::new();
+        let mut history_messages = Vec::new();
+
+        // Built-in and custom layers, filled in priority order. The sort is
+        // stable, so built-ins precede custom layers of equal priority.
+        let mut slots: Vec<(i32, Slot<'_>)> = vec![
+            (priority::LONG_TERM_MEMORY, Slot::LongTermMemory),
+            (priority::WORKING_MEMORY, Slot::WorkingMemory),
+            (priority::KNOWLEDGE, Slot::Knowledge),
+            (priority::TOOL_SCHEMAS, Slot::ToolSchemas),
+            (priority::CONVERSATION_HISTORY, Slot::ConversationHistory),
+        ];
+        slots.extend(
+            self.layers
+                .iter()
+                .map(|l| (l.priority(), Slot::Custom(l.as_ref()))),
         );
-        // remaining -= hist_stats.tokens; // last layer, not needed
-        stats.push(hist_stats);
-        if let Some(d) = hist_drop {
-            drops.push(d);
+        slots.sort_by_key(|(priority, _)| *priority);
+
+        for (_, slot) in slots {
+            match slot {
+                // ── Long-Term Memory ──────────────────────────────────────
+                Slot::LongTermMemory => {
+                    let (section, layer_stats, drop) = self.render_memory_layer(
+                        input.memories,
+                        self.effective_budget(self.budget.per_layer.long_term_memory, remaining),
+                    );
+                    remaining -= layer_stats.tokens;
+                    if !section.is_empty() {
+                        context_sections.push(section);
+                    }
+                    stats.push(layer_stats);
+                    drops.extend(drop);
+                }
+
+                // ── Working Memory ────────────────────────────────────────
+                Slot::WorkingMemory => {
+                    let (section, layer_stats, drop) = self.render_working_memory_layer(
+                        input.working_memory,
+                        self.effective_budget(self.budget.per_layer.working_memory, remaining),
+                    );
+                    remaining -= layer_stats.tokens;
+                    if !section.is_empty() {
+                        context_sections.push(section);
+                    }
+                    stats.push(layer_stats);
+                    drops.extend(drop);
+                }
+
+                // ── Knowledge / RAG ───────────────────────────────────────
+                Slot::Knowledge => {
+                    let (section, layer_stats, drop) = self.render_knowledge_layer(
+                        input.knowledge_chunks,
+                        self.effective_budget(self.budget.per_layer.knowledge, remaining),
+                    );
+                    remaining -= layer_stats.tokens;
+                    if !section.is_empty() {
+                        context_sections.push(section);
+                    }
+                    stats.push(layer_stats);
+                    drops.extend(drop);
+                }
+
+                // ── Tool Schemas (ranked by relevance) ────────────────────
+                Slot::ToolSchemas => {
+                    let (included, hidden, layer_stats, drop) = self.render_tool_layer(
+                        input.tool_definitions,
+                        input.user_message,
+                        self.effective_budget(self.budget.per_layer.tool_schemas, remaining),
+                        remaining,
+                    );
+                    remaining -= layer_stats.tokens;
+                    tools_included = included;
+                    hidden_tools = hidden;
+                    stats.push(layer_stats);
+                    drops.extend(drop);
+                }
+
+                // ── Conversation History (rolling summary + recent turns) ─
+                Slot::ConversationHistory => {
+                    let mut history_budget = self
+                        .effective_budget(self.budget.per_layer.conversation_history, remaining);
+                    if let Some(summary) = &input.conversation.summary {
+                        let (section, layer_stats, drop) =
+                            self.render_summary_layer(&summary.text, history_budget);
+                        history_budget -= layer_stats.tokens;
+                        remaining -= layer_stats.tokens;
+                        if !section.is_empty() {
+                            context_sections.push(section);
+                        }
+                        stats.push(layer_stats);
+                        drops.extend(drop);
+                    }
+                    let (messages, layer_stats, layer_drops) =
+                        self.render_history_layer(input.conversation, history_budget);
+                    remaining -= layer_stats.tokens;
+                    history_messages = messages;
+                    stats.push(layer_stats);
+                    drops.extend(layer_drops);
+                }
+
+                // ── Custom layers ─────────────────────────────────────────
+                Slot::Custom(layer) => {
+                    let (section, layer_stats, drop) = self.render_custom_layer(
+                        layer,
+                        input,
+                        self.effective_budget(layer.max_tokens(), remaining),
+                    );
+                    remaining -= layer_stats.tokens;
+                    if !section.is_empty() {
+                        context_sections.push(section);
+                    }
+                    stats.push(layer_stats);
+                    drops.extend(drop);
+                }
+            }
         }
 
         // ── Assemble final system message ──────────────────────────────────
@@ -325,6 +492,7 @@ impl ContextAssembler {
                 utilization_pct,
                 per_layer: stats,
                 drops,
+                hidden_tools,
             },
         })
     }
@@ -332,6 +500,7 @@ impl ContextAssembler {
     // ── Private layer renderers ───────────────────────────────────────────
 
     fn render_memory_layer(
+        &self,
         memories: &[MemoryEntry],
         budget: usize,
     ) -> (String, LayerStats, Option<DropInfo>) {
@@ -341,11 +510,11 @@ impl ContextAssembler {
         }
 
         let header = "[Long-Term Memory]\n";
-        let header_tokens = token::estimate_tokens(header);
+        let header_tokens = self.counter.count(header);
         if header_tokens >= budget {
             let dropped_tokens: usize = memories
                 .iter()
-                .map(|m| token::estimate_tokens(&m.content) + 2)
+                .map(|m| self.counter.count(&m.content) + 2)
                 .sum();
             return (
                 String::new(),
@@ -366,7 +535,7 @@ impl ContextAssembler {
 
         for entry in memories {
             let line = format!("- {}\n", entry.content);
-            let line_tokens = token::estimate_tokens(&line);
+            let line_tokens = self.counter.count(&line);
             if used + line_tokens <= budget {
                 lines.push(line);
                 used += line_tokens;
@@ -395,6 +564,7 @@ impl ContextAssembler {
     }
 
     fn render_working_memory_layer(
+        &self,
         wm: &WorkingMemory,
         budget: usize,
     ) -> (String, LayerStats, Option<DropInfo>) {
@@ -405,7 +575,7 @@ impl ContextAssembler {
 
         let header = "[Working Memory]\n";
         let full_render = format!("{}{}", header, wm.render());
-        let full_tokens = token::estimate_tokens(&full_render);
+        let full_tokens = self.counter.count(&full_render);
         let item_count = wm.item_count();
 
         if full_tokens <= budget {
@@ -424,7 +594,7 @@ impl ContextAssembler {
 
         // Doesn't fit — trim. Keep plan (always), drop oldest trace entries.
         let mut out = String::from(header);
-        let mut used = token::estimate_tokens(header);
+        let mut used = self.counter.count(header);
         let mut included = 0;
         let mut dropped_count = 0;
         let mut dropped_tokens = 0;
@@ -432,7 +602,7 @@ impl ContextAssembler {
         // Always include plan if present
         if let Some(plan) = &wm.plan {
             let plan_text = format!("Goal: {}\n", plan.goal);
-            let plan_tokens = token::estimate_tokens(&plan_text);
+            let plan_tokens = self.counter.count(&plan_text);
             if used + plan_tokens <= budget {
                 out.push_str(&plan_text);
                 used += plan_tokens;
@@ -449,7 +619,7 @@ impl ContextAssembler {
                 super::working_memory::TraceKind::Reflection => "Reflection",
             };
             let line = format!("[{}] {}\n", label, entry.content);
-            let line_tokens = token::estimate_tokens(&line);
+            let line_tokens = self.counter.count(&line);
             if used + line_tokens <= budget {
                 out.push_str(&line);
                 used += line_tokens;
@@ -478,6 +648,7 @@ impl ContextAssembler {
     }
 
     fn render_knowledge_layer(
+        &self,
         chunks: &[KnowledgeChunk],
         budget: usize,
     ) -> (String, LayerStats, Option<DropInfo>) {
@@ -487,11 +658,11 @@ impl ContextAssembler {
         }
 
         let header = "[Retrieved Knowledge]\n";
-        let header_tokens = token::estimate_tokens(header);
+        let header_tokens = self.counter.count(header);
         if header_tokens >= budget {
             let dropped_tokens: usize = chunks
                 .iter()
-                .map(|c| token::estimate_tokens(&c.content) + 4)
+                .map(|c| self.counter.count(&c.content) + 4)
                 .sum();
             return (
                 String::new(),
@@ -513,7 +684,7 @@ impl ContextAssembler {
         // Chunks are pre-sorted by similarity (highest first)
         for chunk in chunks {
             let entry = format!("[Source: {}] {}\n", chunk.source, chunk.content);
-            let entry_tokens = token::estimate_tokens(&entry);
+            let entry_tokens = self.counter.count(&entry);
             if used + entry_tokens <= budget {
                 lines.push(entry);
                 used += entry_tokens;
@@ -546,76 +717,220 @@ impl ContextAssembler {
         )
     }
 
+    /// Fill the tool layer in relevance order. Pinned tools are exempt from
+    /// the layer cap (`budget`) but must still fit in the `remaining` total.
+    /// Included tools keep registry order; hidden ones are returned by name.
     fn render_tool_layer(
+        &self,
         tools: &[ToolDefinition],
+        user_message: &str,
         budget: usize,
-    ) -> (Vec<ToolDefinition>, LayerStats, Option<DropInfo>) {
+        remaining: usize,
+    ) -> (
+        Vec<ToolDefinition>,
+        Vec<String>,
+        LayerStats,
+        Option<DropInfo>,
+    ) {
         let layer = "tool_schemas";
         if tools.is_empty() {
-            return (Vec::new(), Self::empty_stats(layer, 0), None);
+            return (Vec::new(), Vec::new(), Self::empty_stats(layer, 0), None);
         }
 
         let mut used = 0;
-        let mut included = Vec::new();
-        let mut dropped = 0;
+        let mut keep = vec![false; tools.len()];
+        let mut hidden = Vec::new();
         let mut dropped_tokens = 0;
 
-        for tool in tools {
-            let tool_tokens = token::estimate_tool_tokens(tool);
-            if used + tool_tokens <= budget {
-                included.push(tool.clone());
+        for i in self.tool_ranker.rank(user_message, tools) {
+            let tool = &tools[i];
+            let tool_tokens = token::count_tool_tokens(self.counter.as_ref(), tool);
+            let limit = if self.tool_ranker.is_pinned(&tool.name) {
+                remaining
+            } else {
+                budget
+            };
+            if used + tool_tokens <= limit {
+                keep[i] = true;
                 used += tool_tokens;
             } else {
-                dropped += 1;
+                hidden.push(tool.name.clone());
                 dropped_tokens += tool_tokens;
             }
         }
 
+        let included: Vec<ToolDefinition> = tools
+            .iter()
+            .zip(&keep)
+            .filter(|(_, keep)| **keep)
+            .map(|(tool, _)| tool.clone())
+            .collect();
         (
             included,
+            hidden.clone(),
             LayerStats {
                 name: layer.into(),
                 tokens: used,
-                items_included: tools.len() - dropped,
+                items_included: tools.len() - hidden.len(),
                 items_total: tools.len(),
             },
             Self::maybe_drop(
                 layer,
-                dropped,
+                hidden.len(),
                 dropped_tokens,
                 "Least-relevant tools dropped",
             ),
         )
     }
 
+    fn render_custom_layer(
+        &self,
+        layer: &dyn ContextLayer,
+        input: &AssemblyInput<'_>,
+        budget: usize,
+    ) -> (String, LayerStats, Option<DropInfo>) {
+        let name = layer.name();
+        let items = layer.items(input);
+        if items.is_empty() {
+            return (String::new(), Self::empty_stats(name, 0), None);
+        }
+
+        let header = format!("{}\n", layer.heading());
+        let mut used = self.counter.count(&header);
+        let mut lines = Vec::new();
+        let mut dropped = 0;
+        let mut dropped_tokens = 0;
+
+        // Keep the leading run of items that fits; the rest are dropped.
+        for item in &items {
+            let line = format!("- {}\n", item);
+            let line_tokens = self.counter.count(&line);
+            if dropped == 0 && used + line_tokens <= budget {
+                lines.push(line);
+                used += line_tokens;
+            } else {
+                dropped += 1;
+                dropped_tokens += line_tokens;
+            }
+        }
+
+        if lines.is_empty() {
+            return (
+                String::new(),
+                Self::empty_stats(name, items.len()),
+                Self::maybe_drop(name, dropped, dropped_tokens, "Layer budget exhausted"),
+            );
+        }
+        (
+            format!("{}{}", header, lines.join("")),
+            LayerStats {
+                name: name.into(),
+                tokens: used,
+                items_included: lines.len(),
+                items_total: items.len(),
+            },
+            Self::maybe_drop(name, dropped, dropped_tokens, "Trailing items dropped"),
+        )
+    }
+
+    fn render_summary_layer(
+        &self,
+        summary: &str,
+        budget: usize,
+    ) -> (String, LayerStats, Option<DropInfo>) {
+        let layer = "conversation_summary";
+        let section = format!("[Conversation Summary]\n{}", summary);
+        let tokens = self.counter.count(&section);
+        if tokens > budget {
+            return (
+                String::new(),
+                Self::empty_stats(layer, 1),
+                Self::maybe_drop(layer, 1, tokens, "Summary exceeds history budget"),
+            );
+        }
+        (
+            section,
+            LayerStats {
+                name: layer.into(),
+                tokens,
+                items_included: 1,
+                items_total: 1,
+            },
+            None,
+        )
+    }
+
     fn render_history_layer(
+        &self,
         conversation: &Conversation,
         budget: usize,
-    ) -> (Vec<Message>, LayerStats, Option<DropInfo>) {
+    ) -> (Vec<Message>, LayerStats, Vec<DropInfo>) {
         let layer = "conversation_history";
-        let messages = &conversation.messages;
+        let messages = conversation.unsummarized();
         if messages.is_empty() {
-            return (Vec::new(), Self::empty_stats(layer, 0), None);
+            return (Vec::new(), Self::empty_stats(layer, 0), Vec::new());
+        }
+
+        // Group turns so an assistant message carrying tool calls and the
+        // tool results answering it are kept or dropped together; providers
+        // reject a tool result whose call is missing. Results without a
+        // preceding call (e.g. the call was summarized away) are dropped.
+        // Skip system messages (Layer 1 handles that).
+        let mut groups: Vec<Vec<&Message>> = Vec::new();
+        let mut orphans = 0;
+        let mut orphan_tokens = 0;
+        for msg in messages.iter().filter(|m| m.role != Role::System) {
+            if msg.role != Role::Tool {
+                groups.push(vec![msg]);
+                continue;
+            }
+            match groups.last_mut() {
+                Some(group) if answers_call(group[0], msg) => group.push(msg),
+                _ => {
+                    orphans += 1;
+                    orphan_tokens += self.counter.count_message(msg);
+                }
+            }
         }
 
         let mut used = 0;
         let mut included = Vec::new();
         let mut dropped = 0;
         let mut dropped_tokens = 0;
-
-        // Sliding window: include from newest (end) → oldest.
-        // Skip system messages (Layer 1 handles that).
-        for msg in messages.iter().rev() {
-            if msg.role == rustedclaw_core::message::Role::System {
-                continue;
+        let mut truncated = 0;
+        let mut truncated_tokens = 0;
+
+        // Sliding window: include from newest (end) → oldest, stopping at
+        // the first group that does not fit so the window stays contiguous.
+        for group in groups.iter().rev() {
+            let mut rendered = Vec::with_capacity(group.len());
+            let mut group_tokens = 0;
+            let mut group_truncated = 0;
+            let mut group_saved = 0;
+            for msg in group {
+                let mut msg = (*msg).clone();
+                if msg.role == Role::Tool
+                    && let Some(cap) = self.budget.per_layer.tool_output
+                    && let Some(cut) = self.truncate_to_tokens(&msg.content, cap)
+                {
+                    let before = self.counter.count_message(&msg);
+                    msg.content = cut;
+                    group_truncated += 1;
+                    group_saved += before - self.counter.count_message(&msg);
+                }
+                group_tokens += self.counter.count_message(&msg);
+                rendered.push(msg);
             }
-            let msg_tokens = token::estimate_message_tokens(msg);
-            if used + msg_tokens <= budget {
-                included.push(msg.clone());
-                used += msg_tokens;
+
+            if dropped == 0 && used + group_tokens <= budget {
+                // Pushed in reverse; the final reverse restores group order.
+                included.extend(rendered.into_iter().rev());
+                used += group_tokens;
+                truncated += group_truncated;
+                truncated_tokens += group_saved;
             } else {
-                dropped += 1;
-                dropped_tokens += msg_tokens;
+                dropped += group.len();
+                dropped_tokens += group_tokens + group_saved;
             }
         }
 
@@ -623,6 +938,16 @@ impl ContextAssembler {
         included.reverse();
 
         let included_count = included.len();
+        let drops = [
+            Self::maybe_drop(layer, orphans, orphan_tokens, HISTORY_ORPHAN_DROP),
+            Self::maybe_drop(layer, dropped, dropped_tokens, HISTORY_WINDOW_DROP),
+            Self::maybe_drop(
+                layer,
+                truncated,
+                truncated_tokens,
+                HISTORY_TOOL_OUTPUT_TRUNCATED,
+            ),
+        ];
         (
             included,
             LayerStats {
@@ -631,15 +956,35 @@ impl ContextAssembler {
                 items_included: included_count,
                 items_total: messages.len(),
             },
-            Self::maybe_drop(
-                layer,
-                dropped,
-                dropped_tokens,
-                "Oldest turns dropped (sliding window)",
-            ),
+            drops.into_iter().flatten().collect(),
         )
     }
 
+    /// Cut `text` to at most `cap` tokens including a truncation marker,
+    /// or `None` if it already fits.
+    fn truncate_to_tokens(&self, text: &str, cap: usize) -> Option<String> {
+        let total = self.counter.count(text);
+        if total <= cap {
+            return None;
+        }
+        let marker = format!("\n[... truncated, {total} tokens in full]");
+        let fits =
+            |end: usize| self.counter.count(&text[..end]) + self.counter.count(&marker) <= cap;
+
+        // Longest prefix (on a char boundary) that fits with the marker.
+        let bounds: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+        let (mut lo, mut hi) = (0, bounds.len() - 1);
+        while lo < hi {
+            let mid = (lo + hi).div_ceil(2);
+            if fits(bounds[mid]) {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+        Some(format!("{}{marker}", &text[..bounds[lo]]))
+    }
+
     // ── Helpers ────────────────────────────────────────────────────────────
 
     fn effective_budget(&self, per_layer_limit: Option<usize>, remaining: usize) -> usize {
@@ -672,12 +1017,24 @@ impl ContextAssembler {
     }
 }
 
+/// Whether `result` answers one of the tool calls made by `call`.
+fn answers_call(call: &Message, result: &Message) -> bool {
+    call.role == Role::Assistant
+        && !call.tool_calls.is_empty()
+        && result
+            .tool_call_id
+            .as_ref()
+            .is_none_or(|id| call.tool_calls.iter().any(|c| &c.id == id))
+}
+
 // ── Tests ─────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::context::layer::StaticLayer;
     use chrono::Utc;
+    use rustedclaw_core::memory::MemoryScope;
 
     // ── Helpers ────────────────────────────────────────────────────────
 
@@ -693,8 +1050,12 @@ mod tests {
             source: None,
             created_at: Utc::now(),
             last_accessed: Utc::now(),
+            access_count: 0,
+            expires_at: None,
             score: 0.0,
             embedding: None,
+            embedding_model: None,
+            scope: MemoryScope::default(),
         }
     }
 
@@ -766,6 +1127,36 @@ mod tests {
         assert_eq!(result.messages[0].content, "Hello");
     }
 
+    /// One token per character — far denser than the heuristic.
+    struct CharCounter;
+
+    impl TokenCounter for CharCounter {
+        fn name(&self) -> &str {
+            "chars"
+        }
+
+        fn count(&self, text: &str) -> usize {
+            text.chars().count()
+        }
+    }
+
+    #[test]
+    fn custom_counter_drives_budget() {
+        let id = test_identity();
+        let wm = WorkingMemory::default();
+        let conv = Conversation::new();
+        let input = default_input(&id, &wm, &conv);
+
+        let heuristic = ContextAssembler::with_default_budget()
+            .assemble(&input)
+            .unwrap();
+        let dense = ContextAssembler::with_default_budget()
+            .with_counter(Arc::new(CharCounter))
+            .assemble(&input)
+            .unwrap();
+        assert!(dense.metadata.total_tokens > heuristic.metadata.total_tokens);
+    }
+
     #[test]
     fn budget_exceeded_returns_error() {
         let asm = ContextAssembler::new(TokenBudget {
@@ -896,6 +1287,114 @@ mod tests {
         assert_eq!(tool_layer.items_included, 2);
     }
 
+    #[test]
+    fn tools_selected_by_relevance_and_hidden_reported() {
+        let id = test_identity();
+        let wm = WorkingMemory::default();
+        let conv = Conversation::new();
+        let mut tools = vec![test_tool("calculator"), test_tool("shell")];
+        tools.push(ToolDefinition {
+            name: "web_search".into(),
+            description: "Search the web".into(),
+            parameters: serde_json::json!({"type": "object", "properties": {}}),
+        });
+        let one_tool = token::count_tool_tokens(&HeuristicCounter, &tools[2]);
+        let budget = |tool_schemas| TokenBudget {
+            total: 4096,
+            per_layer: PerLayerBudget {
+                tool_schemas: Some(tool_schemas),
+                ..Default::default()
+            },
+        };
+        let input = AssemblyInput {
+            tool_definitions: &tools,
+            user_message: "Search the web for Rust news",
+            ..default_input(&id, &wm, &conv)
+        };
+
+        let result = ContextAssembler::new(budget(one_tool))
+            .assemble(&input)
+            .unwrap();
+        let names: Vec<&str> = result
+            .tool_definitions
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["web_search"]);
+        assert_eq!(result.metadata.hidden_tools, vec!["calculator", "shell"]);
+
+        // A pinned tool is kept even over the layer cap, in registry order.
+        let result = ContextAssembler::new(budget(one_tool))
+            .with_tool_ranker(ToolRanker::new().with_pinned(["shell"]))
+            .assemble(&input)
+            .unwrap();
+        let names: Vec<&str> = result
+            .tool_definitions
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["shell"]);
+        assert_eq!(
+            result.metadata.hidden_tools,
+            vec!["web_search", "calculator"]
+        );
+    }
+
+    #[test]
+    fn custom_layers_rendered_by_priority_and_reported() {
+        let id = test_identity();
+        let wm = WorkingMemory::default();
+        let conv = Conversation::new();
+        let memories = vec![test_memory("User likes Rust")];
+        let input = AssemblyInput {
+            memories: &memories,
+            ..default_input(&id, &wm, &conv)
+        };
+
+        let profile = StaticLayer::new(
+            "user_profile",
+            priority::LONG_TERM_MEMORY - 1,
+            vec!["Name: Ada".into(), "Timezone: UTC".into()],
+        );
+        let tickets = StaticLayer::new(
+            "open_tickets",
+            priority::KNOWLEDGE,
+            vec!["#12 Fix login".into(), "#13 ".repeat(20)],
+        )
+        .with_max_tokens(10);
+        let result = ContextAssembler::with_default_budget()
+            .with_layer(Arc::new(tickets))
+            .with_layer(Arc::new(profile))
+            .assemble(&input)
+            .unwrap();
+
+        let sys = &result.system_message;
+        let profile_at = sys.find("[user_profile]").unwrap();
+        let memory_at = sys.find("[Long-Term Memory]").unwrap();
+        let tickets_at = sys.find("[open_tickets]").unwrap();
+        assert!(profile_at < memory_at && memory_at < tickets_at);
+        assert!(sys.contains("- #12 Fix login"));
+
+        let tickets_stats = result
+            .metadata
+            .per_layer
+            .iter()
+            .find(|l| l.name == "open_tickets")
+            .unwrap();
+        assert_eq!(tickets_stats.items_included, 1);
+        assert_eq!(tickets_stats.items_total, 2);
+        assert!(tickets_stats.tokens <= 10);
+        assert!(
+            result
+                .metadata
+                .drops
+                .iter()
+                .any(|d| d.layer == "open_tickets" && d.items_dropped == 1)
+        );
+        let total: usize = result.metadata.per_layer.iter().map(|l| l.tokens).sum();
+        assert_eq!(total, result.metadata.total_tokens);
+    }
+
     #[test]
     fn conversation_history_sliding_window() {
         let asm = ContextAssembler::new(TokenBudget {
@@ -950,6 +1449,163 @@ mod tests {
         );
     }
 
+    /// user → assistant(tool call) → tool result (200 chars) → assistant.
+    fn tool_turn_conversation() -> Conversation {
+        let mut conv = Conversation::new();
+        conv.push(Message::user("Find it"));
+        let mut call = Message::assistant("");
+        call.tool_calls
+            .push(rustedclaw_core::message::MessageToolCall {
+                id: "call_1".into(),
+                name: "search".into(),
+                arguments: r#"{"q":"rust"}"#.into(),
+            });
+        conv.push(call);
+        conv.push(Message::tool_result("call_1", "x".repeat(200)));
+        conv.push(Message::assistant("Done"));
+        conv
+    }
+
+    fn history_budget(history: usize, tool_output: Option<usize>) -> TokenBudget {
+        TokenBudget {
+            total: 4096,
+            per_layer: PerLayerBudget {
+                conversation_history: Some(history),
+                tool_output,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn tool_call_and_result_trimmed_together() {
+        let id = test_identity();
+        let wm = WorkingMemory::default();
+        let conv = tool_turn_conversation();
+
+        // Room for the final answer and the tool result (5 + 54 tokens),
+        // but not for the call that produced it (9 more).
+        let asm = ContextAssembler::new(history_budget(64, None));
+        let result = asm.assemble(&default_input(&id, &wm, &conv)).unwrap();
+        assert_eq!(result.messages.len(), 2); // "Done" + current user message
+        assert!(result.messages.iter().all(|m| m.role != Role::Tool));
+        let drop = result
+            .metadata
+            .drops
+            .iter()
+            .find(|d| d.reason == HISTORY_WINDOW_DROP)
+            .unwrap();
+        assert_eq!(drop.items_dropped, 3);
+
+        // With room for the whole group, call and result stay paired.
+        let asm = ContextAssembler::new(history_budget(68, None));
+        let result = asm.assemble(&default_input(&id, &wm, &conv)).unwrap();
+        let roles: Vec<Role> = result.messages.iter().map(|m| m.role.clone()).collect();
+        assert_eq!(
+            roles,
+            vec![Role::Assistant, Role::Tool, Role::Assistant, Role::User]
+        );
+    }
+
+    #[test]
+    fn oversized_tool_output_truncated_instead_of_dropped() {
+        let id = test_identity();
+        let wm = WorkingMemory::default();
+        let conv = tool_turn_conversation();
+
+        let asm = ContextAssembler::new(history_budget(64, Some(20)));
+        let result = asm.assemble(&default_input(&id, &wm, &conv)).unwrap();
+        let tool_msg = result
+            .messages
+            .iter()
+            .find(|m| m.role == Role::Tool)
+            .expect("tool result kept");
+        assert!(tool_msg.content.starts_with("xxxx"));
+        assert!(
+            tool_msg
+                .content
+                .ends_with("[... truncated, 50 tokens in full]")
+        );
+        assert!(HeuristicCounter.count(&tool_msg.content) <= 20);
+
+        let drop = result
+            .metadata
+            .drops
+            .iter()
+            .find(|d| d.reason == HISTORY_TOOL_OUTPUT_TRUNCATED)
+            .unwrap();
+        assert_eq!(drop.items_dropped, 1);
+        assert!(drop.tokens_dropped > 0);
+        assert!(
+            !result
+                .metadata
+                .drops
+                .iter()
+                .any(|d| d.reason == HISTORY_WINDOW_DROP)
+        );
+    }
+
+    #[test]
+    fn orphaned_tool_results_dropped() {
+        let id = test_identity();
+        let wm = WorkingMemory::default();
+        let mut conv = tool_turn_conversation();
+        // The tool call was folded into the summary; its result was not.
+        conv.summary = Some(rustedclaw_core::message::ConversationSummary {
+            text: "User asked to find something.".into(),
+            covered_messages: 2,
+            updated_at: Utc::now(),
+        });
+
+        let asm = ContextAssembler::with_default_budget();
+        let result = asm.assemble(&default_input(&id, &wm, &conv)).unwrap();
+        assert!(result.messages.iter().all(|m| m.role != Role::Tool));
+        let drop = result
+            .metadata
+            .drops
+            .iter()
+            .find(|d| d.reason == HISTORY_ORPHAN_DROP)
+            .unwrap();
+        assert_eq!(drop.items_dropped, 1);
+    }
+
+    #[test]
+    fn budget_sized_to_model_window() {
+        let claude = ModelCapabilities::for_model("anthropic/claude-sonnet-4");
+        let budget = TokenBudget::for_model(&claude, Some(4096), &LayerBudgetConfig::default());
+        assert_eq!(budget.total, 200_000 - 4096);
+        assert!(budget.per_layer.knowledge.is_none());
+
+        // Unknown models keep the historical 4096-token default.
+        let unknown = ModelCapabilities::for_model("mock-model");
+        let budget = TokenBudget::for_model(&unknown, None, &LayerBudgetConfig::default());
+        assert_eq!(budget.total, TokenBudget::default().total);
+
+        // A tiny local model never reserves more than half its window.
+        let tiny = ModelCapabilities::for_model("tinyllama");
+        let budget = TokenBudget::for_model(&tiny, Some(4096), &LayerBudgetConfig::default());
+        assert_eq!(budget.total, 2048 - 512);
+    }
+
+    #[test]
+    fn budget_layer_percentages_from_config() {
+        let config = ContextConfig {
+            context_window: Some(10_000),
+            layers: LayerBudgetConfig {
+                knowledge: Some(25.0),
+                tool_output: Some(5.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let caps = ModelCapabilities::for_model("gpt-4o");
+        let budget = TokenBudget::from_config(&caps, Some(2000), &config);
+        assert_eq!(budget.total, 8000);
+        assert_eq!(budget.per_layer.knowledge, Some(2000));
+        assert_eq!(budget.per_layer.tool_output, Some(400));
+        assert_eq!(budget.per_layer.long_term_memory, None);
+    }
+
     #[test]
     fn empty_layers_produce_no_sections() {
         let asm = ContextAssembler::with_default_budget();
@@ -0,0 +1,392 @@
+//! tiktoken-style byte-pair encoding token counter.
+//!
+//! Loads an OpenAI rank file (`cl100k_base.tiktoken`, `o200k_base.tiktoken`:
+//! one `base64(token) rank` pair per line) and counts tokens by splitting
+//! text into pre-tokenizer pieces and merging byte pairs by rank.
+//!
+//! Rank files are not bundled. They are looked up in `$RUSTEDCLAW_TOKENIZER_DIR`
+//! or `~/.rustedclaw/tokenizers`; when missing, [`token::counter_for_model`]
+//! falls back to the heuristic counter.
+//!
+//! The pre-tokenizer is a hand-written equivalent of the cl100k split
+//! pattern (contractions, letter runs with one leading symbol, 1–3 digit
+//! groups, punctuation runs, whitespace). It is also used for o200k, whose
+//! pattern differs only in how it splits camel-case and contractions, so
+//! counts there may be off by a token on such words.
+//!
+//! [`token::counter_for_model`]: crate::context::token::counter_for_model
+
+use base64::Engine;
+use rustedclaw_core::tokens::TokenCounter;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tracing::{debug, warn};
+
+/// Rank tables loaded so far, keyed by encoding and directory; `None` marks a failed load.
+type LoadedEncodings = HashMap<(Encoding, PathBuf), Option<Arc<BpeCounter>>>;
+
+/// A BPE encoding family.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// GPT-4, GPT-3.5, `text-embedding-3-*`.
+    Cl100k,
+    /// GPT-4o, GPT-4.1, o-series reasoning models.
+    O200k,
+}
+
+impl Encoding {
+    /// Encoding name, also the rank file stem.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Cl100k => "cl100k_base",
+            Self::O200k => "o200k_base",
+        }
+    }
+
+    /// The encoding a model uses, or `None` for non-OpenAI models.
+    ///
+    /// Router prefixes such as `openai/` are ignored.
+    pub fn for_model(model: &str) -> Option<Self> {
+        let model = model.rsplit('/').next().unwrap_or(model);
+        let o200k = [
+            "gpt-4o",
+            "gpt-4.1",
+            "gpt-4.5",
+            "gpt-5",
+            "chatgpt-4o",
+            "o1",
+            "o3",
+            "o4",
+        ];
+        let cl100k = [
+            "gpt-4",
+            "gpt-3.5",
+            "text-embedding-3",
+            "text-embedding-ada-002",
+        ];
+        if o200k.iter().any(|p| model.starts_with(p)) {
+            Some(Self::O200k)
+        } else if cl100k.iter().any(|p| model.starts_with(p)) {
+            Some(Self::Cl100k)
+        } else {
+            None
+        }
+    }
+
+    /// Load this encoding's rank file from `dir`, once per process.
+    ///
+    /// Returns `None` (and logs once) if the file is missing or invalid.
+    pub fn load_cached(self, dir: &Path) -> Option<Arc<BpeCounter>> {
+        static LOADED: OnceLock<Mutex<LoadedEncodings>> = OnceLock::new();
+        let mut loaded = LOADED
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        loaded
+            .entry((self, dir.to_path_buf()))
+            .or_insert_with(|| {
+                let path = dir.join(format!("{}.tiktoken", self.name()));
+                match BpeCounter::load(self.name(), &path) {
+                    Ok(counter) => {
+                        debug!(path = %path.display(), "Loaded BPE encoding");
+                        Some(Arc::new(counter))
+                    }
+                    Err(e) => {
+                        warn!("{e}; falling back to heuristic token counts");
+                        None
+                    }
+                }
+            })
+            .clone()
+    }
+}
+
+/// Token counter backed by a tiktoken rank table.
+pub struct BpeCounter {
+    name: String,
+    ranks: HashMap<Vec<u8>, u32>,
+}
+
+impl BpeCounter {
+    /// Parse a rank table in the tiktoken text format.
+    pub fn from_tiktoken(name: impl Into<String>, data: &str) -> Result<Self, String> {
+        let name = name.into();
+        let mut ranks = HashMap::new();
+        for (lineno, line) in data.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (token, rank) = line
+                .split_once(' ')
+                .ok_or_else(|| format!("{name}: malformed rank line {}", lineno + 1))?;
+            let token = base64::engine::general_purpose::STANDARD
+                .decode(token)
+                .map_err(|e| format!("{name}: bad base64 on line {}: {e}", lineno + 1))?;
+            let rank = rank
+                .parse::<u32>()
+                .map_err(|e| format!("{name}: bad rank on line {}: {e}", lineno + 1))?;
+            ranks.insert(token, rank);
+        }
+        if ranks.is_empty() {
+            return Err(format!("{name}: empty rank table"));
+        }
+        Ok(Self { name, ranks })
+    }
+
+    /// Load a `.tiktoken` rank file.
+    pub fn load(name: impl Into<String>, path: &Path) -> Result<Self, String> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read rank file {}: {e}", path.display()))?;
+        Self::from_tiktoken(name, &data)
+    }
+
+    /// Number of tokens a single pre-tokenized piece encodes to.
+    fn count_piece(&self, piece: &[u8]) -> usize {
+        if piece.len() <= 1 || self.ranks.contains_key(piece) {
+            return 1;
+        }
+
+        // Start with one token per byte and repeatedly merge the adjacent
+        // pair with the lowest rank (leftmost on ties). Tokens form a linked
+        // list over byte offsets and candidate pairs sit in a min-heap, so a
+        // long piece with no whitespace (base64, minified code) costs
+        // O(n log n) rather than a rescan of every pair per merge.
+        let len = piece.len();
+        // `end[i]`: end of the token starting at byte `i`
+        let mut end: Vec<usize> = (1..=len).collect();
+        // `prev[i]`: start of the token before the one at `i`
+        let mut prev: Vec<Option<usize>> = (0..len).map(|i| i.checked_sub(1)).collect();
+        let mut alive = vec![true; len];
+
+        // Candidate merge of the token at `start` with its right neighbour:
+        // (rank, start, middle, end)
+        let pair = |start: usize, end: &[usize]| {
+            let mid = end[start];
+            (mid < len)
+                .then(|| self.ranks.get(&piece[start..end[mid]]))
+                .flatten()
+                .map(|rank| Reverse((*rank, start, mid, end[mid])))
+        };
+        let mut heap: BinaryHeap<_> = (0..len - 1).filter_map(|i| pair(i, &end)).collect();
+
+        let mut tokens = len;
+        while let Some(Reverse((_, start, mid, stop))) = heap.pop() {
+            // Skip pairs invalidated by an earlier merge
+            if !alive[start] || end[start] != mid || end[mid] != stop {
+                continue;
+            }
+            end[start] = stop;
+            alive[mid] = false;
+            if stop < len {
+                prev[stop] = Some(start);
+            }
+            tokens -= 1;
+
+            if let Some(left) = prev[start] {
+                heap.extend(pair(left, &end));
+            }
+            heap.extend(pair(start, &end));
+        }
+        tokens
+    }
+}
+
+impl TokenCounter for BpeCounter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn count(&self, text: &str) -> usize {
+        split_pieces(text)
+            .into_iter()
+            .map(|piece| self.count_piece(piece.as_bytes()))
+            .sum()
+    }
+}
+
+// ── Pre-tokenizer ─────────────────────────────────────────────────────────
+
+fn is_symbol(c: char) -> bool {
+    !c.is_whitespace() && !c.is_alphabetic() && !c.is_numeric()
+}
+
+/// Split text the way the cl100k pattern does before BPE merging.
+fn split_pieces(text: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let byte_at = |i: usize| chars.get(i).map_or(text.len(), |(b, _)| *b);
+    let mut pieces = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i].1;
+        let next = chars.get(i + 1).map(|(_, c)| *c);
+        let start = i;
+
+        if let Some(len) = contraction_len(&chars[i..]) {
+            // 's 't 're 've 'm 'll 'd
+            i += len;
+        } else if c.is_alphabetic()
+            || (c != '\r' && c != '\n' && !c.is_numeric() && next.is_some_and(char::is_alphabetic))
+        {
+            // Optional single leading non-letter, then a letter run.
+            i += 1;
+            while i < chars.len() && chars[i].1.is_alphabetic() {
+                i += 1;
+            }
+        } else if c.is_numeric() {
+            // Digits in groups of at most three.
+            while i < chars.len() && i - start < 3 && chars[i].1.is_numeric() {
+                i += 1;
+            }
+        } else if is_symbol(c) || (c == ' ' && next.is_some_and(is_symbol)) {
+            // Optional space, punctuation run, trailing newlines.
+            i += 1;
+            while i < chars.len() && is_symbol(chars[i].1) {
+                i += 1;
+            }
+            while i < chars.len() && matches!(chars[i].1, '\r' | '\n') {
+                i += 1;
+            }
+        } else {
+            // Whitespace run. Up to the last newline if it contains one;
+            // otherwise leave the final space to prefix the next word.
+            let mut end = i;
+            while end < chars.len() && chars[end].1.is_whitespace() {
+                end += 1;
+            }
+            let last_newline = (i..end).rev().find(|&j| matches!(chars[j].1, '\r' | '\n'));
+            i = match last_newline {
+                Some(j) => j + 1,
+                None if end < chars.len() && end - i > 1 => end - 1,
+                None => end,
+            };
+        }
+
+        pieces.push(&text[byte_at(start)..byte_at(i)]);
+    }
+    pieces
+}
+
+/// Length in chars of an English contraction suffix at the start of `chars`.
+fn contraction_len(chars: &[(usize, char)]) -> Option<usize> {
+    if chars.first()?.1 != '\'' {
+        return None;
+    }
+    let lower = |i: usize| chars.get(i).map(|(_, c)| c.to_ascii_lowercase());
+    match (lower(1)?, lower(2)) {
+        ('r', Some('e')) | ('v', Some('e')) | ('l', Some('l')) => Some(3),
+        ('s' | 't' | 'm' | 'd', _) => Some(2),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rank table with every single byte plus a few merges.
+    fn tiny_counter() -> BpeCounter {
+        let b64 = |s: &[u8]| base64::engine::general_purpose::STANDARD.encode(s);
+        let mut lines: Vec<String> = (0u8..=255).map(|b| format!("{} {b}", b64(&[b]))).collect();
+        for (rank, merge) in [
+            "he", "ll", "llo", "hello", " w", " wor", "or", "ld", " world",
+        ]
+        .iter()
+        .enumerate()
+        {
+            lines.push(format!("{} {}", b64(merge.as_bytes()), 256 + rank));
+        }
+        BpeCounter::from_tiktoken("tiny", &lines.join("\n")).unwrap()
+    }
+
+    #[test]
+    fn splits_like_cl100k() {
+        assert_eq!(
+            split_pieces("Hello world, it's 12345!\n\n  done"),
+            vec![
+                "Hello", " world", ",", " it", "'s", " ", "123", "45", "!\n\n", " ", " done"
+            ]
+        );
+    }
+
+    #[test]
+    fn merges_by_rank() {
+        let counter = tiny_counter();
+        assert_eq!(counter.count("hello world"), 2);
+        // "hex" → "he" + "x"
+        assert_eq!(counter.count("hex"), 2);
+        assert_eq!(counter.count(""), 0);
+    }
+
+    #[test]
+    fn heap_merge_matches_a_full_rescan() {
+        /// The straightforward merge: rescan every pair for the lowest rank.
+        fn rescan(counter: &BpeCounter, piece: &[u8]) -> usize {
+            let mut bounds: Vec<usize> = (0..=piece.len()).collect();
+            while let Some((_, i)) = (0..bounds.len().saturating_sub(2))
+                .filter_map(|i| {
+                    counter
+                        .ranks
+                        .get(&piece[bounds[i]..bounds[i + 2]])
+                        .map(|rank| (*rank, i))
+                })
+                .min()
+            {
+                bounds.remove(i + 1);
+            }
+            bounds.len() - 1
+        }
+
+        let counter = tiny_counter();
+        let mut seed: u32 = 11;
+        for _ in 0..200 {
+            let piece: Vec<u8> = (0..seed % 40)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                    b"helowrd "[(seed >> 16) as usize % 8]
+                })
+                .collect();
+            if piece.len() > 1 {
+                assert_eq!(
+                    counter.count_piece(&piece),
+                    rescan(&counter, &piece),
+                    "{:?}",
+                    String::from_utf8_lossy(&piece)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn long_pieces_stay_fast() {
+        let piece = "hellohex".repeat(50_000);
+        let started = std::time::Instant::now();
+        assert_eq!(tiny_counter().count_piece(piece.as_bytes()), 150_000);
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    fn counts_multibyte_text_per_byte_without_merges() {
+        // Three CJK chars, three UTF-8 bytes each, no merges in the table.
+        assert_eq!(tiny_counter().count("日本語"), 9);
+    }
+
+    #[test]
+    fn model_families() {
+        assert_eq!(Encoding::for_model("gpt-4o-mini"), Some(Encoding::O200k));
+        assert_eq!(
+            Encoding::for_model("openai/gpt-4-turbo"),
+            Some(Encoding::Cl100k)
+        );
+        assert_eq!(Encoding::for_model("anthropic/claude-sonnet-4"), None);
+    }
+
+    #[test]
+    fn rejects_malformed_tables() {
+        assert!(BpeCounter::from_tiktoken("bad", "no-rank-here").is_err());
+        assert!(BpeCounter::from_tiktoken("empty", "").is_err());
+    }
+}
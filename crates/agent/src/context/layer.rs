@@ -0,0 +1,146 @@
+//! Pluggable context layers.
+//!
+//! Besides the built-in layers, a [`ContextAssembler`] can render any number
+//! of custom [`ContextLayer`]s — the current date, a user profile, open
+//! tickets from an issue tracker — registered with
+//! [`ContextAssembler::with_layer`]. Each layer is filled in [`priority`]
+//! order alongside the built-ins, gets its own optional token cap, and is
+//! reported in [`LayerStats`](crate::context::LayerStats) and
+//! [`DropInfo`](crate::context::DropInfo) under its name.
+//!
+//! Layers render synchronously from data they already hold; fetch remote
+//! data (tickets, profiles) before assembly and hand it to the layer.
+//!
+//! [`ContextAssembler`]: crate::context::ContextAssembler
+//! [`ContextAssembler::with_layer`]: crate::context::ContextAssembler::with_layer
+
+use crate::context::assembler::AssemblyInput;
+use chrono::Utc;
+
+/// Fill order of the built-in layers; lower fills first and renders earlier
+/// in the system message. The system prompt and user message always come
+/// first and are never trimmed.
+pub mod priority {
+    pub const LONG_TERM_MEMORY: i32 = 200;
+    pub const WORKING_MEMORY: i32 = 300;
+    pub const KNOWLEDGE: i32 = 400;
+    pub const TOOL_SCHEMAS: i32 = 500;
+    pub const CONVERSATION_HISTORY: i32 = 600;
+}
+
+/// A custom section of the system message.
+pub trait ContextLayer: Send + Sync {
+    /// Layer name, used in `LayerStats` and `DropInfo`.
+    fn name(&self) -> &str;
+
+    /// Fill order relative to the built-in layers (see [`priority`]).
+    /// Layers with equal priority fill in registration order, after built-ins.
+    fn priority(&self) -> i32;
+
+    /// Section heading; defaults to `[name]`.
+    fn heading(&self) -> String {
+        format!("[{}]", self.name())
+    }
+
+    /// Token cap for this layer; `None` uses whatever remains.
+    fn max_tokens(&self) -> Option<usize> {
+        None
+    }
+
+    /// Items to render, most important first. Items that do not fit the
+    /// budget are dropped from the end.
+    fn items(&self, input: &AssemblyInput<'_>) -> Vec<String>;
+}
+
+/// A layer with fixed items, e.g. a user profile loaded at startup.
+#[derive(Debug, Clone)]
+pub struct StaticLayer {
+    name: String,
+    priority: i32,
+    max_tokens: Option<usize>,
+    items: Vec<String>,
+}
+
+impl StaticLayer {
+    /// A layer rendering `items` at `priority`.
+    pub fn new(name: impl Into<String>, priority: i32, items: Vec<String>) -> Self {
+        Self {
+            name: name.into(),
+            priority,
+            max_tokens: None,
+            items,
+        }
+    }
+
+    /// Cap the layer at `tokens`.
+    pub fn with_max_tokens(mut self, tokens: usize) -> Self {
+        self.max_tokens = Some(tokens);
+        self
+    }
+}
+
+impl ContextLayer for StaticLayer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    fn max_tokens(&self) -> Option<usize> {
+        self.max_tokens
+    }
+
+    fn items(&self, _input: &AssemblyInput<'_>) -> Vec<String> {
+        self.items.clone()
+    }
+}
+
+/// The current UTC date and time, so the model can reason about "today".
+///
+/// Makes assembly time-dependent; leave it out where deterministic output
+/// matters (tests, replay).
+#[derive(Debug, Clone, Copy)]
+pub struct DateTimeLayer {
+    priority: i32,
+}
+
+impl DateTimeLayer {
+    /// Rendered just before long-term memory.
+    pub fn new() -> Self {
+        Self {
+            priority: priority::LONG_TERM_MEMORY - 10,
+        }
+    }
+
+    /// Render at `priority` instead.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+impl Default for DateTimeLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContextLayer for DateTimeLayer {
+    fn name(&self) -> &str {
+        "current_time"
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    fn heading(&self) -> String {
+        "[Current Time]".into()
+    }
+
+    fn items(&self, _input: &AssemblyInput<'_>) -> Vec<String> {
+        vec![Utc::now().format("%A, %Y-%m-%d %H:%M UTC").to_string()]
+    }
+}
@@ -13,14 +13,23 @@
 //! | 3. Working Memory | Scratchpad | Oldest entries dropped, plan kept |
 //! | 4. Knowledge/RAG | Retrieved chunks | Lowest-similarity dropped |
 //! | 5. Tool Schemas | Tool registry | Least-relevant dropped |
-//! | 6. Conversation History | Recent turns | Oldest turns dropped |
+//! | 6. Conversation History | Recent turns | Oldest turns dropped, or folded into a rolling summary |
+//!
+//! Custom [`ContextLayer`]s slot in between these by priority.
 
 pub mod assembler;
+pub mod bpe;
+pub mod layer;
+pub mod summarizer;
 pub mod token;
+pub mod tool_ranker;
 pub mod working_memory;
 
 pub use assembler::{
     AssembledContext, AssemblyError, AssemblyInput, AssemblyMetadata, ContextAssembler, DropInfo,
     KnowledgeChunk, LayerStats, PerLayerBudget, TokenBudget,
 };
+pub use layer::{ContextLayer, DateTimeLayer, StaticLayer};
+pub use summarizer::ConversationSummarizer;
+pub use tool_ranker::ToolRanker;
 pub use working_memory::WorkingMemory;
@@ -0,0 +1,207 @@
+//! Rolling conversation summaries.
+//!
+//! When the history layer overflows its budget, the assembler drops the
+//! oldest turns. With a [`ConversationSummarizer`] attached, the agent
+//! instead folds those turns into a [`ConversationSummary`] stored on the
+//! conversation: one LLM call merges the previous summary with the turns
+//! that no longer fit, and the assembler injects the result as a
+//! `[Conversation Summary]` section (layer `conversation_summary`).
+//!
+//! The summary is refreshed incrementally — only turns not yet covered are
+//! sent — so the cost per refresh stays bounded however long the chat gets.
+//! Turns displaced by the summary section itself are folded on the next
+//! refresh rather than with a second call.
+
+use chrono::Utc;
+use rustedclaw_core::message::{Conversation, ConversationSummary, Message, Role};
+use rustedclaw_core::provider::{Provider, ProviderRequest};
+use std::sync::Arc;
+use tracing::debug;
+
+use crate::context::assembler::{AssemblyMetadata, HISTORY_WINDOW_DROP};
+
+const SUMMARY_INSTRUCTIONS: &str = "You maintain a running summary of a conversation between a user and an AI assistant. \
+Merge the new turns into the existing summary. Keep facts, names, numbers, decisions, \
+preferences and open questions; drop pleasantries. Write in the third person and reply \
+with the updated summary only.";
+
+/// Compresses overflowing history turns into a rolling summary.
+#[derive(Clone)]
+pub struct ConversationSummarizer {
+    provider: Arc<dyn Provider>,
+    model: String,
+    max_tokens: u32,
+}
+
+impl ConversationSummarizer {
+    /// Create a summarizer that calls `model` on `provider`.
+    pub fn new(provider: Arc<dyn Provider>, model: impl Into<String>) -> Self {
+        Self {
+            provider,
+            model: model.into(),
+            max_tokens: 400,
+        }
+    }
+
+    /// Set the maximum length of the summary in tokens.
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Fold the `dropped` oldest unsummarized history turns into the summary.
+    ///
+    /// `dropped` counts non-system messages, as reported by the assembler's
+    /// `conversation_history` [`DropInfo`](crate::context::DropInfo).
+    /// Returns `false` if there was nothing to fold.
+    pub async fn fold(
+        &self,
+        conversation: &mut Conversation,
+        dropped: usize,
+    ) -> Result<bool, rustedclaw_core::Error> {
+        let covered = conversation
+            .summary
+            .as_ref()
+            .map_or(0, |s| s.covered_messages);
+
+        // Advance past `dropped` non-system messages, never stopping between
+        // a tool call and its results. Leading tool results are orphans whose
+        // call an earlier fold covered; the assembler drops them, so they
+        // ride along without counting towards `dropped`.
+        let messages = &conversation.messages;
+        let mut cut = covered;
+        while cut < messages.len() && messages[cut].role == Role::Tool {
+            cut += 1;
+        }
+        let mut counted = 0;
+        while cut < messages.len() && counted < dropped {
+            if messages[cut].role != Role::System {
+                counted += 1;
+            }
+            cut += 1;
+        }
+        while cut < messages.len() && messages[cut].role == Role::Tool {
+            cut += 1;
+        }
+        if cut == covered {
+            return Ok(false);
+        }
+
+        let transcript = render_transcript(&conversation.messages[covered..cut]);
+        let previous = conversation
+            .summary
+            .as_ref()
+            .map_or("(none yet)", |s| s.text.as_str());
+
+        let request = ProviderRequest {
+            model: self.model.clone(),
+            messages: vec![
+                Message::system(SUMMARY_INSTRUCTIONS),
+                Message::user(format!(
+                    "Existing summary:\n{previous}\n\nNew turns:\n{transcript}"
+                )),
+            ],
+            temperature: 0.0,
+            max_tokens: Some(self.max_tokens),
+            tools: vec![],
+            stream: false,
+            stop: vec![],
+        };
+        let response = self.provider.complete(request).await?;
+
+        debug!(
+            conversation_id = %conversation.id,
+            folded = cut - covered,
+            covered = cut,
+            "Conversation summary refreshed"
+        );
+        conversation.summary = Some(ConversationSummary {
+            text: response.message.content.trim().to_string(),
+            covered_messages: cut,
+            updated_at: Utc::now(),
+        });
+        Ok(true)
+    }
+}
+
+/// Number of history turns the assembler dropped, if any.
+pub fn history_overflow(metadata: &AssemblyMetadata) -> Option<usize> {
+    metadata
+        .drops
+        .iter()
+        .find(|d| d.layer == "conversation_history" && d.reason == HISTORY_WINDOW_DROP)
+        .map(|d| d.items_dropped)
+}
+
+fn render_transcript(messages: &[Message]) -> String {
+    messages
+        .iter()
+        .filter(|m| m.role != Role::System)
+        .map(|m| {
+            let speaker = match m.role {
+                Role::User => "User",
+                Role::Assistant => "Assistant",
+                _ => "Tool",
+            };
+            format!("{speaker}: {}", m.content)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::test_helpers::{SequentialMockProvider, make_text_response};
+
+    #[tokio::test]
+    async fn folds_dropped_turns_incrementally() {
+        let provider = Arc::new(SequentialMockProvider::new(vec![
+            make_text_response("User is Ada."),
+            make_text_response("User is Ada, likes Rust."),
+        ]));
+        let summarizer = ConversationSummarizer::new(provider, "mock-model");
+
+        let mut conv = Conversation::new();
+        conv.push(Message::system("prompt"));
+        conv.push(Message::user("I'm Ada"));
+        conv.push(Message::assistant("Hi Ada"));
+        conv.push(Message::user("I like Rust"));
+        conv.push(Message::assistant("Nice"));
+
+        assert!(summarizer.fold(&mut conv, 2).await.unwrap());
+        let summary = conv.summary.clone().unwrap();
+        assert_eq!(summary.text, "User is Ada.");
+        assert_eq!(summary.covered_messages, 3);
+        assert_eq!(conv.unsummarized().len(), 2);
+
+        assert!(summarizer.fold(&mut conv, 1).await.unwrap());
+        assert_eq!(conv.summary.as_ref().unwrap().covered_messages, 4);
+        assert_eq!(conv.unsummarized().len(), 1);
+
+        assert!(!summarizer.fold(&mut conv, 0).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn fold_does_not_split_tool_call_from_results() {
+        let provider = Arc::new(SequentialMockProvider::single_text("Searched."));
+        let summarizer = ConversationSummarizer::new(provider, "mock-model");
+
+        let mut conv = Conversation::new();
+        conv.push(Message::user("Find it"));
+        let mut call = Message::assistant("");
+        call.tool_calls
+            .push(rustedclaw_core::message::MessageToolCall {
+                id: "call_1".into(),
+                name: "search".into(),
+                arguments: "{}".into(),
+            });
+        conv.push(call);
+        conv.push(Message::tool_result("call_1", "found"));
+        conv.push(Message::assistant("Done"));
+
+        assert!(summarizer.fold(&mut conv, 2).await.unwrap());
+        assert_eq!(conv.summary.as_ref().unwrap().covered_messages, 3);
+        assert_eq!(conv.unsummarized()[0].content, "Done");
+    }
+}
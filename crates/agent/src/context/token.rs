@@ -1,21 +1,69 @@
-//! Token estimation utilities.
+//! Token counting utilities.
 //!
-//! Uses a character-based heuristic: ~4 characters per token.
-//! This approximation is accurate within ~10% for BPE tokenizers
-//! (GPT-3.5, GPT-4, Claude) on English text. The trial spec sets
-//! 4096 tokens as the default budget, keeping test cases predictable.
+//! The context assembler, the budget middleware and the context debug
+//! endpoint count tokens through a [`TokenCounter`] picked per model by
+//! [`resolve_counter`]:
+//!
+//! 1. the provider's own tokenizer (the local Candle model), else
+//! 2. the model's BPE encoding (cl100k/o200k) if its rank file is installed, else
+//! 3. the length heuristic: ~4 bytes of UTF-8 per token.
+//!
+//! The free `estimate_*` functions are the heuristic, kept for callers that
+//! have no model at hand. The trial spec sets 4096 tokens as the default
+//! budget, keeping test cases predictable.
 
 use rustedclaw_core::message::Message;
-use rustedclaw_core::provider::ToolDefinition;
+use rustedclaw_core::provider::{Provider, ToolDefinition};
+use rustedclaw_core::tokens::{HeuristicCounter, TokenCounter};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::context::bpe::Encoding;
+
+/// Directory searched for `.tiktoken` rank files.
+///
+/// `$RUSTEDCLAW_TOKENIZER_DIR` if set, else `~/.rustedclaw/tokenizers`.
+pub fn tokenizer_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("RUSTEDCLAW_TOKENIZER_DIR") {
+        return PathBuf::from(dir);
+    }
+    let home = std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".rustedclaw").join("tokenizers")
+}
+
+/// Pick a counter by model name: its BPE encoding if available, else the heuristic.
+pub fn counter_for_model(model: &str) -> Arc<dyn TokenCounter> {
+    match Encoding::for_model(model).and_then(|enc| enc.load_cached(&tokenizer_dir())) {
+        Some(bpe) => bpe,
+        None => Arc::new(HeuristicCounter),
+    }
+}
+
+/// Pick a counter for a provider/model pair, preferring the provider's tokenizer.
+pub fn resolve_counter(provider: &dyn Provider, model: &str) -> Arc<dyn TokenCounter> {
+    provider
+        .token_counter()
+        .unwrap_or_else(|| counter_for_model(model))
+}
+
+/// Count tokens for a tool definition (serialized as JSON).
+pub fn count_tool_tokens(counter: &dyn TokenCounter, tool: &ToolDefinition) -> usize {
+    let json = serde_json::to_string(tool).unwrap_or_default();
+    counter.count(&json)
+}
+
+/// Count tokens for a slice of messages, including per-message overhead.
+pub fn count_messages_tokens(counter: &dyn TokenCounter, messages: &[Message]) -> usize {
+    messages.iter().map(|m| counter.count_message(m)).sum()
+}
 
 /// Estimate the token count for a string.
 ///
-/// Heuristic: 1 token ≈ 4 characters. Rounds up.
+/// Heuristic: 1 token ≈ 4 bytes of UTF-8. Rounds up.
 pub fn estimate_tokens(text: &str) -> usize {
-    if text.is_empty() {
-        return 0;
-    }
-    text.len().div_ceil(4)
+    HeuristicCounter.count(text)
 }
 
 /// Estimate tokens for a single message including per-message overhead.
@@ -23,19 +71,17 @@ pub fn estimate_tokens(text: &str) -> usize {
 /// Each message costs ~4 tokens of overhead for role name, delimiters,
 /// and formatting markers in the API wire format.
 pub fn estimate_message_tokens(message: &Message) -> usize {
-    let overhead = 4;
-    overhead + estimate_tokens(&message.content)
+    HeuristicCounter.count_message(message)
 }
 
 /// Estimate tokens for a slice of messages.
 pub fn estimate_messages_tokens(messages: &[Message]) -> usize {
-    messages.iter().map(estimate_message_tokens).sum()
+    count_messages_tokens(&HeuristicCounter, messages)
 }
 
 /// Estimate tokens for a tool definition (serialized as JSON).
 pub fn estimate_tool_tokens(tool: &ToolDefinition) -> usize {
-    let json = serde_json::to_string(tool).unwrap_or_default();
-    estimate_tokens(&json)
+    count_tool_tokens(&HeuristicCounter, tool)
 }
 
 /// Estimate tokens for a slice of tool definitions.
@@ -70,7 +116,7 @@ mod tests {
 
     #[test]
     fn message_includes_overhead() {
-        let msg = Message::user("test"); // 4 chars → 1 token + 4 overhead = 5
+        let msg = Message::user("test"); // 4 bytes → 1 token + 4 overhead = 5
         assert_eq!(estimate_message_tokens(&msg), 5);
     }
 
@@ -103,4 +149,10 @@ mod tests {
     fn empty_tools_is_zero() {
         assert_eq!(estimate_tools_tokens(&[]), 0);
     }
+
+    #[test]
+    fn unknown_models_fall_back_to_heuristic() {
+        assert_eq!(counter_for_model("claude-sonnet-4").name(), "heuristic");
+        assert_eq!(counter_for_model("llama3").count("test"), 1);
+    }
 }
@@ -0,0 +1,294 @@
+//! Relevance ranking for the tool schema layer.
+//!
+//! When the tool layer's budget cannot hold every definition, the assembler
+//! keeps the tools a [`ToolRanker`] scores highest against the current user
+//! message instead of whichever come first in the registry.
+//!
+//! Scoring is keyword overlap between the message and each tool's name
+//! (weighted double) and description. With an embedding provider attached,
+//! [`ToolRanker::for_message`] also embeds the message and the tool
+//! descriptions (cached per tool) and adds their cosine similarity.
+//! Pinned tools always rank first.
+
+use rustedclaw_config::ContextConfig;
+use rustedclaw_core::provider::{EmbeddingRequest, Provider, ToolDefinition};
+use rustedclaw_memory::cosine_similarity;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// Words too common to signal relevance.
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "with", "from", "that", "this", "what", "when", "where", "which", "how",
+    "are", "was", "you", "your", "can", "could", "would", "should", "please", "into", "about",
+    "given", "use", "using", "returns", "tool",
+];
+
+/// Ranks tool definitions by relevance to the user message.
+#[derive(Clone, Default)]
+pub struct ToolRanker {
+    pinned: Vec<String>,
+    embedder: Option<Embedder>,
+    query_embedding: Option<Vec<f32>>,
+}
+
+#[derive(Clone)]
+struct Embedder {
+    provider: Arc<dyn Provider>,
+    model: String,
+    /// Tool description embeddings, keyed by tool name.
+    cache: Arc<Mutex<HashMap<String, Vec<f32>>>>,
+}
+
+impl ToolRanker {
+    /// Keyword-only ranker with no pinned tools.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tools that are always offered first, whatever the message.
+    pub fn with_pinned(mut self, names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.pinned = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Add embedding similarity, computed with `model` on `provider`.
+    pub fn with_embeddings(
+        mut self,
+        provider: Arc<dyn Provider>,
+        model: impl Into<String>,
+    ) -> Self {
+        self.embedder = Some(Embedder {
+            provider,
+            model: model.into(),
+            cache: Arc::default(),
+        });
+        self
+    }
+
+    /// Ranker for the `[context]` config section's pinned tools and
+    /// embedding model.
+    pub fn from_config(config: &ContextConfig, provider: Arc<dyn Provider>) -> Self {
+        let ranker = Self::new().with_pinned(config.pinned_tools.iter().cloned());
+        match &config.tool_embedding_model {
+            Some(model) => ranker.with_embeddings(provider, model),
+            None => ranker,
+        }
+    }
+
+    /// Whether `name` is pinned.
+    pub fn is_pinned(&self, name: &str) -> bool {
+        self.pinned.iter().any(|p| p == name)
+    }
+
+    /// A ranker primed with embeddings for `message` and `tools`.
+    ///
+    /// Without an embedder this is a plain clone. Embedding failures are
+    /// logged and fall back to keyword scoring.
+    pub async fn for_message(&self, message: &str, tools: &[ToolDefinition]) -> Self {
+        let mut ranker = self.clone();
+        let Some(embedder) = &self.embedder else {
+            return ranker;
+        };
+
+        let missing: Vec<&ToolDefinition> = {
+            let cache = embedder.cache.lock().unwrap_or_else(|e| e.into_inner());
+            tools
+                .iter()
+                .filter(|t| !cache.contains_key(&t.name))
+                .collect()
+        };
+        let mut inputs = vec![message.to_string()];
+        inputs.extend(missing.iter().map(|t| tool_text(t)));
+
+        let request = EmbeddingRequest {
+            model: embedder.model.clone(),
+            inputs,
+        };
+        match embedder.provider.embed(request).await {
+            Ok(response) if response.embeddings.len() == missing.len() + 1 => {
+                let mut embeddings = response.embeddings.into_iter();
+                ranker.query_embedding = embeddings.next();
+                let mut cache = embedder.cache.lock().unwrap_or_else(|e| e.into_inner());
+                for (tool, embedding) in missing.iter().zip(embeddings) {
+                    cache.insert(tool.name.clone(), embedding);
+                }
+            }
+            Ok(response) => warn!(
+                expected = missing.len() + 1,
+                got = response.embeddings.len(),
+                "Tool ranking: embedding count mismatch; using keywords only"
+            ),
+            Err(e) => warn!("Tool ranking: embedding failed ({e}); using keywords only"),
+        }
+        ranker
+    }
+
+    /// Indices into `tools`, most relevant first.
+    ///
+    /// Pinned tools come first in pin order; the rest by descending score,
+    /// ties broken by registry order so ranking is deterministic.
+    pub fn rank(&self, message: &str, tools: &[ToolDefinition]) -> Vec<usize> {
+        let query: HashSet<String> = terms(message).collect();
+        let cache = self
+            .embedder
+            .as_ref()
+            .map(|e| e.cache.lock().unwrap_or_else(|e| e.into_inner()));
+
+        let mut scored: Vec<(usize, f32)> = tools
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| !self.is_pinned(&t.name))
+            .map(|(i, tool)| {
+                let mut score = keyword_score(&query, tool);
+                if let (Some(q), Some(cache)) = (&self.query_embedding, &cache)
+                    && let Some(t) = cache.get(&tool.name)
+                {
+                    score += cosine_similarity(q, t).max(0.0);
+                }
+                (i, score)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        self.pinned
+            .iter()
+            .filter_map(|name| tools.iter().position(|t| &t.name == name))
+            .chain(scored.into_iter().map(|(i, _)| i))
+            .collect()
+    }
+}
+
+/// Text embedded for a tool.
+fn tool_text(tool: &ToolDefinition) -> String {
+    format!(
+        "{}: {}",
+        tool.name.replace(['_', '-'], " "),
+        tool.description
+    )
+}
+
+/// Lowercase content words of at least three characters.
+fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .filter(|w| !STOP_WORDS.contains(&w.as_str()))
+}
+
+/// Whether two terms match, allowing simple inflections ("search" / "searching").
+fn term_matches(a: &str, b: &str) -> bool {
+    a == b || (a.len().min(b.len()) >= 4 && (a.starts_with(b) || b.starts_with(a)))
+}
+
+/// Query terms found in the tool name (weight 2) and description (weight 1),
+/// normalized by the description length so verbose tools are not favoured.
+fn keyword_score(query: &HashSet<String>, tool: &ToolDefinition) -> f32 {
+    if query.is_empty() {
+        return 0.0;
+    }
+    let hits = |words: &HashSet<String>| {
+        query
+            .iter()
+            .filter(|q| words.iter().any(|w| term_matches(q, w)))
+            .count() as f32
+    };
+    let name: HashSet<String> = terms(&tool.name).collect();
+    let description: HashSet<String> = terms(&tool.description).collect();
+    let description_hits = hits(&description) / (description.len().max(1) as f32).sqrt();
+    (2.0 * hits(&name) + description_hits) / query.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use rustedclaw_core::error::ProviderError;
+    use rustedclaw_core::provider::{EmbeddingResponse, ProviderRequest, ProviderResponse};
+
+    fn tool(name: &str, description: &str) -> ToolDefinition {
+        ToolDefinition {
+            name: name.into(),
+            description: description.into(),
+            parameters: serde_json::json!({"type": "object"}),
+        }
+    }
+
+    fn registry() -> Vec<ToolDefinition> {
+        vec![
+            tool("calculator", "Evaluate arithmetic expressions"),
+            tool("file_read", "Read the contents of a file from disk"),
+            tool("web_search", "Search the web for current information"),
+            tool("shell", "Run a shell command"),
+        ]
+    }
+
+    #[test]
+    fn keyword_relevance_orders_tools() {
+        let order = ToolRanker::new().rank("Search the web for Rust news", &registry());
+        assert_eq!(order[0], 2);
+
+        let order = ToolRanker::new().rank("Read config.toml from disk", &registry());
+        assert_eq!(order[0], 1);
+    }
+
+    #[test]
+    fn pinned_tools_rank_first_and_ties_keep_registry_order() {
+        let ranker = ToolRanker::new().with_pinned(["shell"]);
+        assert_eq!(ranker.rank("hello", &registry()), vec![3, 0, 1, 2]);
+    }
+
+    /// Embeds "numbers"-related text on one axis, everything else on another.
+    struct AxisEmbedder;
+
+    #[async_trait]
+    impl Provider for AxisEmbedder {
+        fn name(&self) -> &str {
+            "axis"
+        }
+
+        async fn complete(&self, _: ProviderRequest) -> Result<ProviderResponse, ProviderError> {
+            unimplemented!()
+        }
+
+        async fn embed(
+            &self,
+            request: EmbeddingRequest,
+        ) -> Result<EmbeddingResponse, ProviderError> {
+            let embeddings = request
+                .inputs
+                .iter()
+                .map(|text| {
+                    let numeric = ["arithmetic", "sum", "total"]
+                        .iter()
+                        .any(|w| text.to_lowercase().contains(w));
+                    if numeric {
+                        vec![1.0, 0.0]
+                    } else {
+                        vec![0.0, 1.0]
+                    }
+                })
+                .collect();
+            Ok(EmbeddingResponse {
+                embeddings,
+                model: request.model,
+                usage: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn embeddings_surface_semantic_matches() {
+        // No keyword overlap with "calculator"/"arithmetic", which is last.
+        let message = "What is the total of 17 and 25?";
+        let mut tools = registry();
+        tools.rotate_left(1);
+        assert_ne!(ToolRanker::new().rank(message, &tools)[0], 3);
+
+        let ranker = ToolRanker::new()
+            .with_embeddings(Arc::new(AxisEmbedder), "axis-embed")
+            .for_message(message, &tools)
+            .await;
+        assert_eq!(ranker.rank(message, &tools)[0], 3);
+    }
+}
@@ -0,0 +1,487 @@
+//! Sub-agent delegation — the built-in `delegate` tool.
+//!
+//! `delegate` lets a running agent hand a self-contained task to a scoped
+//! child [`ReactAgent`]. The child gets:
+//!
+//! - a restricted subset of tools (chosen per call, limited to an allow-list)
+//! - its own identity and token budget
+//! - a fresh conversation containing only the delegated task
+//!
+//! The tool returns the child's answer followed by a short trace summary.
+//!
+//! # Nesting
+//!
+//! While a [`ReactAgent`] executes a tool it publishes a [`DelegationScope`]
+//! in a task-local. A child agent started inside that tool picks the scope
+//! up, records its spans into the parent's telemetry trace under the
+//! `delegate` tool span, and runs one level deeper. `delegate` refuses to
+//! start a child once the configured depth limit is reached, which stops
+//! runaway recursive delegation.
+
+use async_trait::async_trait;
+use rustedclaw_core::error::ToolError;
+use rustedclaw_core::event::EventBus;
+use rustedclaw_core::identity::Identity;
+use rustedclaw_core::message::{Conversation, Message};
+use rustedclaw_core::provider::Provider;
+use rustedclaw_core::tool::{Tool, ToolRegistry, ToolResult};
+use rustedclaw_telemetry::TelemetryEngine;
+use std::future::Future;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::context::assembler::TokenBudget;
+use crate::context::working_memory::TraceKind;
+use crate::patterns::react::ReactAgent;
+
+/// Name under which the delegation tool is registered.
+pub const DELEGATE_TOOL_NAME: &str = "delegate";
+
+/// Maximum characters per trace entry in the summary returned to the parent.
+const TRACE_SUMMARY_CHARS: usize = 200;
+
+// ── Delegation scope ──────────────────────────────────────────────────────
+
+/// Telemetry and depth context of the agent currently executing a tool.
+#[derive(Debug, Clone, Default)]
+pub struct DelegationScope {
+    /// Trace the calling agent records spans into.
+    pub trace_id: Option<String>,
+    /// Span of the tool call being executed; parent for nested spans.
+    pub parent_span_id: Option<String>,
+    /// Delegation depth of the calling agent (0 = top-level).
+    pub depth: usize,
+}
+
+tokio::task_local! {
+    static SCOPE: DelegationScope;
+}
+
+/// The scope of the tool call currently executing on this task, if any.
+pub fn current_scope() -> Option<DelegationScope> {
+    SCOPE.try_with(|scope| scope.clone()).ok()
+}
+
+/// Run `fut` with `scope` visible to [`current_scope`].
+pub(crate) async fn with_scope<F: Future>(scope: DelegationScope, fut: F) -> F::Output {
+    SCOPE.scope(scope, fut).await
+}
+
+// ── Delegate tool ─────────────────────────────────────────────────────────
+
+/// Tool that runs a task on a scoped child [`ReactAgent`].
+#[derive(Clone)]
+pub struct DelegateTool {
+    provider: Arc<dyn Provider>,
+    model: String,
+    temperature: f32,
+    /// Tools the child may be given.
+    toolbox: Arc<ToolRegistry>,
+    /// Names from `toolbox` the child may use (`None` = all of them).
+    allowed_tools: Option<Vec<String>>,
+    identity: Identity,
+    budget: Option<TokenBudget>,
+    max_iterations: u32,
+    /// Deepest delegation level that may still delegate further.
+    max_depth: usize,
+    event_bus: Arc<EventBus>,
+    telemetry: Option<Arc<TelemetryEngine>>,
+}
+
+impl DelegateTool {
+    /// Create a delegate tool whose children draw tools from `toolbox`.
+    pub fn new(
+        provider: Arc<dyn Provider>,
+        model: impl Into<String>,
+        temperature: f32,
+        toolbox: Arc<ToolRegistry>,
+        event_bus: Arc<EventBus>,
+    ) -> Self {
+        Self {
+            provider,
+            model: model.into(),
+            temperature,
+            toolbox,
+            allowed_tools: None,
+            identity: Identity {
+                name: "Sub-agent".into(),
+                personality: "Focused and concise.".into(),
+                system_prompt: "You are a sub-agent working on a single delegated task. \
+                    Complete it and reply with the result only."
+                    .into(),
+                ..Identity::default()
+            },
+            budget: None,
+            max_iterations: 5,
+            max_depth: 2,
+            event_bus,
+            telemetry: None,
+        }
+    }
+
+    /// Restrict which toolbox tools a child may be given.
+    pub fn with_allowed_tools(mut self, names: Vec<String>) -> Self {
+        self.allowed_tools = Some(names);
+        self
+    }
+
+    /// Set the identity children run with.
+    pub fn with_identity(mut self, identity: Identity) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    /// Set the children's context token budget.
+    pub fn with_budget(mut self, budget: TokenBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Set the children's iteration limit.
+    pub fn with_max_iterations(mut self, max: u32) -> Self {
+        self.max_iterations = max;
+        self
+    }
+
+    /// Set the maximum delegation depth (1 = children cannot delegate).
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Record child runs as nested spans in the caller's trace.
+    pub fn with_telemetry(mut self, engine: Arc<TelemetryEngine>) -> Self {
+        self.telemetry = Some(engine);
+        self
+    }
+
+    fn is_allowed(&self, name: &str) -> bool {
+        if name == DELEGATE_TOOL_NAME {
+            return true;
+        }
+        self.toolbox.get(name).is_some()
+            && self
+                .allowed_tools
+                .as_ref()
+                .is_none_or(|allowed| allowed.iter().any(|a| a == name))
+    }
+
+    fn default_tool_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .toolbox
+            .names()
+            .into_iter()
+            .filter(|n| *n != DELEGATE_TOOL_NAME && self.is_allowed(n))
+            .map(String::from)
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Build the child's registry from the requested tool names.
+    fn child_registry(&self, names: &[String]) -> ToolRegistry {
+        let mut registry = ToolRegistry::new();
+        for name in names {
+            if name == DELEGATE_TOOL_NAME {
+                registry.register(Box::new(self.clone()));
+            } else {
+                registry.register(Box::new(SharedTool {
+                    registry: self.toolbox.clone(),
+                    name: name.clone(),
+                }));
+            }
+        }
+        registry
+    }
+}
+
+#[async_trait]
+impl Tool for DelegateTool {
+    fn name(&self) -> &str {
+        DELEGATE_TOOL_NAME
+    }
+
+    fn description(&self) -> &str {
+        "Delegate a self-contained sub-task to a focused sub-agent with its own \
+         context and a restricted set of tools. Returns the sub-agent's answer \
+         and a summary of its reasoning trace."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "task": {
+                    "type": "string",
+                    "description": "The task for the sub-agent, with all context it needs"
+                },
+                "tools": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Names of tools the sub-agent may use (defaults to all allowed tools)"
+                },
+                "instructions": {
+                    "type": "string",
+                    "description": "Optional extra instructions for the sub-agent's system prompt"
+                }
+            },
+            "required": ["task"]
+        })
+    }
+
+    async fn execute(&self, arguments: serde_json::Value) -> Result<ToolResult, ToolError> {
+        let task = arguments["task"]
+            .as_str()
+            .filter(|t| !t.trim().is_empty())
+            .ok_or_else(|| ToolError::InvalidArguments("Missing 'task' argument".into()))?;
+
+        let depth = current_scope().map_or(0, |scope| scope.depth);
+        if depth >= self.max_depth {
+            return Err(ToolError::PermissionDenied {
+                tool_name: DELEGATE_TOOL_NAME.into(),
+                reason: format!("delegation depth limit ({}) reached", self.max_depth),
+            });
+        }
+
+        let tool_names: Vec<String> = match arguments["tools"].as_array() {
+            Some(requested) => requested
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(String::from)
+                .collect(),
+            None => self.default_tool_names(),
+        };
+        if let Some(denied) = tool_names.iter().find(|n| !self.is_allowed(n)) {
+            return Err(ToolError::InvalidArguments(format!(
+                "Tool '{}' is not available to sub-agents (allowed: {})",
+                denied,
+                self.default_tool_names().join(", ")
+            )));
+        }
+
+        let mut identity = self.identity.clone();
+        if let Some(extra) = arguments["instructions"].as_str() {
+            identity.system_prompt = format!("{}\n\n{}", identity.system_prompt, extra);
+        }
+
+        info!(depth = depth + 1, tools = ?tool_names, "Delegating task to sub-agent");
+
+        let mut child = ReactAgent::new(
+            self.provider.clone(),
+            &self.model,
+            self.temperature,
+            Arc::new(self.child_registry(&tool_names)),
+            identity,
+            self.event_bus.clone(),
+        )
+        .with_budget_override(self.budget.clone())
+        .with_max_iterations(self.max_iterations);
+        if let Some(telemetry) = &self.telemetry {
+            child = child.with_telemetry(telemetry.clone());
+        }
+
+        let mut conv = Conversation::new();
+        conv.push(Message::user(task));
+        let result =
+            child
+                .run(task, &mut conv, &[], &[])
+                .await
+                .map_err(|e| ToolError::ExecutionFailed {
+                    tool_name: DELEGATE_TOOL_NAME.into(),
+                    reason: e.to_string(),
+                })?;
+
+        let mut output = format!(
+            "{}\n\n---\nSub-agent trace ({} iterations, {} tool calls):",
+            result.answer, result.iterations, result.tool_calls_made
+        );
+        let mut trace = Vec::with_capacity(result.trace.len());
+        for entry in &result.trace {
+            let kind = match entry.kind {
+                TraceKind::Thought => "Thought",
+                TraceKind::Action => "Action",
+                TraceKind::Observation => "Observation",
+                TraceKind::Reflection => "Reflection",
+            };
+            let content: String = entry.content.chars().take(TRACE_SUMMARY_CHARS).collect();
+            output.push_str(&format!("\n- {}: {}", kind, content));
+            trace.push(serde_json::json!({ "kind": kind, "content": entry.content }));
+        }
+
+        Ok(ToolResult {
+            call_id: String::new(),
+            success: true,
+            output,
+            data: Some(serde_json::json!({
+                "answer": result.answer,
+                "iterations": result.iterations,
+                "tool_calls": result.tool_calls_made,
+                "tools": tool_names,
+                "depth": depth + 1,
+                "trace": trace,
+            })),
+        })
+    }
+}
+
+/// A tool borrowed from a shared registry by name.
+struct SharedTool {
+    registry: Arc<ToolRegistry>,
+    name: String,
+}
+
+impl SharedTool {
+    fn inner(&self) -> Option<&dyn Tool> {
+        self.registry.get(&self.name)
+    }
+}
+
+#[async_trait]
+impl Tool for SharedTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        self.inner().map_or("", |t| t.description())
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        self.inner()
+            .map_or(serde_json::json!({}), |t| t.parameters_schema())
+    }
+
+    async fn execute(&self, arguments: serde_json::Value) -> Result<ToolResult, ToolError> {
+        match self.inner() {
+            Some(tool) => tool.execute(arguments).await,
+            None => Err(ToolError::NotFound(self.name.clone())),
+        }
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::test_helpers::*;
+    use rustedclaw_telemetry::SpanKind;
+
+    fn delegate_tool(provider: SequentialMockProvider) -> DelegateTool {
+        DelegateTool::new(
+            Arc::new(provider),
+            "mock-model",
+            0.0,
+            Arc::new(rustedclaw_tools::default_registry()),
+            Arc::new(EventBus::default()),
+        )
+    }
+
+    #[tokio::test]
+    async fn child_answers_with_restricted_tools() {
+        let tool = delegate_tool(SequentialMockProvider::tool_then_answer(
+            vec![make_tool_call(
+                "calculator",
+                serde_json::json!({"expression": "2 + 3"}),
+            )],
+            "Calculating",
+            "The answer is 5",
+        ));
+
+        let result = tool
+            .execute(serde_json::json!({"task": "What is 2+3?", "tools": ["calculator"]}))
+            .await
+            .unwrap();
+
+        assert!(result.success);
+        assert!(result.output.starts_with("The answer is 5"));
+        assert!(result.output.contains("Action: calculator"));
+        let data = result.data.unwrap();
+        assert_eq!(data["tool_calls"], 1);
+        assert_eq!(data["depth"], 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_tools_outside_allow_list() {
+        let tool = delegate_tool(SequentialMockProvider::single_text("unused"))
+            .with_allowed_tools(vec!["calculator".into()]);
+
+        let err = tool
+            .execute(serde_json::json!({"task": "List files", "tools": ["shell"]}))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::InvalidArguments(ref m) if m.contains("shell")));
+    }
+
+    #[tokio::test]
+    async fn depth_limit_stops_recursion() {
+        let tool = delegate_tool(SequentialMockProvider::single_text("unused")).with_max_depth(1);
+        let scope = DelegationScope {
+            depth: 1,
+            ..DelegationScope::default()
+        };
+
+        let err = with_scope(scope, tool.execute(serde_json::json!({"task": "Recurse"})))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::PermissionDenied { .. }));
+    }
+
+    #[tokio::test]
+    async fn child_spans_nest_under_parent_trace() {
+        // Parent delegates, child answers, parent answers — in call order.
+        let provider = Arc::new(SequentialMockProvider::new(vec![
+            make_tool_call_response(
+                vec![make_tool_call(
+                    DELEGATE_TOOL_NAME,
+                    serde_json::json!({"task": "Say hi", "tools": []}),
+                )],
+                "Delegating",
+            ),
+            make_text_response("hi"),
+            make_text_response("The sub-agent said hi"),
+        ]));
+        let telemetry = Arc::new(TelemetryEngine::new());
+        let event_bus = Arc::new(EventBus::default());
+
+        let mut tools = ToolRegistry::new();
+        tools.register(Box::new(
+            DelegateTool::new(
+                provider.clone(),
+                "mock-model",
+                0.0,
+                Arc::new(rustedclaw_tools::default_registry()),
+                event_bus.clone(),
+            )
+            .with_telemetry(telemetry.clone()),
+        ));
+        let parent = ReactAgent::new(
+            provider,
+            "mock-model",
+            0.0,
+            Arc::new(tools),
+            Identity::default(),
+            event_bus,
+        )
+        .with_telemetry(telemetry.clone());
+
+        let mut conv = Conversation::new();
+        let result = parent.run("Delegate", &mut conv, &[], &[]).await.unwrap();
+        assert_eq!(result.answer, "The sub-agent said hi");
+
+        assert_eq!(telemetry.trace_count(), 1);
+        let trace = &telemetry.recent_traces(1)[0];
+        let delegate_span = trace
+            .spans
+            .iter()
+            .find(|s| s.kind == SpanKind::ToolExecution && s.label == DELEGATE_TOOL_NAME)
+            .expect("delegate span");
+        let nested: Vec<_> = trace
+            .spans
+            .iter()
+            .filter(|s| s.parent_id.as_deref() == Some(delegate_span.id.as_str()))
+            .collect();
+        assert_eq!(nested.len(), 1);
+        assert_eq!(nested[0].kind, SpanKind::LlmCall);
+    }
+}
@@ -0,0 +1,741 @@
+//! Long-term memory extraction.
+//!
+//! Plain auto-save stores every exchange verbatim, which fills memory with
+//! chatter. A [`FactExtractor`] instead distills the durable facts in a turn
+//! and reconciles them with what memory already holds:
+//!
+//! 1. **Extract** — one LLM call turns the exchange into short,
+//!    self-contained facts, each with a category (`preference`, `personal`,
+//!    `project`, `instruction` or `fact`).
+//! 2. **Match** — for every fact, related memories are looked up: stored
+//!    facts sharing the most words with it plus the most recent facts of the
+//!    same category. Lookups list memory rather than search it, so they
+//!    don't count as recalls in frequency-based ranking.
+//! 3. **Reconcile** — only if something related exists, a second call decides
+//!    per fact whether to add it, update an existing memory with it or skip
+//!    it as a duplicate, and which memories it contradicts and should delete.
+//!
+//! Stored facts are tagged `fact`, `auto-extracted` and `category:<name>`.
+//! Their `source` is the conversation, and provenance tags
+//! `conversation:<id>` and `message:<id>` name the conversation and the
+//! user and assistant messages they came from. Updates keep the memory ID
+//! and accumulate provenance from every turn that touched the fact.
+//!
+//! Facts are stored under the turn's [`MemoryScope`], and only memories with
+//! exactly that scope are candidates for update or deletion, so one sender
+//! never rewrites another's facts or shared ones.
+//!
+//! Agents run extraction with [`FactExtractor::spawn`], in the background,
+//! so the extra model calls never delay an answer.
+
+use chrono::Utc;
+use rustedclaw_core::memory::{MemoryBackend, MemoryEntry, MemoryFilter, MemoryScope};
+use rustedclaw_core::message::{Conversation, Message, Role};
+use rustedclaw_core::provider::{Provider, ProviderRequest};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio_util::task::TaskTracker;
+use tracing::{debug, warn};
+
+/// Categories a fact can be filed under; anything else becomes `fact`.
+pub const FACT_CATEGORIES: &[&str] = &["preference", "personal", "project", "instruction", "fact"];
+
+/// Tag carried by every extracted fact.
+pub const FACT_TAG: &str = "fact";
+
+/// Most recent facts compared with each new one for similarity.
+const SIMILARITY_SCAN: usize = 200;
+
+const EXTRACT_INSTRUCTIONS: &str = "You extract long-term memories from a conversation turn between a user and an AI assistant. \
+List the durable facts worth remembering in future conversations: the user's preferences, \
+personal details, projects, goals, standing instructions, and decisions. Skip greetings, \
+small talk, one-off questions and anything only relevant to this turn. Write each fact as a \
+short, self-contained sentence in the third person (\"User prefers tabs over spaces\"). \
+Reply with a JSON array only, e.g. [{\"content\": \"...\", \"category\": \"preference\"}], \
+using the categories preference, personal, project, instruction or fact. Reply with [] if \
+there is nothing worth remembering.";
+
+const RECONCILE_INSTRUCTIONS: &str = "You keep an AI assistant's long-term memory consistent. Compare the new facts with the \
+existing memories and reply with a JSON array of operations only:\n\
+{\"action\": \"add\", \"fact\": N} — the fact is new information.\n\
+{\"action\": \"update\", \"fact\": N, \"memory\": M} — the fact refines or supersedes memory M; \
+it replaces M's content.\n\
+{\"action\": \"skip\", \"fact\": N} — an existing memory already says the same thing.\n\
+{\"action\": \"delete\", \"memory\": M} — the new facts contradict memory M and none of them \
+replaces it.\n\
+Use the numbers shown. Facts without an operation are added.";
+
+/// What an extraction pass changed in memory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtractionReport {
+    /// IDs of newly stored facts.
+    pub added: Vec<String>,
+    /// IDs of existing memories rewritten with a new fact.
+    pub updated: Vec<String>,
+    /// IDs of memories deleted as contradicted.
+    pub deleted: Vec<String>,
+    /// Facts dropped as duplicates of existing memories.
+    pub skipped: usize,
+}
+
+impl ExtractionReport {
+    /// Whether the pass left memory untouched.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.deleted.is_empty()
+    }
+}
+
+/// A fact distilled from a conversation turn.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ExtractedFact {
+    /// The fact as a self-contained sentence.
+    pub content: String,
+    /// One of [`FACT_CATEGORIES`].
+    #[serde(default)]
+    pub category: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Operation {
+    action: String,
+    #[serde(default)]
+    fact: Option<usize>,
+    #[serde(default)]
+    memory: Option<usize>,
+}
+
+/// Distills durable facts from conversation turns into long-term memory.
+#[derive(Clone)]
+pub struct FactExtractor {
+    provider: Arc<dyn Provider>,
+    model: String,
+    max_tokens: u32,
+    candidates_per_fact: usize,
+    /// Extractions started with [`spawn`](Self::spawn), shared by clones.
+    pending: TaskTracker,
+}
+
+impl FactExtractor {
+    /// Create an extractor that calls `model` on `provider`.
+    pub fn new(provider: Arc<dyn Provider>, model: impl Into<String>) -> Self {
+        Self {
+            provider,
+            model: model.into(),
+            max_tokens: 600,
+            candidates_per_fact: 5,
+            pending: TaskTracker::new(),
+        }
+    }
+
+    /// Extractor for the `[memory]` config section, or `None` when fact
+    /// extraction is disabled. `default_model` is used unless
+    /// `extraction_model` is set.
+    pub fn from_config(
+        config: &rustedclaw_config::MemoryConfig,
+        provider: Arc<dyn Provider>,
+        default_model: &str,
+    ) -> Option<Self> {
+        config.extract_facts.then(|| {
+            Self::new(
+                provider,
+                config.extraction_model.as_deref().unwrap_or(default_model),
+            )
+        })
+    }
+
+    /// Set the maximum length of each model reply in tokens.
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Set how many related memories are recalled per fact for deduplication.
+    pub fn with_candidates_per_fact(mut self, limit: usize) -> Self {
+        self.candidates_per_fact = limit;
+        self
+    }
+
+    /// Run [`process`](Self::process) in a background task, logging the
+    /// outcome. Use [`wait`](Self::wait) to let pending extractions finish
+    /// before exiting.
+    pub fn spawn(
+        &self,
+        memory: Arc<dyn MemoryBackend>,
+        user_message: String,
+        answer: String,
+        conversation: Conversation,
+        scope: MemoryScope,
+    ) {
+        let extractor = self.clone();
+        self.pending.spawn(async move {
+            if let Err(e) = extractor
+                .process(
+                    memory.as_ref(),
+                    &user_message,
+                    &answer,
+                    &conversation,
+                    &scope,
+                )
+                .await
+            {
+                warn!("Fact extraction failed: {e}");
+            }
+        });
+    }
+
+    /// Wait for every extraction started with [`spawn`](Self::spawn) so far.
+    pub async fn wait(&self) {
+        self.pending.close();
+        self.pending.wait().await;
+        self.pending.reopen();
+    }
+
+    /// Extract facts from one exchange and reconcile them with `memory`.
+    ///
+    /// Provenance is taken from `conversation`: the latest user message
+    /// matching `user_message` and the latest assistant message. Facts are
+    /// stored under `scope`.
+    pub async fn process(
+        &self,
+        memory: &dyn MemoryBackend,
+        user_message: &str,
+        answer: &str,
+        conversation: &Conversation,
+        scope: &MemoryScope,
+    ) -> Result<ExtractionReport, rustedclaw_core::Error> {
+        let facts = self.extract(user_message, answer).await?;
+        if facts.is_empty() {
+            return Ok(ExtractionReport::default());
+        }
+
+        let candidates = self.related(memory, &facts, scope).await?;
+        let operations = if candidates.is_empty() {
+            Vec::new()
+        } else {
+            self.reconcile(&facts, &candidates).await?
+        };
+
+        let provenance = provenance_tags(conversation, user_message);
+        let source = conversation.id.to_string();
+        let report = apply(
+            memory,
+            &facts,
+            &candidates,
+            &operations,
+            &provenance,
+            &source,
+            scope,
+        )
+        .await?;
+        debug!(
+            conversation_id = %conversation.id,
+            added = report.added.len(),
+            updated = report.updated.len(),
+            deleted = report.deleted.len(),
+            skipped = report.skipped,
+            "Extracted facts into memory"
+        );
+        Ok(report)
+    }
+
+    /// Ask the model for the durable facts in one exchange.
+    pub async fn extract(
+        &self,
+        user_message: &str,
+        answer: &str,
+    ) -> Result<Vec<ExtractedFact>, rustedclaw_core::Error> {
+        let reply = self
+            .ask(
+                EXTRACT_INSTRUCTIONS,
+                format!("User: {user_message}\nAssistant: {answer}"),
+            )
+            .await?;
+        let facts: Vec<ExtractedFact> = parse_json_array(&reply)?;
+        Ok(facts
+            .into_iter()
+            .filter_map(|mut f| {
+                f.content = f.content.trim().to_string();
+                f.category = normalize_category(&f.category);
+                (!f.content.is_empty()).then_some(f)
+            })
+            .collect())
+    }
+
+    /// Existing facts in `scope` related to any of `facts`, deduplicated by ID.
+    async fn related(
+        &self,
+        memory: &dyn MemoryBackend,
+        facts: &[ExtractedFact],
+        scope: &MemoryScope,
+    ) -> Result<Vec<MemoryEntry>, rustedclaw_core::Error> {
+        let filter = |tag: String| MemoryFilter {
+            tags: vec![tag],
+            scope: Some(scope.clone()),
+            ..MemoryFilter::default()
+        };
+        let stored = memory
+            .list(&filter(FACT_TAG.to_string()), 0, SIMILARITY_SCAN)
+            .await?;
+
+        let mut seen = HashSet::new();
+        let mut related = Vec::new();
+        for fact in facts {
+            let similar = most_similar(&fact.content, &stored, self.candidates_per_fact);
+            let same_category = memory
+                .list(
+                    &filter(category_tag(&fact.category)),
+                    0,
+                    self.candidates_per_fact,
+                )
+                .await?;
+            for entry in similar.into_iter().cloned().chain(same_category) {
+                // Shared memories are visible to the scope but not ours to edit.
+                if entry.scope == *scope && seen.insert(entry.id.clone()) {
+                    related.push(entry);
+                }
+            }
+        }
+        Ok(related)
+    }
+
+    /// Ask the model how `facts` relate to the `candidates` already stored.
+    async fn reconcile(
+        &self,
+        facts: &[ExtractedFact],
+        candidates: &[MemoryEntry],
+    ) -> Result<Vec<Operation>, rustedclaw_core::Error> {
+        let existing = candidates
+            .iter()
+            .enumerate()
+            .map(|(i, e)| format!("{}. {}", i + 1, e.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let new = facts
+            .iter()
+            .enumerate()
+            .map(|(i, f)| format!("{}. {}", i + 1, f.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let reply = self
+            .ask(
+                RECONCILE_INSTRUCTIONS,
+                format!("Existing memories:\n{existing}\n\nNew facts:\n{new}"),
+            )
+            .await?;
+        parse_json_array(&reply)
+    }
+
+    async fn ask(
+        &self,
+        instructions: &str,
+        prompt: String,
+    ) -> Result<String, rustedclaw_core::Error> {
+        let request = ProviderRequest {
+            model: self.model.clone(),
+            messages: vec![Message::system(instructions), Message::user(prompt)],
+            temperature: 0.0,
+            max_tokens: Some(self.max_tokens),
+            tools: vec![],
+            stream: false,
+            stop: vec![],
+        };
+        Ok(self.provider.complete(request).await?.message.content)
+    }
+}
+
+/// Apply reconciliation `operations` (1-based indices) to `memory`.
+async fn apply(
+    memory: &dyn MemoryBackend,
+    facts: &[ExtractedFact],
+    candidates: &[MemoryEntry],
+    operations: &[Operation],
+    provenance: &[String],
+    source: &str,
+    scope: &MemoryScope,
+) -> Result<ExtractionReport, rustedclaw_core::Error> {
+    let mut report = ExtractionReport::default();
+    let mut handled = vec![false; facts.len()];
+    let mut touched = HashSet::new();
+    let fact_at = |n: Option<usize>| {
+        n.and_then(|n| n.checked_sub(1))
+            .filter(|&i| i < facts.len())
+    };
+    let memory_at = |n: Option<usize>| {
+        n.and_then(|n| n.checked_sub(1))
+            .filter(|&i| i < candidates.len())
+    };
+
+    for op in operations {
+        match op.action.as_str() {
+            "update" => {
+                let (Some(f), Some(m)) = (fact_at(op.fact), memory_at(op.memory)) else {
+                    continue;
+                };
+                if handled[f] || !touched.insert(m) {
+                    continue;
+                }
+                handled[f] = true;
+                let mut entry = candidates[m].clone();
+                entry.content = facts[f].content.clone();
+                entry.tags = fact_tags(&facts[f].category, &entry.tags, provenance);
+                entry.source = Some(source.to_string());
+                entry.embedding = None;
+                entry.embedding_model = None;
+                report.updated.push(memory.store(entry).await?);
+            }
+            "skip" => {
+                if let Some(f) = fact_at(op.fact)
+                    && !handled[f]
+                {
+                    handled[f] = true;
+                    report.skipped += 1;
+                }
+            }
+            "delete" => {
+                let Some(m) = memory_at(op.memory) else {
+                    continue;
+                };
+                if touched.insert(m) && memory.delete(&candidates[m].id).await? {
+                    report.deleted.push(candidates[m].id.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Everything else is new, unless it repeats an existing memory verbatim.
+    for (fact, _) in facts.iter().zip(&handled).filter(|(_, h)| !**h) {
+        if candidates
+            .iter()
+            .any(|c| c.content.trim().eq_ignore_ascii_case(&fact.content))
+        {
+            report.skipped += 1;
+            continue;
+        }
+        let entry = MemoryEntry {
+            id: String::new(),
+            content: fact.content.clone(),
+            tags: fact_tags(&fact.category, &[], provenance),
+            source: Some(source.to_string()),
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+            expires_at: None,
+            score: 0.0,
+            embedding: None,
+            embedding_model: None,
+            scope: scope.clone(),
+        };
+        report.added.push(memory.store(entry).await?);
+    }
+    Ok(report)
+}
+
+/// Tags for a fact: the fixed markers, its category, and the provenance of
+/// `previous` (if it is an update) merged with this turn's.
+fn fact_tags(category: &str, previous: &[String], provenance: &[String]) -> Vec<String> {
+    let mut tags = vec![
+        FACT_TAG.to_string(),
+        "auto-extracted".to_string(),
+        category_tag(category),
+    ];
+    let earlier = previous
+        .iter()
+        .filter(|t| t.starts_with("conversation:") || t.starts_with("message:"));
+    for tag in earlier.chain(provenance) {
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+    tags
+}
+
+/// Up to `limit` of `entries` sharing the most words with `text`; ties keep
+/// the order of `entries`.
+fn most_similar<'a>(text: &str, entries: &'a [MemoryEntry], limit: usize) -> Vec<&'a MemoryEntry> {
+    // Facts are written as "User ..." sentences, so "user" says nothing.
+    const STOP_WORDS: &[&str] = &[
+        "user", "the", "and", "for", "with", "that", "this", "from", "has", "have", "are", "was",
+        "their", "they",
+    ];
+    fn words(text: &str) -> HashSet<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| w.chars().count() > 2)
+            .map(str::to_lowercase)
+            .filter(|w| !STOP_WORDS.contains(&w.as_str()))
+            .collect()
+    }
+    let wanted = words(text);
+    let mut scored: Vec<(usize, &MemoryEntry)> = entries
+        .iter()
+        .map(|e| (words(&e.content).intersection(&wanted).count(), e))
+        .filter(|(shared, _)| *shared > 0)
+        .collect();
+    scored.sort_by_key(|(shared, _)| Reverse(*shared));
+    scored.into_iter().take(limit).map(|(_, e)| e).collect()
+}
+
+fn category_tag(category: &str) -> String {
+    format!("category:{category}")
+}
+
+fn normalize_category(category: &str) -> String {
+    let category = category.trim().to_lowercase();
+    if FACT_CATEGORIES.contains(&category.as_str()) {
+        category
+    } else {
+        "fact".into()
+    }
+}
+
+/// `conversation:<id>` plus `message:<id>` for the turn's user and
+/// assistant messages, when they are in the conversation.
+fn provenance_tags(conversation: &Conversation, user_message: &str) -> Vec<String> {
+    let messages = &conversation.messages;
+    let user = messages
+        .iter()
+        .rev()
+        .find(|m| m.role == Role::User && m.content == user_message)
+        .or_else(|| messages.iter().rev().find(|m| m.role == Role::User));
+    let assistant = messages.iter().rev().find(|m| m.role == Role::Assistant);
+
+    std::iter::once(format!("conversation:{}", conversation.id))
+        .chain(
+            user.into_iter()
+                .chain(assistant)
+                .map(|m| format!("message:{}", m.id)),
+        )
+        .collect()
+}
+
+/// Parse the JSON array in a model reply, tolerating code fences and prose
+/// around it.
+fn parse_json_array<T: DeserializeOwned>(reply: &str) -> Result<Vec<T>, rustedclaw_core::Error> {
+    let json = match (reply.find('['), reply.rfind(']')) {
+        (Some(start), Some(end)) if start < end => &reply[start..=end],
+        _ => "[]",
+    };
+    Ok(serde_json::from_str(json)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::test_helpers::{SequentialMockProvider, make_text_response};
+    use rustedclaw_memory::InMemoryBackend;
+
+    fn turn() -> Conversation {
+        let mut conv = Conversation::new();
+        conv.push(Message::user(
+            "I switched to Neovim last week, use it for examples",
+        ));
+        conv.push(Message::assistant("Noted, I'll show Neovim keybindings."));
+        conv
+    }
+
+    async fn store_fact(memory: &InMemoryBackend, content: &str, category: &str) -> String {
+        memory
+            .store(MemoryEntry {
+                id: String::new(),
+                content: content.into(),
+                tags: fact_tags(category, &[], &["message:old".into()]),
+                source: Some("earlier".into()),
+                created_at: Utc::now(),
+                last_accessed: Utc::now(),
+                access_count: 0,
+                expires_at: None,
+                score: 0.0,
+                embedding: None,
+                embedding_model: None,
+                scope: MemoryScope::default(),
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn stores_new_facts_with_category_and_provenance() {
+        let provider = Arc::new(SequentialMockProvider::new(vec![make_text_response(
+            "```json\n[{\"content\": \"User uses Neovim\", \"category\": \"Preference\"},\
+             {\"content\": \"User is learning Lua\", \"category\": \"hobby\"}]\n```",
+        )]));
+        let extractor = FactExtractor::new(provider.clone(), "mock-model");
+        let memory = InMemoryBackend::new();
+        let conv = turn();
+        let alice = MemoryScope {
+            user_id: Some("alice".into()),
+            ..Default::default()
+        };
+
+        let report = extractor
+            .process(&memory, &conv.messages[0].content, "Noted", &conv, &alice)
+            .await
+            .unwrap();
+
+        // Memory was empty, so no reconciliation call was needed.
+        assert_eq!(provider.call_count(), 1);
+        assert_eq!(report.added.len(), 2);
+        let entry = memory.get(&report.added[0]).await.unwrap().unwrap();
+        assert_eq!(entry.content, "User uses Neovim");
+        assert_eq!(entry.scope, alice);
+        assert_eq!(entry.source.as_deref(), Some(conv.id.to_string().as_str()));
+        for tag in [
+            "fact".to_string(),
+            "auto-extracted".to_string(),
+            "category:preference".to_string(),
+            format!("conversation:{}", conv.id),
+            format!("message:{}", conv.messages[0].id),
+            format!("message:{}", conv.messages[1].id),
+        ] {
+            assert!(entry.tags.contains(&tag), "missing {tag}: {:?}", entry.tags);
+        }
+        let other = memory.get(&report.added[1]).await.unwrap().unwrap();
+        assert!(other.tags.contains(&"category:fact".to_string()));
+    }
+
+    #[tokio::test]
+    async fn reconciles_updates_skips_and_deletes() {
+        let memory = InMemoryBackend::new();
+        let editor = store_fact(&memory, "User uses Vim", "preference").await;
+        let theme = store_fact(&memory, "User prefers dark themes", "preference").await;
+        let modal = store_fact(&memory, "User dislikes modal editors", "preference").await;
+
+        let facts = r#"[{"content": "User uses Neovim", "category": "preference"},
+            {"content": "User prefers dark themes", "category": "preference"},
+            {"content": "User writes plugins in Lua", "category": "project"}]"#;
+        // The reconcile prompt numbers candidates in recall order.
+        let probe = FactExtractor::new(Arc::new(SequentialMockProvider::new(vec![])), "mock");
+        let related = probe
+            .related(
+                &memory,
+                &parse_json_array::<ExtractedFact>(facts).unwrap(),
+                &MemoryScope::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(related.len(), 3);
+        let number = |id: &str| related.iter().position(|e| e.id == id).unwrap() + 1;
+
+        let provider = Arc::new(SequentialMockProvider::new(vec![
+            make_text_response(facts),
+            make_text_response(&format!(
+                r#"[{{"action": "update", "fact": 1, "memory": {}}},
+                    {{"action": "skip", "fact": 2}},
+                    {{"action": "delete", "memory": {}}},
+                    {{"action": "delete", "memory": 99}}]"#,
+                number(&editor),
+                number(&modal)
+            )),
+        ]));
+        let extractor = FactExtractor::new(provider.clone(), "mock-model");
+        let conv = turn();
+        let report = extractor
+            .process(
+                &memory,
+                &conv.messages[0].content,
+                "Noted",
+                &conv,
+                &MemoryScope::default(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(provider.call_count(), 2);
+        assert_eq!(report.updated, vec![editor.clone()]);
+        assert_eq!(report.deleted, vec![modal.clone()]);
+        assert_eq!(report.skipped, 1);
+        assert_eq!(report.added.len(), 1);
+
+        let updated = memory.get(&editor).await.unwrap().unwrap();
+        assert_eq!(updated.content, "User uses Neovim");
+        assert!(updated.tags.contains(&"message:old".to_string()));
+        assert!(
+            updated
+                .tags
+                .contains(&format!("message:{}", conv.messages[0].id))
+        );
+        assert!(memory.get(&modal).await.unwrap().is_none());
+        assert!(memory.get(&theme).await.unwrap().is_some());
+        assert_eq!(memory.count().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn dedup_lookups_do_not_count_as_accesses() {
+        let memory = Arc::new(InMemoryBackend::new());
+        store_fact(&memory, "User uses Vim daily", "preference").await;
+        store_fact(&memory, "User lives in Lisbon", "personal").await;
+
+        let provider = Arc::new(SequentialMockProvider::new(vec![
+            make_text_response(r#"[{"content": "User uses Neovim", "category": "project"}]"#),
+            make_text_response("[]"),
+        ]));
+        let extractor = FactExtractor::new(provider.clone(), "mock-model");
+        let conv = turn();
+        extractor.spawn(
+            memory.clone(),
+            "I use Neovim now".into(),
+            "Noted".into(),
+            conv,
+            MemoryScope::default(),
+        );
+        extractor.wait().await;
+
+        // Only the fact sharing words was offered for reconciliation
+        let reconcile = &provider.requests()[1].messages[1].content;
+        assert!(reconcile.contains("User uses Vim daily"));
+        assert!(!reconcile.contains("Lisbon"));
+        let stored = memory.list(&MemoryFilter::default(), 0, 10).await.unwrap();
+        assert_eq!(stored.len(), 3);
+        assert!(stored.iter().all(|e| e.access_count == 0));
+    }
+
+    #[tokio::test]
+    async fn unparseable_or_empty_replies_store_nothing() {
+        let provider = Arc::new(SequentialMockProvider::new(vec![
+            make_text_response("Nothing worth remembering."),
+            make_text_response("[]"),
+        ]));
+        let extractor = FactExtractor::new(provider, "mock-model");
+        let memory = InMemoryBackend::new();
+        let conv = turn();
+
+        for _ in 0..2 {
+            let report = extractor
+                .process(
+                    &memory,
+                    "hello there",
+                    "hi!",
+                    &conv,
+                    &MemoryScope::default(),
+                )
+                .await
+                .unwrap();
+            assert!(report.is_empty());
+        }
+        assert_eq!(memory.count().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn verbatim_duplicates_are_skipped_without_operations() {
+        let memory = InMemoryBackend::new();
+        store_fact(&memory, "User uses Neovim", "preference").await;
+
+        let provider = Arc::new(SequentialMockProvider::new(vec![
+            make_text_response(r#"[{"content": "user uses neovim", "category": "preference"}]"#),
+            make_text_response("[]"),
+        ]));
+        let extractor = FactExtractor::new(provider, "mock-model");
+        let conv = turn();
+        let report = extractor
+            .process(&memory, "anything", "ok", &conv, &MemoryScope::default())
+            .await
+            .unwrap();
+
+        assert_eq!(report.skipped, 1);
+        assert!(report.is_empty());
+        assert_eq!(memory.count().await.unwrap(), 1);
+    }
+}
@@ -11,16 +11,36 @@
 //! The loop continues until the LLM responds with text only (no tool calls)
 //! or the max iteration limit is reached.
 
+pub mod checkpoint;
 pub mod context;
+pub mod delegate;
+pub mod extraction;
 pub mod loop_runner;
+pub mod middleware;
 pub mod patterns;
+pub mod runs;
+pub mod runtime;
 pub mod stream_event;
 
+pub use checkpoint::{
+    CheckpointStore, FileCheckpointStore, InMemoryCheckpointStore, MAX_RUN_ID_LEN, RunCheckpoint,
+    validate_run_id,
+};
 pub use context::{
-    AssembledContext, AssemblyError, AssemblyInput, AssemblyMetadata, ContextAssembler, DropInfo,
-    KnowledgeChunk, LayerStats, PerLayerBudget, TokenBudget, WorkingMemory,
+    AssembledContext, AssemblyError, AssemblyInput, AssemblyMetadata, ContextAssembler,
+    ContextLayer, ConversationSummarizer, DateTimeLayer, DropInfo, KnowledgeChunk, LayerStats,
+    PerLayerBudget, StaticLayer, TokenBudget, ToolRanker, WorkingMemory,
 };
+pub use delegate::{DelegateTool, DelegationScope};
+pub use extraction::{ExtractedFact, ExtractionReport, FactExtractor};
 pub use loop_runner::AgentLoop;
-pub use patterns::{CoordinationResult, CoordinatorAgent, SubTaskResult};
-pub use patterns::{RagAgent, RagResult, ReactAgent, ReactResult};
+pub use middleware::{BudgetMiddleware, ContractMiddleware, Middleware, ToolVerdict};
+pub use patterns::{CoordinationResult, CoordinatorAgent, SubTaskResult, WorkerConfig};
+pub use patterns::{
+    RagAgent, RagResult, RagSource, ReactAgent, ReactResult, ReflexionAgent, ReflexionResult,
+    RetrievalOptions,
+};
+pub use runs::{RunGuard, RunInfo, RunRegistry};
+pub use runtime::{AgentRuntime, RuntimeResult, Strategy};
 pub use stream_event::AgentStreamEvent;
+pub use tokio_util::sync::CancellationToken;
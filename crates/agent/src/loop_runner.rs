@@ -1,57 +1,29 @@
 //! The agent reasoning loop implementation.
+//!
+//! [`AgentLoop`] is the plain tool-calling entry point kept for the legacy
+//! webhook and channel paths. It is a thin wrapper around an
+//! [`AgentRuntime`] using [`Strategy::Loop`], so recall, auto-save,
+//! telemetry, budgets and contracts behave exactly as they do for
+//! `/v1/chat`.
 
-use chrono::Utc;
 use rustedclaw_contracts::ContractEngine;
-use rustedclaw_core::event::{DomainEvent, EventBus};
+use rustedclaw_core::event::EventBus;
 use rustedclaw_core::identity::Identity;
-use rustedclaw_core::memory::{MemoryBackend, MemoryEntry, MemoryQuery, SearchMode};
-use rustedclaw_core::message::{Conversation, Message};
-use rustedclaw_core::provider::{Provider, ProviderRequest};
-use rustedclaw_core::tool::{ToolCall, ToolRegistry};
+use rustedclaw_core::memory::{MemoryBackend, MemoryScope};
+use rustedclaw_core::message::Conversation;
+use rustedclaw_core::provider::Provider;
+use rustedclaw_core::tool::ToolRegistry;
 use rustedclaw_telemetry::TelemetryEngine;
 use std::sync::Arc;
-use tracing::{debug, info, warn};
+use tracing::info;
+
+use crate::extraction::FactExtractor;
+use crate::runtime::{AgentRuntime, Strategy};
 
 /// The core agent loop that orchestrates LLM calls and tool execution.
 pub struct AgentLoop {
-    /// The LLM provider to use
-    provider: Arc<dyn Provider>,
-
-    /// The model to use
-    model: String,
-
-    /// Temperature setting
-    temperature: f32,
-
-    /// Default max tokens per response
-    max_tokens: Option<u32>,
-
-    /// Tool registry
-    tools: Arc<ToolRegistry>,
-
-    /// Agent identity
-    identity: Identity,
-
-    /// Maximum tool call iterations per turn
-    max_iterations: u32,
-
-    /// Event bus for domain events
-    event_bus: Arc<EventBus>,
-
-    /// Optional memory backend for recall and auto-save
-    memory: Option<Arc<dyn MemoryBackend>>,
-
-    /// Whether to auto-save conversation summaries to memory
-    auto_save: bool,
-
-    /// Maximum memories to recall per turn
-    recall_limit: usize,
-
-    /// Optional contract engine for behavior guardrails
-    contracts: Option<Arc<ContractEngine>>,
-
-    /// Optional telemetry engine for cost tracking & tracing
-    telemetry: Option<Arc<TelemetryEngine>>,
+    /// Runtime configured with the plain loop strategy
+    runtime: AgentRuntime,
 }
 
 impl AgentLoop {
@@ -65,175 +37,76 @@ impl AgentLoop {
         event_bus: Arc<EventBus>,
     ) -> Self {
         Self {
-            provider,
-            model: model.into(),
-            temperature,
-            max_tokens: None,
-            tools,
-            identity,
-            max_iterations: 25,
-            event_bus,
-            memory: None,
-            auto_save: false,
-            recall_limit: 5,
-            contracts: None,
-            telemetry: None,
+            runtime: AgentRuntime::new(provider, model, temperature, tools, identity, event_bus)
+                .with_strategy(Strategy::Loop)
+                .with_max_iterations(25)
+                .with_recall_limit(5),
         }
     }
 
     /// Set the maximum number of tool call iterations.
     pub fn with_max_iterations(mut self, max: u32) -> Self {
-        self.max_iterations = max;
+        self.runtime = self.runtime.with_max_iterations(max);
         self
     }
 
     /// Set the default max tokens per LLM response.
     pub fn with_max_tokens(mut self, max: u32) -> Self {
-        self.max_tokens = Some(max);
+        self.runtime = self.runtime.with_max_tokens(max);
         self
     }
 
     /// Attach a memory backend for automatic recall and save.
     pub fn with_memory(mut self, memory: Arc<dyn MemoryBackend>) -> Self {
-        self.memory = Some(memory);
+        self.runtime = self.runtime.with_memory(memory);
         self
     }
 
     /// Enable or disable auto-save of conversation content to memory.
     pub fn with_auto_save(mut self, enabled: bool) -> Self {
-        self.auto_save = enabled;
+        self.runtime = self.runtime.with_auto_save(enabled);
+        self
+    }
+
+    /// Auto-save extracted facts rather than the raw exchange.
+    pub fn with_fact_extractor(mut self, extractor: FactExtractor) -> Self {
+        self.runtime = self.runtime.with_fact_extractor(extractor);
+        self
+    }
+
+    /// Scope memory recall and auto-save (agent ID, namespace, ...).
+    pub fn with_memory_scope(mut self, scope: MemoryScope) -> Self {
+        self.runtime = self.runtime.with_memory_scope(scope);
         self
     }
 
     /// Set the maximum number of memories to recall per turn.
     pub fn with_recall_limit(mut self, limit: usize) -> Self {
-        self.recall_limit = limit;
+        self.runtime = self.runtime.with_recall_limit(limit);
         self
     }
 
     /// Attach a contract engine for behavior guardrails.
     pub fn with_contracts(mut self, engine: Arc<ContractEngine>) -> Self {
-        self.contracts = Some(engine);
+        self.runtime = self.runtime.with_contracts(engine);
         self
     }
 
     /// Attach a telemetry engine for execution tracing and cost tracking.
     pub fn with_telemetry(mut self, engine: Arc<TelemetryEngine>) -> Self {
-        self.telemetry = Some(engine);
+        self.runtime = self.runtime.with_telemetry(engine);
         self
     }
 
-    /// Recall relevant memories based on the user's last message.
-    async fn recall_memories(&self, conversation: &Conversation) -> Vec<MemoryEntry> {
-        let Some(memory) = &self.memory else {
-            return vec![];
-        };
-
-        // Find the last user message
-        let user_msg = conversation
-            .messages
-            .iter()
-            .rev()
-            .find(|m| m.role == rustedclaw_core::message::Role::User)
-            .map(|m| m.content.clone());
-
-        let Some(query_text) = user_msg else {
-            return vec![];
-        };
-
-        match memory
-            .search(MemoryQuery {
-                text: query_text,
-                limit: self.recall_limit,
-                min_score: 0.0,
-                tags: vec![],
-                mode: SearchMode::Hybrid,
-            })
-            .await
-        {
-            Ok(entries) => {
-                if !entries.is_empty() {
-                    debug!(count = entries.len(), "Recalled memories for context");
-                }
-                entries
-            }
-            Err(e) => {
-                warn!("Memory recall failed: {e}");
-                vec![]
-            }
-        }
-    }
-
-    /// Format recalled memories into a context block for the system prompt.
-    fn format_memory_context(memories: &[MemoryEntry]) -> String {
-        if memories.is_empty() {
-            return String::new();
-        }
-
-        let mut ctx = String::from("\n\n## Recalled Memories\n");
-        for (i, mem) in memories.iter().enumerate() {
-            ctx.push_str(&format!(
-                "{}. [score={:.2}] {}\n",
-                i + 1,
-                mem.score,
-                mem.content
-            ));
-        }
-        ctx
-    }
-
-    /// Auto-save important conversation content to memory.
-    async fn auto_save_to_memory(&self, conversation: &Conversation) {
-        let Some(memory) = &self.memory else {
-            return;
-        };
-        if !self.auto_save {
-            return;
-        }
-
-        // Find the last user message and assistant response
-        let messages: Vec<_> = conversation.messages.iter().rev().take(2).collect();
-        let (user_msg, assistant_msg) = match messages.as_slice() {
-            [assistant, user]
-                if assistant.role == rustedclaw_core::message::Role::Assistant
-                    && user.role == rustedclaw_core::message::Role::User =>
-            {
-                (user.content.clone(), assistant.content.clone())
-            }
-            _ => return,
-        };
-
-        // Only save if there's meaningful content (not just short responses)
-        if user_msg.len() < 10 || assistant_msg.len() < 10 {
-            return;
-        }
-
-        let summary = format!(
-            "User asked: {}\nAssistant answered: {}",
-            user_msg, assistant_msg
-        );
-
-        let entry = MemoryEntry {
-            id: String::new(), // auto-generated
-            content: summary,
-            tags: vec!["conversation".into(), "auto-saved".into()],
-            source: Some(conversation.id.to_string()),
-            created_at: Utc::now(),
-            last_accessed: Utc::now(),
-            score: 0.0,
-            embedding: None,
-        };
-
-        match memory.store(entry).await {
-            Ok(id) => debug!(memory_id = %id, "Auto-saved conversation to memory"),
-            Err(e) => warn!("Failed to auto-save to memory: {e}"),
-        }
+    /// The underlying runtime.
+    pub fn runtime(&self) -> &AgentRuntime {
+        &self.runtime
     }
 
     /// Process a user message and generate a response.
     ///
     /// This is the main entry point for the agent loop. It:
-    /// 1. Builds the conversation context with system prompt
+    /// 1. Recalls memories and assembles context for the last user message
     /// 2. Calls the LLM
     /// 3. If tool calls are returned, executes them and loops
     /// 4. Returns the final text response
@@ -246,259 +119,34 @@ impl AgentLoop {
             messages = conversation.messages.len(),
             "Processing conversation"
         );
-
-        // ── Memory recall ──
-        let recalled = self.recall_memories(conversation).await;
-        let memory_context = Self::format_memory_context(&recalled);
-
-        // Build system prompt with recalled memories
-        let system_prompt = if memory_context.is_empty() {
-            self.identity.system_prompt.clone()
-        } else {
-            format!("{}{}", self.identity.system_prompt, memory_context)
-        };
-
-        // Ensure system prompt is the first message
-        if conversation.messages.is_empty()
-            || conversation.messages[0].role != rustedclaw_core::message::Role::System
-        {
-            conversation
-                .messages
-                .insert(0, Message::system(&system_prompt));
-        } else {
-            // Update existing system prompt with recalled memories
-            conversation.messages[0] = Message::system(&system_prompt);
-        }
-
-        let tool_definitions = self.tools.definitions();
-        let mut iteration = 0;
-
-        // ── Start telemetry trace for this turn ──
-        let trace_id = self
-            .telemetry
-            .as_ref()
-            .map(|t| t.start_trace(conversation.id.to_string()));
-
-        loop {
-            iteration += 1;
-
-            if iteration > self.max_iterations {
-                warn!(
-                    conversation_id = %conversation.id,
-                    iterations = iteration,
-                    "Max tool iterations reached, forcing text response"
-                );
-                break;
-            }
-
-            debug!(
-                conversation_id = %conversation.id,
-                iteration = iteration,
-                "Agent loop iteration"
-            );
-
-            // Build the provider request
-            let request = ProviderRequest {
-                model: self.model.clone(),
-                messages: conversation.messages.clone(),
-                temperature: self.temperature,
-                max_tokens: self.max_tokens,
-                tools: tool_definitions.clone(),
-                stream: false,
-                stop: vec![],
-            };
-
-            // ── Budget pre-check ──
-            if let Some(telemetry) = &self.telemetry {
-                // Estimate cost (rough: assume ~1000 output tokens)
-                let est_cost = telemetry.compute_cost(&self.model, 0, 1000);
-                if let Err(e) = telemetry.check_budget(est_cost) {
-                    warn!("Budget exceeded: {e}");
-                    self.event_bus.publish(DomainEvent::BudgetExceeded {
-                        scope: "pre_check".into(),
-                        spent_usd: 0.0,
-                        limit_usd: 0.0,
-                        action: "deny".into(),
-                        timestamp: chrono::Utc::now(),
-                    });
-                    if let Some(tid) = &trace_id {
-                        telemetry.end_trace(tid);
-                    }
-                    return Err(rustedclaw_core::Error::Provider(
-                        rustedclaw_core::error::ProviderError::ApiError {
-                            status_code: 429,
-                            message: format!("Budget exceeded: {e}"),
-                        },
-                    ));
-                }
-            }
-
-            // Call the LLM
-            let llm_start = std::time::Instant::now();
-            let response = self.provider.complete(request).await?;
-            let llm_duration_ms = llm_start.elapsed().as_millis() as u64;
-
-            // Track token usage + telemetry span
-            if let Some(usage) = &response.usage {
-                self.event_bus.publish(DomainEvent::ResponseGenerated {
-                    conversation_id: conversation.id.to_string(),
-                    model: response.model.clone(),
-                    tokens_used: usage.total_tokens,
-                    timestamp: chrono::Utc::now(),
-                });
-
-                // Record telemetry span for this LLM call
-                if let (Some(telemetry), Some(tid)) = (&self.telemetry, &trace_id) {
-                    let cost = telemetry.compute_cost(
-                        &response.model,
-                        usage.prompt_tokens,
-                        usage.completion_tokens,
-                    );
-                    let mut span = rustedclaw_telemetry::Span::new(
-                        rustedclaw_telemetry::SpanKind::LlmCall,
-                        &response.model,
-                    );
-                    span.record_tokens(usage.prompt_tokens, usage.completion_tokens, cost);
-                    span.end(true);
-                    span.duration_ms = Some(llm_duration_ms);
-                    telemetry.record_span(tid, span);
-                }
-            }
-
-            // Check if the LLM wants to call tools
-            if response.message.tool_calls.is_empty() {
-                // No tool calls — this is the final text response
-                let response_text = response.message.content.clone();
-                conversation.push(response.message);
-
-                // ── Auto-save to memory ──
-                self.auto_save_to_memory(conversation).await;
-
-                // ── End telemetry trace ──
-                if let (Some(telemetry), Some(tid)) = (&self.telemetry, &trace_id) {
-                    telemetry.end_trace(tid);
-                }
-
-                return Ok(response_text);
-            }
-
-            // LLM wants to call tools — execute them
-            debug!(
-                tool_count = response.message.tool_calls.len(),
-                "Executing tool calls"
-            );
-
-            // Add the assistant message (with tool calls) to conversation
-            let tool_calls = response.message.tool_calls.clone();
-            conversation.push(response.message);
-
-            // Execute each tool call
-            for tc in &tool_calls {
-                let call = ToolCall {
-                    id: tc.id.clone(),
-                    name: tc.name.clone(),
-                    arguments: serde_json::from_str(&tc.arguments).unwrap_or_default(),
-                };
-
-                // ── Contract check: evaluate before execution ──
-                if let Some(engine) = &self.contracts {
-                    let verdict = engine.check_tool_call(&call.name, &call.arguments);
-                    if !verdict.allowed {
-                        let action_str = format!("{:?}", verdict.action);
-                        self.event_bus.publish(DomainEvent::ContractViolation {
-                            contract_name: verdict.contract_name.clone().unwrap_or_default(),
-                            tool_name: Some(call.name.clone()),
-                            action: action_str,
-                            message: verdict.message.clone(),
-                            timestamp: chrono::Utc::now(),
-                        });
-
-                        let blocked_msg = format!("🛑 Contract violation: {}", verdict.message);
-                        warn!(
-                            tool = %call.name,
-                            contract = ?verdict.contract_name,
-                            "Tool call blocked by contract"
-                        );
-                        conversation.push(Message::tool_result(&tc.id, &blocked_msg));
-                        continue; // Skip execution, move to next tool call
-                    }
-                }
-
-                let start = std::time::Instant::now();
-                let result = self.tools.execute(&call).await;
-                let duration_ms = start.elapsed().as_millis() as u64;
-
-                match result {
-                    Ok(tool_result) => {
-                        self.event_bus.publish(DomainEvent::ToolExecuted {
-                            tool_name: tc.name.clone(),
-                            success: tool_result.success,
-                            duration_ms,
-                            timestamp: chrono::Utc::now(),
-                        });
-
-                        // Record tool span in telemetry
-                        if let (Some(telemetry), Some(tid)) = (&self.telemetry, &trace_id) {
-                            let mut span = rustedclaw_telemetry::Span::new(
-                                rustedclaw_telemetry::SpanKind::ToolExecution,
-                                &tc.name,
-                            );
-                            span.duration_ms = Some(duration_ms);
-                            span.end(tool_result.success);
-                            telemetry.record_span(tid, span);
-                        }
-
-                        // Add tool result to conversation
-                        conversation.push(Message::tool_result(&tc.id, &tool_result.output));
-                    }
-                    Err(e) => {
-                        warn!(tool = %tc.name, error = %e, "Tool execution failed");
-
-                        self.event_bus.publish(DomainEvent::ToolExecuted {
-                            tool_name: tc.name.clone(),
-                            success: false,
-                            duration_ms,
-                            timestamp: chrono::Utc::now(),
-                        });
-
-                        // Record failed tool span
-                        if let (Some(telemetry), Some(tid)) = (&self.telemetry, &trace_id) {
-                            let mut span = rustedclaw_telemetry::Span::new(
-                                rustedclaw_telemetry::SpanKind::ToolExecution,
-                                &tc.name,
-                            );
-                            span.duration_ms = Some(duration_ms);
-                            span.end(false);
-                            telemetry.record_span(tid, span);
-                        }
-
-                        // Report error to the LLM so it can recover
-                        conversation.push(Message::tool_result(&tc.id, format!("Error: {e}")));
-                    }
-                }
-            }
-
-            // Loop back — the LLM will see the tool results and decide what to do next
-        }
-
-        // If we hit max iterations, return whatever we have
-        self.auto_save_to_memory(conversation).await;
-        if let (Some(telemetry), Some(tid)) = (&self.telemetry, &trace_id) {
-            telemetry.end_trace(tid);
-        }
-        Ok("I've reached the maximum number of tool call iterations. Please provide further guidance.".into())
+        self.runtime.process(conversation).await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::Utc;
     use rustedclaw_core::error::ProviderError;
-    use rustedclaw_core::provider::{ProviderResponse, Usage};
+    use rustedclaw_core::memory::{MemoryEntry, MemoryQuery, SearchMode};
+    use rustedclaw_core::message::Message;
+    use rustedclaw_core::provider::{ProviderRequest, ProviderResponse, Usage};
+    use std::sync::Mutex;
 
     /// A mock provider that returns a fixed response.
     struct MockProvider {
         response: String,
+        /// Messages of the last request it received.
+        seen: Mutex<Vec<Message>>,
+    }
+
+    impl MockProvider {
+        fn new(response: impl Into<String>) -> Self {
+            Self {
+                response: response.into(),
+                seen: Mutex::new(Vec::new()),
+            }
+        }
     }
 
     #[async_trait::async_trait]
@@ -509,8 +157,9 @@ mod tests {
 
         async fn complete(
             &self,
-            _request: ProviderRequest,
+            request: ProviderRequest,
         ) -> Result<ProviderResponse, ProviderError> {
+            *self.seen.lock().unwrap() = request.messages;
             Ok(ProviderResponse {
                 message: Message::assistant(&self.response),
                 usage: Some(Usage {
@@ -526,9 +175,7 @@ mod tests {
 
     #[tokio::test]
     async fn simple_text_response() {
-        let provider = Arc::new(MockProvider {
-            response: "Hello! How can I help?".into(),
-        });
+        let provider = Arc::new(MockProvider::new("Hello! How can I help?"));
         let tools = Arc::new(ToolRegistry::new());
         let event_bus = Arc::new(EventBus::default());
 
@@ -546,8 +193,8 @@ mod tests {
 
         let response = agent.process(&mut conv).await.unwrap();
         assert_eq!(response, "Hello! How can I help?");
-        // System + User + Assistant = 3 messages
-        assert_eq!(conv.messages.len(), 3);
+        // User + Assistant; the system prompt is assembled per request
+        assert_eq!(conv.messages.len(), 2);
     }
 
     #[tokio::test]
@@ -564,20 +211,22 @@ mod tests {
             source: None,
             created_at: Utc::now(),
             last_accessed: Utc::now(),
+            access_count: 0,
+            expires_at: None,
             score: 0.0,
             embedding: None,
+            embedding_model: None,
+            scope: MemoryScope::default(),
         })
         .await
         .unwrap();
 
-        let provider = Arc::new(MockProvider {
-            response: "Your favorite color is blue!".into(),
-        });
+        let provider = Arc::new(MockProvider::new("Your favorite color is blue!"));
         let tools = Arc::new(ToolRegistry::new());
         let event_bus = Arc::new(EventBus::default());
 
         let agent = AgentLoop::new(
-            provider,
+            provider.clone(),
             "mock-model",
             0.7,
             tools,
@@ -592,14 +241,86 @@ mod tests {
         let response = agent.process(&mut conv).await.unwrap();
         assert_eq!(response, "Your favorite color is blue!");
 
-        // System prompt should contain recalled memory
-        let system_msg = &conv.messages[0].content;
+        // The request context should contain the recalled memory
+        let seen = provider.seen.lock().unwrap();
         assert!(
-            system_msg.contains("favorite color is blue"),
-            "System prompt should contain recalled memory: {system_msg}"
+            seen.iter()
+                .any(|m| m.content.contains("favorite color is blue")),
+            "Context should contain recalled memory: {seen:?}"
         );
     }
 
+    #[tokio::test]
+    async fn memory_is_scoped_to_the_sender() {
+        use rustedclaw_core::memory::MemoryBackend;
+        use rustedclaw_memory::InMemoryBackend;
+
+        let mem = Arc::new(InMemoryBackend::new());
+        for (user, content) in [("alice", "green"), ("bob", "red")] {
+            mem.store(MemoryEntry {
+                id: String::new(),
+                content: format!("The user's favorite color is {content}"),
+                tags: vec![],
+                source: None,
+                created_at: Utc::now(),
+                last_accessed: Utc::now(),
+                access_count: 0,
+                expires_at: None,
+                score: 0.0,
+                embedding: None,
+                embedding_model: None,
+                scope: MemoryScope {
+                    user_id: Some(user.into()),
+                    ..MemoryScope::default()
+                },
+            })
+            .await
+            .unwrap();
+        }
+
+        let provider = Arc::new(MockProvider::new("Red, I believe."));
+        let agent = AgentLoop::new(
+            provider.clone(),
+            "mock-model",
+            0.7,
+            Arc::new(ToolRegistry::new()),
+            Identity::default(),
+            Arc::new(EventBus::default()),
+        )
+        .with_memory(mem.clone())
+        .with_auto_save(true);
+
+        let mut message = Message::user("favorite color");
+        message
+            .metadata
+            .insert("sender_id".into(), serde_json::json!("bob"));
+        let mut conv = Conversation::new();
+        conv.push(message);
+        agent.process(&mut conv).await.unwrap();
+
+        let seen = provider.seen.lock().unwrap().clone();
+        assert!(seen.iter().any(|m| m.content.contains("color is red")));
+        assert!(!seen.iter().any(|m| m.content.contains("color is green")));
+
+        let saved = mem
+            .search(MemoryQuery {
+                text: String::new(),
+                limit: 10,
+                min_score: 0.0,
+                tags: vec!["auto-saved".into()],
+                mode: SearchMode::Keyword,
+                embedding: None,
+                scope: MemoryScope {
+                    user_id: Some("bob".into()),
+                    ..MemoryScope::default()
+                },
+            })
+            .await
+            .unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].scope.user_id.as_deref(), Some("bob"));
+    }
+
     #[tokio::test]
     async fn auto_save_stores_conversation() {
         use rustedclaw_core::memory::MemoryBackend;
@@ -607,10 +328,9 @@ mod tests {
 
         let mem = Arc::new(InMemoryBackend::new());
 
-        let provider = Arc::new(MockProvider {
-            response: "Rust is a systems programming language known for safety and performance."
-                .into(),
-        });
+        let provider = Arc::new(MockProvider::new(
+            "Rust is a systems programming language known for safety and performance.",
+        ));
         let tools = Arc::new(ToolRegistry::new());
         let event_bus = Arc::new(EventBus::default());
 
@@ -641,6 +361,8 @@ mod tests {
                 min_score: 0.0,
                 tags: vec![],
                 mode: SearchMode::Keyword,
+                embedding: None,
+                scope: MemoryScope::default(),
             })
             .await
             .unwrap();
@@ -650,6 +372,65 @@ mod tests {
         assert!(results[0].tags.contains(&"auto-saved".to_string()));
     }
 
+    #[tokio::test]
+    async fn auto_save_extracts_facts_when_configured() {
+        use crate::extraction::FactExtractor;
+        use crate::patterns::test_helpers::{SequentialMockProvider, make_text_response};
+        use rustedclaw_core::memory::MemoryBackend;
+        use rustedclaw_memory::InMemoryBackend;
+
+        let mem = Arc::new(InMemoryBackend::new());
+        let extractor = FactExtractor::new(
+            Arc::new(SequentialMockProvider::new(vec![make_text_response(
+                r#"[{"content": "User is learning Rust", "category": "project"}]"#,
+            )])),
+            "mock-model",
+        );
+
+        let agent = AgentLoop::new(
+            Arc::new(MockProvider::new("Rust is a systems programming language.")),
+            "mock-model",
+            0.7,
+            Arc::new(ToolRegistry::new()),
+            Identity::default(),
+            Arc::new(EventBus::default()),
+        )
+        .with_memory(mem.clone())
+        .with_auto_save(true)
+        .with_fact_extractor(extractor.clone());
+
+        let mut conv = Conversation::new();
+        conv.push(Message::user("I'm learning Rust, where do I start?"));
+        agent.process(&mut conv).await.unwrap();
+        extractor.wait().await;
+
+        let saved = mem
+            .search(MemoryQuery {
+                text: String::new(),
+                limit: 10,
+                min_score: 0.0,
+                tags: vec![],
+                mode: SearchMode::Keyword,
+                embedding: None,
+                scope: MemoryScope::default(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(saved.len(), 1, "only the extracted fact is stored");
+        assert_eq!(saved[0].content, "User is learning Rust");
+        assert!(saved[0].tags.contains(&"category:project".to_string()));
+        assert!(
+            saved[0]
+                .tags
+                .contains(&format!("message:{}", conv.messages[0].id))
+        );
+        assert!(
+            saved[0]
+                .tags
+                .contains(&format!("message:{}", conv.messages[1].id))
+        );
+    }
+
     #[tokio::test]
     async fn no_auto_save_without_flag() {
         use rustedclaw_core::memory::MemoryBackend;
@@ -657,9 +438,9 @@ mod tests {
 
         let mem = Arc::new(InMemoryBackend::new());
 
-        let provider = Arc::new(MockProvider {
-            response: "Some response that is long enough to save.".into(),
-        });
+        let provider = Arc::new(MockProvider::new(
+            "Some response that is long enough to save.",
+        ));
         let tools = Arc::new(ToolRegistry::new());
         let event_bus = Arc::new(EventBus::default());
 
@@ -682,28 +463,4 @@ mod tests {
         let count = mem.count().await.unwrap();
         assert_eq!(count, 0, "Should not auto-save when auto_save is false");
     }
-
-    #[tokio::test]
-    async fn format_memory_context_empty() {
-        let ctx = AgentLoop::format_memory_context(&[]);
-        assert!(ctx.is_empty());
-    }
-
-    #[tokio::test]
-    async fn format_memory_context_with_entries() {
-        let entries = vec![MemoryEntry {
-            id: "1".into(),
-            content: "User likes Rust".into(),
-            tags: vec![],
-            source: None,
-            created_at: Utc::now(),
-            last_accessed: Utc::now(),
-            score: 0.95,
-            embedding: None,
-        }];
-        let ctx = AgentLoop::format_memory_context(&entries);
-        assert!(ctx.contains("Recalled Memories"));
-        assert!(ctx.contains("User likes Rust"));
-        assert!(ctx.contains("0.95"));
-    }
 }
@@ -0,0 +1,175 @@
+//! Middleware hooks for the agent runtime.
+//!
+//! A [`Middleware`] observes or steers a run at fixed points of the shared
+//! agent loop:
+//!
+//! - `before_llm`  — inspect or rewrite each provider request (may abort)
+//! - `before_tool` — allow or block a tool call
+//! - `after_tool`  — inspect or rewrite a tool's output
+//! - `after_run`   — observe the final answer
+//!
+//! Built-in middleware:
+//!
+//! - [`ContractMiddleware`] — enforces behavior contracts on tool calls
+//! - [`BudgetMiddleware`]   — refuses LLM calls once a telemetry budget is spent
+
+use async_trait::async_trait;
+use rustedclaw_contracts::ContractEngine;
+use rustedclaw_core::event::{DomainEvent, EventBus};
+use rustedclaw_core::provider::ProviderRequest;
+use rustedclaw_core::tokens::{HeuristicCounter, TokenCounter};
+use rustedclaw_core::tool::ToolCall;
+use rustedclaw_telemetry::TelemetryEngine;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::context::token;
+
+/// Outcome of a [`Middleware::before_tool`] check.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolVerdict {
+    /// Execute the tool.
+    Allow,
+    /// Skip execution; the message is returned to the LLM as the tool result.
+    Block(String),
+}
+
+/// Hooks run by the agent loop. Every method has a no-op default.
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    /// Name used in logs.
+    fn name(&self) -> &str;
+
+    /// Called before every LLM request. Returning an error aborts the run.
+    async fn before_llm(
+        &self,
+        _request: &mut ProviderRequest,
+    ) -> Result<(), rustedclaw_core::Error> {
+        Ok(())
+    }
+
+    /// Called before every tool call.
+    async fn before_tool(&self, _call: &ToolCall) -> ToolVerdict {
+        ToolVerdict::Allow
+    }
+
+    /// Called after every executed tool call with its output.
+    async fn after_tool(&self, _call: &ToolCall, _output: &mut String, _success: bool) {}
+
+    /// Called once the run has produced its final answer.
+    async fn after_run(&self, _user_message: &str, _answer: &str) {}
+}
+
+/// Run `before_tool` through a middleware chain; the first block wins.
+pub(crate) async fn check_tool(middleware: &[Arc<dyn Middleware>], call: &ToolCall) -> ToolVerdict {
+    for m in middleware {
+        if let ToolVerdict::Block(message) = m.before_tool(call).await {
+            warn!(middleware = m.name(), tool = %call.name, "Tool call blocked");
+            return ToolVerdict::Block(message);
+        }
+    }
+    ToolVerdict::Allow
+}
+
+// ── Contracts ─────────────────────────────────────────────────────────────
+
+/// Blocks tool calls that violate a behavior contract.
+pub struct ContractMiddleware {
+    engine: Arc<ContractEngine>,
+    event_bus: Arc<EventBus>,
+}
+
+impl ContractMiddleware {
+    pub fn new(engine: Arc<ContractEngine>, event_bus: Arc<EventBus>) -> Self {
+        Self { engine, event_bus }
+    }
+}
+
+#[async_trait]
+impl Middleware for ContractMiddleware {
+    fn name(&self) -> &str {
+        "contracts"
+    }
+
+    async fn before_tool(&self, call: &ToolCall) -> ToolVerdict {
+        let verdict = self.engine.check_tool_call(&call.name, &call.arguments);
+        if verdict.allowed {
+            return ToolVerdict::Allow;
+        }
+
+        self.event_bus.publish(DomainEvent::ContractViolation {
+            contract_name: verdict.contract_name.clone().unwrap_or_default(),
+            tool_name: Some(call.name.clone()),
+            action: format!("{:?}", verdict.action),
+            message: verdict.message.clone(),
+            timestamp: chrono::Utc::now(),
+        });
+        ToolVerdict::Block(format!("🛑 Contract violation: {}", verdict.message))
+    }
+}
+
+// ── Budgets ───────────────────────────────────────────────────────────────
+
+/// Refuses LLM calls once a configured telemetry budget is exhausted.
+pub struct BudgetMiddleware {
+    telemetry: Arc<TelemetryEngine>,
+    event_bus: Arc<EventBus>,
+    counter: Arc<dyn TokenCounter>,
+}
+
+impl BudgetMiddleware {
+    pub fn new(telemetry: Arc<TelemetryEngine>, event_bus: Arc<EventBus>) -> Self {
+        Self {
+            telemetry,
+            event_bus,
+            counter: Arc::new(HeuristicCounter),
+        }
+    }
+
+    /// Count prompt tokens with the model's tokenizer (default: heuristic).
+    pub fn with_token_counter(mut self, counter: Arc<dyn TokenCounter>) -> Self {
+        self.counter = counter;
+        self
+    }
+}
+
+#[async_trait]
+impl Middleware for BudgetMiddleware {
+    fn name(&self) -> &str {
+        "budget"
+    }
+
+    async fn before_llm(
+        &self,
+        request: &mut ProviderRequest,
+    ) -> Result<(), rustedclaw_core::Error> {
+        // Estimate cost: the prompt as sent, plus the response cap (~1000 if unset).
+        let prompt_tokens = token::count_messages_tokens(self.counter.as_ref(), &request.messages)
+            + request
+                .tools
+                .iter()
+                .map(|t| token::count_tool_tokens(self.counter.as_ref(), t))
+                .sum::<usize>();
+        let completion_tokens = request.max_tokens.unwrap_or(1000);
+        let est_cost =
+            self.telemetry
+                .compute_cost(&request.model, prompt_tokens as u32, completion_tokens);
+        if let Err(e) = self.telemetry.check_budget(est_cost) {
+            warn!("Budget exceeded: {e}");
+            self.event_bus.publish(DomainEvent::BudgetExceeded {
+                scope: "pre_check".into(),
+                spent_usd: 0.0,
+                limit_usd: 0.0,
+                action: "deny".into(),
+                timestamp: chrono::Utc::now(),
+            });
+            return Err(rustedclaw_core::Error::Provider(
+                rustedclaw_core::error::ProviderError::ApiError {
+                    status_code: 429,
+                    message: format!("Budget exceeded: {e}"),
+                },
+            ));
+        }
+        Ok(())
+    }
+}
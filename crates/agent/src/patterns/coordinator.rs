@@ -30,6 +30,7 @@ use std::sync::Arc;
 use tracing::{debug, info};
 
 use crate::context::working_memory::{TraceEntry, WorkingMemory};
+use crate::middleware::Middleware;
 use crate::patterns::react::ReactAgent;
 
 /// Coordinator agent that delegates to workers.
@@ -48,9 +49,12 @@ pub struct CoordinatorAgent {
     tools: Arc<ToolRegistry>,
     /// Event bus.
     event_bus: Arc<EventBus>,
+    /// Middleware applied to every inner ReAct run.
+    middleware: Vec<Arc<dyn Middleware>>,
 }
 
 /// Configuration for a worker agent.
+#[derive(Clone)]
 pub struct WorkerConfig {
     /// Worker name (e.g., "researcher", "writer", "analyst").
     pub name: String,
@@ -108,6 +112,7 @@ impl CoordinatorAgent {
             identity,
             tools,
             event_bus,
+            middleware: Vec::new(),
         }
     }
 
@@ -121,6 +126,18 @@ impl CoordinatorAgent {
         self
     }
 
+    /// Add several preconfigured workers.
+    pub fn with_workers(mut self, workers: impl IntoIterator<Item = WorkerConfig>) -> Self {
+        self.workers.extend(workers);
+        self
+    }
+
+    /// Append a middleware to every worker run.
+    pub fn with_middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
     /// Add a worker with custom identity.
     pub fn add_worker_with_identity(
         mut self,
@@ -200,7 +217,8 @@ impl CoordinatorAgent {
                 worker_identity,
                 self.event_bus.clone(),
             )
-            .with_max_iterations(5);
+            .with_max_iterations(5)
+            .with_middleware_chain(&self.middleware);
 
             let mut worker_conv = Conversation::new();
             let result = worker.run(task, &mut worker_conv, memories, &[]).await?;
@@ -6,16 +6,20 @@
 //! 2. **RAG** — Retrieval-Augmented Generation grounded in knowledge chunks
 //! 3. **Coordinator** — Multi-agent task decomposition and delegation
 //!
+//! Plus **Reflexion** — draft, self-critique and revise until approved.
+//!
 //! All patterns use the context assembly pipeline (FR-2) and working
 //! memory (FR-5) for structured reasoning.
 
 pub mod coordinator;
 pub mod rag;
 pub mod react;
+pub mod reflect;
 
-pub use coordinator::{CoordinationResult, CoordinatorAgent, SubTaskResult};
-pub use rag::{RagAgent, RagResult};
+pub use coordinator::{CoordinationResult, CoordinatorAgent, SubTaskResult, WorkerConfig};
+pub use rag::{RagAgent, RagResult, RagSource, RetrievalOptions};
 pub use react::{ReactAgent, ReactResult};
+pub use reflect::{ReflexionAgent, ReflexionResult};
 
 #[cfg(test)]
 pub(crate) mod test_helpers;
@@ -11,6 +11,12 @@
 //! 3. Assemble context with chunks in the Knowledge layer
 //! 4. Generate response grounded in retrieved knowledge
 //! 5. Return answer with source attributions
+//!
+//! Retrieval is hybrid keyword + vector search over ingested documents when
+//! the tool registry was built with a memory backend
+//! (`rustedclaw_tools::registry_with_memory`); [`RetrievalOptions`] sets how
+//! many chunks are fetched, how relevant they must be, whether they are
+//! re-ranked and which document metadata they must match.
 
 use rustedclaw_core::event::EventBus;
 use rustedclaw_core::identity::Identity;
@@ -18,12 +24,14 @@ use rustedclaw_core::memory::MemoryEntry;
 use rustedclaw_core::message::{Conversation, Message};
 use rustedclaw_core::provider::{Provider, ProviderRequest};
 use rustedclaw_core::tool::ToolRegistry;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Arc;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use crate::context::assembler::{AssemblyMetadata, KnowledgeChunk};
 use crate::context::working_memory::WorkingMemory;
-use crate::context::{AssemblyInput, ContextAssembler, TokenBudget};
+use crate::context::{AssemblyInput, ContextAssembler, TokenBudget, token};
 
 /// RAG agent configuration.
 pub struct RagAgent {
@@ -37,19 +45,71 @@ pub struct RagAgent {
     tools: Arc<ToolRegistry>,
     /// Agent identity.
     identity: Identity,
-    /// Token budget.
-    budget: TokenBudget,
+    /// Token budget; derived from the model when unset.
+    budget: Option<TokenBudget>,
+    /// How knowledge chunks are retrieved.
+    retrieval: RetrievalOptions,
     /// Event bus.
     #[allow(dead_code)]
     event_bus: Arc<EventBus>,
 }
 
+/// How the RAG agent queries the knowledge base.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetrievalOptions {
+    /// Maximum number of chunks retrieved.
+    pub top_k: usize,
+    /// Chunks less similar than this are not retrieved.
+    pub min_score: f32,
+    /// Re-rank retrieved chunks before keeping the best `top_k`.
+    pub rerank: bool,
+    /// Document metadata (`key` = `value`) every chunk must match.
+    pub filters: BTreeMap<String, String>,
+}
+
+impl Default for RetrievalOptions {
+    fn default() -> Self {
+        Self {
+            top_k: 5,
+            min_score: 0.0,
+            rerank: false,
+            filters: BTreeMap::new(),
+        }
+    }
+}
+
+/// A retrieved chunk the answer can cite.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RagSource {
+    /// Source document identifier.
+    pub document_id: String,
+    /// Chunk index within the document.
+    pub chunk_index: usize,
+    /// Human-readable source label (filename, URL, etc.).
+    pub source: String,
+    /// Retrieval similarity (0.0–1.0).
+    pub similarity: f32,
+}
+
+impl From<&KnowledgeChunk> for RagSource {
+    fn from(chunk: &KnowledgeChunk) -> Self {
+        Self {
+            document_id: chunk.document_id.clone(),
+            chunk_index: chunk.chunk_index,
+            source: chunk.source.clone(),
+            similarity: chunk.similarity,
+        }
+    }
+}
+
 /// Result of a RAG execution.
 pub struct RagResult {
     /// The generated answer.
     pub answer: String,
     /// Knowledge chunks that were retrieved.
     pub retrieved_chunks: Vec<KnowledgeChunk>,
+    /// Where each retrieved chunk came from, most relevant first.
+    pub sources: Vec<RagSource>,
     /// The query used for retrieval.
     pub retrieval_query: String,
     /// Working memory snapshot.
@@ -74,14 +134,21 @@ impl RagAgent {
             temperature,
             tools,
             identity,
-            budget: TokenBudget::default(),
+            budget: None,
+            retrieval: RetrievalOptions::default(),
             event_bus,
         }
     }
 
     /// Set the token budget.
     pub fn with_budget(mut self, budget: TokenBudget) -> Self {
-        self.budget = budget;
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Set how knowledge chunks are retrieved.
+    pub fn with_retrieval(mut self, retrieval: RetrievalOptions) -> Self {
+        self.retrieval = retrieval;
         self
     }
 
@@ -103,7 +170,7 @@ impl RagAgent {
         // ── Step 1: Retrieve knowledge chunks ──
         wm.add_thought(&format!("Retrieving knowledge for: {}", user_message));
 
-        let chunks = self.retrieve_chunks(user_message).await?;
+        let chunks = retrieve_chunks(&self.tools, user_message, &self.retrieval).await?;
         let retrieval_query = user_message.to_string();
 
         wm.add_observation(&format!("Retrieved {} knowledge chunks", chunks.len()));
@@ -120,7 +187,15 @@ impl RagAgent {
         debug!(chunks = chunks.len(), "RAG: chunks retrieved");
 
         // ── Step 2: Assemble context with knowledge layer ──
-        let assembler = ContextAssembler::new(self.budget.clone());
+        let budget = self.budget.clone().unwrap_or_else(|| {
+            TokenBudget::for_model(
+                &self.provider.capabilities(&self.model),
+                Some(4096),
+                &Default::default(),
+            )
+        });
+        let assembler = ContextAssembler::new(budget)
+            .with_counter(token::resolve_counter(self.provider.as_ref(), &self.model));
         let tool_defs = self.tools.definitions();
 
         let input = AssemblyInput {
@@ -169,48 +244,64 @@ impl RagAgent {
 
         Ok(RagResult {
             answer,
+            sources: chunks.iter().map(RagSource::from).collect(),
             retrieved_chunks: chunks,
             retrieval_query,
             working_memory: wm,
             context_metadata: Some(metadata),
         })
     }
+}
 
-    /// Retrieve knowledge chunks using the knowledge_base_query tool.
-    async fn retrieve_chunks(
-        &self,
-        query: &str,
-    ) -> Result<Vec<KnowledgeChunk>, rustedclaw_core::Error> {
-        let call = rustedclaw_core::tool::ToolCall {
-            id: "rag_retrieval".into(),
-            name: "knowledge_base_query".into(),
-            arguments: serde_json::json!({"query": query, "top_k": 5}),
-        };
+/// Retrieve knowledge chunks using the knowledge_base_query tool.
+pub(crate) async fn retrieve_chunks(
+    tools: &ToolRegistry,
+    query: &str,
+    options: &RetrievalOptions,
+) -> Result<Vec<KnowledgeChunk>, rustedclaw_core::Error> {
+    let call = rustedclaw_core::tool::ToolCall {
+        id: "rag_retrieval".into(),
+        name: "knowledge_base_query".into(),
+        arguments: serde_json::json!({
+            "query": query,
+            "top_k": options.top_k,
+            "min_score": options.min_score,
+            "rerank": options.rerank,
+            "filters": options.filters,
+        }),
+    };
+
+    let result = tools.execute(&call).await.map_err(|e| {
+        rustedclaw_core::Error::Tool(rustedclaw_core::error::ToolError::ExecutionFailed {
+            tool_name: "knowledge_base_query".into(),
+            reason: format!("{}", e),
+        })
+    })?;
 
-        let result = self.tools.execute(&call).await.map_err(|e| {
-            rustedclaw_core::Error::Tool(rustedclaw_core::error::ToolError::ExecutionFailed {
-                tool_name: "knowledge_base_query".into(),
-                reason: format!("{}", e),
-            })
-        })?;
-
-        // Parse the tool output into KnowledgeChunks.
-        let raw: Vec<serde_json::Value> = serde_json::from_str(&result.output).unwrap_or_default();
-
-        let chunks: Vec<KnowledgeChunk> = raw
-            .into_iter()
-            .enumerate()
-            .map(|(i, v)| KnowledgeChunk {
-                document_id: v["document_id"].as_str().unwrap_or("unknown").to_string(),
-                chunk_index: v["chunk_index"].as_u64().unwrap_or(i as u64) as usize,
-                content: v["content"].as_str().unwrap_or("").to_string(),
-                source: v["source"].as_str().unwrap_or("unknown").to_string(),
-                similarity: v["similarity"].as_f64().unwrap_or(0.0) as f32,
-            })
-            .collect();
-
-        Ok(chunks)
+    // Parse the structured hits (or, failing that, the tool output) into
+    // KnowledgeChunks. A failed query retrieves nothing.
+    if !result.success {
+        warn!(reason = %result.output, "RAG: knowledge retrieval unavailable");
+        return Ok(Vec::new());
     }
+    let raw: Vec<serde_json::Value> = match result.data {
+        Some(serde_json::Value::Array(hits)) => hits,
+        _ => serde_json::from_str(&result.output).unwrap_or_default(),
+    };
+
+    let chunks: Vec<KnowledgeChunk> = raw
+        .into_iter()
+        .enumerate()
+        .map(|(i, v)| KnowledgeChunk {
+            document_id: v["document_id"].as_str().unwrap_or("unknown").to_string(),
+            chunk_index: v["chunk_index"].as_u64().unwrap_or(i as u64) as usize,
+            content: v["content"].as_str().unwrap_or("").to_string(),
+            source: v["source"].as_str().unwrap_or("unknown").to_string(),
+            similarity: v["similarity"].as_f64().unwrap_or(0.0) as f32,
+        })
+        .collect();
+
+    Ok(chunks)
 }
 
 // ── Tests ─────────────────────────────────────────────────────────────────
@@ -219,19 +310,62 @@ impl RagAgent {
 mod tests {
     use super::*;
     use crate::patterns::test_helpers::*;
+    use rustedclaw_core::memory::{MemoryBackend, MemoryScope};
+    use rustedclaw_memory::InMemoryBackend;
+    use rustedclaw_memory::ingest::{Document, DocumentFormat, DocumentIngestor};
+
+    /// A RAG agent over a knowledge base of three ingested documents.
+    async fn setup_rag() -> RagAgent {
+        let memory: Arc<dyn MemoryBackend> = Arc::new(InMemoryBackend::new());
+        let ingestor = DocumentIngestor::new(memory.clone());
+        for (id, text, lang) in [
+            (
+                "rust",
+                "# Rust\n\nRust is a systems programming language.\n\n## Ownership\n\nOwnership gives Rust memory safety without a garbage collector.",
+                "en",
+            ),
+            (
+                "wasm",
+                "# WebAssembly\n\nWASM is a portable binary format.",
+                "en",
+            ),
+            (
+                "agents",
+                "# Patterns\n\nReAct agents interleave reasoning and acting.",
+                "en",
+            ),
+            (
+                "rust-de",
+                "# Rust\n\nRust ist eine Systemprogrammiersprache.",
+                "de",
+            ),
+        ] {
+            ingestor
+                .ingest(Document {
+                    id: id.into(),
+                    source: format!("{id}.md"),
+                    format: DocumentFormat::Markdown,
+                    content: text.as_bytes().to_vec(),
+                    tags: vec![format!("lang:{lang}")],
+                    scope: MemoryScope::default(),
+                })
+                .await
+                .unwrap();
+        }
+        rag_agent(rustedclaw_tools::registry_with_memory(memory))
+    }
 
-    fn setup_rag() -> RagAgent {
+    fn rag_agent(tools: ToolRegistry) -> RagAgent {
         let provider = Arc::new(SequentialMockProvider::single_text(
             "Based on the retrieved knowledge, Rust is a systems programming language.",
         ));
-        let tools = Arc::new(rustedclaw_tools::default_registry());
         let event_bus = Arc::new(EventBus::default());
 
         RagAgent::new(
             provider,
             "mock-model",
             0.3,
-            tools,
+            Arc::new(tools),
             Identity::default(),
             event_bus,
         )
@@ -239,7 +373,7 @@ mod tests {
 
     #[tokio::test]
     async fn rag_retrieves_and_generates() {
-        let agent = setup_rag();
+        let agent = setup_rag().await;
         let mut conv = Conversation::new();
 
         let result = agent
@@ -254,7 +388,7 @@ mod tests {
 
     #[tokio::test]
     async fn rag_populates_knowledge_layer() {
-        let agent = setup_rag();
+        let agent = setup_rag().await;
         let mut conv = Conversation::new();
 
         let result = agent
@@ -274,7 +408,7 @@ mod tests {
 
     #[tokio::test]
     async fn rag_records_working_memory() {
-        let agent = setup_rag();
+        let agent = setup_rag().await;
         let mut conv = Conversation::new();
 
         let result = agent
@@ -289,7 +423,7 @@ mod tests {
 
     #[tokio::test]
     async fn rag_with_memories() {
-        let agent = setup_rag();
+        let agent = setup_rag().await;
         let mut conv = Conversation::new();
 
         let memories = vec![MemoryEntry {
@@ -299,8 +433,12 @@ mod tests {
             source: None,
             created_at: chrono::Utc::now(),
             last_accessed: chrono::Utc::now(),
+            access_count: 0,
+            expires_at: None,
             score: 0.0,
             embedding: None,
+            embedding_model: None,
+            scope: MemoryScope::default(),
         }];
 
         let result = agent
@@ -319,7 +457,7 @@ mod tests {
 
     #[tokio::test]
     async fn rag_sources_tracked() {
-        let agent = setup_rag();
+        let agent = setup_rag().await;
         let mut conv = Conversation::new();
 
         let result = agent
@@ -328,9 +466,45 @@ mod tests {
             .unwrap();
 
         // Should have retrieved chunks with source info
+        assert_eq!(result.sources.len(), result.retrieved_chunks.len());
+        let source = &result.sources[0];
+        assert_eq!(source.document_id, "wasm");
+        assert_eq!(source.chunk_index, 0);
+        assert_eq!(source.source, "wasm.md");
+        assert!(source.similarity > 0.0);
         for chunk in &result.retrieved_chunks {
             assert!(!chunk.source.is_empty());
             assert!(!chunk.document_id.is_empty());
         }
     }
+
+    #[tokio::test]
+    async fn rag_retrieval_options_filter_and_limit() {
+        let agent = setup_rag().await.with_retrieval(RetrievalOptions {
+            top_k: 1,
+            filters: [("lang".to_string(), "de".to_string())].into(),
+            ..RetrievalOptions::default()
+        });
+        let mut conv = Conversation::new();
+
+        let result = agent.run("Rust", &mut conv, &[]).await.unwrap();
+
+        assert_eq!(result.sources.len(), 1);
+        assert_eq!(result.sources[0].document_id, "rust-de");
+    }
+
+    #[tokio::test]
+    async fn rag_without_knowledge_base_retrieves_nothing() {
+        let agent = rag_agent(rustedclaw_tools::default_registry());
+        let mut conv = Conversation::new();
+
+        let result = agent
+            .run("Tell me about Rust", &mut conv, &[])
+            .await
+            .unwrap();
+
+        assert!(result.retrieved_chunks.is_empty());
+        assert!(result.sources.is_empty());
+        assert!(!result.answer.is_empty());
+    }
 }
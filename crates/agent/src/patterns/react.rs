@@ -15,22 +15,38 @@
 //! calls, or when max iterations is reached.
 
 use chrono::Utc;
+use rustedclaw_core::error::ProviderError;
 use rustedclaw_core::event::{DomainEvent, EventBus};
 use rustedclaw_core::identity::Identity;
-use rustedclaw_core::memory::{MemoryBackend, MemoryEntry, MemoryQuery, SearchMode};
-use rustedclaw_core::message::{Conversation, Message};
-use rustedclaw_core::provider::{Provider, ProviderRequest};
+use rustedclaw_core::memory::{MemoryBackend, MemoryEntry, MemoryQuery, MemoryScope, SearchMode};
+use rustedclaw_core::message::{Conversation, Message, Role};
+use rustedclaw_core::provider::{Provider, ProviderRequest, ProviderResponse, Usage};
+use rustedclaw_core::tokens::TokenCounter;
 use rustedclaw_core::tool::{ToolCall, ToolRegistry};
-use rustedclaw_telemetry::TelemetryEngine;
+use rustedclaw_telemetry::{Span, SpanKind, TelemetryEngine};
 use std::sync::Arc;
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
+use crate::checkpoint::{CheckpointStore, RunCheckpoint};
 use crate::context::assembler::{AssemblyMetadata, KnowledgeChunk};
+use crate::context::summarizer::{ConversationSummarizer, history_overflow};
 use crate::context::working_memory::{TraceEntry, WorkingMemory};
-use crate::context::{AssemblyInput, ContextAssembler, TokenBudget};
+use crate::context::{
+    AssemblyInput, ContextAssembler, ContextLayer, TokenBudget, ToolRanker, token,
+};
+use crate::delegate::{self, DelegationScope};
+use crate::extraction::FactExtractor;
+use crate::middleware::{Middleware, ToolVerdict, check_tool};
+use crate::runs::RunRegistry;
+use crate::stream_event::AgentStreamEvent;
+
+/// Receives progress events from a streamed run.
+type EventSink = mpsc::Sender<AgentStreamEvent>;
 
 /// Configuration for the ReAct agent.
+#[derive(Clone)]
 pub struct ReactAgent {
     /// LLM provider.
     provider: Arc<dyn Provider>,
@@ -44,8 +60,8 @@ pub struct ReactAgent {
     tools: Arc<ToolRegistry>,
     /// Agent identity.
     identity: Identity,
-    /// Token budget for context assembly.
-    budget: TokenBudget,
+    /// Token budget for context assembly; derived from the model when unset.
+    budget: Option<TokenBudget>,
     /// Maximum reasoning iterations.
     max_iterations: u32,
     /// Event bus.
@@ -54,16 +70,44 @@ pub struct ReactAgent {
     memory: Option<Arc<dyn MemoryBackend>>,
     /// Whether to auto-save conversation summaries to memory.
     auto_save: bool,
+    /// Distills auto-saved turns into facts instead of storing them verbatim.
+    extractor: Option<FactExtractor>,
     /// Maximum memories to recall per turn.
     recall_limit: usize,
+    /// Scope of this agent's memories, narrowed per turn to the sender.
+    memory_scope: MemoryScope,
     /// Optional telemetry engine for execution tracing and cost tracking.
     telemetry: Option<Arc<TelemetryEngine>>,
+    /// Optional checkpoint store for resumable runs.
+    checkpoints: Option<Arc<dyn CheckpointStore>>,
+    /// Run ID to use for the next run (generated if unset).
+    run_id: Option<String>,
+    /// Optional registry that makes runs cancellable by ID.
+    runs: Option<Arc<RunRegistry>>,
+    /// Optional caller-owned cancellation token.
+    cancel: Option<CancellationToken>,
+    /// Middleware run at each step of the loop, in order.
+    middleware: Vec<Arc<dyn Middleware>>,
+    /// Whether the reasoning trace is rendered into the prompt.
+    reasoning_trace: bool,
+    /// Strategy name recorded in checkpoints, when set by a runtime.
+    strategy_name: Option<&'static str>,
+    /// Token counter override (otherwise resolved from provider and model).
+    token_counter: Option<Arc<dyn TokenCounter>>,
+    /// Folds overflowing history into a rolling summary instead of dropping it.
+    summarizer: Option<ConversationSummarizer>,
+    /// Orders tool schemas by relevance when they do not all fit.
+    tool_ranker: ToolRanker,
+    /// Custom context layers added to every assembly.
+    layers: Vec<Arc<dyn ContextLayer>>,
 }
 
 /// The result of a ReAct execution.
 pub struct ReactResult {
     /// The final answer text.
     pub answer: String,
+    /// ID of the run (used for checkpointing and resumption).
+    pub run_id: String,
     /// Complete reasoning trace.
     pub trace: Vec<TraceEntry>,
     /// Working memory snapshot at completion.
@@ -74,6 +118,8 @@ pub struct ReactResult {
     pub tool_calls_made: usize,
     /// Context assembly metadata from the last iteration.
     pub last_context_metadata: Option<AssemblyMetadata>,
+    /// Token usage reported by the last LLM call.
+    pub usage: Option<Usage>,
 }
 
 impl ReactAgent {
@@ -93,19 +139,40 @@ impl ReactAgent {
             max_tokens: None,
             tools,
             identity,
-            budget: TokenBudget::default(),
+            budget: None,
             max_iterations: 10,
             event_bus,
             memory: None,
             auto_save: false,
+            extractor: None,
             recall_limit: 5,
+            memory_scope: MemoryScope::default(),
             telemetry: None,
+            checkpoints: None,
+            run_id: None,
+            runs: None,
+            cancel: None,
+            middleware: Vec::new(),
+            reasoning_trace: true,
+            strategy_name: None,
+            token_counter: None,
+            summarizer: None,
+            tool_ranker: ToolRanker::default(),
+            layers: Vec::new(),
         }
     }
 
-    /// Set the token budget.
+    /// Set the token budget, overriding the one derived from the model.
     pub fn with_budget(mut self, budget: TokenBudget) -> Self {
-        self.budget = budget;
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Set the token budget if one was given, for builders that forward an optional override.
+    pub(crate) fn with_budget_override(mut self, budget: Option<TokenBudget>) -> Self {
+        if budget.is_some() {
+            self.budget = budget;
+        }
         self
     }
 
@@ -133,20 +200,140 @@ impl ReactAgent {
         self
     }
 
+    /// Auto-save extracted facts rather than the raw exchange.
+    pub fn with_fact_extractor(mut self, extractor: FactExtractor) -> Self {
+        self.extractor = Some(extractor);
+        self
+    }
+
     /// Set the maximum number of memories to recall per turn.
     pub fn with_recall_limit(mut self, limit: usize) -> Self {
         self.recall_limit = limit;
         self
     }
 
+    /// Scope recall and auto-save to `scope` (e.g. an agent ID or namespace).
+    /// The sender recorded on each user message narrows it further.
+    pub fn with_memory_scope(mut self, scope: MemoryScope) -> Self {
+        self.memory_scope = scope;
+        self
+    }
+
     /// Attach a telemetry engine for execution tracing and cost tracking.
     pub fn with_telemetry(mut self, engine: Arc<TelemetryEngine>) -> Self {
         self.telemetry = Some(engine);
         self
     }
 
+    /// Attach a checkpoint store so runs survive restarts.
+    pub fn with_checkpoints(mut self, store: Arc<dyn CheckpointStore>) -> Self {
+        self.checkpoints = Some(store);
+        self
+    }
+
+    /// Use a caller-chosen run ID instead of generating one.
+    pub fn with_run_id(mut self, run_id: impl Into<String>) -> Self {
+        self.run_id = Some(run_id.into());
+        self
+    }
+
+    /// Register runs in a registry so they can be cancelled by ID.
+    pub fn with_runs(mut self, registry: Arc<RunRegistry>) -> Self {
+        self.runs = Some(registry);
+        self
+    }
+
+    /// Stop the run when `token` is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Append a middleware to the chain.
+    pub fn with_middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// Append a whole middleware chain, preserving its order.
+    pub(crate) fn with_middleware_chain(mut self, chain: &[Arc<dyn Middleware>]) -> Self {
+        self.middleware.extend(chain.iter().cloned());
+        self
+    }
+
+    /// Include (default) or omit the Thought/Action/Observation trace in the
+    /// prompt. Omitting it turns the agent into a plain tool-calling loop.
+    pub fn with_reasoning_trace(mut self, enabled: bool) -> Self {
+        self.reasoning_trace = enabled;
+        self
+    }
+
+    /// Record runs under this strategy name in checkpoints. Defaults to
+    /// "react", or "loop" without the reasoning trace.
+    pub(crate) fn with_strategy_name(mut self, name: &'static str) -> Self {
+        self.strategy_name = Some(name);
+        self
+    }
+
+    /// Count context tokens with a specific counter.
+    pub fn with_token_counter(mut self, counter: Arc<dyn TokenCounter>) -> Self {
+        self.token_counter = Some(counter);
+        self
+    }
+
+    /// Summarize history that overflows the budget instead of dropping it.
+    pub fn with_summarizer(mut self, summarizer: ConversationSummarizer) -> Self {
+        self.summarizer = Some(summarizer);
+        self
+    }
+
+    /// Rank tool schemas with pinned tools and/or embedding similarity.
+    pub fn with_tool_ranker(mut self, ranker: ToolRanker) -> Self {
+        self.tool_ranker = ranker;
+        self
+    }
+
+    /// Add a custom context layer.
+    pub fn with_context_layer(mut self, layer: Arc<dyn ContextLayer>) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// The token counter for this agent's model.
+    fn counter(&self) -> Arc<dyn TokenCounter> {
+        self.token_counter
+            .clone()
+            .unwrap_or_else(|| token::resolve_counter(self.provider.as_ref(), &self.model))
+    }
+
+    /// The configured budget, or one sized to the model's context window.
+    fn budget(&self) -> TokenBudget {
+        self.budget.clone().unwrap_or_else(|| {
+            TokenBudget::for_model(
+                &self.provider.capabilities(&self.model),
+                self.max_tokens,
+                &Default::default(),
+            )
+        })
+    }
+
+    /// Memory scope of a turn: the delegating parent's, then this agent's,
+    /// then the sender recorded on the latest user message.
+    fn memory_scope_for(&self, conversation: &Conversation) -> MemoryScope {
+        let sender = conversation
+            .messages
+            .iter()
+            .rev()
+            .find(|m| m.role == Role::User)
+            .map(|m| MemoryScope::from_metadata(&m.metadata))
+            .unwrap_or_default();
+        MemoryScope::current()
+            .merge(&self.memory_scope)
+            .merge(&sender)
+    }
+
     /// Recall relevant memories from the backend.
-    async fn recall_memories(&self, user_message: &str) -> Vec<MemoryEntry> {
+    async fn recall_memories(&self, user_message: &str, scope: &MemoryScope) -> Vec<MemoryEntry> {
         let Some(memory) = &self.memory else {
             return vec![];
         };
@@ -158,6 +345,8 @@ impl ReactAgent {
                 min_score: 0.0,
                 tags: vec![],
                 mode: SearchMode::Hybrid,
+                embedding: None,
+                scope: scope.clone(),
             })
             .await
         {
@@ -174,113 +363,212 @@ impl ReactAgent {
         }
     }
 
-    /// Auto-save a summary of the conversation to memory.
-    async fn auto_save_to_memory(
+    /// Execute the ReAct loop.
+    ///
+    /// Takes a user message, optional long-term memories, and optional
+    /// knowledge chunks (for RAG integration). Returns the final answer
+    /// along with the complete reasoning trace.
+    ///
+    /// When a checkpoint store is attached, the run state is saved after
+    /// every iteration so it can be continued with [`resume`](Self::resume).
+    pub async fn run(
+        &self,
+        user_message: &str,
+        conversation: &mut Conversation,
+        memories: &[MemoryEntry],
+        knowledge_chunks: &[KnowledgeChunk],
+    ) -> Result<ReactResult, rustedclaw_core::Error> {
+        let mut checkpoint = self
+            .start_run(user_message, conversation, memories, knowledge_chunks)
+            .await;
+        let result = self.execute(&mut checkpoint, None).await;
+        *conversation = checkpoint.conversation;
+        result
+    }
+
+    /// Recall memories and build the checkpoint a fresh run starts from.
+    async fn start_run(
         &self,
         user_message: &str,
-        answer: &str,
         conversation: &Conversation,
-    ) {
-        let Some(memory) = &self.memory else {
-            return;
-        };
-        if !self.auto_save {
-            return;
-        }
+        memories: &[MemoryEntry],
+        knowledge_chunks: &[KnowledgeChunk],
+    ) -> RunCheckpoint {
+        let run_id = self
+            .run_id
+            .clone()
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
 
-        // Only save meaningful exchanges
-        if user_message.len() < 10 || answer.len() < 10 {
-            return;
+        // ── Auto-recall memories ──
+        let recalled = self
+            .recall_memories(user_message, &self.memory_scope_for(conversation))
+            .await;
+        let mut all_memories = memories.to_vec();
+        // Add recalled memories that aren't already in the provided list
+        for r in recalled {
+            if !all_memories.iter().any(|m| m.id == r.id) {
+                all_memories.push(r);
+            }
         }
 
-        let summary = format!(
-            "User asked: {}\nAssistant answered: {}",
-            user_message, answer
+        let mut checkpoint = RunCheckpoint::new(
+            run_id,
+            user_message,
+            conversation.clone(),
+            WorkingMemory::new(self.max_iterations as usize),
         );
+        checkpoint.strategy = self
+            .strategy_name
+            .unwrap_or(if self.reasoning_trace {
+                "react"
+            } else {
+                "loop"
+            })
+            .to_string();
+        checkpoint.memories = all_memories;
+        checkpoint.knowledge_chunks = knowledge_chunks.to_vec();
 
-        let entry = MemoryEntry {
-            id: String::new(),
-            content: summary,
-            tags: vec!["conversation".into(), "auto-saved".into(), "react".into()],
-            source: Some(conversation.id.to_string()),
-            created_at: Utc::now(),
-            last_accessed: Utc::now(),
-            score: 0.0,
-            embedding: None,
+        checkpoint
+    }
+
+    /// Continue a run from a previously saved checkpoint.
+    ///
+    /// Pending tool calls are executed first, then the loop carries on
+    /// from the checkpointed iteration count. Returns the final
+    /// conversation alongside the result.
+    pub async fn resume(
+        &self,
+        mut checkpoint: RunCheckpoint,
+    ) -> Result<(Conversation, ReactResult), rustedclaw_core::Error> {
+        info!(
+            run_id = %checkpoint.run_id,
+            iterations = checkpoint.iterations(),
+            pending = checkpoint.pending_tool_calls.len(),
+            "ReAct run resuming from checkpoint"
+        );
+
+        let result = self.execute(&mut checkpoint, None).await?;
+        Ok((checkpoint.conversation, result))
+    }
+
+    /// Run the loop under a cancellation token, registered by run ID.
+    ///
+    /// With an event sink the LLM is streamed and progress is reported as
+    /// [`AgentStreamEvent`]s; the caller reports how the run ended.
+    async fn execute(
+        &self,
+        cp: &mut RunCheckpoint,
+        events: Option<&EventSink>,
+    ) -> Result<ReactResult, rustedclaw_core::Error> {
+        let token = self.cancel.clone().unwrap_or_default();
+        let _guard = match &self.runs {
+            Some(runs) => {
+                Some(runs.register(&cp.run_id, cp.conversation.id.to_string(), token.clone())?)
+            }
+            None => None,
         };
+        emit(
+            events,
+            AgentStreamEvent::Started {
+                run_id: cp.run_id.clone(),
+                conversation_id: cp.conversation.id.to_string(),
+            },
+        )
+        .await;
+
+        info!(model = %self.model, run_id = %cp.run_id, max_iter = cp.working_memory.max_iterations, "ReAct loop starting");
+
+        // ── Start telemetry trace (or nest under a delegating parent) ──
+        let (scope, owns_trace) =
+            telemetry_scope(self.telemetry.as_ref(), &cp.conversation.id.to_string());
 
-        match memory.store(entry).await {
-            Ok(id) => debug!(memory_id = %id, "ReactAgent auto-saved to memory"),
-            Err(e) => warn!("ReactAgent failed to auto-save to memory: {e}"),
+        let result = self.execute_loop(cp, &token, &scope, events).await;
+
+        if owns_trace && let (Some(telemetry), Some(tid)) = (&self.telemetry, &scope.trace_id) {
+            telemetry.end_trace(tid);
+        }
+        if let Err(rustedclaw_core::Error::Cancelled(_)) = &result {
+            info!(run_id = %cp.run_id, "ReAct run cancelled");
+            self.clear_checkpoint(&cp.run_id).await;
         }
+        result
     }
 
-    /// Execute the ReAct loop.
-    ///
-    /// Takes a user message, optional long-term memories, and optional
-    /// knowledge chunks (for RAG integration). Returns the final answer
-    /// along with the complete reasoning trace.
-    pub async fn run(
+    /// The ReAct loop proper, operating on checkpointable run state.
+    async fn execute_loop(
         &self,
-        user_message: &str,
-        conversation: &mut Conversation,
-        memories: &[MemoryEntry],
-        knowledge_chunks: &[KnowledgeChunk],
+        cp: &mut RunCheckpoint,
+        token: &CancellationToken,
+        scope: &DelegationScope,
+        events: Option<&EventSink>,
     ) -> Result<ReactResult, rustedclaw_core::Error> {
-        let mut wm = WorkingMemory::new(self.max_iterations as usize);
-        let assembler = ContextAssembler::new(self.budget.clone());
+        let trace_id = scope.trace_id.as_deref();
         let tool_defs = self.tools.definitions();
-        let mut total_tool_calls = 0usize;
+        let ranker = self
+            .tool_ranker
+            .for_message(&cp.user_message, &tool_defs)
+            .await;
+        let assembler = self.layers.iter().cloned().fold(
+            ContextAssembler::new(self.budget())
+                .with_counter(self.counter())
+                .with_tool_ranker(ranker),
+            ContextAssembler::with_layer,
+        );
         let mut last_metadata: Option<AssemblyMetadata> = None;
+        let mut last_usage: Option<Usage> = None;
+        let no_trace = WorkingMemory::new(0);
 
-        info!(model = %self.model, max_iter = self.max_iterations, "ReAct loop starting");
-
-        // ── Start telemetry trace ──
-        let trace_id = self
-            .telemetry
-            .as_ref()
-            .map(|t| t.start_trace(conversation.id.to_string()));
+        loop {
+            // ── Execute tool calls requested in the previous iteration ──
+            if !cp.pending_tool_calls.is_empty() {
+                self.execute_pending_tools(cp, token, scope, events).await?;
+            }
 
-        // ── Auto-recall memories ──
-        let recalled = self.recall_memories(user_message).await;
-        let all_memories: Vec<MemoryEntry> = if recalled.is_empty() {
-            memories.to_vec()
-        } else {
-            let mut combined = memories.to_vec();
-            // Add recalled memories that aren't already in the provided list
-            for r in recalled {
-                if !combined.iter().any(|m| m.id == r.id) {
-                    combined.push(r);
-                }
+            if token.is_cancelled() {
+                return Err(rustedclaw_core::Error::Cancelled(cp.run_id.clone()));
             }
-            combined
-        };
 
-        loop {
-            if !wm.tick() {
-                warn!("ReAct: max iterations reached ({})", self.max_iterations);
+            if !cp.working_memory.tick() {
+                warn!(
+                    "ReAct: max iterations reached ({})",
+                    cp.working_memory.max_iterations
+                );
                 break;
             }
 
-            debug!(iteration = wm.iterations, "ReAct iteration");
+            debug!(iteration = cp.working_memory.iterations, "ReAct iteration");
 
             // ── Assemble context ──
-            let input = AssemblyInput {
-                identity: &self.identity,
-                memories: &all_memories,
-                working_memory: &wm,
-                knowledge_chunks,
-                tool_definitions: &tool_defs,
-                conversation,
-                user_message,
-            };
-
-            let assembled =
+            let assemble = |conversation: &Conversation| {
                 assembler
-                    .assemble(&input)
+                    .assemble(&AssemblyInput {
+                        identity: &self.identity,
+                        memories: &cp.memories,
+                        working_memory: if self.reasoning_trace {
+                            &cp.working_memory
+                        } else {
+                            &no_trace
+                        },
+                        knowledge_chunks: &cp.knowledge_chunks,
+                        tool_definitions: &tool_defs,
+                        conversation,
+                        user_message: &cp.user_message,
+                    })
                     .map_err(|e| rustedclaw_core::Error::Config {
                         message: format!("Context assembly failed: {}", e),
-                    })?;
+                    })
+            };
+
+            let mut assembled = assemble(&cp.conversation)?;
+            if let Some(summarizer) = &self.summarizer
+                && let Some(dropped) = history_overflow(&assembled.metadata)
+            {
+                match summarizer.fold(&mut cp.conversation, dropped).await {
+                    Ok(true) => assembled = assemble(&cp.conversation)?,
+                    Ok(false) => {}
+                    Err(e) => warn!("Conversation summarization failed: {e}"),
+                }
+            }
 
             last_metadata = Some(assembled.metadata.clone());
 
@@ -288,41 +576,48 @@ impl ReactAgent {
             let mut messages = vec![Message::system(&assembled.system_message)];
             messages.extend(assembled.messages);
 
-            let request = ProviderRequest {
+            let mut request = ProviderRequest {
                 model: self.model.clone(),
                 messages,
                 temperature: self.temperature,
                 max_tokens: self.max_tokens,
                 tools: assembled.tool_definitions,
-                stream: false,
+                stream: events.is_some(),
                 stop: vec![],
             };
+            for m in &self.middleware {
+                m.before_llm(&mut request).await?;
+            }
 
             // ── Call LLM ──
             let llm_start = std::time::Instant::now();
-            let response = self.provider.complete(request).await?;
+            let response = tokio::select! {
+                biased;
+                _ = token.cancelled() => {
+                    return Err(rustedclaw_core::Error::Cancelled(cp.run_id.clone()));
+                }
+                response = self.complete(request, events) => response?,
+            };
             let llm_duration_ms = llm_start.elapsed().as_millis() as u64;
 
             // Track usage
             if let Some(usage) = &response.usage {
+                last_usage = Some(usage.clone());
                 self.event_bus.publish(DomainEvent::ResponseGenerated {
-                    conversation_id: conversation.id.to_string(),
+                    conversation_id: cp.conversation.id.to_string(),
                     model: response.model.clone(),
                     tokens_used: usage.total_tokens,
                     timestamp: chrono::Utc::now(),
                 });
 
                 // Record telemetry span for this LLM call
-                if let (Some(telemetry), Some(tid)) = (&self.telemetry, &trace_id) {
+                if let (Some(telemetry), Some(tid)) = (&self.telemetry, trace_id) {
                     let cost = telemetry.compute_cost(
                         &response.model,
                         usage.prompt_tokens,
                         usage.completion_tokens,
                     );
-                    let mut span = rustedclaw_telemetry::Span::new(
-                        rustedclaw_telemetry::SpanKind::LlmCall,
-                        &response.model,
-                    );
+                    let mut span = new_span(scope, SpanKind::LlmCall, &response.model);
                     span.record_tokens(usage.prompt_tokens, usage.completion_tokens, cost);
                     span.duration_ms = Some(llm_duration_ms);
                     span.end(true);
@@ -332,124 +627,65 @@ impl ReactAgent {
 
             // ── Record thought ──
             if !response.message.content.is_empty() {
-                wm.add_thought(&response.message.content);
+                cp.working_memory.add_thought(&response.message.content);
             }
 
             // ── Check for final answer ──
             if response.message.tool_calls.is_empty() {
                 let answer = response.message.content.clone();
-                conversation.push(response.message);
+                cp.conversation.push(response.message);
 
                 // ── Auto-save to memory ──
-                self.auto_save_to_memory(user_message, &answer, conversation)
+                if self.auto_save
+                    && let Some(memory) = &self.memory
+                {
+                    save_turn(
+                        memory,
+                        self.extractor.as_ref(),
+                        &cp.user_message,
+                        &answer,
+                        &cp.conversation,
+                        &self.memory_scope_for(&cp.conversation),
+                        if events.is_some() {
+                            "react-stream"
+                        } else {
+                            "react"
+                        },
+                    )
                     .await;
-
-                // ── End telemetry trace ──
-                if let (Some(telemetry), Some(tid)) = (&self.telemetry, &trace_id) {
-                    telemetry.end_trace(tid);
                 }
+                for m in &self.middleware {
+                    m.after_run(&cp.user_message, &answer).await;
+                }
+
+                self.clear_checkpoint(&cp.run_id).await;
 
                 info!(
-                    iterations = wm.iterations,
-                    tool_calls = total_tool_calls,
+                    iterations = cp.working_memory.iterations,
+                    tool_calls = cp.tool_calls_made,
                     "ReAct loop completed"
                 );
 
                 return Ok(ReactResult {
                     answer,
-                    trace: wm.trace.clone(),
-                    iterations: wm.iterations,
-                    working_memory: wm,
-                    tool_calls_made: total_tool_calls,
+                    run_id: cp.run_id.clone(),
+                    trace: cp.working_memory.trace.clone(),
+                    iterations: cp.working_memory.iterations,
+                    working_memory: cp.working_memory.clone(),
+                    tool_calls_made: cp.tool_calls_made,
                     last_context_metadata: last_metadata,
+                    usage: last_usage,
                 });
             }
 
-            // ── Execute tool calls ──
-            let tool_calls = response.message.tool_calls.clone();
-            conversation.push(response.message);
-
-            for tc in &tool_calls {
-                total_tool_calls += 1;
-
-                // Record Action
-                wm.add_action(&format!("{}({})", tc.name, tc.arguments));
-
-                let call = ToolCall {
-                    id: tc.id.clone(),
-                    name: tc.name.clone(),
-                    arguments: serde_json::from_str(&tc.arguments).unwrap_or_default(),
-                };
-
-                let start = std::time::Instant::now();
-                let result = self.tools.execute(&call).await;
-                let duration_ms = start.elapsed().as_millis() as u64;
-
-                match result {
-                    Ok(tool_result) => {
-                        // Record Observation
-                        wm.add_observation(&tool_result.output);
-                        wm.add_tool_result(
-                            &tc.name,
-                            &tc.arguments,
-                            &tool_result.output,
-                            tool_result.success,
-                        );
-
-                        self.event_bus.publish(DomainEvent::ToolExecuted {
-                            tool_name: tc.name.clone(),
-                            success: tool_result.success,
-                            duration_ms,
-                            timestamp: chrono::Utc::now(),
-                        });
-
-                        // Record tool span in telemetry
-                        if let (Some(telemetry), Some(tid)) = (&self.telemetry, &trace_id) {
-                            let mut span = rustedclaw_telemetry::Span::new(
-                                rustedclaw_telemetry::SpanKind::ToolExecution,
-                                &tc.name,
-                            );
-                            span.duration_ms = Some(duration_ms);
-                            span.end(tool_result.success);
-                            telemetry.record_span(tid, span);
-                        }
-
-                        conversation.push(Message::tool_result(&tc.id, &tool_result.output));
-                    }
-                    Err(e) => {
-                        let error_msg = format!("Error: {}", e);
-                        wm.add_observation(&error_msg);
-                        wm.add_tool_result(&tc.name, &tc.arguments, &error_msg, false);
-
-                        self.event_bus.publish(DomainEvent::ToolExecuted {
-                            tool_name: tc.name.clone(),
-                            success: false,
-                            duration_ms,
-                            timestamp: chrono::Utc::now(),
-                        });
-
-                        // Record failed tool span in telemetry
-                        if let (Some(telemetry), Some(tid)) = (&self.telemetry, &trace_id) {
-                            let mut span = rustedclaw_telemetry::Span::new(
-                                rustedclaw_telemetry::SpanKind::ToolExecution,
-                                &tc.name,
-                            );
-                            span.duration_ms = Some(duration_ms);
-                            span.end(false);
-                            telemetry.record_span(tid, span);
-                        }
-
-                        conversation.push(Message::tool_result(&tc.id, &error_msg));
-                    }
-                }
-            }
+            // ── Queue tool calls for execution ──
+            cp.pending_tool_calls = response.message.tool_calls.clone();
+            cp.conversation.push(response.message);
+            self.save_checkpoint(cp).await;
         }
 
         // Max iterations exceeded — return partial result.
-        // ── End telemetry trace ──
-        if let (Some(telemetry), Some(tid)) = (&self.telemetry, &trace_id) {
-            telemetry.end_trace(tid);
-        }
+        self.clear_checkpoint(&cp.run_id).await;
 
         let answer =
             "I've reached the maximum number of reasoning iterations. Here's what I found so far."
@@ -457,386 +693,352 @@ impl ReactAgent {
 
         Ok(ReactResult {
             answer,
-            trace: wm.trace.clone(),
-            working_memory: wm,
-            iterations: self.max_iterations as usize,
-            tool_calls_made: total_tool_calls,
+            run_id: cp.run_id.clone(),
+            trace: cp.working_memory.trace.clone(),
+            working_memory: cp.working_memory.clone(),
+            iterations: cp.working_memory.max_iterations,
+            tool_calls_made: cp.tool_calls_made,
             last_context_metadata: last_metadata,
+            usage: last_usage,
+        })
+    }
+
+    /// Call the LLM. With an event sink the response is streamed: text
+    /// deltas are forwarded as chunks and the full message is assembled.
+    async fn complete(
+        &self,
+        request: ProviderRequest,
+        events: Option<&EventSink>,
+    ) -> Result<ProviderResponse, ProviderError> {
+        if events.is_none() {
+            return self.provider.complete(request).await;
+        }
+
+        let mut stream = self.provider.stream(request).await?;
+        let mut message = Message::assistant("");
+        let mut usage = None;
+        while let Some(chunk) = stream.recv().await {
+            let chunk = chunk?;
+            if let Some(text) = chunk.content
+                && !text.is_empty()
+            {
+                message.content.push_str(&text);
+                emit(events, AgentStreamEvent::Chunk { content: text }).await;
+            }
+            // Tool call arguments arrive as deltas keyed by call ID.
+            for tc in chunk.tool_calls {
+                match message.tool_calls.iter_mut().find(|t| t.id == tc.id) {
+                    Some(existing) => existing.arguments.push_str(&tc.arguments),
+                    None => message.tool_calls.push(tc),
+                }
+            }
+            if chunk.usage.is_some() {
+                usage = chunk.usage;
+            }
+        }
+
+        Ok(ProviderResponse {
+            message,
+            usage,
+            model: self.model.clone(),
+            metadata: Default::default(),
         })
     }
 
+    /// Execute every pending tool call, checkpointing after each one.
+    async fn execute_pending_tools(
+        &self,
+        cp: &mut RunCheckpoint,
+        token: &CancellationToken,
+        scope: &DelegationScope,
+        events: Option<&EventSink>,
+    ) -> Result<(), rustedclaw_core::Error> {
+        let memory_scope = self.memory_scope_for(&cp.conversation);
+        while !cp.pending_tool_calls.is_empty() {
+            let tc = cp.pending_tool_calls.remove(0);
+            cp.tool_calls_made += 1;
+
+            // Record Action
+            cp.working_memory
+                .add_action(&format!("{}({})", tc.name, tc.arguments));
+
+            let call = ToolCall {
+                id: tc.id.clone(),
+                name: tc.name.clone(),
+                arguments: serde_json::from_str(&tc.arguments).unwrap_or_default(),
+            };
+            emit(
+                events,
+                AgentStreamEvent::ToolCall {
+                    id: call.id.clone(),
+                    name: call.name.clone(),
+                    input: call.arguments.clone(),
+                },
+            )
+            .await;
+
+            if let ToolVerdict::Block(message) = check_tool(&self.middleware, &call).await {
+                cp.working_memory.add_observation(&message);
+                cp.working_memory
+                    .add_tool_result(&tc.name, &tc.arguments, &message, false);
+                emit(
+                    events,
+                    AgentStreamEvent::ToolResult {
+                        id: tc.id.clone(),
+                        name: tc.name.clone(),
+                        output: message.clone(),
+                        success: false,
+                    },
+                )
+                .await;
+                cp.conversation.push(Message::tool_result(&tc.id, &message));
+                self.save_checkpoint(cp).await;
+                continue;
+            }
+
+            // Tools run inside this span's scope so delegated sub-agents nest under it.
+            let mut span = new_span(scope, SpanKind::ToolExecution, &tc.name);
+            let tool_scope = DelegationScope {
+                parent_span_id: Some(span.id.clone()),
+                ..scope.clone()
+            };
+
+            let start = std::time::Instant::now();
+            let result = tokio::select! {
+                biased;
+                _ = token.cancelled() => {
+                    return Err(rustedclaw_core::Error::Cancelled(cp.run_id.clone()));
+                }
+                result = delegate::with_scope(
+                    tool_scope,
+                    memory_scope.clone().enter(self.tools.execute(&call)),
+                ) => result,
+            };
+            let duration_ms = start.elapsed().as_millis() as u64;
+
+            let (mut output, success) = match result {
+                Ok(tool_result) => (tool_result.output, tool_result.success),
+                Err(e) => (format!("Error: {}", e), false),
+            };
+            for m in &self.middleware {
+                m.after_tool(&call, &mut output, success).await;
+            }
+
+            // Record Observation
+            cp.working_memory.add_observation(&output);
+            cp.working_memory
+                .add_tool_result(&tc.name, &tc.arguments, &output, success);
+
+            self.event_bus.publish(DomainEvent::ToolExecuted {
+                tool_name: tc.name.clone(),
+                success,
+                duration_ms,
+                timestamp: chrono::Utc::now(),
+            });
+
+            // Record tool span in telemetry
+            if let (Some(telemetry), Some(tid)) = (&self.telemetry, &scope.trace_id) {
+                span.end(success);
+                span.duration_ms = Some(duration_ms);
+                telemetry.record_span(tid, span);
+            }
+
+            emit(
+                events,
+                AgentStreamEvent::ToolResult {
+                    id: tc.id.clone(),
+                    name: tc.name.clone(),
+                    output: output.clone(),
+                    success,
+                },
+            )
+            .await;
+            cp.conversation.push(Message::tool_result(&tc.id, &output));
+            self.save_checkpoint(cp).await;
+        }
+        Ok(())
+    }
+
+    /// Persist the run state if a checkpoint store is attached.
+    ///
+    /// Failures are logged, not propagated — a broken store must not
+    /// abort an otherwise healthy run.
+    async fn save_checkpoint(&self, cp: &mut RunCheckpoint) {
+        let Some(store) = &self.checkpoints else {
+            return;
+        };
+        cp.updated_at = Utc::now();
+        if let Err(e) = store.save(cp).await {
+            warn!(run_id = %cp.run_id, "Failed to save checkpoint: {e}");
+        }
+    }
+
+    /// Remove the checkpoint of a finished run.
+    async fn clear_checkpoint(&self, run_id: &str) {
+        if let Some(store) = &self.checkpoints
+            && let Err(e) = store.delete(run_id).await
+        {
+            warn!(run_id = %run_id, "Failed to delete checkpoint: {e}");
+        }
+    }
+
     /// Streaming variant of [`run`].
     ///
     /// Returns an `mpsc::Receiver` that yields `AgentStreamEvent`s as the
     /// ReAct loop progresses.  The receiver is populated by a background
-    /// task — the caller simply reads from it.
+    /// task — the caller simply reads from it. The run is checkpointed and
+    /// registered exactly like a blocking one.
     pub async fn run_stream(
         &self,
         user_message: &str,
         conversation: &mut Conversation,
         memories: &[MemoryEntry],
         knowledge_chunks: &[KnowledgeChunk],
-    ) -> Result<mpsc::Receiver<crate::stream_event::AgentStreamEvent>, rustedclaw_core::Error> {
-        use crate::stream_event::AgentStreamEvent;
-
+    ) -> Result<mpsc::Receiver<AgentStreamEvent>, rustedclaw_core::Error> {
         let (tx, rx) = mpsc::channel::<AgentStreamEvent>(128);
 
-        // ── Prepare everything we need to move into the spawned task ──
-        let provider = self.provider.clone();
-        let model = self.model.clone();
-        let temperature = self.temperature;
-        let max_tokens = self.max_tokens;
-        let tools = self.tools.clone();
-        let identity = self.identity.clone();
-        let budget = self.budget.clone();
-        let max_iterations = self.max_iterations;
-        let event_bus = self.event_bus.clone();
-        let memory = self.memory.clone();
-        let auto_save = self.auto_save;
-        let recall_limit = self.recall_limit;
-        let telemetry = self.telemetry.clone();
-        let user_msg = user_message.to_string();
-        let mut conv = conversation.clone();
-        let memories = memories.to_vec();
-        let knowledge_chunks = knowledge_chunks.to_vec();
-
-        tokio::spawn(async move {
-            let mut wm = WorkingMemory::new(max_iterations as usize);
-            let assembler = ContextAssembler::new(budget);
-            let tool_defs = tools.definitions();
-            let mut total_tool_calls = 0usize;
-            let conv_id = conv.id.to_string();
-
-            // ── Start telemetry trace ──
-            let trace_id = telemetry.as_ref().map(|t| t.start_trace(&conv_id));
-
-            // ── Auto-recall memories ──
-            let recalled: Vec<MemoryEntry> = if let Some(mem) = &memory {
-                mem.search(MemoryQuery {
-                    text: user_msg.clone(),
-                    limit: recall_limit,
-                    min_score: 0.0,
-                    tags: vec![],
-                    mode: SearchMode::Hybrid,
-                })
-                .await
-                .unwrap_or_default()
-            } else {
-                vec![]
-            };
-
-            let all_memories: Vec<MemoryEntry> = if recalled.is_empty() {
-                memories.clone()
-            } else {
-                let mut combined = memories.clone();
-                for r in recalled {
-                    if !combined.iter().any(|m| m.id == r.id) {
-                        combined.push(r);
-                    }
-                }
-                combined
-            };
-
-            let mut last_usage = None;
+        let token = self
+            .cancel
+            .as_ref()
+            .map(|t| t.child_token())
+            .unwrap_or_default();
+        let mut agent = self.clone();
+        agent.cancel = Some(token.clone());
 
-            loop {
-                if !wm.tick() {
-                    warn!("ReAct stream: max iterations reached");
-                    break;
+        // Cancel the run if the consumer goes away (e.g. SSE client disconnects).
+        let finished = CancellationToken::new();
+        {
+            let tx = tx.clone();
+            let finished = finished.clone();
+            tokio::spawn(async move {
+                tokio::select! {
+                    _ = tx.closed() => token.cancel(),
+                    _ = finished.cancelled() => {}
                 }
+            });
+        }
 
-                // ── Assemble context ──
-                let input = AssemblyInput {
-                    identity: &identity,
-                    memories: &all_memories,
-                    working_memory: &wm,
-                    knowledge_chunks: &knowledge_chunks,
-                    tool_definitions: &tool_defs,
-                    conversation: &conv,
-                    user_message: &user_msg,
-                };
-
-                let assembled = match assembler.assemble(&input) {
-                    Ok(a) => a,
-                    Err(e) => {
-                        let _ = tx
-                            .send(AgentStreamEvent::Error {
-                                message: format!("Context assembly failed: {}", e),
-                            })
-                            .await;
-                        return;
-                    }
-                };
-
-                let mut messages = vec![Message::system(&assembled.system_message)];
-                messages.extend(assembled.messages);
-
-                let request = ProviderRequest {
-                    model: model.clone(),
-                    messages,
-                    temperature,
-                    max_tokens,
-                    tools: assembled.tool_definitions,
-                    stream: true,
-                    stop: vec![],
-                };
-
-                // ── Stream from provider ──
-                let llm_start = std::time::Instant::now();
-                let mut stream_rx = match provider.stream(request).await {
-                    Ok(rx) => rx,
-                    Err(e) => {
-                        let _ = tx
-                            .send(AgentStreamEvent::Error {
-                                message: format!("Provider error: {}", e),
-                            })
-                            .await;
-                        return;
-                    }
-                };
-
-                // Accumulate the full response from streaming chunks
-                let mut full_content = String::new();
-                let mut accumulated_tool_calls: Vec<rustedclaw_core::message::MessageToolCall> =
-                    Vec::new();
-
-                while let Some(chunk_result) = stream_rx.recv().await {
-                    match chunk_result {
-                        Ok(chunk) => {
-                            // Forward text chunks to client
-                            if let Some(ref text) = chunk.content
-                                && !text.is_empty()
-                            {
-                                full_content.push_str(text);
-                                let _ = tx
-                                    .send(AgentStreamEvent::Chunk {
-                                        content: text.clone(),
-                                    })
-                                    .await;
-                            }
-
-                            // Accumulate tool calls
-                            for tc in &chunk.tool_calls {
-                                // Merge or add tool call deltas
-                                if let Some(existing) =
-                                    accumulated_tool_calls.iter_mut().find(|t| t.id == tc.id)
-                                {
-                                    existing.arguments.push_str(&tc.arguments);
-                                } else {
-                                    accumulated_tool_calls.push(tc.clone());
-                                }
-                            }
-
-                            if let Some(usage) = chunk.usage {
-                                last_usage = Some(usage);
-                            }
-                        }
-                        Err(e) => {
-                            let _ = tx
-                                .send(AgentStreamEvent::Error {
-                                    message: format!("Stream error: {}", e),
-                                })
-                                .await;
-                            return;
-                        }
-                    }
+        let user_message = user_message.to_string();
+        let conversation = conversation.clone();
+        let memories = memories.to_vec();
+        let knowledge_chunks = knowledge_chunks.to_vec();
+        let run = async move {
+            let _finished = finished.drop_guard();
+            let mut cp = agent
+                .start_run(&user_message, &conversation, &memories, &knowledge_chunks)
+                .await;
+            let event = match agent.execute(&mut cp, Some(&tx)).await {
+                Ok(result) => AgentStreamEvent::Done {
+                    conversation_id: cp.conversation.id.to_string(),
+                    usage: result.usage,
+                    iterations: result.iterations,
+                    tool_calls_made: result.tool_calls_made,
+                },
+                Err(rustedclaw_core::Error::Cancelled(run_id)) => {
+                    AgentStreamEvent::Cancelled { run_id }
                 }
+                Err(e) => AgentStreamEvent::Error {
+                    message: e.to_string(),
+                },
+            };
+            let _ = tx.send(event).await;
+        };
 
-                let llm_duration_ms = llm_start.elapsed().as_millis() as u64;
+        // The spawned task keeps the caller's memory and delegation scopes.
+        let run = MemoryScope::current().enter(run);
+        match delegate::current_scope() {
+            Some(parent) => tokio::spawn(delegate::with_scope(parent, run)),
+            None => tokio::spawn(run),
+        };
 
-                // Record telemetry span for this LLM call
-                if let (Some(telem), Some(tid)) = (&telemetry, &trace_id)
-                    && let Some(ref usage) = last_usage
-                {
-                    let cost =
-                        telem.compute_cost(&model, usage.prompt_tokens, usage.completion_tokens);
-                    let mut span = rustedclaw_telemetry::Span::new(
-                        rustedclaw_telemetry::SpanKind::LlmCall,
-                        &model,
-                    );
-                    span.record_tokens(usage.prompt_tokens, usage.completion_tokens, cost);
-                    span.duration_ms = Some(llm_duration_ms);
-                    span.end(true);
-                    telem.record_span(tid, span);
-                }
+        Ok(rx)
+    }
+}
 
-                // Record thought
-                if !full_content.is_empty() {
-                    wm.add_thought(&full_content);
-                }
+/// Send an event to the sink of a streamed run, if there is one.
+async fn emit(events: Option<&EventSink>, event: AgentStreamEvent) {
+    if let Some(tx) = events {
+        let _ = tx.send(event).await;
+    }
+}
 
-                // ── Check for final answer ──
-                if accumulated_tool_calls.is_empty() {
-                    let mut msg = Message::assistant(&full_content);
-                    msg.tool_calls = vec![];
-                    conv.push(msg);
-
-                    // Auto-save
-                    if auto_save
-                        && let Some(mem) = &memory
-                        && user_msg.len() >= 10
-                        && full_content.len() >= 10
-                    {
-                        let summary = format!(
-                            "User asked: {}\nAssistant answered: {}",
-                            user_msg, full_content
-                        );
-                        let entry = MemoryEntry {
-                            id: String::new(),
-                            content: summary,
-                            tags: vec![
-                                "conversation".into(),
-                                "auto-saved".into(),
-                                "react-stream".into(),
-                            ],
-                            source: Some(conv_id.clone()),
-                            created_at: Utc::now(),
-                            last_accessed: Utc::now(),
-                            score: 0.0,
-                            embedding: None,
-                        };
-                        let _ = mem.store(entry).await;
-                    }
-
-                    // ── End telemetry trace ──
-                    if let (Some(telem), Some(tid)) = (&telemetry, &trace_id) {
-                        telem.end_trace(tid);
-                    }
-
-                    let _ = tx
-                        .send(AgentStreamEvent::Done {
-                            conversation_id: conv_id,
-                            usage: last_usage,
-                            iterations: wm.iterations,
-                            tool_calls_made: total_tool_calls,
-                        })
-                        .await;
-                    return;
-                }
+/// Auto-save a finished turn: extracted facts when an extractor is
+/// attached, otherwise the exchange itself tagged with `pattern`.
+/// Extraction runs in the background so it doesn't delay the answer.
+async fn save_turn(
+    memory: &Arc<dyn MemoryBackend>,
+    extractor: Option<&FactExtractor>,
+    user_message: &str,
+    answer: &str,
+    conversation: &Conversation,
+    scope: &MemoryScope,
+    pattern: &str,
+) {
+    // Only save meaningful exchanges
+    if user_message.len() < 10 || answer.len() < 10 {
+        return;
+    }
 
-                // ── Execute tool calls ──
-                let tool_calls_vec = accumulated_tool_calls.clone();
-                let mut assistant_msg = Message::assistant(&full_content);
-                assistant_msg.tool_calls = tool_calls_vec.clone();
-                conv.push(assistant_msg);
-
-                for tc in &tool_calls_vec {
-                    total_tool_calls += 1;
-                    wm.add_action(&format!("{}({})", tc.name, tc.arguments));
-
-                    // Emit tool_call event
-                    let _ = tx
-                        .send(AgentStreamEvent::ToolCall {
-                            id: tc.id.clone(),
-                            name: tc.name.clone(),
-                            input: serde_json::from_str(&tc.arguments).unwrap_or_default(),
-                        })
-                        .await;
-
-                    let call = ToolCall {
-                        id: tc.id.clone(),
-                        name: tc.name.clone(),
-                        arguments: serde_json::from_str(&tc.arguments).unwrap_or_default(),
-                    };
-
-                    let start = std::time::Instant::now();
-                    let result = tools.execute(&call).await;
-                    let duration_ms = start.elapsed().as_millis() as u64;
-
-                    match result {
-                        Ok(tool_result) => {
-                            wm.add_observation(&tool_result.output);
-                            wm.add_tool_result(
-                                &tc.name,
-                                &tc.arguments,
-                                &tool_result.output,
-                                tool_result.success,
-                            );
-
-                            event_bus.publish(DomainEvent::ToolExecuted {
-                                tool_name: tc.name.clone(),
-                                success: tool_result.success,
-                                duration_ms,
-                                timestamp: Utc::now(),
-                            });
-
-                            // Record tool span in telemetry
-                            if let (Some(telem), Some(tid)) = (&telemetry, &trace_id) {
-                                let mut span = rustedclaw_telemetry::Span::new(
-                                    rustedclaw_telemetry::SpanKind::ToolExecution,
-                                    &tc.name,
-                                );
-                                span.duration_ms = Some(duration_ms);
-                                span.end(tool_result.success);
-                                telem.record_span(tid, span);
-                            }
-
-                            let _ = tx
-                                .send(AgentStreamEvent::ToolResult {
-                                    id: tc.id.clone(),
-                                    name: tc.name.clone(),
-                                    output: tool_result.output.clone(),
-                                    success: tool_result.success,
-                                })
-                                .await;
-
-                            conv.push(Message::tool_result(&tc.id, &tool_result.output));
-                        }
-                        Err(e) => {
-                            let error_msg = format!("Error: {}", e);
-                            wm.add_observation(&error_msg);
-                            wm.add_tool_result(&tc.name, &tc.arguments, &error_msg, false);
-
-                            event_bus.publish(DomainEvent::ToolExecuted {
-                                tool_name: tc.name.clone(),
-                                success: false,
-                                duration_ms,
-                                timestamp: Utc::now(),
-                            });
-
-                            // Record failed tool span in telemetry
-                            if let (Some(telem), Some(tid)) = (&telemetry, &trace_id) {
-                                let mut span = rustedclaw_telemetry::Span::new(
-                                    rustedclaw_telemetry::SpanKind::ToolExecution,
-                                    &tc.name,
-                                );
-                                span.duration_ms = Some(duration_ms);
-                                span.end(false);
-                                telem.record_span(tid, span);
-                            }
-
-                            let _ = tx
-                                .send(AgentStreamEvent::ToolResult {
-                                    id: tc.id.clone(),
-                                    name: tc.name.clone(),
-                                    output: error_msg.clone(),
-                                    success: false,
-                                })
-                                .await;
-
-                            conv.push(Message::tool_result(&tc.id, &error_msg));
-                        }
-                    }
-                }
-            }
+    if let Some(extractor) = extractor {
+        extractor.spawn(
+            memory.clone(),
+            user_message.to_string(),
+            answer.to_string(),
+            conversation.clone(),
+            scope.clone(),
+        );
+        return;
+    }
 
-            // Max iterations exceeded
-            // ── End telemetry trace ──
-            if let (Some(telem), Some(tid)) = (&telemetry, &trace_id) {
-                telem.end_trace(tid);
-            }
+    let entry = MemoryEntry {
+        id: String::new(),
+        content: format!("User asked: {user_message}\nAssistant answered: {answer}"),
+        tags: vec!["conversation".into(), "auto-saved".into(), pattern.into()],
+        source: Some(conversation.id.to_string()),
+        created_at: Utc::now(),
+        last_accessed: Utc::now(),
+        access_count: 0,
+        expires_at: None,
+        score: 0.0,
+        embedding: None,
+        embedding_model: None,
+        scope: scope.clone(),
+    };
+    match memory.store(entry).await {
+        Ok(id) => debug!(memory_id = %id, "ReactAgent auto-saved to memory"),
+        Err(e) => warn!("ReactAgent failed to auto-save to memory: {e}"),
+    }
+}
 
-            let _ = tx
-                .send(AgentStreamEvent::Done {
-                    conversation_id: conv_id,
-                    usage: last_usage,
-                    iterations: max_iterations as usize,
-                    tool_calls_made: total_tool_calls,
-                })
-                .await;
-        });
+/// Resolve the telemetry scope for a run starting on the current task.
+///
+/// Inside a delegating parent's tool call the run records into the parent's
+/// trace, one level deeper; otherwise it starts (and owns) a new trace.
+fn telemetry_scope(
+    telemetry: Option<&Arc<TelemetryEngine>>,
+    conversation_id: &str,
+) -> (DelegationScope, bool) {
+    let parent = delegate::current_scope();
+    let depth = parent.as_ref().map_or(0, |p| p.depth + 1);
+    match parent {
+        Some(parent) if parent.trace_id.is_some() => (DelegationScope { depth, ..parent }, false),
+        _ => (
+            DelegationScope {
+                trace_id: telemetry.map(|t| t.start_trace(conversation_id)),
+                parent_span_id: None,
+                depth,
+            },
+            true,
+        ),
+    }
+}
 
-        Ok(rx)
+/// Create a span, parented to the scope's current tool span if any.
+fn new_span(scope: &DelegationScope, kind: SpanKind, label: &str) -> Span {
+    let span = Span::new(kind, label);
+    match &scope.parent_span_id {
+        Some(parent) => span.with_parent(parent),
+        None => span,
     }
 }
 
@@ -873,6 +1075,49 @@ mod tests {
         assert_eq!(result.tool_calls_made, 0);
     }
 
+    #[tokio::test]
+    async fn overflowing_history_is_summarized() {
+        let provider = Arc::new(SequentialMockProvider::new(vec![
+            make_text_response("User discussed topics 0-7."),
+            make_text_response("Final answer"),
+        ]));
+        let identity = Identity::default();
+        let system_tokens = crate::context::token::estimate_tokens(&identity.system_prompt);
+        let agent = ReactAgent::new(
+            provider.clone(),
+            "mock-model",
+            0.7,
+            Arc::new(ToolRegistry::new()),
+            identity,
+            Arc::new(EventBus::default()),
+        )
+        .with_budget(TokenBudget {
+            total: system_tokens + 150,
+            ..TokenBudget::default()
+        })
+        .with_summarizer(ConversationSummarizer::new(provider, "mock-model"));
+
+        let mut conv = Conversation::new();
+        for i in 0..10 {
+            conv.push(Message::user(format!("Topic {i}: {}", "x".repeat(80))));
+        }
+        conv.push(Message::user("Hello"));
+
+        let result = agent.run("Hello", &mut conv, &[], &[]).await.unwrap();
+        assert_eq!(result.answer, "Final answer");
+
+        let summary = conv.summary.as_ref().expect("history should be summarized");
+        assert_eq!(summary.text, "User discussed topics 0-7.");
+        assert!(summary.covered_messages > 0);
+        let metadata = result.last_context_metadata.unwrap();
+        assert!(
+            metadata
+                .per_layer
+                .iter()
+                .any(|l| l.name == "conversation_summary" && l.tokens > 0)
+        );
+    }
+
     #[tokio::test]
     async fn thought_action_observation_trace() {
         let tool_calls = vec![make_tool_call(
@@ -1021,8 +1266,12 @@ mod tests {
             source: None,
             created_at: chrono::Utc::now(),
             last_accessed: chrono::Utc::now(),
+            access_count: 0,
+            expires_at: None,
             score: 0.0,
             embedding: None,
+            embedding_model: None,
+            scope: MemoryScope::default(),
         }];
 
         let result = agent.run("Test", &mut conv, &memories, &[]).await.unwrap();
@@ -1037,6 +1286,105 @@ mod tests {
         assert_eq!(mem_layer.items_included, 1);
     }
 
+    #[tokio::test]
+    async fn checkpoint_survives_failure_and_resumes() {
+        use crate::checkpoint::InMemoryCheckpointStore;
+
+        let store = Arc::new(InMemoryCheckpointStore::new());
+        let tools = Arc::new(rustedclaw_tools::default_registry());
+        let event_bus = Arc::new(EventBus::default());
+
+        // First attempt: one tool call, then the provider goes away.
+        let provider = Arc::new(SequentialMockProvider::failing_after(vec![
+            make_tool_call_response(
+                vec![make_tool_call(
+                    "calculator",
+                    serde_json::json!({"expression": "2 + 3"}),
+                )],
+                "I need to calculate 2 + 3",
+            ),
+        ]));
+        let agent = ReactAgent::new(
+            provider,
+            "mock-model",
+            0.7,
+            tools.clone(),
+            Identity::default(),
+            event_bus.clone(),
+        )
+        .with_checkpoints(store.clone())
+        .with_run_id("run-1")
+        .with_reasoning_trace(false);
+
+        let mut conv = Conversation::new();
+        assert!(
+            agent
+                .run("What is 2+3?", &mut conv, &[], &[])
+                .await
+                .is_err()
+        );
+
+        let checkpoint = store.load("run-1").await.unwrap().unwrap();
+        assert_eq!(checkpoint.iterations(), 1);
+        assert_eq!(checkpoint.tool_calls_made, 1);
+        assert!(checkpoint.pending_tool_calls.is_empty());
+        assert_eq!(checkpoint.strategy, "loop");
+
+        // Second attempt: a fresh agent picks up where the first stopped.
+        let agent = ReactAgent::new(
+            Arc::new(SequentialMockProvider::single_text("The result is 5")),
+            "mock-model",
+            0.7,
+            tools,
+            Identity::default(),
+            event_bus,
+        )
+        .with_checkpoints(store.clone());
+
+        let (conv, result) = agent.resume(checkpoint).await.unwrap();
+        assert_eq!(result.answer, "The result is 5");
+        assert_eq!(result.run_id, "run-1");
+        assert_eq!(result.iterations, 2);
+        assert_eq!(result.tool_calls_made, 1);
+        assert_eq!(conv.messages.last().unwrap().content, "The result is 5");
+
+        // Finished runs leave no checkpoint behind.
+        assert!(store.load("run-1").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn resume_executes_pending_tool_calls() {
+        let (_, mut conv) = setup_react();
+        let tool_call = make_tool_call("calculator", serde_json::json!({"expression": "6 * 7"}));
+        let mut assistant = Message::assistant("Calculating");
+        assistant.tool_calls = vec![tool_call.clone()];
+        conv.push(Message::user("What is 6*7?"));
+        conv.push(assistant);
+
+        let mut wm = WorkingMemory::new(5);
+        wm.tick();
+        let mut checkpoint = RunCheckpoint::new("run-2", "What is 6*7?", conv, wm);
+        checkpoint.pending_tool_calls = vec![tool_call];
+
+        let agent = ReactAgent::new(
+            Arc::new(SequentialMockProvider::single_text("42")),
+            "mock-model",
+            0.7,
+            Arc::new(rustedclaw_tools::default_registry()),
+            Identity::default(),
+            Arc::new(EventBus::default()),
+        );
+
+        let (conv, result) = agent.resume(checkpoint).await.unwrap();
+        assert_eq!(result.answer, "42");
+        assert_eq!(result.tool_calls_made, 1);
+        assert!(
+            conv.messages
+                .iter()
+                .any(|m| m.tool_call_id.as_deref() == Some("call_calculator"))
+        );
+    }
+
     // ── Streaming tests ──
 
     #[tokio::test]
@@ -1138,4 +1486,144 @@ mod tests {
         assert!(has_tool_result, "Missing ToolResult event");
         assert!(has_done, "Missing Done event");
     }
+
+    #[tokio::test]
+    async fn cancelled_run_stops_and_clears_checkpoint() {
+        use crate::checkpoint::InMemoryCheckpointStore;
+
+        let store = Arc::new(InMemoryCheckpointStore::new());
+        let runs = Arc::new(RunRegistry::new());
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let (agent, mut conv) = setup_react();
+        let agent = agent
+            .with_checkpoints(store.clone())
+            .with_runs(runs.clone())
+            .with_run_id("run-c")
+            .with_cancellation(token);
+
+        let Err(err) = agent.run("Hello", &mut conv, &[], &[]).await else {
+            panic!("cancelled run should fail");
+        };
+        assert!(matches!(err, rustedclaw_core::Error::Cancelled(ref id) if id == "run-c"));
+        assert!(store.load("run-c").await.unwrap().is_none());
+        assert!(!runs.is_running("run-c"));
+    }
+
+    #[tokio::test]
+    async fn cancelled_stream_emits_cancelled_event() {
+        use crate::stream_event::AgentStreamEvent;
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let (agent, mut conv) = setup_react();
+        let agent = agent.with_run_id("run-s").with_cancellation(token);
+
+        let mut rx = agent
+            .run_stream("Hello", &mut conv, &[], &[])
+            .await
+            .unwrap();
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+
+        assert!(
+            matches!(&events[0], AgentStreamEvent::Started { run_id, .. } if run_id == "run-s")
+        );
+        assert!(
+            matches!(events.last(), Some(AgentStreamEvent::Cancelled { run_id }) if run_id == "run-s")
+        );
+        assert!(
+            !events
+                .iter()
+                .any(|e| matches!(e, AgentStreamEvent::Done { .. }))
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_stream_reports_error_and_ends_its_trace() {
+        let telemetry = Arc::new(TelemetryEngine::new());
+        let agent = ReactAgent::new(
+            Arc::new(SequentialMockProvider::failing_after(vec![])),
+            "mock-model",
+            0.7,
+            Arc::new(rustedclaw_tools::default_registry()),
+            Identity::default(),
+            Arc::new(EventBus::default()),
+        )
+        .with_telemetry(telemetry.clone());
+
+        let mut conv = Conversation::new();
+        let mut rx = agent
+            .run_stream("Hello", &mut conv, &[], &[])
+            .await
+            .unwrap();
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+
+        assert!(
+            matches!(events.last(), Some(AgentStreamEvent::Error { message }) if message.contains("connection reset"))
+        );
+        let traces = telemetry.recent_traces(10);
+        assert_eq!(traces.len(), 1);
+        assert!(traces[0].ended_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn streamed_run_checkpoints_and_resumes() {
+        use crate::checkpoint::InMemoryCheckpointStore;
+
+        let store = Arc::new(InMemoryCheckpointStore::new());
+        let provider = Arc::new(SequentialMockProvider::failing_after(vec![
+            make_tool_call_response(
+                vec![make_tool_call(
+                    "calculator",
+                    serde_json::json!({"expression": "2 + 3"}),
+                )],
+                "I need to calculate 2 + 3",
+            ),
+        ]));
+        let tools = Arc::new(rustedclaw_tools::default_registry());
+        let event_bus = Arc::new(EventBus::default());
+        let agent = ReactAgent::new(
+            provider,
+            "mock-model",
+            0.7,
+            tools.clone(),
+            Identity::default(),
+            event_bus.clone(),
+        )
+        .with_checkpoints(store.clone())
+        .with_run_id("run-s");
+
+        let mut conv = Conversation::new();
+        let mut rx = agent
+            .run_stream("What is 2+3?", &mut conv, &[], &[])
+            .await
+            .unwrap();
+        while rx.recv().await.is_some() {}
+
+        // The tool ran before the provider failed; the checkpoint has its result.
+        let checkpoint = store.load("run-s").await.unwrap().unwrap();
+        assert_eq!(checkpoint.tool_calls_made, 1);
+        assert!(checkpoint.pending_tool_calls.is_empty());
+
+        let agent = ReactAgent::new(
+            Arc::new(SequentialMockProvider::single_text("The result is 5")),
+            "mock-model",
+            0.7,
+            tools,
+            Identity::default(),
+            event_bus,
+        )
+        .with_checkpoints(store.clone());
+        let (_, result) = agent.resume(checkpoint).await.unwrap();
+        assert_eq!(result.answer, "The result is 5");
+        assert_eq!(result.tool_calls_made, 1);
+        assert!(store.load("run-s").await.unwrap().is_none());
+    }
 }
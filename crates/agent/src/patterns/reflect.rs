@@ -0,0 +1,513 @@
+//! Reflexion pattern — Draft → Critique → Revise loop.
+//!
+//! The agent first produces a draft answer with a regular ReAct run
+//! (tools included), then asks a critic to review the draft against the
+//! original request. If the critic finds problems, the draft is revised
+//! and critiqued again, up to a configurable number of rounds.
+//!
+//! # Flow
+//!
+//! 1. Run a [`ReactAgent`] to produce a draft answer
+//! 2. Critique the draft (optionally with a different model)
+//! 3. If the critic approves, stop; otherwise revise the draft
+//! 4. Repeat 2–3 until approved or `max_rounds` is reached
+//!
+//! Every critique is recorded as a [`TraceKind::Reflection`] entry in
+//! working memory, so the full self-critique history is inspectable.
+//!
+//! [`TraceKind::Reflection`]: crate::context::working_memory::TraceKind::Reflection
+
+use rustedclaw_core::event::{DomainEvent, EventBus};
+use rustedclaw_core::identity::Identity;
+use rustedclaw_core::memory::MemoryEntry;
+use rustedclaw_core::message::{Conversation, Message, Role};
+use rustedclaw_core::provider::{Provider, ProviderRequest, ProviderResponse};
+use rustedclaw_core::tool::ToolRegistry;
+use std::sync::Arc;
+use tracing::{debug, info};
+
+use crate::context::TokenBudget;
+use crate::context::assembler::{AssemblyMetadata, KnowledgeChunk};
+use crate::context::working_memory::{TraceEntry, WorkingMemory};
+use crate::middleware::Middleware;
+use crate::patterns::react::ReactAgent;
+
+/// Marker the critic replies with when the draft needs no changes.
+const APPROVAL_MARKER: &str = "APPROVED";
+
+/// Reflexion agent configuration.
+pub struct ReflexionAgent {
+    /// LLM provider used for the draft and revisions.
+    provider: Arc<dyn Provider>,
+    /// Model name used for the draft and revisions.
+    model: String,
+    /// Temperature.
+    temperature: f32,
+    /// Provider used for critiques (falls back to `provider`).
+    critic_provider: Option<Arc<dyn Provider>>,
+    /// Model used for critiques (falls back to `model`).
+    critic_model: Option<String>,
+    /// Maximum critique/revise rounds.
+    max_rounds: u32,
+    /// Maximum ReAct iterations for the draft.
+    max_iterations: u32,
+    /// Tool registry (available while drafting).
+    tools: Arc<ToolRegistry>,
+    /// Agent identity.
+    identity: Identity,
+    /// Token budget for context assembly.
+    budget: Option<TokenBudget>,
+    /// Event bus.
+    event_bus: Arc<EventBus>,
+    /// Middleware applied to every inner ReAct run.
+    middleware: Vec<Arc<dyn Middleware>>,
+}
+
+/// Result of a Reflexion execution.
+pub struct ReflexionResult {
+    /// The final (possibly revised) answer.
+    pub answer: String,
+    /// The first draft, before any revision.
+    pub draft: String,
+    /// Critiques produced in each round, in order.
+    pub critiques: Vec<String>,
+    /// Number of critique rounds performed.
+    pub rounds: usize,
+    /// Whether the critic approved the final answer.
+    pub approved: bool,
+    /// Complete reasoning trace (draft trace plus reflections).
+    pub trace: Vec<TraceEntry>,
+    /// Working memory snapshot at completion.
+    pub working_memory: WorkingMemory,
+    /// ReAct iterations used while drafting.
+    pub iterations: usize,
+    /// Tool calls made while drafting.
+    pub tool_calls_made: usize,
+    /// Context assembly metadata from the draft run.
+    pub last_context_metadata: Option<AssemblyMetadata>,
+}
+
+impl ReflexionAgent {
+    /// Create a new Reflexion agent.
+    pub fn new(
+        provider: Arc<dyn Provider>,
+        model: impl Into<String>,
+        temperature: f32,
+        tools: Arc<ToolRegistry>,
+        identity: Identity,
+        event_bus: Arc<EventBus>,
+    ) -> Self {
+        Self {
+            provider,
+            model: model.into(),
+            temperature,
+            critic_provider: None,
+            critic_model: None,
+            max_rounds: 2,
+            max_iterations: 10,
+            tools,
+            identity,
+            budget: None,
+            event_bus,
+            middleware: Vec::new(),
+        }
+    }
+
+    /// Use a different model for critiques.
+    pub fn with_critic_model(mut self, model: impl Into<String>) -> Self {
+        self.critic_model = Some(model.into());
+        self
+    }
+
+    /// Use a different provider for critiques.
+    pub fn with_critic_provider(mut self, provider: Arc<dyn Provider>) -> Self {
+        self.critic_provider = Some(provider);
+        self
+    }
+
+    /// Set the maximum number of critique/revise rounds.
+    pub fn with_max_rounds(mut self, rounds: u32) -> Self {
+        self.max_rounds = rounds;
+        self
+    }
+
+    /// Set max ReAct iterations for the draft.
+    pub fn with_max_iterations(mut self, max: u32) -> Self {
+        self.max_iterations = max;
+        self
+    }
+
+    /// Set the token budget.
+    pub fn with_budget(mut self, budget: TokenBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Set the token budget if one was given, for builders that forward an optional override.
+    pub(crate) fn with_budget_override(mut self, budget: Option<TokenBudget>) -> Self {
+        if budget.is_some() {
+            self.budget = budget;
+        }
+        self
+    }
+
+    /// Append a middleware to the drafting run.
+    pub fn with_middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// Execute the Reflexion pattern.
+    ///
+    /// 1. Drafts an answer with a ReAct run
+    /// 2. Critiques and revises it until approved or `max_rounds` is hit
+    pub async fn run(
+        &self,
+        user_message: &str,
+        conversation: &mut Conversation,
+        memories: &[MemoryEntry],
+        knowledge_chunks: &[KnowledgeChunk],
+    ) -> Result<ReflexionResult, rustedclaw_core::Error> {
+        info!(model = %self.model, max_rounds = self.max_rounds, "Reflexion: drafting");
+
+        // ── Step 1: Draft ──
+        let drafter = ReactAgent::new(
+            self.provider.clone(),
+            &self.model,
+            self.temperature,
+            self.tools.clone(),
+            self.identity.clone(),
+            self.event_bus.clone(),
+        )
+        .with_budget_override(self.budget.clone())
+        .with_max_iterations(self.max_iterations)
+        .with_middleware_chain(&self.middleware);
+
+        // The draft works on a scratch copy so only the final answer
+        // lands in the caller's conversation.
+        let mut draft_conv = conversation.clone();
+        let draft_result = drafter
+            .run(user_message, &mut draft_conv, memories, knowledge_chunks)
+            .await?;
+
+        let mut wm = draft_result.working_memory;
+        let draft = draft_result.answer;
+        let mut answer = draft.clone();
+        let mut critiques = Vec::new();
+        let mut approved = false;
+        let conv_id = conversation.id.to_string();
+
+        // ── Step 2: Critique / revise ──
+        for round in 1..=self.max_rounds {
+            let critique = self.critique(&conv_id, user_message, &answer).await?;
+            wm.add_reflection(&format!("Round {}: {}", round, critique));
+            critiques.push(critique.clone());
+
+            if is_approval(&critique) {
+                debug!(round, "Reflexion: critic approved");
+                approved = true;
+                break;
+            }
+
+            wm.add_thought(&format!("Revising answer (round {})", round));
+            answer = self
+                .revise(conversation, user_message, &answer, &critique)
+                .await?;
+        }
+
+        // Callers usually record the user turn before running; add it when
+        // they haven't so the stored exchange is complete.
+        if !ends_with_user_turn(conversation, user_message) {
+            conversation.push(Message::user(user_message));
+        }
+        conversation.push(Message::assistant(&answer));
+
+        info!(
+            rounds = critiques.len(),
+            approved,
+            answer_len = answer.len(),
+            "Reflexion: complete"
+        );
+
+        Ok(ReflexionResult {
+            answer,
+            draft,
+            rounds: critiques.len(),
+            critiques,
+            approved,
+            trace: wm.trace.clone(),
+            working_memory: wm,
+            iterations: draft_result.iterations,
+            tool_calls_made: draft_result.tool_calls_made,
+            last_context_metadata: draft_result.last_context_metadata,
+        })
+    }
+
+    /// Ask the critic to review the current answer.
+    async fn critique(
+        &self,
+        conversation_id: &str,
+        user_message: &str,
+        answer: &str,
+    ) -> Result<String, rustedclaw_core::Error> {
+        let prompt = format!(
+            "You are a strict reviewer. Check whether the answer fully and correctly \
+            addresses the user's request.\n\n\
+            Request: {}\n\n\
+            Answer: {}\n\n\
+            If the answer needs no changes, reply with exactly {}. Otherwise list the \
+            concrete problems and how to fix them. Be concise.",
+            user_message, answer, APPROVAL_MARKER
+        );
+
+        let provider = self.critic_provider.as_ref().unwrap_or(&self.provider);
+        let model = self.critic_model.as_ref().unwrap_or(&self.model);
+
+        let request = ProviderRequest {
+            model: model.clone(),
+            messages: vec![Message::system(&prompt)],
+            temperature: 0.2,
+            max_tokens: Some(1024),
+            tools: vec![],
+            stream: false,
+            stop: vec![],
+        };
+
+        let response = provider.complete(request).await?;
+        self.publish_usage(conversation_id, &response);
+        Ok(response.message.content.trim().to_string())
+    }
+
+    /// Rewrite the answer to address the critique. The model sees the
+    /// conversation the draft was written in, so revisions of follow-up
+    /// questions keep the earlier turns' context.
+    async fn revise(
+        &self,
+        conversation: &Conversation,
+        user_message: &str,
+        answer: &str,
+        critique: &str,
+    ) -> Result<String, rustedclaw_core::Error> {
+        let mut system_prompt = self.identity.system_prompt.clone();
+        if let Some(summary) = &conversation.summary {
+            system_prompt.push_str(&format!("\n\n[Conversation Summary]\n{}", summary.text));
+        }
+
+        // Earlier text turns only: tool exchanges are reflected in the
+        // answers that followed them, and a tool result without its call
+        // would be rejected by the provider.
+        let mut messages = vec![Message::system(&system_prompt)];
+        messages.extend(
+            conversation
+                .unsummarized()
+                .iter()
+                .filter(|m| {
+                    matches!(m.role, Role::User | Role::Assistant) && m.tool_calls.is_empty()
+                })
+                .cloned(),
+        );
+        if !ends_with_user_turn(conversation, user_message) {
+            messages.push(Message::user(user_message));
+        }
+        messages.push(Message::assistant(answer));
+        messages.push(Message::user(format!(
+            "Revise your previous answer to address the reviewer's feedback.\n\n\
+            Feedback: {}\n\n\
+            Reply with the improved answer only.",
+            critique
+        )));
+
+        let request = ProviderRequest {
+            model: self.model.clone(),
+            messages,
+            temperature: self.temperature,
+            max_tokens: Some(4096),
+            tools: vec![],
+            stream: false,
+            stop: vec![],
+        };
+
+        let response = self.provider.complete(request).await?;
+        self.publish_usage(&conversation.id.to_string(), &response);
+        Ok(response.message.content)
+    }
+
+    fn publish_usage(&self, conversation_id: &str, response: &ProviderResponse) {
+        if let Some(usage) = &response.usage {
+            self.event_bus.publish(DomainEvent::ResponseGenerated {
+                conversation_id: conversation_id.to_string(),
+                model: response.model.clone(),
+                tokens_used: usage.total_tokens,
+                timestamp: chrono::Utc::now(),
+            });
+        }
+    }
+}
+
+/// Whether the conversation's latest message is `user_message` from the user.
+fn ends_with_user_turn(conversation: &Conversation, user_message: &str) -> bool {
+    conversation
+        .messages
+        .last()
+        .is_some_and(|m| m.role == Role::User && m.content == user_message)
+}
+
+/// Whether a critique signals approval of the draft.
+fn is_approval(critique: &str) -> bool {
+    critique
+        .trim_start()
+        .to_uppercase()
+        .starts_with(APPROVAL_MARKER)
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::working_memory::TraceKind;
+    use crate::patterns::test_helpers::*;
+
+    fn make_agent(provider: SequentialMockProvider) -> ReflexionAgent {
+        ReflexionAgent::new(
+            Arc::new(provider),
+            "mock-model",
+            0.7,
+            Arc::new(rustedclaw_tools::default_registry()),
+            Identity::default(),
+            Arc::new(EventBus::default()),
+        )
+    }
+
+    #[tokio::test]
+    async fn approved_draft_is_returned_unchanged() {
+        let agent = make_agent(SequentialMockProvider::new(vec![
+            make_text_response("Paris is the capital of France."),
+            make_text_response("APPROVED"),
+        ]));
+        let mut conv = Conversation::new();
+
+        let result = agent
+            .run("What is the capital of France?", &mut conv, &[], &[])
+            .await
+            .unwrap();
+
+        assert!(result.approved);
+        assert_eq!(result.rounds, 1);
+        assert_eq!(result.answer, result.draft);
+        let roles: Vec<Role> = conv.messages.iter().map(|m| m.role.clone()).collect();
+        assert_eq!(roles, [Role::User, Role::Assistant]);
+        assert_eq!(conv.messages[0].content, "What is the capital of France?");
+        assert_eq!(conv.messages[1].content, result.answer);
+    }
+
+    #[tokio::test]
+    async fn critique_triggers_revision() {
+        let agent = make_agent(SequentialMockProvider::new(vec![
+            make_text_response("Lyon."),
+            make_text_response("Wrong city: the capital is Paris."),
+            make_text_response("Paris is the capital of France."),
+            make_text_response("APPROVED"),
+        ]));
+        let mut conv = Conversation::new();
+
+        let result = agent
+            .run("What is the capital of France?", &mut conv, &[], &[])
+            .await
+            .unwrap();
+
+        assert!(result.approved);
+        assert_eq!(result.draft, "Lyon.");
+        assert_eq!(result.answer, "Paris is the capital of France.");
+        assert_eq!(result.critiques.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn max_rounds_respected() {
+        let agent = make_agent(SequentialMockProvider::new(vec![
+            make_text_response("Draft"),
+            make_text_response("Too short."),
+            make_text_response("Revision one"),
+        ]))
+        .with_max_rounds(1);
+        let mut conv = Conversation::new();
+
+        let result = agent.run("Explain", &mut conv, &[], &[]).await.unwrap();
+
+        assert!(!result.approved);
+        assert_eq!(result.rounds, 1);
+        assert_eq!(result.answer, "Revision one");
+    }
+
+    #[tokio::test]
+    async fn critiques_recorded_as_reflections() {
+        let agent = make_agent(SequentialMockProvider::new(vec![
+            make_text_response("Draft"),
+            make_text_response("Missing an example."),
+            make_text_response("Draft with example"),
+            make_text_response("APPROVED"),
+        ]));
+        let mut conv = Conversation::new();
+
+        let result = agent.run("Explain", &mut conv, &[], &[]).await.unwrap();
+
+        let reflections: Vec<_> = result
+            .trace
+            .iter()
+            .filter(|t| t.kind == TraceKind::Reflection)
+            .collect();
+        assert_eq!(reflections.len(), 2);
+        assert!(reflections[0].content.contains("Missing an example"));
+    }
+
+    #[tokio::test]
+    async fn revision_sees_earlier_turns() {
+        let provider = Arc::new(SequentialMockProvider::new(vec![
+            make_text_response("About 2,000 km."),
+            make_text_response("Give the distance in miles, as asked earlier."),
+            make_text_response("About 1,240 miles."),
+            make_text_response("APPROVED"),
+        ]));
+        let agent = ReflexionAgent::new(
+            provider.clone(),
+            "mock-model",
+            0.7,
+            Arc::new(rustedclaw_tools::default_registry()),
+            Identity::default(),
+            Arc::new(EventBus::default()),
+        );
+        let mut conv = Conversation::new();
+        conv.push(Message::user("Answer in miles from now on."));
+        conv.push(Message::assistant("Sure."));
+
+        agent
+            .run("How far is Paris from Rome?", &mut conv, &[], &[])
+            .await
+            .unwrap();
+
+        let revision = &provider.requests()[2];
+        let contents: Vec<&str> = revision
+            .messages
+            .iter()
+            .skip(1)
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(
+            &contents[..4],
+            [
+                "Answer in miles from now on.",
+                "Sure.",
+                "How far is Paris from Rome?",
+                "About 2,000 km."
+            ]
+        );
+        assert!(contents[4].contains("Give the distance in miles"));
+    }
+
+    #[test]
+    fn approval_detection() {
+        assert!(is_approval("APPROVED"));
+        assert!(is_approval("  approved."));
+        assert!(!is_approval("Not approved: wrong units"));
+    }
+}
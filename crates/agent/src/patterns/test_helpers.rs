@@ -8,10 +8,13 @@ use std::sync::Mutex;
 /// A mock provider that returns a sequence of scripted responses.
 ///
 /// Each call to `complete` returns the next response in the queue.
-/// Panics if more calls are made than responses provided.
+/// Panics if more calls are made than responses provided, unless built
+/// with [`failing_after`](Self::failing_after).
 pub struct SequentialMockProvider {
     responses: Mutex<Vec<ProviderResponse>>,
     call_count: Mutex<usize>,
+    requests: Mutex<Vec<ProviderRequest>>,
+    fail_when_exhausted: bool,
 }
 
 impl SequentialMockProvider {
@@ -19,6 +22,17 @@ impl SequentialMockProvider {
         Self {
             responses: Mutex::new(responses),
             call_count: Mutex::new(0),
+            requests: Mutex::new(Vec::new()),
+            fail_when_exhausted: false,
+        }
+    }
+
+    /// Like [`new`](Self::new), but returns a network error once the
+    /// scripted responses run out (simulates a mid-run outage).
+    pub fn failing_after(responses: Vec<ProviderResponse>) -> Self {
+        Self {
+            fail_when_exhausted: true,
+            ..Self::new(responses)
         }
     }
 
@@ -43,6 +57,12 @@ impl SequentialMockProvider {
     pub fn call_count(&self) -> usize {
         *self.call_count.lock().unwrap()
     }
+
+    /// Requests received so far, in order.
+    #[allow(dead_code)]
+    pub fn requests(&self) -> Vec<ProviderRequest> {
+        self.requests.lock().unwrap().clone()
+    }
 }
 
 #[async_trait::async_trait]
@@ -51,11 +71,15 @@ impl Provider for SequentialMockProvider {
         "sequential_mock"
     }
 
-    async fn complete(&self, _request: ProviderRequest) -> Result<ProviderResponse, ProviderError> {
+    async fn complete(&self, request: ProviderRequest) -> Result<ProviderResponse, ProviderError> {
+        self.requests.lock().unwrap().push(request);
         let mut count = self.call_count.lock().unwrap();
         let responses = self.responses.lock().unwrap();
 
         if *count >= responses.len() {
+            if self.fail_when_exhausted {
+                return Err(ProviderError::Network("connection reset".into()));
+            }
             panic!(
                 "SequentialMockProvider: no more responses (call #{}, have {})",
                 *count,
@@ -0,0 +1,168 @@
+//! Run registry — tracks in-flight agent runs so they can be cancelled.
+//!
+//! Every registered run owns a [`CancellationToken`]. The agent checks the
+//! token between iterations and races it against provider calls, stream
+//! reads and tool execution, so cancelling a run stops it at the next
+//! await point instead of letting it run to `max_iterations`.
+//!
+//! Registration is scoped: [`RunRegistry::register`] returns a [`RunGuard`]
+//! that removes the run from the registry when dropped. A run ID can only
+//! be registered once at a time.
+
+use chrono::{DateTime, Utc};
+use rustedclaw_core::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+/// Public view of a running agent run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunInfo {
+    pub run_id: String,
+    pub conversation_id: String,
+    pub started_at: DateTime<Utc>,
+}
+
+struct RunEntry {
+    info: RunInfo,
+    token: CancellationToken,
+    /// Identifies the registration, so a guard only removes its own entry.
+    serial: u64,
+}
+
+/// Registry of in-flight runs keyed by run ID.
+#[derive(Default)]
+pub struct RunRegistry {
+    runs: Mutex<HashMap<String, RunEntry>>,
+    next_serial: AtomicU64,
+}
+
+impl RunRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a run. The run stays registered until the guard is dropped.
+    ///
+    /// Fails with [`Error::RunInProgress`] if a run with this ID is already
+    /// registered.
+    pub fn register(
+        self: &Arc<Self>,
+        run_id: impl Into<String>,
+        conversation_id: impl Into<String>,
+        token: CancellationToken,
+    ) -> Result<RunGuard, Error> {
+        let run_id = run_id.into();
+        let mut runs = self.lock();
+        if runs.contains_key(&run_id) {
+            return Err(Error::RunInProgress(run_id));
+        }
+        let serial = self.next_serial.fetch_add(1, Ordering::Relaxed);
+        let entry = RunEntry {
+            info: RunInfo {
+                run_id: run_id.clone(),
+                conversation_id: conversation_id.into(),
+                started_at: Utc::now(),
+            },
+            token,
+            serial,
+        };
+        runs.insert(run_id.clone(), entry);
+        Ok(RunGuard {
+            registry: self.clone(),
+            run_id,
+            serial,
+        })
+    }
+
+    /// Cancel a run. Returns `false` if no such run is in flight.
+    pub fn cancel(&self, run_id: &str) -> bool {
+        match self.lock().get(run_id) {
+            Some(entry) => {
+                info!(run_id = %run_id, "Cancelling run");
+                entry.token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether a run with this ID is currently registered.
+    pub fn is_running(&self, run_id: &str) -> bool {
+        self.lock().contains_key(run_id)
+    }
+
+    /// List all in-flight runs, oldest first.
+    pub fn list(&self) -> Vec<RunInfo> {
+        let mut runs: Vec<RunInfo> = self.lock().values().map(|e| e.info.clone()).collect();
+        runs.sort_by_key(|r| r.started_at);
+        runs
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, RunEntry>> {
+        self.runs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Keeps a run registered; deregisters it on drop.
+pub struct RunGuard {
+    registry: Arc<RunRegistry>,
+    run_id: String,
+    serial: u64,
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        let mut runs = self.registry.lock();
+        if runs
+            .get(&self.run_id)
+            .is_some_and(|e| e.serial == self.serial)
+        {
+            runs.remove(&self.run_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_cancel_and_deregister() {
+        let registry = Arc::new(RunRegistry::new());
+        let token = CancellationToken::new();
+
+        let guard = registry.register("run-1", "conv-1", token.clone()).unwrap();
+        assert!(registry.is_running("run-1"));
+        assert_eq!(registry.list()[0].conversation_id, "conv-1");
+
+        assert!(registry.cancel("run-1"));
+        assert!(token.is_cancelled());
+
+        drop(guard);
+        assert!(!registry.is_running("run-1"));
+        assert!(!registry.cancel("run-1"));
+    }
+
+    #[test]
+    fn duplicate_run_ids_are_rejected() {
+        let registry = Arc::new(RunRegistry::new());
+        let token = CancellationToken::new();
+
+        let guard = registry.register("run-1", "conv-1", token.clone()).unwrap();
+        let duplicate = registry.register("run-1", "conv-2", CancellationToken::new());
+        assert!(matches!(duplicate, Err(Error::RunInProgress(id)) if id == "run-1"));
+
+        // The failed registration leaves the original run in place.
+        assert!(registry.is_running("run-1"));
+        assert_eq!(registry.list()[0].conversation_id, "conv-1");
+        assert!(registry.cancel("run-1"));
+        assert!(token.is_cancelled());
+
+        drop(guard);
+        assert!(!registry.is_running("run-1"));
+    }
+}
@@ -0,0 +1,647 @@
+//! Agent runtime — one configurable entry point for every agent pattern.
+//!
+//! [`AgentRuntime`] holds the configuration shared by all entry points
+//! (provider, tools, identity, memory, telemetry, contracts, checkpoints,
+//! run registry, middleware) and runs a turn with a pluggable [`Strategy`]:
+//!
+//! - [`Strategy::Loop`] — plain tool-calling loop
+//! - [`Strategy::React`] — ReAct loop with the reasoning trace in context
+//! - [`Strategy::Rag`] — knowledge retrieval, then the ReAct loop grounded in it
+//! - [`Strategy::Reflexion`] — draft, self-critique and revise
+//! - [`Strategy::Coordinator`] — decompose across specialist workers
+//!
+//! The loop, ReAct and RAG strategies share the [`ReactAgent`] core, so
+//! memory recall, auto-save, telemetry, contracts, budgets, checkpoints and
+//! cancellation behave the same whichever route or command started the run.
+
+use rustedclaw_contracts::ContractEngine;
+use rustedclaw_core::event::EventBus;
+use rustedclaw_core::identity::Identity;
+use rustedclaw_core::memory::{MemoryBackend, MemoryScope};
+use rustedclaw_core::message::{Conversation, Role};
+use rustedclaw_core::provider::Provider;
+use rustedclaw_core::tool::ToolRegistry;
+use rustedclaw_telemetry::TelemetryEngine;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use crate::checkpoint::{CheckpointStore, RunCheckpoint};
+use crate::context::assembler::{AssemblyMetadata, KnowledgeChunk, TokenBudget};
+use crate::context::layer::ContextLayer;
+use crate::context::summarizer::ConversationSummarizer;
+use crate::context::token;
+use crate::context::tool_ranker::ToolRanker;
+use crate::context::working_memory::TraceEntry;
+use crate::extraction::FactExtractor;
+use crate::middleware::{BudgetMiddleware, ContractMiddleware, Middleware};
+use crate::patterns::coordinator::{CoordinatorAgent, WorkerConfig};
+use crate::patterns::rag::{RetrievalOptions, retrieve_chunks};
+use crate::patterns::react::{ReactAgent, ReactResult};
+use crate::patterns::reflect::ReflexionAgent;
+use crate::runs::RunRegistry;
+use crate::stream_event::AgentStreamEvent;
+
+/// How a runtime turns a user message into an answer.
+#[derive(Clone, Default)]
+pub enum Strategy {
+    /// Tool-calling loop without the reasoning trace in the prompt.
+    Loop,
+    /// ReAct loop: Thought → Action → Observation with a visible trace.
+    #[default]
+    React,
+    /// Retrieve knowledge chunks first, then run the ReAct loop over them.
+    Rag,
+    /// Draft with ReAct, then critique and revise.
+    Reflexion,
+    /// Decompose the task across the given workers and aggregate.
+    Coordinator(Vec<WorkerConfig>),
+}
+
+impl Strategy {
+    /// Parse an API pattern name ("react", "direct", "loop", "rag",
+    /// "reflect"). "direct" is an alias for "react", as it has always been
+    /// on the HTTP API; "loop" selects the plain loop without a trace.
+    pub fn from_pattern(pattern: &str) -> Option<Self> {
+        match pattern {
+            "react" | "direct" => Some(Self::React),
+            "loop" => Some(Self::Loop),
+            "rag" => Some(Self::Rag),
+            "reflect" | "reflexion" => Some(Self::Reflexion),
+            _ => None,
+        }
+    }
+
+    /// The pattern name this strategy is recorded under in checkpoints.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Loop => "loop",
+            Self::React => "react",
+            Self::Rag => "rag",
+            Self::Reflexion => "reflect",
+            Self::Coordinator(_) => "coordinator",
+        }
+    }
+}
+
+/// Result of a runtime turn, whatever the strategy.
+pub struct RuntimeResult {
+    /// The final answer text.
+    pub answer: String,
+    /// Run ID, for strategies that checkpoint (loop, ReAct, RAG).
+    pub run_id: Option<String>,
+    /// Reasoning trace of the run.
+    pub trace: Vec<TraceEntry>,
+    /// Iterations used.
+    pub iterations: usize,
+    /// Tool calls made.
+    pub tool_calls_made: usize,
+    /// Knowledge chunks injected into context (RAG).
+    pub knowledge_chunks: Vec<KnowledgeChunk>,
+    /// Context assembly metadata from the last LLM call.
+    pub context_metadata: Option<AssemblyMetadata>,
+}
+
+impl RuntimeResult {
+    fn from_react(result: ReactResult, knowledge_chunks: Vec<KnowledgeChunk>) -> Self {
+        Self {
+            answer: result.answer,
+            run_id: Some(result.run_id),
+            trace: result.trace,
+            iterations: result.iterations,
+            tool_calls_made: result.tool_calls_made,
+            knowledge_chunks,
+            context_metadata: result.last_context_metadata,
+        }
+    }
+}
+
+/// Configurable agent runtime shared by the gateway, CLI and channels.
+#[derive(Clone)]
+pub struct AgentRuntime {
+    provider: Arc<dyn Provider>,
+    model: String,
+    temperature: f32,
+    max_tokens: Option<u32>,
+    max_iterations: Option<u32>,
+    tools: Arc<ToolRegistry>,
+    identity: Identity,
+    budget: Option<TokenBudget>,
+    event_bus: Arc<EventBus>,
+    strategy: Strategy,
+    memory: Option<Arc<dyn MemoryBackend>>,
+    auto_save: bool,
+    extractor: Option<FactExtractor>,
+    memory_scope: MemoryScope,
+    recall_limit: Option<usize>,
+    contracts: Option<Arc<ContractEngine>>,
+    telemetry: Option<Arc<TelemetryEngine>>,
+    checkpoints: Option<Arc<dyn CheckpointStore>>,
+    runs: Option<Arc<RunRegistry>>,
+    run_id: Option<String>,
+    cancel: Option<CancellationToken>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    summarizer: Option<ConversationSummarizer>,
+    tool_ranker: Option<ToolRanker>,
+    layers: Vec<Arc<dyn ContextLayer>>,
+}
+
+impl AgentRuntime {
+    /// Create a runtime using the ReAct strategy.
+    pub fn new(
+        provider: Arc<dyn Provider>,
+        model: impl Into<String>,
+        temperature: f32,
+        tools: Arc<ToolRegistry>,
+        identity: Identity,
+        event_bus: Arc<EventBus>,
+    ) -> Self {
+        Self {
+            provider,
+            model: model.into(),
+            temperature,
+            max_tokens: None,
+            max_iterations: None,
+            tools,
+            identity,
+            budget: None,
+            event_bus,
+            strategy: Strategy::default(),
+            memory: None,
+            auto_save: false,
+            extractor: None,
+            memory_scope: MemoryScope::default(),
+            recall_limit: None,
+            contracts: None,
+            telemetry: None,
+            checkpoints: None,
+            runs: None,
+            run_id: None,
+            cancel: None,
+            middleware: Vec::new(),
+            summarizer: None,
+            tool_ranker: None,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Set the strategy.
+    pub fn with_strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Set the maximum number of loop iterations.
+    pub fn with_max_iterations(mut self, max: u32) -> Self {
+        self.max_iterations = Some(max);
+        self
+    }
+
+    /// Set the default max tokens per LLM response.
+    pub fn with_max_tokens(mut self, max: u32) -> Self {
+        self.max_tokens = Some(max);
+        self
+    }
+
+    /// Set the context token budget.
+    pub fn with_budget(mut self, budget: TokenBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Attach a memory backend for automatic recall and save.
+    pub fn with_memory(mut self, memory: Arc<dyn MemoryBackend>) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Enable or disable auto-save of conversation content to memory.
+    pub fn with_auto_save(mut self, enabled: bool) -> Self {
+        self.auto_save = enabled;
+        self
+    }
+
+    /// Auto-save extracted facts rather than the raw exchange.
+    pub fn with_fact_extractor(mut self, extractor: FactExtractor) -> Self {
+        self.extractor = Some(extractor);
+        self
+    }
+
+    /// Scope memory recall and auto-save (agent ID, namespace, ...).
+    pub fn with_memory_scope(mut self, scope: MemoryScope) -> Self {
+        self.memory_scope = scope;
+        self
+    }
+
+    /// Set the maximum number of memories to recall per turn.
+    pub fn with_recall_limit(mut self, limit: usize) -> Self {
+        self.recall_limit = Some(limit);
+        self
+    }
+
+    /// Enforce behavior contracts on tool calls.
+    pub fn with_contracts(mut self, engine: Arc<ContractEngine>) -> Self {
+        self.contracts = Some(engine);
+        self
+    }
+
+    /// Attach a telemetry engine for tracing, cost tracking and budgets.
+    pub fn with_telemetry(mut self, engine: Arc<TelemetryEngine>) -> Self {
+        self.telemetry = Some(engine);
+        self
+    }
+
+    /// Checkpoint runs so they can be resumed.
+    pub fn with_checkpoints(mut self, store: Arc<dyn CheckpointStore>) -> Self {
+        self.checkpoints = Some(store);
+        self
+    }
+
+    /// Register runs so they can be cancelled by ID.
+    pub fn with_runs(mut self, registry: Arc<RunRegistry>) -> Self {
+        self.runs = Some(registry);
+        self
+    }
+
+    /// Run ID to use for the next run (generated if unset).
+    pub fn with_run_id(mut self, run_id: impl Into<String>) -> Self {
+        self.run_id = Some(run_id.into());
+        self
+    }
+
+    /// Stop the run when `token` is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Append a middleware; custom middleware runs after the built-ins.
+    pub fn with_middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// Fold history that overflows the context budget into a rolling summary.
+    pub fn with_summarizer(mut self, summarizer: ConversationSummarizer) -> Self {
+        self.summarizer = Some(summarizer);
+        self
+    }
+
+    /// Rank tool schemas with pinned tools and/or embedding similarity.
+    pub fn with_tool_ranker(mut self, ranker: ToolRanker) -> Self {
+        self.tool_ranker = Some(ranker);
+        self
+    }
+
+    /// Add a custom context layer (date/time, user profile, ...).
+    pub fn with_context_layer(mut self, layer: Arc<dyn ContextLayer>) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// The configured strategy.
+    pub fn strategy(&self) -> &Strategy {
+        &self.strategy
+    }
+
+    /// Built-in middleware (budget, contracts) followed by custom middleware.
+    fn middleware_chain(&self) -> Vec<Arc<dyn Middleware>> {
+        let mut chain: Vec<Arc<dyn Middleware>> = Vec::new();
+        if let Some(telemetry) = &self.telemetry {
+            chain.push(Arc::new(
+                BudgetMiddleware::new(telemetry.clone(), self.event_bus.clone())
+                    .with_token_counter(token::resolve_counter(
+                        self.provider.as_ref(),
+                        &self.model,
+                    )),
+            ));
+        }
+        if let Some(engine) = &self.contracts {
+            chain.push(Arc::new(ContractMiddleware::new(
+                engine.clone(),
+                self.event_bus.clone(),
+            )));
+        }
+        chain.extend(self.middleware.iter().cloned());
+        chain
+    }
+
+    /// Build the shared loop core with this runtime's configuration.
+    pub fn core_agent(&self) -> ReactAgent {
+        let mut agent = ReactAgent::new(
+            self.provider.clone(),
+            &self.model,
+            self.temperature,
+            self.tools.clone(),
+            self.identity.clone(),
+            self.event_bus.clone(),
+        )
+        .with_budget_override(self.budget.clone())
+        .with_auto_save(self.auto_save)
+        .with_reasoning_trace(!matches!(self.strategy, Strategy::Loop))
+        .with_strategy_name(self.strategy.name());
+
+        if let Some(max) = self.max_iterations {
+            agent = agent.with_max_iterations(max);
+        }
+        if let Some(max) = self.max_tokens {
+            agent = agent.with_max_tokens(max);
+        }
+        if let Some(memory) = &self.memory {
+            agent = agent.with_memory(memory.clone());
+        }
+        if let Some(extractor) = &self.extractor {
+            agent = agent.with_fact_extractor(extractor.clone());
+        }
+        agent = agent.with_memory_scope(self.memory_scope.clone());
+        if let Some(limit) = self.recall_limit {
+            agent = agent.with_recall_limit(limit);
+        }
+        if let Some(telemetry) = &self.telemetry {
+            agent = agent.with_telemetry(telemetry.clone());
+        }
+        agent = agent.with_middleware_chain(&self.middleware_chain());
+        if let Some(store) = &self.checkpoints {
+            agent = agent.with_checkpoints(store.clone());
+        }
+        if let Some(runs) = &self.runs {
+            agent = agent.with_runs(runs.clone());
+        }
+        if let Some(run_id) = &self.run_id {
+            agent = agent.with_run_id(run_id.clone());
+        }
+        if let Some(token) = &self.cancel {
+            agent = agent.with_cancellation(token.clone());
+        }
+        if let Some(summarizer) = &self.summarizer {
+            agent = agent.with_summarizer(summarizer.clone());
+        }
+        if let Some(ranker) = &self.tool_ranker {
+            agent = agent.with_tool_ranker(ranker.clone());
+        }
+        for layer in &self.layers {
+            agent = agent.with_context_layer(layer.clone());
+        }
+        agent
+    }
+
+    /// Run one turn for `user_message`.
+    ///
+    /// `conversation` should already end with the user message; the answer
+    /// (and any tool traffic) is appended to it.
+    pub async fn run(
+        &self,
+        user_message: &str,
+        conversation: &mut Conversation,
+    ) -> Result<RuntimeResult, rustedclaw_core::Error> {
+        match &self.strategy {
+            Strategy::Loop | Strategy::React => {
+                let result = self
+                    .core_agent()
+                    .run(user_message, conversation, &[], &[])
+                    .await?;
+                Ok(RuntimeResult::from_react(result, Vec::new()))
+            }
+            Strategy::Rag => {
+                let chunks =
+                    retrieve_chunks(&self.tools, user_message, &RetrievalOptions::default())
+                        .await?;
+                let result = self
+                    .core_agent()
+                    .run(user_message, conversation, &[], &chunks)
+                    .await?;
+                Ok(RuntimeResult::from_react(result, chunks))
+            }
+            Strategy::Reflexion => {
+                let mut agent = ReflexionAgent::new(
+                    self.provider.clone(),
+                    &self.model,
+                    self.temperature,
+                    self.tools.clone(),
+                    self.identity.clone(),
+                    self.event_bus.clone(),
+                )
+                .with_budget_override(self.budget.clone());
+                for m in self.middleware_chain() {
+                    agent = agent.with_middleware(m);
+                }
+                if let Some(max) = self.max_iterations {
+                    agent = agent.with_max_iterations(max);
+                }
+                let result = agent.run(user_message, conversation, &[], &[]).await?;
+                Ok(RuntimeResult {
+                    answer: result.answer,
+                    run_id: None,
+                    trace: result.trace,
+                    iterations: result.iterations,
+                    tool_calls_made: result.tool_calls_made,
+                    knowledge_chunks: Vec::new(),
+                    context_metadata: result.last_context_metadata,
+                })
+            }
+            Strategy::Coordinator(workers) => {
+                let mut agent = CoordinatorAgent::new(
+                    self.provider.clone(),
+                    &self.model,
+                    self.temperature,
+                    self.tools.clone(),
+                    self.identity.clone(),
+                    self.event_bus.clone(),
+                )
+                .with_workers(workers.iter().cloned());
+                for m in self.middleware_chain() {
+                    agent = agent.with_middleware(m);
+                }
+                let result = agent.run(user_message, &[]).await?;
+                conversation.push(rustedclaw_core::message::Message::assistant(&result.answer));
+                Ok(RuntimeResult {
+                    answer: result.answer,
+                    run_id: None,
+                    trace: result.working_memory.trace,
+                    iterations: result.total_iterations,
+                    tool_calls_made: result.total_tool_calls,
+                    knowledge_chunks: Vec::new(),
+                    context_metadata: None,
+                })
+            }
+        }
+    }
+
+    /// Run a turn for the last user message in `conversation`.
+    ///
+    /// Convenience for channel-style callers that only keep a conversation.
+    pub async fn process(
+        &self,
+        conversation: &mut Conversation,
+    ) -> Result<String, rustedclaw_core::Error> {
+        let user_message = conversation
+            .messages
+            .iter()
+            .rev()
+            .find(|m| m.role == Role::User)
+            .map(|m| m.content.clone())
+            .unwrap_or_default();
+        Ok(self.run(&user_message, conversation).await?.answer)
+    }
+
+    /// Stream a turn as [`AgentStreamEvent`]s.
+    ///
+    /// Streaming always runs on the loop core; strategies other than
+    /// loop and ReAct stream as ReAct.
+    pub async fn run_stream(
+        &self,
+        user_message: &str,
+        conversation: &mut Conversation,
+    ) -> Result<mpsc::Receiver<AgentStreamEvent>, rustedclaw_core::Error> {
+        self.core_agent()
+            .run_stream(user_message, conversation, &[], &[])
+            .await
+    }
+
+    /// Continue a checkpointed run on the loop core.
+    ///
+    /// Build the runtime with the strategy recorded in the checkpoint
+    /// (see [`RunCheckpoint::strategy`]) so the run continues unchanged.
+    pub async fn resume(
+        &self,
+        checkpoint: RunCheckpoint,
+    ) -> Result<(Conversation, RuntimeResult), rustedclaw_core::Error> {
+        let chunks = checkpoint.knowledge_chunks.clone();
+        let (conversation, result) = self.core_agent().resume(checkpoint).await?;
+        Ok((conversation, RuntimeResult::from_react(result, chunks)))
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::ToolVerdict;
+    use crate::patterns::test_helpers::*;
+    use async_trait::async_trait;
+    use rustedclaw_core::message::Message;
+    use rustedclaw_core::tool::ToolCall;
+
+    fn runtime(provider: SequentialMockProvider) -> AgentRuntime {
+        AgentRuntime::new(
+            Arc::new(provider),
+            "mock-model",
+            0.0,
+            Arc::new(rustedclaw_tools::default_registry()),
+            Identity::default(),
+            Arc::new(EventBus::default()),
+        )
+    }
+
+    /// Blocks every tool call.
+    struct DenyAll;
+
+    #[async_trait]
+    impl Middleware for DenyAll {
+        fn name(&self) -> &str {
+            "deny-all"
+        }
+
+        async fn before_tool(&self, call: &ToolCall) -> ToolVerdict {
+            ToolVerdict::Block(format!("{} is disabled", call.name))
+        }
+    }
+
+    #[test]
+    fn strategy_from_pattern() {
+        assert!(matches!(
+            Strategy::from_pattern("direct"),
+            Some(Strategy::React)
+        ));
+        assert!(matches!(
+            Strategy::from_pattern("loop"),
+            Some(Strategy::Loop)
+        ));
+        assert!(matches!(Strategy::from_pattern("rag"), Some(Strategy::Rag)));
+        assert!(Strategy::from_pattern("bogus").is_none());
+        for strategy in [
+            Strategy::Loop,
+            Strategy::React,
+            Strategy::Rag,
+            Strategy::Reflexion,
+        ] {
+            assert_eq!(
+                Strategy::from_pattern(strategy.name()).map(|s| s.name()),
+                Some(strategy.name())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn loop_strategy_processes_conversation() {
+        let rt =
+            runtime(SequentialMockProvider::single_text("Hi there")).with_strategy(Strategy::Loop);
+
+        let mut conv = Conversation::new();
+        conv.push(Message::user("Hello"));
+        let answer = rt.process(&mut conv).await.unwrap();
+
+        assert_eq!(answer, "Hi there");
+        assert_eq!(conv.messages.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn middleware_blocks_tool_calls() {
+        let rt = runtime(SequentialMockProvider::tool_then_answer(
+            vec![make_tool_call(
+                "calculator",
+                serde_json::json!({"expression": "2 + 3"}),
+            )],
+            "Calculating",
+            "Could not calculate",
+        ))
+        .with_middleware(Arc::new(DenyAll));
+
+        let mut conv = Conversation::new();
+        conv.push(Message::user("What is 2+3?"));
+        let result = rt.run("What is 2+3?", &mut conv).await.unwrap();
+
+        assert_eq!(result.answer, "Could not calculate");
+        assert!(
+            conv.messages
+                .iter()
+                .any(|m| m.content == "calculator is disabled")
+        );
+    }
+
+    #[tokio::test]
+    async fn contracts_block_tool_calls() {
+        let contracts = rustedclaw_contracts::ContractSet::from_toml(
+            r#"
+            [[contracts]]
+            name = "no-calculator"
+            trigger = "tool:calculator"
+            action = "deny"
+            message = "Calculator disabled"
+            "#,
+        )
+        .unwrap();
+        let rt = runtime(SequentialMockProvider::tool_then_answer(
+            vec![make_tool_call(
+                "calculator",
+                serde_json::json!({"expression": "2 + 3"}),
+            )],
+            "Calculating",
+            "Blocked",
+        ))
+        .with_strategy(Strategy::Loop)
+        .with_contracts(Arc::new(ContractEngine::new(contracts).unwrap()));
+
+        let mut conv = Conversation::new();
+        conv.push(Message::user("What is 2+3?"));
+        let result = rt.run("What is 2+3?", &mut conv).await.unwrap();
+
+        assert_eq!(result.tool_calls_made, 1);
+        assert!(
+            conv.messages
+                .iter()
+                .any(|m| m.content.contains("Contract violation"))
+        );
+    }
+}
@@ -9,15 +9,23 @@ use serde::{Deserialize, Serialize};
 /// Events emitted by the agent during streaming execution.
 ///
 /// These follow the PRD-defined WebSocket protocol:
+/// - `started`     — run accepted; carries the run ID used for cancellation
 /// - `chunk`       — partial text token from the LLM
 /// - `tool_call`   — agent is invoking a tool
 /// - `tool_result` — tool execution completed
 /// - `thought`     — ReAct reasoning step
 /// - `done`        — stream is complete
 /// - `error`       — an error occurred
+/// - `cancelled`   — the run was cancelled before completing
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum AgentStreamEvent {
+    /// The run has started.
+    Started {
+        run_id: String,
+        conversation_id: String,
+    },
+
     /// Partial text token from the LLM.
     Chunk { content: String },
 
@@ -49,18 +57,23 @@ pub enum AgentStreamEvent {
 
     /// An error occurred mid-stream.
     Error { message: String },
+
+    /// The run was cancelled.
+    Cancelled { run_id: String },
 }
 
 impl AgentStreamEvent {
     /// SSE event name for this event type.
     pub fn event_type(&self) -> &'static str {
         match self {
+            Self::Started { .. } => "started",
             Self::Chunk { .. } => "chunk",
             Self::ToolCall { .. } => "tool_call",
             Self::ToolResult { .. } => "tool_result",
             Self::Thought { .. } => "thought",
             Self::Done { .. } => "done",
             Self::Error { .. } => "error",
+            Self::Cancelled { .. } => "cancelled",
         }
     }
 }
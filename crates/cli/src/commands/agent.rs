@@ -1,6 +1,9 @@
 //! `rustedclaw agent` — Interactive or single-message chat mode.
 
-use rustedclaw_agent::AgentLoop;
+use rustedclaw_agent::{
+    AgentRuntime, CheckpointStore, ConversationSummarizer, DateTimeLayer, DelegateTool,
+    FactExtractor, FileCheckpointStore, Strategy, TokenBudget, ToolRanker,
+};
 use rustedclaw_channels::CliChannel;
 use rustedclaw_config::AppConfig;
 use rustedclaw_core::channel::Channel;
@@ -13,122 +16,95 @@ pub async fn run(
     message: Option<String>,
     local: bool,
     model_override: Option<String>,
+    conversation_id: Option<String>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut config = AppConfig::load().map_err(|e| format!("Failed to load config: {e}"))?;
-
-    // If --local is set, override provider to "local" (no API key needed)
-    if local {
-        let model = model_override
-            .clone()
-            .unwrap_or_else(|| "tinyllama".to_string());
-
-        config.default_provider = "local".to_string();
-        config.default_model = model.clone();
-
-        // Ensure we have a provider entry for "local"
-        config.providers.insert(
-            "local".to_string(),
-            rustedclaw_config::ProviderConfig {
-                api_key: None,
-                api_url: Some("local://candle".to_string()),
-                default_model: Some(model),
-            },
-        );
+    let config = load_config(local, model_override)?;
+    let identity = load_identity(&config);
 
-        eprintln!();
-        eprintln!("  ⚡ Local Inference Mode (Candle)");
-        eprintln!("  Model:   {}", config.default_model);
-        eprintln!("  Engine:  Candle (Rust-native ML)");
-        eprintln!("  API Key: not required");
-        eprintln!("  Network: not required");
-        eprintln!();
-    } else if let Some(ref m) = model_override {
-        // --model without --local: just override the model name
-        config.default_model = m.clone();
-    }
-
-    // Check for API key — but skip for local provider
-    if !local && config.api_key.is_none() {
-        eprintln!();
-        eprintln!("  ERROR: No API key configured!");
-        eprintln!();
-        eprintln!("  Set one of these environment variables:");
-        eprintln!("    $env:OPENROUTER_API_KEY = 'sk-or-v1-...'   (recommended)");
-        eprintln!("    $env:OPENAI_API_KEY     = 'sk-...'         (for OpenAI direct)");
-        eprintln!("    $env:RUSTEDCLAW_API_KEY   = 'sk-...'         (generic)");
-        eprintln!();
-        eprintln!("  Or add it to your config file:");
-        eprintln!(
-            "    {}",
-            AppConfig::config_dir().join("config.toml").display()
-        );
-        eprintln!();
-        eprintln!("  Get an OpenRouter key at: https://openrouter.ai/keys");
-        eprintln!();
-        eprintln!("  TIP: For zero-API-key usage, try: rustedclaw agent --local --model tinyllama");
-        eprintln!();
-        return Err("No API key found. See above for setup instructions.".into());
-    }
-
-    // --- Context Loading ---
-    // Build context paths from config + current working directory
-    let cwd = std::env::current_dir().unwrap_or_default();
-    let project_dir = if config.identity.load_project_context {
-        let candidate = cwd.join(".rustedclaw");
-        if candidate.is_dir() {
-            Some(candidate)
-        } else {
-            None
-        }
-    } else {
-        None
-    };
-
-    let context_paths = ContextPaths {
-        global_dir: Some(AppConfig::workspace_dir()),
-        project_dir,
-        extra_files: config
-            .identity
-            .extra_context_files
-            .iter()
-            .map(std::path::PathBuf::from)
-            .collect(),
-        system_prompt_override: config.identity.system_prompt_override.clone(),
+    // Conversations are saved after every turn so they can be continued later
+    let store =
+        rustedclaw_memory::conversation_store_from_config(&config.memory.conversations).await?;
+    let mut conv = match conversation_id {
+        Some(id) => store
+            .load(&id)
+            .await?
+            .ok_or_else(|| format!("No stored conversation '{id}'"))?,
+        None => Conversation::new(),
     };
 
-    let identity = Identity::load(&context_paths);
-
     // Build provider from config
     let router = rustedclaw_providers::router::build_from_config(&config);
     let provider = router.default().ok_or("No default provider configured")?;
 
-    // Build tools
-    let tools = Arc::new(rustedclaw_tools::default_registry());
+    // Build tools, including `delegate` for scoped sub-agents
+    let memory = rustedclaw_memory::with_embeddings(
+        rustedclaw_memory::from_config(&config.memory).await?,
+        &config.memory,
+        router.get(&config.memory.embedding_provider),
+    );
+    let event_bus = Arc::new(EventBus::default());
+    let mut registry = rustedclaw_tools::registry_from_config(&config, memory.clone());
+    if config.tools.is_enabled("delegate") {
+        registry.register(Box::new(DelegateTool::new(
+            provider.clone(),
+            &config.default_model,
+            config.default_temperature,
+            Arc::new(rustedclaw_tools::registry_from_config(
+                &config,
+                memory.clone(),
+            )),
+            event_bus.clone(),
+        )));
+    }
+    let tools = Arc::new(registry);
 
     // Build agent with loaded context
-    let event_bus = Arc::new(EventBus::default());
     let context_files_count = identity.loaded_files.len();
     let context_tokens = identity.estimated_tokens();
     let agent_name = identity.name.clone();
-    let agent = AgentLoop::new(
-        provider,
+    let mut agent = AgentRuntime::new(
+        provider.clone(),
         &config.default_model,
         config.default_temperature,
         tools,
         identity,
         event_bus,
     )
-    .with_max_tokens(config.default_max_tokens);
+    .with_strategy(Strategy::Loop)
+    .with_max_iterations(25)
+    .with_max_tokens(config.default_max_tokens)
+    .with_memory(memory)
+    .with_auto_save(config.memory.auto_save)
+    .with_budget(TokenBudget::from_config(
+        &provider.capabilities(&config.default_model),
+        Some(config.default_max_tokens),
+        &config.context,
+    ))
+    .with_tool_ranker(ToolRanker::from_config(&config.context, provider.clone()));
+    if config.context.current_time {
+        agent = agent.with_context_layer(Arc::new(DateTimeLayer::new()));
+    }
+    let extractor =
+        FactExtractor::from_config(&config.memory, provider.clone(), &config.default_model);
+    if let Some(extractor) = &extractor {
+        agent = agent.with_fact_extractor(extractor.clone());
+    }
+    if config.context.summarize_history {
+        agent = agent.with_summarizer(
+            ConversationSummarizer::new(provider, &config.default_model)
+                .with_max_tokens(config.context.summary_max_tokens),
+        );
+    }
 
     if let Some(msg) = message {
         // Single message mode
-        let mut conv = Conversation::new();
         conv.push(Message::user(&msg));
 
         eprint!("  Thinking...");
         let response = agent.process(&mut conv).await?;
         eprint!("\r              \r");
         println!("{response}");
+        store.save(&conv).await?;
     } else {
         // Interactive mode
         println!();
@@ -142,15 +118,20 @@ pub async fn run(
             println!("  Engine:    Candle (Rust-native, in-process)");
             println!("  Network:   OFFLINE — zero API calls");
         }
-        println!("  Tools:     shell, file_read, file_write");
+        println!("  Tools:     shell, file_read, file_write, delegate");
         println!(
             "  Context:   {} files loaded (~{} tokens)",
             context_files_count, context_tokens
         );
         println!("  Agent:     {}", agent_name);
+        println!(
+            "  Session:   {} ({} messages, continue with --conversation)",
+            conv.id,
+            conv.messages.len()
+        );
         println!();
         println!("  Type your message and press Enter.");
-        println!("  Type 'exit' or Ctrl+C to quit.");
+        println!("  Type 'exit' or Ctrl+C to quit. Ctrl+C while thinking cancels the turn.");
         println!();
 
         let channel = CliChannel::new();
@@ -158,21 +139,40 @@ pub async fn run(
             .start()
             .await
             .map_err(|e| format!("Channel error: {e}"))?;
-        let mut conv = Conversation::new();
 
         print!("  You > ");
         use std::io::Write;
         std::io::stdout().flush()?;
 
-        while let Some(result) = rx.recv().await {
+        loop {
+            let result = tokio::select! {
+                r = rx.recv() => match r {
+                    Some(r) => r,
+                    None => break,
+                },
+                _ = tokio::signal::ctrl_c() => break,
+            };
             match result {
                 Ok(chan_msg) => {
-                    conv.push(Message::user(&chan_msg.content));
+                    // Snapshot so a cancelled turn leaves no half-finished history.
+                    let snapshot = conv.clone();
+                    conv.push(chan_msg.to_message());
 
                     eprint!("  ...");
 
-                    match agent.process(&mut conv).await {
-                        Ok(response) => {
+                    let outcome = tokio::select! {
+                        r = agent.process(&mut conv) => Some(r),
+                        _ = tokio::signal::ctrl_c() => None,
+                    };
+
+                    match outcome {
+                        None => {
+                            conv = snapshot;
+                            eprint!("\r     \r");
+                            eprintln!("  [Cancelled]");
+                            println!();
+                        }
+                        Some(Ok(response)) => {
                             eprint!("\r     \r");
                             println!();
                             // Print with a visible assistant prefix
@@ -180,8 +180,11 @@ pub async fn run(
                                 println!("  Assistant > {line}");
                             }
                             println!();
+                            if let Err(e) = store.save(&conv).await {
+                                eprintln!("  [Warning] Conversation not saved: {e}");
+                            }
                         }
-                        Err(e) => {
+                        Some(Err(e)) => {
                             eprint!("\r     \r");
                             eprintln!("  [Error] {e}");
                             println!();
@@ -203,5 +206,172 @@ pub async fn run(
         println!();
     }
 
+    // Facts are extracted in the background; let the last turn's finish.
+    if let Some(extractor) = extractor {
+        extractor.wait().await;
+    }
+    Ok(())
+}
+
+/// `rustedclaw agent --resume <id>` — continue an interrupted ReAct run.
+///
+/// Loads the run's checkpoint from `~/.rustedclaw/runs` (shared with the
+/// gateway) and runs it to completion.
+pub async fn resume(
+    run_id: &str,
+    local: bool,
+    model_override: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = load_config(local, model_override)?;
+    let identity = load_identity(&config);
+
+    let store: Arc<dyn CheckpointStore> =
+        Arc::new(FileCheckpointStore::new(FileCheckpointStore::default_path()));
+    let checkpoint = store
+        .load(run_id)
+        .await?
+        .ok_or_else(|| format!("No checkpoint found for run '{run_id}'"))?;
+
+    let router = rustedclaw_providers::router::build_from_config(&config);
+    let provider = router.default().ok_or("No default provider configured")?;
+
+    let budget = TokenBudget::from_config(
+        &provider.capabilities(&config.default_model),
+        Some(config.default_max_tokens),
+        &config.context,
+    );
+    let memory = rustedclaw_memory::with_embeddings(
+        rustedclaw_memory::from_config(&config.memory).await?,
+        &config.memory,
+        router.get(&config.memory.embedding_provider),
+    );
+    let mut agent = AgentRuntime::new(
+        provider.clone(),
+        &config.default_model,
+        config.default_temperature,
+        Arc::new(rustedclaw_tools::registry_from_config(
+            &config,
+            memory.clone(),
+        )),
+        identity,
+        Arc::new(EventBus::default()),
+    )
+    .with_max_tokens(config.default_max_tokens)
+    .with_memory(memory)
+    .with_auto_save(config.memory.auto_save)
+    .with_budget(budget)
+    .with_checkpoints(store)
+    .with_strategy(Strategy::from_pattern(&checkpoint.strategy).unwrap_or_default());
+    let extractor = FactExtractor::from_config(&config.memory, provider, &config.default_model);
+    if let Some(extractor) = &extractor {
+        agent = agent.with_fact_extractor(extractor.clone());
+    }
+
+    eprintln!(
+        "  Resuming run {run_id} (iteration {}, {} pending tool calls)...",
+        checkpoint.iterations(),
+        checkpoint.pending_tool_calls.len()
+    );
+    let (_, result) = agent.resume(checkpoint).await?;
+    println!("{}", result.answer);
+
+    if let Some(extractor) = extractor {
+        extractor.wait().await;
+    }
     Ok(())
 }
+
+/// Load config and apply `--local` / `--model` overrides.
+fn load_config(
+    local: bool,
+    model_override: Option<String>,
+) -> Result<AppConfig, Box<dyn std::error::Error>> {
+    let mut config = AppConfig::load().map_err(|e| format!("Failed to load config: {e}"))?;
+
+    // If --local is set, override provider to "local" (no API key needed)
+    if local {
+        let model = model_override
+            .clone()
+            .unwrap_or_else(|| "tinyllama".to_string());
+
+        config.default_provider = "local".to_string();
+        config.default_model = model.clone();
+
+        // Ensure we have a provider entry for "local"
+        config.providers.insert(
+            "local".to_string(),
+            rustedclaw_config::ProviderConfig {
+                api_key: None,
+                api_url: Some("local://candle".to_string()),
+                default_model: Some(model),
+            },
+        );
+
+        eprintln!();
+        eprintln!("  ⚡ Local Inference Mode (Candle)");
+        eprintln!("  Model:   {}", config.default_model);
+        eprintln!("  Engine:  Candle (Rust-native ML)");
+        eprintln!("  API Key: not required");
+        eprintln!("  Network: not required");
+        eprintln!();
+    } else if let Some(ref m) = model_override {
+        // --model without --local: just override the model name
+        config.default_model = m.clone();
+    }
+
+    // Check for API key — but skip for local provider
+    if !local && config.api_key.is_none() {
+        eprintln!();
+        eprintln!("  ERROR: No API key configured!");
+        eprintln!();
+        eprintln!("  Set one of these environment variables:");
+        eprintln!("    $env:OPENROUTER_API_KEY = 'sk-or-v1-...'   (recommended)");
+        eprintln!("    $env:OPENAI_API_KEY     = 'sk-...'         (for OpenAI direct)");
+        eprintln!("    $env:RUSTEDCLAW_API_KEY   = 'sk-...'         (generic)");
+        eprintln!();
+        eprintln!("  Or add it to your config file:");
+        eprintln!(
+            "    {}",
+            AppConfig::config_dir().join("config.toml").display()
+        );
+        eprintln!();
+        eprintln!("  Get an OpenRouter key at: https://openrouter.ai/keys");
+        eprintln!();
+        eprintln!("  TIP: For zero-API-key usage, try: rustedclaw agent --local --model tinyllama");
+        eprintln!();
+        return Err("No API key found. See above for setup instructions.".into());
+    }
+
+    Ok(config)
+}
+
+/// Load the agent identity from the workspace and project context files.
+fn load_identity(config: &AppConfig) -> Identity {
+    // --- Context Loading ---
+    // Build context paths from config + current working directory
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let project_dir = if config.identity.load_project_context {
+        let candidate = cwd.join(".rustedclaw");
+        if candidate.is_dir() {
+            Some(candidate)
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    let context_paths = ContextPaths {
+        global_dir: Some(AppConfig::workspace_dir()),
+        project_dir,
+        extra_files: config
+            .identity
+            .extra_context_files
+            .iter()
+            .map(std::path::PathBuf::from)
+            .collect(),
+        system_prompt_override: config.identity.system_prompt_override.clone(),
+    };
+
+    Identity::load(&context_paths)
+}
@@ -20,6 +20,9 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
         }
     );
     println!("   Routines: {}", config.routines.len());
+    if !config.tools.disabled.is_empty() {
+        println!("   Disabled tools: {}", config.tools.disabled.join(", "));
+    }
 
     // Start workflow engine
     let workflow = std::sync::Arc::new(rustedclaw_workflow::WorkflowEngine::new(
@@ -65,7 +68,8 @@ pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
         }
     });
 
-    // Start gateway (this blocks)
+    // Start gateway (this blocks); its tool registry is built from
+    // `[tools]` and `[autonomy]`
     rustedclaw_gateway::start(config).await?;
 
     Ok(())
@@ -0,0 +1,69 @@
+//! `rustedclaw ingest` — Chunk documents into memory for retrieval.
+
+use rustedclaw_config::AppConfig;
+use rustedclaw_memory::ingest::{DirectoryWatcher, DocumentIngestor, SyncReport};
+use std::path::Path;
+use std::time::Duration;
+
+pub async fn run(
+    path: &str,
+    watch: bool,
+    interval: Option<u64>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load().map_err(|e| format!("Failed to load config: {e}"))?;
+    if !Path::new(path).exists() {
+        return Err(format!("{path} does not exist").into());
+    }
+
+    let backend = super::memory::open_with_embeddings(&config).await?;
+    let ingestor = DocumentIngestor::from_config(backend.clone(), &config.memory.ingest);
+    let mut watcher = DirectoryWatcher::new(path);
+
+    let report = ingestor.sync(&mut watcher).await?;
+    if report.ingested.is_empty() && report.failed.is_empty() {
+        println!("ℹ️  No Markdown, HTML, text or PDF files found in {path}.");
+    }
+    print_report(&report);
+    let chunks: usize = report.ingested.iter().map(|r| r.chunk_ids.len()).sum();
+    println!(
+        "📚 Ingested {} documents ({chunks} chunks) into the {} backend.",
+        report.ingested.len(),
+        backend.name()
+    );
+
+    if !watch {
+        return Ok(());
+    }
+    let interval = interval.unwrap_or(config.memory.ingest.watch_interval_secs);
+    let interval = Duration::from_secs(interval.max(1));
+    println!("👀 Watching {path} for changes (Ctrl-C to stop)...");
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            _ = tokio::time::sleep(interval) => {}
+        }
+        // A failed pass (an unreadable directory, the embedder being down)
+        // is retried on the next tick rather than ending the watch.
+        match ingestor.sync(&mut watcher).await {
+            Ok(report) => print_report(&report),
+            Err(e) => eprintln!("⚠️  Sync failed, retrying in {}s: {e}", interval.as_secs()),
+        }
+    }
+    Ok(())
+}
+
+fn print_report(report: &SyncReport) {
+    for ingested in &report.ingested {
+        println!(
+            "📄 {} — {} chunks",
+            ingested.document_id,
+            ingested.chunk_ids.len()
+        );
+    }
+    for id in &report.removed {
+        println!("🗑️  {id} — removed");
+    }
+    for (path, error) in &report.failed {
+        println!("⚠️  {} — {error}", path.display());
+    }
+}
@@ -1,8 +1,15 @@
 //! `rustedclaw memory` — Memory management commands.
 
-use rustedclaw_config::AppConfig;
-use rustedclaw_core::memory::{MemoryBackend, MemoryQuery, SearchMode};
-use rustedclaw_memory::InMemoryBackend;
+use rustedclaw_config::{AppConfig, MemoryConfig};
+use rustedclaw_core::memory::{
+    EXPORT_PAGE_SIZE, MemoryBackend, MemoryFilter, MemoryQuery, MemoryScope, SearchMode,
+};
+use rustedclaw_memory::{EmbeddingMemory, Migration};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Backends that keep memories beyond the process, and so can be migrated.
+const PERSISTENT_BACKENDS: [&str; 5] = ["sqlite", "file", "jsonl", "postgres", "postgresql"];
 
 pub async fn stats() -> Result<(), Box<dyn std::error::Error>> {
     let config = AppConfig::load().map_err(|e| format!("Failed to load config: {e}"))?;
@@ -11,15 +18,27 @@ pub async fn stats() -> Result<(), Box<dyn std::error::Error>> {
     println!("====================");
     println!("  Backend:    {}", config.memory.backend);
     println!("  Auto-save:  {}", config.memory.auto_save);
-    println!("  Embeddings: {}", config.memory.embedding_provider);
+    println!(
+        "  Embeddings: {} ({})",
+        config.memory.embedding_provider, config.memory.embedding_model
+    );
     println!(
         "  Weights:    vector={:.1}, keyword={:.1}",
         config.memory.vector_weight, config.memory.keyword_weight
     );
+    let decay = &config.memory.decay;
+    println!(
+        "  Decay:      half-life {}h, recency={:.1}, frequency={:.1}, ttl={}",
+        decay.half_life_hours,
+        decay.recency_weight,
+        decay.frequency_weight,
+        decay
+            .ttl_hours
+            .map_or_else(|| "none".to_string(), |h| format!("{h}h"))
+    );
 
-    // Show database file info if SQLite
-    if config.memory.backend == "sqlite" {
-        let db_path = AppConfig::config_dir().join("memory.sqlite");
+    // Show the storage file for file-based backends
+    if let Some(db_path) = storage_path(&config) {
         if db_path.exists() {
             let meta = std::fs::metadata(&db_path)?;
             let size_kb = meta.len() as f64 / 1024.0;
@@ -29,6 +48,9 @@ pub async fn stats() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    let backend = rustedclaw_memory::from_config(&config.memory).await?;
+    println!("  Entries:    {}", backend.count().await?);
+
     // Show workspace memory files
     let workspace = AppConfig::workspace_dir();
     if workspace.exists() {
@@ -43,24 +65,26 @@ pub async fn stats() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 pub async fn search(query: &str, limit: usize) -> Result<(), Box<dyn std::error::Error>> {
-    let _config = AppConfig::load().map_err(|e| format!("Failed to load config: {e}"))?;
+    let config = AppConfig::load().map_err(|e| format!("Failed to load config: {e}"))?;
 
     println!("🔍 Searching memories for: \"{query}\"");
     println!();
 
-    // For now, use in-memory backend (upgrade to SQLite when running with daemon)
-    let backend = InMemoryBackend::new();
+    // Embed the query too, so hybrid search has a vector side
+    let backend = open_with_embeddings(&config).await?;
     let mq = MemoryQuery {
         text: query.to_string(),
         limit,
         min_score: 0.0,
         tags: vec![],
         mode: SearchMode::Hybrid,
+        embedding: None,
+        scope: MemoryScope::default(),
     };
 
     let results = backend.search(mq).await?;
     if results.is_empty() {
-        println!("   No memories found. (Memory backend may need a running daemon.)");
+        println!("   No memories found.");
     } else {
         for (i, entry) in results.iter().enumerate() {
             println!(
@@ -77,35 +101,101 @@ pub async fn search(query: &str, limit: usize) -> Result<(), Box<dyn std::error:
     Ok(())
 }
 
-pub async fn export(output: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let _config = AppConfig::load().map_err(|e| format!("Failed to load config: {e}"))?;
+/// Layout of `rustedclaw memory export`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// Workspace memory files as a JSON array
+    #[default]
+    Json,
+    /// Stored entries and workspace files: `{"entries", "workspace_files"}`
+    Full,
+    /// One stored entry per line, as read by `memory import`
+    Jsonl,
+}
 
-    // Export workspace memory files as JSON
-    let workspace = AppConfig::workspace_dir();
-    let memory_dir = workspace.join("memories");
-    let mut memories = Vec::new();
+pub async fn export(output: &str, format: ExportFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load().map_err(|e| format!("Failed to load config: {e}"))?;
 
-    if memory_dir.exists() {
-        for entry in std::fs::read_dir(&memory_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path
-                .extension()
-                .is_some_and(|e| e == "md" || e == "txt" || e == "json")
-            {
-                let content = std::fs::read_to_string(&path)?;
-                memories.push(serde_json::json!({
-                    "file": path.file_name().unwrap().to_string_lossy(),
-                    "content": content,
-                    "size_bytes": content.len(),
-                }));
-            }
+    let files = workspace_files()?;
+    if format == ExportFormat::Json {
+        std::fs::write(output, serde_json::to_string_pretty(&files)?)?;
+        println!("📤 Exported {} memories to {output}", files.len());
+        return Ok(());
+    }
+
+    let backend = rustedclaw_memory::from_config(&config.memory).await?;
+    let mut out = tokio::io::BufWriter::new(tokio::fs::File::create(output).await?);
+    if format == ExportFormat::Jsonl {
+        let exported = backend.export(&MemoryFilter::default(), &mut out).await?;
+        println!("📤 Exported {exported} memories to {output}");
+        return Ok(());
+    }
+
+    // Entries are written a page at a time, however many there are.
+    use tokio::io::AsyncWriteExt;
+    out.write_all(b"{\"entries\": [").await?;
+    let all = MemoryFilter::default();
+    let mut exported = 0;
+    loop {
+        let page = backend.list(&all, exported, EXPORT_PAGE_SIZE).await?;
+        for entry in &page {
+            out.write_all(if exported == 0 { b"\n" } else { b",\n" })
+                .await?;
+            out.write_all(&serde_json::to_vec(entry)?).await?;
+            exported += 1;
+        }
+        if page.len() < EXPORT_PAGE_SIZE {
+            break;
         }
     }
+    out.write_all(b"\n], \"workspace_files\": ").await?;
+    out.write_all(&serde_json::to_vec_pretty(&files)?).await?;
+    out.write_all(b"}\n").await?;
+    out.flush().await?;
+    println!(
+        "📤 Exported {exported} memories and {} workspace files to {output}",
+        files.len()
+    );
 
-    let json = serde_json::to_string_pretty(&memories)?;
-    std::fs::write(output, &json)?;
-    println!("📤 Exported {} memories to {output}", memories.len());
+    Ok(())
+}
+
+/// Memory files in the workspace (`.md`, `.txt` and `.json`).
+fn workspace_files() -> std::io::Result<Vec<serde_json::Value>> {
+    let memory_dir = AppConfig::workspace_dir().join("memories");
+    let mut files = Vec::new();
+    if !memory_dir.exists() {
+        return Ok(files);
+    }
+    for entry in std::fs::read_dir(&memory_dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|e| e == "md" || e == "txt" || e == "json")
+        {
+            let content = std::fs::read_to_string(&path)?;
+            files.push(serde_json::json!({
+                "file": path.file_name().unwrap().to_string_lossy(),
+                "content": content,
+                "size_bytes": content.len(),
+            }));
+        }
+    }
+    Ok(files)
+}
+
+pub async fn import(input: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load().map_err(|e| format!("Failed to load config: {e}"))?;
+
+    let file = tokio::fs::File::open(input)
+        .await
+        .map_err(|e| format!("Cannot read {input}: {e}"))?;
+    let backend = open_with_embeddings(&config).await?;
+    let imported = backend.import(&mut tokio::io::BufReader::new(file)).await?;
+    println!(
+        "📥 Imported {imported} memories into the {} backend.",
+        backend.name()
+    );
 
     Ok(())
 }
@@ -120,14 +210,9 @@ pub async fn clear(confirm: bool) -> Result<(), Box<dyn std::error::Error>> {
 
     let config = AppConfig::load().map_err(|e| format!("Failed to load config: {e}"))?;
 
-    // Clear SQLite database
-    if config.memory.backend == "sqlite" {
-        let db_path = AppConfig::config_dir().join("memory.sqlite");
-        if db_path.exists() {
-            std::fs::remove_file(&db_path)?;
-            println!("🗑️  Deleted memory database.");
-        }
-    }
+    let backend = rustedclaw_memory::from_config(&config.memory).await?;
+    backend.clear().await?;
+    println!("🗑️  Cleared the {} memory backend.", backend.name());
 
     // Clear workspace memory files
     let memory_dir = AppConfig::workspace_dir().join("memories");
@@ -142,10 +227,222 @@ pub async fn clear(confirm: bool) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+pub async fn reembed(all: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load().map_err(|e| format!("Failed to load config: {e}"))?;
+
+    let name = &config.memory.embedding_provider;
+    if name == "none" {
+        return Err("Embeddings are disabled; set [memory] embedding_provider first".into());
+    }
+    let router = rustedclaw_providers::router::build_from_config(&config);
+    let provider = router
+        .get(name)
+        .ok_or_else(|| format!("Embedding provider '{name}' is not configured"))?;
+
+    let backend = rustedclaw_memory::from_config(&config.memory).await?;
+    let pipeline = EmbeddingMemory::from_config(backend, provider, &config.memory);
+    println!(
+        "🧬 Re-embedding memories with {name}/{}...",
+        pipeline.model()
+    );
+
+    let report = pipeline.reembed(all).await?;
+    println!(
+        "✅ Re-embedded {} of {} memories.",
+        report.reembedded, report.scanned
+    );
+
+    Ok(())
+}
+
+pub async fn compact() -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load().map_err(|e| format!("Failed to load config: {e}"))?;
+
+    let backend = rustedclaw_memory::from_config(&config.memory).await?;
+    let forgotten = backend.compact().await?;
+    println!(
+        "🧹 Forgot {forgotten} expired or stale memories; {} remain.",
+        backend.count().await?
+    );
+
+    Ok(())
+}
+
+/// Arguments of `rustedclaw memory migrate`.
+pub struct MigrateArgs<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    pub from_path: Option<&'a str>,
+    pub to_path: Option<&'a str>,
+    pub batch_size: usize,
+    pub restart: bool,
+}
+
+pub async fn migrate(args: MigrateArgs<'_>) -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load().map_err(|e| format!("Failed to load config: {e}"))?;
+
+    for kind in [args.from, args.to] {
+        if !PERSISTENT_BACKENDS.contains(&kind) {
+            return Err(format!(
+                "Cannot migrate with the '{kind}' backend (expected sqlite, file or postgres)"
+            )
+            .into());
+        }
+    }
+    if args.from == args.to && args.from_path == args.to_path {
+        return Err(
+            "The source and target are the same backend; pass --from-path or --to-path".into(),
+        );
+    }
+
+    let from = backend_config(&config, args.from, args.from_path);
+    let to = backend_config(&config, args.to, args.to_path);
+    let checkpoint = migration_checkpoint(&from, &to);
+    let source = rustedclaw_memory::from_config(&from).await?;
+    let target = rustedclaw_memory::from_config(&to).await?;
+    if args.restart && checkpoint.exists() {
+        std::fs::remove_file(&checkpoint)?;
+    }
+
+    println!(
+        "🚚 Migrating memories from {} to {}...",
+        source.name(),
+        target.name()
+    );
+    let result = Migration::new(source, target.clone())
+        .with_batch_size(args.batch_size)
+        .with_checkpoint(&checkpoint)
+        .run(|progress| {
+            print!(
+                "\r   Copied {}/{} memories",
+                progress.copied, progress.total
+            );
+            let _ = std::io::Write::flush(&mut std::io::stdout());
+        })
+        .await;
+    println!();
+
+    let report = match result {
+        Ok(report) => report,
+        Err(e) => {
+            if checkpoint.exists() {
+                println!("💾 Progress saved; rerun the same command to resume.");
+            }
+            return Err(e.into());
+        }
+    };
+    if report.resumed_from > 0 {
+        println!(
+            "↪️  Resumed after {} memories copied earlier.",
+            report.resumed_from
+        );
+    }
+    println!(
+        "✅ Migrated {} memories; all {} verified in the {} backend.",
+        report.copied,
+        report.verified,
+        target.name()
+    );
+    println!(
+        "   Set [memory] backend = \"{}\" to start using it.",
+        args.to
+    );
+
+    Ok(())
+}
+
+/// Memory config for the `kind` backend at `location`, falling back to the
+/// configured location when `kind` is the configured backend and to its
+/// default otherwise.
+fn backend_config(config: &AppConfig, kind: &str, location: Option<&str>) -> MemoryConfig {
+    let mut memory = config.memory.clone();
+    if memory.backend != kind {
+        memory.path = None;
+    }
+    if let Some(location) = location {
+        if kind.starts_with("postgres") {
+            memory.url = Some(location.to_string());
+        } else {
+            memory.path = Some(location.to_string());
+        }
+    }
+    memory.backend = kind.to_string();
+    memory
+}
+
+/// Checkpoint file of a migration between two locations. Its name hashes
+/// both backends and locations, so interrupted migrations between other
+/// stores never resume from it and database URLs stay out of the name.
+fn migration_checkpoint(from: &MemoryConfig, to: &MemoryConfig) -> PathBuf {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    for memory in [from, to] {
+        for part in [
+            Some(&memory.backend),
+            memory.path.as_ref(),
+            memory.url.as_ref(),
+        ] {
+            hasher.update(part.map_or("", String::as_str));
+            hasher.update([0]);
+        }
+    }
+    let key = hex::encode(&hasher.finalize()[..8]);
+    AppConfig::config_dir()
+        .join("migrations")
+        .join(format!("{}-to-{}-{key}.json", from.backend, to.backend))
+}
+
+/// The configured backend wrapped in the embedding pipeline, when enabled.
+pub(crate) async fn open_with_embeddings(
+    config: &AppConfig,
+) -> Result<Arc<dyn MemoryBackend>, Box<dyn std::error::Error>> {
+    let backend = rustedclaw_memory::from_config(&config.memory).await?;
+    let router = rustedclaw_providers::router::build_from_config(config);
+    Ok(rustedclaw_memory::with_embeddings(
+        backend,
+        &config.memory,
+        router.get(&config.memory.embedding_provider),
+    ))
+}
+
+/// Storage file of the configured backend, if it keeps one locally.
+fn storage_path(config: &AppConfig) -> Option<PathBuf> {
+    let configured = config.memory.path.as_ref().map(PathBuf::from);
+    match config.memory.backend.as_str() {
+        "sqlite" => Some(configured.unwrap_or_else(rustedclaw_memory::default_sqlite_path)),
+        "file" | "jsonl" => {
+            Some(configured.unwrap_or_else(rustedclaw_memory::FileBackend::default_path))
+        }
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn memory_stats_runs_without_config() {
         // Just verifying the module compiles
     }
+
+    #[test]
+    fn migration_checkpoints_are_per_location() {
+        let config = AppConfig::default();
+        let from = backend_config(&config, "file", Some("a.jsonl"));
+        let to = backend_config(&config, "sqlite", Some("a.sqlite"));
+        let other = backend_config(&config, "sqlite", Some("b.sqlite"));
+
+        let checkpoint = migration_checkpoint(&from, &to);
+        assert_eq!(checkpoint, migration_checkpoint(&from, &to));
+        assert_ne!(checkpoint, migration_checkpoint(&from, &other));
+        assert_ne!(checkpoint, migration_checkpoint(&to, &from));
+        assert!(
+            checkpoint
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with("file-to-sqlite-")
+        );
+    }
 }
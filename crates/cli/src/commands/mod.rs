@@ -5,6 +5,7 @@ pub mod daemon;
 pub mod doctor;
 pub mod estop;
 pub mod gateway;
+pub mod ingest;
 pub mod memory;
 pub mod migrate;
 pub mod onboard;
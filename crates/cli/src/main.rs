@@ -56,6 +56,14 @@ enum Commands {
         /// Model to use (e.g. "tinyllama", "smollm:135m", or a path to a .gguf file)
         #[arg(long)]
         model: Option<String>,
+
+        /// Resume an interrupted run from its checkpoint
+        #[arg(long, value_name = "RUN_ID", conflicts_with = "message")]
+        resume: Option<String>,
+
+        /// Continue a stored conversation instead of starting a new one
+        #[arg(short, long, value_name = "ID", conflicts_with = "resume")]
+        conversation: Option<String>,
     },
 
     /// Start the HTTP gateway server
@@ -127,6 +135,20 @@ enum Commands {
         action: MemoryAction,
     },
 
+    /// Chunk Markdown, HTML, text and PDF documents into memory
+    Ingest {
+        /// File or directory to ingest
+        path: String,
+
+        /// Keep running and re-ingest files as they change
+        #[arg(long)]
+        watch: bool,
+
+        /// Seconds between scans while watching (default: memory.ingest.watch_interval_secs)
+        #[arg(long)]
+        interval: Option<u64>,
+    },
+
     /// Configuration management
     Config {
         #[command(subcommand)]
@@ -190,12 +212,50 @@ enum MemoryAction {
         /// Output file path
         #[arg(short, long, default_value = "memories.json")]
         output: String,
+        /// Output layout
+        #[arg(long, value_enum, default_value_t)]
+        format: commands::memory::ExportFormat,
+    },
+    /// Import memories from a JSONL export, keeping their IDs
+    Import {
+        /// JSONL file written by `memory export --format jsonl`
+        input: String,
     },
     /// Clear all memories (requires --confirm)
     Clear {
         #[arg(long)]
         confirm: bool,
     },
+    /// Embed memories missing an embedding from the configured model
+    /// (run after changing `embedding_model`)
+    Reembed {
+        /// Re-embed every memory, not only stale ones
+        #[arg(long)]
+        all: bool,
+    },
+    /// Forget expired and stale memories now (see `[memory.decay]`)
+    Compact,
+    /// Copy every memory from one backend to another (e.g. file to sqlite)
+    Migrate {
+        /// Backend to copy from: sqlite, file or postgres
+        #[arg(long)]
+        from: String,
+        /// Backend to copy to: sqlite, file or postgres
+        #[arg(long)]
+        to: String,
+        /// Storage path (connection URL for postgres) of the source backend
+        #[arg(long)]
+        from_path: Option<String>,
+        /// Storage path (connection URL for postgres) of the target backend
+        #[arg(long)]
+        to_path: Option<String>,
+        /// Memories copied per batch
+        #[arg(long, default_value = "500")]
+        batch_size: usize,
+        /// Start over instead of resuming an interrupted migration
+        #[arg(long)]
+        restart: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -270,7 +330,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             message,
             local,
             model,
-        } => commands::agent::run(message, local, model).await?,
+            resume,
+            conversation,
+        } => match resume {
+            Some(run_id) => commands::agent::resume(&run_id, local, model).await?,
+            None => commands::agent::run(message, local, model, conversation).await?,
+        },
         Commands::Gateway {
             port,
             host,
@@ -313,10 +378,39 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             MemoryAction::Search { query, limit } => {
                 commands::memory::search(&query, limit).await?
             }
-            MemoryAction::Export { output } => commands::memory::export(&output).await?,
+            MemoryAction::Export { output, format } => {
+                commands::memory::export(&output, format).await?
+            }
+            MemoryAction::Import { input } => commands::memory::import(&input).await?,
             MemoryAction::Clear { confirm } => commands::memory::clear(confirm).await?,
+            MemoryAction::Reembed { all } => commands::memory::reembed(all).await?,
+            MemoryAction::Compact => commands::memory::compact().await?,
+            MemoryAction::Migrate {
+                from,
+                to,
+                from_path,
+                to_path,
+                batch_size,
+                restart,
+            } => {
+                commands::memory::migrate(commands::memory::MigrateArgs {
+                    from: &from,
+                    to: &to,
+                    from_path: from_path.as_deref(),
+                    to_path: to_path.as_deref(),
+                    batch_size,
+                    restart,
+                })
+                .await?
+            }
         },
 
+        Commands::Ingest {
+            path,
+            watch,
+            interval,
+        } => commands::ingest::run(&path, watch, interval).await?,
+
         Commands::Config { action } => match action {
             ConfigAction::Validate => commands::config_cmd::validate().await?,
             ConfigAction::Show => commands::config_cmd::show().await?,
@@ -12,10 +12,11 @@ use rustedclaw_agent::{
 use rustedclaw_core::error::ProviderError;
 use rustedclaw_core::event::EventBus;
 use rustedclaw_core::identity::Identity;
-use rustedclaw_core::memory::MemoryEntry;
+use rustedclaw_core::memory::{MemoryEntry, MemoryScope};
 use rustedclaw_core::message::{Conversation, Message, MessageToolCall};
 use rustedclaw_core::provider::{Provider, ProviderRequest, ProviderResponse, Usage};
 use rustedclaw_tools::default_registry;
+use rustedclaw_tools::web_search::{SearchBackend, SearchResult, WebSearchTool};
 
 // ── Mock Provider ────────────────────────────────────────────────────────
 
@@ -191,8 +192,12 @@ async fn e2e_react_with_long_term_memories() {
         source: None,
         created_at: chrono::Utc::now(),
         last_accessed: chrono::Utc::now(),
+        access_count: 0,
+        expires_at: None,
         score: 0.9,
         embedding: None,
+        embedding_model: None,
+        scope: MemoryScope::default(),
     }];
 
     let agent = ReactAgent::new(provider.clone(), "mock", 0.7, tools, identity, event_bus);
@@ -234,6 +239,39 @@ async fn e2e_react_direct_answer_no_tools() {
 
 // ── E2E: Full RAG Pipeline ──────────────────────────────────────────────
 
+/// Tools backed by an in-memory store holding two ingested documents.
+async fn knowledge_registry() -> rustedclaw_core::tool::ToolRegistry {
+    use rustedclaw_core::memory::MemoryBackend;
+    use rustedclaw_memory::InMemoryBackend;
+    use rustedclaw_memory::ingest::{Document, DocumentFormat, DocumentIngestor};
+
+    let memory: Arc<dyn MemoryBackend> = Arc::new(InMemoryBackend::new());
+    let ingestor = DocumentIngestor::new(memory.clone());
+    for (id, text) in [
+        (
+            "rust_overview",
+            "# Rust\n\nRust enforces memory safety without a garbage collector.\n\n## Ownership\n\nThe ownership system and borrow checker rule out data races.",
+        ),
+        (
+            "python_overview",
+            "# Python\n\nPython is a dynamically typed language with a garbage collector.",
+        ),
+    ] {
+        ingestor
+            .ingest(Document {
+                id: id.into(),
+                source: format!("{id}.md"),
+                format: DocumentFormat::Markdown,
+                content: text.as_bytes().to_vec(),
+                tags: vec![],
+                scope: MemoryScope::default(),
+            })
+            .await
+            .unwrap();
+    }
+    rustedclaw_tools::registry_with_memory(memory)
+}
+
 #[tokio::test]
 async fn e2e_rag_knowledge_grounded_response() {
     // RAG agent: retrieves knowledge via knowledge_base_query tool, then generates.
@@ -245,7 +283,7 @@ async fn e2e_rag_knowledge_grounded_response() {
         ),
     ]));
 
-    let tools = Arc::new(default_registry());
+    let tools = Arc::new(knowledge_registry().await);
     let identity = Identity::default();
     let event_bus = Arc::new(EventBus::default());
     let mut conv = Conversation::new();
@@ -260,6 +298,10 @@ async fn e2e_rag_knowledge_grounded_response() {
     assert!(!result.answer.is_empty());
     assert!(!result.retrieved_chunks.is_empty());
     assert_eq!(result.retrieval_query, "How does Rust handle memory?");
+    // The best match is cited by document and chunk.
+    assert_eq!(result.sources[0].document_id, "rust_overview");
+    assert_eq!(result.sources[0].chunk_index, 0);
+    assert_eq!(result.sources[0].source, "rust_overview.md");
 
     // Working memory should record the retrieval.
     assert!(!result.working_memory.trace.is_empty());
@@ -271,7 +313,7 @@ async fn e2e_rag_with_existing_memories() {
         "Considering your background in Python and the retrieved Rust documentation, here's a comparison...",
     ));
 
-    let tools = Arc::new(default_registry());
+    let tools = Arc::new(knowledge_registry().await);
     let identity = Identity::default();
     let event_bus = Arc::new(EventBus::default());
     let mut conv = Conversation::new();
@@ -283,8 +325,12 @@ async fn e2e_rag_with_existing_memories() {
         source: None,
         created_at: chrono::Utc::now(),
         last_accessed: chrono::Utc::now(),
+        access_count: 0,
+        expires_at: None,
         score: 0.8,
         embedding: None,
+        embedding_model: None,
+        scope: MemoryScope::default(),
     }];
 
     let agent = RagAgent::new(provider.clone(), "mock", 0.7, tools, identity, event_bus);
@@ -296,7 +342,19 @@ async fn e2e_rag_with_existing_memories() {
 
     assert!(result.answer.contains("Python"));
     // Context metadata should show knowledge layer was populated.
-    assert!(result.context_metadata.is_some());
+    let meta = result.context_metadata.expect("context metadata");
+    let knowledge = meta
+        .per_layer
+        .iter()
+        .find(|l| l.name == "knowledge")
+        .unwrap();
+    assert!(knowledge.items_included > 0);
+    let cited: Vec<&str> = result
+        .sources
+        .iter()
+        .map(|s| s.document_id.as_str())
+        .collect();
+    assert!(cited.contains(&"rust_overview") && cited.contains(&"python_overview"));
 }
 
 // ── E2E: Multi-Agent Coordinator ────────────────────────────────────────
@@ -368,8 +426,12 @@ async fn e2e_context_assembly_full_layers() {
         source: None,
         created_at: chrono::Utc::now(),
         last_accessed: chrono::Utc::now(),
+        access_count: 0,
+        expires_at: None,
         score: 0.9,
         embedding: None,
+        embedding_model: None,
+        scope: MemoryScope::default(),
     }];
 
     let mut wm = WorkingMemory::default();
@@ -443,8 +505,12 @@ async fn e2e_context_assembly_budget_pressure() {
             source: None,
             created_at: chrono::Utc::now(),
             last_accessed: chrono::Utc::now(),
+            access_count: 0,
+            expires_at: None,
             score: 0.5,
             embedding: None,
+            embedding_model: None,
+            scope: MemoryScope::default(),
         })
         .collect();
 
@@ -533,11 +599,40 @@ async fn e2e_working_memory_full_lifecycle() {
     assert_eq!(deserialized.trace.len(), wm.trace.len());
 }
 
+/// Search backend answering every query with numbered results.
+struct FixedSearchBackend;
+
+#[async_trait::async_trait]
+impl SearchBackend for FixedSearchBackend {
+    fn name(&self) -> &str {
+        "fixed"
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        count: usize,
+    ) -> Result<Vec<SearchResult>, rustedclaw_core::error::ToolError> {
+        Ok((1..=count)
+            .map(|i| {
+                SearchResult::new(
+                    &format!("{query} result {i}"),
+                    &format!("https://example.com/{i}"),
+                    "",
+                )
+            })
+            .collect())
+    }
+}
+
 // ── E2E: Tool Registry Full Coverage ────────────────────────────────────
 
 #[tokio::test]
 async fn e2e_all_tools_executable() {
-    let registry = default_registry();
+    let mut registry = default_registry();
+    registry.register(Box::new(WebSearchTool::with_backend(Arc::new(
+        FixedSearchBackend,
+    ))));
 
     // Verify all 7 tools are registered.
     let names = registry.names();
@@ -582,6 +677,7 @@ async fn e2e_all_tools_executable() {
         .await
         .expect("Web search should work");
     assert!(search_result.success);
+    assert_eq!(search_result.data.unwrap()[0]["title"], "rust result 1");
 
     // Test weather.
     let weather_result = registry
@@ -595,8 +691,9 @@ async fn e2e_all_tools_executable() {
     assert!(weather_result.success);
     assert!(weather_result.output.contains("New York"));
 
-    // Test knowledge_base_query.
-    let kb_result = registry
+    // Test knowledge_base_query over ingested documents.
+    let kb_result = knowledge_registry()
+        .await
         .execute(&rustedclaw_core::tool::ToolCall {
             id: "tc4".into(),
             name: "knowledge_base_query".into(),
@@ -605,6 +702,7 @@ async fn e2e_all_tools_executable() {
         .await
         .expect("KB query should work");
     assert!(kb_result.success);
+    assert_eq!(kb_result.data.unwrap()[0]["document_id"], "rust_overview");
 }
 
 // ── E2E: Memory Backends ────────────────────────────────────────────────
@@ -625,8 +723,12 @@ async fn e2e_in_memory_backend_lifecycle() {
             source: Some("conversation_1".into()),
             created_at: chrono::Utc::now(),
             last_accessed: chrono::Utc::now(),
+            access_count: 0,
+            expires_at: None,
             score: 0.0,
             embedding: None,
+            embedding_model: None,
+            scope: MemoryScope::default(),
         })
         .await
         .expect("Store should work");
@@ -639,8 +741,12 @@ async fn e2e_in_memory_backend_lifecycle() {
             source: Some("conversation_2".into()),
             created_at: chrono::Utc::now(),
             last_accessed: chrono::Utc::now(),
+            access_count: 0,
+            expires_at: None,
             score: 0.0,
             embedding: None,
+            embedding_model: None,
+            scope: MemoryScope::default(),
         })
         .await
         .expect("Store should work");
@@ -656,6 +762,8 @@ async fn e2e_in_memory_backend_lifecycle() {
             min_score: 0.0,
             tags: vec![],
             mode: SearchMode::Keyword,
+            embedding: None,
+            scope: MemoryScope::default(),
         })
         .await
         .expect("Search should work");
@@ -697,8 +805,12 @@ async fn e2e_file_backend_persistence() {
                 source: None,
                 created_at: chrono::Utc::now(),
                 last_accessed: chrono::Utc::now(),
+                access_count: 0,
+                expires_at: None,
                 score: 0.0,
                 embedding: None,
+                embedding_model: None,
+                scope: MemoryScope::default(),
             })
             .await
             .expect("Store should work");
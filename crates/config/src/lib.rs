@@ -83,6 +83,14 @@ pub struct AppConfig {
     /// Telemetry, cost tracking, and budget configuration
     #[serde(default)]
     pub telemetry: TelemetryConfig,
+
+    /// Context assembly configuration
+    #[serde(default)]
+    pub context: ContextConfig,
+
+    /// Built-in tool configuration
+    #[serde(default)]
+    pub tools: ToolsConfig,
 }
 
 fn default_provider() -> String {
@@ -127,6 +135,22 @@ impl std::fmt::Debug for AppConfig {
             .field("routines", &self.routines)
             .field("contracts", &self.contracts)
             .field("telemetry", &self.telemetry)
+            .field("context", &self.context)
+            .field("tools", &self.tools)
+            .finish()
+    }
+}
+
+impl std::fmt::Debug for WebSearchToolConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebSearchToolConfig")
+            .field("backend", &self.backend)
+            .field("endpoint", &self.endpoint)
+            .field("api_key", &redact(&self.api_key))
+            .field("timeout_secs", &self.timeout_secs)
+            .field("cache_ttl_secs", &self.cache_ttl_secs)
+            .field("cache_capacity", &self.cache_capacity)
+            .field("json", &self.json)
             .finish()
     }
 }
@@ -149,14 +173,179 @@ pub struct MemoryConfig {
     #[serde(default = "default_true")]
     pub auto_save: bool,
 
+    /// Distill durable facts from each turn instead of auto-saving the raw
+    /// exchange. Extraction runs in the background after the answer is
+    /// returned.
+    #[serde(default = "default_true")]
+    pub extract_facts: bool,
+
+    /// Model used for fact extraction; defaults to the agent's model.
+    #[serde(default)]
+    pub extraction_model: Option<String>,
+
+    /// Provider (by name) that embeds stored memories; `none` disables
+    /// embeddings and vector search degrades to keyword search.
     #[serde(default = "default_embedding_provider")]
     pub embedding_provider: String,
 
+    /// Embedding model passed to the provider. Entries embedded with a
+    /// different model are refreshed by `rustedclaw memory reembed`.
+    #[serde(default = "default_embedding_model")]
+    pub embedding_model: String,
+
+    /// Maximum number of texts sent in one embedding request.
+    #[serde(default = "default_embedding_batch_size")]
+    pub embedding_batch_size: usize,
+
     #[serde(default = "default_vector_weight")]
     pub vector_weight: f32,
 
     #[serde(default = "default_keyword_weight")]
     pub keyword_weight: f32,
+
+    /// Storage path for the `sqlite` and `file` backends; each has its own
+    /// default under `~/.rustedclaw`.
+    #[serde(default)]
+    pub path: Option<String>,
+
+    /// Connection URL for the `postgres` backend; falls back to `DATABASE_URL`.
+    #[serde(default)]
+    pub url: Option<String>,
+
+    /// Where conversations are persisted (`[memory.conversations]`).
+    #[serde(default)]
+    pub conversations: ConversationStoreConfig,
+
+    /// How memories age (`[memory.decay]`).
+    #[serde(default)]
+    pub decay: MemoryDecayConfig,
+
+    /// How documents are split into chunks (`[memory.ingest]`).
+    #[serde(default)]
+    pub ingest: MemoryIngestConfig,
+}
+
+/// Recency and frequency ranking, expiry and compaction of memories.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryDecayConfig {
+    /// Hours after which the ranking boost of an unused memory halves.
+    #[serde(default = "default_half_life_hours")]
+    pub half_life_hours: f64,
+
+    /// Weight of recency in ranking; 0 ranks by relevance alone.
+    #[serde(default = "default_recency_weight")]
+    pub recency_weight: f32,
+
+    /// Weight of access frequency in ranking.
+    #[serde(default = "default_frequency_weight")]
+    pub frequency_weight: f32,
+
+    /// Lifetime of new memories in hours; unset keeps them until forgotten.
+    #[serde(default)]
+    pub ttl_hours: Option<f64>,
+
+    /// Compaction forgets memories whose value (recency times one plus the
+    /// access count) falls below this; 0 only removes expired memories.
+    #[serde(default)]
+    pub forget_below: f32,
+
+    /// Minutes between compaction runs; 0 disables the job.
+    #[serde(default = "default_compaction_interval_minutes")]
+    pub compaction_interval_minutes: u64,
+}
+
+fn default_half_life_hours() -> f64 {
+    24.0 * 30.0
+}
+fn default_recency_weight() -> f32 {
+    0.3
+}
+fn default_frequency_weight() -> f32 {
+    0.2
+}
+fn default_compaction_interval_minutes() -> u64 {
+    60
+}
+
+/// Document ingestion: chunking and directory watching.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryIngestConfig {
+    /// Target chunk size in tokens.
+    #[serde(default = "default_chunk_size")]
+    pub chunk_size: usize,
+
+    /// Tokens repeated from the end of one chunk at the start of the next.
+    #[serde(default = "default_chunk_overlap")]
+    pub chunk_overlap: usize,
+
+    /// Start a new chunk at every heading and prefix chunks with their
+    /// heading path.
+    #[serde(default = "default_true")]
+    pub heading_aware: bool,
+
+    /// Seconds between scans when `rustedclaw ingest --watch` is running.
+    #[serde(default = "default_watch_interval_secs")]
+    pub watch_interval_secs: u64,
+}
+
+fn default_chunk_size() -> usize {
+    512
+}
+fn default_chunk_overlap() -> usize {
+    64
+}
+fn default_watch_interval_secs() -> u64 {
+    5
+}
+
+impl Default for MemoryIngestConfig {
+    fn default() -> Self {
+        Self {
+            chunk_size: default_chunk_size(),
+            chunk_overlap: default_chunk_overlap(),
+            heading_aware: true,
+            watch_interval_secs: default_watch_interval_secs(),
+        }
+    }
+}
+
+impl Default for MemoryDecayConfig {
+    fn default() -> Self {
+        Self {
+            half_life_hours: default_half_life_hours(),
+            recency_weight: default_recency_weight(),
+            frequency_weight: default_frequency_weight(),
+            ttl_hours: None,
+            forget_below: 0.0,
+            compaction_interval_minutes: default_compaction_interval_minutes(),
+        }
+    }
+}
+
+/// Conversation persistence settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationStoreConfig {
+    /// `sqlite` (default), `file` or `memory`.
+    #[serde(default = "default_conversation_backend")]
+    pub backend: String,
+
+    /// Database file (`sqlite`) or directory (`file`); each has its own
+    /// default under `~/.rustedclaw`.
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+fn default_conversation_backend() -> String {
+    "sqlite".into()
+}
+
+impl Default for ConversationStoreConfig {
+    fn default() -> Self {
+        Self {
+            backend: default_conversation_backend(),
+            path: None,
+        }
+    }
 }
 
 fn default_memory_backend() -> String {
@@ -165,6 +354,12 @@ fn default_memory_backend() -> String {
 fn default_embedding_provider() -> String {
     "none".into()
 }
+fn default_embedding_model() -> String {
+    "text-embedding-3-small".into()
+}
+fn default_embedding_batch_size() -> usize {
+    32
+}
 fn default_vector_weight() -> f32 {
     0.7
 }
@@ -180,9 +375,18 @@ impl Default for MemoryConfig {
         Self {
             backend: default_memory_backend(),
             auto_save: true,
+            extract_facts: true,
+            extraction_model: None,
             embedding_provider: default_embedding_provider(),
+            embedding_model: default_embedding_model(),
+            embedding_batch_size: default_embedding_batch_size(),
             vector_weight: default_vector_weight(),
             keyword_weight: default_keyword_weight(),
+            path: None,
+            url: None,
+            conversations: ConversationStoreConfig::default(),
+            decay: MemoryDecayConfig::default(),
+            ingest: MemoryIngestConfig::default(),
         }
     }
 }
@@ -228,10 +432,10 @@ pub struct AutonomyConfig {
     #[serde(default = "default_true")]
     pub workspace_only: bool,
 
-    #[serde(default)]
+    #[serde(default = "default_allowed_commands")]
     pub allowed_commands: Vec<String>,
 
-    #[serde(default)]
+    #[serde(default = "default_forbidden_paths")]
     pub forbidden_paths: Vec<String>,
 
     #[serde(default)]
@@ -242,28 +446,27 @@ fn default_autonomy_level() -> String {
     "supervised".into()
 }
 
+fn default_allowed_commands() -> Vec<String> {
+    ["git", "npm", "cargo", "ls", "cat", "grep"]
+        .map(String::from)
+        .to_vec()
+}
+
+fn default_forbidden_paths() -> Vec<String> {
+    [
+        "/etc", "/root", "/proc", "/sys", "~/.ssh", "~/.gnupg", "~/.aws",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
 impl Default for AutonomyConfig {
     fn default() -> Self {
         Self {
             level: default_autonomy_level(),
             workspace_only: true,
-            allowed_commands: vec![
-                "git".into(),
-                "npm".into(),
-                "cargo".into(),
-                "ls".into(),
-                "cat".into(),
-                "grep".into(),
-            ],
-            forbidden_paths: vec![
-                "/etc".into(),
-                "/root".into(),
-                "/proc".into(),
-                "/sys".into(),
-                "~/.ssh".into(),
-                "~/.gnupg".into(),
-                "~/.aws".into(),
-            ],
+            allowed_commands: default_allowed_commands(),
+            forbidden_paths: default_forbidden_paths(),
             allowed_roots: vec![],
         }
     }
@@ -526,6 +729,333 @@ pub struct BudgetConfig {
     pub on_exceed: String,
 }
 
+/// Context assembly configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextConfig {
+    /// Compress turns that overflow the history budget into a rolling
+    /// summary (one extra LLM call) instead of dropping them
+    #[serde(default)]
+    pub summarize_history: bool,
+
+    /// Maximum tokens for the rolling conversation summary
+    #[serde(default = "default_summary_max_tokens")]
+    pub summary_max_tokens: u32,
+
+    /// Context window in tokens, overriding the built-in model table
+    /// (for fine-tunes, self-hosted models or custom local context sizes)
+    #[serde(default)]
+    pub context_window: Option<usize>,
+
+    /// Per-layer caps as a percentage of the prompt budget
+    #[serde(default)]
+    pub layers: LayerBudgetConfig,
+
+    /// Tools always offered to the model, whatever the message
+    #[serde(default)]
+    pub pinned_tools: Vec<String>,
+
+    /// Embedding model used to rank tools by similarity to the message
+    /// (keyword relevance only when unset)
+    #[serde(default)]
+    pub tool_embedding_model: Option<String>,
+
+    /// Add a `[Current Time]` section with today's date and time
+    #[serde(default)]
+    pub current_time: bool,
+}
+
+/// Per-layer shares of the prompt budget, in percent (0–100).
+///
+/// Unset layers take whatever the higher-priority layers leave.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LayerBudgetConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_term_memory: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_memory: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub knowledge: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_schemas: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation_history: Option<f32>,
+    /// Cap on a single tool result kept in history
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_output: Option<f32>,
+}
+
+impl LayerBudgetConfig {
+    fn shares(&self) -> [(&'static str, Option<f32>); 6] {
+        [
+            ("long_term_memory", self.long_term_memory),
+            ("working_memory", self.working_memory),
+            ("knowledge", self.knowledge),
+            ("tool_schemas", self.tool_schemas),
+            ("conversation_history", self.conversation_history),
+            ("tool_output", self.tool_output),
+        ]
+    }
+}
+
+fn default_summary_max_tokens() -> u32 {
+    400
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        Self {
+            summarize_history: false,
+            summary_max_tokens: default_summary_max_tokens(),
+            context_window: None,
+            layers: LayerBudgetConfig::default(),
+            pinned_tools: Vec::new(),
+            tool_embedding_model: None,
+            current_time: false,
+        }
+    }
+}
+
+/// Tool registry configuration (`[tools]`).
+///
+/// The shell and file tools take their command allowlist, allowed roots
+/// and forbidden paths from `[autonomy]`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolsConfig {
+    /// Tools to leave out of the registry, by name (e.g. `"shell"`).
+    #[serde(default)]
+    pub disabled: Vec<String>,
+
+    /// Directory of WASM tools (`<name>.wasm` plus `<name>.tool.json`).
+    /// Requires a build with the `wasm` feature.
+    #[serde(default)]
+    pub wasm_dir: Option<String>,
+
+    /// Settings for the `shell` tool.
+    #[serde(default)]
+    pub shell: ShellToolConfig,
+
+    /// Limits for the `http_request` tool.
+    #[serde(default)]
+    pub http_request: HttpRequestToolConfig,
+
+    /// Search backend for the `web_search` tool.
+    #[serde(default)]
+    pub web_search: WebSearchToolConfig,
+}
+
+impl ToolsConfig {
+    /// Whether the tool called `name` should be registered.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        !self.disabled.iter().any(|disabled| disabled == name)
+    }
+}
+
+/// Settings for the `shell` tool (`[tools.shell]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShellToolConfig {
+    /// Seconds before a command is killed.
+    #[serde(default = "default_shell_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_shell_timeout_secs() -> u64 {
+    30
+}
+
+impl Default for ShellToolConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: default_shell_timeout_secs(),
+        }
+    }
+}
+
+/// Limits for the `http_request` tool (`[tools.http_request]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpRequestToolConfig {
+    /// Hosts the tool may call: exact names or `*.example.com` patterns.
+    /// Empty allows any public host.
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+
+    /// Allow loopback, private and link-local addresses. Off by default so
+    /// agents can't reach internal services or cloud metadata endpoints.
+    #[serde(default)]
+    pub allow_private_networks: bool,
+
+    /// Largest response body read from the network, in bytes.
+    #[serde(default = "default_max_response_bytes")]
+    pub max_response_bytes: usize,
+
+    /// Characters of a text body returned to the agent.
+    #[serde(default = "default_max_body_chars")]
+    pub max_body_chars: usize,
+
+    /// Redirects followed before the request fails.
+    #[serde(default = "default_max_redirects")]
+    pub max_redirects: usize,
+
+    /// Timeout used when a call doesn't ask for one.
+    #[serde(default = "default_http_timeout_secs")]
+    pub timeout_secs: u64,
+
+    /// Upper bound for a timeout a call asks for.
+    #[serde(default = "default_http_max_timeout_secs")]
+    pub max_timeout_secs: u64,
+}
+
+fn default_max_response_bytes() -> usize {
+    1024 * 1024
+}
+fn default_max_body_chars() -> usize {
+    20_000
+}
+fn default_max_redirects() -> usize {
+    5
+}
+fn default_http_timeout_secs() -> u64 {
+    30
+}
+fn default_http_max_timeout_secs() -> u64 {
+    120
+}
+
+impl Default for HttpRequestToolConfig {
+    fn default() -> Self {
+        Self {
+            allowed_hosts: Vec::new(),
+            allow_private_networks: false,
+            max_response_bytes: default_max_response_bytes(),
+            max_body_chars: default_max_body_chars(),
+            max_redirects: default_max_redirects(),
+            timeout_secs: default_http_timeout_secs(),
+            max_timeout_secs: default_http_max_timeout_secs(),
+        }
+    }
+}
+
+/// Search backend for the `web_search` tool (`[tools.web_search]`).
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WebSearchToolConfig {
+    /// `"searxng"`, `"brave"` or `"json"`. Unset leaves `web_search`
+    /// without a backend.
+    #[serde(default)]
+    pub backend: Option<String>,
+
+    /// SearXNG instance URL or JSON API endpoint. Brave defaults to its
+    /// public API.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+
+    /// API key: Brave's subscription token, or sent in
+    /// `json.api_key_header` for the JSON adapter.
+    #[serde(default)]
+    pub api_key: Option<String>,
+
+    #[serde(default = "default_search_timeout_secs")]
+    pub timeout_secs: u64,
+
+    /// Seconds a query's results are reused; 0 disables caching.
+    #[serde(default = "default_search_cache_ttl_secs")]
+    pub cache_ttl_secs: u64,
+
+    /// Most queries kept in the cache.
+    #[serde(default = "default_search_cache_capacity")]
+    pub cache_capacity: usize,
+
+    /// Response mapping for the generic JSON adapter.
+    #[serde(default)]
+    pub json: JsonSearchConfig,
+}
+
+fn default_search_timeout_secs() -> u64 {
+    15
+}
+fn default_search_cache_ttl_secs() -> u64 {
+    600
+}
+fn default_search_cache_capacity() -> usize {
+    256
+}
+
+impl Default for WebSearchToolConfig {
+    fn default() -> Self {
+        Self {
+            backend: None,
+            endpoint: None,
+            api_key: None,
+            timeout_secs: default_search_timeout_secs(),
+            cache_ttl_secs: default_search_cache_ttl_secs(),
+            cache_capacity: default_search_cache_capacity(),
+            json: JsonSearchConfig::default(),
+        }
+    }
+}
+
+/// How the generic JSON adapter calls an API and reads its results
+/// (`[tools.web_search.json]`). Paths are dot-separated, e.g.
+/// `"data.items"`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonSearchConfig {
+    /// Query-string parameter carrying the query.
+    #[serde(default = "default_query_param")]
+    pub query_param: String,
+
+    /// Query-string parameter carrying the result count, if the API has one.
+    #[serde(default)]
+    pub count_param: Option<String>,
+
+    /// Header carrying `api_key`.
+    #[serde(default = "default_api_key_header")]
+    pub api_key_header: String,
+
+    /// Path to the array of results in the response.
+    #[serde(default = "default_results_path")]
+    pub results_path: String,
+
+    #[serde(default = "default_title_field")]
+    pub title_field: String,
+
+    #[serde(default = "default_url_field")]
+    pub url_field: String,
+
+    #[serde(default = "default_snippet_field")]
+    pub snippet_field: String,
+}
+
+fn default_query_param() -> String {
+    "q".into()
+}
+fn default_api_key_header() -> String {
+    "Authorization".into()
+}
+fn default_results_path() -> String {
+    "results".into()
+}
+fn default_title_field() -> String {
+    "title".into()
+}
+fn default_url_field() -> String {
+    "url".into()
+}
+fn default_snippet_field() -> String {
+    "snippet".into()
+}
+
+impl Default for JsonSearchConfig {
+    fn default() -> Self {
+        Self {
+            query_param: default_query_param(),
+            count_param: None,
+            api_key_header: default_api_key_header(),
+            results_path: default_results_path(),
+            title_field: default_title_field(),
+            url_field: default_url_field(),
+            snippet_field: default_snippet_field(),
+        }
+    }
+}
+
 /// Custom per-million-token pricing for a model.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PricingOverrideConfig {
@@ -613,6 +1143,69 @@ impl AppConfig {
             ));
         }
 
+        if self.memory.decay.half_life_hours <= 0.0 {
+            return Err(ConfigError::ValidationError(
+                "memory.decay.half_life_hours must be > 0".into(),
+            ));
+        }
+
+        let ingest = &self.memory.ingest;
+        if ingest.chunk_size == 0 || ingest.chunk_overlap >= ingest.chunk_size {
+            return Err(ConfigError::ValidationError(
+                "memory.ingest.chunk_size must be > 0 and larger than chunk_overlap".into(),
+            ));
+        }
+
+        if self.tools.shell.timeout_secs == 0 {
+            return Err(ConfigError::ValidationError(
+                "tools.shell.timeout_secs must be > 0".into(),
+            ));
+        }
+
+        let http = &self.tools.http_request;
+        if http.max_response_bytes == 0 || http.max_body_chars == 0 {
+            return Err(ConfigError::ValidationError(
+                "tools.http_request.max_response_bytes and max_body_chars must be > 0".into(),
+            ));
+        }
+        if http.timeout_secs == 0 || http.timeout_secs > http.max_timeout_secs {
+            return Err(ConfigError::ValidationError(
+                "tools.http_request.timeout_secs must be > 0 and at most max_timeout_secs".into(),
+            ));
+        }
+
+        let search = &self.tools.web_search;
+        match search.backend.as_deref() {
+            None => {}
+            Some("searxng" | "json") if search.endpoint.is_none() => {
+                return Err(ConfigError::ValidationError(
+                    "tools.web_search.endpoint is required for the searxng and json backends"
+                        .into(),
+                ));
+            }
+            Some("brave") if search.api_key.is_none() => {
+                return Err(ConfigError::ValidationError(
+                    "tools.web_search.api_key is required for the brave backend".into(),
+                ));
+            }
+            Some("searxng" | "brave" | "json") => {}
+            Some(other) => {
+                return Err(ConfigError::ValidationError(format!(
+                    "tools.web_search.backend must be searxng, brave or json, not '{other}'"
+                )));
+            }
+        }
+
+        for (layer, share) in self.context.layers.shares() {
+            if let Some(pct) = share
+                && !(0.0..=100.0).contains(&pct)
+            {
+                return Err(ConfigError::ValidationError(format!(
+                    "context.layers.{layer} must be between 0 and 100"
+                )));
+            }
+        }
+
         Ok(())
     }
 
@@ -649,6 +1242,8 @@ impl Default for AppConfig {
             routines: vec![],
             contracts: vec![],
             telemetry: TelemetryConfig::default(),
+            context: ContextConfig::default(),
+            tools: ToolsConfig::default(),
         }
     }
 }
@@ -712,6 +1307,166 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn context_layer_percentages_parsed_and_validated() {
+        let toml_str = r#"
+[context]
+context_window = 32000
+
+[context.layers]
+knowledge = 25.0
+tool_output = 5
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.context.context_window, Some(32000));
+        assert_eq!(config.context.layers.knowledge, Some(25.0));
+        assert_eq!(config.context.layers.tool_output, Some(5.0));
+        assert_eq!(config.context.layers.conversation_history, None);
+        assert!(config.validate().is_ok());
+
+        let mut bad = config;
+        bad.context.layers.working_memory = Some(150.0);
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn partial_autonomy_keeps_default_lists() {
+        let toml_str = r#"
+[autonomy]
+level = "full"
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        let defaults = AutonomyConfig::default();
+        assert_eq!(config.autonomy.level, "full");
+        assert_eq!(config.autonomy.allowed_commands, defaults.allowed_commands);
+        assert_eq!(config.autonomy.forbidden_paths, defaults.forbidden_paths);
+
+        // An explicit empty list still means none.
+        let toml_str = r#"
+[autonomy]
+allowed_commands = []
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        assert!(config.autonomy.allowed_commands.is_empty());
+    }
+
+    #[test]
+    fn memory_decay_parsed_and_validated() {
+        let toml_str = r#"
+[memory.decay]
+half_life_hours = 48
+ttl_hours = 720
+compaction_interval_minutes = 0
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        let decay = &config.memory.decay;
+        assert_eq!(decay.half_life_hours, 48.0);
+        assert_eq!(decay.ttl_hours, Some(720.0));
+        assert_eq!(decay.compaction_interval_minutes, 0);
+        assert_eq!(decay.recency_weight, 0.3);
+        assert!(config.validate().is_ok());
+
+        let mut bad = config;
+        bad.memory.decay.half_life_hours = 0.0;
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn memory_ingest_parsed_and_validated() {
+        let toml_str = r#"
+[memory.ingest]
+chunk_size = 256
+heading_aware = false
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        let ingest = &config.memory.ingest;
+        assert_eq!(ingest.chunk_size, 256);
+        assert_eq!(ingest.chunk_overlap, 64);
+        assert!(!ingest.heading_aware);
+        assert!(config.validate().is_ok());
+
+        let mut bad = config;
+        bad.memory.ingest.chunk_overlap = 256;
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn http_request_tool_parsed_and_validated() {
+        let toml_str = r#"
+[tools.http_request]
+allowed_hosts = ["api.github.com", "*.example.com"]
+max_redirects = 0
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        let http = &config.tools.http_request;
+        assert_eq!(http.allowed_hosts.len(), 2);
+        assert_eq!(http.max_redirects, 0);
+        assert!(!http.allow_private_networks);
+        assert_eq!(http.max_response_bytes, 1024 * 1024);
+        assert!(config.validate().is_ok());
+
+        let mut bad = config;
+        bad.tools.http_request.timeout_secs = 600;
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn tools_section_parsed_and_validated() {
+        let toml_str = r#"
+[tools]
+disabled = ["shell", "weather_lookup"]
+wasm_dir = "~/.rustedclaw/tools"
+
+[tools.shell]
+timeout_secs = 60
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        assert!(!config.tools.is_enabled("shell"));
+        assert!(config.tools.is_enabled("calculator"));
+        assert_eq!(
+            config.tools.wasm_dir.as_deref(),
+            Some("~/.rustedclaw/tools")
+        );
+        assert_eq!(config.tools.shell.timeout_secs, 60);
+        assert!(config.validate().is_ok());
+
+        let mut bad = config;
+        bad.tools.shell.timeout_secs = 0;
+        assert!(bad.validate().is_err());
+
+        assert!(AppConfig::default().tools.disabled.is_empty());
+    }
+
+    #[test]
+    fn web_search_parsed_and_validated() {
+        let toml_str = r#"
+[tools.web_search]
+backend = "json"
+endpoint = "https://search.example.com/api"
+api_key = "secret"
+
+[tools.web_search.json]
+results_path = "data.items"
+url_field = "link"
+"#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        let search = &config.tools.web_search;
+        assert_eq!(search.backend.as_deref(), Some("json"));
+        assert_eq!(search.json.results_path, "data.items");
+        assert_eq!(search.json.query_param, "q");
+        assert_eq!(search.cache_ttl_secs, 600);
+        assert!(config.validate().is_ok());
+        assert!(!format!("{search:?}").contains("secret"));
+
+        let mut bad = config.clone();
+        bad.tools.web_search.endpoint = None;
+        assert!(bad.validate().is_err());
+
+        let mut bad = config;
+        bad.tools.web_search.backend = Some("google".into());
+        assert!(bad.validate().is_err());
+    }
+
     #[test]
     fn missing_config_file_returns_defaults() {
         let result = AppConfig::load_from(Path::new("/nonexistent/config.toml"));
@@ -222,7 +222,7 @@ impl ContractEngine {
             })
             .collect();
 
-        matching.sort_by(|a, b| b.0.priority.cmp(&a.0.priority));
+        matching.sort_by_key(|m| std::cmp::Reverse(m.0.priority));
 
         for (contract, condition) in &matching {
             if condition.evaluate(ctx) {
@@ -5,6 +5,8 @@
 //! responses back.
 
 use crate::error::ChannelError;
+use crate::memory::MemoryScope;
+use crate::message::Message;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
@@ -50,6 +52,24 @@ pub struct ChannelMessage {
     pub metadata: serde_json::Map<String, serde_json::Value>,
 }
 
+impl ChannelMessage {
+    /// This message as a user turn. The channel and sender are recorded in
+    /// the message metadata, which scopes memory recall to them.
+    pub fn to_message(&self) -> Message {
+        let mut message = Message::user(&self.content);
+        MemoryScope {
+            user_id: Some(self.sender_id.clone()),
+            channel: Some(self.channel_id.0.clone()),
+            ..Default::default()
+        }
+        .write_metadata(&mut message.metadata);
+        message
+            .metadata
+            .insert("chat_id".into(), self.chat_id.clone().into());
+        message
+    }
+}
+
 /// An attachment in a channel message.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Attachment {
@@ -152,6 +172,12 @@ mod tests {
         };
         assert_eq!(msg.channel_id.0, "telegram");
         assert_eq!(msg.content, "Hello bot!");
+
+        let turn = msg.to_message();
+        assert_eq!(turn.content, "Hello bot!");
+        let scope = MemoryScope::from_metadata(&turn.metadata);
+        assert_eq!(scope.user_id.as_deref(), Some("12345"));
+        assert_eq!(scope.channel.as_deref(), Some("telegram"));
     }
 
     #[test]
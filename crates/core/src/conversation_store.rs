@@ -0,0 +1,226 @@
+//! Conversation persistence.
+//!
+//! A [`ConversationStore`] keeps whole [`Conversation`]s across restarts.
+//! The gateway's conversation endpoints and the CLI agent read and write
+//! through it. Implementations (SQLite, file, in-memory) live in
+//! `rustedclaw-memory`.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::error::MemoryError;
+use crate::message::{Conversation, Role};
+
+/// Longest preview kept in a [`ConversationInfo`], in characters.
+const PREVIEW_CHARS: usize = 120;
+
+/// Listing entry for a stored conversation; the messages are not loaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationInfo {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub message_count: usize,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Start of the first user message, for untitled conversations.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<String>,
+}
+
+impl From<&Conversation> for ConversationInfo {
+    fn from(conversation: &Conversation) -> Self {
+        let preview = conversation
+            .messages
+            .iter()
+            .find(|m| m.role == Role::User)
+            .map(|m| m.content.chars().take(PREVIEW_CHARS).collect());
+        Self {
+            id: conversation.id.to_string(),
+            title: conversation.title.clone(),
+            message_count: conversation.messages.len(),
+            created_at: conversation.created_at,
+            updated_at: conversation.updated_at,
+            preview,
+        }
+    }
+}
+
+/// One page of [`ConversationStore::list`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationPage {
+    /// Conversations on this page, most recently updated first.
+    pub conversations: Vec<ConversationInfo>,
+    /// Total number of stored conversations.
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+/// Format for [`ExportFormat::render`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// The conversation as stored, pretty-printed.
+    #[default]
+    Json,
+    /// A readable transcript.
+    Markdown,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "markdown" | "md" => Ok(Self::Markdown),
+            other => Err(format!(
+                "Unknown export format '{other}' (expected json or markdown)"
+            )),
+        }
+    }
+}
+
+impl ExportFormat {
+    /// Render `conversation` in this format.
+    pub fn render(self, conversation: &Conversation) -> crate::Result<String> {
+        match self {
+            Self::Json => Ok(serde_json::to_string_pretty(conversation)?),
+            Self::Markdown => Ok(to_markdown(conversation)),
+        }
+    }
+
+    /// MIME type of the rendered output.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Markdown => "text/markdown; charset=utf-8",
+        }
+    }
+}
+
+/// A Markdown transcript: title, then one section per message. Tool calls
+/// and results are shown as code blocks.
+fn to_markdown(conversation: &Conversation) -> String {
+    let title = conversation.title.as_deref().unwrap_or("Conversation");
+    let mut out = format!(
+        "# {title}\n\n_{} · started {}_\n",
+        conversation.id,
+        conversation.created_at.format("%Y-%m-%d %H:%M UTC")
+    );
+    for message in &conversation.messages {
+        let heading = match message.role {
+            Role::System => "System",
+            Role::User => "User",
+            Role::Assistant => "Assistant",
+            Role::Tool => "Tool result",
+        };
+        out.push_str(&format!("\n## {heading}\n\n"));
+        if message.role == Role::Tool {
+            out.push_str(&format!("```\n{}\n```\n", message.content));
+        } else if !message.content.is_empty() {
+            out.push_str(&message.content);
+            out.push('\n');
+        }
+        for call in &message.tool_calls {
+            out.push_str(&format!(
+                "\n`{}` call:\n\n```json\n{}\n```\n",
+                call.name, call.arguments
+            ));
+        }
+    }
+    out
+}
+
+/// Persistent storage for conversations.
+#[async_trait]
+pub trait ConversationStore: Send + Sync {
+    /// The store name (e.g., "sqlite", "file").
+    fn name(&self) -> &str;
+
+    /// Insert or replace the conversation with `conversation.id`.
+    async fn save(&self, conversation: &Conversation) -> Result<(), MemoryError>;
+
+    /// Load a conversation by ID.
+    async fn load(&self, id: &str) -> Result<Option<Conversation>, MemoryError>;
+
+    /// Delete a conversation. Returns `true` if it existed.
+    async fn delete(&self, id: &str) -> Result<bool, MemoryError>;
+
+    /// Up to `limit` conversations after skipping `offset`, most recently
+    /// updated first.
+    async fn list(&self, offset: usize, limit: usize) -> Result<ConversationPage, MemoryError>;
+
+    /// Conversations whose title or message content contains `text`
+    /// (case-insensitive), most recently updated first.
+    async fn search(&self, text: &str, limit: usize) -> Result<Vec<ConversationInfo>, MemoryError>;
+
+    /// Set a conversation's title. Returns `false` if it does not exist.
+    async fn set_title(&self, id: &str, title: &str) -> Result<bool, MemoryError> {
+        let Some(mut conversation) = self.load(id).await? else {
+            return Ok(false);
+        };
+        conversation.title = Some(title.to_string());
+        self.save(&conversation).await?;
+        Ok(true)
+    }
+
+    /// Number of stored conversations.
+    async fn count(&self) -> Result<usize, MemoryError> {
+        Ok(self.list(0, 0).await?.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::{Message, MessageToolCall};
+
+    fn sample() -> Conversation {
+        let mut conv = Conversation::new();
+        conv.title = Some("Arithmetic".into());
+        conv.push(Message::user("What is 2+3?"));
+        let mut call = Message::assistant("");
+        call.tool_calls.push(MessageToolCall {
+            id: "call_1".into(),
+            name: "calculator".into(),
+            arguments: r#"{"expression":"2+3"}"#.into(),
+        });
+        conv.push(call);
+        conv.push(Message::tool_result("call_1", "5"));
+        conv.push(Message::assistant("2+3 is 5."));
+        conv
+    }
+
+    #[test]
+    fn info_summarizes_conversation() {
+        let conv = sample();
+        let info = ConversationInfo::from(&conv);
+        assert_eq!(info.id, conv.id.to_string());
+        assert_eq!(info.message_count, 4);
+        assert_eq!(info.preview.as_deref(), Some("What is 2+3?"));
+    }
+
+    #[test]
+    fn markdown_export_renders_transcript() {
+        let md = ExportFormat::Markdown.render(&sample()).unwrap();
+        assert!(md.starts_with("# Arithmetic\n"));
+        assert!(md.contains("## User\n\nWhat is 2+3?"));
+        assert!(md.contains("`calculator` call:"));
+        assert!(md.contains("## Tool result\n\n```\n5\n```"));
+        assert!(md.contains("## Assistant\n\n2+3 is 5."));
+    }
+
+    #[test]
+    fn json_export_roundtrips() {
+        let conv = sample();
+        let json = ExportFormat::Json.render(&conv).unwrap();
+        let back: Conversation = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.messages.len(), 4);
+        assert_eq!("md".parse::<ExportFormat>(), Ok(ExportFormat::Markdown));
+        assert!("pdf".parse::<ExportFormat>().is_err());
+    }
+}
@@ -32,6 +32,14 @@ pub enum Error {
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 
+    // --- Cancellation ---
+    #[error("Run cancelled: {0}")]
+    Cancelled(String),
+
+    // --- Run registration ---
+    #[error("Run already in progress: {0}")]
+    RunInProgress(String),
+
     // --- Generic ---
     #[error("Internal error: {0}")]
     Internal(String),
@@ -100,6 +108,9 @@ pub enum MemoryError {
 
     #[error("Migration failed: {0}")]
     MigrationFailed(String),
+
+    #[error("Invalid document: {0}")]
+    InvalidDocument(String),
 }
 
 #[derive(Debug, Error)]
@@ -379,7 +379,7 @@ impl Identity {
         None
     }
 
-    /// Estimate the token count of the system prompt (rough: 4 chars ≈ 1 token).
+    /// Estimate the token count of the system prompt (rough: 4 bytes ≈ 1 token).
     pub fn estimated_tokens(&self) -> usize {
         self.system_prompt.len() / 4
     }
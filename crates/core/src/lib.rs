@@ -14,21 +14,27 @@
 
 pub mod agent;
 pub mod channel;
+pub mod conversation_store;
 pub mod error;
 pub mod event;
 pub mod identity;
 pub mod memory;
 pub mod message;
+pub mod model;
 pub mod provider;
+pub mod tokens;
 pub mod tool;
 
 // Re-export key types at crate root for ergonomics
 pub use agent::{AgentConfig, AgentState};
 pub use channel::{Channel, ChannelId, ChannelMessage};
+pub use conversation_store::{ConversationInfo, ConversationPage, ConversationStore, ExportFormat};
 pub use error::{Error, Result};
 pub use event::{DomainEvent, EventBus};
 pub use identity::{ContextPaths, Identity};
-pub use memory::{MemoryBackend, MemoryEntry, MemoryQuery};
-pub use message::{Conversation, ConversationId, Message, Role};
+pub use memory::{MemoryBackend, MemoryEntry, MemoryQuery, MemoryScope};
+pub use message::{Conversation, ConversationId, ConversationSummary, Message, Role};
+pub use model::ModelCapabilities;
 pub use provider::{Provider, ProviderRequest, ProviderResponse, StreamChunk};
+pub use tokens::{HeuristicCounter, TokenCounter};
 pub use tool::{Tool, ToolCall, ToolRegistry, ToolResult};
@@ -5,11 +5,30 @@
 //! - Full-text search (keyword matching via FTS)
 //! - Vector search (semantic similarity via embeddings)
 //! - Hybrid search (weighted combination of both)
+//!
+//! Every entry carries a [`MemoryScope`] naming who it belongs to, and
+//! backends only return entries visible to the query's scope.
+//!
+//! Backends count every recall of an entry and rank results with a
+//! [`DecayPolicy`], which favours recently and frequently used memories,
+//! expires entries past their TTL and lets [`MemoryBackend::compact`]
+//! forget the ones nobody uses any more.
+//!
+//! Besides recall, backends support partial updates ([`MemoryPatch`]),
+//! paginated listing and bulk deletion by [`MemoryFilter`], batched
+//! stores, and JSONL export/import for moving memories between backends.
 
 use crate::error::MemoryError;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Entries fetched per [`MemoryBackend::list`] call while exporting.
+pub const EXPORT_PAGE_SIZE: usize = 500;
+
+/// Entries handed to [`MemoryBackend::store_batch`] at once while importing.
+pub const IMPORT_BATCH_SIZE: usize = 100;
 
 /// A single memory entry.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,13 +53,30 @@ pub struct MemoryEntry {
     /// When this memory was last accessed
     pub last_accessed: DateTime<Utc>,
 
+    /// How many times the memory has been recalled
+    #[serde(default)]
+    pub access_count: u32,
+
+    /// When the memory expires; expired entries are never returned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+
     /// Relevance score (set by search operations)
     #[serde(default)]
     pub score: f32,
 
     /// Optional embedding vector (stored as blob in DB)
-    #[serde(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub embedding: Option<Vec<f32>>,
+
+    /// Model that produced `embedding`, so entries can be re-embedded when
+    /// the configured embedding model changes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<String>,
+
+    /// Who the memory belongs to
+    #[serde(default, skip_serializing_if = "MemoryScope::is_empty")]
+    pub scope: MemoryScope,
 }
 
 /// A query for searching memories.
@@ -64,12 +100,339 @@ pub struct MemoryQuery {
     /// Search mode
     #[serde(default)]
     pub mode: SearchMode,
+
+    /// Embedding of `text`, used by vector and hybrid search. Backends fall
+    /// back to keyword search when it is missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
+
+    /// Only entries visible to this scope are returned
+    #[serde(default)]
+    pub scope: MemoryScope,
 }
 
 fn default_limit() -> usize {
     10
 }
 
+impl MemoryEntry {
+    /// Whether the entry's TTL has run out at `now`.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+
+    /// Record one access at `now`.
+    pub fn touch(&mut self, now: DateTime<Utc>) {
+        self.last_accessed = now;
+        self.access_count = self.access_count.saturating_add(1);
+    }
+}
+
+impl MemoryQuery {
+    /// Whether the query looks something up (by text or embedding) rather
+    /// than listing entries. Only recalls count as accesses and are ranked
+    /// with the [`DecayPolicy`].
+    pub fn is_recall(&self) -> bool {
+        !self.text.trim().is_empty() || self.embedding.is_some()
+    }
+
+    /// The query's tag and scope filters.
+    pub fn filter(&self) -> MemoryFilter {
+        MemoryFilter {
+            tags: self.tags.clone(),
+            scope: Some(self.scope.clone()),
+            ..MemoryFilter::default()
+        }
+    }
+}
+
+/// A partial update of a memory entry; unset fields are left unchanged.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemoryPatch {
+    /// New content; the embedding of the old content is dropped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+
+    /// New tags, replacing the old ones
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+}
+
+impl MemoryPatch {
+    /// Whether the patch changes nothing.
+    pub fn is_empty(&self) -> bool {
+        self.content.is_none() && self.tags.is_none()
+    }
+
+    /// Apply the patch to `entry`. New content invalidates its embedding.
+    pub fn apply(&self, entry: &mut MemoryEntry) {
+        if let Some(content) = &self.content
+            && *content != entry.content
+        {
+            entry.content = content.clone();
+            entry.embedding = None;
+            entry.embedding_model = None;
+        }
+        if let Some(tags) = &self.tags {
+            entry.tags = tags.clone();
+        }
+    }
+}
+
+/// Which entries [`MemoryBackend::list`], [`MemoryBackend::delete_where`]
+/// and [`MemoryBackend::export`] operate on. Every set criterion must
+/// hold; expired entries never match.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemoryFilter {
+    /// Entries carrying any of these tags (all entries when empty)
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Entries with exactly this source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
+    /// Entries created at or after this time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_after: Option<DateTime<Utc>>,
+
+    /// Entries created before this time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_before: Option<DateTime<Utc>>,
+
+    /// Entries visible to this scope (entries in every scope when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<MemoryScope>,
+}
+
+impl MemoryFilter {
+    /// Whether `entry` matches at `now`.
+    pub fn matches(&self, entry: &MemoryEntry, now: DateTime<Utc>) -> bool {
+        (self.tags.is_empty() || self.tags.iter().any(|t| entry.tags.contains(t)))
+            && self
+                .source
+                .as_ref()
+                .is_none_or(|source| entry.source.as_ref() == Some(source))
+            && self.created_after.is_none_or(|at| entry.created_at >= at)
+            && self.created_before.is_none_or(|at| entry.created_at < at)
+            && self.scope.as_ref().is_none_or(|s| s.allows(&entry.scope))
+            && !entry.is_expired(now)
+    }
+}
+
+/// How memories age.
+///
+/// Ranking multiplies an entry's relevance by
+/// `1 + recency_weight * recency + frequency_weight * frequency`, where
+/// `recency` halves every `half_life_hours` since the last access and
+/// `frequency` grows from 0 towards 1 with the access count. Compaction
+/// forgets expired entries and those whose value (recency times one plus
+/// the access count) has dropped below `forget_below`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecayPolicy {
+    /// Hours after which the recency of an unused entry halves
+    pub half_life_hours: f64,
+
+    /// Weight of recency in ranking (0 disables)
+    pub recency_weight: f32,
+
+    /// Weight of access frequency in ranking (0 disables)
+    pub frequency_weight: f32,
+
+    /// Lifetime given to new entries without an explicit expiry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_hours: Option<f64>,
+
+    /// Value below which compaction forgets an entry (0 keeps everything
+    /// that has not expired)
+    #[serde(default)]
+    pub forget_below: f32,
+}
+
+impl Default for DecayPolicy {
+    fn default() -> Self {
+        Self {
+            half_life_hours: 24.0 * 30.0,
+            recency_weight: 0.3,
+            frequency_weight: 0.2,
+            ttl_hours: None,
+            forget_below: 0.0,
+        }
+    }
+}
+
+impl DecayPolicy {
+    /// Whether ranking differs from plain relevance.
+    pub fn reranks(&self) -> bool {
+        self.recency_weight != 0.0 || self.frequency_weight != 0.0
+    }
+
+    /// 1 for an entry accessed at `now`, halving every half-life since.
+    pub fn recency(&self, last_accessed: DateTime<Utc>, now: DateTime<Utc>) -> f32 {
+        if self.half_life_hours <= 0.0 {
+            return 1.0;
+        }
+        let idle_hours = (now - last_accessed).num_seconds().max(0) as f64 / 3600.0;
+        0.5f64.powf(idle_hours / self.half_life_hours) as f32
+    }
+
+    /// 0 for an entry never recalled, approaching 1 as accesses add up.
+    pub fn frequency(access_count: u32) -> f32 {
+        1.0 - 1.0 / (1.0 + access_count as f32)
+    }
+
+    /// `entry.score` (its relevance) adjusted for recency and frequency.
+    pub fn score(&self, entry: &MemoryEntry, now: DateTime<Utc>) -> f32 {
+        let boost = self.recency_weight * self.recency(entry.last_accessed, now)
+            + self.frequency_weight * Self::frequency(entry.access_count);
+        entry.score * (1.0 + boost)
+    }
+
+    /// Rescore `entries` with [`score`](Self::score) and sort them best
+    /// first. Ties keep their relevance order.
+    pub fn rank(&self, entries: &mut [MemoryEntry], now: DateTime<Utc>) {
+        if !self.reranks() {
+            return;
+        }
+        for entry in entries.iter_mut() {
+            entry.score = self.score(entry, now);
+        }
+        entries.sort_by(|a, b| b.score.total_cmp(&a.score));
+    }
+
+    /// How much an entry is still worth keeping.
+    pub fn value(
+        &self,
+        last_accessed: DateTime<Utc>,
+        access_count: u32,
+        now: DateTime<Utc>,
+    ) -> f32 {
+        self.recency(last_accessed, now) * (1.0 + access_count as f32)
+    }
+
+    /// Whether compaction should forget an entry with these statistics.
+    pub fn is_stale(
+        &self,
+        last_accessed: DateTime<Utc>,
+        access_count: u32,
+        expires_at: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> bool {
+        expires_at.is_some_and(|at| at <= now)
+            || self.value(last_accessed, access_count, now) < self.forget_below
+    }
+
+    /// The expiry to store `entry` with: its own, or `ttl_hours` after it
+    /// was created.
+    pub fn expiry(&self, entry: &MemoryEntry) -> Option<DateTime<Utc>> {
+        entry.expires_at.or_else(|| {
+            self.ttl_hours
+                .map(|hours| entry.created_at + chrono::Duration::seconds((hours * 3600.0) as i64))
+        })
+    }
+}
+
+/// The owner of a memory, along four independent dimensions.
+///
+/// An unset field on an entry means the entry is shared along that
+/// dimension: an entry without a `user_id` is visible to every user. A
+/// query with a field set sees entries with the same value plus shared
+/// ones, so memories of one user are never recalled for another; with the
+/// field unset it sees only shared ones, so an unscoped query sees only
+/// global entries.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MemoryScope {
+    /// Tenant or workspace partition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+
+    /// Agent the memory belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+
+    /// End user (channel sender) the memory is about
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+
+    /// Channel the memory was learned on (e.g. "telegram")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+}
+
+impl MemoryScope {
+    /// Whether no field is set (a global entry, or an unscoped query).
+    pub fn is_empty(&self) -> bool {
+        self.fields().iter().all(|(_, v)| v.is_none())
+    }
+
+    /// The fields as `(column name, value)` pairs, in a fixed order.
+    pub fn fields(&self) -> [(&'static str, Option<&str>); 4] {
+        [
+            ("namespace", self.namespace.as_deref()),
+            ("agent_id", self.agent_id.as_deref()),
+            ("user_id", self.user_id.as_deref()),
+            ("channel", self.channel.as_deref()),
+        ]
+    }
+
+    /// Whether an entry scoped to `entry` is visible to a query scoped to `self`.
+    pub fn allows(&self, entry: &MemoryScope) -> bool {
+        self.fields()
+            .iter()
+            .zip(entry.fields())
+            .all(|((_, want), (_, have))| have.is_none_or(|have| *want == Some(have)))
+    }
+
+    /// `self` with every field set in `other` overriding its own.
+    pub fn merge(&self, other: &MemoryScope) -> MemoryScope {
+        MemoryScope {
+            namespace: other.namespace.clone().or_else(|| self.namespace.clone()),
+            agent_id: other.agent_id.clone().or_else(|| self.agent_id.clone()),
+            user_id: other.user_id.clone().or_else(|| self.user_id.clone()),
+            channel: other.channel.clone().or_else(|| self.channel.clone()),
+        }
+    }
+
+    /// Record the set fields in message metadata, under their own names.
+    pub fn write_metadata(&self, metadata: &mut serde_json::Map<String, serde_json::Value>) {
+        for (key, value) in self.fields() {
+            if let Some(value) = value {
+                metadata.insert(key.into(), value.into());
+            }
+        }
+    }
+
+    /// Scope of the agent run executing on this task; empty outside one.
+    ///
+    /// Tools and sub-agents use it to see only what the run's sender may see.
+    pub fn current() -> MemoryScope {
+        CURRENT_SCOPE
+            .try_with(|scope| scope.clone())
+            .unwrap_or_default()
+    }
+
+    /// Run `fut` with `self` as the [`current`](Self::current) scope.
+    pub async fn enter<F: std::future::Future>(self, fut: F) -> F::Output {
+        CURRENT_SCOPE.scope(self, fut).await
+    }
+
+    /// Scope recorded in message metadata: `namespace`, `agent_id`,
+    /// `user_id` (or a channel's `sender_id`) and `channel`.
+    pub fn from_metadata(metadata: &serde_json::Map<String, serde_json::Value>) -> Self {
+        let get = |key: &str| metadata.get(key).and_then(|v| v.as_str()).map(String::from);
+        MemoryScope {
+            namespace: get("namespace"),
+            agent_id: get("agent_id"),
+            user_id: get("user_id").or_else(|| get("sender_id")),
+            channel: get("channel"),
+        }
+    }
+}
+
+tokio::task_local! {
+    static CURRENT_SCOPE: MemoryScope;
+}
+
 /// How to search the memory.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -91,10 +454,12 @@ pub trait MemoryBackend: Send + Sync {
     /// The backend name (e.g., "sqlite", "postgres", "none").
     fn name(&self) -> &str;
 
-    /// Store a new memory entry.
+    /// Store a memory entry, replacing any existing entry with the same ID.
     async fn store(&self, entry: MemoryEntry) -> std::result::Result<String, MemoryError>;
 
-    /// Search memories by query.
+    /// Search memories by query. Recalls (see [`MemoryQuery::is_recall`])
+    /// are ranked with the backend's [`DecayPolicy`] and count as an access
+    /// of every returned entry.
     async fn search(
         &self,
         query: MemoryQuery,
@@ -103,7 +468,8 @@ pub trait MemoryBackend: Send + Sync {
     /// Delete a memory by ID.
     async fn delete(&self, id: &str) -> std::result::Result<bool, MemoryError>;
 
-    /// Get a memory by ID.
+    /// Get a memory by ID, counting it as an access. Expired entries are
+    /// not returned.
     async fn get(&self, id: &str) -> std::result::Result<Option<MemoryEntry>, MemoryError>;
 
     /// Get total memory count.
@@ -111,6 +477,104 @@ pub trait MemoryBackend: Send + Sync {
 
     /// Clear all memories.
     async fn clear(&self) -> std::result::Result<(), MemoryError>;
+
+    /// Forget expired entries and those the backend's [`DecayPolicy`]
+    /// considers stale. Returns how many were removed.
+    async fn compact(&self) -> std::result::Result<usize, MemoryError>;
+
+    /// Apply `patch` to the entry with `id`, returning the updated entry or
+    /// `None` when there is no such (unexpired) entry. Updates do not count
+    /// as an access.
+    async fn update(
+        &self,
+        id: &str,
+        patch: MemoryPatch,
+    ) -> std::result::Result<Option<MemoryEntry>, MemoryError>;
+
+    /// One page of the entries matching `filter`, newest first, skipping
+    /// `offset` of them. Listing does not count as an access.
+    async fn list(
+        &self,
+        filter: &MemoryFilter,
+        offset: usize,
+        limit: usize,
+    ) -> std::result::Result<Vec<MemoryEntry>, MemoryError>;
+
+    /// Store several entries, returning their IDs in order.
+    async fn store_batch(
+        &self,
+        entries: Vec<MemoryEntry>,
+    ) -> std::result::Result<Vec<String>, MemoryError> {
+        let mut ids = Vec::with_capacity(entries.len());
+        for entry in entries {
+            ids.push(self.store(entry).await?);
+        }
+        Ok(ids)
+    }
+
+    /// Delete every entry matching `filter`. Returns how many were removed.
+    async fn delete_where(&self, filter: &MemoryFilter) -> std::result::Result<usize, MemoryError>;
+
+    /// Write the entries matching `filter` to `out` as JSON lines, one
+    /// [`MemoryEntry`] per line. Returns how many were written.
+    async fn export(
+        &self,
+        filter: &MemoryFilter,
+        out: &mut (dyn AsyncWrite + Send + Unpin),
+    ) -> std::result::Result<usize, MemoryError> {
+        let io_error = |e: std::io::Error| MemoryError::Storage(format!("Export failed: {e}"));
+        let mut exported = 0;
+        loop {
+            let page = self.list(filter, exported, EXPORT_PAGE_SIZE).await?;
+            for entry in &page {
+                let mut line = serde_json::to_vec(entry).map_err(|e| {
+                    MemoryError::Storage(format!("Failed to serialize memory: {e}"))
+                })?;
+                line.push(b'\n');
+                out.write_all(&line).await.map_err(io_error)?;
+            }
+            exported += page.len();
+            if page.len() < EXPORT_PAGE_SIZE {
+                break;
+            }
+        }
+        out.flush().await.map_err(io_error)?;
+        Ok(exported)
+    }
+
+    /// Store every entry read from JSON lines written by
+    /// [`export`](Self::export), keeping their IDs. Returns how many were
+    /// imported.
+    async fn import(
+        &self,
+        input: &mut (dyn AsyncBufRead + Send + Unpin),
+    ) -> std::result::Result<usize, MemoryError> {
+        let mut lines = input.lines();
+        let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+        let mut imported = 0;
+        let mut line_number = 0;
+        while let Some(line) = lines
+            .next_line()
+            .await
+            .map_err(|e| MemoryError::Storage(format!("Import failed: {e}")))?
+        {
+            line_number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str::<MemoryEntry>(&line).map_err(|e| {
+                MemoryError::Storage(format!("Invalid memory on line {line_number}: {e}"))
+            })?;
+            batch.push(entry);
+            if batch.len() == IMPORT_BATCH_SIZE {
+                imported += self.store_batch(std::mem::take(&mut batch)).await?.len();
+            }
+        }
+        if !batch.is_empty() {
+            imported += self.store_batch(batch).await?.len();
+        }
+        Ok(imported)
+    }
 }
 
 #[cfg(test)]
@@ -125,6 +589,8 @@ mod tests {
             min_score: 0.0,
             tags: vec![],
             mode: SearchMode::default(),
+            embedding: None,
+            scope: MemoryScope::default(),
         };
         assert_eq!(query.limit, 10);
         assert!(matches!(query.mode, SearchMode::Hybrid));
@@ -139,11 +605,163 @@ mod tests {
             source: Some("conversation_123".into()),
             created_at: Utc::now(),
             last_accessed: Utc::now(),
+            access_count: 0,
+            expires_at: None,
             score: 0.95,
             embedding: None,
+            embedding_model: None,
+            scope: MemoryScope::default(),
         };
         let json = serde_json::to_string(&entry).unwrap();
         assert!(json.contains("Rust over C++"));
         assert!(json.contains("preference"));
+        assert!(!json.contains("scope"));
+    }
+
+    fn entry(content: &str) -> MemoryEntry {
+        MemoryEntry {
+            id: "mem_001".into(),
+            content: content.into(),
+            tags: vec!["preference".into()],
+            source: Some("chat".into()),
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+            expires_at: None,
+            score: 1.0,
+            embedding: Some(vec![1.0, 0.0]),
+            embedding_model: Some("test-embed".into()),
+            scope: MemoryScope::default(),
+        }
+    }
+
+    #[test]
+    fn patch_drops_embedding_only_when_content_changes() {
+        let mut memory = entry("Prefers tea");
+        let retag = MemoryPatch {
+            content: Some("Prefers tea".into()),
+            tags: Some(vec!["drink".into()]),
+        };
+        retag.apply(&mut memory);
+        assert_eq!(memory.tags, ["drink"]);
+        assert!(memory.embedding.is_some());
+
+        MemoryPatch {
+            content: Some("Prefers coffee".into()),
+            ..MemoryPatch::default()
+        }
+        .apply(&mut memory);
+        assert_eq!(memory.content, "Prefers coffee");
+        assert_eq!(memory.tags, ["drink"]);
+        assert!(memory.embedding.is_none() && memory.embedding_model.is_none());
+    }
+
+    #[test]
+    fn filter_requires_every_criterion() {
+        let now = Utc::now();
+        let memory = entry("Prefers tea");
+        assert!(MemoryFilter::default().matches(&memory, now));
+
+        let filter = MemoryFilter {
+            tags: vec!["other".into(), "preference".into()],
+            source: Some("chat".into()),
+            created_after: Some(now - chrono::Duration::hours(1)),
+            created_before: Some(now + chrono::Duration::hours(1)),
+            scope: Some(MemoryScope::default()),
+        };
+        assert!(filter.matches(&memory, now));
+        for miss in [
+            MemoryFilter {
+                tags: vec!["other".into()],
+                ..filter.clone()
+            },
+            MemoryFilter {
+                source: Some("import".into()),
+                ..filter.clone()
+            },
+            MemoryFilter {
+                created_before: Some(memory.created_at),
+                ..filter.clone()
+            },
+        ] {
+            assert!(!miss.matches(&memory, now), "{miss:?}");
+        }
+
+        let mut scoped = memory.clone();
+        scoped.scope.user_id = Some("alice".into());
+        assert!(!filter.matches(&scoped, now));
+        assert!(
+            MemoryFilter {
+                scope: None,
+                ..filter.clone()
+            }
+            .matches(&scoped, now),
+            "no scope filter matches every scope"
+        );
+
+        let mut expired = memory;
+        expired.expires_at = Some(now);
+        assert!(!filter.matches(&expired, now));
+    }
+
+    #[test]
+    fn scope_hides_other_owners_but_not_shared_entries() {
+        let alice = MemoryScope {
+            user_id: Some("alice".into()),
+            channel: Some("telegram".into()),
+            ..Default::default()
+        };
+        let bob = MemoryScope {
+            user_id: Some("bob".into()),
+            ..Default::default()
+        };
+        let shared = MemoryScope::default();
+
+        assert!(alice.allows(&alice));
+        assert!(!alice.allows(&bob));
+        assert!(alice.allows(&shared));
+        assert!(shared.allows(&shared));
+        assert!(
+            !shared.allows(&bob),
+            "an unscoped query sees only shared entries"
+        );
+        let alice_anywhere = MemoryScope {
+            user_id: Some("alice".into()),
+            ..Default::default()
+        };
+        assert!(alice.allows(&alice_anywhere));
+        assert!(
+            !alice_anywhere.allows(&alice),
+            "an unset field matches only unset"
+        );
+
+        let metadata = serde_json::json!({ "sender_id": "alice", "channel": "telegram" });
+        let from_message = MemoryScope::from_metadata(metadata.as_object().unwrap());
+        assert_eq!(from_message, alice);
+
+        let agent = MemoryScope {
+            agent_id: Some("helper".into()),
+            user_id: Some("nobody".into()),
+            ..Default::default()
+        };
+        let merged = agent.merge(&alice);
+        assert_eq!(merged.agent_id.as_deref(), Some("helper"));
+        assert_eq!(merged.user_id.as_deref(), Some("alice"));
+
+        let mut metadata = serde_json::Map::new();
+        merged.write_metadata(&mut metadata);
+        assert_eq!(MemoryScope::from_metadata(&metadata), merged);
+    }
+
+    #[tokio::test]
+    async fn current_scope_is_task_local() {
+        assert!(MemoryScope::current().is_empty());
+        let alice = MemoryScope {
+            user_id: Some("alice".into()),
+            ..Default::default()
+        };
+        let seen = alice.clone().enter(async { MemoryScope::current() }).await;
+        assert_eq!(seen, alice);
+        assert!(MemoryScope::current().is_empty());
     }
 }
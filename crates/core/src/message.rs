@@ -7,6 +7,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::tokens::{HeuristicCounter, TokenCounter};
+
 /// Unique identifier for a conversation (session).
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ConversationId(pub String);
@@ -164,6 +166,26 @@ pub struct Conversation {
     /// Conversation-level metadata
     #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
     pub metadata: serde_json::Map<String, serde_json::Value>,
+
+    /// Rolling summary of turns compressed out of the history window
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<ConversationSummary>,
+}
+
+/// A rolling LLM-written summary of the oldest turns of a conversation.
+///
+/// The first `covered_messages` entries of [`Conversation::messages`] are
+/// represented by `text` and are no longer sent to the model verbatim.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    /// The summary text
+    pub text: String,
+
+    /// Number of leading messages folded into the summary
+    pub covered_messages: usize,
+
+    /// When the summary was last refreshed
+    pub updated_at: DateTime<Utc>,
 }
 
 impl Conversation {
@@ -177,6 +199,7 @@ impl Conversation {
             updated_at: now,
             title: None,
             metadata: serde_json::Map::new(),
+            summary: None,
         }
     }
 
@@ -186,9 +209,23 @@ impl Conversation {
         self.messages.push(message);
     }
 
-    /// Get the total token count estimate (rough: 4 chars ≈ 1 token).
+    /// Messages not yet folded into the rolling summary.
+    pub fn unsummarized(&self) -> &[Message] {
+        let covered = self.summary.as_ref().map_or(0, |s| s.covered_messages);
+        &self.messages[covered.min(self.messages.len())..]
+    }
+
+    /// Get the total token count estimate (rough: 4 bytes ≈ 1 token).
     pub fn estimated_tokens(&self) -> usize {
-        self.messages.iter().map(|m| m.content.len() / 4).sum()
+        self.token_count(&HeuristicCounter)
+    }
+
+    /// Count message content tokens with a model-specific counter.
+    pub fn token_count(&self, counter: &dyn TokenCounter) -> usize {
+        self.messages
+            .iter()
+            .map(|m| counter.count(&m.content))
+            .sum()
     }
 }
 
@@ -0,0 +1,147 @@
+//! Model capabilities.
+//!
+//! A static table of what each known model family supports: context window,
+//! maximum output, vision and tool calling. Context assembly sizes its
+//! token budget from [`ModelCapabilities::context_window`], so a 200k-token
+//! model is not starved and a 2k-token local model does not overflow.
+//!
+//! Lookups match the longest known prefix of the model name after any
+//! router prefix (`anthropic/`, `openai/`, ...); unknown models get
+//! [`ModelCapabilities::FALLBACK`].
+
+use serde::{Deserialize, Serialize};
+
+/// What a model supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelCapabilities {
+    /// Total context window in tokens (prompt + completion).
+    pub context_window: usize,
+    /// Maximum completion tokens per response.
+    pub max_output: usize,
+    /// Accepts image inputs.
+    pub vision: bool,
+    /// Supports native tool/function calling.
+    pub tools: bool,
+}
+
+const fn caps(
+    context_window: usize,
+    max_output: usize,
+    vision: bool,
+    tools: bool,
+) -> ModelCapabilities {
+    ModelCapabilities {
+        context_window,
+        max_output,
+        vision,
+        tools,
+    }
+}
+
+/// Known model families, matched by prefix.
+const KNOWN_MODELS: &[(&str, ModelCapabilities)] = &[
+    // Anthropic
+    ("claude-3-haiku", caps(200_000, 4_096, true, true)),
+    ("claude-3-opus", caps(200_000, 4_096, true, true)),
+    ("claude-3-5", caps(200_000, 8_192, true, true)),
+    ("claude-3-7", caps(200_000, 64_000, true, true)),
+    ("claude-sonnet-4", caps(200_000, 64_000, true, true)),
+    ("claude-opus-4", caps(200_000, 32_000, true, true)),
+    ("claude", caps(200_000, 8_192, true, true)),
+    // OpenAI
+    ("gpt-4o", caps(128_000, 16_384, true, true)),
+    ("chatgpt-4o", caps(128_000, 16_384, true, true)),
+    ("gpt-4.1", caps(1_047_576, 32_768, true, true)),
+    ("gpt-4.5", caps(128_000, 16_384, true, true)),
+    ("gpt-4-turbo", caps(128_000, 4_096, true, true)),
+    ("gpt-4", caps(8_192, 8_192, false, true)),
+    ("gpt-3.5-turbo", caps(16_385, 4_096, false, true)),
+    ("gpt-5", caps(400_000, 128_000, true, true)),
+    ("o1", caps(200_000, 100_000, true, true)),
+    ("o3", caps(200_000, 100_000, true, true)),
+    ("o4", caps(200_000, 100_000, true, true)),
+    // Google
+    ("gemini-1.5", caps(1_048_576, 8_192, true, true)),
+    ("gemini-2", caps(1_048_576, 8_192, true, true)),
+    // Open-weight models, hosted or local
+    ("llama-3.1", caps(131_072, 4_096, false, true)),
+    ("llama-3.2", caps(131_072, 4_096, false, true)),
+    ("llama-3.3", caps(131_072, 4_096, false, true)),
+    ("llama-3", caps(8_192, 4_096, false, false)),
+    ("meta-llama-3.1", caps(131_072, 4_096, false, true)),
+    ("mistral", caps(32_768, 4_096, false, true)),
+    ("mixtral", caps(32_768, 4_096, false, true)),
+    ("deepseek", caps(65_536, 8_192, false, true)),
+    ("qwen", caps(32_768, 4_096, false, true)),
+    ("gemma", caps(8_192, 4_096, false, false)),
+    // Bundled local presets
+    ("tinyllama", caps(2_048, 512, false, false)),
+    ("tiny-llama", caps(2_048, 512, false, false)),
+    ("smollm", caps(2_048, 512, false, false)),
+    ("phi2", caps(2_048, 512, false, false)),
+    ("phi-2", caps(2_048, 512, false, false)),
+];
+
+impl ModelCapabilities {
+    /// Assumed for models missing from the table: 8k window, 4k output.
+    pub const FALLBACK: Self = caps(8_192, 4_096, false, true);
+
+    /// Capabilities of `model`, or [`FALLBACK`](Self::FALLBACK) if unknown.
+    pub fn for_model(model: &str) -> Self {
+        Self::lookup(model).unwrap_or(Self::FALLBACK)
+    }
+
+    /// Capabilities of `model` if it is in the table.
+    pub fn lookup(model: &str) -> Option<Self> {
+        let model = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+        KNOWN_MODELS
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, caps)| *caps)
+    }
+
+    /// Override the context window (e.g. from configuration).
+    pub fn with_context_window(mut self, tokens: usize) -> Self {
+        self.context_window = tokens;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn longest_prefix_wins() {
+        assert_eq!(
+            ModelCapabilities::for_model("anthropic/claude-sonnet-4").max_output,
+            64_000
+        );
+        assert_eq!(
+            ModelCapabilities::for_model("claude-2.1").context_window,
+            200_000
+        );
+        assert_eq!(
+            ModelCapabilities::for_model("gpt-4o-mini").context_window,
+            128_000
+        );
+        assert_eq!(
+            ModelCapabilities::for_model("gpt-4-0613").context_window,
+            8_192
+        );
+    }
+
+    #[test]
+    fn unknown_models_fall_back() {
+        assert_eq!(ModelCapabilities::lookup("mock-model"), None);
+        assert_eq!(
+            ModelCapabilities::for_model("mock-model"),
+            ModelCapabilities::FALLBACK
+        );
+        assert_eq!(
+            ModelCapabilities::for_model("TinyLlama").context_window,
+            2_048
+        );
+    }
+}
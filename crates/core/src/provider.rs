@@ -7,6 +7,8 @@
 
 use crate::error::ProviderError;
 use crate::message::{Message, MessageToolCall};
+use crate::model::ModelCapabilities;
+use crate::tokens::TokenCounter;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
@@ -186,6 +188,22 @@ pub trait Provider: Send + Sync {
     async fn health_check(&self) -> std::result::Result<bool, ProviderError> {
         Ok(true)
     }
+
+    /// The provider's own tokenizer, if it has one.
+    ///
+    /// Remote APIs return `None` and callers pick a counter by model name;
+    /// in-process models return their real tokenizer.
+    fn token_counter(&self) -> Option<std::sync::Arc<dyn TokenCounter>> {
+        None
+    }
+
+    /// Context window and feature support for `model`.
+    ///
+    /// Defaults to the built-in table; providers that know better (e.g. a
+    /// local model loaded with a custom context size) override this.
+    fn capabilities(&self, model: &str) -> ModelCapabilities {
+        ModelCapabilities::for_model(model)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,60 @@
+//! Token counting.
+//!
+//! A [`TokenCounter`] turns text into a token count for a specific model
+//! family. Implementations live in their respective crates (BPE encodings
+//! in `rustedclaw-agent`, the local model's tokenizer in
+//! `rustedclaw-providers`); this module defines the trait and the
+//! [`HeuristicCounter`] fallback used when no real tokenizer is available.
+
+use crate::message::Message;
+
+/// Counts tokens the way a particular model's tokenizer would.
+pub trait TokenCounter: Send + Sync {
+    /// Counter name (e.g. "cl100k_base", "heuristic").
+    fn name(&self) -> &str;
+
+    /// Number of tokens in `text`.
+    fn count(&self, text: &str) -> usize;
+
+    /// Tokens for a message, including ~4 tokens of per-message overhead
+    /// for the role name and delimiters in the API wire format, plus the
+    /// name and arguments of any tool calls it carries.
+    fn count_message(&self, message: &Message) -> usize {
+        let calls: usize = message
+            .tool_calls
+            .iter()
+            .map(|c| self.count(&c.name) + self.count(&c.arguments))
+            .sum();
+        4 + self.count(&message.content) + calls
+    }
+}
+
+/// Length-based fallback: 1 token ≈ 4 bytes of UTF-8, rounded up.
+///
+/// Within ~10% for BPE tokenizers on English prose, but undercounts code,
+/// CJK text and JSON.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicCounter;
+
+impl TokenCounter for HeuristicCounter {
+    fn name(&self) -> &str {
+        "heuristic"
+    }
+
+    fn count(&self, text: &str) -> usize {
+        text.len().div_ceil(4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heuristic_rounds_up() {
+        assert_eq!(HeuristicCounter.count(""), 0);
+        assert_eq!(HeuristicCounter.count("test"), 1);
+        assert_eq!(HeuristicCounter.count("hello"), 2);
+        assert_eq!(HeuristicCounter.count_message(&Message::user("test")), 5);
+    }
+}
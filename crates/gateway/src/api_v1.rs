@@ -4,25 +4,28 @@
 //!
 //! - `POST /v1/chat`               — Send a message, get a response
 //! - `POST /v1/chat/stream`        — Send a message, get SSE stream
+//! - `POST /v1/runs/:id/resume`    — Resume an interrupted ReAct run
 //! - `GET  /v1/ws`                 — WebSocket for bidirectional streaming
 //! - `GET  /v1/logs`               — SSE log stream
-//! - `GET  /v1/conversations`      — List conversations
+//! - `GET  /v1/conversations`      — List (paginated) or search conversations
 //! - `POST /v1/conversations`      — Create a conversation
 //! - `GET  /v1/conversations/:id`  — Get a specific conversation
+//! - `PATCH /v1/conversations/:id` — Set a conversation's title
+//! - `DELETE /v1/conversations/:id` — Delete a conversation
+//! - `GET  /v1/conversations/:id/export` — Export as JSON or Markdown
 //! - `GET  /v1/tools`              — List available tools
 //! - `POST /v1/context/debug`      — Context assembly debug view
 
 use axum::{
     Router,
     extract::ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::sse::{Event as SseEvent, Sse},
     response::{IntoResponse, Json},
     routing::{get, post},
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::convert::Infallible;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -30,27 +33,34 @@ use tokio_stream::StreamExt;
 use tokio_stream::wrappers::ReceiverStream;
 use tracing::info;
 
+use rustedclaw_agent::context::token;
 use rustedclaw_agent::{
-    AgentStreamEvent, AssemblyInput, ContextAssembler, KnowledgeChunk, ReactAgent, TokenBudget,
-    WorkingMemory,
+    AgentRuntime, AgentStreamEvent, AssemblyInput, CheckpointStore, ContextAssembler,
+    ConversationSummarizer, DateTimeLayer, FactExtractor, KnowledgeChunk, RunInfo, RunRegistry,
+    RuntimeResult, Strategy, TokenBudget, ToolRanker, WorkingMemory, validate_run_id,
 };
 use rustedclaw_contracts::ContractEngine;
+use rustedclaw_core::conversation_store::{ConversationInfo, ConversationStore, ExportFormat};
 use rustedclaw_core::event::EventBus;
 use rustedclaw_core::identity::Identity;
-use rustedclaw_core::memory::MemoryEntry;
+use rustedclaw_core::memory::{MemoryBackend, MemoryEntry, MemoryFilter, MemoryPatch, MemoryScope};
 use rustedclaw_core::message::{Conversation, ConversationId, Message};
+use rustedclaw_core::model::ModelCapabilities;
 use rustedclaw_core::provider::Provider;
 use rustedclaw_core::tool::ToolRegistry;
+use rustedclaw_memory::ingest::{
+    DOCUMENT_TAG, Document, DocumentFormat, DocumentIngestor, metadata_tags,
+};
 use rustedclaw_telemetry::TelemetryEngine;
 
 // ── State ─────────────────────────────────────────────────────────────────
 
-/// Maximum number of in-memory conversations before oldest are evicted.
-const MAX_CONVERSATIONS: usize = 1_000;
-/// Maximum number of in-memory memory entries.
-const MAX_MEMORIES: usize = 10_000;
-/// Maximum number of in-memory document entries.
-const MAX_DOCUMENTS: usize = 5_000;
+/// Default page size for `GET /v1/conversations`.
+const DEFAULT_CONVERSATION_PAGE: usize = 50;
+/// Largest page `GET /v1/conversations` will return.
+const MAX_CONVERSATION_PAGE: usize = 500;
+/// Maximum number of entries returned by the memory listing endpoints.
+const MEMORY_LIST_LIMIT: usize = 10_000;
 
 /// Shared state for the v1 API.
 pub struct ApiV1State {
@@ -62,12 +72,19 @@ pub struct ApiV1State {
     pub event_bus: Arc<EventBus>,
     pub contracts: Arc<ContractEngine>,
     pub telemetry: Arc<TelemetryEngine>,
-    pub conversations: RwLock<HashMap<String, Conversation>>,
+    /// Persistent conversation history.
+    pub conversations: Arc<dyn ConversationStore>,
+    /// Checkpoints of in-flight ReAct runs, for resumption after restarts.
+    pub checkpoints: Arc<dyn CheckpointStore>,
+    /// In-flight runs, cancellable via `DELETE /v1/runs/:id`.
+    pub runs: Arc<RunRegistry>,
     pub workflow: Option<Arc<rustedclaw_workflow::WorkflowEngine>>,
     pub config: RwLock<rustedclaw_config::AppConfig>,
     pub start_time: chrono::DateTime<chrono::Utc>,
-    pub memories: RwLock<Vec<MemoryEntry>>,
-    pub documents: RwLock<Vec<DocumentEntry>>,
+    /// The configured memory backend, shared with agents and the
+    /// `memory_search` tool. Ingested documents are stored here too,
+    /// tagged `document`.
+    pub memory: Arc<dyn MemoryBackend>,
     pub jobs: RwLock<Vec<JobEntry>>,
     /// Bearer tokens for API authentication.
     pub bearer_tokens: RwLock<Vec<String>>,
@@ -82,11 +99,23 @@ pub fn v1_router(state: SharedApiState) -> Router {
     Router::new()
         .route("/chat", post(chat_handler))
         .route("/chat/stream", post(chat_stream_handler))
+        .route("/runs", get(list_runs_handler))
+        .route("/runs/{id}", axum::routing::delete(cancel_run_handler))
+        .route("/runs/{id}/resume", post(resume_run_handler))
         .route("/ws", get(ws_handler))
         .route("/logs", get(log_stream_handler))
         .route("/conversations", get(list_conversations_handler))
         .route("/conversations", post(create_conversation_handler))
-        .route("/conversations/{id}", get(get_conversation_handler))
+        .route(
+            "/conversations/{id}",
+            get(get_conversation_handler)
+                .patch(update_conversation_handler)
+                .delete(delete_conversation_handler),
+        )
+        .route(
+            "/conversations/{id}/export",
+            get(export_conversation_handler),
+        )
         .route("/tools", get(list_tools_handler))
         .route("/tools/install", post(install_tool_handler))
         .route("/context/debug", post(context_debug_handler))
@@ -98,9 +127,14 @@ pub fn v1_router(state: SharedApiState) -> Router {
             axum::routing::delete(delete_routine_handler),
         )
         .route("/documents", post(ingest_document_handler))
+        .route(
+            "/documents/{id}",
+            axum::routing::delete(delete_document_handler),
+        )
         .route("/memory", get(search_memory_handler))
         .route("/memory", post(create_memory_handler))
         .route("/memory/agent/{agent_id}", get(list_agent_memory_handler))
+        .route("/memory/{id}", axum::routing::patch(update_memory_handler))
         .route("/memory/{id}", axum::routing::delete(delete_memory_handler))
         .route("/jobs", get(list_jobs_handler))
         .route("/jobs/{id}", get(get_job_handler))
@@ -136,18 +170,35 @@ struct ChatRequest {
     conversation_id: Option<String>,
     /// The user's message.
     message: String,
-    /// Which agent pattern to use: "react" (default), "rag", "direct".
+    /// Which agent pattern to use: "react" (default), "rag", "reflect", "direct".
     #[serde(default = "default_pattern")]
     pattern: String,
+    /// Run ID for checkpointing (omit to generate one). Must be new: IDs of
+    /// running or resumable runs are rejected with `409 Conflict`.
+    #[serde(default)]
+    run_id: Option<String>,
+    /// Who is talking (`user_id`, `channel`, ...); memory recall is limited to it.
+    #[serde(flatten)]
+    scope: MemoryScope,
 }
 
 fn default_pattern() -> String {
     "react".into()
 }
 
+/// The incoming user message, tagged with the caller's memory scope.
+fn user_message(content: &str, scope: &MemoryScope) -> Message {
+    let mut message = Message::user(content);
+    scope.write_metadata(&mut message.metadata);
+    message
+}
+
 #[derive(Serialize)]
 struct ChatResponse {
     conversation_id: String,
+    /// Run ID of a checkpointed ReAct run (resumable via `/v1/runs/{id}/resume`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run_id: Option<String>,
     response: String,
     pattern: String,
     iterations: usize,
@@ -169,6 +220,9 @@ struct ContextMetadataDto {
     utilization_pct: f32,
     layers: Vec<LayerStatsDto>,
     drops: Vec<DropInfoDto>,
+    /// Tools left out of the request, most relevant first.
+    #[serde(default)]
+    hidden_tools: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -186,9 +240,23 @@ struct DropInfoDto {
     reason: String,
 }
 
+#[derive(Deserialize)]
+struct ConversationListQuery {
+    #[serde(default)]
+    offset: Option<usize>,
+    #[serde(default)]
+    limit: Option<usize>,
+    /// Search text; matches titles and message content.
+    #[serde(default)]
+    q: Option<String>,
+}
+
 #[derive(Serialize, Deserialize)]
 struct ConversationListResponse {
     conversations: Vec<ConversationSummaryDto>,
+    total: usize,
+    offset: usize,
+    limit: usize,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -198,6 +266,38 @@ struct ConversationSummaryDto {
     created_at: String,
     updated_at: String,
     title: Option<String>,
+    #[serde(default)]
+    preview: Option<String>,
+}
+
+impl From<ConversationInfo> for ConversationSummaryDto {
+    fn from(info: ConversationInfo) -> Self {
+        Self {
+            id: info.id,
+            message_count: info.message_count,
+            created_at: info.created_at.to_rfc3339(),
+            updated_at: info.updated_at.to_rfc3339(),
+            title: info.title,
+            preview: info.preview,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CreateConversationRequest {
+    #[serde(default)]
+    title: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UpdateConversationRequest {
+    title: String,
+}
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    #[serde(default)]
+    format: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -243,12 +343,9 @@ struct ContextDebugRequest {
     memories: Vec<MemoryDto>,
     #[serde(default)]
     knowledge_chunks: Vec<KnowledgeChunkDto>,
-    #[serde(default = "default_budget")]
-    budget: usize,
-}
-
-fn default_budget() -> usize {
-    4096
+    /// Total budget override; sized from the model's context window if unset.
+    #[serde(default)]
+    budget: Option<usize>,
 }
 
 #[derive(Deserialize)]
@@ -271,9 +368,23 @@ struct ContextDebugResponse {
     system_message: String,
     messages: Vec<MessageDto>,
     tool_definitions: Vec<String>,
+    model: String,
+    capabilities: ModelCapabilities,
+    layer_budgets: LayerBudgetDto,
     metadata: ContextMetadataDto,
 }
 
+/// Per-layer token caps in effect (`None` = whatever remains).
+#[derive(Serialize, Deserialize)]
+struct LayerBudgetDto {
+    long_term_memory: Option<usize>,
+    working_memory: Option<usize>,
+    knowledge: Option<usize>,
+    tool_schemas: Option<usize>,
+    conversation_history: Option<usize>,
+    tool_output: Option<usize>,
+}
+
 #[derive(Serialize)]
 struct ErrorResponse {
     error: String,
@@ -281,199 +392,332 @@ struct ErrorResponse {
 
 // ── Handlers ──────────────────────────────────────────────────────────────
 
+/// Create a conversation whose ID matches the key it is stored under.
+fn new_conversation(id: &str) -> Conversation {
+    let mut conv = Conversation::new();
+    conv.id = ConversationId::from(id);
+    conv
+}
+
+/// Load conversation `id` from the store, or start a new one under that ID.
+async fn load_or_new_conversation(
+    state: &ApiV1State,
+    id: &str,
+) -> Result<Conversation, rustedclaw_core::error::MemoryError> {
+    Ok(state
+        .conversations
+        .load(id)
+        .await?
+        .unwrap_or_else(|| new_conversation(id)))
+}
+
+/// Map a conversation store failure to `500 Internal Server Error`.
+fn store_error(e: rustedclaw_core::error::MemoryError) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            error: format!("Conversation store error: {e}"),
+        }),
+    )
+}
+
+/// Build an [`AgentRuntime`] wired to the gateway's shared state.
+///
+/// Every chat entry point (blocking, SSE, WebSocket, resume) goes through
+/// this so telemetry, budgets, contracts, checkpoints, cancellation and
+/// history summarization apply uniformly.
+async fn agent_runtime(state: &ApiV1State, strategy: Strategy) -> AgentRuntime {
+    let mut runtime = AgentRuntime::new(
+        state.provider.clone(),
+        &state.model,
+        state.temperature,
+        state.tools.clone(),
+        state.identity.clone(),
+        state.event_bus.clone(),
+    )
+    .with_strategy(strategy)
+    .with_telemetry(state.telemetry.clone())
+    .with_contracts(state.contracts.clone())
+    .with_checkpoints(state.checkpoints.clone())
+    .with_runs(state.runs.clone());
+
+    let (context, max_tokens, memory) = {
+        let config = state.config.read().await;
+        (
+            config.context.clone(),
+            config.default_max_tokens,
+            config.memory.clone(),
+        )
+    };
+    runtime = runtime.with_budget(TokenBudget::from_config(
+        &state.provider.capabilities(&state.model),
+        Some(max_tokens),
+        &context,
+    ));
+    runtime = runtime.with_tool_ranker(ToolRanker::from_config(&context, state.provider.clone()));
+    runtime = runtime
+        .with_memory(state.memory.clone())
+        .with_auto_save(memory.auto_save);
+    if let Some(extractor) =
+        FactExtractor::from_config(&memory, state.provider.clone(), &state.model)
+    {
+        runtime = runtime.with_fact_extractor(extractor);
+    }
+    if context.current_time {
+        runtime = runtime.with_context_layer(Arc::new(DateTimeLayer::new()));
+    }
+    if context.summarize_history {
+        runtime = runtime.with_summarizer(
+            ConversationSummarizer::new(state.provider.clone(), &state.model)
+                .with_max_tokens(context.summary_max_tokens),
+        );
+    }
+    runtime
+}
+
+/// Parse a request pattern, rejecting unknown names with `400 Bad Request`.
+fn parse_strategy(pattern: &str) -> Result<Strategy, (StatusCode, Json<ErrorResponse>)> {
+    Strategy::from_pattern(pattern).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: format!(
+                    "Unknown pattern: '{}'. Use 'react', 'rag', 'reflect', or 'direct'.",
+                    pattern
+                ),
+            }),
+        )
+    })
+}
+
+/// Check a client-supplied run ID before starting a run: `400 Bad Request`
+/// if it can't key a checkpoint, `409 Conflict` if a run with that ID is in
+/// flight or has a checkpoint waiting to be resumed.
+async fn check_run_id(
+    state: &ApiV1State,
+    run_id: &str,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let error = |status, error| (status, Json(ErrorResponse { error }));
+    validate_run_id(run_id).map_err(|e| error(StatusCode::BAD_REQUEST, e.to_string()))?;
+    if state.runs.is_running(run_id) {
+        return Err(error(
+            StatusCode::CONFLICT,
+            format!("Run '{}' is already running", run_id),
+        ));
+    }
+    match state.checkpoints.load(run_id).await {
+        Ok(None) => Ok(()),
+        Ok(Some(_)) => Err(error(
+            StatusCode::CONFLICT,
+            format!(
+                "Run '{}' has a checkpoint; resume it with POST /v1/runs/{}/resume",
+                run_id, run_id
+            ),
+        )),
+        Err(e) => Err(error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load checkpoint: {}", e),
+        )),
+    }
+}
+
+fn chat_response(conversation_id: String, pattern: String, result: RuntimeResult) -> ChatResponse {
+    let trace: Vec<TraceEntryDto> = result
+        .trace
+        .iter()
+        .map(|t| TraceEntryDto {
+            kind: format!("{:?}", t.kind),
+            content: t.content.clone(),
+        })
+        .collect();
+
+    ChatResponse {
+        conversation_id,
+        run_id: result.run_id,
+        response: result.answer,
+        pattern,
+        iterations: result.iterations,
+        tool_calls: result.tool_calls_made,
+        trace,
+        context_metadata: result.context_metadata.map(context_metadata_dto),
+    }
+}
+
 async fn chat_handler(
     State(state): State<SharedApiState>,
     Json(payload): Json<ChatRequest>,
 ) -> Result<Json<ChatResponse>, (StatusCode, Json<ErrorResponse>)> {
     info!(pattern = %payload.pattern, "v1/chat request");
 
+    let strategy = parse_strategy(&payload.pattern)?;
+    if let Some(run_id) = &payload.run_id {
+        check_run_id(&state, run_id).await?;
+    }
+
     // Get or create conversation.
     let conv_id = payload
         .conversation_id
         .unwrap_or_else(|| ConversationId::new().to_string());
 
-    let mut conversations = state.conversations.write().await;
+    let mut conv = load_or_new_conversation(&state, &conv_id)
+        .await
+        .map_err(store_error)?;
+    conv.push(user_message(&payload.message, &payload.scope));
+
+    let runtime = agent_runtime(&state, strategy).await.with_run_id(
+        payload
+            .run_id
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+    );
+    let result = runtime
+        .run(&payload.message, &mut conv)
+        .await
+        .map_err(agent_error)?;
+
+    // Store updated conversation back.
+    state.conversations.save(&conv).await.map_err(store_error)?;
+
+    Ok(Json(chat_response(conv_id, payload.pattern, result)))
+}
 
-    // Evict oldest conversation if at capacity
-    if conversations.len() >= MAX_CONVERSATIONS
-        && !conversations.contains_key(&conv_id)
-        && let Some(oldest_key) = conversations
+fn context_metadata_dto(m: rustedclaw_agent::AssemblyMetadata) -> ContextMetadataDto {
+    ContextMetadataDto {
+        total_tokens: m.total_tokens,
+        budget: m.budget,
+        utilization_pct: m.utilization_pct,
+        layers: m
+            .per_layer
             .iter()
-            .min_by_key(|(_, c)| c.created_at)
-            .map(|(k, _)| k.clone())
-    {
-        conversations.remove(&oldest_key);
+            .map(|l| LayerStatsDto {
+                name: l.name.clone(),
+                tokens: l.tokens,
+                items_included: l.items_included,
+                items_total: l.items_total,
+            })
+            .collect(),
+        drops: m
+            .drops
+            .iter()
+            .map(|d| DropInfoDto {
+                layer: d.layer.clone(),
+                items_dropped: d.items_dropped,
+                reason: d.reason.clone(),
+            })
+            .collect(),
+        hidden_tools: m.hidden_tools,
     }
+}
 
-    let conv = conversations
-        .entry(conv_id.clone())
-        .or_insert_with(Conversation::new);
+/// `POST /v1/runs/:id/resume` — Continue a checkpointed ReAct run.
+///
+/// Picks up an interrupted run (e.g. after a gateway restart) from its
+/// last checkpoint and returns the same payload as `/v1/chat`.
+async fn resume_run_handler(
+    State(state): State<SharedApiState>,
+    Path(run_id): Path<String>,
+) -> Result<Json<ChatResponse>, (StatusCode, Json<ErrorResponse>)> {
+    info!(run_id = %run_id, "v1/runs resume request");
 
-    conv.push(Message::user(&payload.message));
+    if state.runs.is_running(&run_id) {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: format!("Run '{}' is already running", run_id),
+            }),
+        ));
+    }
 
-    // Execute with the requested pattern.
-    match payload.pattern.as_str() {
-        "react" | "direct" => {
-            let agent = ReactAgent::new(
-                state.provider.clone(),
-                &state.model,
-                state.temperature,
-                state.tools.clone(),
-                state.identity.clone(),
-                state.event_bus.clone(),
+    let checkpoint = state
+        .checkpoints
+        .load(&run_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: format!("Failed to load checkpoint: {}", e),
+                }),
             )
-            .with_telemetry(state.telemetry.clone());
-
-            // Release the lock before the async LLM call.
-            // We need to clone the conversation for the agent.
-            let mut conv_clone = conv.clone();
-            drop(conversations);
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: format!("No checkpoint for run '{}'", run_id),
+                }),
+            )
+        })?;
 
-            let result = agent
-                .run(&payload.message, &mut conv_clone, &[], &[])
-                .await
-                .map_err(|e| {
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(ErrorResponse {
-                            error: format!("Agent error: {}", e),
-                        }),
-                    )
-                })?;
-
-            // Store updated conversation back.
-            let mut conversations = state.conversations.write().await;
-            conversations.insert(conv_id.clone(), conv_clone);
-
-            let trace: Vec<TraceEntryDto> = result
-                .trace
-                .iter()
-                .map(|t| TraceEntryDto {
-                    kind: format!("{:?}", t.kind),
-                    content: t.content.clone(),
-                })
-                .collect();
-
-            let context_metadata = result.last_context_metadata.map(|m| ContextMetadataDto {
-                total_tokens: m.total_tokens,
-                budget: m.budget,
-                utilization_pct: m.utilization_pct,
-                layers: m
-                    .per_layer
-                    .iter()
-                    .map(|l| LayerStatsDto {
-                        name: l.name.clone(),
-                        tokens: l.tokens,
-                        items_included: l.items_included,
-                        items_total: l.items_total,
-                    })
-                    .collect(),
-                drops: m
-                    .drops
-                    .iter()
-                    .map(|d| DropInfoDto {
-                        layer: d.layer.clone(),
-                        items_dropped: d.items_dropped,
-                        reason: d.reason.clone(),
-                    })
-                    .collect(),
-            });
-
-            Ok(Json(ChatResponse {
-                conversation_id: conv_id,
-                response: result.answer,
-                pattern: payload.pattern,
-                iterations: result.iterations,
-                tool_calls: result.tool_calls_made,
-                trace,
-                context_metadata,
-            }))
-        }
-        "rag" => {
-            let agent = rustedclaw_agent::RagAgent::new(
-                state.provider.clone(),
-                &state.model,
-                state.temperature,
-                state.tools.clone(),
-                state.identity.clone(),
-                state.event_bus.clone(),
-            );
-
-            let mut conv_clone = conv.clone();
-            drop(conversations);
-
-            let result = agent
-                .run(&payload.message, &mut conv_clone, &[])
-                .await
-                .map_err(|e| {
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(ErrorResponse {
-                            error: format!("RAG agent error: {}", e),
-                        }),
-                    )
-                })?;
-
-            let mut conversations = state.conversations.write().await;
-            conversations.insert(conv_id.clone(), conv_clone);
-
-            let trace: Vec<TraceEntryDto> = result
-                .working_memory
-                .trace
-                .iter()
-                .map(|t| TraceEntryDto {
-                    kind: format!("{:?}", t.kind),
-                    content: t.content.clone(),
-                })
-                .collect();
-
-            let context_metadata = result.context_metadata.map(|m| ContextMetadataDto {
-                total_tokens: m.total_tokens,
-                budget: m.budget,
-                utilization_pct: m.utilization_pct,
-                layers: m
-                    .per_layer
-                    .iter()
-                    .map(|l| LayerStatsDto {
-                        name: l.name.clone(),
-                        tokens: l.tokens,
-                        items_included: l.items_included,
-                        items_total: l.items_total,
-                    })
-                    .collect(),
-                drops: m
-                    .drops
-                    .iter()
-                    .map(|d| DropInfoDto {
-                        layer: d.layer.clone(),
-                        items_dropped: d.items_dropped,
-                        reason: d.reason.clone(),
-                    })
-                    .collect(),
-            });
-
-            Ok(Json(ChatResponse {
-                conversation_id: conv_id,
-                response: result.answer,
-                pattern: "rag".into(),
-                iterations: 1,
-                tool_calls: result.retrieved_chunks.len(),
-                trace,
-                context_metadata,
-            }))
-        }
-        other => Err((
-            StatusCode::BAD_REQUEST,
+    let strategy = Strategy::from_pattern(&checkpoint.strategy).ok_or_else(|| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
                 error: format!(
-                    "Unknown pattern: '{}'. Use 'react', 'rag', or 'direct'.",
-                    other
+                    "Run '{}' was checkpointed under unknown strategy '{}'",
+                    run_id, checkpoint.strategy
                 ),
             }),
-        )),
+        )
+    })?;
+
+    let pattern = strategy.name().to_string();
+    let (conversation, result) = agent_runtime(&state, strategy)
+        .await
+        .resume(checkpoint)
+        .await
+        .map_err(agent_error)?;
+
+    let conv_id = conversation.id.to_string();
+    state
+        .conversations
+        .save(&conversation)
+        .await
+        .map_err(store_error)?;
+
+    Ok(Json(chat_response(conv_id, pattern, result)))
+}
+
+/// `GET /v1/runs` — List in-flight runs.
+async fn list_runs_handler(State(state): State<SharedApiState>) -> Json<Vec<RunInfo>> {
+    Json(state.runs.list())
+}
+
+/// `DELETE /v1/runs/:id` — Cancel an in-flight run.
+///
+/// The run stops at its next await point; a blocking `/v1/chat` call for it
+/// returns `409 Conflict` and a stream emits a `cancelled` event.
+async fn cancel_run_handler(
+    State(state): State<SharedApiState>,
+    Path(run_id): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    if state.runs.cancel(&run_id) {
+        Ok(Json(serde_json::json!({ "cancelled": run_id })))
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: format!("No running run '{}'", run_id),
+            }),
+        ))
     }
 }
 
+/// Map an agent error to an HTTP error, reporting cancellation as a conflict.
+fn agent_error(e: rustedclaw_core::Error) -> (StatusCode, Json<ErrorResponse>) {
+    let status = match e {
+        rustedclaw_core::Error::Cancelled(_) | rustedclaw_core::Error::RunInProgress(_) => {
+            StatusCode::CONFLICT
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (
+        status,
+        Json(ErrorResponse {
+            error: format!("Agent error: {}", e),
+        }),
+    )
+}
+
 // ── SSE Streaming ─────────────────────────────────────────────────────────
 
 /// `POST /v1/chat/stream` — Send a message, receive an SSE stream of events.
@@ -486,43 +730,28 @@ async fn chat_stream_handler(
 > {
     info!(pattern = %payload.pattern, "v1/chat/stream SSE request");
 
+    let strategy = parse_strategy(&payload.pattern)?;
+    if let Some(run_id) = &payload.run_id {
+        check_run_id(&state, run_id).await?;
+    }
+
     let conv_id = payload
         .conversation_id
         .unwrap_or_else(|| ConversationId::new().to_string());
 
-    let mut conversations = state.conversations.write().await;
+    let mut conv = load_or_new_conversation(&state, &conv_id)
+        .await
+        .map_err(store_error)?;
+    conv.push(user_message(&payload.message, &payload.scope));
+    state.conversations.save(&conv).await.map_err(store_error)?;
 
-    // Evict oldest conversation if at capacity
-    if conversations.len() >= MAX_CONVERSATIONS
-        && !conversations.contains_key(&conv_id)
-        && let Some(oldest_key) = conversations
-            .iter()
-            .min_by_key(|(_, c)| c.created_at)
-            .map(|(k, _)| k.clone())
-    {
-        conversations.remove(&oldest_key);
+    let mut runtime = agent_runtime(&state, strategy).await;
+    if let Some(run_id) = payload.run_id {
+        runtime = runtime.with_run_id(run_id);
     }
 
-    let conv = conversations
-        .entry(conv_id.clone())
-        .or_insert_with(Conversation::new);
-    conv.push(Message::user(&payload.message));
-
-    let agent = ReactAgent::new(
-        state.provider.clone(),
-        &state.model,
-        state.temperature,
-        state.tools.clone(),
-        state.identity.clone(),
-        state.event_bus.clone(),
-    )
-    .with_telemetry(state.telemetry.clone());
-
-    let mut conv_clone = conv.clone();
-    drop(conversations);
-
-    let rx = agent
-        .run_stream(&payload.message, &mut conv_clone, &[], &[])
+    let rx = runtime
+        .run_stream(&payload.message, &mut conv)
         .await
         .map_err(|e| {
             (
@@ -548,7 +777,8 @@ async fn chat_stream_handler(
 ///
 /// Protocol (per PRD):
 /// - Client → Server: `{ "type": "message", "content": "..." }`
-/// - Server → Client: `AgentStreamEvent` JSON frames (chunk, tool_call, tool_result, done)
+/// - Client → Server: `{ "type": "cancel", "run_id": "..." }`
+/// - Server → Client: `AgentStreamEvent` JSON frames (started, chunk, tool_call, tool_result, done, cancelled)
 async fn ws_handler(
     ws: WebSocketUpgrade,
     State(state): State<SharedApiState>,
@@ -561,9 +791,30 @@ async fn ws_handler(
 struct WsClientMessage {
     #[serde(rename = "type")]
     msg_type: String,
+    #[serde(default)]
     content: String,
     #[serde(default)]
     conversation_id: Option<String>,
+    #[serde(default)]
+    run_id: Option<String>,
+    #[serde(flatten)]
+    scope: MemoryScope,
+}
+
+async fn send_ws_event(socket: &mut WebSocket, event: &AgentStreamEvent) -> bool {
+    let json = serde_json::to_string(event).unwrap_or_default();
+    socket.send(WsMessage::Text(json.into())).await.is_ok()
+}
+
+/// Handle a client `cancel` frame.
+async fn handle_ws_cancel(socket: &mut WebSocket, state: &ApiV1State, run_id: Option<&str>) {
+    let cancelled = run_id.is_some_and(|id| state.runs.cancel(id));
+    if !cancelled {
+        let err = AgentStreamEvent::Error {
+            message: format!("No running run '{}'", run_id.unwrap_or_default()),
+        };
+        let _ = send_ws_event(socket, &err).await;
+    }
 }
 
 async fn handle_ws_connection(mut socket: WebSocket, state: SharedApiState) {
@@ -592,6 +843,11 @@ async fn handle_ws_connection(mut socket: WebSocket, state: SharedApiState) {
             }
         };
 
+        if client_msg.msg_type == "cancel" {
+            handle_ws_cancel(&mut socket, &state, client_msg.run_id.as_deref()).await;
+            continue;
+        }
+
         if client_msg.msg_type != "message" {
             let err = AgentStreamEvent::Error {
                 message: format!("Unknown message type: '{}'", client_msg.msg_type),
@@ -604,41 +860,73 @@ async fn handle_ws_connection(mut socket: WebSocket, state: SharedApiState) {
             continue;
         }
 
+        if let Some(run_id) = &client_msg.run_id
+            && let Err((_, Json(e))) = check_run_id(&state, run_id).await
+        {
+            let err = AgentStreamEvent::Error { message: e.error };
+            let _ = send_ws_event(&mut socket, &err).await;
+            continue;
+        }
+
         let conv_id = client_msg
             .conversation_id
             .unwrap_or_else(|| ConversationId::new().to_string());
 
-        let mut conversations = state.conversations.write().await;
-        let conv = conversations
-            .entry(conv_id.clone())
-            .or_insert_with(Conversation::new);
-        conv.push(Message::user(&client_msg.content));
-
-        let agent = ReactAgent::new(
-            state.provider.clone(),
-            &state.model,
-            state.temperature,
-            state.tools.clone(),
-            state.identity.clone(),
-            state.event_bus.clone(),
-        )
-        .with_telemetry(state.telemetry.clone());
+        let stored = match load_or_new_conversation(&state, &conv_id).await {
+            Ok(mut conv) => {
+                conv.push(user_message(&client_msg.content, &client_msg.scope));
+                state.conversations.save(&conv).await.map(|()| conv)
+            }
+            Err(e) => Err(e),
+        };
+        let mut conv = match stored {
+            Ok(conv) => conv,
+            Err(e) => {
+                let err = AgentStreamEvent::Error {
+                    message: format!("Conversation store error: {e}"),
+                };
+                let _ = send_ws_event(&mut socket, &err).await;
+                continue;
+            }
+        };
 
-        let mut conv_clone = conv.clone();
-        drop(conversations);
+        let mut runtime = agent_runtime(&state, Strategy::React).await;
+        if let Some(run_id) = client_msg.run_id {
+            runtime = runtime.with_run_id(run_id);
+        }
 
-        match agent
-            .run_stream(&client_msg.content, &mut conv_clone, &[], &[])
-            .await
-        {
-            Ok(mut rx) => {
-                while let Some(event) = rx.recv().await {
-                    let json = serde_json::to_string(&event).unwrap_or_default();
-                    if socket.send(WsMessage::Text(json.into())).await.is_err() {
-                        return; // client disconnected
-                    }
+        match runtime.run_stream(&client_msg.content, &mut conv).await {
+            Ok(mut rx) => loop {
+                // Keep reading client frames while streaming so `cancel` is honoured.
+                tokio::select! {
+                    event = rx.recv() => match event {
+                        Some(event) => {
+                            if !send_ws_event(&mut socket, &event).await {
+                                return; // client disconnected
+                            }
+                        }
+                        None => break,
+                    },
+                    incoming = socket.recv() => match incoming {
+                        Some(Ok(WsMessage::Text(text))) => {
+                            match serde_json::from_str::<WsClientMessage>(&text) {
+                                Ok(m) if m.msg_type == "cancel" => {
+                                    handle_ws_cancel(&mut socket, &state, m.run_id.as_deref())
+                                        .await;
+                                }
+                                _ => {
+                                    let err = AgentStreamEvent::Error {
+                                        message: "A run is already in progress".into(),
+                                    };
+                                    let _ = send_ws_event(&mut socket, &err).await;
+                                }
+                            }
+                        }
+                        Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => return,
+                        Some(Ok(_)) => {}
+                    },
                 }
-            }
+            },
             Err(e) => {
                 let err = AgentStreamEvent::Error {
                     message: format!("Agent error: {}", e),
@@ -691,64 +979,90 @@ async fn log_stream_handler(
 
 async fn list_conversations_handler(
     State(state): State<SharedApiState>,
-) -> Json<ConversationListResponse> {
-    let conversations = state.conversations.read().await;
-
-    let mut summaries: Vec<ConversationSummaryDto> = conversations
-        .values()
-        .map(|c| ConversationSummaryDto {
-            id: c.id.to_string(),
-            message_count: c.messages.len(),
-            created_at: c.created_at.to_rfc3339(),
-            updated_at: c.updated_at.to_rfc3339(),
-            title: c.title.clone(),
-        })
-        .collect();
-
-    summaries.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    Query(query): Query<ConversationListQuery>,
+) -> Result<Json<ConversationListResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_CONVERSATION_PAGE)
+        .min(MAX_CONVERSATION_PAGE);
+    let offset = query.offset.unwrap_or(0);
+
+    let (infos, total) = match query.q.as_deref().filter(|q| !q.trim().is_empty()) {
+        Some(q) => {
+            let hits = state
+                .conversations
+                .search(q, offset + limit)
+                .await
+                .map_err(store_error)?;
+            let total = hits.len();
+            (hits.into_iter().skip(offset).collect(), total)
+        }
+        None => {
+            let page = state
+                .conversations
+                .list(offset, limit)
+                .await
+                .map_err(store_error)?;
+            (page.conversations, page.total)
+        }
+    };
 
-    Json(ConversationListResponse {
-        conversations: summaries,
-    })
+    Ok(Json(ConversationListResponse {
+        conversations: infos
+            .into_iter()
+            .map(ConversationSummaryDto::from)
+            .collect(),
+        total,
+        offset,
+        limit,
+    }))
 }
 
 async fn create_conversation_handler(
     State(state): State<SharedApiState>,
-) -> (StatusCode, Json<CreateConversationResponse>) {
-    let conv = Conversation::new();
+    body: Option<Json<CreateConversationRequest>>,
+) -> Result<(StatusCode, Json<CreateConversationResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let mut conv = Conversation::new();
+    conv.title = body.and_then(|Json(req)| req.title);
     let id = conv.id.to_string();
     let created = conv.created_at.to_rfc3339();
 
-    let mut conversations = state.conversations.write().await;
+    state.conversations.save(&conv).await.map_err(store_error)?;
 
-    // Evict oldest if at capacity
-    if conversations.len() >= MAX_CONVERSATIONS
-        && let Some(oldest_key) = conversations
-            .iter()
-            .min_by_key(|(_, c)| c.created_at)
-            .map(|(k, _)| k.clone())
-    {
-        conversations.remove(&oldest_key);
-    }
-
-    conversations.insert(id.clone(), conv);
-
-    (
+    Ok((
         StatusCode::CREATED,
         Json(CreateConversationResponse {
             id,
             created_at: created,
         }),
-    )
+    ))
+}
+
+/// Load conversation `id`, or `404 Not Found`.
+async fn stored_conversation(
+    state: &ApiV1State,
+    id: &str,
+) -> Result<Conversation, (StatusCode, Json<ErrorResponse>)> {
+    state
+        .conversations
+        .load(id)
+        .await
+        .map_err(store_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: format!("Conversation '{id}' not found"),
+                }),
+            )
+        })
 }
 
 async fn get_conversation_handler(
     State(state): State<SharedApiState>,
     Path(id): Path<String>,
-) -> Result<Json<ConversationDetailResponse>, StatusCode> {
-    let conversations = state.conversations.read().await;
-
-    let conv = conversations.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+) -> Result<Json<ConversationDetailResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let conv = stored_conversation(&state, &id).await?;
 
     Ok(Json(ConversationDetailResponse {
         id: conv.id.to_string(),
@@ -768,18 +1082,76 @@ async fn get_conversation_handler(
     }))
 }
 
-async fn list_tools_handler(State(state): State<SharedApiState>) -> Json<ToolListResponse> {
-    let defs = state.tools.definitions();
-    let count = defs.len();
-
-    Json(ToolListResponse {
-        tools: defs
-            .into_iter()
-            .map(|d| ToolDto {
-                name: d.name,
-                description: d.description,
-                parameters: d.parameters,
-            })
+/// `PATCH /v1/conversations/:id` — Set the title.
+async fn update_conversation_handler(
+    State(state): State<SharedApiState>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateConversationRequest>,
+) -> Result<Json<ConversationSummaryDto>, (StatusCode, Json<ErrorResponse>)> {
+    let mut conv = stored_conversation(&state, &id).await?;
+    conv.title = Some(req.title);
+    state.conversations.save(&conv).await.map_err(store_error)?;
+    Ok(Json(ConversationSummaryDto::from(ConversationInfo::from(
+        &conv,
+    ))))
+}
+
+/// `DELETE /v1/conversations/:id`
+async fn delete_conversation_handler(
+    State(state): State<SharedApiState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    if state.conversations.delete(&id).await.map_err(store_error)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: format!("Conversation '{id}' not found"),
+            }),
+        ))
+    }
+}
+
+/// `GET /v1/conversations/:id/export?format=json|markdown`
+async fn export_conversation_handler(
+    State(state): State<SharedApiState>,
+    Path(id): Path<String>,
+    Query(query): Query<ExportQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let format = match query.format.as_deref() {
+        Some(f) => f
+            .parse::<ExportFormat>()
+            .map_err(|error| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })))?,
+        None => ExportFormat::Json,
+    };
+    let conv = stored_conversation(&state, &id).await?;
+    let body = format.render(&conv).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Export failed: {e}"),
+            }),
+        )
+    })?;
+    Ok((
+        [(axum::http::header::CONTENT_TYPE, format.content_type())],
+        body,
+    ))
+}
+
+async fn list_tools_handler(State(state): State<SharedApiState>) -> Json<ToolListResponse> {
+    let defs = state.tools.definitions();
+    let count = defs.len();
+
+    Json(ToolListResponse {
+        tools: defs
+            .into_iter()
+            .map(|d| ToolDto {
+                name: d.name,
+                description: d.description,
+                parameters: d.parameters,
+            })
             .collect(),
         count,
     })
@@ -789,15 +1161,43 @@ async fn context_debug_handler(
     State(state): State<SharedApiState>,
     Json(payload): Json<ContextDebugRequest>,
 ) -> Result<Json<ContextDebugResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let budget = TokenBudget {
-        total: payload.budget,
-        ..TokenBudget::default()
+    let (context, max_tokens) = {
+        let config = state.config.read().await;
+        (config.context.clone(), config.default_max_tokens)
+    };
+    let capabilities = state.provider.capabilities(&state.model);
+    let capabilities = match context.context_window {
+        Some(window) => capabilities.with_context_window(window),
+        None => capabilities,
+    };
+    let budget = match payload.budget {
+        Some(total) => TokenBudget::from_percentages(total, &context.layers),
+        None => TokenBudget::for_model(&capabilities, Some(max_tokens), &context.layers),
+    };
+    let layer_budgets = LayerBudgetDto {
+        long_term_memory: budget.per_layer.long_term_memory,
+        working_memory: budget.per_layer.working_memory,
+        knowledge: budget.per_layer.knowledge,
+        tool_schemas: budget.per_layer.tool_schemas,
+        conversation_history: budget.per_layer.conversation_history,
+        tool_output: budget.per_layer.tool_output,
     };
 
-    let assembler = ContextAssembler::new(budget);
+    let tool_defs = state.tools.definitions();
+    let ranker = ToolRanker::from_config(&context, state.provider.clone())
+        .for_message(&payload.message, &tool_defs)
+        .await;
+    let mut assembler = ContextAssembler::new(budget)
+        .with_counter(token::resolve_counter(
+            state.provider.as_ref(),
+            &state.model,
+        ))
+        .with_tool_ranker(ranker);
+    if context.current_time {
+        assembler = assembler.with_layer(Arc::new(DateTimeLayer::new()));
+    }
     let wm = WorkingMemory::default();
     let conv = Conversation::new();
-    let tool_defs = state.tools.definitions();
 
     let memories: Vec<MemoryEntry> = payload
         .memories
@@ -809,8 +1209,12 @@ async fn context_debug_handler(
             source: None,
             created_at: chrono::Utc::now(),
             last_accessed: chrono::Utc::now(),
+            access_count: 0,
+            expires_at: None,
             score: 0.0,
             embedding: None,
+            embedding_model: None,
+            scope: MemoryScope::default(),
         })
         .collect();
 
@@ -863,32 +1267,10 @@ async fn context_debug_handler(
             .iter()
             .map(|t| t.name.clone())
             .collect(),
-        metadata: ContextMetadataDto {
-            total_tokens: assembled.metadata.total_tokens,
-            budget: assembled.metadata.budget,
-            utilization_pct: assembled.metadata.utilization_pct,
-            layers: assembled
-                .metadata
-                .per_layer
-                .iter()
-                .map(|l| LayerStatsDto {
-                    name: l.name.clone(),
-                    tokens: l.tokens,
-                    items_included: l.items_included,
-                    items_total: l.items_total,
-                })
-                .collect(),
-            drops: assembled
-                .metadata
-                .drops
-                .iter()
-                .map(|d| DropInfoDto {
-                    layer: d.layer.clone(),
-                    items_dropped: d.items_dropped,
-                    reason: d.reason.clone(),
-                })
-                .collect(),
-        },
+        model: state.model.clone(),
+        capabilities,
+        layer_budgets,
+        metadata: context_metadata_dto(assembled.metadata),
     }))
 }
 
@@ -1124,19 +1506,7 @@ async fn delete_routine_handler(
     }
 }
 
-// ── Document + Memory + Job types ─────────────────────────────────────────
-
-/// A document chunk stored in the knowledge base.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DocumentEntry {
-    pub id: String,
-    pub content: String,
-    pub source: String,
-    pub metadata: Option<serde_json::Value>,
-    pub chunk_index: usize,
-    pub document_id: String,
-    pub created_at: chrono::DateTime<chrono::Utc>,
-}
+// ── Job types ─────────────────────────────────────────
 
 /// A job (routine execution) entry.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1164,78 +1534,158 @@ pub enum JobStatus {
 struct IngestDocumentRequest {
     content: String,
     source: String,
+    /// `markdown`, `html`, `text`, `pdf` or a MIME type; defaults to the
+    /// source's extension, then plain text.
     #[serde(default)]
-    metadata: Option<serde_json::Value>,
+    format: Option<String>,
+    /// `base64` when `content` is base64-encoded, as PDFs must be.
+    #[serde(default)]
+    encoding: Option<String>,
     #[serde(default)]
-    chunk_index: Option<usize>,
+    metadata: Option<serde_json::Value>,
+    /// Re-ingesting an existing ID replaces that document's chunks.
     #[serde(default)]
     document_id: Option<String>,
+    #[serde(flatten)]
+    scope: MemoryScope,
 }
 
 #[derive(Serialize, Deserialize)]
 struct IngestDocumentResponse {
-    id: String,
     document_id: String,
-    chunk_index: usize,
     source: String,
+    title: Option<String>,
+    /// IDs of the stored chunks, in document order.
+    chunk_ids: Vec<String>,
 }
 
 async fn ingest_document_handler(
     State(state): State<SharedApiState>,
     Json(req): Json<IngestDocumentRequest>,
-) -> (StatusCode, Json<IngestDocumentResponse>) {
-    let id = uuid::Uuid::new_v4().to_string();
-    let document_id = req
-        .document_id
-        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
-    let chunk_index = req.chunk_index.unwrap_or(0);
-
-    let entry = DocumentEntry {
-        id: id.clone(),
-        content: req.content,
-        source: req.source.clone(),
-        metadata: req.metadata,
-        chunk_index,
-        document_id: document_id.clone(),
-        created_at: chrono::Utc::now(),
+) -> Result<(StatusCode, Json<IngestDocumentResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error }));
+
+    let format = match &req.format {
+        Some(name) => DocumentFormat::from_name(name)
+            .ok_or_else(|| bad_request(format!("Unsupported document format: {name}")))?,
+        None => DocumentFormat::from_path(std::path::Path::new(&req.source))
+            .unwrap_or(DocumentFormat::Text),
+    };
+    let content = match req.encoding.as_deref() {
+        None | Some("utf-8") => req.content.into_bytes(),
+        Some("base64") => {
+            use base64::Engine as _;
+            base64::engine::general_purpose::STANDARD
+                .decode(req.content.trim())
+                .map_err(|e| bad_request(format!("Invalid base64 content: {e}")))?
+        }
+        Some(other) => return Err(bad_request(format!("Unsupported encoding: {other}"))),
     };
 
-    let mut documents = state.documents.write().await;
+    let document = Document {
+        id: req
+            .document_id
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        source: req.source,
+        format,
+        content,
+        tags: req.metadata.as_ref().map(metadata_tags).unwrap_or_default(),
+        scope: req.scope,
+    };
+    let source = document.source.clone();
+    let ingest_config = state.config.read().await.memory.ingest.clone();
+    let report = DocumentIngestor::from_config(state.memory.clone(), &ingest_config)
+        .ingest(document)
+        .await
+        .map_err(|e| match e {
+            rustedclaw_core::error::MemoryError::InvalidDocument(msg) => bad_request(msg),
+            other => memory_error(other),
+        })?;
 
-    // Evict oldest 10% if at capacity
-    if documents.len() >= MAX_DOCUMENTS {
-        let drain_count = MAX_DOCUMENTS / 10;
-        // Sort by created_at to remove oldest
-        documents.sort_by_key(|d| d.created_at);
-        documents.drain(..drain_count);
-    }
+    Ok((
+        StatusCode::CREATED,
+        Json(IngestDocumentResponse {
+            document_id: report.document_id,
+            source,
+            title: report.title,
+            chunk_ids: report.chunk_ids,
+        }),
+    ))
+}
+
+#[derive(Serialize, Deserialize)]
+struct DeleteDocumentResponse {
+    document_id: String,
+    deleted_chunks: usize,
+}
 
-    documents.push(entry);
+async fn delete_document_handler(
+    State(state): State<SharedApiState>,
+    Path(id): Path<String>,
+) -> Result<Json<DeleteDocumentResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let deleted_chunks = DocumentIngestor::new(state.memory.clone())
+        .remove(&id)
+        .await
+        .map_err(memory_error)?;
+    if deleted_chunks == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: format!("Document '{id}' not found"),
+            }),
+        ));
+    }
+    Ok(Json(DeleteDocumentResponse {
+        document_id: id,
+        deleted_chunks,
+    }))
+}
 
+/// Map a memory backend failure to `500 Internal Server Error`.
+fn memory_error(e: rustedclaw_core::error::MemoryError) -> (StatusCode, Json<ErrorResponse>) {
     (
-        StatusCode::CREATED,
-        Json(IngestDocumentResponse {
-            id,
-            document_id,
-            chunk_index,
-            source: req.source,
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            error: format!("Memory error: {e}"),
         }),
     )
 }
 
+/// Every entry carrying any of `tags` (all entries when empty) that is
+/// visible within `scope` (in any scope when `None`), newest first.
+async fn list_memory(
+    memory: &dyn MemoryBackend,
+    tags: Vec<String>,
+    scope: Option<MemoryScope>,
+) -> Result<Vec<MemoryEntry>, (StatusCode, Json<ErrorResponse>)> {
+    let filter = MemoryFilter {
+        tags,
+        scope,
+        ..MemoryFilter::default()
+    };
+    memory
+        .list(&filter, 0, MEMORY_LIST_LIMIT)
+        .await
+        .map_err(memory_error)
+}
+
 // ── Memory CRUD ───────────────────────────────────────────────────────────
 
 #[derive(Deserialize)]
 struct CreateMemoryRequest {
     content: String,
-    #[serde(default)]
-    agent_id: Option<String>,
+    /// Owner of the memory (`namespace`, `agent_id`, `user_id`, `channel`).
+    #[serde(flatten)]
+    scope: MemoryScope,
     #[serde(default)]
     category: Option<String>,
     #[serde(default)]
     tags: Vec<String>,
     #[serde(default = "default_confidence")]
     confidence: f32,
+    /// Forget the memory this many seconds after it is stored.
+    #[serde(default)]
+    ttl_seconds: Option<i64>,
 }
 
 fn default_confidence() -> f32 {
@@ -1263,6 +1713,12 @@ struct MemoryItemDto {
     score: f32,
     created_at: String,
     last_accessed: String,
+    #[serde(default)]
+    access_count: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<String>,
+    #[serde(flatten)]
+    scope: MemoryScope,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -1274,63 +1730,60 @@ struct MemoryDeleteResponse {
 async fn create_memory_handler(
     State(state): State<SharedApiState>,
     Json(req): Json<CreateMemoryRequest>,
-) -> (StatusCode, Json<CreateMemoryResponse>) {
-    let id = uuid::Uuid::new_v4().to_string();
+) -> Result<(StatusCode, Json<CreateMemoryResponse>), (StatusCode, Json<ErrorResponse>)> {
     let now = chrono::Utc::now();
 
     let mut tags = req.tags;
     if let Some(ref cat) = req.category {
         tags.push(format!("category:{cat}"));
     }
-    if let Some(ref agent_id) = req.agent_id {
-        tags.push(format!("agent:{agent_id}"));
-    }
 
     let entry = MemoryEntry {
-        id: id.clone(),
+        id: uuid::Uuid::new_v4().to_string(),
         content: req.content.clone(),
         tags,
-        source: req.agent_id,
+        source: None,
         created_at: now,
         last_accessed: now,
+        access_count: 0,
+        expires_at: req
+            .ttl_seconds
+            .map(|secs| now + chrono::Duration::seconds(secs)),
         score: req.confidence,
         embedding: None,
+        embedding_model: None,
+        scope: req.scope,
     };
+    let id = state.memory.store(entry).await.map_err(memory_error)?;
 
-    let mut memories = state.memories.write().await;
-
-    // Evict oldest 10% if at capacity
-    if memories.len() >= MAX_MEMORIES {
-        let drain_count = MAX_MEMORIES / 10;
-        memories.sort_by_key(|m| m.created_at);
-        memories.drain(..drain_count);
-    }
-
-    memories.push(entry);
-
-    (
+    Ok((
         StatusCode::CREATED,
         Json(CreateMemoryResponse {
             id,
             content: req.content,
             created_at: now.to_rfc3339(),
         }),
-    )
+    ))
 }
 
-async fn search_memory_handler(State(state): State<SharedApiState>) -> Json<MemoryListResponse> {
-    let memories = state.memories.read().await;
-    let items: Vec<MemoryItemDto> = memories
-        .iter()
-        .map(|m| MemoryItemDto {
-            id: m.id.clone(),
-            content: m.content.clone(),
-            tags: m.tags.clone(),
+impl From<MemoryEntry> for MemoryItemDto {
+    fn from(m: MemoryEntry) -> Self {
+        Self {
+            id: m.id,
+            content: m.content,
+            tags: m.tags,
             score: m.score,
             created_at: m.created_at.to_rfc3339(),
             last_accessed: m.last_accessed.to_rfc3339(),
-        })
-        .collect();
+            access_count: m.access_count,
+            expires_at: m.expires_at.map(|at| at.to_rfc3339()),
+            scope: m.scope,
+        }
+    }
+}
+
+fn memory_list(entries: impl Iterator<Item = MemoryEntry>) -> Json<MemoryListResponse> {
+    let items: Vec<MemoryItemDto> = entries.map(MemoryItemDto::from).collect();
     let count = items.len();
     Json(MemoryListResponse {
         memories: items,
@@ -1338,41 +1791,69 @@ async fn search_memory_handler(State(state): State<SharedApiState>) -> Json<Memo
     })
 }
 
+async fn search_memory_handler(
+    State(state): State<SharedApiState>,
+    Query(scope): Query<MemoryScope>,
+) -> Result<Json<MemoryListResponse>, (StatusCode, Json<ErrorResponse>)> {
+    // Without scope parameters every entry is listed.
+    let scope = (!scope.is_empty()).then_some(scope);
+    let entries = list_memory(state.memory.as_ref(), Vec::new(), scope).await?;
+    Ok(memory_list(
+        entries
+            .into_iter()
+            .filter(|m| !m.tags.iter().any(|t| t == DOCUMENT_TAG)),
+    ))
+}
+
 async fn list_agent_memory_handler(
     State(state): State<SharedApiState>,
     Path(agent_id): Path<String>,
-) -> Json<MemoryListResponse> {
-    let memories = state.memories.read().await;
-    let tag_filter = format!("agent:{agent_id}");
-    let items: Vec<MemoryItemDto> = memories
-        .iter()
-        .filter(|m| {
-            m.tags.iter().any(|t| t == &tag_filter) || m.source.as_deref() == Some(&agent_id)
-        })
-        .map(|m| MemoryItemDto {
-            id: m.id.clone(),
-            content: m.content.clone(),
-            tags: m.tags.clone(),
-            score: m.score,
-            created_at: m.created_at.to_rfc3339(),
-            last_accessed: m.last_accessed.to_rfc3339(),
-        })
-        .collect();
-    let count = items.len();
-    Json(MemoryListResponse {
-        memories: items,
-        count,
-    })
+) -> Result<Json<MemoryListResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let scope = MemoryScope {
+        agent_id: Some(agent_id.clone()),
+        ..MemoryScope::default()
+    };
+    let entries = list_memory(state.memory.as_ref(), Vec::new(), Some(scope)).await?;
+    Ok(memory_list(entries.into_iter().filter(|m| {
+        m.scope.agent_id.as_deref() == Some(&agent_id)
+    })))
+}
+
+async fn update_memory_handler(
+    State(state): State<SharedApiState>,
+    Path(id): Path<String>,
+    Json(patch): Json<MemoryPatch>,
+) -> Result<Json<MemoryItemDto>, (StatusCode, Json<ErrorResponse>)> {
+    match state
+        .memory
+        .update(&id, patch)
+        .await
+        .map_err(memory_error)?
+    {
+        Some(entry) => Ok(Json(entry.into())),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: format!("Memory '{id}' not found"),
+            }),
+        )),
+    }
 }
 
 async fn delete_memory_handler(
     State(state): State<SharedApiState>,
     Path(id): Path<String>,
 ) -> Result<Json<MemoryDeleteResponse>, (StatusCode, Json<MemoryDeleteResponse>)> {
-    let mut memories = state.memories.write().await;
-    let before = memories.len();
-    memories.retain(|m| m.id != id);
-    if memories.len() < before {
+    let deleted = state.memory.delete(&id).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(MemoryDeleteResponse {
+                success: false,
+                message: format!("Memory error: {e}"),
+            }),
+        )
+    })?;
+    if deleted {
         Ok(Json(MemoryDeleteResponse {
             success: true,
             message: format!("Memory '{id}' deleted"),
@@ -1917,9 +2398,13 @@ struct StatusResponse {
 }
 
 async fn status_handler(State(state): State<SharedApiState>) -> Json<StatusResponse> {
-    let conversations = state.conversations.read().await;
-    let memories = state.memories.read().await;
-    let documents = state.documents.read().await;
+    let active_conversations = state.conversations.count().await.unwrap_or(0);
+    // Status stays healthy when storage fails; counts read as zero.
+    let total_entries = state.memory.count().await.unwrap_or(0);
+    let document_entries = list_memory(state.memory.as_ref(), vec![DOCUMENT_TAG.into()], None)
+        .await
+        .map(|docs| docs.len())
+        .unwrap_or(0);
 
     let uptime = chrono::Utc::now()
         .signed_duration_since(state.start_time)
@@ -1929,9 +2414,9 @@ async fn status_handler(State(state): State<SharedApiState>) -> Json<StatusRespo
         status: "healthy".into(),
         version: env!("CARGO_PKG_VERSION").into(),
         uptime_secs: uptime,
-        active_conversations: conversations.len(),
-        memory_entries: memories.len(),
-        document_entries: documents.len(),
+        active_conversations,
+        memory_entries: total_entries.saturating_sub(document_entries),
+        document_entries,
         tools_count: state.tools.definitions().len(),
         contracts_count: state.contracts.active_count(),
         session_cost_usd: state.telemetry.usage_snapshot().session_cost_usd,
@@ -2005,12 +2490,13 @@ mod tests {
             event_bus,
             contracts: Arc::new(rustedclaw_contracts::ContractEngine::empty()),
             telemetry: Arc::new(rustedclaw_telemetry::TelemetryEngine::new()),
-            conversations: RwLock::new(HashMap::new()),
+            conversations: Arc::new(rustedclaw_memory::InMemoryConversationStore::new()),
+            checkpoints: Arc::new(rustedclaw_agent::InMemoryCheckpointStore::new()),
+            runs: Arc::new(RunRegistry::new()),
             workflow: Some(Arc::new(rustedclaw_workflow::WorkflowEngine::default())),
             config: RwLock::new(rustedclaw_config::AppConfig::default()),
             start_time: chrono::Utc::now(),
-            memories: RwLock::new(Vec::new()),
-            documents: RwLock::new(Vec::new()),
+            memory: Arc::new(rustedclaw_memory::InMemoryBackend::new()),
             jobs: RwLock::new(Vec::new()),
             bearer_tokens: RwLock::new(Vec::new()),
         })
@@ -2084,6 +2570,82 @@ mod tests {
         assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 
+    #[tokio::test]
+    async fn conversation_search_title_export_and_delete() {
+        let state = test_api_state();
+        for (id, text) in [
+            ("conv-rust", "Explain Rust traits"),
+            ("conv-go", "Explain Go"),
+        ] {
+            let mut conv = new_conversation(id);
+            conv.push(Message::user(text));
+            state.conversations.save(&conv).await.unwrap();
+        }
+
+        let send = |req: Request<Body>| v1_router(state.clone()).oneshot(req);
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        // Search by content, then page through everything.
+        let response = send(get("/conversations?q=rust")).await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let list: ConversationListResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(list.total, 1);
+        assert_eq!(list.conversations[0].id, "conv-rust");
+
+        let response = send(get("/conversations?limit=1&offset=1")).await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let list: ConversationListResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!((list.total, list.conversations.len()), (2, 1));
+
+        // Rename, then export as Markdown.
+        let req = Request::builder()
+            .method("PATCH")
+            .uri("/conversations/conv-rust")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"title": "Traits"}"#))
+            .unwrap();
+        assert_eq!(send(req).await.unwrap().status(), StatusCode::OK);
+
+        let response = send(get("/conversations/conv-rust/export?format=markdown"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let markdown = String::from_utf8(body.to_vec()).unwrap();
+        assert!(markdown.starts_with("# Traits\n"));
+        assert!(markdown.contains("Explain Rust traits"));
+
+        let response = send(get("/conversations/conv-rust/export?format=pdf"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Delete.
+        let delete = || {
+            Request::builder()
+                .method("DELETE")
+                .uri("/conversations/conv-rust")
+                .body(Body::empty())
+                .unwrap()
+        };
+        assert_eq!(
+            send(delete()).await.unwrap().status(),
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            send(delete()).await.unwrap().status(),
+            StatusCode::NOT_FOUND
+        );
+        assert!(
+            state
+                .conversations
+                .load("conv-rust")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
     #[tokio::test]
     async fn context_debug_endpoint() {
         let app = v1_router(test_api_state());
@@ -2111,6 +2673,38 @@ mod tests {
         assert!(debug.system_message.contains("[Retrieved Knowledge]"));
         assert!(debug.metadata.total_tokens > 0);
         assert!(!debug.tool_definitions.is_empty());
+        assert_eq!(debug.model, "mock-model");
+        assert_eq!(debug.capabilities, ModelCapabilities::FALLBACK);
+        assert_eq!(debug.metadata.budget, 4096);
+    }
+
+    #[tokio::test]
+    async fn context_debug_applies_configured_layer_percentages() {
+        let state = test_api_state();
+        {
+            let mut config = state.config.write().await;
+            config.context.context_window = Some(20_000);
+            config.context.layers.knowledge = Some(10.0);
+        }
+        let app = v1_router(state);
+
+        let body = serde_json::json!({"message": "What is Rust?"});
+        let req = Request::builder()
+            .method("POST")
+            .uri("/context/debug")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&body).unwrap()))
+            .unwrap();
+
+        let response = app.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let debug: ContextDebugResponse = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(debug.capabilities.context_window, 20_000);
+        assert_eq!(debug.metadata.budget, 20_000 - 4096);
+        assert_eq!(debug.layer_budgets.knowledge, Some((20_000 - 4096) / 10));
+        assert_eq!(debug.layer_budgets.working_memory, None);
     }
 
     #[tokio::test]
@@ -2133,6 +2727,213 @@ mod tests {
         assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[tokio::test]
+    async fn chat_direct_pattern_runs_react() {
+        let app = v1_router(test_api_state());
+
+        let body = serde_json::json!({
+            "message": "Hello",
+            "pattern": "direct"
+        });
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/chat")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&body).unwrap()))
+            .unwrap();
+
+        let response = app.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["pattern"], "direct");
+        assert_eq!(json["response"], "Mock response from agent");
+        assert!(json["run_id"].is_string());
+        // Same as "react": the answer comes with a reasoning trace
+        assert!(!json["trace"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn chat_reflect_pattern() {
+        let app = v1_router(test_api_state());
+
+        let body = serde_json::json!({
+            "message": "Hello",
+            "pattern": "reflect"
+        });
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/chat")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&body).unwrap()))
+            .unwrap();
+
+        let response = app.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["pattern"], "reflect");
+        let trace = json["trace"].as_array().unwrap();
+        assert!(trace.iter().any(|t| t["kind"] == "Reflection"));
+    }
+
+    #[tokio::test]
+    async fn resume_unknown_run_not_found() {
+        let app = v1_router(test_api_state());
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/runs/missing-run/resume")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn resume_of_a_running_run_conflicts() {
+        let state = test_api_state();
+        let checkpoint = rustedclaw_agent::RunCheckpoint::new(
+            "run-1",
+            "Hello",
+            new_conversation("conv-1"),
+            WorkingMemory::new(5),
+        );
+        state.checkpoints.save(&checkpoint).await.unwrap();
+        let _guard = state
+            .runs
+            .register(
+                "run-1",
+                "conv-1",
+                rustedclaw_agent::CancellationToken::new(),
+            )
+            .unwrap();
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/runs/run-1/resume")
+            .body(Body::empty())
+            .unwrap();
+        let response = v1_router(state.clone()).oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        // The checkpoint is left for the running run.
+        assert!(state.checkpoints.load("run-1").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn cancel_run_endpoint() {
+        let state = test_api_state();
+        let token = rustedclaw_agent::CancellationToken::new();
+        let _guard = state
+            .runs
+            .register("run-1", "conv-1", token.clone())
+            .unwrap();
+
+        let req = Request::builder().uri("/runs").body(Body::empty()).unwrap();
+        let response = v1_router(state.clone()).oneshot(req).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let runs: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(runs[0]["run_id"], "run-1");
+
+        let req = Request::builder()
+            .method("DELETE")
+            .uri("/runs/run-1")
+            .body(Body::empty())
+            .unwrap();
+        let response = v1_router(state.clone()).oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(token.is_cancelled());
+
+        let req = Request::builder()
+            .method("DELETE")
+            .uri("/runs/missing-run")
+            .body(Body::empty())
+            .unwrap();
+        let response = v1_router(state).oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn chat_rejects_unusable_run_ids() {
+        let state = test_api_state();
+        let conv = new_conversation("conv-1");
+        let checkpoint =
+            rustedclaw_agent::RunCheckpoint::new("run-1", "Hello", conv, WorkingMemory::new(5));
+        state.checkpoints.save(&checkpoint).await.unwrap();
+        let _guard = state
+            .runs
+            .register(
+                "run-2",
+                "conv-2",
+                rustedclaw_agent::CancellationToken::new(),
+            )
+            .unwrap();
+
+        for (run_id, status) in [
+            ("../escape", StatusCode::BAD_REQUEST),
+            ("run-1", StatusCode::CONFLICT),
+            ("run-2", StatusCode::CONFLICT),
+            ("run-3", StatusCode::OK),
+        ] {
+            let body = serde_json::json!({"message": "Hello", "run_id": run_id});
+            let req = Request::builder()
+                .method("POST")
+                .uri("/chat")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&body).unwrap()))
+                .unwrap();
+            let response = v1_router(state.clone()).oneshot(req).await.unwrap();
+            assert_eq!(response.status(), status, "run_id {run_id}");
+        }
+
+        // The interrupted run's checkpoint was left alone
+        let kept = state.checkpoints.load("run-1").await.unwrap().unwrap();
+        assert_eq!(kept.user_message, "Hello");
+    }
+
+    #[tokio::test]
+    async fn resume_checkpointed_run() {
+        let state = test_api_state();
+
+        let mut conv = new_conversation("conv-1");
+        conv.push(Message::user("Hello"));
+        let mut wm = WorkingMemory::new(5);
+        wm.tick();
+        let mut checkpoint = rustedclaw_agent::RunCheckpoint::new("run-1", "Hello", conv, wm);
+        checkpoint.strategy = "loop".into();
+        state.checkpoints.save(&checkpoint).await.unwrap();
+
+        let app = v1_router(state.clone());
+        let req = Request::builder()
+            .method("POST")
+            .uri("/runs/run-1/resume")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["run_id"], "run-1");
+        assert_eq!(json["conversation_id"], "conv-1");
+        assert_eq!(json["pattern"], "loop");
+        assert_eq!(json["response"], "Mock response from agent");
+        assert_eq!(json["iterations"], 2);
+
+        // The finished run is no longer resumable, and the conversation is stored.
+        assert!(state.checkpoints.load("run-1").await.unwrap().is_none());
+        let conv = state.conversations.load("conv-1").await.unwrap().unwrap();
+        assert_eq!(conv.messages.len(), 2);
+    }
+
     #[tokio::test]
     async fn list_routines_empty() {
         let app = v1_router(test_api_state());
@@ -2342,6 +3143,64 @@ mod tests {
         let list: MemoryListResponse = serde_json::from_slice(&body).unwrap();
         assert_eq!(list.count, 1);
         assert!(list.memories[0].content.contains("Fact A"));
+        assert_eq!(list.memories[0].scope.agent_id.as_deref(), Some("agent1"));
+    }
+
+    #[tokio::test]
+    async fn memory_listing_respects_user_scope() {
+        let state = test_api_state();
+        for body in [
+            serde_json::json!({ "content": "alice likes tea", "user_id": "alice" }),
+            serde_json::json!({ "content": "bob likes coffee", "user_id": "bob" }),
+            serde_json::json!({ "content": "office opens at nine" }),
+        ] {
+            let req = Request::builder()
+                .method("POST")
+                .uri("/memory")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&body).unwrap()))
+                .unwrap();
+            v1_router(state.clone()).oneshot(req).await.unwrap();
+        }
+
+        let req = Request::builder()
+            .uri("/memory?user_id=alice")
+            .body(Body::empty())
+            .unwrap();
+        let response = v1_router(state.clone()).oneshot(req).await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let list: MemoryListResponse = serde_json::from_slice(&body).unwrap();
+        let mut contents: Vec<_> = list.memories.iter().map(|m| m.content.as_str()).collect();
+        contents.sort_unstable();
+        assert_eq!(contents, ["alice likes tea", "office opens at nine"]);
+    }
+
+    #[tokio::test]
+    async fn expired_memories_are_not_listed() {
+        let state = test_api_state();
+        for body in [
+            serde_json::json!({ "content": "already gone", "ttl_seconds": -1 }),
+            serde_json::json!({ "content": "still here", "ttl_seconds": 3600 }),
+        ] {
+            let req = Request::builder()
+                .method("POST")
+                .uri("/memory")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&body).unwrap()))
+                .unwrap();
+            v1_router(state.clone()).oneshot(req).await.unwrap();
+        }
+
+        let req = Request::builder()
+            .uri("/memory")
+            .body(Body::empty())
+            .unwrap();
+        let response = v1_router(state.clone()).oneshot(req).await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let list: MemoryListResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(list.memories.len(), 1);
+        assert_eq!(list.memories[0].content, "still here");
+        assert!(list.memories[0].expires_at.is_some());
     }
 
     #[tokio::test]
@@ -2386,6 +3245,44 @@ mod tests {
         assert_eq!(list.count, 0);
     }
 
+    #[tokio::test]
+    async fn patch_memory_updates_content_and_tags() {
+        let state = test_api_state();
+        let body = serde_json::json!({ "content": "Standup at nine", "tags": ["meeting"] });
+        let req = Request::builder()
+            .method("POST")
+            .uri("/memory")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&body).unwrap()))
+            .unwrap();
+        let response = v1_router(state.clone()).oneshot(req).await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let created: CreateMemoryResponse = serde_json::from_slice(&body).unwrap();
+
+        let patch = serde_json::json!({ "content": "Standup at ten" });
+        let req = Request::builder()
+            .method("PATCH")
+            .uri(format!("/memory/{}", created.id))
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&patch).unwrap()))
+            .unwrap();
+        let response = v1_router(state.clone()).oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let updated: MemoryItemDto = serde_json::from_slice(&body).unwrap();
+        assert_eq!(updated.content, "Standup at ten");
+        assert_eq!(updated.tags, ["meeting"]);
+
+        let req = Request::builder()
+            .method("PATCH")
+            .uri("/memory/missing")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&patch).unwrap()))
+            .unwrap();
+        let response = v1_router(state).oneshot(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
     #[tokio::test]
     async fn delete_nonexistent_memory() {
         let app = v1_router(test_api_state());
@@ -2402,16 +3299,133 @@ mod tests {
 
     // ── Document endpoint tests ────────────────────────────────────────
 
+    async fn post_document(
+        state: &SharedApiState,
+        body: serde_json::Value,
+    ) -> (StatusCode, axum::body::Bytes) {
+        let req = Request::builder()
+            .method("POST")
+            .uri("/documents")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&body).unwrap()))
+            .unwrap();
+        let response = v1_router(state.clone()).oneshot(req).await.unwrap();
+        let status = response.status();
+        (
+            status,
+            response.into_body().collect().await.unwrap().to_bytes(),
+        )
+    }
+
     #[tokio::test]
     async fn ingest_document() {
         let state = test_api_state();
 
+        let (status, body) = post_document(
+            &state,
+            serde_json::json!({
+                "content": "# Overview\n\nRust is a systems programming language.\n\n## Safety\n\nThe borrow checker prevents data races.",
+                "source": "docs/overview.md",
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let resp: IngestDocumentResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(resp.source, "docs/overview.md");
+        assert_eq!(resp.title.as_deref(), Some("Overview"));
+        assert_eq!(resp.chunk_ids.len(), 2, "one chunk per section");
+
+        let safety = state.memory.get(&resp.chunk_ids[1]).await.unwrap().unwrap();
+        assert_eq!(
+            safety.content,
+            "Overview › Safety\n\nThe borrow checker prevents data races."
+        );
+        assert!(safety.tags.iter().any(|t| t == "format:markdown"));
+    }
+
+    #[tokio::test]
+    async fn ingest_base64_pdf_and_reject_invalid_documents() {
+        use base64::Engine as _;
+        let state = test_api_state();
+        let pdf = b"%PDF-1.4\n1 0 obj\n<< /Length 24 >>\nstream\nBT (Hello from a PDF) Tj ET\nendstream\nendobj\n%%EOF\n";
+
+        let (status, body) = post_document(
+            &state,
+            serde_json::json!({
+                "content": base64::engine::general_purpose::STANDARD.encode(pdf),
+                "encoding": "base64",
+                "format": "application/pdf",
+                "source": "https://example.com/hello.pdf",
+            }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let resp: IngestDocumentResponse = serde_json::from_slice(&body).unwrap();
+        let chunk = state.memory.get(&resp.chunk_ids[0]).await.unwrap().unwrap();
+        assert_eq!(chunk.content, "Hello from a PDF");
+
+        for body in [
+            serde_json::json!({"content": "x", "source": "a", "format": "docx"}),
+            serde_json::json!({"content": "%%%", "source": "a", "encoding": "base64"}),
+            serde_json::json!({"content": "not a pdf", "source": "a.pdf"}),
+            serde_json::json!({"content": "  ", "source": "a.txt"}),
+        ] {
+            let (status, _) = post_document(&state, body.clone()).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+        }
+    }
+
+    #[tokio::test]
+    async fn reingest_replaces_and_delete_removes_documents() {
+        let state = test_api_state();
+        let long = "A sentence about ingestion. ".repeat(200);
+        let (_, body) = post_document(
+            &state,
+            serde_json::json!({"content": long, "source": "a.txt", "document_id": "doc"}),
+        )
+        .await;
+        let first: IngestDocumentResponse = serde_json::from_slice(&body).unwrap();
+        assert!(first.chunk_ids.len() > 1);
+
+        let (_, body) = post_document(
+            &state,
+            serde_json::json!({"content": "Short now.", "source": "a.txt", "document_id": "doc"}),
+        )
+        .await;
+        let second: IngestDocumentResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(second.chunk_ids, ["doc#0"]);
+        assert_eq!(state.memory.count().await.unwrap(), 1);
+
+        let delete = |id: &str| {
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/documents/{id}"))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = v1_router(state.clone())
+            .oneshot(delete("doc"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(state.memory.count().await.unwrap(), 0);
+        let response = v1_router(state.clone())
+            .oneshot(delete("doc"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn documents_stored_in_memory_backend_but_not_listed_as_memories() {
+        let state = test_api_state();
+
         let body = serde_json::json!({
             "content": "Rust is a systems programming language",
             "source": "docs/overview.md",
-            "chunk_index": 0
+            "document_id": "overview",
+            "metadata": {"lang": "en"}
         });
-
         let app = v1_router(state.clone());
         let req = Request::builder()
             .method("POST")
@@ -2419,14 +3433,36 @@ mod tests {
             .header("content-type", "application/json")
             .body(Body::from(serde_json::to_string(&body).unwrap()))
             .unwrap();
-
         let response = app.oneshot(req).await.unwrap();
-        assert_eq!(response.status(), StatusCode::CREATED);
-
         let body = response.into_body().collect().await.unwrap().to_bytes();
         let resp: IngestDocumentResponse = serde_json::from_slice(&body).unwrap();
-        assert_eq!(resp.source, "docs/overview.md");
-        assert_eq!(resp.chunk_index, 0);
+
+        let stored = state.memory.get(&resp.chunk_ids[0]).await.unwrap().unwrap();
+        assert_eq!(stored.source.as_deref(), Some("docs/overview.md"));
+        for tag in ["document", "document:overview", "chunk:0", "lang:en"] {
+            assert!(stored.tags.iter().any(|t| t == tag), "missing tag {tag}");
+        }
+
+        let app = v1_router(state.clone());
+        let req = Request::builder()
+            .uri("/memory")
+            .body(Body::empty())
+            .unwrap();
+        let body = app.oneshot(req).await.unwrap().into_body();
+        let list: MemoryListResponse =
+            serde_json::from_slice(&body.collect().await.unwrap().to_bytes()).unwrap();
+        assert_eq!(list.count, 0);
+
+        let app = v1_router(state);
+        let req = Request::builder()
+            .uri("/status")
+            .body(Body::empty())
+            .unwrap();
+        let body = app.oneshot(req).await.unwrap().into_body();
+        let status: StatusResponse =
+            serde_json::from_slice(&body.collect().await.unwrap().to_bytes()).unwrap();
+        assert_eq!(status.memory_entries, 0);
+        assert_eq!(status.document_entries, 1);
     }
 
     // ── Job endpoint tests ─────────────────────────────────────────────
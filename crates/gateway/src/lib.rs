@@ -25,8 +25,9 @@ use tokio::sync::RwLock;
 use tower_http::cors::CorsLayer;
 use tracing::{info, warn};
 
-use rustedclaw_agent::AgentLoop;
+use rustedclaw_agent::{AgentLoop, FactExtractor};
 use rustedclaw_contracts::ContractEngine;
+use rustedclaw_core::channel::{ChannelId, ChannelMessage};
 use rustedclaw_core::event::EventBus;
 use rustedclaw_core::identity::{ContextPaths, Identity};
 use rustedclaw_core::message::{Conversation, Message};
@@ -104,8 +105,8 @@ pub fn build_full_router(legacy_state: SharedState, api_state: api_v1::SharedApi
 
 /// Start the gateway HTTP server.
 ///
-/// Memory-optimized: builds provider, tools, identity, and event bus
-/// only ONCE and shares them via Arc between legacy and v1 state.
+/// Memory-optimized: builds provider, memory backend, tools, identity, and
+/// event bus only ONCE and shares them via Arc between legacy and v1 state.
 pub async fn start(config: rustedclaw_config::AppConfig) -> Result<(), Box<dyn std::error::Error>> {
     let host = config.gateway.host.clone();
     let port = config.gateway.port;
@@ -138,8 +139,15 @@ pub async fn start(config: rustedclaw_config::AppConfig) -> Result<(), Box<dyn s
         system_prompt_override: config.identity.system_prompt_override.clone(),
     };
     let identity = Identity::load(&context_paths);
-    let tools = Arc::new(rustedclaw_tools::default_registry());
     let event_bus = Arc::new(EventBus::default());
+    let memory = rustedclaw_memory::with_embeddings(
+        rustedclaw_memory::from_config(&config.memory).await?,
+        &config.memory,
+        router.get(&config.memory.embedding_provider),
+    );
+    rustedclaw_memory::spawn_compaction_from_config(memory.clone(), &config.memory);
+    let conversations =
+        rustedclaw_memory::conversation_store_from_config(&config.memory.conversations).await?;
 
     // Build contract engine from config
     let contract_engine = {
@@ -200,20 +208,47 @@ pub async fn start(config: rustedclaw_config::AppConfig) -> Result<(), Box<dyn s
         Arc::new(engine)
     };
 
+    // Built-in tools plus `delegate`, whose sub-agents draw on the built-ins.
+    let tools = {
+        let mut registry = rustedclaw_tools::registry_from_config(&config, memory.clone());
+        if config.tools.is_enabled("delegate") {
+            registry.register(Box::new(
+                rustedclaw_agent::DelegateTool::new(
+                    provider.clone(),
+                    &config.default_model,
+                    config.default_temperature,
+                    Arc::new(rustedclaw_tools::registry_from_config(
+                        &config,
+                        memory.clone(),
+                    )),
+                    event_bus.clone(),
+                )
+                .with_telemetry(telemetry_engine.clone()),
+            ));
+        }
+        Arc::new(registry)
+    };
+
     // Shared agent for legacy routes (reuses same provider/tools/identity)
-    let agent = Arc::new(
-        AgentLoop::new(
-            provider.clone(),
-            &config.default_model,
-            config.default_temperature,
-            tools.clone(),
-            identity.clone(),
-            event_bus.clone(),
-        )
-        .with_max_tokens(config.default_max_tokens)
-        .with_contracts(contract_engine.clone())
-        .with_telemetry(telemetry_engine.clone()),
-    );
+    let mut agent = AgentLoop::new(
+        provider.clone(),
+        &config.default_model,
+        config.default_temperature,
+        tools.clone(),
+        identity.clone(),
+        event_bus.clone(),
+    )
+    .with_max_tokens(config.default_max_tokens)
+    .with_memory(memory.clone())
+    .with_auto_save(config.memory.auto_save)
+    .with_contracts(contract_engine.clone())
+    .with_telemetry(telemetry_engine.clone());
+    if let Some(extractor) =
+        FactExtractor::from_config(&config.memory, provider.clone(), &config.default_model)
+    {
+        agent = agent.with_fact_extractor(extractor);
+    }
+    let agent = Arc::new(agent);
 
     // Build shared state for legacy routes.
     let legacy_state = Arc::new(RwLock::new(GatewayState {
@@ -233,15 +268,18 @@ pub async fn start(config: rustedclaw_config::AppConfig) -> Result<(), Box<dyn s
         event_bus,
         contracts: contract_engine,
         telemetry: telemetry_engine,
-        conversations: RwLock::new(HashMap::new()),
+        conversations,
+        checkpoints: Arc::new(rustedclaw_agent::FileCheckpointStore::new(
+            rustedclaw_agent::FileCheckpointStore::default_path(),
+        )),
+        runs: Arc::new(rustedclaw_agent::RunRegistry::new()),
         workflow: Some(Arc::new(rustedclaw_workflow::WorkflowEngine::new(
             config.heartbeat.enabled,
             config.heartbeat.interval_minutes,
         ))),
         config: RwLock::new(config.clone()),
         start_time: chrono::Utc::now(),
-        memories: RwLock::new(Vec::new()),
-        documents: RwLock::new(Vec::new()),
+        memory,
         jobs: RwLock::new(Vec::new()),
         bearer_tokens: RwLock::new(Vec::new()),
     });
@@ -396,6 +434,40 @@ async fn pair_handler(
 #[derive(Deserialize)]
 struct WebhookRequest {
     message: String,
+    /// Channel the sender is on; defaults to `webhook`.
+    #[serde(default)]
+    channel: Option<String>,
+    /// Chat or group within the channel; defaults to the sender.
+    #[serde(default)]
+    chat_id: Option<String>,
+}
+
+impl WebhookRequest {
+    /// The user turn, scoped to `sender_id` when the caller authenticated.
+    fn to_message(&self, sender_id: Option<String>) -> Message {
+        let Some(sender_id) = sender_id else {
+            return Message::user(&self.message);
+        };
+        ChannelMessage {
+            channel_id: ChannelId(self.channel.clone().unwrap_or_else(|| "webhook".into())),
+            chat_id: self.chat_id.clone().unwrap_or_else(|| sender_id.clone()),
+            sender_id,
+            sender_name: None,
+            content: self.message.clone(),
+            reply_to_message_id: None,
+            attachments: Vec::new(),
+            metadata: serde_json::Map::new(),
+        }
+        .to_message()
+    }
+}
+
+/// Sender ID of a webhook caller, derived from its bearer token so memory
+/// recall follows the authenticated client without storing the token.
+fn token_sender_id(token: &str) -> String {
+    use sha2::{Digest, Sha256};
+    let digest = Sha256::digest(token.as_bytes());
+    format!("token-{}", hex::encode(&digest[..8]))
 }
 
 #[derive(Serialize)]
@@ -410,7 +482,8 @@ async fn webhook_handler(
 ) -> Result<Json<WebhookResponse>, StatusCode> {
     let state_read = state.read().await;
 
-    // Check bearer token
+    // Check bearer token; the token identifies the sender
+    let mut sender_id = None;
     if !state_read.bearer_tokens.is_empty() {
         let auth_header = headers
             .get("Authorization")
@@ -418,7 +491,9 @@ async fn webhook_handler(
             .and_then(|v| v.strip_prefix("Bearer "));
 
         match auth_header {
-            Some(token) if state_read.bearer_tokens.contains(&token.to_string()) => {}
+            Some(token) if state_read.bearer_tokens.contains(&token.to_string()) => {
+                sender_id = Some(token_sender_id(token));
+            }
             _ => return Err(StatusCode::UNAUTHORIZED),
         }
     }
@@ -433,7 +508,7 @@ async fn webhook_handler(
     drop(state_read); // Release the lock before async work
 
     let mut conv = Conversation::new();
-    conv.push(Message::user(&payload.message));
+    conv.push(payload.to_message(sender_id));
 
     match agent.process(&mut conv).await {
         Ok(response) => Ok(Json(WebhookResponse { response })),
@@ -542,4 +617,101 @@ mod tests {
         let response = app.oneshot(req).await.unwrap();
         assert_eq!(response.status(), StatusCode::OK);
     }
+
+    /// Replies with the system prompt, so tests can see what was recalled.
+    struct EchoContextProvider;
+
+    #[async_trait::async_trait]
+    impl rustedclaw_core::Provider for EchoContextProvider {
+        fn name(&self) -> &str {
+            "echo_context"
+        }
+
+        async fn complete(
+            &self,
+            request: rustedclaw_core::provider::ProviderRequest,
+        ) -> Result<
+            rustedclaw_core::provider::ProviderResponse,
+            rustedclaw_core::error::ProviderError,
+        > {
+            Ok(rustedclaw_core::provider::ProviderResponse {
+                message: Message::assistant(&request.messages[0].content),
+                usage: None,
+                model: "echo".into(),
+                metadata: serde_json::Map::new(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn webhook_recall_is_scoped_to_the_sender() {
+        use rustedclaw_core::memory::{MemoryBackend, MemoryEntry, MemoryScope};
+
+        let memory = Arc::new(rustedclaw_memory::InMemoryBackend::new());
+        for (token, fact) in [
+            ("alice-token", "cat is named Tom"),
+            ("bob-token", "cat is named Rex"),
+        ] {
+            memory
+                .store(MemoryEntry {
+                    id: String::new(),
+                    content: fact.into(),
+                    tags: vec![],
+                    source: None,
+                    created_at: chrono::Utc::now(),
+                    last_accessed: chrono::Utc::now(),
+                    access_count: 0,
+                    expires_at: None,
+                    score: 0.0,
+                    embedding: None,
+                    embedding_model: None,
+                    scope: MemoryScope {
+                        user_id: Some(token_sender_id(token)),
+                        channel: Some("telegram".into()),
+                        ..Default::default()
+                    },
+                })
+                .await
+                .unwrap();
+        }
+        let state = test_state();
+        state.write().await.bearer_tokens = vec!["alice-token".into(), "bob-token".into()];
+        state.write().await.agent = Arc::new(
+            AgentLoop::new(
+                Arc::new(EchoContextProvider),
+                "echo",
+                0.0,
+                Arc::new(rustedclaw_core::tool::ToolRegistry::new()),
+                Identity::default(),
+                Arc::new(EventBus::default()),
+            )
+            .with_memory(memory),
+        );
+
+        for (token, own, other) in [("alice-token", "Tom", "Rex"), ("bob-token", "Rex", "Tom")] {
+            let body = serde_json::json!({
+                // The in-memory backend recalls by substring
+                "message": "cat",
+                // A claimed sender doesn't override the token's identity
+                "sender_id": if own == "Tom" { "bob" } else { "alice" },
+                "channel": "telegram",
+            });
+            let req = Request::builder()
+                .method("POST")
+                .uri("/webhook")
+                .header("content-type", "application/json")
+                .header("Authorization", format!("Bearer {token}"))
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let response = build_router(state.clone()).oneshot(req).await.unwrap();
+            let body = http_body_util::BodyExt::collect(response.into_body())
+                .await
+                .unwrap()
+                .to_bytes();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            let reply = json["response"].as_str().unwrap();
+            assert!(reply.contains(own), "{token} should recall {own}: {reply}");
+            assert!(!reply.contains(other), "{token} recalled {other}: {reply}");
+        }
+    }
 }
@@ -0,0 +1,134 @@
+//! Recall and latency of the HNSW index against brute-force vector search.
+//!
+//! ```text
+//! cargo bench -p rustedclaw-memory --bench vector_index
+//! BENCH_ENTRIES=50000 BENCH_DIM=768 cargo bench -p rustedclaw-memory --bench vector_index
+//! ```
+//!
+//! Vectors are drawn around random cluster centres, which is closer to real
+//! embeddings than uniform noise. Recall@k is the share of the exact top-k
+//! (from [`vector_search`]) that the index returns.
+
+use chrono::Utc;
+use rustedclaw_core::memory::{MemoryEntry, MemoryScope};
+use rustedclaw_memory::{HnswIndex, HnswParams, vector_search};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+const K: usize = 10;
+
+fn env_or(name: &str, default: usize) -> usize {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// xorshift64*, uniform in [-1, 1).
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        let bits = self.0.wrapping_mul(0x2545F4914F6CDD1D) >> 40;
+        bits as f32 / (1u64 << 23) as f32 - 1.0
+    }
+}
+
+fn clustered(rng: &mut Rng, centres: &[Vec<f32>], count: usize) -> Vec<Vec<f32>> {
+    (0..count)
+        .map(|i| {
+            let centre = &centres[i % centres.len()];
+            centre.iter().map(|c| c + 0.35 * rng.next()).collect()
+        })
+        .collect()
+}
+
+fn entry(id: usize, embedding: Vec<f32>) -> MemoryEntry {
+    MemoryEntry {
+        id: format!("m{id}"),
+        content: String::new(),
+        tags: vec![],
+        source: None,
+        created_at: Utc::now(),
+        last_accessed: Utc::now(),
+        access_count: 0,
+        expires_at: None,
+        score: 0.0,
+        embedding: Some(embedding),
+        embedding_model: None,
+        scope: MemoryScope::default(),
+    }
+}
+
+/// Mean and 99th percentile.
+fn summarize(mut samples: Vec<Duration>) -> (Duration, Duration) {
+    samples.sort();
+    let mean = samples.iter().sum::<Duration>() / samples.len() as u32;
+    let p99 = samples[(samples.len() * 99 / 100).min(samples.len() - 1)];
+    (mean, p99)
+}
+
+fn main() {
+    let entries = env_or("BENCH_ENTRIES", 20_000);
+    let dim = env_or("BENCH_DIM", 384);
+    let queries = env_or("BENCH_QUERIES", 200);
+
+    let mut rng = Rng(0x9E3779B97F4A7C15);
+    let centres: Vec<Vec<f32>> = (0..64)
+        .map(|_| (0..dim).map(|_| rng.next()).collect())
+        .collect();
+    let data: Vec<MemoryEntry> = clustered(&mut rng, &centres, entries)
+        .into_iter()
+        .enumerate()
+        .map(|(i, v)| entry(i, v))
+        .collect();
+    let probes = clustered(&mut rng, &centres, queries);
+
+    println!("vector_index: {entries} entries, {dim} dimensions, {queries} queries, k={K}");
+
+    let start = Instant::now();
+    let mut index = HnswIndex::new(HnswParams::default());
+    for e in &data {
+        index
+            .insert(&e.id, e.embedding.as_deref().unwrap())
+            .unwrap();
+    }
+    let build = start.elapsed();
+    println!(
+        "  hnsw build       {:>10.2?} ({:.1} µs/insert)",
+        build,
+        build.as_secs_f64() * 1e6 / entries as f64
+    );
+
+    let mut exact = Vec::with_capacity(queries);
+    let mut brute_times = Vec::with_capacity(queries);
+    for probe in &probes {
+        let start = Instant::now();
+        let hits = vector_search(&data, probe, K, -1.0);
+        brute_times.push(start.elapsed());
+        exact.push(hits.into_iter().map(|e| e.id).collect::<HashSet<_>>());
+    }
+    let (brute_mean, brute_p99) = summarize(brute_times);
+    println!("  brute force      mean {brute_mean:>10.2?}  p99 {brute_p99:>10.2?}  recall 1.000");
+
+    for ef in [16, 32, 64, 128, 256] {
+        index.set_ef_search(ef);
+        let mut found = 0;
+        let mut times = Vec::with_capacity(queries);
+        for (probe, truth) in probes.iter().zip(&exact) {
+            let start = Instant::now();
+            let hits = index.search(probe, K);
+            times.push(start.elapsed());
+            found += hits.iter().filter(|(id, _)| truth.contains(id)).count();
+        }
+        let (mean, p99) = summarize(times);
+        let recall = found as f64 / (queries * K) as f64;
+        println!(
+            "  hnsw ef={ef:<4}     mean {mean:>10.2?}  p99 {p99:>10.2?}  recall {recall:.3}  ({:.0}x)",
+            brute_mean.as_secs_f64() / mean.as_secs_f64()
+        );
+    }
+}
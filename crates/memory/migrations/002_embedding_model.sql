@@ -0,0 +1,4 @@
+-- Track which model produced each embedding so entries can be re-embedded
+-- when the configured embedding model changes.
+
+ALTER TABLE memories ADD COLUMN IF NOT EXISTS embedding_model TEXT;
@@ -0,0 +1,17 @@
+-- Scope every memory to a namespace, agent, user and channel. NULL means
+-- the memory is shared along that dimension.
+
+ALTER TABLE memories ADD COLUMN IF NOT EXISTS namespace TEXT;
+ALTER TABLE memories ADD COLUMN IF NOT EXISTS agent_id TEXT;
+ALTER TABLE memories ADD COLUMN IF NOT EXISTS user_id TEXT;
+ALTER TABLE memories ADD COLUMN IF NOT EXISTS channel TEXT;
+
+-- Memories created through the API before scoping recorded their agent as
+-- the source plus an `agent:<id>` tag.
+UPDATE memories SET agent_id = source
+    WHERE agent_id IS NULL AND source IS NOT NULL AND ('agent:' || source) = ANY (tags);
+
+CREATE INDEX IF NOT EXISTS idx_memories_namespace ON memories (namespace);
+CREATE INDEX IF NOT EXISTS idx_memories_agent_id ON memories (agent_id);
+CREATE INDEX IF NOT EXISTS idx_memories_user_id ON memories (user_id);
+CREATE INDEX IF NOT EXISTS idx_memories_channel ON memories (channel);
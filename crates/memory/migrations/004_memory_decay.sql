@@ -0,0 +1,7 @@
+-- Track how often and how recently each memory is recalled, and let
+-- memories expire.
+
+ALTER TABLE memories ADD COLUMN IF NOT EXISTS access_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE memories ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_memories_expires_at ON memories (expires_at);
@@ -0,0 +1,373 @@
+//! Behaviour every [`MemoryBackend`] must share.
+//!
+//! Each backend's tests call [`run`] on a fresh instance; every case starts
+//! by clearing the backend.
+
+use chrono::{Duration, Utc};
+use rustedclaw_core::memory::{MemoryBackend, MemoryEntry, MemoryFilter, MemoryPatch, MemoryScope};
+
+/// Run the whole suite against `backend`.
+pub(crate) async fn run(backend: &dyn MemoryBackend) {
+    store_and_get(backend).await;
+    update(backend).await;
+    list(backend).await;
+    list_filters(backend).await;
+    store_batch(backend).await;
+    delete_where(backend).await;
+    export_import(backend).await;
+}
+
+fn entry(content: &str, tags: &[&str], source: Option<&str>, age_hours: i64) -> MemoryEntry {
+    let at = Utc::now() - Duration::hours(age_hours);
+    MemoryEntry {
+        id: String::new(),
+        content: content.into(),
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+        source: source.map(String::from),
+        created_at: at,
+        last_accessed: at,
+        access_count: 0,
+        expires_at: None,
+        score: 1.0,
+        embedding: None,
+        embedding_model: None,
+        scope: MemoryScope::default(),
+    }
+}
+
+fn owned_by(user: &str, mut entry: MemoryEntry) -> MemoryEntry {
+    entry.scope.user_id = Some(user.into());
+    entry
+}
+
+fn expired(mut entry: MemoryEntry) -> MemoryEntry {
+    entry.expires_at = Some(Utc::now() - Duration::minutes(1));
+    entry
+}
+
+async fn contents(backend: &dyn MemoryBackend, filter: &MemoryFilter) -> Vec<String> {
+    let mut contents: Vec<String> = backend
+        .list(filter, 0, 100)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|e| e.content)
+        .collect();
+    contents.sort();
+    contents
+}
+
+async fn store_and_get(backend: &dyn MemoryBackend) {
+    backend.clear().await.unwrap();
+    let id = backend
+        .store(owned_by(
+            "alice",
+            entry("Likes tea", &["drink"], Some("chat"), 0),
+        ))
+        .await
+        .unwrap();
+    assert!(!id.is_empty(), "store assigns an ID");
+
+    let stored = backend.get(&id).await.unwrap().expect("stored entry");
+    assert_eq!(stored.content, "Likes tea");
+    assert_eq!(stored.tags, ["drink"]);
+    assert_eq!(stored.source.as_deref(), Some("chat"));
+    assert_eq!(stored.scope.user_id.as_deref(), Some("alice"));
+
+    let mut replacement = entry("Likes coffee", &[], None, 0);
+    replacement.id = id.clone();
+    backend.store(replacement).await.unwrap();
+    assert_eq!(backend.count().await.unwrap(), 1, "store replaces by ID");
+    assert_eq!(
+        backend.get(&id).await.unwrap().unwrap().content,
+        "Likes coffee"
+    );
+}
+
+async fn update(backend: &dyn MemoryBackend) {
+    backend.clear().await.unwrap();
+    let mut embedded = entry("Likes tea", &["drink"], Some("chat"), 0);
+    embedded.embedding = Some(vec![1.0, 0.0]);
+    embedded.embedding_model = Some("test-embed".into());
+    let id = backend.store(embedded).await.unwrap();
+
+    let retagged = backend
+        .update(
+            &id,
+            MemoryPatch {
+                tags: Some(vec!["preference".into()]),
+                ..MemoryPatch::default()
+            },
+        )
+        .await
+        .unwrap()
+        .expect("updated entry");
+    assert_eq!(retagged.content, "Likes tea");
+    assert_eq!(retagged.tags, ["preference"]);
+    assert_eq!(retagged.source.as_deref(), Some("chat"));
+    assert_eq!(retagged.embedding.as_deref(), Some(&[1.0, 0.0][..]));
+
+    let rewritten = backend
+        .update(
+            &id,
+            MemoryPatch {
+                content: Some("Likes coffee".into()),
+                ..MemoryPatch::default()
+            },
+        )
+        .await
+        .unwrap()
+        .expect("updated entry");
+    assert_eq!(rewritten.tags, ["preference"], "unset fields are kept");
+    assert!(
+        rewritten.embedding.is_none(),
+        "new content drops the embedding"
+    );
+    let stored = backend.get(&id).await.unwrap().unwrap();
+    assert_eq!(stored.content, "Likes coffee");
+    assert_eq!(stored.tags, ["preference"]);
+    assert_eq!(backend.count().await.unwrap(), 1);
+
+    let patch = MemoryPatch {
+        content: Some("anything".into()),
+        ..MemoryPatch::default()
+    };
+    assert!(
+        backend
+            .update("missing", patch.clone())
+            .await
+            .unwrap()
+            .is_none()
+    );
+    let gone = backend
+        .store(expired(entry("Old news", &[], None, 0)))
+        .await
+        .unwrap();
+    assert!(backend.update(&gone, patch).await.unwrap().is_none());
+}
+
+async fn list(backend: &dyn MemoryBackend) {
+    backend.clear().await.unwrap();
+    for age in [3, 0, 4, 1, 2] {
+        backend
+            .store(entry(&format!("{age} hours old"), &[], None, age))
+            .await
+            .unwrap();
+    }
+    backend
+        .store(expired(entry("expired", &[], None, 0)))
+        .await
+        .unwrap();
+
+    let all = MemoryFilter::default();
+    let mut pages = Vec::new();
+    for offset in [0, 2, 4, 6] {
+        let page: Vec<String> = backend
+            .list(&all, offset, 2)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.content)
+            .collect();
+        pages.push(page);
+    }
+    assert_eq!(
+        pages,
+        [
+            vec!["0 hours old", "1 hours old"],
+            vec!["2 hours old", "3 hours old"],
+            vec!["4 hours old"],
+            vec![],
+        ],
+        "newest first, paginated, without expired entries"
+    );
+
+    let listed = backend.list(&all, 0, 1).await.unwrap();
+    let recalled = backend.get(&listed[0].id).await.unwrap().unwrap();
+    assert_eq!(recalled.access_count, 1, "listing is not an access");
+}
+
+async fn list_filters(backend: &dyn MemoryBackend) {
+    backend.clear().await.unwrap();
+    let entries = [
+        owned_by("alice", entry("alice note", &["work"], Some("chat"), 1)),
+        owned_by("bob", entry("bob note", &["work"], Some("chat"), 1)),
+        entry("shared note", &["home"], Some("import"), 10),
+        entry("untagged note", &[], None, 30),
+    ];
+    for e in entries {
+        backend.store(e).await.unwrap();
+    }
+
+    let cases = [
+        (
+            MemoryFilter {
+                tags: vec!["home".into(), "work".into()],
+                ..MemoryFilter::default()
+            },
+            vec!["alice note", "bob note", "shared note"],
+        ),
+        (
+            MemoryFilter {
+                source: Some("import".into()),
+                ..MemoryFilter::default()
+            },
+            vec!["shared note"],
+        ),
+        (
+            MemoryFilter {
+                created_after: Some(Utc::now() - Duration::hours(20)),
+                created_before: Some(Utc::now() - Duration::hours(5)),
+                ..MemoryFilter::default()
+            },
+            vec!["shared note"],
+        ),
+        (
+            MemoryFilter {
+                scope: Some(MemoryScope {
+                    user_id: Some("alice".into()),
+                    ..MemoryScope::default()
+                }),
+                ..MemoryFilter::default()
+            },
+            vec!["alice note", "shared note", "untagged note"],
+        ),
+        (
+            MemoryFilter {
+                scope: Some(MemoryScope::default()),
+                ..MemoryFilter::default()
+            },
+            vec!["shared note", "untagged note"],
+        ),
+        (
+            MemoryFilter {
+                tags: vec!["work".into()],
+                source: Some("chat".into()),
+                scope: Some(MemoryScope {
+                    user_id: Some("bob".into()),
+                    ..MemoryScope::default()
+                }),
+                ..MemoryFilter::default()
+            },
+            vec!["bob note"],
+        ),
+    ];
+    for (filter, expected) in cases {
+        assert_eq!(contents(backend, &filter).await, expected, "{filter:?}");
+    }
+}
+
+async fn store_batch(backend: &dyn MemoryBackend) {
+    backend.clear().await.unwrap();
+    assert!(backend.store_batch(Vec::new()).await.unwrap().is_empty());
+
+    let mut named = entry("second", &[], None, 0);
+    named.id = "named".into();
+    let ids = backend
+        .store_batch(vec![
+            entry("first", &[], None, 0),
+            named,
+            entry("third", &[], None, 0),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(ids.len(), 3);
+    assert_eq!(ids[1], "named", "IDs are returned in order");
+    assert!(ids.iter().all(|id| !id.is_empty()));
+    assert_eq!(backend.count().await.unwrap(), 3);
+    assert_eq!(
+        backend.get(&ids[2]).await.unwrap().unwrap().content,
+        "third"
+    );
+}
+
+async fn delete_where(backend: &dyn MemoryBackend) {
+    backend.clear().await.unwrap();
+    backend
+        .store_batch(vec![
+            owned_by("alice", entry("alice draft", &["draft"], None, 0)),
+            owned_by("bob", entry("bob draft", &["draft"], None, 0)),
+            entry("shared draft", &["draft"], None, 0),
+            entry("final", &["final"], None, 0),
+        ])
+        .await
+        .unwrap();
+
+    let bobs_drafts = MemoryFilter {
+        tags: vec!["draft".into()],
+        scope: Some(MemoryScope {
+            user_id: Some("bob".into()),
+            ..MemoryScope::default()
+        }),
+        ..MemoryFilter::default()
+    };
+    assert_eq!(backend.delete_where(&bobs_drafts).await.unwrap(), 2);
+    assert_eq!(
+        contents(backend, &MemoryFilter::default()).await,
+        ["alice draft", "final"]
+    );
+    assert_eq!(
+        backend
+            .delete_where(&MemoryFilter {
+                source: Some("nowhere".into()),
+                ..MemoryFilter::default()
+            })
+            .await
+            .unwrap(),
+        0
+    );
+}
+
+async fn export_import(backend: &dyn MemoryBackend) {
+    backend.clear().await.unwrap();
+    let mut embedded = owned_by("alice", entry("Likes tea", &["drink"], Some("chat"), 2));
+    embedded.embedding = Some(vec![0.5, 0.5]);
+    embedded.embedding_model = Some("test-embed".into());
+    backend
+        .store_batch(vec![
+            embedded,
+            entry("Works remotely", &["work"], None, 1),
+            entry("Owns a cat", &[], None, 0),
+        ])
+        .await
+        .unwrap();
+    let before = backend
+        .list(&MemoryFilter::default(), 0, 100)
+        .await
+        .unwrap();
+
+    let mut jsonl = Vec::new();
+    let exported = backend
+        .export(&MemoryFilter::default(), &mut jsonl)
+        .await
+        .unwrap();
+    assert_eq!(exported, 3);
+    assert_eq!(String::from_utf8_lossy(&jsonl).lines().count(), 3);
+
+    let mut work = Vec::new();
+    let filter = MemoryFilter {
+        tags: vec!["work".into()],
+        ..MemoryFilter::default()
+    };
+    assert_eq!(backend.export(&filter, &mut work).await.unwrap(), 1);
+
+    backend.clear().await.unwrap();
+    assert_eq!(backend.import(&mut jsonl.as_slice()).await.unwrap(), 3);
+    let after = backend
+        .list(&MemoryFilter::default(), 0, 100)
+        .await
+        .unwrap();
+    assert_eq!(after.len(), before.len());
+    for (a, b) in before.iter().zip(&after) {
+        assert_eq!(a.id, b.id, "import keeps IDs");
+        assert_eq!(a.content, b.content);
+        assert_eq!(a.tags, b.tags);
+        assert_eq!(a.source, b.source);
+        assert_eq!(a.scope, b.scope);
+        assert_eq!(a.embedding, b.embedding);
+        assert_eq!(a.created_at.timestamp(), b.created_at.timestamp());
+    }
+
+    let mut invalid = &b"{\"not\": \"a memory\"}\n"[..];
+    assert!(backend.import(&mut invalid).await.is_err());
+}
@@ -0,0 +1,151 @@
+//! File-backed conversation store: one `<id>.json` file per conversation.
+//!
+//! Listing and search read every file, which is fine for a personal
+//! assistant's history; use the SQLite store for large archives.
+
+use super::{paginate, search_loaded};
+use async_trait::async_trait;
+use rustedclaw_core::conversation_store::{ConversationInfo, ConversationPage, ConversationStore};
+use rustedclaw_core::error::MemoryError;
+use rustedclaw_core::message::Conversation;
+use std::path::PathBuf;
+use tracing::warn;
+
+/// Conversations as JSON files under a directory.
+///
+/// Files are written to a temporary path and renamed into place, so a
+/// crash mid-write never leaves a truncated conversation behind.
+pub struct FileConversationStore {
+    dir: PathBuf,
+}
+
+impl FileConversationStore {
+    /// Create a store rooted at `dir` (created on first write).
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Default directory: `~/.rustedclaw/conversations`
+    pub fn default_path() -> PathBuf {
+        let home = std::env::var("HOME")
+            .or_else(|_| std::env::var("USERPROFILE"))
+            .unwrap_or_else(|_| ".".to_string());
+        PathBuf::from(home)
+            .join(".rustedclaw")
+            .join("conversations")
+    }
+
+    fn path_for(&self, id: &str) -> Result<PathBuf, MemoryError> {
+        // IDs become file names — reject anything that could escape the directory.
+        if id.is_empty()
+            || !id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(MemoryError::Storage(format!(
+                "Invalid conversation ID for file store: '{id}'"
+            )));
+        }
+        Ok(self.dir.join(format!("{id}.json")))
+    }
+
+    /// Every readable conversation in the directory.
+    async fn load_all(&self) -> Result<Vec<Conversation>, MemoryError> {
+        let mut dir = match tokio::fs::read_dir(&self.dir).await {
+            Ok(d) => d,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(io_error(e)),
+        };
+
+        let mut conversations = Vec::new();
+        while let Some(entry) = dir.next_entry().await.map_err(io_error)? {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let parsed = tokio::fs::read(&path)
+                .await
+                .map_err(io_error)
+                .and_then(|bytes| serde_json::from_slice(&bytes).map_err(json_error));
+            match parsed {
+                Ok(conversation) => conversations.push(conversation),
+                Err(e) => warn!(path = %path.display(), "Skipping unreadable conversation: {e}"),
+            }
+        }
+        Ok(conversations)
+    }
+}
+
+fn io_error(e: std::io::Error) -> MemoryError {
+    MemoryError::Storage(format!("Conversation file I/O: {e}"))
+}
+
+fn json_error(e: serde_json::Error) -> MemoryError {
+    MemoryError::Storage(format!("Conversation file format: {e}"))
+}
+
+#[async_trait]
+impl ConversationStore for FileConversationStore {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    async fn save(&self, conversation: &Conversation) -> Result<(), MemoryError> {
+        let path = self.path_for(&conversation.id.0)?;
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(io_error)?;
+        let json = serde_json::to_vec_pretty(conversation).map_err(json_error)?;
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, json).await.map_err(io_error)?;
+        tokio::fs::rename(&tmp, &path).await.map_err(io_error)?;
+        Ok(())
+    }
+
+    async fn load(&self, id: &str) -> Result<Option<Conversation>, MemoryError> {
+        let path = self.path_for(id)?;
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes).map_err(json_error)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error(e)),
+        }
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool, MemoryError> {
+        let path = self.path_for(id)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(io_error(e)),
+        }
+    }
+
+    async fn list(&self, offset: usize, limit: usize) -> Result<ConversationPage, MemoryError> {
+        let conversations = self.load_all().await?;
+        let infos = conversations.iter().map(ConversationInfo::from).collect();
+        Ok(paginate(infos, offset, limit))
+    }
+
+    async fn search(&self, text: &str, limit: usize) -> Result<Vec<ConversationInfo>, MemoryError> {
+        let conversations = self.load_all().await?;
+        Ok(search_loaded(conversations.iter(), text, limit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversations::tests::exercise_store;
+
+    #[tokio::test]
+    async fn file_store() {
+        let dir = tempfile::tempdir().unwrap();
+        exercise_store(&FileConversationStore::new(dir.path().to_path_buf())).await;
+    }
+
+    #[tokio::test]
+    async fn rejects_path_traversal() {
+        let store = FileConversationStore::new(std::env::temp_dir());
+        assert!(store.load("../etc/passwd").await.is_err());
+    }
+}
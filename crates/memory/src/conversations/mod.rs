@@ -0,0 +1,247 @@
+//! Conversation stores.
+//!
+//! Implementations of [`ConversationStore`]:
+//!
+//! - [`SqliteConversationStore`] — one table in a SQLite database (default)
+//! - [`FileConversationStore`] — one JSON file per conversation
+//! - [`InMemoryConversationStore`] — process-local, for tests and ephemeral use
+//!
+//! [`from_config`] picks one from `[memory.conversations]`.
+
+pub mod file;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+pub use file::FileConversationStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteConversationStore;
+
+use async_trait::async_trait;
+use rustedclaw_config::{AppConfig, ConversationStoreConfig};
+use rustedclaw_core::conversation_store::{ConversationInfo, ConversationPage, ConversationStore};
+use rustedclaw_core::error::MemoryError;
+use rustedclaw_core::message::Conversation;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::info;
+
+/// Open the conversation store described by `config`.
+pub async fn from_config(
+    config: &ConversationStoreConfig,
+) -> Result<Arc<dyn ConversationStore>, MemoryError> {
+    let path = config.path.as_ref().map(PathBuf::from);
+    let store: Arc<dyn ConversationStore> = match config.backend.as_str() {
+        "sqlite" => open_sqlite(path).await?,
+        "file" | "json" => Arc::new(FileConversationStore::new(
+            path.unwrap_or_else(FileConversationStore::default_path),
+        )),
+        "memory" | "in_memory" => Arc::new(InMemoryConversationStore::new()),
+        other => {
+            return Err(MemoryError::Storage(format!(
+                "Unknown conversation store '{other}' (expected sqlite, file or memory)"
+            )));
+        }
+    };
+    info!(store = store.name(), "Conversation store ready");
+    Ok(store)
+}
+
+/// Default SQLite database path: `~/.rustedclaw/conversations.sqlite`.
+pub fn default_sqlite_path() -> PathBuf {
+    AppConfig::config_dir().join("conversations.sqlite")
+}
+
+#[cfg(feature = "sqlite")]
+async fn open_sqlite(path: Option<PathBuf>) -> Result<Arc<dyn ConversationStore>, MemoryError> {
+    let path = path.unwrap_or_else(default_sqlite_path);
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            MemoryError::Storage(format!("Cannot create {}: {e}", parent.display()))
+        })?;
+    }
+    let store = SqliteConversationStore::new(&format!("sqlite://{}", path.display())).await?;
+    Ok(Arc::new(store))
+}
+
+#[cfg(not(feature = "sqlite"))]
+async fn open_sqlite(_path: Option<PathBuf>) -> Result<Arc<dyn ConversationStore>, MemoryError> {
+    Err(MemoryError::Storage(
+        "The sqlite conversation store requires the `sqlite` feature".into(),
+    ))
+}
+
+/// Lowercased title and message text, matched by [`ConversationStore::search`].
+pub(crate) fn searchable_text(conversation: &Conversation) -> String {
+    let mut text = conversation.title.clone().unwrap_or_default();
+    for message in &conversation.messages {
+        text.push('\n');
+        text.push_str(&message.content);
+    }
+    text.to_lowercase()
+}
+
+/// Sort `infos` newest-updated first and cut the requested page.
+pub(crate) fn paginate(
+    mut infos: Vec<ConversationInfo>,
+    offset: usize,
+    limit: usize,
+) -> ConversationPage {
+    infos.sort_by_key(|info| std::cmp::Reverse(info.updated_at));
+    let total = infos.len();
+    ConversationPage {
+        conversations: infos.into_iter().skip(offset).take(limit).collect(),
+        total,
+        offset,
+        limit,
+    }
+}
+
+/// Search a set of loaded conversations.
+pub(crate) fn search_loaded<'a>(
+    conversations: impl Iterator<Item = &'a Conversation>,
+    text: &str,
+    limit: usize,
+) -> Vec<ConversationInfo> {
+    let needle = text.to_lowercase();
+    let matches = conversations
+        .filter(|c| searchable_text(c).contains(&needle))
+        .map(ConversationInfo::from)
+        .collect();
+    paginate(matches, 0, limit).conversations
+}
+
+/// Process-local conversation store. Conversations do not survive restarts.
+#[derive(Default)]
+pub struct InMemoryConversationStore {
+    conversations: RwLock<HashMap<String, Conversation>>,
+}
+
+impl InMemoryConversationStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ConversationStore for InMemoryConversationStore {
+    fn name(&self) -> &str {
+        "in_memory"
+    }
+
+    async fn save(&self, conversation: &Conversation) -> Result<(), MemoryError> {
+        self.conversations
+            .write()
+            .await
+            .insert(conversation.id.to_string(), conversation.clone());
+        Ok(())
+    }
+
+    async fn load(&self, id: &str) -> Result<Option<Conversation>, MemoryError> {
+        Ok(self.conversations.read().await.get(id).cloned())
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool, MemoryError> {
+        Ok(self.conversations.write().await.remove(id).is_some())
+    }
+
+    async fn list(&self, offset: usize, limit: usize) -> Result<ConversationPage, MemoryError> {
+        let conversations = self.conversations.read().await;
+        let infos = conversations.values().map(ConversationInfo::from).collect();
+        Ok(paginate(infos, offset, limit))
+    }
+
+    async fn search(&self, text: &str, limit: usize) -> Result<Vec<ConversationInfo>, MemoryError> {
+        let conversations = self.conversations.read().await;
+        Ok(search_loaded(conversations.values(), text, limit))
+    }
+
+    async fn count(&self) -> Result<usize, MemoryError> {
+        Ok(self.conversations.read().await.len())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+    use rustedclaw_core::message::{ConversationId, Message};
+
+    fn conversation(id: &str, user: &str, age_minutes: i64) -> Conversation {
+        let mut conv = Conversation::new();
+        conv.id = ConversationId::from(id);
+        conv.push(Message::user(user));
+        conv.push(Message::assistant("Noted."));
+        conv.updated_at = Utc::now() - Duration::minutes(age_minutes);
+        conv
+    }
+
+    /// Behaviour every store must share.
+    pub(crate) async fn exercise_store(store: &dyn ConversationStore) {
+        store
+            .save(&conversation("conv-a", "Plan a trip to Lisbon", 30))
+            .await
+            .unwrap();
+        store
+            .save(&conversation("conv-b", "Explain Rust lifetimes", 20))
+            .await
+            .unwrap();
+        store
+            .save(&conversation("conv-c", "Rust async traits", 10))
+            .await
+            .unwrap();
+
+        let loaded = store.load("conv-b").await.unwrap().unwrap();
+        assert_eq!(loaded.messages.len(), 2);
+        assert_eq!(loaded.messages[0].content, "Explain Rust lifetimes");
+        assert!(store.load("missing").await.unwrap().is_none());
+
+        // Pagination, newest first.
+        let page = store.list(1, 1).await.unwrap();
+        assert_eq!(page.total, 3);
+        assert_eq!(page.conversations.len(), 1);
+        assert_eq!(page.conversations[0].id, "conv-b");
+        assert_eq!(store.count().await.unwrap(), 3);
+
+        // Case-insensitive content search.
+        let hits = store.search("RUST", 10).await.unwrap();
+        let ids: Vec<&str> = hits.iter().map(|h| h.id.as_str()).collect();
+        assert_eq!(ids, vec!["conv-c", "conv-b"]);
+
+        // Titles are searchable and survive a reload.
+        assert!(store.set_title("conv-a", "Holiday").await.unwrap());
+        assert!(!store.set_title("missing", "x").await.unwrap());
+        let hits = store.search("holiday", 10).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].title.as_deref(), Some("Holiday"));
+
+        // Saving again replaces rather than duplicates.
+        let mut updated = store.load("conv-a").await.unwrap().unwrap();
+        updated.push(Message::user("And Porto?"));
+        store.save(&updated).await.unwrap();
+        assert_eq!(store.count().await.unwrap(), 3);
+        assert_eq!(
+            store.list(0, 1).await.unwrap().conversations[0].id,
+            "conv-a"
+        );
+
+        assert!(store.delete("conv-a").await.unwrap());
+        assert!(!store.delete("conv-a").await.unwrap());
+        assert_eq!(store.count().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn in_memory_store() {
+        exercise_store(&InMemoryConversationStore::new()).await;
+    }
+
+    #[tokio::test]
+    async fn unknown_store_rejected() {
+        let config = ConversationStoreConfig {
+            backend: "redis".into(),
+            path: None,
+        };
+        assert!(from_config(&config).await.is_err());
+    }
+}
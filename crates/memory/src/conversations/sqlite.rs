@@ -0,0 +1,262 @@
+//! SQLite conversation store.
+//!
+//! Each conversation is one row: listing columns (title, counts,
+//! timestamps, preview), a lowercased `content` column for search, and the
+//! full conversation as JSON in `data`.
+
+use super::searchable_text;
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rustedclaw_core::conversation_store::{ConversationInfo, ConversationPage, ConversationStore};
+use rustedclaw_core::error::MemoryError;
+use rustedclaw_core::message::Conversation;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
+use sqlx::{Row, SqlitePool};
+use std::str::FromStr;
+use tracing::info;
+
+/// Conversations in a SQLite database.
+pub struct SqliteConversationStore {
+    pool: SqlitePool,
+}
+
+impl SqliteConversationStore {
+    /// Open (or create) the database at `path`.
+    ///
+    /// Pass `":memory:"` for an in-process ephemeral database (useful for tests).
+    pub async fn new(path: &str) -> Result<Self, MemoryError> {
+        let options = SqliteConnectOptions::from_str(path)
+            .map_err(|e| MemoryError::Storage(format!("Invalid SQLite path: {e}")))?
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal);
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(4)
+            .connect_with(options)
+            .await
+            .map_err(|e| MemoryError::Storage(format!("Failed to open SQLite: {e}")))?;
+
+        let store = Self::from_pool(pool).await?;
+        info!("SQLite conversation store initialized at {path}");
+        Ok(store)
+    }
+
+    /// Create from an existing pool (useful for testing).
+    pub async fn from_pool(pool: SqlitePool) -> Result<Self, MemoryError> {
+        let store = Self { pool };
+        store.run_migrations().await?;
+        Ok(store)
+    }
+
+    async fn run_migrations(&self) -> Result<(), MemoryError> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS conversations (
+                id            TEXT PRIMARY KEY,
+                title         TEXT,
+                message_count INTEGER NOT NULL,
+                preview       TEXT,
+                created_at    TEXT NOT NULL,
+                updated_at    TEXT NOT NULL,
+                content       TEXT NOT NULL,
+                data          TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| MemoryError::MigrationFailed(format!("conversations table: {e}")))?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_conversations_updated_at \
+             ON conversations(updated_at DESC)",
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| MemoryError::MigrationFailed(format!("updated_at index: {e}")))?;
+
+        Ok(())
+    }
+
+    /// Fixed-width RFC 3339 so timestamps sort lexicographically.
+    fn timestamp(t: DateTime<Utc>) -> String {
+        t.to_rfc3339_opts(SecondsFormat::Micros, true)
+    }
+
+    fn row_to_info(row: &sqlx::sqlite::SqliteRow) -> Result<ConversationInfo, MemoryError> {
+        let column = |e: sqlx::Error| MemoryError::QueryFailed(format!("conversation row: {e}"));
+        let parse_time = |s: String| {
+            DateTime::parse_from_rfc3339(&s)
+                .map(|dt| dt.with_timezone(&Utc))
+                .map_err(|e| MemoryError::QueryFailed(format!("conversation timestamp: {e}")))
+        };
+        Ok(ConversationInfo {
+            id: row.try_get("id").map_err(column)?,
+            title: row.try_get("title").map_err(column)?,
+            message_count: row.try_get::<i64, _>("message_count").map_err(column)? as usize,
+            created_at: parse_time(row.try_get("created_at").map_err(column)?)?,
+            updated_at: parse_time(row.try_get("updated_at").map_err(column)?)?,
+            preview: row.try_get("preview").map_err(column)?,
+        })
+    }
+}
+
+const INFO_COLUMNS: &str = "id, title, message_count, preview, created_at, updated_at";
+
+#[async_trait]
+impl ConversationStore for SqliteConversationStore {
+    fn name(&self) -> &str {
+        "sqlite"
+    }
+
+    async fn save(&self, conversation: &Conversation) -> Result<(), MemoryError> {
+        let info = ConversationInfo::from(conversation);
+        let data = serde_json::to_string(conversation)
+            .map_err(|e| MemoryError::Storage(format!("Conversation serialization: {e}")))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO conversations
+                (id, title, message_count, preview, created_at, updated_at, content, data)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT(id) DO UPDATE SET
+                title = excluded.title,
+                message_count = excluded.message_count,
+                preview = excluded.preview,
+                updated_at = excluded.updated_at,
+                content = excluded.content,
+                data = excluded.data
+            "#,
+        )
+        .bind(&info.id)
+        .bind(&info.title)
+        .bind(info.message_count as i64)
+        .bind(&info.preview)
+        .bind(Self::timestamp(info.created_at))
+        .bind(Self::timestamp(info.updated_at))
+        .bind(searchable_text(conversation))
+        .bind(data)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| MemoryError::Storage(format!("Save conversation: {e}")))?;
+        Ok(())
+    }
+
+    async fn load(&self, id: &str) -> Result<Option<Conversation>, MemoryError> {
+        let row = sqlx::query("SELECT data FROM conversations WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| MemoryError::QueryFailed(format!("Load conversation: {e}")))?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let data: String = row
+            .try_get("data")
+            .map_err(|e| MemoryError::QueryFailed(format!("data column: {e}")))?;
+        serde_json::from_str(&data)
+            .map(Some)
+            .map_err(|e| MemoryError::QueryFailed(format!("Conversation format: {e}")))
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool, MemoryError> {
+        let result = sqlx::query("DELETE FROM conversations WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| MemoryError::Storage(format!("Delete conversation: {e}")))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list(&self, offset: usize, limit: usize) -> Result<ConversationPage, MemoryError> {
+        let rows = sqlx::query(&format!(
+            "SELECT {INFO_COLUMNS} FROM conversations \
+             ORDER BY updated_at DESC LIMIT ?1 OFFSET ?2"
+        ))
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| MemoryError::QueryFailed(format!("List conversations: {e}")))?;
+
+        Ok(ConversationPage {
+            conversations: rows
+                .iter()
+                .map(Self::row_to_info)
+                .collect::<Result<_, _>>()?,
+            total: self.count().await?,
+            offset,
+            limit,
+        })
+    }
+
+    async fn search(&self, text: &str, limit: usize) -> Result<Vec<ConversationInfo>, MemoryError> {
+        // Escape LIKE wildcards so the text matches literally.
+        let escaped = text
+            .to_lowercase()
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        let rows = sqlx::query(&format!(
+            "SELECT {INFO_COLUMNS} FROM conversations \
+             WHERE content LIKE ?1 ESCAPE '\\' \
+             ORDER BY updated_at DESC LIMIT ?2"
+        ))
+        .bind(format!("%{escaped}%"))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| MemoryError::QueryFailed(format!("Search conversations: {e}")))?;
+
+        rows.iter().map(Self::row_to_info).collect()
+    }
+
+    async fn count(&self) -> Result<usize, MemoryError> {
+        let row = sqlx::query("SELECT COUNT(*) AS n FROM conversations")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| MemoryError::QueryFailed(format!("Count conversations: {e}")))?;
+        let n: i64 = row
+            .try_get("n")
+            .map_err(|e| MemoryError::QueryFailed(format!("count column: {e}")))?;
+        Ok(n as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversations::tests::exercise_store;
+    use rustedclaw_core::message::Message;
+
+    #[tokio::test]
+    async fn sqlite_store() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        exercise_store(&SqliteConversationStore::from_pool(pool).await.unwrap()).await;
+    }
+
+    #[tokio::test]
+    async fn persists_across_reopen_and_matches_wildcards_literally() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}", dir.path().join("conv.sqlite").display());
+
+        let mut conv = Conversation::new();
+        conv.push(Message::user("Discount is 50% today"));
+        SqliteConversationStore::new(&url)
+            .await
+            .unwrap()
+            .save(&conv)
+            .await
+            .unwrap();
+
+        let store = SqliteConversationStore::new(&url).await.unwrap();
+        assert_eq!(store.count().await.unwrap(), 1);
+        assert_eq!(store.search("50%", 10).await.unwrap().len(), 1);
+        assert!(store.search("5_%", 10).await.unwrap().is_empty());
+    }
+}
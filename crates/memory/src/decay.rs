@@ -0,0 +1,195 @@
+//! Decay-aware ranking, access tracking and compaction shared by the
+//! backends.
+//!
+//! A backend answering a recall fetches [`candidate_limit`] entries by
+//! relevance, lets [`finish`] re-rank them with its [`DecayPolicy`] and cut
+//! them to the requested limit, and then records an access for each entry
+//! it returns. [`spawn_compaction`] periodically asks a backend to forget
+//! what has expired or gone stale.
+
+use chrono::{DateTime, Utc};
+use rustedclaw_core::memory::{DecayPolicy, MemoryBackend, MemoryEntry, MemoryQuery};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Candidates fetched per requested result when the decay policy may
+/// reorder them.
+pub const RANK_OVERFETCH: usize = 4;
+
+/// How many entries to fetch by relevance for `query`.
+pub fn candidate_limit(query: &MemoryQuery, decay: &DecayPolicy) -> usize {
+    if query.is_recall() && decay.reranks() {
+        query.limit.saturating_mul(RANK_OVERFETCH)
+    } else {
+        query.limit
+    }
+}
+
+/// Rank relevance-ordered `results` with `decay` (recalls only) and keep
+/// the query's limit.
+pub fn finish(
+    query: &MemoryQuery,
+    decay: &DecayPolicy,
+    mut results: Vec<MemoryEntry>,
+    now: DateTime<Utc>,
+) -> Vec<MemoryEntry> {
+    if query.is_recall() {
+        decay.rank(&mut results, now);
+    }
+    results.truncate(query.limit);
+    results
+}
+
+/// Record an access of every entry in `hits`, both in `stored` (the
+/// backend's own copies) and in the copies handed back to the caller.
+pub fn touch(stored: &mut [MemoryEntry], hits: &mut [MemoryEntry], now: DateTime<Utc>) {
+    for hit in hits.iter_mut() {
+        if let Some(entry) = stored.iter_mut().find(|e| e.id == hit.id) {
+            entry.touch(now);
+            hit.last_accessed = entry.last_accessed;
+            hit.access_count = entry.access_count;
+        }
+    }
+}
+
+/// Whether `decay` lets compaction forget `entry`.
+pub fn is_stale(decay: &DecayPolicy, entry: &MemoryEntry, now: DateTime<Utc>) -> bool {
+    decay.is_stale(
+        entry.last_accessed,
+        entry.access_count,
+        entry.expires_at,
+        now,
+    )
+}
+
+/// Run [`MemoryBackend::compact`] on `memory` every `every`, starting one
+/// period from now.
+pub fn spawn_compaction(memory: Arc<dyn MemoryBackend>, every: Duration) -> JoinHandle<()> {
+    info!(every_secs = every.as_secs(), "Memory compaction scheduled");
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + every, every);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            match memory.compact().await {
+                Ok(0) => debug!("Memory compaction found nothing to forget"),
+                Ok(forgotten) => info!(forgotten, "Memory compaction forgot stale entries"),
+                Err(e) => warn!("Memory compaction failed: {e}"),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryBackend;
+    use rustedclaw_core::memory::{MemoryScope, SearchMode};
+
+    fn entry(content: &str, idle_days: i64, access_count: u32) -> MemoryEntry {
+        let at = Utc::now() - chrono::Duration::days(idle_days);
+        MemoryEntry {
+            id: content.into(),
+            content: content.into(),
+            tags: vec![],
+            source: None,
+            created_at: at,
+            last_accessed: at,
+            access_count,
+            expires_at: None,
+            score: 1.0,
+            embedding: None,
+            embedding_model: None,
+            scope: MemoryScope::default(),
+        }
+    }
+
+    fn recall(text: &str, limit: usize) -> MemoryQuery {
+        MemoryQuery {
+            text: text.into(),
+            limit,
+            min_score: 0.0,
+            tags: vec![],
+            mode: SearchMode::Keyword,
+            embedding: None,
+            scope: MemoryScope::default(),
+        }
+    }
+
+    #[test]
+    fn recent_and_frequent_entries_rank_first() {
+        let decay = DecayPolicy::default();
+        let now = Utc::now();
+        let results = vec![
+            entry("stale", 120, 0),
+            entry("fresh", 0, 0),
+            entry("popular", 120, 20),
+        ];
+
+        let ranked = finish(&recall("x", 2), &decay, results.clone(), now);
+        let ids: Vec<&str> = ranked.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["fresh", "popular"]);
+
+        // Listings keep their order and are not re-scored.
+        let listed = finish(&recall("", 3), &decay, results, now);
+        assert_eq!(listed[0].id, "stale");
+        assert_eq!(listed[0].score, 1.0);
+    }
+
+    #[test]
+    fn neutral_policy_keeps_relevance_order() {
+        let decay = DecayPolicy {
+            recency_weight: 0.0,
+            frequency_weight: 0.0,
+            ..DecayPolicy::default()
+        };
+        let query = recall("x", 5);
+        assert_eq!(candidate_limit(&query, &decay), 5);
+        let ranked = finish(
+            &query,
+            &decay,
+            vec![entry("stale", 120, 0), entry("fresh", 0, 0)],
+            Utc::now(),
+        );
+        assert_eq!(ranked[0].id, "stale");
+        assert_eq!(
+            candidate_limit(&query, &DecayPolicy::default()),
+            5 * RANK_OVERFETCH
+        );
+    }
+
+    #[test]
+    fn value_halves_every_half_life() {
+        let decay = DecayPolicy {
+            half_life_hours: 24.0,
+            forget_below: 0.2,
+            ..DecayPolicy::default()
+        };
+        let now = Utc::now();
+        let two_days_ago = now - chrono::Duration::days(2);
+        assert!((decay.value(two_days_ago, 0, now) - 0.25).abs() < 1e-3);
+        assert!((decay.value(two_days_ago, 3, now) - 1.0).abs() < 1e-3);
+
+        assert!(!decay.is_stale(two_days_ago, 0, None, now));
+        assert!(decay.is_stale(now - chrono::Duration::days(3), 0, None, now));
+        assert!(decay.is_stale(now, 5, Some(now), now), "expired");
+    }
+
+    #[tokio::test]
+    async fn compaction_job_runs_periodically() {
+        let memory = Arc::new(InMemoryBackend::new());
+        let mut expired = entry("expired", 0, 0);
+        expired.expires_at = Some(Utc::now() - chrono::Duration::seconds(1));
+        memory.store(expired).await.unwrap();
+        memory.store(entry("kept", 0, 0)).await.unwrap();
+
+        let job = spawn_compaction(memory.clone(), Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        job.abort();
+
+        assert_eq!(memory.count().await.unwrap(), 1);
+        assert!(memory.get("kept").await.unwrap().is_some());
+    }
+}
@@ -0,0 +1,720 @@
+//! Embedding pipeline for stored memories.
+//!
+//! [`EmbeddingMemory`] wraps any [`MemoryBackend`] and embeds entries on the
+//! way in through the configured [`Provider::embed`]:
+//!
+//! - Concurrent `store` calls are coalesced into batched embedding requests,
+//!   and `store_batch` embeds its entries `batch_size` at a time.
+//! - Updates that change an entry's content re-embed it.
+//! - If embedding fails the entry is stored without a vector and a
+//!   background task retries it with exponential backoff.
+//! - Vector and hybrid searches get their query text embedded.
+//! - Each entry records the model that embedded it, so
+//!   [`EmbeddingMemory::reembed`] can refresh entries after the configured
+//!   model changes.
+
+use async_trait::async_trait;
+use rustedclaw_config::MemoryConfig;
+use rustedclaw_core::error::MemoryError;
+use rustedclaw_core::memory::{
+    MemoryBackend, MemoryEntry, MemoryFilter, MemoryPatch, MemoryQuery, SearchMode,
+};
+use rustedclaw_core::provider::{EmbeddingRequest, Provider};
+use std::collections::HashSet;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, info, warn};
+
+/// Default number of texts per embedding request.
+const DEFAULT_BATCH_SIZE: usize = 32;
+
+/// How long the batcher waits for more texts before sending a request.
+const DEFAULT_BATCH_WINDOW: Duration = Duration::from_millis(10);
+
+/// A memory backend that embeds entries before storing them.
+pub struct EmbeddingMemory {
+    inner: Arc<dyn MemoryBackend>,
+    embedder: Arc<Embedder>,
+    batch_size: usize,
+    batch_window: Duration,
+    retry: RetryPolicy,
+    workers: OnceLock<Workers>,
+}
+
+/// Backoff for entries whose embedding failed at store time.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Attempts before the pending entries are given up on (they can still
+    /// be embedded later with `rustedclaw memory reembed`).
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled after every failure.
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between retries.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 6,
+            initial_backoff: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(120),
+        }
+    }
+}
+
+/// Outcome of [`EmbeddingMemory::reembed`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReembedReport {
+    /// Entries examined.
+    pub scanned: usize,
+    /// Entries that were (re-)embedded with the current model.
+    pub reembedded: usize,
+}
+
+/// The provider and model used for every embedding request.
+struct Embedder {
+    provider: Arc<dyn Provider>,
+    model: String,
+}
+
+impl Embedder {
+    async fn embed(&self, inputs: Vec<String>) -> Result<Vec<Vec<f32>>, MemoryError> {
+        let expected = inputs.len();
+        let response = self
+            .provider
+            .embed(EmbeddingRequest {
+                model: self.model.clone(),
+                inputs,
+            })
+            .await
+            .map_err(|e| MemoryError::EmbeddingFailed(e.to_string()))?;
+        if response.embeddings.len() != expected {
+            return Err(MemoryError::EmbeddingFailed(format!(
+                "{} returned {} embeddings for {expected} inputs",
+                self.provider.name(),
+                response.embeddings.len()
+            )));
+        }
+        Ok(response.embeddings)
+    }
+
+    /// Embed `entries` and store them in `backend`, `batch_size` at a time.
+    /// Returns how many were stored before the first failure.
+    async fn embed_and_store(
+        &self,
+        backend: &dyn MemoryBackend,
+        entries: Vec<MemoryEntry>,
+        batch_size: usize,
+    ) -> Result<usize, MemoryError> {
+        let mut stored = 0;
+        let mut entries = entries.into_iter().peekable();
+        while entries.peek().is_some() {
+            let batch: Vec<MemoryEntry> = entries.by_ref().take(batch_size.max(1)).collect();
+            let vectors = self
+                .embed(batch.iter().map(|e| e.content.clone()).collect())
+                .await?;
+            for (mut entry, vector) in batch.into_iter().zip(vectors) {
+                entry.embedding = Some(vector);
+                entry.embedding_model = Some(self.model.clone());
+                backend.store(entry).await?;
+                stored += 1;
+            }
+        }
+        Ok(stored)
+    }
+}
+
+/// One text waiting to be embedded by the batcher.
+struct EmbedJob {
+    text: String,
+    reply: oneshot::Sender<Result<Vec<f32>, MemoryError>>,
+}
+
+/// Channels into the background tasks, started on first use.
+struct Workers {
+    jobs: mpsc::UnboundedSender<EmbedJob>,
+    retries: mpsc::UnboundedSender<String>,
+}
+
+impl EmbeddingMemory {
+    /// Embed entries stored in `inner` with `model` from `provider`.
+    pub fn new(
+        inner: Arc<dyn MemoryBackend>,
+        provider: Arc<dyn Provider>,
+        model: impl Into<String>,
+    ) -> Self {
+        Self {
+            inner,
+            embedder: Arc::new(Embedder {
+                provider,
+                model: model.into(),
+            }),
+            batch_size: DEFAULT_BATCH_SIZE,
+            batch_window: DEFAULT_BATCH_WINDOW,
+            retry: RetryPolicy::default(),
+            workers: OnceLock::new(),
+        }
+    }
+
+    /// Build from `[memory]` settings (`embedding_model`,
+    /// `embedding_batch_size`).
+    pub fn from_config(
+        inner: Arc<dyn MemoryBackend>,
+        provider: Arc<dyn Provider>,
+        config: &MemoryConfig,
+    ) -> Self {
+        Self::new(inner, provider, config.embedding_model.clone())
+            .with_batch_size(config.embedding_batch_size)
+    }
+
+    /// Maximum number of texts per embedding request.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// How long to wait for more texts before sending a partial batch.
+    pub fn with_batch_window(mut self, window: Duration) -> Self {
+        self.batch_window = window;
+        self
+    }
+
+    /// Backoff for entries whose embedding failed.
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// The embedding model new entries are embedded with.
+    pub fn model(&self) -> &str {
+        &self.embedder.model
+    }
+
+    /// Embed every entry that has no embedding or was embedded by a
+    /// different model (or every entry, with `all`).
+    pub async fn reembed(&self, all: bool) -> Result<ReembedReport, MemoryError> {
+        let total = self.inner.count().await?;
+        let entries = self.inner.list(&MemoryFilter::default(), 0, total).await?;
+        let scanned = entries.len();
+        let stale: Vec<MemoryEntry> = entries
+            .into_iter()
+            .filter(|e| all || e.embedding_model.as_deref() != Some(self.model()))
+            .collect();
+        info!(
+            scanned,
+            stale = stale.len(),
+            model = self.model(),
+            "Re-embedding memories"
+        );
+        let reembedded = self
+            .embedder
+            .embed_and_store(self.inner.as_ref(), stale, self.batch_size)
+            .await?;
+        Ok(ReembedReport {
+            scanned,
+            reembedded,
+        })
+    }
+
+    fn workers(&self) -> &Workers {
+        self.workers.get_or_init(|| {
+            let (jobs, job_rx) = mpsc::unbounded_channel();
+            let (retries, retry_rx) = mpsc::unbounded_channel();
+            tokio::spawn(run_batcher(
+                self.embedder.clone(),
+                job_rx,
+                self.batch_size,
+                self.batch_window,
+            ));
+            tokio::spawn(run_retries(
+                self.inner.clone(),
+                self.embedder.clone(),
+                retry_rx,
+                self.retry,
+                self.batch_size,
+            ));
+            Workers { jobs, retries }
+        })
+    }
+
+    /// Embed `entry` if it has content but no embedding. Returns `false`
+    /// when embedding failed and the entry should be retried once stored.
+    async fn embed_entry(&self, entry: &mut MemoryEntry) -> bool {
+        if entry.embedding.is_some() || entry.content.trim().is_empty() {
+            return true;
+        }
+        match self.embed_text(entry.content.clone()).await {
+            Ok(vector) => {
+                entry.embedding = Some(vector);
+                entry.embedding_model = Some(self.model().to_string());
+                true
+            }
+            Err(e) => {
+                warn!("Storing memory without embedding, will retry: {e}");
+                false
+            }
+        }
+    }
+
+    /// Embed one text through the batcher.
+    async fn embed_text(&self, text: String) -> Result<Vec<f32>, MemoryError> {
+        let (reply, response) = oneshot::channel();
+        self.workers()
+            .jobs
+            .send(EmbedJob { text, reply })
+            .map_err(|_| MemoryError::EmbeddingFailed("embedding worker stopped".into()))?;
+        response
+            .await
+            .map_err(|_| MemoryError::EmbeddingFailed("embedding worker stopped".into()))?
+    }
+}
+
+/// Collect jobs into batches of up to `batch_size`, waiting at most
+/// `window` after the first job, and embed each batch in one request.
+async fn run_batcher(
+    embedder: Arc<Embedder>,
+    mut jobs: mpsc::UnboundedReceiver<EmbedJob>,
+    batch_size: usize,
+    window: Duration,
+) {
+    while let Some(first) = jobs.recv().await {
+        let mut batch = vec![first];
+        let deadline = tokio::time::Instant::now() + window;
+        while batch.len() < batch_size {
+            match tokio::time::timeout_at(deadline, jobs.recv()).await {
+                Ok(Some(job)) => batch.push(job),
+                _ => break,
+            }
+        }
+
+        debug!(size = batch.len(), "Embedding batch");
+        let texts = batch.iter().map(|job| job.text.clone()).collect();
+        match embedder.embed(texts).await {
+            Ok(vectors) => {
+                for (job, vector) in batch.into_iter().zip(vectors) {
+                    let _ = job.reply.send(Ok(vector));
+                }
+            }
+            Err(e) => {
+                let message = e.to_string();
+                for job in batch {
+                    let _ = job
+                        .reply
+                        .send(Err(MemoryError::EmbeddingFailed(message.clone())));
+                }
+            }
+        }
+    }
+}
+
+/// Re-embed entries stored without a vector. Failures back off
+/// exponentially; after `max_attempts` the pending entries are dropped
+/// from the queue.
+async fn run_retries(
+    backend: Arc<dyn MemoryBackend>,
+    embedder: Arc<Embedder>,
+    mut ids: mpsc::UnboundedReceiver<String>,
+    policy: RetryPolicy,
+    batch_size: usize,
+) {
+    let mut pending: HashSet<String> = HashSet::new();
+    let mut delay = policy.initial_backoff;
+    let mut attempts = 0;
+    let mut open = true;
+
+    loop {
+        if pending.is_empty() {
+            if !open {
+                return;
+            }
+            match ids.recv().await {
+                Some(id) => pending.insert(id),
+                None => return,
+            };
+            delay = policy.initial_backoff;
+            attempts = 0;
+        }
+
+        // Wait out the backoff, collecting further failures meanwhile.
+        let sleep = tokio::time::sleep(delay);
+        tokio::pin!(sleep);
+        loop {
+            tokio::select! {
+                _ = &mut sleep => break,
+                id = ids.recv(), if open => match id {
+                    Some(id) => {
+                        pending.insert(id);
+                    }
+                    None => open = false,
+                },
+            }
+        }
+
+        match retry_pending(backend.as_ref(), &embedder, &mut pending, batch_size).await {
+            Ok(()) => {
+                delay = policy.initial_backoff;
+                attempts = 0;
+            }
+            Err(e) => {
+                attempts += 1;
+                if attempts >= policy.max_attempts {
+                    warn!(
+                        pending = pending.len(),
+                        "Giving up on embedding memories after {attempts} attempts: {e}; \
+                         run `rustedclaw memory reembed` once the provider is reachable"
+                    );
+                    pending.clear();
+                } else {
+                    warn!(pending = pending.len(), "Embedding retry failed: {e}");
+                    delay = (delay * 2).min(policy.max_backoff);
+                }
+            }
+        }
+    }
+}
+
+/// Embed the pending entries that still need it, removing each from
+/// `pending` once it is stored (or deleted, or already embedded).
+async fn retry_pending(
+    backend: &dyn MemoryBackend,
+    embedder: &Embedder,
+    pending: &mut HashSet<String>,
+    batch_size: usize,
+) -> Result<(), MemoryError> {
+    let mut entries = Vec::new();
+    for id in pending.iter() {
+        match backend.get(id).await? {
+            Some(entry) if entry.embedding_model.as_deref() != Some(embedder.model.as_str()) => {
+                entries.push(entry)
+            }
+            _ => {}
+        }
+    }
+    pending.retain(|id| entries.iter().any(|e| &e.id == id));
+
+    for chunk in entries.chunks(batch_size.max(1)) {
+        embedder
+            .embed_and_store(backend, chunk.to_vec(), batch_size)
+            .await?;
+        for entry in chunk {
+            pending.remove(&entry.id);
+        }
+    }
+    debug!("Embedding retry succeeded");
+    Ok(())
+}
+
+#[async_trait]
+impl MemoryBackend for EmbeddingMemory {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn store(&self, mut entry: MemoryEntry) -> Result<String, MemoryError> {
+        let embedded = self.embed_entry(&mut entry).await;
+        let id = self.inner.store(entry).await?;
+        if !embedded {
+            let _ = self.workers().retries.send(id.clone());
+        }
+        Ok(id)
+    }
+
+    async fn search(&self, mut query: MemoryQuery) -> Result<Vec<MemoryEntry>, MemoryError> {
+        let wants_vector = !matches!(query.mode, SearchMode::Keyword);
+        if wants_vector && query.embedding.is_none() && !query.text.trim().is_empty() {
+            match self.embed_text(query.text.clone()).await {
+                Ok(vector) => query.embedding = Some(vector),
+                Err(e) => warn!("Query embedding failed, searching by keyword: {e}"),
+            }
+        }
+        self.inner.search(query).await
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool, MemoryError> {
+        self.inner.delete(id).await
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<MemoryEntry>, MemoryError> {
+        self.inner.get(id).await
+    }
+
+    async fn count(&self) -> Result<usize, MemoryError> {
+        self.inner.count().await
+    }
+
+    async fn clear(&self) -> Result<(), MemoryError> {
+        self.inner.clear().await
+    }
+
+    async fn compact(&self) -> Result<usize, MemoryError> {
+        self.inner.compact().await
+    }
+
+    async fn update(
+        &self,
+        id: &str,
+        patch: MemoryPatch,
+    ) -> Result<Option<MemoryEntry>, MemoryError> {
+        let Some(mut entry) = self.inner.update(id, patch).await? else {
+            return Ok(None);
+        };
+        if entry.embedding.is_none() {
+            if self.embed_entry(&mut entry).await {
+                self.inner.store(entry.clone()).await?;
+            } else {
+                let _ = self.workers().retries.send(entry.id.clone());
+            }
+        }
+        Ok(Some(entry))
+    }
+
+    async fn list(
+        &self,
+        filter: &MemoryFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<MemoryEntry>, MemoryError> {
+        self.inner.list(filter, offset, limit).await
+    }
+
+    async fn store_batch(&self, mut entries: Vec<MemoryEntry>) -> Result<Vec<String>, MemoryError> {
+        let missing: Vec<usize> = (0..entries.len())
+            .filter(|&i| entries[i].embedding.is_none() && !entries[i].content.trim().is_empty())
+            .collect();
+        for chunk in missing.chunks(self.batch_size.max(1)) {
+            let texts = chunk.iter().map(|&i| entries[i].content.clone()).collect();
+            match self.embedder.embed(texts).await {
+                Ok(vectors) => {
+                    for (&i, vector) in chunk.iter().zip(vectors) {
+                        entries[i].embedding = Some(vector);
+                        entries[i].embedding_model = Some(self.model().to_string());
+                    }
+                }
+                Err(e) => warn!(
+                    "Storing {} memories without embedding, will retry: {e}",
+                    chunk.len()
+                ),
+            }
+        }
+        let failed: Vec<usize> = missing
+            .into_iter()
+            .filter(|&i| entries[i].embedding.is_none())
+            .collect();
+
+        let ids = self.inner.store_batch(entries).await?;
+        for i in failed {
+            let _ = self.workers().retries.send(ids[i].clone());
+        }
+        Ok(ids)
+    }
+
+    async fn delete_where(&self, filter: &MemoryFilter) -> Result<usize, MemoryError> {
+        self.inner.delete_where(filter).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryBackend;
+    use chrono::Utc;
+    use rustedclaw_core::error::ProviderError;
+    use rustedclaw_core::memory::MemoryScope;
+    use rustedclaw_core::provider::{EmbeddingResponse, ProviderRequest, ProviderResponse};
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Embeds "rust" text on one axis and everything else on another,
+    /// recording the size of every request. Fails while `down` is set.
+    #[derive(Default)]
+    struct AxisEmbedder {
+        requests: Mutex<Vec<usize>>,
+        down: AtomicBool,
+    }
+
+    #[async_trait]
+    impl Provider for AxisEmbedder {
+        fn name(&self) -> &str {
+            "axis"
+        }
+
+        async fn complete(&self, _: ProviderRequest) -> Result<ProviderResponse, ProviderError> {
+            unimplemented!()
+        }
+
+        async fn embed(
+            &self,
+            request: EmbeddingRequest,
+        ) -> Result<EmbeddingResponse, ProviderError> {
+            if self.down.load(Ordering::SeqCst) {
+                return Err(ProviderError::Network("connection refused".into()));
+            }
+            self.requests.lock().unwrap().push(request.inputs.len());
+            let embeddings = request
+                .inputs
+                .iter()
+                .map(|text| {
+                    if text.to_lowercase().contains("rust") {
+                        vec![1.0, 0.0]
+                    } else {
+                        vec![0.0, 1.0]
+                    }
+                })
+                .collect();
+            Ok(EmbeddingResponse {
+                embeddings,
+                model: request.model,
+                usage: None,
+            })
+        }
+    }
+
+    fn entry(content: &str) -> MemoryEntry {
+        MemoryEntry {
+            id: String::new(),
+            content: content.into(),
+            tags: vec![],
+            source: None,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            access_count: 0,
+            expires_at: None,
+            score: 0.0,
+            embedding: None,
+            embedding_model: None,
+            scope: MemoryScope::default(),
+        }
+    }
+
+    fn query(text: &str, mode: SearchMode) -> MemoryQuery {
+        MemoryQuery {
+            text: text.into(),
+            limit: 10,
+            min_score: 0.0,
+            tags: vec![],
+            mode,
+            embedding: None,
+            scope: MemoryScope::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn stores_embeddings_and_batches_concurrent_writes() {
+        let provider = Arc::new(AxisEmbedder::default());
+        let inner = Arc::new(InMemoryBackend::new());
+        let memory = Arc::new(
+            EmbeddingMemory::new(inner.clone(), provider.clone(), "axis-1")
+                .with_batch_window(Duration::from_millis(50)),
+        );
+
+        let writes = ["Rust ownership", "Sourdough bread", "Rust traits"].map(|text| {
+            let memory = memory.clone();
+            tokio::spawn(async move { memory.store(entry(text)).await.unwrap() })
+        });
+        let mut ids = Vec::new();
+        for write in writes {
+            ids.push(write.await.unwrap());
+        }
+
+        assert_eq!(*provider.requests.lock().unwrap(), vec![3]);
+        let stored = inner.get(&ids[0]).await.unwrap().unwrap();
+        assert_eq!(stored.embedding, Some(vec![1.0, 0.0]));
+        assert_eq!(stored.embedding_model.as_deref(), Some("axis-1"));
+    }
+
+    #[tokio::test]
+    async fn vector_search_embeds_the_query() {
+        let provider = Arc::new(AxisEmbedder::default());
+        let memory = EmbeddingMemory::new(Arc::new(InMemoryBackend::new()), provider, "axis-1");
+        memory.store(entry("Sourdough bread")).await.unwrap();
+        memory.store(entry("Rust borrow checker")).await.unwrap();
+
+        // No keyword overlap; only the embedding can find it.
+        let results = memory
+            .search(query("rustacean habits", SearchMode::Vector))
+            .await
+            .unwrap();
+        assert_eq!(results[0].content, "Rust borrow checker");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_embeddings_are_retried_in_the_background() {
+        let provider = Arc::new(AxisEmbedder::default());
+        provider.down.store(true, Ordering::SeqCst);
+        let inner = Arc::new(InMemoryBackend::new());
+        let memory = EmbeddingMemory::new(inner.clone(), provider.clone(), "axis-1");
+
+        let id = memory.store(entry("Rust macros")).await.unwrap();
+        assert!(inner.get(&id).await.unwrap().unwrap().embedding.is_none());
+
+        // First retry fails, the backed-off second one succeeds.
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        provider.down.store(false, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_secs(5)).await;
+
+        let stored = inner.get(&id).await.unwrap().unwrap();
+        assert_eq!(stored.embedding, Some(vec![1.0, 0.0]));
+    }
+
+    #[tokio::test]
+    async fn batches_and_updates_are_embedded() {
+        let provider = Arc::new(AxisEmbedder::default());
+        let inner = Arc::new(InMemoryBackend::new());
+        let memory =
+            EmbeddingMemory::new(inner.clone(), provider.clone(), "axis-1").with_batch_size(2);
+
+        let ids = memory
+            .store_batch(vec![
+                entry("Rust ownership"),
+                entry("Sourdough bread"),
+                entry("Rust traits"),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(*provider.requests.lock().unwrap(), vec![2, 1]);
+        let stored = inner.get(&ids[1]).await.unwrap().unwrap();
+        assert_eq!(stored.embedding, Some(vec![0.0, 1.0]));
+
+        let updated = memory
+            .update(
+                &ids[1],
+                MemoryPatch {
+                    content: Some("Rust async".into()),
+                    ..MemoryPatch::default()
+                },
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.embedding, Some(vec![1.0, 0.0]));
+        let stored = inner.get(&ids[1]).await.unwrap().unwrap();
+        assert_eq!(stored.embedding, Some(vec![1.0, 0.0]));
+        assert_eq!(stored.embedding_model.as_deref(), Some("axis-1"));
+    }
+
+    #[tokio::test]
+    async fn reembed_refreshes_entries_from_other_models() {
+        let provider = Arc::new(AxisEmbedder::default());
+        let inner = Arc::new(InMemoryBackend::new());
+        EmbeddingMemory::new(inner.clone(), provider.clone(), "axis-1")
+            .store(entry("Rust lifetimes"))
+            .await
+            .unwrap();
+        inner.store(entry("Added before embeddings")).await.unwrap();
+
+        let upgraded = EmbeddingMemory::new(inner.clone(), provider, "axis-2");
+        let report = upgraded.reembed(false).await.unwrap();
+        assert_eq!(
+            report,
+            ReembedReport {
+                scanned: 2,
+                reembedded: 2
+            }
+        );
+        assert_eq!(upgraded.reembed(false).await.unwrap().reembedded, 0);
+        assert_eq!(upgraded.reembed(true).await.unwrap().reembedded, 2);
+        assert_eq!(inner.count().await.unwrap(), 2);
+    }
+}
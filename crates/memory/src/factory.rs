@@ -0,0 +1,248 @@
+//! Build the configured memory backend.
+//!
+//! `[memory] backend` selects the implementation:
+//!
+//! | backend              | storage                                               |
+//! |----------------------|-------------------------------------------------------|
+//! | `sqlite` (default)   | `path`, default `~/.rustedclaw/memory.sqlite`         |
+//! | `file` / `jsonl`     | `path`, default `~/.rustedclaw/memory/memories.jsonl` |
+//! | `postgres`           | `url`, or the `DATABASE_URL` environment variable     |
+//! | `memory`             | process memory only, lost on exit                     |
+//! | `none`               | nothing is stored                                     |
+//!
+//! [`with_embeddings`] then wraps the backend in the embedding pipeline when
+//! `embedding_provider` names a configured provider.
+//!
+//! Every backend ranks and forgets with the [`DecayPolicy`] built from
+//! `[memory.decay]`; [`spawn_compaction_from_config`] schedules the periodic
+//! compaction.
+//!
+//! The gateway, the agent runtime, the `memory_search` tool and the
+//! `rustedclaw memory` commands all go through [`from_config`], so they see
+//! the same entries.
+
+use crate::{EmbeddingMemory, FileBackend, InMemoryBackend, NoopMemory};
+use rustedclaw_config::{AppConfig, MemoryConfig};
+use rustedclaw_core::error::MemoryError;
+use rustedclaw_core::memory::{DecayPolicy, MemoryBackend};
+use rustedclaw_core::provider::Provider;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Open the backend described by `config`.
+pub async fn from_config(config: &MemoryConfig) -> Result<Arc<dyn MemoryBackend>, MemoryError> {
+    let backend: Arc<dyn MemoryBackend> = match config.backend.as_str() {
+        "sqlite" => open_sqlite(config).await?,
+        "file" | "jsonl" => {
+            let path = config
+                .path
+                .as_ref()
+                .map(PathBuf::from)
+                .unwrap_or_else(FileBackend::default_path);
+            Arc::new(FileBackend::new(path).with_decay(decay_policy(config)))
+        }
+        "postgres" | "postgresql" => open_postgres(config).await?,
+        "memory" | "in_memory" => Arc::new(InMemoryBackend::new().with_decay(decay_policy(config))),
+        "none" | "noop" => Arc::new(NoopMemory),
+        other => {
+            return Err(MemoryError::Storage(format!(
+                "Unknown memory backend '{other}' (expected sqlite, file, postgres, memory or none)"
+            )));
+        }
+    };
+    info!(backend = backend.name(), "Memory backend ready");
+    Ok(backend)
+}
+
+/// The ranking and retention policy described by `[memory.decay]`.
+pub fn decay_policy(config: &MemoryConfig) -> DecayPolicy {
+    DecayPolicy {
+        half_life_hours: config.decay.half_life_hours,
+        recency_weight: config.decay.recency_weight,
+        frequency_weight: config.decay.frequency_weight,
+        ttl_hours: config.decay.ttl_hours,
+        forget_below: config.decay.forget_below,
+    }
+}
+
+/// Start the periodic compaction of `backend`, unless
+/// `compaction_interval_minutes` is 0.
+pub fn spawn_compaction_from_config(
+    backend: Arc<dyn MemoryBackend>,
+    config: &MemoryConfig,
+) -> Option<JoinHandle<()>> {
+    let minutes = config.decay.compaction_interval_minutes;
+    (minutes > 0).then(|| crate::spawn_compaction(backend, Duration::from_secs(minutes * 60)))
+}
+
+/// Embed entries stored in `backend` with `provider`, the provider named by
+/// `[memory] embedding_provider`. Returns `backend` unchanged when embeddings
+/// are disabled (`none`) or the provider is not configured.
+pub fn with_embeddings(
+    backend: Arc<dyn MemoryBackend>,
+    config: &MemoryConfig,
+    provider: Option<Arc<dyn Provider>>,
+) -> Arc<dyn MemoryBackend> {
+    if config.embedding_provider == "none" {
+        return backend;
+    }
+    match provider {
+        Some(provider) => {
+            info!(
+                provider = %config.embedding_provider,
+                model = %config.embedding_model,
+                "Memory embeddings enabled"
+            );
+            Arc::new(EmbeddingMemory::from_config(backend, provider, config))
+        }
+        None => {
+            warn!(
+                provider = %config.embedding_provider,
+                "Embedding provider is not configured; memories will not be embedded"
+            );
+            backend
+        }
+    }
+}
+
+/// Default SQLite database path: `~/.rustedclaw/memory.sqlite`.
+pub fn default_sqlite_path() -> PathBuf {
+    AppConfig::config_dir().join("memory.sqlite")
+}
+
+#[cfg(feature = "sqlite")]
+async fn open_sqlite(config: &MemoryConfig) -> Result<Arc<dyn MemoryBackend>, MemoryError> {
+    let path = config
+        .path
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(default_sqlite_path);
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            MemoryError::Storage(format!("Cannot create {}: {e}", parent.display()))
+        })?;
+    }
+    let backend = crate::SqliteBackend::new(&format!("sqlite://{}", path.display())).await?;
+    Ok(Arc::new(backend.with_decay(decay_policy(config))))
+}
+
+#[cfg(not(feature = "sqlite"))]
+async fn open_sqlite(_config: &MemoryConfig) -> Result<Arc<dyn MemoryBackend>, MemoryError> {
+    Err(MemoryError::Storage(
+        "The sqlite memory backend requires the `sqlite` feature".into(),
+    ))
+}
+
+#[cfg(feature = "postgres")]
+async fn open_postgres(config: &MemoryConfig) -> Result<Arc<dyn MemoryBackend>, MemoryError> {
+    let url = config
+        .url
+        .clone()
+        .or_else(|| std::env::var("DATABASE_URL").ok())
+        .ok_or_else(|| {
+            MemoryError::Storage(
+                "The postgres memory backend needs [memory] url or DATABASE_URL".into(),
+            )
+        })?;
+    let backend = crate::PostgresBackend::connect(&url).await?;
+    Ok(Arc::new(backend.with_decay(decay_policy(config))))
+}
+
+#[cfg(not(feature = "postgres"))]
+async fn open_postgres(_config: &MemoryConfig) -> Result<Arc<dyn MemoryBackend>, MemoryError> {
+    Err(MemoryError::Storage(
+        "The postgres memory backend requires the `postgres` feature".into(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustedclaw_core::memory::{MemoryEntry, MemoryQuery, MemoryScope, SearchMode};
+
+    fn config(backend: &str, path: Option<PathBuf>) -> MemoryConfig {
+        MemoryConfig {
+            backend: backend.into(),
+            path: path.map(|p| p.display().to_string()),
+            ..MemoryConfig::default()
+        }
+    }
+
+    fn entry(content: &str) -> MemoryEntry {
+        MemoryEntry {
+            id: String::new(),
+            content: content.into(),
+            tags: vec!["note".into()],
+            source: None,
+            created_at: chrono::Utc::now(),
+            last_accessed: chrono::Utc::now(),
+            access_count: 0,
+            expires_at: None,
+            score: 0.0,
+            embedding: None,
+            embedding_model: None,
+            scope: MemoryScope::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn file_backend_persists_across_opens() {
+        let dir = tempfile::tempdir().unwrap();
+        let cfg = config("file", Some(dir.path().join("mem.jsonl")));
+
+        let backend = from_config(&cfg).await.unwrap();
+        assert_eq!(backend.name(), "file");
+        backend.store(entry("Rust is fast")).await.unwrap();
+
+        let reopened = from_config(&cfg).await.unwrap();
+        assert_eq!(reopened.count().await.unwrap(), 1);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn sqlite_backend_created_at_configured_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("memory.sqlite");
+        let cfg = config("sqlite", Some(path.clone()));
+
+        let backend = from_config(&cfg).await.unwrap();
+        assert_eq!(backend.name(), "sqlite");
+        backend.store(entry("Rust is fast")).await.unwrap();
+        assert!(path.exists());
+
+        let hits = backend
+            .search(MemoryQuery {
+                text: "Rust".into(),
+                limit: 5,
+                min_score: 0.0,
+                tags: vec![],
+                mode: SearchMode::Keyword,
+                embedding: None,
+                scope: MemoryScope::default(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn ephemeral_and_disabled_backends() {
+        assert_eq!(
+            from_config(&config("memory", None)).await.unwrap().name(),
+            "in_memory"
+        );
+        assert_eq!(
+            from_config(&config("none", None)).await.unwrap().name(),
+            "none"
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_backend_rejected() {
+        let err = from_config(&config("redis", None)).await.err().unwrap();
+        assert!(err.to_string().contains("redis"));
+    }
+}
@@ -8,10 +8,13 @@
 //! This backend is simple, portable, human-inspectable, and requires zero
 //! external dependencies (no SQLite, no Postgres).
 
+use crate::{decay, vector};
 use async_trait::async_trait;
 use chrono::Utc;
 use rustedclaw_core::error::MemoryError;
-use rustedclaw_core::memory::{MemoryBackend, MemoryEntry, MemoryQuery};
+use rustedclaw_core::memory::{
+    DecayPolicy, MemoryBackend, MemoryEntry, MemoryFilter, MemoryPatch, MemoryQuery, SearchMode,
+};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -21,10 +24,13 @@ use uuid::Uuid;
 /// A file-backed memory store using JSONL (one JSON object per line).
 ///
 /// Entries are loaded into memory on creation and flushed to disk on every
-/// mutation (store, delete, clear). This gives fast reads with durable writes.
+/// mutation (store, update, delete, clear, compact, and the access
+/// statistics updated by recalls); a batch is flushed once. This gives fast
+/// reads with durable writes.
 pub struct FileBackend {
     path: PathBuf,
     entries: Arc<RwLock<Vec<MemoryEntry>>>,
+    decay: DecayPolicy,
 }
 
 impl FileBackend {
@@ -38,9 +44,16 @@ impl FileBackend {
         Self {
             path,
             entries: Arc::new(RwLock::new(entries)),
+            decay: DecayPolicy::default(),
         }
     }
 
+    /// Rank recalls and forget stale entries with `decay`.
+    pub fn with_decay(mut self, decay: DecayPolicy) -> Self {
+        self.decay = decay;
+        self
+    }
+
     /// Default path: `~/.rustedclaw/memory/memories.jsonl`
     pub fn default_path() -> PathBuf {
         let home = std::env::var("HOME")
@@ -72,6 +85,20 @@ impl FileBackend {
             .collect()
     }
 
+    /// Insert `entry` (or replace the one with its ID), returning the ID.
+    fn upsert(&self, entries: &mut Vec<MemoryEntry>, mut entry: MemoryEntry) -> String {
+        if entry.id.is_empty() {
+            entry.id = Uuid::new_v4().to_string();
+        }
+        entry.expires_at = self.decay.expiry(&entry);
+        let id = entry.id.clone();
+        match entries.iter_mut().find(|e| e.id == id) {
+            Some(existing) => *existing = entry,
+            None => entries.push(entry),
+        }
+        id
+    }
+
     /// Flush all entries to disk as JSONL.
     async fn flush(&self) -> Result<(), MemoryError> {
         let entries = self.entries.read().await;
@@ -105,34 +132,32 @@ impl MemoryBackend for FileBackend {
         "file"
     }
 
-    async fn store(&self, mut entry: MemoryEntry) -> Result<String, MemoryError> {
-        if entry.id.is_empty() {
-            entry.id = Uuid::new_v4().to_string();
-        }
-        let id = entry.id.clone();
-        self.entries.write().await.push(entry);
+    async fn store(&self, entry: MemoryEntry) -> Result<String, MemoryError> {
+        let id = self.upsert(&mut *self.entries.write().await, entry);
         self.flush().await?;
         Ok(id)
     }
 
     async fn search(&self, query: MemoryQuery) -> Result<Vec<MemoryEntry>, MemoryError> {
-        let entries = self.entries.read().await;
+        let now = Utc::now();
+        let mut entries = self.entries.write().await;
+        let wide = MemoryQuery {
+            limit: decay::candidate_limit(&query, &self.decay),
+            ..query.clone()
+        };
         let query_lower = query.text.to_lowercase();
 
         let mut results: Vec<MemoryEntry> = entries
             .iter()
             .filter(|e| {
                 let content_match = e.content.to_lowercase().contains(&query_lower);
-                let tag_match =
-                    query.tags.is_empty() || query.tags.iter().any(|t| e.tags.contains(t));
-                content_match && tag_match
+                content_match && vector::matches_filters(e, &query)
             })
             .cloned()
             .map(|mut e| {
                 // Keyword relevance scoring
                 let occurrences = e.content.to_lowercase().matches(&query_lower).count();
                 e.score = occurrences as f32 / (e.content.len() as f32 / 100.0).max(1.0);
-                e.last_accessed = Utc::now();
                 e
             })
             .filter(|e| e.score >= query.min_score)
@@ -143,8 +168,22 @@ impl MemoryBackend for FileBackend {
                 .partial_cmp(&a.score)
                 .unwrap_or(std::cmp::Ordering::Equal)
         });
-        results.truncate(query.limit);
-
+        results.truncate(wide.limit);
+
+        if !matches!(query.mode, SearchMode::Keyword) {
+            let candidates: Vec<MemoryEntry> = entries
+                .iter()
+                .filter(|e| vector::matches_filters(e, &query))
+                .cloned()
+                .collect();
+            results = vector::rank_by_mode(&wide, &candidates, results);
+        }
+        let mut results = decay::finish(&query, &self.decay, results, now);
+        if query.is_recall() {
+            decay::touch(&mut entries, &mut results, now);
+            drop(entries);
+            self.flush().await?;
+        }
         Ok(results)
     }
 
@@ -161,8 +200,19 @@ impl MemoryBackend for FileBackend {
     }
 
     async fn get(&self, id: &str) -> Result<Option<MemoryEntry>, MemoryError> {
-        let entries = self.entries.read().await;
-        Ok(entries.iter().find(|e| e.id == id).cloned())
+        let now = Utc::now();
+        let mut entries = self.entries.write().await;
+        let Some(entry) = entries
+            .iter_mut()
+            .find(|e| e.id == id && !e.is_expired(now))
+        else {
+            return Ok(None);
+        };
+        entry.touch(now);
+        let entry = entry.clone();
+        drop(entries);
+        self.flush().await?;
+        Ok(Some(entry))
     }
 
     async fn count(&self) -> Result<usize, MemoryError> {
@@ -174,12 +224,80 @@ impl MemoryBackend for FileBackend {
         self.flush().await?;
         Ok(())
     }
+
+    async fn compact(&self) -> Result<usize, MemoryError> {
+        let now = Utc::now();
+        let mut entries = self.entries.write().await;
+        let before = entries.len();
+        entries.retain(|e| !decay::is_stale(&self.decay, e, now));
+        let forgotten = before - entries.len();
+        drop(entries);
+        if forgotten > 0 {
+            self.flush().await?;
+        }
+        Ok(forgotten)
+    }
+
+    async fn update(
+        &self,
+        id: &str,
+        patch: MemoryPatch,
+    ) -> Result<Option<MemoryEntry>, MemoryError> {
+        let now = Utc::now();
+        let mut entries = self.entries.write().await;
+        let Some(entry) = entries
+            .iter_mut()
+            .find(|e| e.id == id && !e.is_expired(now))
+        else {
+            return Ok(None);
+        };
+        patch.apply(entry);
+        let entry = entry.clone();
+        drop(entries);
+        self.flush().await?;
+        Ok(Some(entry))
+    }
+
+    async fn list(
+        &self,
+        filter: &MemoryFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<MemoryEntry>, MemoryError> {
+        let entries = self.entries.read().await;
+        Ok(vector::list_page(&entries, filter, offset, limit))
+    }
+
+    async fn store_batch(&self, batch: Vec<MemoryEntry>) -> Result<Vec<String>, MemoryError> {
+        let ids = {
+            let mut entries = self.entries.write().await;
+            batch
+                .into_iter()
+                .map(|entry| self.upsert(&mut entries, entry))
+                .collect()
+        };
+        self.flush().await?;
+        Ok(ids)
+    }
+
+    async fn delete_where(&self, filter: &MemoryFilter) -> Result<usize, MemoryError> {
+        let now = Utc::now();
+        let mut entries = self.entries.write().await;
+        let before = entries.len();
+        entries.retain(|e| !filter.matches(e, now));
+        let deleted = before - entries.len();
+        drop(entries);
+        if deleted > 0 {
+            self.flush().await?;
+        }
+        Ok(deleted)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rustedclaw_core::memory::SearchMode;
+    use rustedclaw_core::memory::MemoryScope;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -191,11 +309,26 @@ mod tests {
             source: None,
             created_at: Utc::now(),
             last_accessed: Utc::now(),
+            access_count: 0,
+            expires_at: None,
             score: 0.0,
             embedding: None,
+            embedding_model: None,
+            scope: MemoryScope::default(),
         }
     }
 
+    #[tokio::test]
+    async fn conformance() {
+        let tmp = NamedTempFile::new().unwrap();
+        let mem = FileBackend::new(tmp.path().to_path_buf());
+        crate::conformance::run(&mem).await;
+
+        // Batches, updates and bulk deletes reached the file.
+        let reloaded = FileBackend::new(tmp.path().to_path_buf());
+        assert_eq!(reloaded.count().await.unwrap(), mem.count().await.unwrap());
+    }
+
     #[tokio::test]
     async fn store_and_retrieve_persists() {
         let tmp = NamedTempFile::new().unwrap();
@@ -239,6 +372,8 @@ mod tests {
             min_score: 0.0,
             tags: vec![],
             mode: SearchMode::Keyword,
+            embedding: None,
+            scope: MemoryScope::default(),
         };
 
         let results = mem.search(query).await.unwrap();
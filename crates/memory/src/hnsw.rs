@@ -0,0 +1,719 @@
+//! HNSW approximate nearest-neighbour index over memory embeddings.
+//!
+//! A Hierarchical Navigable Small World graph (Malkov & Yashunin, 2016):
+//! every vector is a node linked to its nearest neighbours on a stack of
+//! layers, sparse at the top and complete at layer 0. A search walks greedily
+//! down the layers and then explores layer 0 with a bounded candidate list,
+//! touching a few hundred nodes instead of all of them.
+//!
+//! Similarity is cosine, matching [`crate::vector::cosine_similarity`];
+//! vectors are normalized on insert so distances are `1 - dot`. Removed
+//! entries are tombstoned and dropped when the graph is rebuilt. The index
+//! holds vectors of a single dimension, fixed by the first insert.
+//!
+//! [`HnswIndex::save`] and [`HnswIndex::load`] use a compact little-endian
+//! binary file, so the graph does not have to be rebuilt on startup.
+
+use rustedclaw_core::error::MemoryError;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::io::{BufReader, Read, Write};
+use std::path::Path;
+
+/// File signature and format version.
+const MAGIC: &[u8; 8] = b"RCHNSW01";
+
+/// Highest layer a node can be assigned to.
+const MAX_LEVEL: usize = 16;
+
+/// Tombstones tolerated before the graph is rebuilt without them.
+const MIN_TOMBSTONES_FOR_REBUILD: usize = 64;
+
+/// Graph construction and search parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HnswParams {
+    /// Links per node on upper layers (layer 0 keeps twice as many).
+    pub m: usize,
+    /// Candidate list size while inserting; higher builds a better graph.
+    pub ef_construction: usize,
+    /// Candidate list size while searching; higher trades speed for recall.
+    pub ef_search: usize,
+}
+
+impl Default for HnswParams {
+    fn default() -> Self {
+        Self {
+            m: 16,
+            ef_construction: 100,
+            ef_search: 64,
+        }
+    }
+}
+
+/// Cosine distance between normalized vectors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Dist(f32);
+
+impl Eq for Dist {}
+
+impl PartialOrd for Dist {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Dist {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+struct Node {
+    id: String,
+    vector: Vec<f32>,
+    /// Neighbour lists, one per layer the node lives on.
+    links: Vec<Vec<u32>>,
+    deleted: bool,
+}
+
+/// An HNSW graph keyed by memory ID.
+pub struct HnswIndex {
+    params: HnswParams,
+    dimensions: Option<usize>,
+    nodes: Vec<Node>,
+    ids: HashMap<String, u32>,
+    entry_point: Option<u32>,
+    max_level: usize,
+    tombstones: usize,
+    rng: u64,
+}
+
+impl HnswIndex {
+    pub fn new(params: HnswParams) -> Self {
+        Self {
+            params: HnswParams {
+                m: params.m.max(2),
+                ef_construction: params.ef_construction.max(1),
+                ef_search: params.ef_search.max(1),
+            },
+            dimensions: None,
+            nodes: Vec::new(),
+            ids: HashMap::new(),
+            entry_point: None,
+            max_level: 0,
+            tombstones: 0,
+            rng: 0x5DEECE66D,
+        }
+    }
+
+    pub fn params(&self) -> HnswParams {
+        self.params
+    }
+
+    /// Change the search-time candidate list size (`ef_search`).
+    pub fn set_ef_search(&mut self, ef_search: usize) {
+        self.params.ef_search = ef_search.max(1);
+    }
+
+    /// Number of live (not removed) entries.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Dimension of the indexed vectors, once the first one is inserted.
+    pub fn dimensions(&self) -> Option<usize> {
+        self.dimensions
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.ids.contains_key(id)
+    }
+
+    /// IDs of all live entries.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.ids.keys().map(String::as_str)
+    }
+
+    /// Whether `id` is indexed with `vector` (up to scale).
+    pub fn holds(&self, id: &str, vector: &[f32]) -> bool {
+        self.ids
+            .get(id)
+            .is_some_and(|&node| self.nodes[node as usize].vector == normalized(vector))
+    }
+
+    /// Insert `vector` under `id`, replacing any previous vector for it.
+    ///
+    /// Fails, leaving the index unchanged, if the vector's dimension differs
+    /// from the index's.
+    pub fn insert(&mut self, id: &str, vector: &[f32]) -> Result<(), MemoryError> {
+        if vector.is_empty() {
+            return Err(MemoryError::Storage("Cannot index an empty vector".into()));
+        }
+        if let Some(dim) = self.dimensions
+            && dim != vector.len()
+        {
+            return Err(MemoryError::Storage(format!(
+                "Vector has {} dimensions, index expects {dim}",
+                vector.len()
+            )));
+        }
+        self.remove(id);
+        // Removing the last entry clears the index, dimension included.
+        self.dimensions = Some(vector.len());
+
+        let vector = normalized(vector);
+        let level = self.random_level();
+        let new = self.nodes.len() as u32;
+        self.nodes.push(Node {
+            id: id.to_string(),
+            vector,
+            links: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+        self.ids.insert(id.to_string(), new);
+
+        let Some(mut entry) = self.entry_point else {
+            self.entry_point = Some(new);
+            self.max_level = level;
+            return Ok(());
+        };
+
+        // Greedy descent through the layers above the new node's top layer.
+        let query = self.nodes[new as usize].vector.clone();
+        for layer in (level + 1..=self.max_level).rev() {
+            entry = self.search_layer(&query, &[entry], 1, layer)[0].1;
+        }
+
+        let mut entries = vec![entry];
+        for layer in (0..=level.min(self.max_level)).rev() {
+            let candidates =
+                self.search_layer(&query, &entries, self.params.ef_construction, layer);
+            let neighbours = self.select_neighbours(&candidates, self.params.m);
+            self.nodes[new as usize].links[layer] = neighbours.clone();
+
+            let max_links = self.max_links(layer);
+            for neighbour in neighbours {
+                let links = &mut self.nodes[neighbour as usize].links[layer];
+                links.push(new);
+                if links.len() > max_links {
+                    self.prune(neighbour, layer, max_links);
+                }
+            }
+            entries = candidates.into_iter().map(|(_, node)| node).collect();
+        }
+
+        if level > self.max_level {
+            self.max_level = level;
+            self.entry_point = Some(new);
+        }
+        Ok(())
+    }
+
+    /// Remove `id`. Returns `false` if it was not indexed.
+    pub fn remove(&mut self, id: &str) -> bool {
+        let Some(node) = self.ids.remove(id) else {
+            return false;
+        };
+        self.nodes[node as usize].deleted = true;
+        self.tombstones += 1;
+
+        if self.ids.is_empty() {
+            self.clear();
+        } else if self.tombstones >= MIN_TOMBSTONES_FOR_REBUILD && self.tombstones > self.len() {
+            self.rebuild();
+        }
+        true
+    }
+
+    /// Remove every entry; the next insert may use a new dimension.
+    pub fn clear(&mut self) {
+        *self = Self::new(self.params);
+    }
+
+    /// Up to `k` nearest live entries to `query`, most similar first, as
+    /// `(id, cosine similarity)`. Returns nothing if the dimension differs.
+    pub fn search(&self, query: &[f32], k: usize) -> Vec<(String, f32)> {
+        let Some(mut entry) = self.entry_point else {
+            return Vec::new();
+        };
+        if k == 0 || self.dimensions != Some(query.len()) {
+            return Vec::new();
+        }
+
+        let query = normalized(query);
+        for layer in (1..=self.max_level).rev() {
+            entry = self.search_layer(&query, &[entry], 1, layer)[0].1;
+        }
+        // Tombstones take up candidate slots, so widen the search to match.
+        let ef = self.params.ef_search.max(k) + self.tombstones.min(k);
+        self.search_layer(&query, &[entry], ef, 0)
+            .into_iter()
+            .filter(|(_, node)| !self.nodes[*node as usize].deleted)
+            .take(k)
+            .map(|(dist, node)| (self.nodes[node as usize].id.clone(), 1.0 - dist.0))
+            .collect()
+    }
+
+    /// Write the index to `path` (via a temporary file and rename).
+    pub fn save(&self, path: &Path) -> Result<(), MemoryError> {
+        Self::write_file(path, &self.to_bytes())
+    }
+
+    /// The index in the format read by [`HnswIndex::load`], for callers
+    /// that snapshot it under a lock and write it out elsewhere.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write_to(&mut out)
+            .expect("writing to a Vec cannot fail");
+        out
+    }
+
+    /// Write bytes from [`HnswIndex::to_bytes`] to `path`. The data goes to
+    /// a temporary file that is synced and renamed into place, so a crash
+    /// mid-write leaves the previous index intact.
+    pub fn write_file(path: &Path, bytes: &[u8]) -> Result<(), MemoryError> {
+        let tmp = path.with_extension("hnsw.tmp");
+        let mut file = std::fs::File::create(&tmp).map_err(io_error)?;
+        file.write_all(bytes).map_err(io_error)?;
+        file.sync_all().map_err(io_error)?;
+        drop(file);
+        std::fs::rename(&tmp, path).map_err(io_error)
+    }
+
+    /// Read an index written by [`HnswIndex::save`].
+    pub fn load(path: &Path) -> Result<Self, MemoryError> {
+        let file = std::fs::File::open(path).map_err(io_error)?;
+        Self::read_from(&mut BufReader::new(file)).map_err(|e| {
+            MemoryError::Storage(format!("Corrupt vector index {}: {e}", path.display()))
+        })
+    }
+
+    fn max_links(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.params.m * 2
+        } else {
+            self.params.m
+        }
+    }
+
+    /// Level for a new node: geometric with ratio `1/m`.
+    fn random_level(&mut self) -> usize {
+        // splitmix64
+        self.rng = self.rng.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^= z >> 31;
+        let uniform = ((z >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        let level = -uniform.ln() / (self.params.m as f64).ln();
+        (level as usize).min(MAX_LEVEL)
+    }
+
+    fn distance(&self, query: &[f32], node: u32) -> Dist {
+        let vector = &self.nodes[node as usize].vector;
+        Dist(1.0 - dot(query, vector))
+    }
+
+    /// Best-first search of one layer. Returns up to `ef` nodes, nearest first.
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entries: &[u32],
+        ef: usize,
+        layer: usize,
+    ) -> Vec<(Dist, u32)> {
+        let mut visited = Visited::new(self.nodes.len());
+        for &entry in entries {
+            visited.insert(entry);
+        }
+        let mut candidates: BinaryHeap<Reverse<(Dist, u32)>> = BinaryHeap::new();
+        let mut nearest: BinaryHeap<(Dist, u32)> = BinaryHeap::new();
+        for &entry in entries {
+            let dist = self.distance(query, entry);
+            candidates.push(Reverse((dist, entry)));
+            nearest.push((dist, entry));
+        }
+        while nearest.len() > ef {
+            nearest.pop();
+        }
+
+        while let Some(Reverse((dist, node))) = candidates.pop() {
+            if nearest.len() >= ef && nearest.peek().is_some_and(|(worst, _)| dist > *worst) {
+                break;
+            }
+            let Some(links) = self.nodes[node as usize].links.get(layer) else {
+                continue;
+            };
+            for &neighbour in links {
+                if !visited.insert(neighbour) {
+                    continue;
+                }
+                let dist = self.distance(query, neighbour);
+                if nearest.len() < ef || nearest.peek().is_some_and(|(worst, _)| dist < *worst) {
+                    candidates.push(Reverse((dist, neighbour)));
+                    nearest.push((dist, neighbour));
+                    if nearest.len() > ef {
+                        nearest.pop();
+                    }
+                }
+            }
+        }
+        nearest.into_sorted_vec()
+    }
+
+    /// Neighbour selection heuristic: keep a candidate only if it is closer
+    /// to the query than to every neighbour already kept, which preserves
+    /// links into distinct clusters. Top up with the nearest leftovers.
+    fn select_neighbours(&self, candidates: &[(Dist, u32)], m: usize) -> Vec<u32> {
+        let mut selected: Vec<u32> = Vec::with_capacity(m);
+        let mut skipped = Vec::new();
+        for &(dist, candidate) in candidates {
+            if selected.len() >= m {
+                break;
+            }
+            let vector = &self.nodes[candidate as usize].vector;
+            let diverse = selected
+                .iter()
+                .all(|&kept| self.distance(vector, kept) > dist);
+            if diverse {
+                selected.push(candidate);
+            } else {
+                skipped.push(candidate);
+            }
+        }
+        for candidate in skipped {
+            if selected.len() >= m {
+                break;
+            }
+            selected.push(candidate);
+        }
+        selected
+    }
+
+    /// Shrink `node`'s links on `layer` back to `max_links`.
+    fn prune(&mut self, node: u32, layer: usize, max_links: usize) {
+        let vector = self.nodes[node as usize].vector.clone();
+        let mut candidates: Vec<(Dist, u32)> = self.nodes[node as usize].links[layer]
+            .iter()
+            .map(|&n| (self.distance(&vector, n), n))
+            .collect();
+        candidates.sort();
+        self.nodes[node as usize].links[layer] = self.select_neighbours(&candidates, max_links);
+    }
+
+    /// Re-insert the live entries into a fresh graph, dropping tombstones.
+    fn rebuild(&mut self) {
+        let live: Vec<Node> = std::mem::take(&mut self.nodes)
+            .into_iter()
+            .filter(|node| !node.deleted)
+            .collect();
+        let rng = self.rng;
+        self.clear();
+        self.rng = rng;
+        for node in live {
+            // Same dimension as before, so this cannot fail.
+            let _ = self.insert(&node.id, &node.vector);
+        }
+    }
+
+    fn write_to(&self, out: &mut impl Write) -> std::io::Result<()> {
+        out.write_all(MAGIC)?;
+        for value in [
+            self.params.m,
+            self.params.ef_construction,
+            self.params.ef_search,
+            self.dimensions.unwrap_or(0),
+            self.max_level,
+            self.nodes.len(),
+        ] {
+            write_u32(out, value as u32)?;
+        }
+        write_u32(out, self.entry_point.unwrap_or(u32::MAX))?;
+        out.write_all(&self.rng.to_le_bytes())?;
+
+        for node in &self.nodes {
+            out.write_all(&[node.deleted as u8])?;
+            write_u32(out, node.id.len() as u32)?;
+            out.write_all(node.id.as_bytes())?;
+            for value in &node.vector {
+                out.write_all(&value.to_le_bytes())?;
+            }
+            write_u32(out, node.links.len() as u32)?;
+            for links in &node.links {
+                write_u32(out, links.len() as u32)?;
+                for &link in links {
+                    write_u32(out, link)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read_from(input: &mut impl Read) -> std::io::Result<Self> {
+        let mut magic = [0u8; 8];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("unrecognized file signature"));
+        }
+        let params = HnswParams {
+            m: read_u32(input)? as usize,
+            ef_construction: read_u32(input)? as usize,
+            ef_search: read_u32(input)? as usize,
+        };
+        let dimensions = read_u32(input)? as usize;
+        let max_level = read_u32(input)? as usize;
+        let count = read_u32(input)? as usize;
+        let entry_point = Some(read_u32(input)?).filter(|&e| e != u32::MAX);
+        let mut rng = [0u8; 8];
+        input.read_exact(&mut rng)?;
+
+        let mut index = Self::new(params);
+        index.dimensions = Some(dimensions).filter(|&d| d > 0);
+        index.max_level = max_level;
+        index.entry_point = entry_point;
+        index.rng = u64::from_le_bytes(rng);
+
+        for position in 0..count as u32 {
+            let mut deleted = [0u8; 1];
+            input.read_exact(&mut deleted)?;
+            let mut id = vec![0u8; read_u32(input)? as usize];
+            input.read_exact(&mut id)?;
+            let id = String::from_utf8(id).map_err(|_| invalid("non-UTF-8 memory ID"))?;
+            let mut vector = Vec::with_capacity(dimensions);
+            for _ in 0..dimensions {
+                let mut bytes = [0u8; 4];
+                input.read_exact(&mut bytes)?;
+                vector.push(f32::from_le_bytes(bytes));
+            }
+            let layers = read_u32(input)? as usize;
+            if layers == 0 || layers > MAX_LEVEL + 1 {
+                return Err(invalid("bad layer count"));
+            }
+            let mut links = Vec::with_capacity(layers);
+            for _ in 0..layers {
+                let len = read_u32(input)? as usize;
+                let mut layer = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let link = read_u32(input)?;
+                    if link as usize >= count {
+                        return Err(invalid("link out of range"));
+                    }
+                    layer.push(link);
+                }
+                links.push(layer);
+            }
+
+            if deleted[0] == 0 {
+                index.ids.insert(id.clone(), position);
+            } else {
+                index.tombstones += 1;
+            }
+            index.nodes.push(Node {
+                id,
+                vector,
+                links,
+                deleted: deleted[0] != 0,
+            });
+        }
+        if entry_point.is_some_and(|e| e as usize >= count) {
+            return Err(invalid("entry point out of range"));
+        }
+        Ok(index)
+    }
+}
+
+/// Bitset of nodes already seen by one layer search.
+struct Visited(Vec<u64>);
+
+impl Visited {
+    fn new(nodes: usize) -> Self {
+        Self(vec![0; nodes.div_ceil(64)])
+    }
+
+    /// Mark `node`; returns `false` if it was already marked.
+    fn insert(&mut self, node: u32) -> bool {
+        let (word, bit) = (node as usize / 64, 1u64 << (node % 64));
+        let fresh = self.0[word] & bit == 0;
+        self.0[word] |= bit;
+        fresh
+    }
+}
+
+/// Dot product with eight independent accumulators, which the compiler
+/// can vectorize (a single running sum cannot be reordered).
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    let mut lanes = [0.0f32; 8];
+    let (a_chunks, b_chunks) = (a.chunks_exact(8), b.chunks_exact(8));
+    let tail: f32 = a_chunks
+        .remainder()
+        .iter()
+        .zip(b_chunks.remainder())
+        .map(|(x, y)| x * y)
+        .sum();
+    for (x, y) in a_chunks.zip(b_chunks) {
+        for i in 0..8 {
+            lanes[i] += x[i] * y[i];
+        }
+    }
+    lanes.iter().sum::<f32>() + tail
+}
+
+fn normalized(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm < 1e-10 {
+        return vector.to_vec();
+    }
+    vector.iter().map(|v| v / norm).collect()
+}
+
+fn write_u32(out: &mut impl Write, value: u32) -> std::io::Result<()> {
+    out.write_all(&value.to_le_bytes())
+}
+
+fn read_u32(input: &mut impl Read) -> std::io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    input.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn invalid(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn io_error(e: std::io::Error) -> MemoryError {
+    MemoryError::Storage(format!("Vector index I/O: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::cosine_similarity;
+
+    /// Deterministic pseudo-random unit-cube vectors.
+    fn vectors(count: usize, dim: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut state = seed;
+        (0..count)
+            .map(|_| {
+                (0..dim)
+                    .map(|_| {
+                        state ^= state << 13;
+                        state ^= state >> 7;
+                        state ^= state << 17;
+                        (state % 2000) as f32 / 1000.0 - 1.0
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn exact(data: &[Vec<f32>], query: &[f32], k: usize) -> Vec<String> {
+        let mut scored: Vec<(f32, usize)> = data
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (cosine_similarity(v, query), i))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored
+            .iter()
+            .take(k)
+            .map(|(_, i)| format!("m{i}"))
+            .collect()
+    }
+
+    fn build(data: &[Vec<f32>]) -> HnswIndex {
+        let mut index = HnswIndex::new(HnswParams::default());
+        for (i, v) in data.iter().enumerate() {
+            index.insert(&format!("m{i}"), v).unwrap();
+        }
+        index
+    }
+
+    #[test]
+    fn high_recall_against_exact_search() {
+        let data = vectors(1000, 24, 7);
+        let index = build(&data);
+        let mut found = 0;
+        let queries = vectors(40, 24, 99);
+        for query in &queries {
+            let truth = exact(&data, query, 10);
+            let hits = index.search(query, 10);
+            found += hits.iter().filter(|(id, _)| truth.contains(id)).count();
+        }
+        let recall = found as f32 / (queries.len() * 10) as f32;
+        assert!(recall > 0.95, "recall {recall}");
+    }
+
+    #[test]
+    fn scores_are_cosine_similarity() {
+        let data = vectors(100, 8, 3);
+        let index = build(&data);
+        let (id, score) = &index.search(&data[42], 1)[0];
+        assert_eq!(id, "m42");
+        assert!((score - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn remove_and_replace() {
+        let data = vectors(300, 8, 11);
+        let mut index = build(&data);
+        assert!(index.remove("m5"));
+        assert!(!index.remove("m5"));
+        assert!(index.search(&data[5], 10).iter().all(|(id, _)| id != "m5"));
+
+        // Re-inserting an ID replaces its vector.
+        index.insert("m6", &data[7]).unwrap();
+        assert_eq!(index.len(), 299);
+        let top: Vec<String> = index.search(&data[7], 2).into_iter().map(|h| h.0).collect();
+        assert!(top.contains(&"m6".to_string()) && top.contains(&"m7".to_string()));
+
+        // Removing most entries rebuilds the graph without tombstones.
+        for i in 0..250 {
+            index.remove(&format!("m{i}"));
+        }
+        assert_eq!(index.len(), 50);
+        assert!(index.tombstones < MIN_TOMBSTONES_FOR_REBUILD);
+        assert_eq!(index.search(&data[260], 1)[0].0, "m260");
+    }
+
+    #[test]
+    fn rejects_mismatched_dimensions() {
+        let mut index = HnswIndex::new(HnswParams::default());
+        index.insert("a", &[0.0, 1.0]).unwrap();
+        index.insert("a", &[1.0, 0.0]).unwrap();
+        assert_eq!(index.dimensions(), Some(2));
+        assert!(index.holds("a", &[2.0, 0.0]));
+        assert!(index.insert("b", &[1.0, 0.0, 0.0]).is_err());
+        assert!(index.search(&[1.0, 0.0, 0.0], 1).is_empty());
+
+        // A rejected replacement keeps the previous vector.
+        assert!(index.insert("a", &[0.0, 0.0, 1.0]).is_err());
+        assert!(index.holds("a", &[1.0, 0.0]));
+        index.remove("a");
+        assert!(index.insert("b", &[1.0, 0.0, 0.0]).is_ok());
+    }
+
+    #[test]
+    fn save_and_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("memory.sqlite.hnsw");
+        let data = vectors(500, 12, 5);
+        let mut index = build(&data);
+        index.remove("m3");
+        index.save(&path).unwrap();
+
+        let loaded = HnswIndex::load(&path).unwrap();
+        assert_eq!(loaded.len(), 499);
+        assert!(!loaded.contains("m3"));
+        assert_eq!(loaded.params(), HnswParams::default());
+        for query in &data[..20] {
+            assert_eq!(loaded.search(query, 5), index.search(query, 5));
+        }
+
+        std::fs::write(&path, b"garbage").unwrap();
+        assert!(HnswIndex::load(&path).is_err());
+    }
+}
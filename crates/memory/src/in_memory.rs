@@ -1,9 +1,12 @@
 //! In-memory backend — useful for testing and ephemeral sessions.
 
+use crate::{decay, vector};
 use async_trait::async_trait;
 use chrono::Utc;
 use rustedclaw_core::error::MemoryError;
-use rustedclaw_core::memory::{MemoryBackend, MemoryEntry, MemoryQuery};
+use rustedclaw_core::memory::{
+    DecayPolicy, MemoryBackend, MemoryEntry, MemoryFilter, MemoryPatch, MemoryQuery, SearchMode,
+};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use uuid::Uuid;
@@ -12,14 +15,22 @@ use uuid::Uuid;
 /// Useful for testing and sessions where persistence isn't needed.
 pub struct InMemoryBackend {
     entries: Arc<RwLock<Vec<MemoryEntry>>>,
+    decay: DecayPolicy,
 }
 
 impl InMemoryBackend {
     pub fn new() -> Self {
         Self {
             entries: Arc::new(RwLock::new(Vec::new())),
+            decay: DecayPolicy::default(),
         }
     }
+
+    /// Rank recalls and forget stale entries with `decay`.
+    pub fn with_decay(mut self, decay: DecayPolicy) -> Self {
+        self.decay = decay;
+        self
+    }
 }
 
 impl Default for InMemoryBackend {
@@ -38,29 +49,38 @@ impl MemoryBackend for InMemoryBackend {
         if entry.id.is_empty() {
             entry.id = Uuid::new_v4().to_string();
         }
+        entry.expires_at = self.decay.expiry(&entry);
         let id = entry.id.clone();
-        self.entries.write().await.push(entry);
+        {
+            let mut entries = self.entries.write().await;
+            match entries.iter_mut().find(|e| e.id == id) {
+                Some(existing) => *existing = entry,
+                None => entries.push(entry),
+            }
+        }
         Ok(id)
     }
 
     async fn search(&self, query: MemoryQuery) -> Result<Vec<MemoryEntry>, MemoryError> {
-        let entries = self.entries.read().await;
+        let now = Utc::now();
+        let mut entries = self.entries.write().await;
+        let wide = MemoryQuery {
+            limit: decay::candidate_limit(&query, &self.decay),
+            ..query.clone()
+        };
         let query_lower = query.text.to_lowercase();
 
         let mut results: Vec<MemoryEntry> = entries
             .iter()
             .filter(|e| {
                 let content_match = e.content.to_lowercase().contains(&query_lower);
-                let tag_match =
-                    query.tags.is_empty() || query.tags.iter().any(|t| e.tags.contains(t));
-                content_match && tag_match
+                content_match && vector::matches_filters(e, &query)
             })
             .cloned()
             .map(|mut e| {
                 // Simple keyword relevance score
                 let occurrences = e.content.to_lowercase().matches(&query_lower).count();
                 e.score = occurrences as f32 / (e.content.len() as f32 / 100.0).max(1.0);
-                e.last_accessed = Utc::now();
                 e
             })
             .filter(|e| e.score >= query.min_score)
@@ -71,8 +91,20 @@ impl MemoryBackend for InMemoryBackend {
                 .partial_cmp(&a.score)
                 .unwrap_or(std::cmp::Ordering::Equal)
         });
-        results.truncate(query.limit);
+        results.truncate(wide.limit);
 
+        if !matches!(query.mode, SearchMode::Keyword) {
+            let candidates: Vec<MemoryEntry> = entries
+                .iter()
+                .filter(|e| vector::matches_filters(e, &query))
+                .cloned()
+                .collect();
+            results = vector::rank_by_mode(&wide, &candidates, results);
+        }
+        let mut results = decay::finish(&query, &self.decay, results, now);
+        if query.is_recall() {
+            decay::touch(&mut entries, &mut results, now);
+        }
         Ok(results)
     }
 
@@ -84,8 +116,15 @@ impl MemoryBackend for InMemoryBackend {
     }
 
     async fn get(&self, id: &str) -> Result<Option<MemoryEntry>, MemoryError> {
-        let entries = self.entries.read().await;
-        Ok(entries.iter().find(|e| e.id == id).cloned())
+        let now = Utc::now();
+        let mut entries = self.entries.write().await;
+        Ok(entries
+            .iter_mut()
+            .find(|e| e.id == id && !e.is_expired(now))
+            .map(|entry| {
+                entry.touch(now);
+                entry.clone()
+            }))
     }
 
     async fn count(&self) -> Result<usize, MemoryError> {
@@ -96,12 +135,54 @@ impl MemoryBackend for InMemoryBackend {
         self.entries.write().await.clear();
         Ok(())
     }
+
+    async fn compact(&self) -> Result<usize, MemoryError> {
+        let now = Utc::now();
+        let mut entries = self.entries.write().await;
+        let before = entries.len();
+        entries.retain(|e| !decay::is_stale(&self.decay, e, now));
+        Ok(before - entries.len())
+    }
+
+    async fn update(
+        &self,
+        id: &str,
+        patch: MemoryPatch,
+    ) -> Result<Option<MemoryEntry>, MemoryError> {
+        let now = Utc::now();
+        let mut entries = self.entries.write().await;
+        Ok(entries
+            .iter_mut()
+            .find(|e| e.id == id && !e.is_expired(now))
+            .map(|entry| {
+                patch.apply(entry);
+                entry.clone()
+            }))
+    }
+
+    async fn list(
+        &self,
+        filter: &MemoryFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<MemoryEntry>, MemoryError> {
+        let entries = self.entries.read().await;
+        Ok(vector::list_page(&entries, filter, offset, limit))
+    }
+
+    async fn delete_where(&self, filter: &MemoryFilter) -> Result<usize, MemoryError> {
+        let now = Utc::now();
+        let mut entries = self.entries.write().await;
+        let before = entries.len();
+        entries.retain(|e| !filter.matches(e, now));
+        Ok(before - entries.len())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rustedclaw_core::memory::SearchMode;
+    use rustedclaw_core::memory::MemoryScope;
 
     fn test_entry(content: &str) -> MemoryEntry {
         MemoryEntry {
@@ -111,8 +192,12 @@ mod tests {
             source: None,
             created_at: Utc::now(),
             last_accessed: Utc::now(),
+            access_count: 0,
+            expires_at: None,
             score: 0.0,
             embedding: None,
+            embedding_model: None,
+            scope: MemoryScope::default(),
         }
     }
 
@@ -130,6 +215,18 @@ mod tests {
         assert_eq!(entry.unwrap().content, "Rust is a systems language");
     }
 
+    #[tokio::test]
+    async fn store_replaces_existing_id() {
+        let mem = InMemoryBackend::new();
+        let id = mem.store(test_entry("First draft")).await.unwrap();
+        let mut revised = test_entry("Second draft");
+        revised.id = id.clone();
+        mem.store(revised).await.unwrap();
+
+        assert_eq!(mem.count().await.unwrap(), 1);
+        assert_eq!(mem.get(&id).await.unwrap().unwrap().content, "Second draft");
+    }
+
     #[tokio::test]
     async fn search_by_keyword() {
         let mem = InMemoryBackend::new();
@@ -150,6 +247,8 @@ mod tests {
                 min_score: 0.0,
                 tags: vec![],
                 mode: SearchMode::Keyword,
+                embedding: None,
+                scope: MemoryScope::default(),
             })
             .await
             .unwrap();
@@ -158,6 +257,73 @@ mod tests {
         assert!(results[0].content.contains("Rust"));
     }
 
+    #[tokio::test]
+    async fn search_respects_scope() {
+        let mem = InMemoryBackend::new();
+        let mut team = test_entry("Deploys happen on Fridays");
+        team.scope.namespace = Some("team-a".into());
+        mem.store(team).await.unwrap();
+        let mut other = test_entry("Deploys happen on Mondays");
+        other.scope.namespace = Some("team-b".into());
+        mem.store(other).await.unwrap();
+        mem.store(test_entry("Deploys need a changelog"))
+            .await
+            .unwrap();
+
+        let results = mem
+            .search(MemoryQuery {
+                text: "Deploys".into(),
+                limit: 10,
+                min_score: 0.0,
+                tags: vec![],
+                mode: SearchMode::Keyword,
+                embedding: None,
+                scope: MemoryScope {
+                    namespace: Some("team-a".into()),
+                    ..MemoryScope::default()
+                },
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|e| !e.content.contains("Mondays")));
+    }
+
+    #[tokio::test]
+    async fn recall_records_access_and_hides_expired() {
+        let mem = InMemoryBackend::new();
+        let id = mem.store(test_entry("Coffee at nine")).await.unwrap();
+        let mut expired = test_entry("Coffee at ten");
+        expired.expires_at = Some(Utc::now() - chrono::Duration::seconds(1));
+        let expired = mem.store(expired).await.unwrap();
+
+        let results = mem
+            .search(MemoryQuery {
+                text: "Coffee".into(),
+                limit: 10,
+                min_score: 0.0,
+                tags: vec![],
+                mode: SearchMode::Keyword,
+                embedding: None,
+                scope: MemoryScope::default(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].access_count, 1);
+        assert_eq!(mem.get(&id).await.unwrap().unwrap().access_count, 2);
+        assert!(mem.get(&expired).await.unwrap().is_none());
+
+        assert_eq!(mem.compact().await.unwrap(), 1);
+        assert_eq!(mem.count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn conformance() {
+        crate::conformance::run(&InMemoryBackend::new()).await;
+    }
+
     #[tokio::test]
     async fn delete_entry() {
         let mem = InMemoryBackend::new();
@@ -0,0 +1,351 @@
+//! Splitting parsed documents into chunks.
+//!
+//! Chunks are packed from whole paragraphs where they fit, then sentences,
+//! then runs of words, up to [`ChunkingOptions::chunk_size`] tokens. Each
+//! chunk after the first in a section repeats the last
+//! [`chunk_overlap`](ChunkingOptions::chunk_overlap) tokens of the one before
+//! it, so text cut at a boundary stays retrievable from either side.
+//!
+//! Token counts use the context assembler's estimate of four characters per
+//! token.
+
+use super::ParsedDocument;
+
+/// Separator between the headings of a chunk's heading path.
+const HEADING_SEPARATOR: &str = " › ";
+
+/// How documents are split.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkingOptions {
+    /// Target chunk size in tokens.
+    pub chunk_size: usize,
+    /// Tokens repeated from the end of one chunk at the start of the next.
+    pub chunk_overlap: usize,
+    /// Never let a chunk span two sections, and prefix each chunk with its
+    /// heading path.
+    pub heading_aware: bool,
+}
+
+impl Default for ChunkingOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 512,
+            chunk_overlap: 64,
+            heading_aware: true,
+        }
+    }
+}
+
+/// One piece of a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    /// Position in the document, from 0.
+    pub index: usize,
+    /// Headings enclosing the chunk; empty unless heading-aware.
+    pub headings: Vec<String>,
+    pub text: String,
+}
+
+fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Split `document` into chunks.
+pub fn chunk_document(document: &ParsedDocument, options: &ChunkingOptions) -> Vec<Chunk> {
+    let size = options.chunk_size.max(1);
+    let overlap = options.chunk_overlap.min(size / 2);
+
+    let sections: Vec<(Vec<String>, String)> = if options.heading_aware {
+        document
+            .sections
+            .iter()
+            .map(|s| (s.headings.clone(), s.text.clone()))
+            .collect()
+    } else {
+        // Keep headings as text so they still reach the chunks.
+        let text = document
+            .sections
+            .iter()
+            .map(|s| match s.headings.last() {
+                Some(heading) => format!("{heading}\n\n{}", s.text),
+                None => s.text.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        vec![(Vec::new(), text)]
+    };
+
+    let mut chunks = Vec::new();
+    for (headings, text) in sections {
+        let prefix = headings.join(HEADING_SEPARATOR);
+        // Leave at least half the budget for text under very long headings.
+        let budget = size
+            .saturating_sub(estimate_tokens(&prefix))
+            .max(size / 2)
+            .max(1);
+        for body in pack(pieces(&text, budget, overlap), budget, overlap) {
+            let text = if prefix.is_empty() {
+                body
+            } else {
+                format!("{prefix}\n\n{body}")
+            };
+            chunks.push(Chunk {
+                index: chunks.len(),
+                headings: headings.clone(),
+                text,
+            });
+        }
+    }
+    chunks
+}
+
+/// A unit of text that is never split further, and whether it starts a
+/// paragraph.
+struct Piece {
+    text: String,
+    paragraph: bool,
+}
+
+/// Break `text` into paragraphs, falling back to sentences and then word runs
+/// for anything larger than `budget`. Word runs leave room for `overlap`.
+fn pieces(text: &str, budget: usize, overlap: usize) -> Vec<Piece> {
+    let run_budget = match overlap {
+        0 => budget,
+        _ => budget.saturating_sub(overlap + 1).max(1),
+    };
+    let mut pieces = Vec::new();
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        if estimate_tokens(paragraph) <= budget {
+            pieces.push(Piece {
+                text: paragraph.to_string(),
+                paragraph: true,
+            });
+            continue;
+        }
+        let mut first = true;
+        for sentence in sentences(paragraph) {
+            let runs = if estimate_tokens(sentence) <= budget {
+                vec![sentence.to_string()]
+            } else {
+                word_runs(sentence, run_budget)
+            };
+            for text in runs {
+                pieces.push(Piece {
+                    text,
+                    paragraph: first,
+                });
+                first = false;
+            }
+        }
+    }
+    pieces
+}
+
+/// Split after `.`, `!` or `?` followed by whitespace.
+fn sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if matches!(c, '.' | '!' | '?')
+            && chars.peek().is_some_and(|(_, next)| next.is_whitespace())
+        {
+            sentences.push(text[start..=i].trim());
+            start = i + 1;
+        }
+    }
+    sentences.push(text[start..].trim());
+    sentences.retain(|s| !s.is_empty());
+    sentences
+}
+
+/// Runs of whole words of at most `budget` tokens; a single longer word is
+/// cut by characters.
+fn word_runs(text: &str, budget: usize) -> Vec<String> {
+    let max_chars = budget * 4;
+    let mut runs = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        let mut word = word;
+        while word.chars().count() > max_chars {
+            if !current.is_empty() {
+                runs.push(std::mem::take(&mut current));
+            }
+            let cut = word
+                .char_indices()
+                .nth(max_chars)
+                .map_or(word.len(), |(i, _)| i);
+            runs.push(word[..cut].to_string());
+            word = &word[cut..];
+        }
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > max_chars {
+            runs.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() {
+        runs.push(current);
+    }
+    runs
+}
+
+/// The trailing words of `text` that fit in `tokens`.
+fn tail(text: &str, tokens: usize) -> String {
+    let mut words = Vec::new();
+    let mut chars = 0;
+    for word in text.split_whitespace().rev() {
+        chars += word.chars().count() + 1;
+        if chars.div_ceil(4) > tokens {
+            break;
+        }
+        words.push(word);
+    }
+    words.reverse();
+    words.join(" ")
+}
+
+/// Greedily pack `pieces` into chunks of at most `budget` tokens.
+fn pack(pieces: Vec<Piece>, budget: usize, overlap: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for piece in pieces {
+        let separator = if piece.paragraph { "\n\n" } else { " " };
+        if !current.is_empty()
+            && estimate_tokens(&current) + estimate_tokens(separator) + estimate_tokens(&piece.text)
+                > budget
+        {
+            let carried = tail(&current, overlap);
+            chunks.push(std::mem::take(&mut current));
+            if estimate_tokens(&carried) + 1 + estimate_tokens(&piece.text) <= budget {
+                current = carried;
+            }
+        }
+        if !current.is_empty() {
+            current.push_str(separator);
+        }
+        current.push_str(&piece.text);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingest::Section;
+
+    fn document(sections: &[(&[&str], &str)]) -> ParsedDocument {
+        ParsedDocument {
+            title: None,
+            sections: sections
+                .iter()
+                .map(|(headings, text)| Section {
+                    headings: headings.iter().map(|h| h.to_string()).collect(),
+                    text: text.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    fn options(chunk_size: usize, chunk_overlap: usize, heading_aware: bool) -> ChunkingOptions {
+        ChunkingOptions {
+            chunk_size,
+            chunk_overlap,
+            heading_aware,
+        }
+    }
+
+    fn texts(chunks: &[Chunk]) -> Vec<&str> {
+        chunks.iter().map(|c| c.text.as_str()).collect()
+    }
+
+    #[test]
+    fn small_paragraphs_share_a_chunk() {
+        let doc = document(&[(&[], "One.\n\nTwo.\n\nThree.")]);
+        let chunks = chunk_document(&doc, &options(100, 0, true));
+        assert_eq!(texts(&chunks), ["One.\n\nTwo.\n\nThree."]);
+    }
+
+    #[test]
+    fn chunks_respect_the_token_budget() {
+        let text = (0..200)
+            .map(|i| format!("Sentence number {i} is here."))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let doc = document(&[(&[], &text)]);
+        let chunks = chunk_document(&doc, &options(32, 0, true));
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(estimate_tokens(&chunk.text) <= 32, "{:?}", chunk.text);
+            assert!(chunk.text.ends_with('.'), "split at sentences");
+        }
+        assert_eq!(
+            chunks.iter().map(|c| c.index).collect::<Vec<_>>(),
+            (0..chunks.len()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn chunks_overlap() {
+        let text = (0..60)
+            .map(|i| format!("w{i}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let doc = document(&[(&[], &text)]);
+        let chunks = chunk_document(&doc, &options(20, 5, true));
+        assert!(chunks.len() > 1);
+        for pair in chunks.windows(2) {
+            let last_word = pair[0].text.split_whitespace().last().unwrap();
+            assert!(
+                pair[1].text.split_whitespace().any(|w| w == last_word),
+                "{:?} does not repeat {last_word}",
+                pair[1].text
+            );
+        }
+        assert!(chunks.last().unwrap().text.ends_with("w59"));
+    }
+
+    #[test]
+    fn long_words_are_cut() {
+        let doc = document(&[(&[], &"x".repeat(50))]);
+        let chunks = chunk_document(&doc, &options(4, 0, true));
+        assert_eq!(
+            texts(&chunks),
+            ["x".repeat(16), "x".repeat(16), "x".repeat(16), "xx".into()]
+        );
+    }
+
+    #[test]
+    fn heading_aware_chunks_stay_in_their_section() {
+        let doc = document(&[
+            (&["Guide"], "Intro."),
+            (&["Guide", "Install"], "Download it."),
+        ]);
+        let chunks = chunk_document(&doc, &options(100, 0, true));
+        assert_eq!(
+            texts(&chunks),
+            ["Guide\n\nIntro.", "Guide › Install\n\nDownload it."]
+        );
+        assert_eq!(chunks[1].headings, ["Guide", "Install"]);
+
+        let chunks = chunk_document(&doc, &options(100, 0, false));
+        assert_eq!(
+            texts(&chunks),
+            ["Guide\n\nIntro.\n\nInstall\n\nDownload it."]
+        );
+        assert!(chunks[0].headings.is_empty());
+    }
+
+    #[test]
+    fn sentences_split_on_terminal_punctuation() {
+        assert_eq!(
+            sentences("Version 1.2 shipped. Did it work? Yes!"),
+            ["Version 1.2 shipped.", "Did it work?", "Yes!"]
+        );
+    }
+}
@@ -0,0 +1,300 @@
+//! HTML parsing.
+//!
+//! A forgiving tag scanner rather than a DOM: `<h1>`–`<h6>` start sections,
+//! block elements break lines, and `<script>`, `<style>`, `<head>` and
+//! similar non-visible content is skipped. The `<title>` element, or else
+//! the first `<h1>`, is the title. Character references are decoded.
+
+use super::{ParsedDocument, SectionBuilder};
+
+/// Elements whose content is never visible text.
+const SKIPPED: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "head", "title", "iframe", "object",
+];
+
+/// Elements that end the current line.
+const BLOCKS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tr",
+    "ul",
+];
+
+/// Extract sections from an HTML page.
+pub fn parse(source: &str) -> ParsedDocument {
+    let mut sections = SectionBuilder::default();
+    let mut title = extract_title(source);
+    let mut line = String::new();
+    let mut heading: Option<(usize, String)> = None;
+
+    let mut rest = source;
+    while let Some(lt) = rest.find('<') {
+        push_text(heading_or_line(&mut heading, &mut line), &rest[..lt]);
+        rest = &rest[lt..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let Some(gt) = rest.find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[1..gt];
+        rest = &rest[gt + 1..];
+
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        if !closing && SKIPPED.contains(&name.as_str()) && !tag.ends_with('/') {
+            rest = skip_element(rest, &name);
+        } else if let Some(level) = heading_level(&name) {
+            if closing {
+                if let Some((level, text)) = heading.take() {
+                    let text = collapse_spaces(&text);
+                    if level == 1 && title.is_none() {
+                        title = Some(text.clone());
+                    }
+                    flush_line(&mut sections, &mut line);
+                    sections.heading(level, &text);
+                }
+            } else {
+                flush_line(&mut sections, &mut line);
+                heading = Some((level, String::new()));
+            }
+        } else if BLOCKS.contains(&name.as_str()) {
+            if let Some((_, text)) = &mut heading {
+                text.push(' ');
+            } else {
+                flush_line(&mut sections, &mut line);
+                if matches!(name.as_str(), "p" | "div" | "section" | "article") {
+                    sections.line("");
+                }
+            }
+        } else if !line.ends_with(' ') && matches!(name.as_str(), "td" | "th") {
+            line.push(' ');
+        }
+    }
+    push_text(heading_or_line(&mut heading, &mut line), rest);
+    flush_line(&mut sections, &mut line);
+    sections.finish(title)
+}
+
+fn heading_level(name: &str) -> Option<usize> {
+    match name.as_bytes() {
+        [b'h', level @ b'1'..=b'6'] => Some(usize::from(level - b'0')),
+        _ => None,
+    }
+}
+
+fn heading_or_line<'a>(
+    heading: &'a mut Option<(usize, String)>,
+    line: &'a mut String,
+) -> &'a mut String {
+    match heading {
+        Some((_, text)) => text,
+        None => line,
+    }
+}
+
+fn push_text(target: &mut String, text: &str) {
+    let decoded = decode_entities(text);
+    let separate = |target: &mut String| {
+        if !target.is_empty() && !target.ends_with(' ') {
+            target.push(' ');
+        }
+    };
+    if decoded.starts_with(char::is_whitespace) {
+        separate(target);
+    }
+    for (i, word) in decoded.split_whitespace().enumerate() {
+        if i > 0 {
+            separate(target);
+        }
+        target.push_str(word);
+    }
+    if decoded.ends_with(char::is_whitespace) {
+        separate(target);
+    }
+}
+
+fn flush_line(sections: &mut SectionBuilder, line: &mut String) {
+    let text = line.trim();
+    if !text.is_empty() {
+        sections.line(text);
+    }
+    line.clear();
+}
+
+fn collapse_spaces(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The text after `</name>`, or nothing if the element is never closed.
+fn skip_element<'a>(rest: &'a str, name: &str) -> &'a str {
+    let lower = rest.to_ascii_lowercase();
+    let close = format!("</{name}");
+    match lower.find(&close) {
+        Some(start) => {
+            let after = &rest[start..];
+            after.find('>').map_or("", |end| &after[end + 1..])
+        }
+        None => "",
+    }
+}
+
+/// Text of the `<title>` element, if any.
+fn extract_title(source: &str) -> Option<String> {
+    let lower = source.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = collapse_spaces(&decode_entities(&source[start..end]));
+    (!title.is_empty()).then_some(title)
+}
+
+/// Decode named (common ones only) and numeric character references.
+pub(crate) fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| decode_entity(&rest[1..=end]).map(|c| (c, end + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "middot" => '·',
+        "bull" => '•',
+        "euro" => '€',
+        "pound" => '£',
+        "yen" => '¥',
+        "cent" => '¢',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingest::Section;
+
+    #[test]
+    fn headings_and_blocks() {
+        let doc = parse(
+            r#"<!DOCTYPE html>
+<html><head><title>Fish &amp; Chips</title><style>p { color: red }</style></head>
+<body>
+<nav>Home</nav>
+<h1 class="top">Menu</h1>
+<p>Fresh  cod,
+   fried.</p><p>Served <b>hot</b>.</p>
+<h2>Prices <small>(GBP)</small></h2>
+<ul><li>Small: &pound;5</li><li>Large &#8211; &#x00A3;8</li></ul>
+<script>alert("<h1>not a heading</h1>")</script>
+<!-- <p>hidden</p> -->
+</body></html>"#,
+        );
+        assert_eq!(doc.title.as_deref(), Some("Fish & Chips"));
+        assert_eq!(
+            doc.sections,
+            [
+                Section {
+                    headings: vec![],
+                    text: "Home".into(),
+                },
+                Section {
+                    headings: vec!["Menu".into()],
+                    text: "Fresh cod, fried.\n\nServed hot.".into(),
+                },
+                Section {
+                    headings: vec!["Menu".into(), "Prices (GBP)".into()],
+                    text: "Small: £5\nLarge – £8".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn first_h1_is_the_title_without_a_title_element() {
+        let doc = parse("<h1>Release notes</h1><p>Fixed things.</p>");
+        assert_eq!(doc.title.as_deref(), Some("Release notes"));
+    }
+
+    #[test]
+    fn entities() {
+        assert_eq!(
+            decode_entities("a &lt;b&gt; &amp;c; AT&T &#65;&#x42; &bogus;"),
+            "a <b> &c; AT&T AB &bogus;"
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Markdown parsing.
+//!
+//! ATX (`#`) and setext (underlined) headings start sections; the first
+//! level-one heading is the title. Front matter, HTML comments, thematic
+//! breaks and inline markup (emphasis, code spans, links and images) are
+//! dropped, keeping the visible text. Fenced code blocks are kept verbatim.
+
+use super::{ParsedDocument, SectionBuilder};
+
+/// Extract sections from Markdown source.
+pub fn parse(source: &str) -> ParsedDocument {
+    let mut sections = SectionBuilder::default();
+    let mut title = None;
+    let mut heading = |sections: &mut SectionBuilder, level: usize, text: &str| {
+        let text = inline(text);
+        if level == 1 && title.is_none() {
+            title = Some(text.clone());
+        }
+        sections.heading(level, &text);
+    };
+
+    let mut fence: Option<&str> = None;
+    let mut in_comment = false;
+    // The previous line, held back in case the next one underlines it.
+    let mut pending: Option<&str> = None;
+    for line in strip_front_matter(source).lines() {
+        let trimmed = line.trim();
+
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            } else {
+                sections.line(line);
+            }
+            continue;
+        }
+        if in_comment {
+            in_comment = !trimmed.contains("-->");
+            continue;
+        }
+
+        if let Some(previous) = pending.take() {
+            if !trimmed.is_empty() && trimmed.chars().all(|c| c == '=') {
+                heading(&mut sections, 1, previous);
+                continue;
+            }
+            if trimmed.len() >= 2 && trimmed.chars().all(|c| c == '-') {
+                heading(&mut sections, 2, previous);
+                continue;
+            }
+            sections.line(&inline(previous));
+        }
+
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+        } else if trimmed.starts_with("<!--") {
+            in_comment = !trimmed.contains("-->");
+        } else if let Some((level, text)) = atx_heading(trimmed) {
+            heading(&mut sections, level, text);
+        } else if trimmed.is_empty() || is_thematic_break(trimmed) {
+            sections.line("");
+        } else {
+            pending = Some(strip_block_markers(trimmed));
+        }
+    }
+    if let Some(previous) = pending {
+        sections.line(&inline(previous));
+    }
+    sections.finish(title)
+}
+
+fn strip_front_matter(source: &str) -> &str {
+    let Some(rest) = source.strip_prefix("---\n") else {
+        return source;
+    };
+    match rest.find("\n---") {
+        Some(end) => rest[end + 4..].trim_start_matches(['-', '\n']),
+        None => source,
+    }
+}
+
+/// `## Heading ##` → `(2, "Heading")`.
+fn atx_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    Some((level, rest.trim().trim_end_matches('#').trim_end()))
+}
+
+fn is_thematic_break(line: &str) -> bool {
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|&marker| compact.chars().all(|c| c == marker))
+}
+
+/// Drop blockquote markers; list markers are kept as readable text.
+fn strip_block_markers(line: &str) -> &str {
+    let mut line = line;
+    while let Some(rest) = line.strip_prefix('>') {
+        line = rest.trim_start();
+    }
+    line
+}
+
+/// Strip inline markup from one line of text.
+fn inline(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        // `![alt](url)` and `[text](url)` keep only the bracketed text.
+        let image = rest.starts_with("![");
+        if (image || c == '[')
+            && let Some((label, after)) = link(&rest[usize::from(image) + 1..])
+        {
+            out.push_str(&inline(label));
+            rest = after;
+            continue;
+        }
+        match c {
+            '`' | '*' => {}
+            '_' if rest.starts_with("__") => {
+                rest = &rest[2..];
+                continue;
+            }
+            '\\' if rest.len() > 1 => {
+                rest = &rest[1..];
+                let escaped = rest.chars().next().unwrap_or_default();
+                out.push(escaped);
+                rest = &rest[escaped.len_utf8()..];
+                continue;
+            }
+            _ => out.push(c),
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// Given the text after `[`, split `label](target)rest` into the label and
+/// what follows the closing parenthesis.
+fn link(text: &str) -> Option<(&str, &str)> {
+    let close = text.find("](")?;
+    let label = &text[..close];
+    if label.contains('[') {
+        return None;
+    }
+    let after = &text[close + 2..];
+    let end = after.find(')')?;
+    Some((label, &after[end + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingest::Section;
+
+    fn section(headings: &[&str], text: &str) -> Section {
+        Section {
+            headings: headings.iter().map(|h| h.to_string()).collect(),
+            text: text.into(),
+        }
+    }
+
+    #[test]
+    fn headings_start_sections() {
+        let doc = parse(
+            "---\ntitle: ignored\n---\nPreamble.\n\n# Guide\n\nIntro text.\n\n## Install ##\n\nStep one.\n\n### Linux\nUse apt.\n\n## Usage\nRun it.",
+        );
+        assert_eq!(doc.title.as_deref(), Some("Guide"));
+        assert_eq!(
+            doc.sections,
+            [
+                section(&[], "Preamble."),
+                section(&["Guide"], "Intro text."),
+                section(&["Guide", "Install"], "Step one."),
+                section(&["Guide", "Install", "Linux"], "Use apt."),
+                section(&["Guide", "Usage"], "Run it."),
+            ]
+        );
+    }
+
+    #[test]
+    fn setext_headings() {
+        let doc = parse("Title\n=====\n\nBody.\n\nPart\n----\nMore.");
+        assert_eq!(doc.title.as_deref(), Some("Title"));
+        assert_eq!(
+            doc.sections,
+            [
+                section(&["Title"], "Body."),
+                section(&["Title", "Part"], "More."),
+            ]
+        );
+    }
+
+    #[test]
+    fn inline_markup_is_stripped() {
+        let doc = parse(
+            "> **Bold** and *italic* with `code`, a [link](https://x.dev) and ![an image](a.png).\n<!-- hidden\nstill hidden -->\n- item_one\n\n***\n\nsnake_case \\*stays\\*",
+        );
+        assert_eq!(
+            doc.sections[0].text,
+            "Bold and italic with code, a link and an image.\n- item_one\n\nsnake_case *stays*"
+        );
+    }
+
+    #[test]
+    fn code_blocks_are_verbatim() {
+        let doc = parse("Example:\n\n```rust\n# not a heading\nlet x = *y;\n```\nAfter.");
+        assert!(doc.sections[0].headings.is_empty());
+        assert_eq!(
+            doc.sections[0].text,
+            "Example:\n\n# not a heading\nlet x = *y;\nAfter."
+        );
+    }
+}
@@ -0,0 +1,668 @@
+//! Document ingestion.
+//!
+//! Documents are parsed into sections ([`markdown`], [`html`], [`pdf`] or
+//! plain text), split into chunks by [`chunk`], and stored as memories tagged
+//! with their document:
+//!
+//! - `document` — every chunk of every ingested document
+//! - `document:<id>` — every chunk of one document
+//! - `chunk:<n>` — the chunk's position in its document
+//! - `format:<name>` — the format it was parsed from
+//!
+//! Re-ingesting a document replaces its chunks. Chunks are embedded when the
+//! backend is wrapped by [`with_embeddings`](crate::with_embeddings).
+//! [`watch`] tracks a directory so changed files can be re-ingested.
+
+pub mod chunk;
+pub mod html;
+pub mod markdown;
+pub mod pdf;
+pub mod watch;
+
+pub use chunk::{Chunk, ChunkingOptions, chunk_document};
+pub use watch::{DirectoryChanges, DirectoryWatcher};
+
+use chrono::Utc;
+use rustedclaw_config::MemoryIngestConfig;
+use rustedclaw_core::error::MemoryError;
+use rustedclaw_core::memory::{MemoryBackend, MemoryEntry, MemoryFilter, MemoryScope};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Tag carried by every chunk of every ingested document.
+pub const DOCUMENT_TAG: &str = "document";
+
+/// Tag carried by every chunk of the document `document_id`.
+pub fn document_tag(document_id: &str) -> String {
+    format!("{DOCUMENT_TAG}:{document_id}")
+}
+
+/// Prefix of the tag carrying a chunk's position in its document.
+pub const CHUNK_TAG: &str = "chunk";
+
+/// Tag carried by the chunk at `index` in its document.
+pub fn chunk_tag(index: usize) -> String {
+    format!("{CHUNK_TAG}:{index}")
+}
+
+/// Scalar metadata fields as `key:value` tags, so they survive in any backend.
+pub fn metadata_tags(metadata: &serde_json::Value) -> Vec<String> {
+    let serde_json::Value::Object(fields) = metadata else {
+        return Vec::new();
+    };
+    fields
+        .iter()
+        .filter_map(|(key, value)| match value {
+            serde_json::Value::String(s) => Some(format!("{key}:{s}")),
+            serde_json::Value::Number(_) | serde_json::Value::Bool(_) => {
+                Some(format!("{key}:{value}"))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Formats documents can be parsed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentFormat {
+    Markdown,
+    Html,
+    Text,
+    Pdf,
+}
+
+impl DocumentFormat {
+    /// Every supported format.
+    pub const ALL: [DocumentFormat; 4] = [Self::Markdown, Self::Html, Self::Text, Self::Pdf];
+
+    /// The format for a file extension, a format name or a MIME type.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_ascii_lowercase();
+        let name = name.split(';').next().unwrap_or_default().trim();
+        match name {
+            "md" | "markdown" | "mdx" | "text/markdown" => Some(Self::Markdown),
+            "html" | "htm" | "xhtml" | "text/html" | "application/xhtml+xml" => Some(Self::Html),
+            "txt" | "text" | "plain" | "text/plain" => Some(Self::Text),
+            "pdf" | "application/pdf" => Some(Self::Pdf),
+            _ => None,
+        }
+    }
+
+    /// The format for a path, from its extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_name)
+    }
+
+    /// Name used in `format:<name>` tags.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Markdown => "markdown",
+            Self::Html => "html",
+            Self::Text => "text",
+            Self::Pdf => "pdf",
+        }
+    }
+}
+
+/// A run of text under one heading path.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Section {
+    /// Enclosing headings, outermost first; empty before the first heading.
+    pub headings: Vec<String>,
+    pub text: String,
+}
+
+/// Text extracted from a document.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedDocument {
+    pub title: Option<String>,
+    pub sections: Vec<Section>,
+}
+
+impl ParsedDocument {
+    /// Whether no section has any text.
+    pub fn is_empty(&self) -> bool {
+        self.sections.iter().all(|s| s.text.trim().is_empty())
+    }
+}
+
+/// Collects sections while a parser walks a document.
+#[derive(Default)]
+pub(crate) struct SectionBuilder {
+    headings: Vec<(usize, String)>,
+    text: String,
+    sections: Vec<Section>,
+}
+
+impl SectionBuilder {
+    /// Append a line of text to the current section.
+    pub(crate) fn line(&mut self, line: &str) {
+        self.text.push_str(line);
+        self.text.push('\n');
+    }
+
+    /// Start a section under a heading of `level` (1 is outermost).
+    pub(crate) fn heading(&mut self, level: usize, heading: &str) {
+        self.finish_section();
+        self.headings.retain(|(l, _)| *l < level);
+        self.headings.push((level, heading.to_string()));
+    }
+
+    fn finish_section(&mut self) {
+        let text = collapse_blank_lines(&std::mem::take(&mut self.text));
+        if !text.is_empty() {
+            self.sections.push(Section {
+                headings: self.headings.iter().map(|(_, h)| h.clone()).collect(),
+                text,
+            });
+        }
+    }
+
+    pub(crate) fn finish(mut self, title: Option<String>) -> ParsedDocument {
+        self.finish_section();
+        ParsedDocument {
+            title,
+            sections: self.sections,
+        }
+    }
+}
+
+/// Trim every line, and keep at most one blank line between paragraphs.
+pub(crate) fn collapse_blank_lines(text: &str) -> String {
+    let mut out = String::new();
+    let mut blank = false;
+    for line in text.lines().map(str::trim_end) {
+        if line.trim().is_empty() {
+            blank = !out.is_empty();
+            continue;
+        }
+        if blank {
+            out.push('\n');
+            blank = false;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.truncate(out.trim_end().len());
+    out
+}
+
+/// Extract text from `content` in `format`.
+pub fn parse(format: DocumentFormat, content: &[u8]) -> Result<ParsedDocument, MemoryError> {
+    let text = || {
+        let text = String::from_utf8_lossy(content);
+        text.strip_prefix('\u{feff}').unwrap_or(&text).to_string()
+    };
+    let parsed = match format {
+        DocumentFormat::Markdown => markdown::parse(&text()),
+        DocumentFormat::Html => html::parse(&text()),
+        DocumentFormat::Text => {
+            let mut sections = SectionBuilder::default();
+            for line in text().lines() {
+                sections.line(line);
+            }
+            sections.finish(None)
+        }
+        DocumentFormat::Pdf => pdf::parse(content)?,
+    };
+    if parsed.is_empty() {
+        return Err(MemoryError::InvalidDocument(format!(
+            "no text found in {} document",
+            format.name()
+        )));
+    }
+    Ok(parsed)
+}
+
+/// A document to ingest.
+#[derive(Debug, Clone)]
+pub struct Document {
+    /// Stable ID; re-ingesting the same ID replaces the document's chunks.
+    pub id: String,
+    /// Where the document came from (a path or URL), stored as each chunk's
+    /// source.
+    pub source: String,
+    pub format: DocumentFormat,
+    pub content: Vec<u8>,
+    /// Extra tags added to every chunk.
+    pub tags: Vec<String>,
+    pub scope: MemoryScope,
+}
+
+impl Document {
+    /// Read the file at `path`; its canonical path is both its ID and source.
+    pub async fn from_path(path: &Path) -> Result<Self, MemoryError> {
+        let format = DocumentFormat::from_path(path).ok_or_else(|| {
+            MemoryError::InvalidDocument(format!("unsupported file type: {}", path.display()))
+        })?;
+        let content = tokio::fs::read(path)
+            .await
+            .map_err(|e| MemoryError::Storage(format!("Failed to read {}: {e}", path.display())))?;
+        let id = path_id(path);
+        Ok(Self {
+            source: id.clone(),
+            id,
+            format,
+            content,
+            tags: Vec::new(),
+            scope: MemoryScope::default(),
+        })
+    }
+}
+
+/// Document ID of the file at `path`.
+pub fn path_id(path: &Path) -> String {
+    std::fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .display()
+        .to_string()
+}
+
+/// What ingesting one document stored.
+#[derive(Debug, Clone)]
+pub struct IngestReport {
+    pub document_id: String,
+    pub title: Option<String>,
+    /// IDs of the stored chunks, in document order.
+    pub chunk_ids: Vec<String>,
+}
+
+/// What one [`DocumentIngestor::sync`] pass changed.
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub ingested: Vec<IngestReport>,
+    /// Document IDs of files that disappeared.
+    pub removed: Vec<String>,
+    pub failed: Vec<(PathBuf, MemoryError)>,
+}
+
+/// Parses, chunks and stores documents in a memory backend.
+pub struct DocumentIngestor {
+    memory: Arc<dyn MemoryBackend>,
+    options: ChunkingOptions,
+}
+
+impl DocumentIngestor {
+    pub fn new(memory: Arc<dyn MemoryBackend>) -> Self {
+        Self {
+            memory,
+            options: ChunkingOptions::default(),
+        }
+    }
+
+    /// Ingestor chunking as configured in `[memory.ingest]`.
+    pub fn from_config(memory: Arc<dyn MemoryBackend>, config: &MemoryIngestConfig) -> Self {
+        Self::new(memory).with_chunking(ChunkingOptions {
+            chunk_size: config.chunk_size,
+            chunk_overlap: config.chunk_overlap,
+            heading_aware: config.heading_aware,
+        })
+    }
+
+    pub fn with_chunking(mut self, options: ChunkingOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Parse, chunk and store `document`, replacing any earlier version.
+    pub async fn ingest(&self, document: Document) -> Result<IngestReport, MemoryError> {
+        let parsed = parse(document.format, &document.content)?;
+        let chunks = chunk_document(&parsed, &self.options);
+
+        let now = Utc::now();
+        let entries = chunks
+            .into_iter()
+            .map(|chunk| {
+                let mut tags = vec![
+                    DOCUMENT_TAG.to_string(),
+                    document_tag(&document.id),
+                    chunk_tag(chunk.index),
+                    format!("format:{}", document.format.name()),
+                ];
+                tags.extend(document.tags.iter().cloned());
+                MemoryEntry {
+                    id: format!("{}#{}", document.id, chunk.index),
+                    content: chunk.text,
+                    tags,
+                    source: Some(document.source.clone()),
+                    created_at: now,
+                    last_accessed: now,
+                    access_count: 0,
+                    expires_at: None,
+                    score: 0.0,
+                    embedding: None,
+                    embedding_model: None,
+                    scope: document.scope.clone(),
+                }
+            })
+            .collect();
+
+        self.remove(&document.id).await?;
+        let chunk_ids = self.memory.store_batch(entries).await?;
+        info!(
+            document_id = %document.id,
+            chunks = chunk_ids.len(),
+            "Ingested document"
+        );
+        Ok(IngestReport {
+            document_id: document.id,
+            title: parsed.title,
+            chunk_ids,
+        })
+    }
+
+    /// Ingest the file at `path`.
+    pub async fn ingest_path(&self, path: &Path) -> Result<IngestReport, MemoryError> {
+        self.ingest(Document::from_path(path).await?).await
+    }
+
+    /// Forget every chunk of a document; returns how many were removed.
+    pub async fn remove(&self, document_id: &str) -> Result<usize, MemoryError> {
+        self.memory
+            .delete_where(&MemoryFilter {
+                tags: vec![document_tag(document_id)],
+                ..MemoryFilter::default()
+            })
+            .await
+    }
+
+    /// Ingest the files `watcher` saw change and forget the ones it saw
+    /// disappear. A file that fails does not stop the others; it is left
+    /// unmarked so the next sync retries it, unless it can't be parsed, in
+    /// which case it waits until it changes again.
+    pub async fn sync(&self, watcher: &mut DirectoryWatcher) -> Result<SyncReport, MemoryError> {
+        let changes = watcher
+            .scan()
+            .map_err(|e| MemoryError::Storage(format!("Failed to scan documents: {e}")))?;
+        let mut report = SyncReport::default();
+        for path in changes.removed {
+            let id = path.display().to_string();
+            match self.remove(&id).await {
+                Ok(_) => {
+                    watcher.mark_synced(&path);
+                    report.removed.push(id);
+                }
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "Failed to remove document");
+                    report.failed.push((path, e));
+                }
+            }
+        }
+        for path in changes.changed {
+            match self.ingest_path(&path).await {
+                Ok(ingested) => {
+                    watcher.mark_synced(&path);
+                    report.ingested.push(ingested);
+                }
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "Failed to ingest document");
+                    if matches!(e, MemoryError::InvalidDocument(_)) {
+                        watcher.mark_synced(&path);
+                    }
+                    report.failed.push((path, e));
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryBackend;
+
+    fn document(id: &str, format: DocumentFormat, content: &str) -> Document {
+        Document {
+            id: id.into(),
+            source: format!("https://example.com/{id}"),
+            format,
+            content: content.as_bytes().to_vec(),
+            tags: vec!["team:docs".into()],
+            scope: MemoryScope::default(),
+        }
+    }
+
+    fn all_chunks() -> MemoryFilter {
+        MemoryFilter {
+            tags: vec![DOCUMENT_TAG.into()],
+            ..MemoryFilter::default()
+        }
+    }
+
+    #[test]
+    fn formats_from_names_and_paths() {
+        assert_eq!(
+            DocumentFormat::from_path(Path::new("notes/README.MD")),
+            Some(DocumentFormat::Markdown)
+        );
+        assert_eq!(
+            DocumentFormat::from_name("text/html; charset=utf-8"),
+            Some(DocumentFormat::Html)
+        );
+        assert_eq!(
+            DocumentFormat::from_name("application/pdf"),
+            Some(DocumentFormat::Pdf)
+        );
+        assert_eq!(DocumentFormat::from_path(Path::new("photo.png")), None);
+        for format in DocumentFormat::ALL {
+            assert_eq!(DocumentFormat::from_name(format.name()), Some(format));
+        }
+    }
+
+    #[test]
+    fn empty_documents_are_rejected() {
+        let err = parse(DocumentFormat::Html, b"<html><script>x()</script></html>").unwrap_err();
+        assert!(matches!(err, MemoryError::InvalidDocument(_)));
+    }
+
+    #[tokio::test]
+    async fn chunks_are_tagged_and_replaced_on_reingest() {
+        let memory = Arc::new(InMemoryBackend::new());
+        let ingestor = DocumentIngestor::new(memory.clone()).with_chunking(ChunkingOptions {
+            chunk_size: 16,
+            chunk_overlap: 0,
+            heading_aware: true,
+        });
+
+        let report = ingestor
+            .ingest(document(
+                "guide",
+                DocumentFormat::Markdown,
+                "# Guide\n\nInstall the binary first.\n\n## Usage\n\nRun it with a config file.",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(report.title.as_deref(), Some("Guide"));
+        assert_eq!(report.chunk_ids, ["guide#0", "guide#1"]);
+
+        let second = memory.get("guide#1").await.unwrap().unwrap();
+        assert_eq!(
+            second.content,
+            "Guide › Usage\n\nRun it with a config file."
+        );
+        assert_eq!(second.source.as_deref(), Some("https://example.com/guide"));
+        for tag in [
+            "document",
+            "document:guide",
+            "chunk:1",
+            "format:markdown",
+            "team:docs",
+        ] {
+            assert!(second.tags.iter().any(|t| t == tag), "missing {tag}");
+        }
+
+        ingestor
+            .ingest(document("other", DocumentFormat::Text, "Unrelated."))
+            .await
+            .unwrap();
+        let report = ingestor
+            .ingest(document("guide", DocumentFormat::Text, "Rewritten."))
+            .await
+            .unwrap();
+        assert_eq!(report.chunk_ids, ["guide#0"]);
+        assert_eq!(memory.list(&all_chunks(), 0, 10).await.unwrap().len(), 2);
+
+        assert_eq!(ingestor.remove("guide").await.unwrap(), 1);
+        assert_eq!(memory.count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn sync_follows_directory_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let notes = dir.path().join("notes.md");
+        std::fs::write(&notes, "# Notes\n\nFirst draft.").unwrap();
+        std::fs::write(dir.path().join("image.png"), [0u8; 4]).unwrap();
+
+        let memory = Arc::new(InMemoryBackend::new());
+        let ingestor = DocumentIngestor::new(memory.clone());
+        let mut watcher = DirectoryWatcher::new(dir.path());
+
+        let report = ingestor.sync(&mut watcher).await.unwrap();
+        assert_eq!(report.ingested.len(), 1);
+        assert_eq!(report.ingested[0].document_id, path_id(&notes));
+
+        let report = ingestor.sync(&mut watcher).await.unwrap();
+        assert!(report.ingested.is_empty(), "unchanged files are skipped");
+
+        std::fs::write(&notes, "# Notes\n\nSecond draft, somewhat longer.").unwrap();
+        let report = ingestor.sync(&mut watcher).await.unwrap();
+        assert_eq!(report.ingested.len(), 1);
+        let chunks = memory.list(&all_chunks(), 0, 10).await.unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].content.contains("Second draft"));
+
+        std::fs::remove_file(&notes).unwrap();
+        let report = ingestor.sync(&mut watcher).await.unwrap();
+        assert_eq!(report.removed, [path_id(dir.path()) + "/notes.md"]);
+        assert_eq!(memory.count().await.unwrap(), 0);
+    }
+
+    /// Fails every store until `healthy` is set.
+    struct FlakyBackend {
+        inner: InMemoryBackend,
+        healthy: std::sync::atomic::AtomicBool,
+    }
+
+    #[async_trait::async_trait]
+    impl MemoryBackend for FlakyBackend {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+        async fn store(&self, entry: MemoryEntry) -> Result<String, MemoryError> {
+            if !self.healthy.load(std::sync::atomic::Ordering::Relaxed) {
+                return Err(MemoryError::Storage("disk full".into()));
+            }
+            self.inner.store(entry).await
+        }
+        async fn search(
+            &self,
+            query: rustedclaw_core::memory::MemoryQuery,
+        ) -> Result<Vec<MemoryEntry>, MemoryError> {
+            self.inner.search(query).await
+        }
+        async fn get(&self, id: &str) -> Result<Option<MemoryEntry>, MemoryError> {
+            self.inner.get(id).await
+        }
+        async fn delete(&self, id: &str) -> Result<bool, MemoryError> {
+            self.inner.delete(id).await
+        }
+        async fn count(&self) -> Result<usize, MemoryError> {
+            self.inner.count().await
+        }
+        async fn clear(&self) -> Result<(), MemoryError> {
+            self.inner.clear().await
+        }
+        async fn compact(&self) -> Result<usize, MemoryError> {
+            self.inner.compact().await
+        }
+        async fn update(
+            &self,
+            id: &str,
+            patch: rustedclaw_core::memory::MemoryPatch,
+        ) -> Result<Option<MemoryEntry>, MemoryError> {
+            self.inner.update(id, patch).await
+        }
+        async fn delete_where(&self, filter: &MemoryFilter) -> Result<usize, MemoryError> {
+            self.inner.delete_where(filter).await
+        }
+        async fn list(
+            &self,
+            filter: &MemoryFilter,
+            offset: usize,
+            limit: usize,
+        ) -> Result<Vec<MemoryEntry>, MemoryError> {
+            self.inner.list(filter, offset, limit).await
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_files_are_retried_by_the_next_sync() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.md"), "# Notes\n\nFirst draft.").unwrap();
+        std::fs::write(dir.path().join("empty.html"), "<html></html>").unwrap();
+
+        let memory = Arc::new(FlakyBackend {
+            inner: InMemoryBackend::new(),
+            healthy: false.into(),
+        });
+        let ingestor = DocumentIngestor::new(memory.clone());
+        let mut watcher = DirectoryWatcher::new(dir.path());
+
+        let report = ingestor.sync(&mut watcher).await.unwrap();
+        assert!(report.ingested.is_empty());
+        assert_eq!(report.failed.len(), 2);
+
+        // The storage failure is retried; the unparsable file waits for a change.
+        memory
+            .healthy
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+        let report = ingestor.sync(&mut watcher).await.unwrap();
+        assert_eq!(report.ingested.len(), 1);
+        assert!(report.failed.is_empty());
+        assert!(
+            ingestor
+                .sync(&mut watcher)
+                .await
+                .unwrap()
+                .ingested
+                .is_empty()
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn sqlite_reingest_replaces_chunks_of_underscored_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let notes = dir.path().join("meeting_notes.md");
+        std::fs::write(&notes, "# Notes\n\nFirst draft.").unwrap();
+        // Matches `meeting_notes` if `_` were a LIKE wildcard.
+        std::fs::write(dir.path().join("meetingXnotes.md"), "# Other\n\nKeep me.").unwrap();
+
+        let memory = Arc::new(
+            crate::sqlite::SqliteBackend::new("sqlite::memory:")
+                .await
+                .unwrap(),
+        );
+        let ingestor = DocumentIngestor::new(memory.clone());
+        let mut watcher = DirectoryWatcher::new(dir.path());
+        assert_eq!(ingestor.sync(&mut watcher).await.unwrap().ingested.len(), 2);
+
+        std::fs::write(&notes, "# Notes\n\nSecond draft.").unwrap();
+        let report = ingestor.sync(&mut watcher).await.unwrap();
+        assert_eq!(report.ingested.len(), 1);
+        let chunks = memory.list(&all_chunks(), 0, 10).await.unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().any(|c| c.content.contains("Second draft")));
+        assert!(chunks.iter().any(|c| c.content.contains("Keep me")));
+        assert!(!chunks.iter().any(|c| c.content.contains("First draft")));
+
+        assert_eq!(ingestor.remove(&path_id(&notes)).await.unwrap(), 1);
+        let chunks = memory.list(&all_chunks(), 0, 10).await.unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].content.contains("Keep me"));
+    }
+}
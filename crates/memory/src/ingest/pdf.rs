@@ -0,0 +1,542 @@
+//! PDF text extraction.
+//!
+//! Content streams (uncompressed or `FlateDecode`) are scanned for the text
+//! operators `Tj`, `TJ`, `'` and `"`, with line breaks inferred from text
+//! positioning. Each page-level stream becomes a paragraph; the document
+//! `/Title`, when stored uncompressed, is the title.
+//!
+//! This covers PDFs produced by most writers with standard fonts. Text drawn
+//! with embedded CID fonts comes out as glyph codes, and encrypted or scanned
+//! PDFs have no extractable text; both are reported as errors by
+//! [`parse`](super::parse) when nothing readable is found.
+
+use super::{ParsedDocument, SectionBuilder};
+use flate2::read::{DeflateDecoder, ZlibDecoder};
+use rustedclaw_core::error::MemoryError;
+use std::io::Read;
+
+/// Decompressed streams larger than this are skipped, to bound memory on
+/// hostile input.
+const MAX_STREAM_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Extract text from a PDF file.
+pub fn parse(data: &[u8]) -> Result<ParsedDocument, MemoryError> {
+    if find(&data[..data.len().min(1024)], b"%PDF-").is_none() {
+        return Err(MemoryError::InvalidDocument("not a PDF file".into()));
+    }
+    if find(data, b"/Encrypt").is_some() {
+        return Err(MemoryError::InvalidDocument(
+            "encrypted PDFs are not supported".into(),
+        ));
+    }
+
+    let mut sections = SectionBuilder::default();
+    for content in streams(data) {
+        let text = extract_text(&content);
+        if !text.trim().is_empty() {
+            for line in text.lines() {
+                sections.line(line.trim());
+            }
+            sections.line("");
+        }
+    }
+    Ok(sections.finish(title(data)))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|w| w == needle)
+}
+
+/// Decoded data of every stream that can hold page content.
+fn streams(data: &[u8]) -> Vec<Vec<u8>> {
+    let mut streams = Vec::new();
+    let mut pos = 0;
+    while let Some(found) = find(&data[pos..], b"stream") {
+        let keyword = pos + found;
+        pos = keyword + b"stream".len();
+        // Skip `endstream` and anything that is not the stream keyword.
+        if data[..keyword].ends_with(b"end") {
+            continue;
+        }
+        let start = match &data[pos..] {
+            [b'\r', b'\n', ..] => pos + 2,
+            [b'\n' | b'\r', ..] => pos + 1,
+            _ => continue,
+        };
+        let dict_start = rfind(&data[..keyword], b"obj").map_or(0, |at| at + 3);
+        let dict = &data[dict_start..keyword];
+        let Some(end) = find(&data[start..], b"endstream").map(|at| start + at) else {
+            break;
+        };
+        pos = end;
+
+        let raw = trim_eol(&data[start..end]);
+        if let Some(content) = decode_stream(dict, raw) {
+            streams.push(content);
+        }
+    }
+    streams
+}
+
+fn trim_eol(data: &[u8]) -> &[u8] {
+    let data = data.strip_suffix(b"\n").unwrap_or(data);
+    data.strip_suffix(b"\r").unwrap_or(data)
+}
+
+/// Decode a stream if it may contain text: not an image, font, XRef or
+/// metadata stream, and compressed with nothing but `FlateDecode`.
+fn decode_stream(dict: &[u8], raw: &[u8]) -> Option<Vec<u8>> {
+    const NOT_CONTENT: &[&[u8]] = &[
+        b"/Image",
+        b"/Length1",
+        b"/Length2",
+        b"/Length3",
+        b"/XRef",
+        b"/ObjStm",
+        b"/Metadata",
+        b"/EmbeddedFile",
+        b"/ICCBased",
+    ];
+    if NOT_CONTENT
+        .iter()
+        .any(|marker| find(dict, marker).is_some())
+    {
+        return None;
+    }
+    match filters(dict).as_slice() {
+        [] => Some(raw.to_vec()),
+        [only] if only == "FlateDecode" || only == "Fl" => inflate(raw),
+        _ => None,
+    }
+}
+
+/// Decompress a `FlateDecode` stream. A missing or foreign zlib header is
+/// tolerated by decoding the data as raw DEFLATE, as PDF readers do.
+fn inflate(raw: &[u8]) -> Option<Vec<u8>> {
+    fn read_capped(reader: impl Read) -> Option<Vec<u8>> {
+        let mut out = Vec::new();
+        reader
+            .take(MAX_STREAM_BYTES + 1)
+            .read_to_end(&mut out)
+            .ok()?;
+        (out.len() as u64 <= MAX_STREAM_BYTES).then_some(out)
+    }
+    read_capped(ZlibDecoder::new(raw)).or_else(|| read_capped(DeflateDecoder::new(raw)))
+}
+
+/// Names in a stream dictionary's `/Filter` entry.
+fn filters(dict: &[u8]) -> Vec<String> {
+    let Some(at) = find(dict, b"/Filter") else {
+        return Vec::new();
+    };
+    let value = dict[at + b"/Filter".len()..].trim_ascii_start();
+    let value = match value.strip_prefix(b"[") {
+        Some(array) => &array[..find(array, b"]").unwrap_or(array.len())],
+        None => {
+            let end = value[1.min(value.len())..]
+                .iter()
+                .position(|c| c.is_ascii_whitespace() || b"/<>[]".contains(c))
+                .map_or(value.len(), |at| at + 1);
+            &value[..end]
+        }
+    };
+    String::from_utf8_lossy(value)
+        .split(['/', ' ', '\n', '\r', '\t'])
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// The document information `/Title`, if stored as a plain string.
+fn title(data: &[u8]) -> Option<String> {
+    let at = find(data, b"/Title")?;
+    let mut lexer = Lexer::new(&data[at + b"/Title".len()..]);
+    match lexer.token()? {
+        Token::String(bytes) => {
+            let title = decode_string(&bytes);
+            let title = title.trim();
+            (!title.is_empty()).then(|| title.to_string())
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    String(Vec<u8>),
+    Number(f64),
+    Array(Vec<Token>),
+    Operator(String),
+    /// Names, dictionaries and anything else text extraction ignores.
+    Other,
+}
+
+struct Lexer<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_ascii_whitespace() || c == 0 {
+                self.pos += 1;
+            } else if c == b'%' {
+                while self.peek().is_some_and(|c| c != b'\n' && c != b'\r') {
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn token(&mut self) -> Option<Token> {
+        self.skip_whitespace();
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(match c {
+            b'(' => Token::String(self.literal_string()),
+            b'<' if self.peek() == Some(b'<') => {
+                self.pos += 1;
+                Token::Other
+            }
+            b'<' => Token::String(self.hex_string()),
+            b'>' => {
+                if self.peek() == Some(b'>') {
+                    self.pos += 1;
+                }
+                Token::Other
+            }
+            b'[' => {
+                let mut items = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        None => break,
+                        Some(b']') => {
+                            self.pos += 1;
+                            break;
+                        }
+                        _ => items.push(self.token()?),
+                    }
+                }
+                Token::Array(items)
+            }
+            b']' | b'{' | b'}' | b')' => Token::Other,
+            b'/' => {
+                self.regular();
+                Token::Other
+            }
+            b'0'..=b'9' | b'-' | b'+' | b'.' => {
+                let start = self.pos - 1;
+                self.regular();
+                std::str::from_utf8(&self.data[start..self.pos])
+                    .ok()
+                    .and_then(|n| n.parse().ok())
+                    .map_or(Token::Other, Token::Number)
+            }
+            _ => {
+                let start = self.pos - 1;
+                self.regular();
+                Token::Operator(String::from_utf8_lossy(&self.data[start..self.pos]).into())
+            }
+        })
+    }
+
+    /// Advance past regular characters (not whitespace or delimiters).
+    fn regular(&mut self) {
+        while self
+            .peek()
+            .is_some_and(|c| !c.is_ascii_whitespace() && !b"()<>[]{}/%".contains(&c))
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn literal_string(&mut self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                b'(' => {
+                    depth += 1;
+                    out.push(c);
+                }
+                b')' if depth == 0 => break,
+                b')' => {
+                    depth -= 1;
+                    out.push(c);
+                }
+                b'\\' => {
+                    let Some(escaped) = self.peek() else { break };
+                    self.pos += 1;
+                    match escaped {
+                        b'n' => out.push(b'\n'),
+                        b'r' => out.push(b'\r'),
+                        b't' => out.push(b'\t'),
+                        b'b' => out.push(0x08),
+                        b'f' => out.push(0x0c),
+                        b'0'..=b'7' => {
+                            let mut value = u32::from(escaped - b'0');
+                            for _ in 0..2 {
+                                match self.peek() {
+                                    Some(d @ b'0'..=b'7') => {
+                                        value = value * 8 + u32::from(d - b'0');
+                                        self.pos += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            out.push(value as u8);
+                        }
+                        // A backslash before a line break continues the string.
+                        b'\r' => {
+                            if self.peek() == Some(b'\n') {
+                                self.pos += 1;
+                            }
+                        }
+                        b'\n' => {}
+                        other => out.push(other),
+                    }
+                }
+                _ => out.push(c),
+            }
+        }
+        out
+    }
+
+    fn hex_string(&mut self) -> Vec<u8> {
+        let mut digits = Vec::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            if c == b'>' {
+                break;
+            }
+            if let Some(d) = (c as char).to_digit(16) {
+                digits.push(d as u8);
+            }
+        }
+        if digits.len() % 2 == 1 {
+            digits.push(0);
+        }
+        digits
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair[1])
+            .collect()
+    }
+
+    /// Skip inline image data, which follows `ID` up to `EI`.
+    fn skip_inline_image(&mut self) {
+        let rest = &self.data[self.pos..];
+        self.pos += rest
+            .windows(3)
+            .position(|w| w[0].is_ascii_whitespace() && &w[1..] == b"EI")
+            .map_or(rest.len(), |at| at + 3);
+    }
+}
+
+/// Text drawn by a content stream.
+fn extract_text(content: &[u8]) -> String {
+    let mut out = String::new();
+    let newline = |out: &mut String| {
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+    };
+    let space = |out: &mut String| {
+        if !out.is_empty() && !out.ends_with([' ', '\n']) {
+            out.push(' ');
+        }
+    };
+
+    let mut lexer = Lexer::new(content);
+    let mut operands: Vec<Token> = Vec::new();
+    let mut line_y: Option<f64> = None;
+    while let Some(token) = lexer.token() {
+        let Token::Operator(op) = token else {
+            operands.push(token);
+            continue;
+        };
+        let number = |i: usize| match operands.get(i) {
+            Some(Token::Number(n)) => *n,
+            _ => 0.0,
+        };
+        match op.as_str() {
+            "BT" => line_y = None,
+            "ET" => newline(&mut out),
+            "Td" | "TD" => {
+                if number(1).abs() > 0.01 {
+                    newline(&mut out);
+                } else {
+                    space(&mut out);
+                }
+            }
+            "Tm" => {
+                let y = number(5);
+                if line_y.is_some_and(|previous| (previous - y).abs() > 0.01) {
+                    newline(&mut out);
+                } else {
+                    space(&mut out);
+                }
+                line_y = Some(y);
+            }
+            "T*" => newline(&mut out),
+            "Tj" | "'" | "\"" => {
+                if op != "Tj" {
+                    newline(&mut out);
+                }
+                if let Some(Token::String(bytes)) = operands.last() {
+                    out.push_str(&decode_string(bytes));
+                }
+            }
+            "TJ" => {
+                if let Some(Token::Array(items)) = operands.last() {
+                    for item in items {
+                        match item {
+                            Token::String(bytes) => out.push_str(&decode_string(bytes)),
+                            // Large negative adjustments are word gaps.
+                            Token::Number(n) if *n < -200.0 => space(&mut out),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            "ID" => lexer.skip_inline_image(),
+            _ => {}
+        }
+        operands.clear();
+    }
+    out
+}
+
+/// Decode a PDF string: UTF-16BE with a byte order mark, otherwise one
+/// character per byte. Control characters are dropped.
+fn decode_string(bytes: &[u8]) -> String {
+    let text = match bytes {
+        [0xfe, 0xff, rest @ ..] => {
+            let units: Vec<u16> = rest
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => bytes.iter().map(|&b| char::from(b)).collect(),
+    };
+    text.chars()
+        .filter(|c| !c.is_control() || *c == '\n')
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A minimal PDF holding `streams` as page content, with offsets that
+    /// are close enough for a forgiving reader.
+    fn pdf(info: &str, streams: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut out = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        for (i, (dict, data)) in streams.iter().enumerate() {
+            out.extend(
+                format!(
+                    "{} 0 obj\n<< {dict} /Length {} >>\nstream\n",
+                    i + 4,
+                    data.len()
+                )
+                .bytes(),
+            );
+            out.extend(data);
+            out.extend(b"\nendstream\nendobj\n");
+        }
+        out.extend(
+            format!("3 0 obj\n<< {info} >>\nendobj\ntrailer\n<< /Info 3 0 R >>\n%%EOF\n").bytes(),
+        );
+        out
+    }
+
+    #[test]
+    fn extracts_text_operators() {
+        let content = br"BT /F1 12 Tf 72 720 Td (Hello, ) Tj (world!) Tj 0 -14 Td [(Kern)-30(ed) -400(words)] TJ T* (Escaped \(parens\) and \101\102) Tj ET";
+        assert_eq!(
+            extract_text(content),
+            "Hello, world!\nKerned words\nEscaped (parens) and AB\n"
+        );
+    }
+
+    #[test]
+    fn hex_and_utf16_strings() {
+        let content = b"BT <48656C6C6F> Tj 0 -14 Td <FEFF00E90074006500> Tj ET";
+        assert_eq!(extract_text(content), "Hello\n\u{e9}te\n");
+    }
+
+    #[test]
+    fn inline_images_are_skipped() {
+        let content = b"BI /W 1 /H 1 ID \x00(Tj)\xff EI BT (After) Tj ET";
+        assert_eq!(extract_text(content), "After\n");
+    }
+
+    #[test]
+    fn parses_plain_and_compressed_pages() {
+        // zlib.compress(b"BT (Second page) Tj ET")
+        let compressed = vec![
+            0x78, 0x9c, 0x73, 0x0a, 0x51, 0xd0, 0x08, 0x4e, 0x4d, 0xce, 0xcf, 0x4b, 0x51, 0x28,
+            0x48, 0x4c, 0x4f, 0xd5, 0x54, 0x08, 0xc9, 0x52, 0x70, 0x0d, 0x01, 0x00, 0x4d, 0xbc,
+            0x06, 0xb8,
+        ];
+        let file = pdf(
+            "/Title (Quarterly report) /Producer (test)",
+            &[
+                ("", b"BT (First page) Tj ET".to_vec()),
+                (
+                    "/Subtype /Image /Filter /DCTDecode",
+                    b"\xff\xd8(Tj) Tj".to_vec(),
+                ),
+                ("/Filter /FlateDecode", compressed),
+            ],
+        );
+        let doc = parse(&file).unwrap();
+        assert_eq!(doc.title.as_deref(), Some("Quarterly report"));
+        assert_eq!(doc.sections.len(), 1);
+        assert_eq!(doc.sections[0].text, "First page\n\nSecond page");
+    }
+
+    #[test]
+    fn inflates_zlib_and_raw_deflate_streams() {
+        use flate2::Compression;
+        use flate2::write::{DeflateEncoder, ZlibEncoder};
+        use std::io::Write;
+
+        let text = b"BT (Compressed) Tj ET".repeat(10);
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(&text).unwrap();
+        let mut raw = DeflateEncoder::new(Vec::new(), Compression::default());
+        raw.write_all(&text).unwrap();
+
+        assert_eq!(inflate(&zlib.finish().unwrap()), Some(text.clone()));
+        assert_eq!(inflate(&raw.finish().unwrap()), Some(text));
+        assert_eq!(inflate(&[0x07, 0xff, 0xff]), None);
+    }
+
+    #[test]
+    fn rejects_non_pdf_and_encrypted_files() {
+        assert!(parse(b"hello").is_err());
+        let file = pdf("/Encrypt 9 0 R", &[]);
+        assert!(matches!(
+            parse(&file),
+            Err(MemoryError::InvalidDocument(msg)) if msg.contains("encrypted")
+        ));
+    }
+}
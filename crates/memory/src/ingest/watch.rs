@@ -0,0 +1,223 @@
+//! Polling for changed documents.
+//!
+//! [`DirectoryWatcher`] remembers the size and modification time of every
+//! supported file under a root; each [`scan`](DirectoryWatcher::scan)
+//! reports what changed since the files were last marked synced. The first
+//! scan reports every file, and a file stays reported until it is marked.
+
+use super::DocumentFormat;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Files that changed between two scans, as canonical paths.
+#[derive(Debug, Default, PartialEq)]
+pub struct DirectoryChanges {
+    /// New or modified files.
+    pub changed: Vec<PathBuf>,
+    /// Files that no longer exist.
+    pub removed: Vec<PathBuf>,
+}
+
+/// Tracks the supported documents under a file or directory.
+pub struct DirectoryWatcher {
+    root: PathBuf,
+    /// State of each file when it was last marked synced.
+    seen: HashMap<PathBuf, Stamp>,
+    /// State of each file at the last scan.
+    scanned: HashMap<PathBuf, Stamp>,
+}
+
+/// Size and modification time of a file.
+type Stamp = (u64, Option<SystemTime>);
+
+impl DirectoryWatcher {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            seen: HashMap::new(),
+            scanned: HashMap::new(),
+        }
+    }
+
+    /// Walk the root and report changes since the files were last marked
+    /// synced. Nothing is recorded until [`mark_synced`](Self::mark_synced),
+    /// so a file that failed to sync is reported again by the next scan.
+    pub fn scan(&mut self) -> io::Result<DirectoryChanges> {
+        let mut files = HashMap::new();
+        match std::fs::canonicalize(&self.root) {
+            Ok(root) => collect(&root, &root, &mut files, &mut HashSet::new())?,
+            // A watched file that was deleted reports as removed.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        let mut changes = DirectoryChanges {
+            changed: files
+                .iter()
+                .filter(|(path, stamp)| self.seen.get(*path) != Some(stamp))
+                .map(|(path, _)| path.clone())
+                .collect(),
+            removed: self
+                .seen
+                .keys()
+                .filter(|path| !files.contains_key(*path))
+                .cloned()
+                .collect(),
+        };
+        changes.changed.sort();
+        changes.removed.sort();
+        self.scanned = files;
+        Ok(changes)
+    }
+
+    /// Record that a path reported by the last scan has been handled: a
+    /// changed file is remembered as scanned, a removed one is forgotten.
+    pub fn mark_synced(&mut self, path: &Path) {
+        match self.scanned.get(path) {
+            Some(stamp) => self.seen.insert(path.to_path_buf(), *stamp),
+            None => self.seen.remove(path),
+        };
+    }
+}
+
+/// Supported files under `path`, by canonical path, skipping hidden
+/// entries. Symlinks are followed within `root` only, and each directory is
+/// walked once, so a link back to a parent can't loop.
+fn collect(
+    root: &Path,
+    path: &Path,
+    files: &mut HashMap<PathBuf, Stamp>,
+    visited: &mut HashSet<PathBuf>,
+) -> io::Result<()> {
+    let canonical = std::fs::canonicalize(path)?;
+    if !canonical.starts_with(root) {
+        return Ok(());
+    }
+    let metadata = std::fs::metadata(&canonical)?;
+    if metadata.is_dir() {
+        if !visited.insert(canonical.clone()) {
+            return Ok(());
+        }
+        for entry in std::fs::read_dir(&canonical)? {
+            let entry = entry?;
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            // Files can vanish between listing and reading.
+            match collect(root, &entry.path(), files, visited) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                other => other?,
+            }
+        }
+    } else if DocumentFormat::from_path(&canonical).is_some() {
+        files.insert(canonical, (metadata.len(), metadata.modified().ok()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scan and mark every reported path synced.
+    fn sync(watcher: &mut DirectoryWatcher) -> DirectoryChanges {
+        let changes = watcher.scan().unwrap();
+        for path in changes.changed.iter().chain(&changes.removed) {
+            watcher.mark_synced(path);
+        }
+        changes
+    }
+
+    #[test]
+    fn reports_new_modified_and_removed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir(root.join("nested")).unwrap();
+        std::fs::create_dir(root.join(".git")).unwrap();
+        std::fs::write(root.join("a.md"), "a").unwrap();
+        std::fs::write(root.join("nested/b.txt"), "b").unwrap();
+        std::fs::write(root.join(".git/c.md"), "c").unwrap();
+        std::fs::write(root.join("d.rs"), "d").unwrap();
+
+        let mut watcher = DirectoryWatcher::new(dir.path());
+        assert_eq!(
+            sync(&mut watcher).changed,
+            [root.join("a.md"), root.join("nested/b.txt")]
+        );
+        assert_eq!(sync(&mut watcher), DirectoryChanges::default());
+
+        std::fs::write(root.join("a.md"), "changed").unwrap();
+        std::fs::remove_file(root.join("nested/b.txt")).unwrap();
+        assert_eq!(
+            sync(&mut watcher),
+            DirectoryChanges {
+                changed: vec![root.join("a.md")],
+                removed: vec![root.join("nested/b.txt")],
+            }
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_directories_are_walked_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir(root.join("docs")).unwrap();
+        std::fs::write(root.join("docs/a.md"), "a").unwrap();
+        std::os::unix::fs::symlink(&root, root.join("docs/loop")).unwrap();
+
+        let mut watcher = DirectoryWatcher::new(&root);
+        assert_eq!(watcher.scan().unwrap().changed, [root.join("docs/a.md")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_out_of_the_root_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = std::fs::canonicalize(dir.path()).unwrap();
+        let (root, outside) = (base.join("root"), base.join("outside"));
+        std::fs::create_dir(&root).unwrap();
+        std::fs::create_dir(&outside).unwrap();
+        std::fs::write(outside.join("secret.md"), "s").unwrap();
+        std::fs::write(root.join("a.md"), "a").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("escape")).unwrap();
+        std::os::unix::fs::symlink(outside.join("secret.md"), root.join("b.md")).unwrap();
+        std::os::unix::fs::symlink(root.join("a.md"), root.join("alias.md")).unwrap();
+
+        // Links inside the root resolve to their target's canonical path.
+        let mut watcher = DirectoryWatcher::new(&root);
+        assert_eq!(watcher.scan().unwrap().changed, [root.join("a.md")]);
+    }
+
+    #[test]
+    fn unmarked_changes_are_reported_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::write(root.join("a.md"), "a").unwrap();
+        std::fs::write(root.join("b.md"), "b").unwrap();
+
+        let mut watcher = DirectoryWatcher::new(&root);
+        watcher.scan().unwrap();
+        watcher.mark_synced(&root.join("a.md"));
+        assert_eq!(watcher.scan().unwrap().changed, [root.join("b.md")]);
+
+        std::fs::remove_file(root.join("a.md")).unwrap();
+        assert_eq!(watcher.scan().unwrap().removed, [root.join("a.md")]);
+        assert_eq!(watcher.scan().unwrap().removed, [root.join("a.md")]);
+        watcher.mark_synced(&root.join("a.md"));
+        assert!(watcher.scan().unwrap().removed.is_empty());
+    }
+
+    #[test]
+    fn watches_a_single_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("page.html");
+        std::fs::write(&file, "<p>hi</p>").unwrap();
+        let mut watcher = DirectoryWatcher::new(&file);
+        assert_eq!(sync(&mut watcher).changed.len(), 1);
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(sync(&mut watcher).removed.len(), 1);
+    }
+}
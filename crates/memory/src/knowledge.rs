@@ -0,0 +1,586 @@
+//! Knowledge-base retrieval over ingested documents.
+//!
+//! Chunks stored by [`DocumentIngestor`](crate::ingest::DocumentIngestor)
+//! are searched twice — by keyword (BM25 on SQLite and PostgreSQL) and by
+//! vector similarity when the backend embeds entries — and the two rankings
+//! are fused with [`reciprocal_rank_fusion`]. Natural-language questions
+//! rarely contain every word of a passage, so when the full question finds
+//! too few chunks its terms are also searched one at a time and those
+//! rankings fused first.
+//!
+//! A hit's `similarity` is its fused score normalised to `[0, 1]`: `1.0`
+//! for a chunk ranked first by both searches. A [`Reranker`] can re-score
+//! the fused candidates, in which case its score becomes the similarity.
+
+use crate::ingest::{CHUNK_TAG, DOCUMENT_TAG, document_tag};
+use crate::vector::{RRF_K, fuse_rankings, reciprocal_rank_fusion};
+use async_trait::async_trait;
+use rustedclaw_core::error::MemoryError;
+use rustedclaw_core::memory::{MemoryBackend, MemoryEntry, MemoryQuery, MemoryScope, SearchMode};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Results returned when a query does not say how many it wants.
+pub const DEFAULT_TOP_K: usize = 5;
+
+/// Candidates fetched per requested result, leaving room for filtering and
+/// re-ranking.
+const CANDIDATE_FACTOR: usize = 4;
+const MIN_CANDIDATES: usize = 20;
+
+/// Words too common to be worth searching for on their own.
+const STOP_WORDS: &[&str] = &[
+    "a", "about", "an", "and", "are", "as", "at", "be", "by", "can", "do", "does", "for", "from",
+    "how", "i", "in", "is", "it", "me", "my", "of", "on", "or", "tell", "that", "the", "this",
+    "to", "was", "what", "when", "where", "which", "who", "why", "with", "you",
+];
+
+/// A knowledge-base search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnowledgeQuery {
+    /// The question or search text.
+    pub text: String,
+    /// Maximum number of hits.
+    pub top_k: usize,
+    /// Hits with a lower similarity are dropped.
+    pub min_score: f32,
+    /// `key:value` metadata tags every hit must carry.
+    pub filters: Vec<String>,
+    /// Only search this document's chunks.
+    pub document_id: Option<String>,
+    /// Re-score candidates with the knowledge base's [`Reranker`].
+    pub rerank: bool,
+    /// Only chunks visible to this scope are searched.
+    pub scope: MemoryScope,
+}
+
+impl KnowledgeQuery {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            top_k: DEFAULT_TOP_K,
+            min_score: 0.0,
+            filters: Vec::new(),
+            document_id: None,
+            rerank: false,
+            scope: MemoryScope::default(),
+        }
+    }
+
+    pub fn with_top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k;
+        self
+    }
+
+    pub fn with_min_score(mut self, min_score: f32) -> Self {
+        self.min_score = min_score;
+        self
+    }
+
+    /// Require the `key:value` metadata tag `filter`.
+    pub fn with_filter(mut self, filter: impl Into<String>) -> Self {
+        self.filters.push(filter.into());
+        self
+    }
+
+    pub fn with_document(mut self, document_id: impl Into<String>) -> Self {
+        self.document_id = Some(document_id.into());
+        self
+    }
+
+    pub fn with_rerank(mut self, rerank: bool) -> Self {
+        self.rerank = rerank;
+        self
+    }
+
+    pub fn with_scope(mut self, scope: MemoryScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// The one tag the backend filters on; the rest are checked afterwards.
+    fn anchor_tag(&self) -> String {
+        match (&self.document_id, self.filters.first()) {
+            (Some(id), _) => document_tag(id),
+            (None, Some(filter)) => filter.clone(),
+            (None, None) => DOCUMENT_TAG.to_string(),
+        }
+    }
+
+    fn matches(&self, entry: &MemoryEntry) -> bool {
+        let has = |tag: &str| entry.tags.iter().any(|t| t == tag);
+        has(DOCUMENT_TAG)
+            && self
+                .document_id
+                .as_deref()
+                .is_none_or(|id| has(&document_tag(id)))
+            && self.filters.iter().all(|filter| has(filter))
+    }
+}
+
+/// A retrieved document chunk.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnowledgeHit {
+    /// The document the chunk belongs to.
+    pub document_id: String,
+    /// The chunk's position in its document.
+    pub chunk_index: usize,
+    pub content: String,
+    /// Where the document came from (path, URL or upload name).
+    pub source: String,
+    /// Relevance in `[0, 1]`, highest first.
+    pub similarity: f32,
+}
+
+impl KnowledgeHit {
+    /// Read a chunk's document and position back from its tags.
+    fn from_entry(entry: MemoryEntry, similarity: f32) -> Option<Self> {
+        let value = |prefix: &str| {
+            entry
+                .tags
+                .iter()
+                .find_map(|t| t.strip_prefix(prefix)?.strip_prefix(':'))
+        };
+        let document_id = value(DOCUMENT_TAG)?.to_string();
+        let chunk_index = value(CHUNK_TAG)
+            .and_then(|index| index.parse().ok())
+            .unwrap_or_default();
+        Some(Self {
+            document_id,
+            chunk_index,
+            content: entry.content,
+            source: entry.source.unwrap_or_default(),
+            similarity,
+        })
+    }
+}
+
+/// Scores passages against a query, for re-ranking retrieved chunks.
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    /// Relevance of each passage to `query` in `[0, 1]`, in passage order.
+    async fn score(&self, query: &str, passages: &[&str]) -> Result<Vec<f32>, MemoryError>;
+}
+
+/// Scores a passage by the share of the query's terms it contains.
+///
+/// Cheap and local; a cross-encoder or LLM judge can be plugged in through
+/// [`Reranker`] instead.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LexicalReranker;
+
+#[async_trait]
+impl Reranker for LexicalReranker {
+    async fn score(&self, query: &str, passages: &[&str]) -> Result<Vec<f32>, MemoryError> {
+        let terms = query_terms(query);
+        if terms.is_empty() {
+            return Ok(vec![0.0; passages.len()]);
+        }
+        Ok(passages
+            .iter()
+            .map(|passage| {
+                let words = words(passage);
+                let found = terms
+                    .iter()
+                    .filter(|term| words.iter().any(|word| term_matches(term, word)))
+                    .count();
+                found as f32 / terms.len() as f32
+            })
+            .collect())
+    }
+}
+
+/// Searches the chunks of ingested documents.
+pub struct KnowledgeBase {
+    memory: Arc<dyn MemoryBackend>,
+    reranker: Option<Arc<dyn Reranker>>,
+}
+
+impl KnowledgeBase {
+    /// Search documents ingested into `memory`.
+    pub fn new(memory: Arc<dyn MemoryBackend>) -> Self {
+        Self {
+            memory,
+            reranker: None,
+        }
+    }
+
+    /// Re-score candidates with `reranker` when a query asks for it.
+    pub fn with_reranker(mut self, reranker: Arc<dyn Reranker>) -> Self {
+        self.reranker = Some(reranker);
+        self
+    }
+
+    /// The chunks most relevant to `query`, best first.
+    pub async fn search(&self, query: &KnowledgeQuery) -> Result<Vec<KnowledgeHit>, MemoryError> {
+        let text = query.text.trim();
+        if text.is_empty() || query.top_k == 0 {
+            return Ok(Vec::new());
+        }
+
+        let candidates = query
+            .top_k
+            .saturating_mul(CANDIDATE_FACTOR)
+            .max(MIN_CANDIDATES);
+        let base = MemoryQuery {
+            text: text.to_string(),
+            limit: candidates,
+            min_score: 0.0,
+            tags: vec![query.anchor_tag()],
+            mode: SearchMode::Keyword,
+            embedding: None,
+            scope: query.scope.clone(),
+        };
+        let keyword = self.keyword_ranking(&base).await?;
+        let vector = self
+            .memory
+            .search(MemoryQuery {
+                mode: SearchMode::Vector,
+                ..base
+            })
+            .await?;
+
+        // Two first places is the best possible fused score.
+        let best = 2.0 / (RRF_K as f32 + 1.0);
+        let mut hits: Vec<KnowledgeHit> =
+            reciprocal_rank_fusion(&keyword, &vector, RRF_K, candidates)
+                .into_iter()
+                .filter(|entry| query.matches(entry))
+                .filter_map(|entry| {
+                    let similarity = (entry.score / best).min(1.0);
+                    KnowledgeHit::from_entry(entry, similarity)
+                })
+                .collect();
+
+        if query.rerank
+            && let Some(reranker) = &self.reranker
+        {
+            let passages: Vec<&str> = hits.iter().map(|hit| hit.content.as_str()).collect();
+            let scores = reranker.score(text, &passages).await?;
+            for (hit, score) in hits.iter_mut().zip(scores) {
+                hit.similarity = score.clamp(0.0, 1.0);
+            }
+            // Stable: equal scores keep their fused order.
+            hits.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+        }
+
+        hits.retain(|hit| hit.similarity >= query.min_score);
+        hits.truncate(query.top_k);
+        Ok(hits)
+    }
+
+    /// Keyword results for the whole query, topped up with results for each
+    /// of its terms when the whole query finds too few.
+    async fn keyword_ranking(&self, base: &MemoryQuery) -> Result<Vec<MemoryEntry>, MemoryError> {
+        let terms = query_terms(&base.text);
+        let joined = terms.join(" ");
+        let search = |text: &str| {
+            self.memory.search(MemoryQuery {
+                text: text.to_string(),
+                ..base.clone()
+            })
+        };
+
+        let whole = search(if joined.is_empty() {
+            &base.text
+        } else {
+            &joined
+        })
+        .await?;
+        if whole.len() >= base.limit || terms.len() < 2 {
+            return Ok(whole);
+        }
+        let mut lists = vec![whole];
+        for term in &terms {
+            lists.push(search(term).await?);
+        }
+        let lists: Vec<&[MemoryEntry]> = lists.iter().map(Vec::as_slice).collect();
+        Ok(fuse_rankings(&lists, RRF_K, base.limit))
+    }
+}
+
+/// Lowercase words of `text`.
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// The distinct words of `text` worth searching for, in order.
+fn query_terms(text: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for word in words(text) {
+        if !STOP_WORDS.contains(&word.as_str()) && !terms.contains(&word) {
+            terms.push(word);
+        }
+    }
+    terms
+}
+
+/// Whether `word` is `term`, or an inflection sharing a long enough stem.
+fn term_matches(term: &str, word: &str) -> bool {
+    word == term
+        || (term.len() >= 4
+            && word.len() >= 4
+            && (word.starts_with(term) || term.starts_with(word)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryBackend;
+    use crate::embedding::EmbeddingMemory;
+    use crate::ingest::{Document, DocumentFormat, DocumentIngestor};
+    use rustedclaw_core::error::ProviderError;
+    use rustedclaw_core::provider::{
+        EmbeddingRequest, EmbeddingResponse, Provider, ProviderRequest, ProviderResponse,
+    };
+
+    fn document(id: &str, text: &str, tags: &[&str]) -> Document {
+        Document {
+            id: id.into(),
+            source: format!("{id}.md"),
+            format: DocumentFormat::Markdown,
+            content: text.as_bytes().to_vec(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            scope: MemoryScope::default(),
+        }
+    }
+
+    async fn knowledge_base(memory: Arc<dyn MemoryBackend>) -> KnowledgeBase {
+        let ingestor = DocumentIngestor::new(memory.clone());
+        for doc in [
+            document(
+                "rust",
+                "# Rust\n\nRust guarantees memory safety through ownership.\n\n## Tooling\n\nCargo builds Rust packages.",
+                &["lang:en", "topic:rust"],
+            ),
+            document(
+                "wasm",
+                "# WebAssembly\n\nWASM is a portable binary format for a stack machine.",
+                &["lang:en"],
+            ),
+            document(
+                "rust-de",
+                "# Rust\n\nRust garantiert Speichersicherheit durch Ownership.",
+                &["lang:de", "topic:rust"],
+            ),
+        ] {
+            ingestor.ingest(doc).await.unwrap();
+        }
+        KnowledgeBase::new(memory)
+    }
+
+    #[tokio::test]
+    async fn questions_find_chunks_with_their_position() {
+        let kb = knowledge_base(Arc::new(InMemoryBackend::new())).await;
+
+        let hits = kb
+            .search(&KnowledgeQuery::new("How does Rust handle memory safety?"))
+            .await
+            .unwrap();
+        let first = &hits[0];
+        assert_eq!(first.document_id, "rust");
+        assert_eq!(first.chunk_index, 0);
+        assert_eq!(first.source, "rust.md");
+        assert!(first.content.contains("memory safety"));
+        assert!(first.similarity > 0.0 && first.similarity <= 1.0);
+        assert!(hits.windows(2).all(|w| w[0].similarity >= w[1].similarity));
+        assert!(hits.iter().all(|h| h.document_id != "wasm"));
+
+        let cargo = kb.search(&KnowledgeQuery::new("cargo")).await.unwrap();
+        assert_eq!(cargo.len(), 1);
+        assert_eq!(
+            (cargo[0].document_id.as_str(), cargo[0].chunk_index),
+            ("rust", 1)
+        );
+        assert!(
+            kb.search(&KnowledgeQuery::new("  "))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn bm25_ranks_questions_over_sqlite() {
+        let sqlite = crate::SqliteBackend::new("sqlite::memory:").await.unwrap();
+        let kb = knowledge_base(Arc::new(sqlite)).await;
+
+        let hits = kb
+            .search(&KnowledgeQuery::new("How does Rust handle memory safety?"))
+            .await
+            .unwrap();
+        assert_eq!(
+            (hits[0].document_id.as_str(), hits[0].chunk_index),
+            ("rust", 0)
+        );
+
+        let hits = kb
+            .search(&KnowledgeQuery::new("portable binary"))
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].document_id, "wasm");
+        assert_eq!(hits[0].similarity, 1.0);
+    }
+
+    #[tokio::test]
+    async fn filters_restrict_documents() {
+        let kb = knowledge_base(Arc::new(InMemoryBackend::new())).await;
+
+        let german = kb
+            .search(&KnowledgeQuery::new("rust ownership").with_filter("lang:de"))
+            .await
+            .unwrap();
+        assert_eq!(german.len(), 1);
+        assert_eq!(german[0].document_id, "rust-de");
+
+        let english_rust = kb
+            .search(
+                &KnowledgeQuery::new("rust")
+                    .with_filter("topic:rust")
+                    .with_filter("lang:en"),
+            )
+            .await
+            .unwrap();
+        assert!(!english_rust.is_empty());
+        assert!(english_rust.iter().all(|h| h.document_id == "rust"));
+
+        let one_doc = kb
+            .search(&KnowledgeQuery::new("rust").with_document("rust-de"))
+            .await
+            .unwrap();
+        assert!(one_doc.iter().all(|h| h.document_id == "rust-de"));
+
+        let capped = kb
+            .search(&KnowledgeQuery::new("rust").with_top_k(1))
+            .await
+            .unwrap();
+        assert_eq!(capped.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn plain_memories_are_not_knowledge() {
+        let memory: Arc<dyn MemoryBackend> = Arc::new(InMemoryBackend::new());
+        memory
+            .store(MemoryEntry {
+                id: "fact".into(),
+                content: "The user writes Rust every day".into(),
+                tags: vec![],
+                source: None,
+                created_at: chrono::Utc::now(),
+                last_accessed: chrono::Utc::now(),
+                access_count: 0,
+                expires_at: None,
+                score: 0.0,
+                embedding: None,
+                embedding_model: None,
+                scope: MemoryScope::default(),
+            })
+            .await
+            .unwrap();
+        let kb = KnowledgeBase::new(memory);
+        assert!(
+            kb.search(&KnowledgeQuery::new("rust"))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn reranking_rescores_by_term_coverage() {
+        let kb = knowledge_base(Arc::new(InMemoryBackend::new()))
+            .await
+            .with_reranker(Arc::new(LexicalReranker));
+        let query = KnowledgeQuery::new("rust packages").with_rerank(true);
+
+        let hits = kb.search(&query).await.unwrap();
+        assert_eq!(
+            (hits[0].document_id.as_str(), hits[0].chunk_index),
+            ("rust", 1)
+        );
+        assert_eq!(hits[0].similarity, 1.0);
+        assert!(hits[1..].iter().all(|h| h.similarity == 0.5));
+
+        let strict = kb.search(&query.with_min_score(0.9)).await.unwrap();
+        assert_eq!(strict.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn lexical_reranker_matches_inflections() {
+        let scores = LexicalReranker
+            .score(
+                "What packages does cargo build?",
+                &["Cargo builds packages", "Cargo is a tool", "nothing here"],
+            )
+            .await
+            .unwrap();
+        assert_eq!(scores, [1.0, 1.0 / 3.0, 0.0]);
+    }
+
+    /// Embeds vehicle words on one axis and everything else on another.
+    struct VehicleEmbedder;
+
+    #[async_trait]
+    impl Provider for VehicleEmbedder {
+        fn name(&self) -> &str {
+            "vehicle"
+        }
+
+        async fn complete(&self, _: ProviderRequest) -> Result<ProviderResponse, ProviderError> {
+            unimplemented!()
+        }
+
+        async fn embed(
+            &self,
+            request: EmbeddingRequest,
+        ) -> Result<EmbeddingResponse, ProviderError> {
+            let embeddings = request
+                .inputs
+                .iter()
+                .map(|text| {
+                    let text = text.to_lowercase();
+                    if ["car", "automobile", "truck"]
+                        .iter()
+                        .any(|w| text.contains(w))
+                    {
+                        vec![1.0, 0.0]
+                    } else {
+                        vec![0.0, 1.0]
+                    }
+                })
+                .collect();
+            Ok(EmbeddingResponse {
+                embeddings,
+                model: request.model,
+                usage: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn vector_search_finds_chunks_without_shared_words() {
+        let memory: Arc<dyn MemoryBackend> = Arc::new(EmbeddingMemory::new(
+            Arc::new(InMemoryBackend::new()),
+            Arc::new(VehicleEmbedder),
+            "vehicle-embed",
+        ));
+        let ingestor = DocumentIngestor::new(memory.clone());
+        for doc in [
+            document("bikes", "Bicycles are pedalled.", &[]),
+            document("cars", "Cars run on fuel or batteries.", &[]),
+        ] {
+            ingestor.ingest(doc).await.unwrap();
+        }
+
+        let hits = KnowledgeBase::new(memory)
+            .search(&KnowledgeQuery::new("automobile"))
+            .await
+            .unwrap();
+        assert_eq!(hits[0].document_id, "cars");
+    }
+}
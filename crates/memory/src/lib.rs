@@ -1,21 +1,46 @@
 //! Memory system implementations for RustedClaw.
 
+pub mod conversations;
+pub mod decay;
+pub mod embedding;
+pub mod factory;
 pub mod file_backend;
+pub mod hnsw;
 pub mod in_memory;
+pub mod ingest;
+pub mod knowledge;
+pub mod migrate;
 pub mod noop;
 pub mod vector;
 
+#[cfg(test)]
+mod conformance;
+
 #[cfg(feature = "sqlite")]
 pub mod sqlite;
 
 #[cfg(feature = "postgres")]
 pub mod postgres;
 
+pub use conversations::{
+    FileConversationStore, InMemoryConversationStore, from_config as conversation_store_from_config,
+};
+pub use decay::spawn_compaction;
+pub use embedding::{EmbeddingMemory, ReembedReport, RetryPolicy};
+pub use factory::{
+    decay_policy, default_sqlite_path, from_config, spawn_compaction_from_config, with_embeddings,
+};
 pub use file_backend::FileBackend;
+pub use hnsw::{HnswIndex, HnswParams};
 pub use in_memory::InMemoryBackend;
+pub use ingest::{Document, DocumentFormat, DocumentIngestor, IngestReport};
+pub use knowledge::{KnowledgeBase, KnowledgeHit, KnowledgeQuery, LexicalReranker, Reranker};
+pub use migrate::{Migration, MigrationProgress, MigrationReport};
 pub use noop::NoopMemory;
-pub use vector::{cosine_similarity, reciprocal_rank_fusion, vector_search};
+pub use vector::{cosine_similarity, fuse_rankings, reciprocal_rank_fusion, vector_search};
 
+#[cfg(feature = "sqlite")]
+pub use conversations::SqliteConversationStore;
 #[cfg(feature = "sqlite")]
 pub use sqlite::SqliteBackend;
 
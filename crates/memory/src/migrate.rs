@@ -0,0 +1,579 @@
+//! Copy memories from one backend to another.
+//!
+//! Entries are streamed a page at a time from the source (see
+//! [`MemoryBackend::list`]) and written to the target with
+//! [`MemoryBackend::store_batch`], keeping their IDs, tags, scopes, access
+//! history and embeddings. Since entries keep their IDs, copying one twice
+//! just overwrites it.
+//!
+//! With a checkpoint file, the last entry copied is saved after every batch;
+//! running the same migration again after a failure resumes with the entries
+//! listed after it, so entries deleted from the source in between don't
+//! shift the rest. Once everything is copied, every source entry is looked
+//! up in the target and the migration fails if any is missing.
+
+use chrono::{DateTime, Utc};
+use rustedclaw_core::error::MemoryError;
+use rustedclaw_core::memory::{MemoryBackend, MemoryEntry, MemoryFilter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::info;
+
+/// Entries read and written per batch unless configured otherwise.
+pub const DEFAULT_BATCH_SIZE: usize = 500;
+
+/// How far a migration has got, reported after every batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MigrationProgress {
+    /// Entries copied so far, including those copied before a resume.
+    pub copied: usize,
+    /// Entries in the source when the migration started.
+    pub total: usize,
+}
+
+/// Outcome of a completed migration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// Entries copied, including those copied before a resume.
+    pub copied: usize,
+    /// Entries skipped because an earlier run had already copied them.
+    pub resumed_from: usize,
+    /// Source entries found in the target afterwards.
+    pub verified: usize,
+}
+
+/// Saved progress of an interrupted migration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Checkpoint {
+    source: String,
+    target: String,
+    copied: usize,
+    /// Where the copied entries end in listing order.
+    last: Cursor,
+}
+
+/// Position in the source's newest-first listing: the creation time of the
+/// last entry copied and the IDs of every copied entry created then.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Cursor {
+    created_at: Option<DateTime<Utc>>,
+    ids: Vec<String>,
+}
+
+impl Cursor {
+    /// Whether `entry` is listed after the cursor.
+    fn precedes(&self, entry: &MemoryEntry) -> bool {
+        self.created_at.is_none_or(|at| {
+            entry.created_at < at || (entry.created_at == at && !self.ids.contains(&entry.id))
+        })
+    }
+
+    /// Move past `page`, which was listed right after the cursor.
+    fn advance(&mut self, page: &[MemoryEntry]) {
+        let Some(last) = page.last() else {
+            return;
+        };
+        if self.created_at != Some(last.created_at) {
+            self.created_at = Some(last.created_at);
+            self.ids.clear();
+        }
+        self.ids.extend(
+            page.iter()
+                .filter(|entry| entry.created_at == last.created_at)
+                .map(|entry| entry.id.clone()),
+        );
+    }
+}
+
+/// Copies every memory from `source` to `target`.
+pub struct Migration {
+    source: Arc<dyn MemoryBackend>,
+    target: Arc<dyn MemoryBackend>,
+    batch_size: usize,
+    checkpoint: Option<PathBuf>,
+}
+
+impl Migration {
+    pub fn new(source: Arc<dyn MemoryBackend>, target: Arc<dyn MemoryBackend>) -> Self {
+        Self {
+            source,
+            target,
+            batch_size: DEFAULT_BATCH_SIZE,
+            checkpoint: None,
+        }
+    }
+
+    /// Entries read and written at once.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Save progress to `path`, and resume from it when it records an
+    /// earlier run between the same backends. It is removed once the
+    /// migration succeeds. Use a separate path for each pair of locations.
+    pub fn with_checkpoint(mut self, path: impl Into<PathBuf>) -> Self {
+        self.checkpoint = Some(path.into());
+        self
+    }
+
+    /// Run the migration, calling `progress` after every batch.
+    pub async fn run(
+        &self,
+        mut progress: impl FnMut(MigrationProgress),
+    ) -> Result<MigrationReport, MemoryError> {
+        let (resumed_from, mut cursor) = self
+            .load_checkpoint()
+            .await?
+            .map_or((0, Cursor::default()), |c| (c.copied, c.last));
+        let total = self.source.count().await?;
+        info!(
+            source = self.source.name(),
+            target = self.target.name(),
+            total,
+            resumed_from,
+            "Migrating memories"
+        );
+
+        let mut copied = resumed_from;
+        loop {
+            let page = self.next_page(&cursor).await?;
+            if page.is_empty() {
+                break;
+            }
+            cursor.advance(&page);
+            copied += page.len();
+            self.target.store_batch(page).await?;
+            self.save_checkpoint(copied, &cursor).await?;
+            progress(MigrationProgress { copied, total });
+        }
+
+        let verified = match self.verify().await {
+            Ok(verified) => verified,
+            Err(e) => {
+                // Nothing recorded is trustworthy; the next run starts over.
+                self.remove_checkpoint().await?;
+                return Err(e);
+            }
+        };
+        self.remove_checkpoint().await?;
+        info!(copied, verified, "Memory migration complete");
+        Ok(MigrationReport {
+            copied,
+            resumed_from,
+            verified,
+        })
+    }
+
+    /// Up to a batch of the source entries listed after `cursor`.
+    async fn next_page(&self, cursor: &Cursor) -> Result<Vec<MemoryEntry>, MemoryError> {
+        // Listing from just past the cursor's time keeps entries created in
+        // the same instant, whatever the backend's precision.
+        let filter = MemoryFilter {
+            created_before: cursor
+                .created_at
+                .map(|at| at + chrono::Duration::milliseconds(1)),
+            ..MemoryFilter::default()
+        };
+        let mut page = Vec::new();
+        let mut offset = 0;
+        while page.len() < self.batch_size {
+            let read = self.source.list(&filter, offset, self.batch_size).await?;
+            offset += read.len();
+            let done = read.len() < self.batch_size;
+            page.extend(read.into_iter().filter(|entry| cursor.precedes(entry)));
+            if done {
+                break;
+            }
+        }
+        page.truncate(self.batch_size);
+        Ok(page)
+    }
+
+    /// Check that every source entry is in the target; returns how many
+    /// there are.
+    async fn verify(&self) -> Result<usize, MemoryError> {
+        let all = MemoryFilter::default();
+        let mut expected = HashSet::new();
+        let mut offset = 0;
+        loop {
+            let page = self.source.list(&all, offset, self.batch_size).await?;
+            offset += page.len();
+            let done = page.len() < self.batch_size;
+            expected.extend(page.into_iter().map(|entry| entry.id));
+            if done {
+                break;
+            }
+        }
+
+        let mut found = 0;
+        let mut offset = 0;
+        loop {
+            let page = self.target.list(&all, offset, self.batch_size).await?;
+            offset += page.len();
+            found += page
+                .iter()
+                .filter(|entry| expected.contains(&entry.id))
+                .count();
+            if page.len() < self.batch_size {
+                break;
+            }
+        }
+
+        if found < expected.len() {
+            return Err(MemoryError::Storage(format!(
+                "Migration verification failed: {} of {} memories are missing from the {} backend",
+                expected.len() - found,
+                expected.len(),
+                self.target.name()
+            )));
+        }
+        Ok(found)
+    }
+
+    async fn load_checkpoint(&self) -> Result<Option<Checkpoint>, MemoryError> {
+        let Some(path) = &self.checkpoint else {
+            return Ok(None);
+        };
+        let raw = match tokio::fs::read(path).await {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(MemoryError::Storage(format!(
+                    "Cannot read {}: {e}",
+                    path.display()
+                )));
+            }
+        };
+        let checkpoint: Checkpoint = serde_json::from_slice(&raw).map_err(|e| {
+            MemoryError::Storage(format!("Invalid checkpoint {}: {e}", path.display()))
+        })?;
+        if checkpoint.source != self.source.name() || checkpoint.target != self.target.name() {
+            return Err(MemoryError::Storage(format!(
+                "{} records a migration from {} to {}; remove it to migrate from {} to {}",
+                path.display(),
+                checkpoint.source,
+                checkpoint.target,
+                self.source.name(),
+                self.target.name()
+            )));
+        }
+        Ok(Some(checkpoint))
+    }
+
+    async fn save_checkpoint(&self, copied: usize, last: &Cursor) -> Result<(), MemoryError> {
+        let Some(path) = &self.checkpoint else {
+            return Ok(());
+        };
+        let checkpoint = Checkpoint {
+            source: self.source.name().to_string(),
+            target: self.target.name().to_string(),
+            copied,
+            last: last.clone(),
+        };
+        let json = serde_json::to_vec(&checkpoint)
+            .map_err(|e| MemoryError::Storage(format!("Cannot serialize checkpoint: {e}")))?;
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| {
+                MemoryError::Storage(format!("Cannot create {}: {e}", parent.display()))
+            })?;
+        }
+        tokio::fs::write(path, json)
+            .await
+            .map_err(|e| MemoryError::Storage(format!("Cannot write {}: {e}", path.display())))
+    }
+
+    async fn remove_checkpoint(&self) -> Result<(), MemoryError> {
+        let Some(path) = &self.checkpoint else {
+            return Ok(());
+        };
+        match tokio::fs::remove_file(path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(MemoryError::Storage(format!(
+                "Cannot remove {}: {e}",
+                path.display()
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryBackend;
+    use async_trait::async_trait;
+    use chrono::Utc;
+    use rustedclaw_core::memory::{MemoryEntry, MemoryPatch, MemoryQuery, MemoryScope};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn entry(i: usize) -> MemoryEntry {
+        MemoryEntry {
+            id: format!("m{i:03}"),
+            content: format!("Memory number {i}"),
+            tags: vec!["note".into(), format!("n:{i}")],
+            source: Some("test".into()),
+            created_at: Utc::now() - chrono::Duration::seconds(i as i64),
+            last_accessed: Utc::now(),
+            access_count: i as u32,
+            expires_at: None,
+            score: 0.0,
+            embedding: Some(vec![i as f32, 1.0]),
+            embedding_model: Some("embed-v1".into()),
+            scope: MemoryScope {
+                user_id: Some("alice".into()),
+                ..MemoryScope::default()
+            },
+        }
+    }
+
+    async fn source(count: usize) -> Arc<dyn MemoryBackend> {
+        let source = InMemoryBackend::new();
+        source
+            .store_batch((0..count).map(entry).collect())
+            .await
+            .unwrap();
+        Arc::new(source)
+    }
+
+    /// Forwards to an in-memory backend, failing batch writes once
+    /// `fail_after` batches have been stored.
+    struct FlakyBackend {
+        inner: InMemoryBackend,
+        batches: AtomicUsize,
+        fail_after: usize,
+    }
+
+    #[async_trait]
+    impl MemoryBackend for FlakyBackend {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+        async fn store(&self, entry: MemoryEntry) -> Result<String, MemoryError> {
+            self.inner.store(entry).await
+        }
+        async fn store_batch(&self, entries: Vec<MemoryEntry>) -> Result<Vec<String>, MemoryError> {
+            if self.batches.fetch_add(1, Ordering::SeqCst) >= self.fail_after {
+                return Err(MemoryError::Storage("disk full".into()));
+            }
+            self.inner.store_batch(entries).await
+        }
+        async fn search(&self, query: MemoryQuery) -> Result<Vec<MemoryEntry>, MemoryError> {
+            self.inner.search(query).await
+        }
+        async fn delete(&self, id: &str) -> Result<bool, MemoryError> {
+            self.inner.delete(id).await
+        }
+        async fn get(&self, id: &str) -> Result<Option<MemoryEntry>, MemoryError> {
+            self.inner.get(id).await
+        }
+        async fn count(&self) -> Result<usize, MemoryError> {
+            self.inner.count().await
+        }
+        async fn clear(&self) -> Result<(), MemoryError> {
+            self.inner.clear().await
+        }
+        async fn compact(&self) -> Result<usize, MemoryError> {
+            self.inner.compact().await
+        }
+        async fn update(
+            &self,
+            id: &str,
+            patch: MemoryPatch,
+        ) -> Result<Option<MemoryEntry>, MemoryError> {
+            self.inner.update(id, patch).await
+        }
+        async fn list(
+            &self,
+            filter: &MemoryFilter,
+            offset: usize,
+            limit: usize,
+        ) -> Result<Vec<MemoryEntry>, MemoryError> {
+            self.inner.list(filter, offset, limit).await
+        }
+        async fn delete_where(&self, filter: &MemoryFilter) -> Result<usize, MemoryError> {
+            self.inner.delete_where(filter).await
+        }
+    }
+
+    #[tokio::test]
+    async fn copies_every_entry_with_progress() {
+        let source = source(25).await;
+        let target: Arc<dyn MemoryBackend> = Arc::new(InMemoryBackend::new());
+
+        let mut reports = Vec::new();
+        let report = Migration::new(source, target.clone())
+            .with_batch_size(10)
+            .run(|p| reports.push(p))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            report,
+            MigrationReport {
+                copied: 25,
+                resumed_from: 0,
+                verified: 25
+            }
+        );
+        let copied: Vec<usize> = reports.iter().map(|p| p.copied).collect();
+        assert_eq!(copied, [10, 20, 25]);
+        assert!(reports.iter().all(|p| p.total == 25));
+
+        let moved = target
+            .list(&MemoryFilter::default(), 0, 100)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|e| e.id == "m007")
+            .unwrap();
+        let original = entry(7);
+        assert_eq!(moved.tags, original.tags);
+        assert_eq!(moved.embedding, original.embedding);
+        assert_eq!(moved.embedding_model, original.embedding_model);
+        assert_eq!(moved.scope, original.scope);
+        assert_eq!(moved.access_count, 7);
+    }
+
+    #[tokio::test]
+    async fn resumes_from_checkpoint_after_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoint = dir.path().join("state").join("migration.json");
+        let source = source(25).await;
+        let flaky = Arc::new(FlakyBackend {
+            inner: InMemoryBackend::new(),
+            batches: AtomicUsize::new(0),
+            fail_after: 2,
+        });
+
+        let err = Migration::new(source.clone(), flaky.clone())
+            .with_batch_size(10)
+            .with_checkpoint(&checkpoint)
+            .run(|_| {})
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("disk full"));
+        let saved: Checkpoint =
+            serde_json::from_slice(&std::fs::read(&checkpoint).unwrap()).unwrap();
+        assert_eq!(saved.copied, 20);
+        assert_eq!(saved.last.ids, ["m019"]);
+
+        // A checkpoint for a different pair of backends is not reused.
+        let other = Migration::new(source.clone(), Arc::new(InMemoryBackend::new()))
+            .with_checkpoint(&checkpoint)
+            .run(|_| {})
+            .await
+            .unwrap_err();
+        assert!(other.to_string().contains("remove it"));
+
+        // Deleting a copied entry doesn't make the resumed run skip one.
+        source.delete("m003").await.unwrap();
+        flaky.batches.store(0, Ordering::SeqCst);
+        let mut copied = Vec::new();
+        let report = Migration::new(source, flaky.clone())
+            .with_batch_size(10)
+            .with_checkpoint(&checkpoint)
+            .run(|p| copied.push(p.copied))
+            .await
+            .unwrap();
+        assert_eq!(copied, [25]);
+        assert_eq!(report.resumed_from, 20);
+        assert_eq!(report.verified, 24);
+        assert_eq!(flaky.count().await.unwrap(), 25);
+        assert!(!checkpoint.exists());
+    }
+
+    #[tokio::test]
+    async fn resumes_among_entries_created_together() {
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoint = dir.path().join("migration.json");
+        let created_at = Utc::now();
+        let source = InMemoryBackend::new();
+        source
+            .store_batch(
+                (0..25)
+                    .map(|i| MemoryEntry {
+                        created_at,
+                        ..entry(i)
+                    })
+                    .collect(),
+            )
+            .await
+            .unwrap();
+        let source: Arc<dyn MemoryBackend> = Arc::new(source);
+        let flaky = Arc::new(FlakyBackend {
+            inner: InMemoryBackend::new(),
+            batches: AtomicUsize::new(0),
+            fail_after: 2,
+        });
+
+        Migration::new(source.clone(), flaky.clone())
+            .with_batch_size(10)
+            .with_checkpoint(&checkpoint)
+            .run(|_| {})
+            .await
+            .unwrap_err();
+        flaky.batches.store(0, Ordering::SeqCst);
+        let report = Migration::new(source, flaky.clone())
+            .with_batch_size(10)
+            .with_checkpoint(&checkpoint)
+            .run(|_| {})
+            .await
+            .unwrap();
+        assert_eq!(report.copied, 25);
+        assert_eq!(report.verified, 25);
+    }
+
+    #[tokio::test]
+    async fn missing_entries_fail_verification() {
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoint = dir.path().join("migration.json");
+        // A stale checkpoint claims everything was already copied.
+        std::fs::write(
+            &checkpoint,
+            r#"{"source":"in_memory","target":"in_memory","copied":5,
+                "last":{"created_at":"2000-01-01T00:00:00Z","ids":[]}}"#,
+        )
+        .unwrap();
+
+        let err = Migration::new(source(5).await, Arc::new(InMemoryBackend::new()))
+            .with_checkpoint(&checkpoint)
+            .run(|_| {})
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("5 of 5 memories are missing"));
+        assert!(!checkpoint.exists(), "the next run starts over");
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn file_to_sqlite() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = Arc::new(crate::FileBackend::new(dir.path().join("memories.jsonl")));
+        file.store_batch((0..12).map(entry).collect())
+            .await
+            .unwrap();
+        let sqlite = Arc::new(
+            crate::SqliteBackend::new(&format!(
+                "sqlite://{}",
+                dir.path().join("memory.sqlite").display()
+            ))
+            .await
+            .unwrap(),
+        );
+
+        let report = Migration::new(file, sqlite.clone())
+            .with_batch_size(5)
+            .run(|_| {})
+            .await
+            .unwrap();
+        assert_eq!(report.verified, 12);
+
+        let moved = sqlite.list(&MemoryFilter::default(), 0, 1).await.unwrap();
+        assert_eq!(moved[0].id, "m000");
+        assert_eq!(moved[0].embedding, Some(vec![0.0, 1.0]));
+        assert_eq!(moved[0].tags, ["note", "n:0"]);
+    }
+}
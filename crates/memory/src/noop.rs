@@ -2,7 +2,7 @@
 
 use async_trait::async_trait;
 use rustedclaw_core::error::MemoryError;
-use rustedclaw_core::memory::{MemoryBackend, MemoryEntry, MemoryQuery};
+use rustedclaw_core::memory::{MemoryBackend, MemoryEntry, MemoryFilter, MemoryPatch, MemoryQuery};
 
 /// A no-op memory backend that stores nothing.
 pub struct NoopMemory;
@@ -36,4 +36,29 @@ impl MemoryBackend for NoopMemory {
     async fn clear(&self) -> Result<(), MemoryError> {
         Ok(())
     }
+
+    async fn compact(&self) -> Result<usize, MemoryError> {
+        Ok(0)
+    }
+
+    async fn update(
+        &self,
+        _id: &str,
+        _patch: MemoryPatch,
+    ) -> Result<Option<MemoryEntry>, MemoryError> {
+        Ok(None)
+    }
+
+    async fn list(
+        &self,
+        _filter: &MemoryFilter,
+        _offset: usize,
+        _limit: usize,
+    ) -> Result<Vec<MemoryEntry>, MemoryError> {
+        Ok(Vec::new())
+    }
+
+    async fn delete_where(&self, _filter: &MemoryFilter) -> Result<usize, MemoryError> {
+        Ok(0)
+    }
 }
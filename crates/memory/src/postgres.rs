@@ -25,17 +25,23 @@
 
 use async_trait::async_trait;
 use sqlx::Row;
-use sqlx::postgres::{PgPool, PgPoolOptions, PgRow};
+use sqlx::postgres::{PgArguments, PgPool, PgPoolOptions, PgRow, Postgres};
 use tracing::{debug, info, warn};
 
+use crate::{decay, vector};
+use chrono::Utc;
 use rustedclaw_core::error::MemoryError;
-use rustedclaw_core::memory::{MemoryBackend, MemoryEntry, MemoryQuery, SearchMode};
+use rustedclaw_core::memory::{
+    DecayPolicy, MemoryBackend, MemoryEntry, MemoryFilter, MemoryPatch, MemoryQuery, MemoryScope,
+    SearchMode,
+};
 
 /// PostgreSQL memory backend with optional pgvector support.
 pub struct PostgresBackend {
     pool: PgPool,
     /// Dimension of embedding vectors (default 1536 for ada-002).
     embedding_dim: usize,
+    decay: DecayPolicy,
 }
 
 impl PostgresBackend {
@@ -62,6 +68,7 @@ impl PostgresBackend {
         Ok(Self {
             pool,
             embedding_dim: 1536,
+            decay: DecayPolicy::default(),
         })
     }
 
@@ -70,6 +77,7 @@ impl PostgresBackend {
         Self {
             pool,
             embedding_dim: 1536,
+            decay: DecayPolicy::default(),
         }
     }
 
@@ -79,14 +87,27 @@ impl PostgresBackend {
         self
     }
 
+    /// Rank recalls and forget stale entries with `decay`.
+    pub fn with_decay(mut self, decay: DecayPolicy) -> Self {
+        self.decay = decay;
+        self
+    }
+
     /// Run the schema migration.
     pub async fn migrate(&self) -> Result<(), MemoryError> {
-        let migration_sql = include_str!("../migrations/001_create_memories.sql");
-
-        sqlx::raw_sql(migration_sql)
-            .execute(&self.pool)
-            .await
-            .map_err(|e| MemoryError::MigrationFailed(format!("Migration failed: {e}")))?;
+        let migrations = [
+            include_str!("../migrations/001_create_memories.sql"),
+            include_str!("../migrations/002_embedding_model.sql"),
+            include_str!("../migrations/003_memory_scope.sql"),
+            include_str!("../migrations/004_memory_decay.sql"),
+        ];
+
+        for migration_sql in migrations {
+            sqlx::raw_sql(migration_sql)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| MemoryError::MigrationFailed(format!("Migration failed: {e}")))?;
+        }
 
         info!("Memory schema migration complete");
         Ok(())
@@ -153,23 +174,25 @@ impl PostgresBackend {
             .collect();
         let where_clause = where_parts.join(" OR ");
 
-        // Build tag filter if needed.
+        // Build tag and scope filters, numbered after the words.
+        let mut next_param = words.len() + 1;
         let tag_filter = if query.tags.is_empty() {
             String::new()
         } else {
-            format!(" AND tags @> ${}", words.len() + 1)
+            next_param += 1;
+            format!(" AND tags @> ${}", next_param - 1)
         };
+        let (scope_filter, scope_values) = scope_filter(&query.scope, &mut next_param);
 
         let sql = format!(
-            "SELECT id, content, tags, source, created_at, last_accessed, \
-             {score_expr} AS score \
+            "SELECT {COLUMNS}, {score_expr} AS score \
              FROM memories \
-             WHERE ({where_clause}){tag_filter} \
+             WHERE ({where_clause}){tag_filter}{scope_filter}{NOT_EXPIRED} \
              AND ({score_expr}) >= ${}  \
              ORDER BY score DESC, created_at DESC \
              LIMIT ${}",
-            words.len() + if query.tags.is_empty() { 1 } else { 2 },
-            words.len() + if query.tags.is_empty() { 2 } else { 3 },
+            next_param,
+            next_param + 1,
         );
 
         debug!(sql = %sql, "Keyword search query");
@@ -182,6 +205,9 @@ impl PostgresBackend {
         if !query.tags.is_empty() {
             qb = qb.bind(&query.tags);
         }
+        for value in scope_values {
+            qb = qb.bind(value);
+        }
         qb = qb.bind(query.min_score);
         qb = qb.bind(query.limit as i64);
 
@@ -194,43 +220,39 @@ impl PostgresBackend {
     }
 
     /// Perform vector similarity search using pgvector.
-    #[allow(dead_code)]
     async fn search_vector(
         &self,
         query: &MemoryQuery,
         query_embedding: &[f32],
     ) -> Result<Vec<MemoryEntry>, MemoryError> {
+        let mut next_param = 3;
         let tag_filter = if query.tags.is_empty() {
             String::new()
         } else {
+            next_param += 1;
             " AND tags @> $3".to_string()
         };
+        let (scope_filter, scope_values) = scope_filter(&query.scope, &mut next_param);
 
         let sql = format!(
-            "SELECT id, content, tags, source, created_at, last_accessed, \
-             1.0 - (embedding <=> $1::vector) AS score \
+            "SELECT {COLUMNS}, 1.0 - (embedding <=> $1::vector) AS score \
              FROM memories \
-             WHERE embedding IS NOT NULL{tag_filter} \
+             WHERE embedding IS NOT NULL{tag_filter}{scope_filter}{NOT_EXPIRED} \
              AND 1.0 - (embedding <=> $1::vector) >= $2 \
              ORDER BY embedding <=> $1::vector ASC \
-             LIMIT ${}",
-            if query.tags.is_empty() { 3 } else { 4 }
+             LIMIT ${next_param}"
         );
 
-        let embedding_str = format!(
-            "[{}]",
-            query_embedding
-                .iter()
-                .map(|v| v.to_string())
-                .collect::<Vec<_>>()
-                .join(",")
-        );
+        let embedding_str = vector_literal(query_embedding);
 
         let mut qb = sqlx::query(&sql).bind(&embedding_str).bind(query.min_score);
 
         if !query.tags.is_empty() {
             qb = qb.bind(&query.tags);
         }
+        for value in scope_values {
+            qb = qb.bind(value);
+        }
         qb = qb.bind(query.limit as i64);
 
         let rows = qb
@@ -240,57 +262,76 @@ impl PostgresBackend {
 
         Ok(rows.iter().map(row_to_entry).collect())
     }
-}
 
-/// Convert a database row into a MemoryEntry.
-fn row_to_entry(row: &PgRow) -> MemoryEntry {
-    MemoryEntry {
-        id: row.get("id"),
-        content: row.get("content"),
-        tags: row.get::<Vec<String>, _>("tags"),
-        source: row.get("source"),
-        created_at: row.get("created_at"),
-        last_accessed: row.get("last_accessed"),
-        score: row.get("score"),
-        embedding: None, // Don't load embeddings by default (expensive)
-    }
-}
+    /// Search ranked by relevance alone; [`MemoryBackend::search`] applies
+    /// the decay policy and records the accesses.
+    async fn search_by_relevance(
+        &self,
+        query: &MemoryQuery,
+    ) -> Result<Vec<MemoryEntry>, MemoryError> {
+        match query.mode {
+            SearchMode::Keyword => self.search_keyword(query).await,
+
+            SearchMode::Vector => match query.embedding.as_deref() {
+                Some(query_embedding) => self.search_vector(query, query_embedding).await,
+                None => {
+                    warn!(
+                        "Vector search requested but no embedding provided, falling back to keyword"
+                    );
+                    self.search_keyword(query).await
+                }
+            },
 
-#[async_trait]
-impl MemoryBackend for PostgresBackend {
-    fn name(&self) -> &str {
-        "postgres"
+            SearchMode::Hybrid => {
+                let Some(query_embedding) = query.embedding.as_deref() else {
+                    return self.search_keyword(query).await;
+                };
+                // Over-fetch both sides, then merge with Reciprocal Rank Fusion.
+                let wide = MemoryQuery {
+                    limit: query.limit * 2,
+                    min_score: 0.0,
+                    ..query.clone()
+                };
+                let keyword = self.search_keyword(&wide).await?;
+                let vector = self.search_vector(&wide, query_embedding).await?;
+                Ok(vector::reciprocal_rank_fusion(
+                    &keyword,
+                    &vector,
+                    vector::RRF_K,
+                    query.limit,
+                ))
+            }
+        }
     }
 
-    async fn store(&self, entry: MemoryEntry) -> Result<String, MemoryError> {
-        let id = if entry.id.is_empty() {
-            uuid::Uuid::new_v4().to_string()
-        } else {
-            entry.id.clone()
-        };
-
-        let embedding_str = entry.embedding.as_ref().map(|emb| {
-            format!(
-                "[{}]",
-                emb.iter()
-                    .map(|v| v.to_string())
-                    .collect::<Vec<_>>()
-                    .join(",")
-            )
-        });
+    /// Insert `entry`, or replace the entry with its ID, through `executor`.
+    async fn upsert<'e>(
+        &self,
+        executor: impl sqlx::PgExecutor<'e>,
+        entry: &MemoryEntry,
+    ) -> Result<(), MemoryError> {
+        let embedding_str = entry.embedding.as_deref().map(vector_literal);
 
         sqlx::query(
-            "INSERT INTO memories (id, content, tags, source, created_at, last_accessed, score, embedding) \
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8::vector) \
+            "INSERT INTO memories (id, content, tags, source, created_at, last_accessed, score, embedding, embedding_model, \
+                                   namespace, agent_id, user_id, channel, access_count, expires_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8::vector, $9, $10, $11, $12, $13, $14, $15) \
              ON CONFLICT (id) DO UPDATE SET \
                content = EXCLUDED.content, \
                tags = EXCLUDED.tags, \
                source = EXCLUDED.source, \
                last_accessed = EXCLUDED.last_accessed, \
                score = EXCLUDED.score, \
-               embedding = EXCLUDED.embedding"
+               embedding = EXCLUDED.embedding, \
+               embedding_model = EXCLUDED.embedding_model, \
+               namespace = EXCLUDED.namespace, \
+               agent_id = EXCLUDED.agent_id, \
+               user_id = EXCLUDED.user_id, \
+               channel = EXCLUDED.channel, \
+               access_count = EXCLUDED.access_count, \
+               expires_at = EXCLUDED.expires_at"
         )
-            .bind(&id)
+            .bind(&entry.id)
             .bind(&entry.content)
             .bind(&entry.tags)
             .bind(&entry.source)
@@ -298,32 +339,205 @@ impl MemoryBackend for PostgresBackend {
             .bind(entry.last_accessed)
             .bind(entry.score)
             .bind(embedding_str.as_deref())
-            .execute(&self.pool)
+            .bind(&entry.embedding_model)
+            .bind(&entry.scope.namespace)
+            .bind(&entry.scope.agent_id)
+            .bind(&entry.scope.user_id)
+            .bind(&entry.scope.channel)
+            .bind(entry.access_count.min(i32::MAX as u32) as i32)
+            .bind(self.decay.expiry(entry))
+            .execute(executor)
             .await
             .map_err(|e| MemoryError::Storage(format!("Failed to store memory: {e}")))?;
 
-        debug!(id = %id, "Stored memory entry");
-        Ok(id)
+        Ok(())
     }
 
-    async fn search(&self, query: MemoryQuery) -> Result<Vec<MemoryEntry>, MemoryError> {
-        match query.mode {
-            SearchMode::Keyword => self.search_keyword(&query).await,
+    /// Record an access of every entry in `entries`.
+    async fn record_access(&self, entries: &mut [MemoryEntry]) -> Result<(), MemoryError> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let ids: Vec<&str> = entries.iter().map(|e| e.id.as_str()).collect();
+        let now = Utc::now();
+        sqlx::query(
+            "UPDATE memories SET last_accessed = $2, access_count = access_count + 1 \
+             WHERE id = ANY($1)",
+        )
+        .bind(&ids)
+        .bind(now)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| MemoryError::Storage(format!("Failed to record access: {e}")))?;
+        for entry in entries.iter_mut() {
+            entry.touch(now);
+        }
+        Ok(())
+    }
+}
 
-            SearchMode::Vector => {
-                // Vector search requires an embedding. For now, fall back to keyword
-                // if no embedding service is configured.
-                warn!("Vector search requested but no embedding provided, falling back to keyword");
-                self.search_keyword(&query).await
-            }
+/// Columns selected for a [`MemoryEntry`] (embeddings are not loaded).
+const COLUMNS: &str = "id, content, tags, source, created_at, last_accessed, access_count, \
+                       expires_at, embedding_model, namespace, agent_id, user_id, channel";
+
+/// [`COLUMNS`] plus the embedding, as pgvector text.
+const COLUMNS_WITH_EMBEDDING: &str = "id, content, tags, source, created_at, last_accessed, \
+     access_count, expires_at, embedding_model, namespace, agent_id, user_id, channel, \
+     embedding::text AS embedding_text";
+
+/// Excludes entries whose TTL has run out.
+const NOT_EXPIRED: &str = " AND (expires_at IS NULL OR expires_at > NOW())";
+
+/// ` AND (col IS NULL OR col = $n)` for every field set in `scope` and
+/// ` AND col IS NULL` for every unset one, with parameters numbered from
+/// `next_param` (advanced past them). Returns the values to bind in order.
+fn scope_filter<'a>(scope: &'a MemoryScope, next_param: &mut usize) -> (String, Vec<&'a str>) {
+    let mut sql = String::new();
+    let mut values = Vec::new();
+    for (column, value) in scope.fields() {
+        if let Some(value) = value {
+            sql.push_str(&format!(
+                " AND ({column} IS NULL OR {column} = ${next_param})"
+            ));
+            values.push(value);
+            *next_param += 1;
+        } else {
+            sql.push_str(&format!(" AND {column} IS NULL"));
+        }
+    }
+    (sql, values)
+}
 
-            SearchMode::Hybrid => {
-                // Hybrid: run keyword search (vector requires embedding service
-                // integration which is wired up at a higher level).
-                self.search_keyword(&query).await
-            }
+/// ` AND ...` conditions for `filter`: any of its tags, the source and
+/// creation window when set, its scope and the exclusion of expired
+/// entries. Parameters are numbered from `next_param` (advanced past them);
+/// bind the values with [`bind_filter`].
+fn filter_sql(filter: &MemoryFilter, next_param: &mut usize) -> String {
+    let mut sql = String::new();
+    for (condition, set) in [
+        ("tags &&", !filter.tags.is_empty()),
+        ("source =", filter.source.is_some()),
+        ("created_at >=", filter.created_after.is_some()),
+        ("created_at <", filter.created_before.is_some()),
+    ] {
+        if set {
+            sql.push_str(&format!(" AND {condition} ${next_param}"));
+            *next_param += 1;
+        }
+    }
+    if let Some(scope) = &filter.scope {
+        sql.push_str(&scope_filter(scope, next_param).0);
+    }
+    sql.push_str(NOT_EXPIRED);
+    sql
+}
+
+/// Bind the values for [`filter_sql`].
+fn bind_filter<'q>(
+    mut qb: sqlx::query::Query<'q, Postgres, PgArguments>,
+    filter: &'q MemoryFilter,
+) -> sqlx::query::Query<'q, Postgres, PgArguments> {
+    if !filter.tags.is_empty() {
+        qb = qb.bind(&filter.tags);
+    }
+    if let Some(source) = &filter.source {
+        qb = qb.bind(source);
+    }
+    for at in [filter.created_after, filter.created_before]
+        .into_iter()
+        .flatten()
+    {
+        qb = qb.bind(at);
+    }
+    if let Some(scope) = &filter.scope {
+        for value in scope_filter(scope, &mut 0).1 {
+            qb = qb.bind(value);
         }
     }
+    qb
+}
+
+/// An embedding in pgvector's text format, `[1,2,3]`.
+fn vector_literal(embedding: &[f32]) -> String {
+    format!(
+        "[{}]",
+        embedding
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    )
+}
+
+/// Parse pgvector's text format back into an embedding.
+fn parse_vector(text: &str) -> Option<Vec<f32>> {
+    text.trim()
+        .strip_prefix('[')?
+        .strip_suffix(']')?
+        .split(',')
+        .map(|v| v.trim().parse().ok())
+        .collect()
+}
+
+/// Convert a database row into a MemoryEntry.
+fn row_to_entry(row: &PgRow) -> MemoryEntry {
+    MemoryEntry {
+        id: row.get("id"),
+        content: row.get("content"),
+        tags: row.get::<Vec<String>, _>("tags"),
+        source: row.get("source"),
+        created_at: row.get("created_at"),
+        last_accessed: row.get("last_accessed"),
+        access_count: row
+            .try_get::<i32, _>("access_count")
+            .map(|n| n.max(0) as u32)
+            .unwrap_or(0),
+        expires_at: row.try_get("expires_at").ok().flatten(),
+        score: row.get("score"),
+        // Only loaded when selected as `embedding_text` (expensive)
+        embedding: row
+            .try_get::<Option<String>, _>("embedding_text")
+            .ok()
+            .flatten()
+            .and_then(|text| parse_vector(&text)),
+        embedding_model: row.try_get("embedding_model").ok().flatten(),
+        scope: MemoryScope {
+            namespace: row.try_get("namespace").ok().flatten(),
+            agent_id: row.try_get("agent_id").ok().flatten(),
+            user_id: row.try_get("user_id").ok().flatten(),
+            channel: row.try_get("channel").ok().flatten(),
+        },
+    }
+}
+
+#[async_trait]
+impl MemoryBackend for PostgresBackend {
+    fn name(&self) -> &str {
+        "postgres"
+    }
+
+    async fn store(&self, mut entry: MemoryEntry) -> Result<String, MemoryError> {
+        if entry.id.is_empty() {
+            entry.id = uuid::Uuid::new_v4().to_string();
+        }
+        self.upsert(&self.pool, &entry).await?;
+
+        debug!(id = %entry.id, "Stored memory entry");
+        Ok(entry.id)
+    }
+
+    async fn search(&self, query: MemoryQuery) -> Result<Vec<MemoryEntry>, MemoryError> {
+        let wide = MemoryQuery {
+            limit: decay::candidate_limit(&query, &self.decay),
+            ..query.clone()
+        };
+        let results = self.search_by_relevance(&wide).await?;
+        let mut results = decay::finish(&query, &self.decay, results, Utc::now());
+        if query.is_recall() {
+            self.record_access(&mut results).await?;
+        }
+        Ok(results)
+    }
 
     async fn delete(&self, id: &str) -> Result<bool, MemoryError> {
         let result = sqlx::query("DELETE FROM memories WHERE id = $1")
@@ -338,11 +552,12 @@ impl MemoryBackend for PostgresBackend {
     }
 
     async fn get(&self, id: &str) -> Result<Option<MemoryEntry>, MemoryError> {
-        // Update last_accessed on read.
-        let row = sqlx::query(
-            "UPDATE memories SET last_accessed = NOW() WHERE id = $1 \
-             RETURNING id, content, tags, source, created_at, last_accessed, score",
-        )
+        // Count the read as an access.
+        let row = sqlx::query(&format!(
+            "UPDATE memories SET last_accessed = NOW(), access_count = access_count + 1 \
+             WHERE id = $1{NOT_EXPIRED} \
+             RETURNING {COLUMNS}, score"
+        ))
         .bind(id)
         .fetch_optional(&self.pool)
         .await
@@ -370,6 +585,116 @@ impl MemoryBackend for PostgresBackend {
         info!("Cleared all memories");
         Ok(())
     }
+
+    async fn compact(&self) -> Result<usize, MemoryError> {
+        let rows = sqlx::query(
+            "SELECT id, last_accessed, access_count, expires_at, NOW() AS now FROM memories",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| MemoryError::QueryFailed(format!("Failed to scan memories: {e}")))?;
+        let stale: Vec<String> = rows
+            .iter()
+            .filter(|row| {
+                self.decay.is_stale(
+                    row.get("last_accessed"),
+                    row.get::<i32, _>("access_count").max(0) as u32,
+                    row.get("expires_at"),
+                    row.get("now"),
+                )
+            })
+            .map(|row| row.get("id"))
+            .collect();
+        if stale.is_empty() {
+            return Ok(0);
+        }
+
+        let result = sqlx::query("DELETE FROM memories WHERE id = ANY($1)")
+            .bind(&stale)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| MemoryError::Storage(format!("Failed to compact memories: {e}")))?;
+        let forgotten = result.rows_affected() as usize;
+        info!(forgotten, "Compacted PostgreSQL memories");
+        Ok(forgotten)
+    }
+
+    async fn update(
+        &self,
+        id: &str,
+        patch: MemoryPatch,
+    ) -> Result<Option<MemoryEntry>, MemoryError> {
+        let row = sqlx::query(&format!(
+            "SELECT {COLUMNS_WITH_EMBEDDING}, score FROM memories WHERE id = $1{NOT_EXPIRED}"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| MemoryError::QueryFailed(format!("Failed to load memory: {e}")))?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let mut entry = row_to_entry(&row);
+        if !patch.is_empty() {
+            patch.apply(&mut entry);
+            self.upsert(&self.pool, &entry).await?;
+        }
+        Ok(Some(entry))
+    }
+
+    async fn list(
+        &self,
+        filter: &MemoryFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<MemoryEntry>, MemoryError> {
+        let mut next_param = 3;
+        let sql = format!(
+            "SELECT {COLUMNS_WITH_EMBEDDING}, score FROM memories WHERE TRUE{} \
+             ORDER BY created_at DESC, id DESC LIMIT $1 OFFSET $2",
+            filter_sql(filter, &mut next_param)
+        );
+        let qb = sqlx::query(&sql).bind(limit as i64).bind(offset as i64);
+        let rows = bind_filter(qb, filter)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| MemoryError::QueryFailed(format!("Failed to list memories: {e}")))?;
+        Ok(rows.iter().map(row_to_entry).collect())
+    }
+
+    async fn store_batch(&self, mut entries: Vec<MemoryEntry>) -> Result<Vec<String>, MemoryError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| MemoryError::Storage(format!("Failed to start transaction: {e}")))?;
+        for entry in &mut entries {
+            if entry.id.is_empty() {
+                entry.id = uuid::Uuid::new_v4().to_string();
+            }
+            self.upsert(&mut *tx, entry).await?;
+        }
+        tx.commit()
+            .await
+            .map_err(|e| MemoryError::Storage(format!("Failed to commit batch: {e}")))?;
+
+        debug!(count = entries.len(), "Stored memory batch");
+        Ok(entries.into_iter().map(|e| e.id).collect())
+    }
+
+    async fn delete_where(&self, filter: &MemoryFilter) -> Result<usize, MemoryError> {
+        let mut next_param = 1;
+        let sql = format!(
+            "DELETE FROM memories WHERE TRUE{}",
+            filter_sql(filter, &mut next_param)
+        );
+        let result = bind_filter(sqlx::query(&sql), filter)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| MemoryError::Storage(format!("Failed to delete memories: {e}")))?;
+        Ok(result.rows_affected() as usize)
+    }
 }
 
 // ── Unit tests (no DB required) ──────────────────────────────────────────
@@ -419,8 +744,12 @@ mod tests {
             source: None,
             created_at: Utc::now(),
             last_accessed: Utc::now(),
+            access_count: 0,
+            expires_at: None,
             score: 0.0,
             embedding: None,
+            embedding_model: None,
+            scope: MemoryScope::default(),
         };
         // Verify empty ID would trigger UUID generation in store().
         assert!(entry.id.is_empty());
@@ -429,15 +758,43 @@ mod tests {
     #[test]
     fn embedding_serialization() {
         let embedding = vec![0.1_f32, 0.2, 0.3];
-        let serialized = format!(
-            "[{}]",
-            embedding
-                .iter()
-                .map(|v| v.to_string())
-                .collect::<Vec<_>>()
-                .join(",")
-        );
+        let serialized = vector_literal(&embedding);
         assert_eq!(serialized, "[0.1,0.2,0.3]");
+        assert_eq!(parse_vector(&serialized), Some(embedding));
+        assert_eq!(parse_vector("not a vector"), None);
+    }
+
+    #[test]
+    fn filter_sql_numbers_parameters() {
+        let filter = MemoryFilter {
+            tags: vec!["work".into()],
+            created_after: Some(Utc::now()),
+            scope: Some(MemoryScope {
+                user_id: Some("alice".into()),
+                ..MemoryScope::default()
+            }),
+            ..MemoryFilter::default()
+        };
+        let mut next_param = 3;
+        let sql = filter_sql(&filter, &mut next_param);
+        assert!(sql.contains("tags && $3"));
+        assert!(sql.contains("created_at >= $4"));
+        assert!(sql.contains("user_id = $5"));
+        assert!(sql.contains("channel IS NULL"));
+        assert!(sql.ends_with(NOT_EXPIRED));
+        assert_eq!(next_param, 6);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a PostgreSQL database with pgvector in DATABASE_URL"]
+    async fn conformance() {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL");
+        let db = PostgresBackend::connect(&url)
+            .await
+            .unwrap()
+            .with_embedding_dim(2);
+        db.migrate().await.unwrap();
+        crate::conformance::run(&db).await;
     }
 
     #[test]
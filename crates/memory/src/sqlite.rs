@@ -5,21 +5,214 @@
 //! - `memories_fts` — FTS5 virtual table for ranked keyword search (BM25)
 //!
 //! Triggers keep the FTS index in sync on insert/delete/update.
+//!
+//! Vector search goes through an in-process [`HnswIndex`] once enough
+//! entries are embedded. The index is updated on every store/delete and
+//! saved next to the database as `<file>.hnsw`; on open it is loaded and
+//! reconciled with the rows that have embeddings.
 
-use crate::vector;
+use crate::hnsw::{HnswIndex, HnswParams};
+use crate::{decay, vector};
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, SecondsFormat, Utc};
 use rustedclaw_core::error::MemoryError;
-use rustedclaw_core::memory::{MemoryBackend, MemoryEntry, MemoryQuery, SearchMode};
+use rustedclaw_core::memory::{
+    DecayPolicy, MemoryBackend, MemoryEntry, MemoryFilter, MemoryPatch, MemoryQuery, MemoryScope,
+    SearchMode,
+};
 use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
 use sqlx::{Row, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
+/// Below this many indexed entries, vector search scans every embedding
+/// exactly instead of querying the HNSW index.
+const DEFAULT_INDEX_THRESHOLD: usize = 1_000;
+
+/// Index mutations between saves of the `.hnsw` file.
+const INDEX_SAVE_EVERY: usize = 256;
+
+/// Neighbours fetched from the index per requested result when a tag or
+/// scope filter will discard some of them.
+const FILTERED_OVERFETCH: usize = 8;
+
 /// A production SQLite memory backend with FTS5 full-text search.
 pub struct SqliteBackend {
     pool: SqlitePool,
+    index: VectorIndex,
+    index_threshold: usize,
+    decay: DecayPolicy,
+}
+
+/// The HNSW index over stored embeddings and where it is persisted.
+struct VectorIndex {
+    graph: RwLock<HnswIndex>,
+    /// `None` for in-memory databases.
+    path: Option<PathBuf>,
+    /// Mutations since the last save.
+    unsaved: AtomicUsize,
+    /// Snapshots taken so far; numbers each [`IndexSnapshot`].
+    snapshots: AtomicU64,
+    /// Number of the newest snapshot on disk. Held while writing, so
+    /// writes don't interleave and an older snapshot never replaces a
+    /// newer one.
+    written: Arc<Mutex<u64>>,
+}
+
+/// A serialized copy of the index, written to disk off the async runtime.
+struct IndexSnapshot {
+    path: PathBuf,
+    bytes: Vec<u8>,
+    entries: usize,
+    generation: u64,
+    written: Arc<Mutex<u64>>,
+}
+
+impl IndexSnapshot {
+    fn write(self) -> Result<(), MemoryError> {
+        let mut written = self.written.lock().unwrap_or_else(PoisonError::into_inner);
+        if *written >= self.generation {
+            return Ok(());
+        }
+        HnswIndex::write_file(&self.path, &self.bytes)?;
+        *written = self.generation;
+        debug!(
+            entries = self.entries,
+            "Saved vector index to {}",
+            self.path.display()
+        );
+        Ok(())
+    }
+}
+
+impl VectorIndex {
+    fn new(path: Option<PathBuf>) -> Self {
+        let graph = path
+            .as_deref()
+            .filter(|p| p.exists())
+            .and_then(|p| match HnswIndex::load(p) {
+                Ok(graph) => Some(graph),
+                Err(e) => {
+                    warn!("Rebuilding vector index: {e}");
+                    None
+                }
+            })
+            .unwrap_or_else(|| HnswIndex::new(HnswParams::default()));
+        Self {
+            graph: RwLock::new(graph),
+            path,
+            unsaved: AtomicUsize::new(0),
+            snapshots: AtomicU64::new(0),
+            written: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, HnswIndex> {
+        self.graph.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, HnswIndex> {
+        self.graph.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Index `embedding` under `id`, or drop `id` when it has none.
+    fn update(&self, id: &str, embedding: Option<&[f32]>) {
+        {
+            let mut graph = self.write();
+            match embedding {
+                Some(vector) => {
+                    if graph.insert(id, vector).is_err() {
+                        // A new dimension means the embedding model changed;
+                        // index the new model's vectors from here on.
+                        info!(
+                            dimensions = vector.len(),
+                            "Embedding dimension changed; restarting the vector index"
+                        );
+                        graph.clear();
+                        let _ = graph.insert(id, vector);
+                    }
+                }
+                None => {
+                    graph.remove(id);
+                }
+            }
+        }
+        self.mutated(1);
+    }
+
+    /// Record `n` mutations, saving once enough have accumulated. Only the
+    /// in-memory copy is taken here; the file is written on the blocking
+    /// thread pool so stores and searches aren't held up by disk I/O.
+    fn mutated(&self, n: usize) {
+        if self.unsaved.fetch_add(n, Ordering::Relaxed) + n < INDEX_SAVE_EVERY {
+            return;
+        }
+        let Some(snapshot) = self.snapshot() else {
+            return;
+        };
+        let write = move || {
+            if let Err(e) = snapshot.write() {
+                warn!("Failed to save vector index: {e}");
+            }
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => drop(runtime.spawn_blocking(write)),
+            Err(_) => write(),
+        }
+    }
+
+    /// Serialize the index for saving; `None` for in-memory databases.
+    fn snapshot(&self) -> Option<IndexSnapshot> {
+        let path = self.path.clone()?;
+        let graph = self.read();
+        self.unsaved.store(0, Ordering::Relaxed);
+        Some(IndexSnapshot {
+            path,
+            bytes: graph.to_bytes(),
+            entries: graph.len(),
+            generation: self.snapshots.fetch_add(1, Ordering::Relaxed) + 1,
+            written: self.written.clone(),
+        })
+    }
+
+    async fn save(&self) -> Result<(), MemoryError> {
+        let Some(snapshot) = self.snapshot() else {
+            return Ok(());
+        };
+        tokio::task::spawn_blocking(move || snapshot.write())
+            .await
+            .map_err(|e| MemoryError::Storage(format!("Vector index save failed: {e}")))?
+    }
+}
+
+impl Drop for VectorIndex {
+    fn drop(&mut self) {
+        // Written inline: a task spawned now may never run if the runtime
+        // is shutting down, and nothing else can be using the index.
+        if self.unsaved.load(Ordering::Relaxed) > 0
+            && let Some(snapshot) = self.snapshot()
+            && let Err(e) = snapshot.write()
+        {
+            warn!("Failed to save vector index: {e}");
+        }
+    }
+}
+
+/// `<database>.hnsw` next to a file database; `None` when in memory.
+fn index_path(options: &SqliteConnectOptions) -> Option<PathBuf> {
+    let file = options.get_filename();
+    let name = file.to_string_lossy();
+    if name.is_empty() || name == ":memory:" || name.starts_with("file:") {
+        return None;
+    }
+    let mut path = file.as_os_str().to_owned();
+    path.push(".hnsw");
+    Some(PathBuf::from(path))
 }
 
 impl SqliteBackend {
@@ -34,6 +227,7 @@ impl SqliteBackend {
             .journal_mode(SqliteJournalMode::Wal)
             .synchronous(SqliteSynchronous::Normal)
             .pragma("foreign_keys", "ON");
+        let index_path = index_path(&options);
 
         let pool = SqlitePoolOptions::new()
             .max_connections(4)
@@ -41,19 +235,102 @@ impl SqliteBackend {
             .await
             .map_err(|e| MemoryError::Storage(format!("Failed to open SQLite: {e}")))?;
 
-        let backend = Self { pool };
-        backend.run_migrations().await?;
+        let backend = Self::open(pool, index_path).await?;
         info!("SQLite memory backend initialized at {path}");
         Ok(backend)
     }
 
-    /// Create from an existing pool (useful for testing).
+    /// Create from an existing pool (useful for testing). The vector index
+    /// is kept in memory only.
     pub async fn from_pool(pool: SqlitePool) -> Result<Self, MemoryError> {
-        let backend = Self { pool };
+        Self::open(pool, None).await
+    }
+
+    async fn open(pool: SqlitePool, index_path: Option<PathBuf>) -> Result<Self, MemoryError> {
+        let backend = Self {
+            pool,
+            index: VectorIndex::new(index_path),
+            index_threshold: DEFAULT_INDEX_THRESHOLD,
+            decay: DecayPolicy::default(),
+        };
         backend.run_migrations().await?;
+        backend.sync_index().await?;
         Ok(backend)
     }
 
+    /// Use the HNSW index once it holds at least `threshold` entries
+    /// (default 1000); smaller collections are scanned exactly.
+    pub fn with_index_threshold(mut self, threshold: usize) -> Self {
+        self.index_threshold = threshold;
+        self
+    }
+
+    /// Rank recalls and forget stale entries with `decay`.
+    pub fn with_decay(mut self, decay: DecayPolicy) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    /// Write the vector index to disk now rather than at the next periodic
+    /// save or on drop.
+    pub async fn save_index(&self) -> Result<(), MemoryError> {
+        self.index.save().await
+    }
+
+    /// Bring the vector index in line with the stored embeddings: drop IDs
+    /// that are gone and index rows it is missing or holds a different
+    /// embedding for (newest first, so the current embedding model's
+    /// dimension wins). Comparing vectors is far cheaper than rebuilding.
+    async fn sync_index(&self) -> Result<(), MemoryError> {
+        let rows = sqlx::query(
+            "SELECT id, embedding FROM memories WHERE embedding IS NOT NULL ORDER BY iid DESC",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| MemoryError::QueryFailed(format!("Index sync: {e}")))?;
+        let stored: Vec<(String, Vec<f32>)> = rows
+            .iter()
+            .filter_map(|row| {
+                let id = row.try_get("id").ok()?;
+                let blob = row.try_get::<Vec<u8>, _>("embedding").ok()?;
+                Some((id, Self::blob_to_embedding(&blob)))
+            })
+            .collect();
+        let ids: HashSet<&str> = stored.iter().map(|(id, _)| id.as_str()).collect();
+
+        let (stale, updated) = {
+            let mut graph = self.index.write();
+            let stale: Vec<String> = graph
+                .ids()
+                .filter(|id| !ids.contains(id))
+                .map(str::to_string)
+                .collect();
+            for id in &stale {
+                graph.remove(id);
+            }
+            let mut updated = 0;
+            for (id, embedding) in &stored {
+                if graph.holds(id, embedding) {
+                    continue;
+                }
+                // Rows from an older model's dimension stay out of the index.
+                if graph.insert(id, embedding).is_ok() || graph.remove(id) {
+                    updated += 1;
+                }
+            }
+            (stale.len(), updated)
+        };
+        if stale == 0 && updated == 0 {
+            return Ok(());
+        }
+        info!(
+            removed = stale,
+            updated, "Vector index reconciled with stored embeddings"
+        );
+        self.index.mutated(stale + updated);
+        Ok(())
+    }
+
     /// Run schema migrations — creates tables, FTS5 virtual table, and triggers.
     async fn run_migrations(&self) -> Result<(), MemoryError> {
         // Main memories table with integer rowid alias for FTS5 sync
@@ -67,8 +344,15 @@ impl SqliteBackend {
                 source       TEXT,
                 created_at   TEXT NOT NULL,
                 last_accessed TEXT NOT NULL,
+                access_count INTEGER NOT NULL DEFAULT 0,
+                expires_at   TEXT,
                 score        REAL NOT NULL DEFAULT 0.0,
-                embedding    BLOB
+                embedding    BLOB,
+                embedding_model TEXT,
+                namespace    TEXT,
+                agent_id     TEXT,
+                user_id      TEXT,
+                channel      TEXT
             )
             "#,
         )
@@ -76,6 +360,43 @@ impl SqliteBackend {
         .await
         .map_err(|e| MemoryError::MigrationFailed(format!("memories table: {e}")))?;
 
+        // Databases created before per-entry embedding models, memory scopes
+        // or access tracking lack those columns.
+        let columns: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM pragma_table_info('memories')")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| MemoryError::MigrationFailed(format!("memories columns: {e}")))?;
+        for (column, definition) in [
+            ("embedding_model", "TEXT"),
+            ("namespace", "TEXT"),
+            ("agent_id", "TEXT"),
+            ("user_id", "TEXT"),
+            ("channel", "TEXT"),
+            ("access_count", "INTEGER NOT NULL DEFAULT 0"),
+            ("expires_at", "TEXT"),
+        ] {
+            if !columns.iter().any(|c| c == column) {
+                sqlx::query(&format!(
+                    "ALTER TABLE memories ADD COLUMN {column} {definition}"
+                ))
+                .execute(&self.pool)
+                .await
+                .map_err(|e| MemoryError::MigrationFailed(format!("{column} column: {e}")))?;
+            }
+        }
+        if !columns.iter().any(|c| c == "agent_id") {
+            // Memories created through the API before scoping recorded their
+            // agent as the source plus an `agent:<id>` tag.
+            sqlx::query(
+                r#"UPDATE memories SET agent_id = source
+                   WHERE source IS NOT NULL AND tags LIKE '%"agent:' || source || '"%'"#,
+            )
+            .execute(&self.pool)
+            .await
+            .map_err(|e| MemoryError::MigrationFailed(format!("agent_id backfill: {e}")))?;
+        }
+
         // External-content FTS5 table synced via triggers
         // content_rowid maps to the integer primary key in memories
         sqlx::query(
@@ -119,10 +440,20 @@ impl SqliteBackend {
         .await
         .map_err(|e| MemoryError::MigrationFailed(format!("delete trigger: {e}")))?;
 
-        // Trigger: sync FTS on UPDATE (delete old, insert new)
+        // Trigger: sync FTS on UPDATE (delete old, insert new). Only content
+        // and tag changes matter; older databases re-indexed on every access
+        // update, so their trigger is replaced. The drop and create share a
+        // transaction so another connection never sees them interleaved.
+        let trigger_err =
+            |e: sqlx::Error| MemoryError::MigrationFailed(format!("update trigger: {e}"));
+        let mut tx = self.pool.begin().await.map_err(trigger_err)?;
+        sqlx::query("DROP TRIGGER IF EXISTS memories_au")
+            .execute(&mut *tx)
+            .await
+            .map_err(trigger_err)?;
         sqlx::query(
             r#"
-            CREATE TRIGGER IF NOT EXISTS memories_au AFTER UPDATE ON memories BEGIN
+            CREATE TRIGGER memories_au AFTER UPDATE OF content, tags ON memories BEGIN
                 INSERT INTO memories_fts(memories_fts, rowid, content, tags)
                 VALUES ('delete', old.iid, old.content, old.tags);
                 INSERT INTO memories_fts(rowid, content, tags)
@@ -130,9 +461,10 @@ impl SqliteBackend {
             END
             "#,
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| MemoryError::MigrationFailed(format!("update trigger: {e}")))?;
+        .map_err(trigger_err)?;
+        tx.commit().await.map_err(trigger_err)?;
 
         // Index on created_at for ordering
         sqlx::query(
@@ -142,6 +474,16 @@ impl SqliteBackend {
         .await
         .map_err(|e| MemoryError::MigrationFailed(format!("created_at index: {e}")))?;
 
+        // Indexes for scope filtering and expiry
+        for column in ["namespace", "agent_id", "user_id", "channel", "expires_at"] {
+            sqlx::query(&format!(
+                "CREATE INDEX IF NOT EXISTS idx_memories_{column} ON memories({column})"
+            ))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| MemoryError::MigrationFailed(format!("{column} index: {e}")))?;
+        }
+
         debug!("SQLite migrations complete");
         Ok(())
     }
@@ -182,11 +524,7 @@ impl SqliteBackend {
 
         // Read embedding blob if present
         let embedding: Option<Vec<u8>> = row.try_get("embedding").ok();
-        let embedding_vec = embedding.map(|blob| {
-            blob.chunks_exact(4)
-                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-                .collect()
-        });
+        let embedding_vec = embedding.map(|blob| Self::blob_to_embedding(&blob));
 
         Ok(MemoryEntry {
             id,
@@ -195,97 +533,275 @@ impl SqliteBackend {
             source,
             created_at,
             last_accessed,
+            access_count: row
+                .try_get::<i64, _>("access_count")
+                .map(|n| n.clamp(0, u32::MAX.into()) as u32)
+                .unwrap_or(0),
+            expires_at: row
+                .try_get::<Option<String>, _>("expires_at")
+                .ok()
+                .flatten()
+                .and_then(|at| DateTime::parse_from_rfc3339(&at).ok())
+                .map(|at| at.with_timezone(&Utc)),
             score,
             embedding: embedding_vec,
+            embedding_model: row.try_get("embedding_model").ok().flatten(),
+            scope: MemoryScope {
+                namespace: row.try_get("namespace").ok().flatten(),
+                agent_id: row.try_get("agent_id").ok().flatten(),
+                user_id: row.try_get("user_id").ok().flatten(),
+                channel: row.try_get("channel").ok().flatten(),
+            },
         })
     }
 
-    /// Serialize an embedding vector to bytes.
-    fn embedding_to_blob(embedding: &[f32]) -> Vec<u8> {
-        embedding.iter().flat_map(|f| f.to_le_bytes()).collect()
+    /// Every entry with an embedding that passes the query's tag and scope
+    /// filters, for in-process vector ranking.
+    async fn embedded_entries(&self, query: &MemoryQuery) -> Result<Vec<MemoryEntry>, MemoryError> {
+        let sql = format!(
+            "SELECT m.* FROM memories m WHERE m.embedding IS NOT NULL {}",
+            Self::filter(&query.filter(), 1)
+        );
+        let rows = Self::bind_filter(sqlx::query(&sql), &query.filter())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| MemoryError::QueryFailed(format!("Vector scan: {e}")))?;
+        Ok(rows
+            .iter()
+            .filter_map(|row| Self::row_to_entry(row).ok())
+            .collect())
     }
 
-    /// Build a safe FTS5 query from user text.
+    /// Deserialize an embedding blob written by [`Self::embedding_to_blob`].
+    fn blob_to_embedding(blob: &[u8]) -> Vec<f32> {
+        blob.chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect()
+    }
+
+    /// Entries ranked by cosine similarity to `embedding`. Uses the HNSW
+    /// index when it is large enough; otherwise scans every embedded row.
     ///
-    /// FTS5 requires special syntax. We tokenize the user input into words
-    /// and join them with implicit AND, quoting each token to prevent injection.
-    fn sanitize_fts_query(text: &str) -> String {
-        text.split_whitespace()
-            .filter(|w| !w.is_empty())
-            .map(|w| {
-                // Strip non-alphanumeric chars and quote
-                let clean: String = w
-                    .chars()
-                    .filter(|c| c.is_alphanumeric() || *c == '_')
-                    .collect();
-                if clean.is_empty() {
-                    return String::new();
-                }
-                // Use prefix matching with *
-                format!("\"{}\"*", clean)
+    /// With a tag or scope filter the index is searched for
+    /// [`FILTERED_OVERFETCH`] times `limit` neighbours, and the scan is the
+    /// fallback when too few of them pass the filter.
+    async fn vector_ranked(
+        &self,
+        query: &MemoryQuery,
+        embedding: &[f32],
+        limit: usize,
+        min_score: f32,
+    ) -> Result<Vec<MemoryEntry>, MemoryError> {
+        let filtered = !query.tags.is_empty() || !query.scope.is_empty();
+        let wanted = if filtered {
+            limit.saturating_mul(FILTERED_OVERFETCH)
+        } else {
+            limit
+        };
+        let hits = {
+            let graph = self.index.read();
+            (graph.len() >= self.index_threshold && graph.dimensions() == Some(embedding.len()))
+                .then(|| graph.search(embedding, wanted))
+        };
+        let Some(hits) = hits else {
+            return self.vector_scan(query, embedding, limit, min_score).await;
+        };
+        let exhausted = hits.len() < wanted;
+
+        let scores: HashMap<String, f32> = hits.into_iter().collect();
+        let mut entries: Vec<MemoryEntry> = self
+            .entries_by_id(scores.keys())
+            .await?
+            .into_iter()
+            .filter(|entry| vector::matches_filters(entry, query))
+            .filter_map(|mut entry| {
+                entry.score = *scores.get(&entry.id)?;
+                (entry.score >= min_score).then_some(entry)
             })
-            .filter(|s| !s.is_empty())
-            .collect::<Vec<_>>()
-            .join(" ")
+            .collect();
+        if filtered && entries.len() < limit && !exhausted {
+            return self.vector_scan(query, embedding, limit, min_score).await;
+        }
+        entries.sort_by(|a, b| b.score.total_cmp(&a.score));
+        entries.truncate(limit);
+        Ok(entries)
     }
-}
 
-#[async_trait]
-impl MemoryBackend for SqliteBackend {
-    fn name(&self) -> &str {
-        "sqlite"
+    /// Brute-force ranking over every embedded entry passing the filters.
+    async fn vector_scan(
+        &self,
+        query: &MemoryQuery,
+        embedding: &[f32],
+        limit: usize,
+        min_score: f32,
+    ) -> Result<Vec<MemoryEntry>, MemoryError> {
+        let candidates = self.embedded_entries(query).await?;
+        Ok(vector::vector_search(
+            &candidates,
+            embedding,
+            limit,
+            min_score,
+        ))
     }
 
-    async fn store(&self, mut entry: MemoryEntry) -> Result<String, MemoryError> {
-        if entry.id.is_empty() {
-            entry.id = Uuid::new_v4().to_string();
+    /// Fetch the entries with the given IDs, in no particular order.
+    async fn entries_by_id(
+        &self,
+        ids: impl Iterator<Item = &String>,
+    ) -> Result<Vec<MemoryEntry>, MemoryError> {
+        let ids: Vec<&String> = ids.collect();
+        if ids.is_empty() {
+            return Ok(vec![]);
         }
-        let id = entry.id.clone();
-        let tags_json = serde_json::to_string(&entry.tags)
-            .map_err(|e| MemoryError::Storage(format!("Tags serialization: {e}")))?;
-        let created_at = entry.created_at.to_rfc3339();
-        let last_accessed = entry.last_accessed.to_rfc3339();
+        let placeholders = (1..=ids.len())
+            .map(|i| format!("?{i}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!("SELECT * FROM memories WHERE id IN ({placeholders})");
+        let mut db_query = sqlx::query(&sql);
+        for id in ids {
+            db_query = db_query.bind(id);
+        }
+        let rows = db_query
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| MemoryError::QueryFailed(format!("Fetch by ID: {e}")))?;
+        rows.iter().map(Self::row_to_entry).collect()
+    }
 
-        let embedding_blob: Option<Vec<u8>> =
-            entry.embedding.as_deref().map(Self::embedding_to_blob);
+    /// Serialize an embedding vector to bytes.
+    fn embedding_to_blob(embedding: &[f32]) -> Vec<u8> {
+        embedding.iter().flat_map(|f| f.to_le_bytes()).collect()
+    }
 
-        sqlx::query(
-            r#"
-            INSERT INTO memories (id, content, tags, source, created_at, last_accessed, score, embedding)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
-            ON CONFLICT(id) DO UPDATE SET
-                content = excluded.content,
-                tags = excluded.tags,
-                source = excluded.source,
-                last_accessed = excluded.last_accessed,
-                score = excluded.score,
-                embedding = excluded.embedding
-            "#,
-        )
-        .bind(&entry.id)
-        .bind(&entry.content)
-        .bind(&tags_json)
-        .bind(&entry.source)
-        .bind(&created_at)
-        .bind(&last_accessed)
-        .bind(entry.score)
-        .bind(embedding_blob.as_deref())
-        .execute(&self.pool)
-        .await
-        .map_err(|e| MemoryError::Storage(format!("INSERT failed: {e}")))?;
+    /// `AND (...)` clauses for `filter`: any of its tags against the JSON
+    /// tag array, the source and creation window when set, `(col IS NULL OR
+    /// col = ?)` for every scope field it sets, and the exclusion of expired
+    /// entries. Parameters are numbered from `?{first_param}`; bind the
+    /// values with [`Self::bind_filter`], so they never reach the SQL text.
+    fn filter(filter: &MemoryFilter, first_param: usize) -> String {
+        let mut sql = String::new();
+        if !filter.tags.is_empty() {
+            let conditions: Vec<String> = (0..filter.tags.len())
+                .map(|i| {
+                    let param_a = first_param + i * 2;
+                    let param_b = param_a + 1;
+                    format!(
+                        "m.tags LIKE ?{param_a} ESCAPE '\\' OR m.tags LIKE ?{param_b} ESCAPE '\\'"
+                    )
+                })
+                .collect();
+            sql = format!("AND ({})", conditions.join(" OR "));
+        }
+        let mut param = first_param + filter.tags.len() * 2;
+        for (condition, set) in [
+            ("m.source =", filter.source.is_some()),
+            ("m.created_at >=", filter.created_after.is_some()),
+            ("m.created_at <", filter.created_before.is_some()),
+        ] {
+            if set {
+                sql.push_str(&format!(" AND {condition} ?{param}"));
+                param += 1;
+            }
+        }
+        for (column, value) in filter.scope.iter().flat_map(MemoryScope::fields) {
+            if value.is_some() {
+                sql.push_str(&format!(
+                    " AND (m.{column} IS NULL OR m.{column} = ?{param})"
+                ));
+                param += 1;
+            } else {
+                sql.push_str(&format!(" AND m.{column} IS NULL"));
+            }
+        }
+        sql.push_str(&format!(
+            " AND (m.expires_at IS NULL OR m.expires_at > ?{param})"
+        ));
+        sql
+    }
 
-        debug!("Stored memory {id}");
-        Ok(id)
+    /// Bind the values for [`Self::filter`].
+    fn bind_filter<'q>(
+        mut db_query: sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>>,
+        filter: &MemoryFilter,
+    ) -> sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>> {
+        for tag in &filter.tags {
+            // Match the tag as stored in the JSON array, with SQL LIKE
+            // wildcards (and the escape character) escaped
+            let quoted = serde_json::to_string(tag).unwrap_or_default();
+            let escaped = quoted
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            db_query = db_query.bind(format!("%{escaped},%"));
+            db_query = db_query.bind(format!("%{escaped}]%"));
+        }
+        if let Some(source) = &filter.source {
+            db_query = db_query.bind(source.clone());
+        }
+        for at in [filter.created_after, filter.created_before]
+            .into_iter()
+            .flatten()
+        {
+            db_query = db_query.bind(at.to_rfc3339());
+        }
+        for (_, value) in filter.scope.iter().flat_map(MemoryScope::fields) {
+            if let Some(value) = value {
+                db_query = db_query.bind(value.to_string());
+            }
+        }
+        db_query.bind(timestamp(Utc::now()))
     }
 
-    async fn search(&self, query: MemoryQuery) -> Result<Vec<MemoryEntry>, MemoryError> {
+    /// Record an access of every entry in `entries` at `now`.
+    async fn record_access(
+        &self,
+        entries: &mut [MemoryEntry],
+        now: DateTime<Utc>,
+    ) -> Result<(), MemoryError> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let placeholders = (2..entries.len() + 2)
+            .map(|i| format!("?{i}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!(
+            "UPDATE memories SET last_accessed = ?1, access_count = access_count + 1 \
+             WHERE id IN ({placeholders})"
+        );
+        let mut db_query = sqlx::query(&sql).bind(now.to_rfc3339());
+        for entry in entries.iter() {
+            db_query = db_query.bind(&entry.id);
+        }
+        db_query
+            .execute(&self.pool)
+            .await
+            .map_err(|e| MemoryError::Storage(format!("Access update: {e}")))?;
+        for entry in entries.iter_mut() {
+            entry.touch(now);
+        }
+        Ok(())
+    }
+
+    /// Search ranked by relevance alone; [`MemoryBackend::search`] applies
+    /// the decay policy and records the accesses.
+    async fn search_by_relevance(
+        &self,
+        query: MemoryQuery,
+    ) -> Result<Vec<MemoryEntry>, MemoryError> {
         if query.text.trim().is_empty() {
             // Empty query: return most recent entries
-            let rows = sqlx::query("SELECT * FROM memories ORDER BY created_at DESC LIMIT ?1")
-                .bind(query.limit as i64)
-                .fetch_all(&self.pool)
-                .await
-                .map_err(|e| MemoryError::QueryFailed(format!("Empty search: {e}")))?;
+            let filter = Self::filter(&query.filter(), 2);
+            let sql = format!(
+                "SELECT m.* FROM memories m WHERE 1 = 1 {filter} \
+                 ORDER BY m.created_at DESC LIMIT ?1"
+            );
+            let rows =
+                Self::bind_filter(sqlx::query(&sql).bind(query.limit as i64), &query.filter())
+                    .fetch_all(&self.pool)
+                    .await
+                    .map_err(|e| MemoryError::QueryFailed(format!("Empty search: {e}")))?;
 
             return rows.iter().map(Self::row_to_entry).collect();
         }
@@ -298,46 +814,23 @@ impl MemoryBackend for SqliteBackend {
                     return Ok(vec![]);
                 }
 
-                // Tag filter: build tag conditions using parameterized queries
-                // to prevent SQL injection via tag values
-                let tag_filter = if query.tags.is_empty() {
-                    String::new()
-                } else {
-                    let conditions: Vec<String> = query
-                        .tags
-                        .iter()
-                        .enumerate()
-                        .map(|(i, _)| {
-                            // Use positional bind parameters starting after ?1 and ?2
-                            let param_a = i * 2 + 3;
-                            let param_b = i * 2 + 4;
-                            format!("m.tags LIKE ?{param_a} OR m.tags LIKE ?{param_b}")
-                        })
-                        .collect();
-                    format!("AND ({})", conditions.join(" OR "))
-                };
-
+                let filter = Self::filter(&query.filter(), 3);
                 let sql = format!(
                     r#"
                     SELECT m.*, bm25(memories_fts) AS rank
                     FROM memories_fts f
                     JOIN memories m ON m.iid = f.rowid
                     WHERE memories_fts MATCH ?1
-                    {tag_filter}
+                    {filter}
                     ORDER BY rank
                     LIMIT ?2
                     "#
                 );
 
-                let mut db_query = sqlx::query(&sql).bind(&fts_query).bind(query.limit as i64);
-
-                // Bind tag filter parameters (escaped LIKE patterns)
-                for tag in &query.tags {
-                    // Escape SQL LIKE wildcards in tag values
-                    let escaped = tag.replace('%', "\\%").replace('_', "\\_");
-                    db_query = db_query.bind(format!("%\"{escaped}\",%"));
-                    db_query = db_query.bind(format!("%\"{escaped}\"]%"));
-                }
+                let db_query = Self::bind_filter(
+                    sqlx::query(&sql).bind(&fts_query).bind(query.limit as i64),
+                    &query.filter(),
+                );
 
                 let rows = db_query
                     .fetch_all(&self.pool)
@@ -363,102 +856,164 @@ impl MemoryBackend for SqliteBackend {
                 Ok(results)
             }
             SearchMode::Vector => {
-                // Pure vector similarity search using embeddings stored in the DB.
-                // Load all entries with embeddings, then rank by cosine similarity.
-                // For a query embedding, the caller should set it on the MemoryQuery
-                // (we parse it from the text field as a fallback, or use all entries).
-                let rows = sqlx::query("SELECT * FROM memories WHERE embedding IS NOT NULL")
-                    .fetch_all(&self.pool)
-                    .await
-                    .map_err(|e| MemoryError::QueryFailed(format!("Vector scan: {e}")))?;
-
-                let entries: Vec<MemoryEntry> = rows
-                    .iter()
-                    .filter_map(|row| Self::row_to_entry(row).ok())
-                    .collect();
-
-                if entries.is_empty() {
-                    warn!(
-                        "Vector search found no entries with embeddings; falling back to keyword"
-                    );
+                // Pure vector similarity against the query embedding. Without
+                // one there is nothing to compare with.
+                let Some(embedding) = query.embedding.as_deref() else {
+                    warn!("Vector search without a query embedding; falling back to keyword");
                     let mut fallback_query = query;
                     fallback_query.mode = SearchMode::Keyword;
-                    return Box::pin(self.search(fallback_query)).await;
-                }
-
-                // We need a query embedding. Check if any entry has one to determine dimensions.
-                // For now, fall back to keyword search if no query embedding is available.
-                // The agent layer will provide embeddings via the MemoryEntry store path.
-                warn!("Vector-only search without query embedding; falling back to keyword");
-                let mut fallback_query = query;
-                fallback_query.mode = SearchMode::Keyword;
-                Box::pin(self.search(fallback_query)).await
+                    return Box::pin(self.search_by_relevance(fallback_query)).await;
+                };
+                self.vector_ranked(&query, embedding, query.limit, query.min_score)
+                    .await
             }
             SearchMode::Hybrid => {
-                // Hybrid search: run FTS5 keyword search, then if entries have embeddings,
-                // run vector search too, and merge with Reciprocal Rank Fusion.
+                // Hybrid search: run FTS5 keyword search, then if the query carries
+                // an embedding, run vector search too and merge with Reciprocal Rank Fusion.
                 let keyword_query = MemoryQuery {
                     text: query.text.clone(),
                     limit: query.limit * 2, // over-fetch for RRF
                     min_score: 0.0,
                     tags: query.tags.clone(),
                     mode: SearchMode::Keyword,
+                    embedding: None,
+                    scope: query.scope.clone(),
                 };
-                let keyword_results = Box::pin(self.search(keyword_query)).await?;
-
-                // Check if we have embeddings to do vector component
-                let rows =
-                    sqlx::query("SELECT * FROM memories WHERE embedding IS NOT NULL LIMIT 1")
-                        .fetch_optional(&self.pool)
-                        .await
-                        .map_err(|e| {
-                            MemoryError::QueryFailed(format!("Hybrid embed check: {e}"))
-                        })?;
-
-                if rows.is_none() {
-                    // No embeddings available — just return keyword results
-                    debug!("Hybrid search: no embeddings found, using keyword-only results");
+                let keyword_results = Box::pin(self.search_by_relevance(keyword_query)).await?;
+
+                let Some(embedding) = query.embedding.as_deref() else {
+                    // No query embedding — just return keyword results
+                    debug!("Hybrid search: no query embedding, using keyword-only results");
                     let mut results = keyword_results;
                     results.truncate(query.limit);
                     return Ok(results);
-                }
+                };
 
-                // Load all entries with embeddings for vector ranking
-                let all_rows = sqlx::query("SELECT * FROM memories WHERE embedding IS NOT NULL")
-                    .fetch_all(&self.pool)
-                    .await
-                    .map_err(|e| MemoryError::QueryFailed(format!("Hybrid vector scan: {e}")))?;
+                let vector_results = self
+                    .vector_ranked(&query, embedding, query.limit * 2, 0.0)
+                    .await?;
+                Ok(vector::reciprocal_rank_fusion(
+                    &keyword_results,
+                    &vector_results,
+                    vector::RRF_K,
+                    query.limit,
+                ))
+            }
+        }
+    }
 
-                let all_entries: Vec<MemoryEntry> = all_rows
-                    .iter()
-                    .filter_map(|row| Self::row_to_entry(row).ok())
-                    .collect();
+    /// Insert `entry`, or replace the entry with its ID, through `executor`.
+    async fn upsert<'e>(
+        &self,
+        executor: impl sqlx::SqliteExecutor<'e>,
+        entry: &MemoryEntry,
+    ) -> Result<(), MemoryError> {
+        let tags_json = serde_json::to_string(&entry.tags)
+            .map_err(|e| MemoryError::Storage(format!("Tags serialization: {e}")))?;
+        let created_at = entry.created_at.to_rfc3339();
+        let last_accessed = entry.last_accessed.to_rfc3339();
 
-                // Use the first keyword result's embedding as proxy query embedding
-                // (in practice, the agent layer provides the query embedding)
-                let query_emb = keyword_results.iter().find_map(|e| e.embedding.as_ref());
-
-                if let Some(qe) = query_emb {
-                    let vector_results =
-                        vector::vector_search(&all_entries, qe, query.limit * 2, 0.0);
-
-                    let merged = vector::reciprocal_rank_fusion(
-                        &keyword_results,
-                        &vector_results,
-                        60,
-                        query.limit,
-                    );
-
-                    Ok(merged)
-                } else {
-                    // No query embedding available — return keyword results
-                    debug!("Hybrid search: no query embedding, using keyword-only");
-                    let mut results = keyword_results;
-                    results.truncate(query.limit);
-                    Ok(results)
+        let embedding_blob: Option<Vec<u8>> =
+            entry.embedding.as_deref().map(Self::embedding_to_blob);
+
+        sqlx::query(
+            r#"
+            INSERT INTO memories
+                (id, content, tags, source, created_at, last_accessed, score, embedding, embedding_model,
+                 namespace, agent_id, user_id, channel, access_count, expires_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+            ON CONFLICT(id) DO UPDATE SET
+                content = excluded.content,
+                tags = excluded.tags,
+                source = excluded.source,
+                last_accessed = excluded.last_accessed,
+                score = excluded.score,
+                embedding = excluded.embedding,
+                embedding_model = excluded.embedding_model,
+                namespace = excluded.namespace,
+                agent_id = excluded.agent_id,
+                user_id = excluded.user_id,
+                channel = excluded.channel,
+                access_count = excluded.access_count,
+                expires_at = excluded.expires_at
+            "#,
+        )
+        .bind(&entry.id)
+        .bind(&entry.content)
+        .bind(&tags_json)
+        .bind(&entry.source)
+        .bind(&created_at)
+        .bind(&last_accessed)
+        .bind(entry.score)
+        .bind(embedding_blob.as_deref())
+        .bind(&entry.embedding_model)
+        .bind(&entry.scope.namespace)
+        .bind(&entry.scope.agent_id)
+        .bind(&entry.scope.user_id)
+        .bind(&entry.scope.channel)
+        .bind(i64::from(entry.access_count))
+        .bind(self.decay.expiry(entry).map(timestamp))
+        .execute(executor)
+        .await
+        .map_err(|e| MemoryError::Storage(format!("INSERT failed: {e}")))?;
+        Ok(())
+    }
+
+    /// Build a safe FTS5 query from user text.
+    ///
+    /// FTS5 requires special syntax. We tokenize the user input into words
+    /// and join them with implicit AND, quoting each token to prevent injection.
+    fn sanitize_fts_query(text: &str) -> String {
+        text.split_whitespace()
+            .filter(|w| !w.is_empty())
+            .map(|w| {
+                // Strip non-alphanumeric chars and quote
+                let clean: String = w
+                    .chars()
+                    .filter(|c| c.is_alphanumeric() || *c == '_')
+                    .collect();
+                if clean.is_empty() {
+                    return String::new();
                 }
-            }
+                // Use prefix matching with *
+                format!("\"{}\"*", clean)
+            })
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[async_trait]
+impl MemoryBackend for SqliteBackend {
+    fn name(&self) -> &str {
+        "sqlite"
+    }
+
+    async fn store(&self, mut entry: MemoryEntry) -> Result<String, MemoryError> {
+        if entry.id.is_empty() {
+            entry.id = Uuid::new_v4().to_string();
         }
+        let id = entry.id.clone();
+        self.upsert(&self.pool, &entry).await?;
+        self.index.update(&id, entry.embedding.as_deref());
+
+        debug!("Stored memory {id}");
+        Ok(id)
+    }
+
+    async fn search(&self, query: MemoryQuery) -> Result<Vec<MemoryEntry>, MemoryError> {
+        let now = Utc::now();
+        let wide = MemoryQuery {
+            limit: decay::candidate_limit(&query, &self.decay),
+            ..query.clone()
+        };
+        let results = self.search_by_relevance(wide).await?;
+        let mut results = decay::finish(&query, &self.decay, results, now);
+        if query.is_recall() {
+            self.record_access(&mut results, now).await?;
+        }
+        Ok(results)
     }
 
     async fn delete(&self, id: &str) -> Result<bool, MemoryError> {
@@ -468,18 +1023,31 @@ impl MemoryBackend for SqliteBackend {
             .await
             .map_err(|e| MemoryError::Storage(format!("DELETE failed: {e}")))?;
 
-        Ok(result.rows_affected() > 0)
+        let deleted = result.rows_affected() > 0;
+        if deleted {
+            self.index.update(id, None);
+        }
+        Ok(deleted)
     }
 
     async fn get(&self, id: &str) -> Result<Option<MemoryEntry>, MemoryError> {
-        let row = sqlx::query("SELECT * FROM memories WHERE id = ?1")
-            .bind(id)
-            .fetch_optional(&self.pool)
-            .await
-            .map_err(|e| MemoryError::QueryFailed(format!("GET by ID: {e}")))?;
+        let now = Utc::now();
+        let row = sqlx::query(
+            "SELECT * FROM memories WHERE id = ?1 AND (expires_at IS NULL OR expires_at > ?2)",
+        )
+        .bind(id)
+        .bind(timestamp(now))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| MemoryError::QueryFailed(format!("GET by ID: {e}")))?;
 
         match row {
-            Some(ref r) => Ok(Some(Self::row_to_entry(r)?)),
+            Some(ref r) => {
+                let mut entry = Self::row_to_entry(r)?;
+                self.record_access(std::slice::from_mut(&mut entry), now)
+                    .await?;
+                Ok(Some(entry))
+            }
             None => Ok(None),
         }
     }
@@ -503,8 +1071,149 @@ impl MemoryBackend for SqliteBackend {
             .await
             .map_err(|e| MemoryError::Storage(format!("CLEAR failed: {e}")))?;
 
+        self.index.write().clear();
+        self.index.mutated(1);
         Ok(())
     }
+
+    async fn compact(&self) -> Result<usize, MemoryError> {
+        let now = Utc::now();
+        let rows = sqlx::query("SELECT id, last_accessed, access_count, expires_at FROM memories")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| MemoryError::QueryFailed(format!("Compaction scan: {e}")))?;
+        let parse = |at: String| {
+            DateTime::parse_from_rfc3339(&at)
+                .ok()
+                .map(|at| at.with_timezone(&Utc))
+        };
+        let stale: Vec<String> = rows
+            .iter()
+            .filter_map(|row| {
+                let id: String = row.try_get("id").ok()?;
+                let last_accessed = parse(row.try_get("last_accessed").ok()?)?;
+                let access_count: i64 = row.try_get("access_count").unwrap_or(0);
+                let expires_at = row
+                    .try_get::<Option<String>, _>("expires_at")
+                    .ok()
+                    .flatten()
+                    .and_then(parse);
+                self.decay
+                    .is_stale(
+                        last_accessed,
+                        access_count.clamp(0, u32::MAX.into()) as u32,
+                        expires_at,
+                        now,
+                    )
+                    .then_some(id)
+            })
+            .collect();
+
+        let mut forgotten = 0;
+        for id in &stale {
+            if self.delete(id).await? {
+                forgotten += 1;
+            }
+        }
+        if forgotten > 0 {
+            info!(forgotten, "Compacted SQLite memories");
+        }
+        Ok(forgotten)
+    }
+
+    async fn update(
+        &self,
+        id: &str,
+        patch: MemoryPatch,
+    ) -> Result<Option<MemoryEntry>, MemoryError> {
+        let row = sqlx::query(
+            "SELECT * FROM memories WHERE id = ?1 AND (expires_at IS NULL OR expires_at > ?2)",
+        )
+        .bind(id)
+        .bind(timestamp(Utc::now()))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| MemoryError::QueryFailed(format!("UPDATE lookup: {e}")))?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let mut entry = Self::row_to_entry(&row)?;
+        if !patch.is_empty() {
+            patch.apply(&mut entry);
+            self.upsert(&self.pool, &entry).await?;
+            self.index.update(id, entry.embedding.as_deref());
+        }
+        Ok(Some(entry))
+    }
+
+    async fn list(
+        &self,
+        filter: &MemoryFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<MemoryEntry>, MemoryError> {
+        let sql = format!(
+            "SELECT m.* FROM memories m WHERE 1 = 1 {} \
+             ORDER BY m.created_at DESC, m.id DESC LIMIT ?1 OFFSET ?2",
+            Self::filter(filter, 3)
+        );
+        let db_query = sqlx::query(&sql).bind(limit as i64).bind(offset as i64);
+        let rows = Self::bind_filter(db_query, filter)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| MemoryError::QueryFailed(format!("LIST: {e}")))?;
+        rows.iter().map(Self::row_to_entry).collect()
+    }
+
+    async fn store_batch(&self, mut entries: Vec<MemoryEntry>) -> Result<Vec<String>, MemoryError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| MemoryError::Storage(format!("Batch transaction: {e}")))?;
+        let mut ids = Vec::with_capacity(entries.len());
+        for entry in &mut entries {
+            if entry.id.is_empty() {
+                entry.id = Uuid::new_v4().to_string();
+            }
+            self.upsert(&mut *tx, entry).await?;
+            ids.push(entry.id.clone());
+        }
+        tx.commit()
+            .await
+            .map_err(|e| MemoryError::Storage(format!("Batch commit: {e}")))?;
+
+        for entry in &entries {
+            self.index.update(&entry.id, entry.embedding.as_deref());
+        }
+        debug!(count = ids.len(), "Stored memory batch");
+        Ok(ids)
+    }
+
+    async fn delete_where(&self, filter: &MemoryFilter) -> Result<usize, MemoryError> {
+        let sql = format!(
+            "DELETE FROM memories AS m WHERE 1 = 1 {} RETURNING id",
+            Self::filter(filter, 1)
+        );
+        let rows = Self::bind_filter(sqlx::query(&sql), filter)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| MemoryError::Storage(format!("DELETE WHERE failed: {e}")))?;
+        for row in &rows {
+            let id: String = row
+                .try_get("id")
+                .map_err(|e| MemoryError::QueryFailed(format!("id column: {e}")))?;
+            self.index.update(&id, None);
+        }
+        Ok(rows.len())
+    }
+}
+
+/// Fixed-width RFC 3339 UTC timestamp, so stored expiries compare
+/// correctly as text.
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Micros, true)
 }
 
 #[cfg(test)]
@@ -524,8 +1233,12 @@ mod tests {
             source: None,
             created_at: Utc::now(),
             last_accessed: Utc::now(),
+            access_count: 0,
+            expires_at: None,
             score: 0.0,
             embedding: None,
+            embedding_model: None,
+            scope: MemoryScope::default(),
         }
     }
 
@@ -537,8 +1250,12 @@ mod tests {
             source: Some("test".into()),
             created_at: Utc::now(),
             last_accessed: Utc::now(),
+            access_count: 0,
+            expires_at: None,
             score: 0.0,
             embedding: None,
+            embedding_model: None,
+            scope: MemoryScope::default(),
         }
     }
 
@@ -604,6 +1321,8 @@ mod tests {
                 min_score: 0.0,
                 tags: vec![],
                 mode: SearchMode::Keyword,
+                embedding: None,
+                scope: MemoryScope::default(),
             })
             .await
             .unwrap();
@@ -633,6 +1352,8 @@ mod tests {
                 min_score: 0.0,
                 tags: vec![],
                 mode: SearchMode::Keyword,
+                embedding: None,
+                scope: MemoryScope::default(),
             })
             .await
             .unwrap();
@@ -665,6 +1386,8 @@ mod tests {
                 min_score: 0.0,
                 tags: vec!["safety".into()],
                 mode: SearchMode::Keyword,
+                embedding: None,
+                scope: MemoryScope::default(),
             })
             .await
             .unwrap();
@@ -687,6 +1410,8 @@ mod tests {
                 min_score: 0.0,
                 tags: vec![],
                 mode: SearchMode::Keyword,
+                embedding: None,
+                scope: MemoryScope::default(),
             })
             .await
             .unwrap();
@@ -694,6 +1419,33 @@ mod tests {
         assert_eq!(results.len(), 2);
     }
 
+    #[tokio::test]
+    async fn empty_search_respects_tags() {
+        let db = test_backend().await;
+        db.store(make_tagged_entry("Rust notes", vec!["rust", "notes"]))
+            .await
+            .unwrap();
+        db.store(make_tagged_entry("Python notes", vec!["python"]))
+            .await
+            .unwrap();
+
+        let results = db
+            .search(MemoryQuery {
+                text: "".into(),
+                limit: 10,
+                min_score: 0.0,
+                tags: vec!["python".into()],
+                mode: SearchMode::Keyword,
+                embedding: None,
+                scope: MemoryScope::default(),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content, "Python notes");
+    }
+
     #[tokio::test]
     async fn delete_entry() {
         let db = test_backend().await;
@@ -742,6 +1494,8 @@ mod tests {
                 min_score: 0.0,
                 tags: vec![],
                 mode: SearchMode::Keyword,
+                embedding: None,
+                scope: MemoryScope::default(),
             })
             .await
             .unwrap();
@@ -756,6 +1510,8 @@ mod tests {
                 min_score: 0.0,
                 tags: vec![],
                 mode: SearchMode::Keyword,
+                embedding: None,
+                scope: MemoryScope::default(),
             })
             .await
             .unwrap();
@@ -782,6 +1538,12 @@ mod tests {
         assert_eq!(db.count().await.unwrap(), 0);
     }
 
+    #[tokio::test]
+    async fn conformance() {
+        crate::conformance::run(&test_backend().await).await;
+        crate::conformance::run(&test_backend().await.with_index_threshold(0)).await;
+    }
+
     #[tokio::test]
     async fn backend_name() {
         let db = test_backend().await;
@@ -825,6 +1587,8 @@ mod tests {
                 min_score: 0.0,
                 tags: vec![],
                 mode: SearchMode::Keyword,
+                embedding: None,
+                scope: MemoryScope::default(),
             })
             .await
             .unwrap();
@@ -868,6 +1632,8 @@ mod tests {
                 min_score: 0.0,
                 tags: vec![],
                 mode: SearchMode::Vector,
+                embedding: None,
+                scope: MemoryScope::default(),
             })
             .await
             .unwrap();
@@ -893,6 +1659,8 @@ mod tests {
                 min_score: 0.0,
                 tags: vec![],
                 mode: SearchMode::Hybrid,
+                embedding: None,
+                scope: MemoryScope::default(),
             })
             .await
             .unwrap();
@@ -926,6 +1694,8 @@ mod tests {
                 min_score: 0.0,
                 tags: vec![],
                 mode: SearchMode::Hybrid,
+                embedding: None,
+                scope: MemoryScope::default(),
             })
             .await
             .unwrap();
@@ -936,4 +1706,348 @@ mod tests {
         assert!(ids.iter().any(|c| c.contains("Machine learning")));
         assert!(ids.iter().any(|c| c.contains("Deep learning")));
     }
+
+    #[tokio::test]
+    async fn vector_search_uses_query_embedding() {
+        let db = test_backend().await;
+        let mut ml = make_entry("Gradient descent notes");
+        ml.embedding = Some(vec![1.0, 0.0]);
+        ml.embedding_model = Some("embed-v1".into());
+        let ml_id = db.store(ml).await.unwrap();
+        let mut food = make_entry("Pasta recipes");
+        food.embedding = Some(vec![0.0, 1.0]);
+        db.store(food).await.unwrap();
+
+        let results = db
+            .search(MemoryQuery {
+                text: "optimisation".into(),
+                limit: 1,
+                min_score: 0.0,
+                tags: vec![],
+                mode: SearchMode::Vector,
+                embedding: Some(vec![0.9, 0.1]),
+                scope: MemoryScope::default(),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, ml_id);
+        assert_eq!(results[0].embedding_model.as_deref(), Some("embed-v1"));
+    }
+
+    fn embedded(content: &str, embedding: Vec<f32>) -> MemoryEntry {
+        let mut entry = make_entry(content);
+        entry.embedding = Some(embedding);
+        entry
+    }
+
+    fn vector_query(embedding: Vec<f32>, limit: usize) -> MemoryQuery {
+        MemoryQuery {
+            text: "unused".into(),
+            limit,
+            min_score: 0.0,
+            tags: vec![],
+            mode: SearchMode::Vector,
+            embedding: Some(embedding),
+            scope: MemoryScope::default(),
+        }
+    }
+
+    fn owned_by(content: &str, user: &str, embedding: Vec<f32>) -> MemoryEntry {
+        let mut entry = embedded(content, embedding);
+        entry.scope.user_id = Some(user.into());
+        entry
+    }
+
+    #[tokio::test]
+    async fn search_is_limited_to_scope() {
+        let db = test_backend().await.with_index_threshold(0);
+        let alice = db
+            .store(owned_by("alice drinks tea", "alice", vec![1.0, 0.0]))
+            .await
+            .unwrap();
+        db.store(owned_by("bob drinks tea", "bob", vec![1.0, 0.1]))
+            .await
+            .unwrap();
+        let shared = db
+            .store(embedded("everyone drinks tea", vec![0.9, 0.2]))
+            .await
+            .unwrap();
+        let scope = MemoryScope {
+            user_id: Some("alice".into()),
+            ..MemoryScope::default()
+        };
+
+        for text in ["tea", ""] {
+            let mut ids: Vec<String> = db
+                .search(MemoryQuery {
+                    text: text.into(),
+                    limit: 10,
+                    min_score: 0.0,
+                    tags: vec![],
+                    mode: SearchMode::Keyword,
+                    embedding: None,
+                    scope: scope.clone(),
+                })
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|e| e.id)
+                .collect();
+            ids.sort();
+            let mut expected = vec![alice.clone(), shared.clone()];
+            expected.sort();
+            assert_eq!(ids, expected, "keyword search for {text:?}");
+        }
+
+        let mut query = vector_query(vec![1.0, 0.1], 2);
+        query.scope = scope;
+        let results = db.search(query).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].id, alice);
+        assert_eq!(results[0].scope.user_id.as_deref(), Some("alice"));
+        assert_eq!(results[1].id, shared);
+    }
+
+    #[tokio::test]
+    async fn migration_adds_scope_columns_and_backfills_agents() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}", dir.path().join("old.sqlite").display());
+        let pool = SqlitePool::connect_with(
+            url.parse::<SqliteConnectOptions>()
+                .unwrap()
+                .create_if_missing(true),
+        )
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE TABLE memories (
+                iid INTEGER PRIMARY KEY AUTOINCREMENT, id TEXT UNIQUE NOT NULL,
+                content TEXT NOT NULL, tags TEXT NOT NULL DEFAULT '[]', source TEXT,
+                created_at TEXT NOT NULL, last_accessed TEXT NOT NULL,
+                score REAL NOT NULL DEFAULT 0.0, embedding BLOB)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let now = Utc::now().to_rfc3339();
+        for (id, tags, source) in [
+            ("owned", r#"["agent:agent1"]"#, "agent1"),
+            ("imported", "[]", "import"),
+        ] {
+            sqlx::query(
+                "INSERT INTO memories (id, content, tags, source, created_at, last_accessed)
+                 VALUES (?1, 'old memory', ?2, ?3, ?4, ?4)",
+            )
+            .bind(id)
+            .bind(tags)
+            .bind(source)
+            .bind(&now)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        pool.close().await;
+
+        let db = SqliteBackend::new(&url).await.unwrap();
+        let owned = db.get("owned").await.unwrap().unwrap();
+        assert_eq!(owned.scope.agent_id.as_deref(), Some("agent1"));
+        assert_eq!(owned.access_count, 1, "access columns are added too");
+        let imported = db.get("imported").await.unwrap().unwrap();
+        assert!(imported.scope.is_empty());
+    }
+
+    #[tokio::test]
+    async fn recalls_and_gets_record_access() {
+        let db = test_backend().await;
+        let id = db.store(make_entry("Rust ownership rules")).await.unwrap();
+        let mut query = MemoryQuery {
+            text: "ownership".into(),
+            limit: 5,
+            min_score: 0.0,
+            tags: vec![],
+            mode: SearchMode::Keyword,
+            embedding: None,
+            scope: MemoryScope::default(),
+        };
+
+        let results = db.search(query.clone()).await.unwrap();
+        assert_eq!(results[0].access_count, 1);
+        assert_eq!(db.get(&id).await.unwrap().unwrap().access_count, 2);
+
+        // Listings are not recalls.
+        query.text.clear();
+        db.search(query).await.unwrap();
+        assert_eq!(db.get(&id).await.unwrap().unwrap().access_count, 3);
+    }
+
+    #[tokio::test]
+    async fn expired_entries_are_hidden_and_compacted() {
+        let db = test_backend().await.with_decay(DecayPolicy {
+            half_life_hours: 24.0,
+            forget_below: 0.1,
+            ..DecayPolicy::default()
+        });
+        let mut expired = make_entry("expired note");
+        expired.expires_at = Some(Utc::now() - chrono::Duration::minutes(1));
+        let expired = db.store(expired).await.unwrap();
+        let mut forgotten = make_entry("forgotten note");
+        forgotten.last_accessed = Utc::now() - chrono::Duration::days(30);
+        db.store(forgotten).await.unwrap();
+        let kept = db.store(make_entry("kept note")).await.unwrap();
+
+        assert!(db.get(&expired).await.unwrap().is_none());
+        let results = db
+            .search(MemoryQuery {
+                text: "note".into(),
+                limit: 1,
+                min_score: 0.0,
+                tags: vec![],
+                mode: SearchMode::Keyword,
+                embedding: None,
+                scope: MemoryScope::default(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, kept, "recent entries rank first");
+
+        assert_eq!(db.compact().await.unwrap(), 2);
+        assert_eq!(db.count().await.unwrap(), 1);
+        assert!(db.get(&kept).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn ttl_policy_sets_expiry_on_store() {
+        let db = test_backend().await.with_decay(DecayPolicy {
+            ttl_hours: Some(2.0),
+            ..DecayPolicy::default()
+        });
+        let id = db.store(make_entry("short lived")).await.unwrap();
+        let entry = db.get(&id).await.unwrap().unwrap();
+        let ttl = entry.expires_at.unwrap() - entry.created_at;
+        assert!((ttl - chrono::Duration::hours(2)).num_seconds().abs() <= 1);
+    }
+
+    #[tokio::test]
+    async fn hnsw_index_tracks_stores_and_deletes() {
+        let db = test_backend().await.with_index_threshold(0);
+        let near = db.store(embedded("near", vec![1.0, 0.1])).await.unwrap();
+        let far = db.store(embedded("far", vec![0.0, 1.0])).await.unwrap();
+
+        let results = db.search(vector_query(vec![1.0, 0.0], 1)).await.unwrap();
+        assert_eq!(results[0].id, near);
+        assert!(results[0].score > 0.99);
+
+        db.delete(&near).await.unwrap();
+        let results = db.search(vector_query(vec![1.0, 0.0], 5)).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, far);
+
+        // Re-storing without an embedding drops it from the index.
+        let mut plain = make_entry("far");
+        plain.id = far;
+        db.store(plain).await.unwrap();
+        assert!(
+            db.search(vector_query(vec![1.0, 0.0], 5))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn hnsw_index_persists_and_reconciles_on_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("memory.sqlite");
+        let url = format!("sqlite://{}", db_path.display());
+
+        let db = SqliteBackend::new(&url).await.unwrap();
+        let kept = db.store(embedded("kept", vec![1.0, 0.0])).await.unwrap();
+        db.save_index().await.unwrap();
+        assert!(dir.path().join("memory.sqlite.hnsw").exists());
+
+        // Written after the last save: picked up by reconciliation.
+        let added = db.store(embedded("added", vec![0.0, 1.0])).await.unwrap();
+        let mut moved = embedded("kept", vec![-1.0, 0.0]);
+        moved.id = kept.clone();
+        db.store(moved).await.unwrap();
+        db.index.unsaved.store(0, Ordering::Relaxed); // simulate a crash
+        drop(db);
+
+        let db = SqliteBackend::new(&url)
+            .await
+            .unwrap()
+            .with_index_threshold(0);
+        assert_eq!(db.index.read().len(), 2);
+        assert!(db.index.read().holds(&kept, &[-1.0, 0.0]));
+        let results = db.search(vector_query(vec![0.0, 1.0], 1)).await.unwrap();
+        assert_eq!(results[0].id, added);
+        let results = db.search(vector_query(vec![-1.0, 0.0], 1)).await.unwrap();
+        assert_eq!(results[0].id, kept);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn periodic_index_saves_happen_in_the_background() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}", dir.path().join("memory.sqlite").display());
+        let index_file = dir.path().join("memory.sqlite.hnsw");
+
+        let db = SqliteBackend::new(&url).await.unwrap();
+        for i in 0..INDEX_SAVE_EVERY {
+            db.store(embedded(&format!("entry {i}"), vec![1.0, i as f32]))
+                .await
+                .unwrap();
+        }
+        for _ in 0..100 {
+            if index_file.exists() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(
+            HnswIndex::load(&index_file).unwrap().len(),
+            INDEX_SAVE_EVERY
+        );
+        assert!(!dir.path().join("memory.sqlite.hnsw.tmp").exists());
+    }
+
+    #[test]
+    fn older_index_snapshots_never_replace_newer_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = VectorIndex::new(Some(dir.path().join("memory.hnsw")));
+        let older = index.snapshot().unwrap();
+        index.update("a", Some(&[1.0, 0.0]));
+        let newer = index.snapshot().unwrap();
+
+        newer.write().unwrap();
+        older.write().unwrap();
+        assert_eq!(
+            HnswIndex::load(&dir.path().join("memory.hnsw"))
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn new_embedding_dimension_restarts_index() {
+        let db = test_backend().await.with_index_threshold(0);
+        db.store(embedded("old model", vec![1.0, 0.0]))
+            .await
+            .unwrap();
+        let new = db
+            .store(embedded("new model", vec![0.0, 0.0, 1.0]))
+            .await
+            .unwrap();
+
+        assert_eq!(db.index.read().dimensions(), Some(3));
+        let results = db
+            .search(vector_query(vec![0.0, 0.0, 1.0], 5))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, new);
+    }
 }
@@ -3,8 +3,10 @@
 //! Pure-Rust implementations of:
 //! - Cosine similarity
 //! - Reciprocal Rank Fusion (RRF) for merging ranked result lists
+//! - [`rank_by_mode`], the vector/hybrid step shared by backends that rank
+//!   in process, and [`list_page`], their listing
 
-use rustedclaw_core::memory::MemoryEntry;
+use rustedclaw_core::memory::{MemoryEntry, MemoryFilter, MemoryQuery, SearchMode};
 
 /// Compute cosine similarity between two vectors.
 ///
@@ -79,40 +81,38 @@ pub fn reciprocal_rank_fusion(
     k: u32,
     limit: usize,
 ) -> Vec<MemoryEntry> {
+    fuse_rankings(&[keyword_results, vector_results], k, limit)
+}
+
+/// Reciprocal Rank Fusion over any number of ranked lists.
+///
+/// Ties keep the order in which entries were first seen, so fusing the
+/// same lists always gives the same ranking.
+pub fn fuse_rankings(lists: &[&[MemoryEntry]], k: u32, limit: usize) -> Vec<MemoryEntry> {
     use std::collections::HashMap;
 
     let k = k as f32;
 
-    // Map: id → (rrf_score, best_entry)
-    let mut scores: HashMap<String, (f32, MemoryEntry)> = HashMap::new();
-
-    // Score keyword results
-    for (rank, entry) in keyword_results.iter().enumerate() {
-        let rrf_score = 1.0 / (k + rank as f32 + 1.0);
-        scores
-            .entry(entry.id.clone())
-            .and_modify(|(score, _)| *score += rrf_score)
-            .or_insert_with(|| (rrf_score, entry.clone()));
-    }
-
-    // Score vector results
-    for (rank, entry) in vector_results.iter().enumerate() {
-        let rrf_score = 1.0 / (k + rank as f32 + 1.0);
-        scores
-            .entry(entry.id.clone())
-            .and_modify(|(score, _)| *score += rrf_score)
-            .or_insert_with(|| (rrf_score, entry.clone()));
+    // id → position in `results`
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut results: Vec<MemoryEntry> = Vec::new();
+
+    for list in lists {
+        for (rank, entry) in list.iter().enumerate() {
+            let rrf_score = 1.0 / (k + rank as f32 + 1.0);
+            match positions.get(&entry.id) {
+                Some(&i) => results[i].score += rrf_score,
+                None => {
+                    positions.insert(entry.id.clone(), results.len());
+                    let mut entry = entry.clone();
+                    entry.score = rrf_score;
+                    results.push(entry);
+                }
+            }
+        }
     }
 
-    // Collect, sort by RRF score descending
-    let mut results: Vec<MemoryEntry> = scores
-        .into_values()
-        .map(|(score, mut entry)| {
-            entry.score = score;
-            entry
-        })
-        .collect();
-
+    // Sort by RRF score descending
     results.sort_by(|a, b| {
         b.score
             .partial_cmp(&a.score)
@@ -122,10 +122,76 @@ pub fn reciprocal_rank_fusion(
     results
 }
 
+/// Standard RRF constant used when fusing keyword and vector results.
+pub const RRF_K: u32 = 60;
+
+/// Apply the query's search mode on top of keyword results.
+///
+/// `candidates` are the entries eligible for vector ranking (already tag
+/// filtered). Keyword mode, or a query without an embedding, returns
+/// `keyword` unchanged; vector mode ranks `candidates` by cosine
+/// similarity; hybrid mode fuses both lists with RRF.
+pub fn rank_by_mode(
+    query: &MemoryQuery,
+    candidates: &[MemoryEntry],
+    keyword: Vec<MemoryEntry>,
+) -> Vec<MemoryEntry> {
+    let Some(query_embedding) = query.embedding.as_deref() else {
+        return keyword;
+    };
+    match query.mode {
+        SearchMode::Keyword => keyword,
+        SearchMode::Vector => {
+            vector_search(candidates, query_embedding, query.limit, query.min_score)
+        }
+        SearchMode::Hybrid => {
+            let vector = vector_search(candidates, query_embedding, query.limit * 2, 0.0);
+            reciprocal_rank_fusion(&keyword, &vector, RRF_K, query.limit)
+        }
+    }
+}
+
+/// Whether `entry` passes the query's tag filter (any tag matches).
+pub fn matches_tags(entry: &MemoryEntry, tags: &[String]) -> bool {
+    tags.is_empty() || tags.iter().any(|t| entry.tags.contains(t))
+}
+
+/// Whether `entry` passes the query's tag filter, is visible to its scope
+/// and has not expired.
+pub fn matches_filters(entry: &MemoryEntry, query: &MemoryQuery) -> bool {
+    matches_tags(entry, &query.tags)
+        && query.scope.allows(&entry.scope)
+        && !entry.is_expired(chrono::Utc::now())
+}
+
+/// One page of the `entries` matching `filter`, newest first.
+pub fn list_page(
+    entries: &[MemoryEntry],
+    filter: &MemoryFilter,
+    offset: usize,
+    limit: usize,
+) -> Vec<MemoryEntry> {
+    let now = chrono::Utc::now();
+    let mut matching: Vec<&MemoryEntry> =
+        entries.iter().filter(|e| filter.matches(e, now)).collect();
+    matching.sort_by(|a, b| {
+        b.created_at
+            .cmp(&a.created_at)
+            .then_with(|| b.id.cmp(&a.id))
+    });
+    matching
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .cloned()
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use chrono::Utc;
+    use rustedclaw_core::memory::MemoryScope;
 
     fn entry(id: &str, embedding: Option<Vec<f32>>) -> MemoryEntry {
         MemoryEntry {
@@ -135,8 +201,12 @@ mod tests {
             source: None,
             created_at: Utc::now(),
             last_accessed: Utc::now(),
+            access_count: 0,
+            expires_at: None,
             score: 0.0,
             embedding,
+            embedding_model: None,
+            scope: MemoryScope::default(),
         }
     }
 
@@ -287,6 +357,19 @@ mod tests {
         assert_eq!(results.len(), 5);
     }
 
+    #[test]
+    fn fuse_rankings_sums_many_lists_and_keeps_ties_stable() {
+        let first = vec![entry("a", None), entry("b", None)];
+        let second = vec![entry("c", None), entry("b", None)];
+        let third = vec![entry("d", None)];
+
+        let results = fuse_rankings(&[&first, &second, &third], 60, 10);
+        let ids: Vec<&str> = results.iter().map(|e| e.id.as_str()).collect();
+        // "b" is in two lists; "a", "c" and "d" tie at rank 0 in one list each.
+        assert_eq!(ids, ["b", "a", "c", "d"]);
+        assert!((results[0].score - 2.0 / 62.0).abs() < 1e-6);
+    }
+
     #[test]
     fn rrf_empty_lists() {
         let results = reciprocal_rank_fusion(&[], &[], 60, 10);
@@ -457,15 +457,13 @@ impl rustedclaw_core::Provider for AnthropicProvider {
                                     _ => {}
                                 }
                             }
-                            "content_block_stop" => {
-                                if in_tool_use {
-                                    tool_calls.push(MessageToolCall {
-                                        id: std::mem::take(&mut current_tool_id),
-                                        name: std::mem::take(&mut current_tool_name),
-                                        arguments: std::mem::take(&mut tool_args_buffer),
-                                    });
-                                    in_tool_use = false;
-                                }
+                            "content_block_stop" if in_tool_use => {
+                                tool_calls.push(MessageToolCall {
+                                    id: std::mem::take(&mut current_tool_id),
+                                    name: std::mem::take(&mut current_tool_name),
+                                    arguments: std::mem::take(&mut tool_args_buffer),
+                                });
+                                in_tool_use = false;
                             }
                             "message_delta" => {
                                 // May contain usage
@@ -171,6 +171,19 @@ impl rustedclaw_core::Provider for FallbackProvider {
         }
         Ok(false)
     }
+
+    /// The primary provider's tokenizer.
+    fn token_counter(&self) -> Option<Arc<dyn rustedclaw_core::tokens::TokenCounter>> {
+        self.chain.first()?.provider.token_counter()
+    }
+
+    /// The primary provider's view of the model.
+    fn capabilities(&self, model: &str) -> rustedclaw_core::ModelCapabilities {
+        match self.chain.first() {
+            Some(entry) => entry.provider.capabilities(model),
+            None => rustedclaw_core::ModelCapabilities::for_model(model),
+        }
+    }
 }
 
 #[cfg(test)]
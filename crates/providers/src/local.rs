@@ -26,8 +26,9 @@ use hf_hub::api::sync::Api;
 use rustedclaw_core::error::ProviderError;
 use rustedclaw_core::message::{Message, Role};
 use rustedclaw_core::provider::{ProviderRequest, ProviderResponse, StreamChunk, Usage};
+use rustedclaw_core::tokens::{HeuristicCounter, TokenCounter};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use tokenizers::Tokenizer;
 use tokio::sync::Mutex;
 use tracing::{debug, info, warn};
@@ -114,6 +115,8 @@ fn resolve_preset(alias: &str) -> Option<ModelPreset> {
 pub struct LocalProvider {
     inner: Arc<Mutex<Option<LocalModelState>>>,
     model_name: String,
+    /// The model's tokenizer, shared with [`TokenizerCounter`] once loaded.
+    tokenizer: Arc<OnceLock<Tokenizer>>,
 }
 
 /// The loaded model state (tokenizer + weights + config).
@@ -137,15 +140,18 @@ impl LocalProvider {
         Self {
             inner: Arc::new(Mutex::new(None)),
             model_name: model_name.to_string(),
+            tokenizer: Arc::new(OnceLock::new()),
         }
     }
 
     /// Eagerly load the model (downloads if needed, then loads into memory).
     pub fn load(model_name: &str) -> Result<Self, ProviderError> {
         let state = LocalModelState::load(model_name)?;
+        let tokenizer = Arc::new(OnceLock::from(state.tokenizer.clone()));
         Ok(Self {
             inner: Arc::new(Mutex::new(Some(state))),
             model_name: model_name.to_string(),
+            tokenizer,
         })
     }
 
@@ -544,6 +550,32 @@ fn map_candle_err(e: candle_core::Error) -> ProviderError {
     }
 }
 
+// ── Token counting ─────────────────────────────────────────────────────
+
+/// Counts tokens with the local model's own tokenizer.
+///
+/// Until the model has been loaded (it loads lazily on the first request)
+/// counts fall back to the heuristic.
+pub struct TokenizerCounter {
+    tokenizer: Arc<OnceLock<Tokenizer>>,
+}
+
+impl TokenCounter for TokenizerCounter {
+    fn name(&self) -> &str {
+        "tokenizers"
+    }
+
+    fn count(&self, text: &str) -> usize {
+        match self.tokenizer.get() {
+            Some(tokenizer) => tokenizer
+                .encode(text, false)
+                .map(|encoding| encoding.len())
+                .unwrap_or_else(|_| HeuristicCounter.count(text)),
+            None => HeuristicCounter.count(text),
+        }
+    }
+}
+
 // ── Provider trait implementation ──────────────────────────────────────
 
 #[async_trait]
@@ -573,6 +605,7 @@ impl rustedclaw_core::provider::Provider for LocalProvider {
                             message: format!("Model loading task failed: {e}"),
                         })??;
 
+                let _ = self.tokenizer.set(loaded.tokenizer.clone());
                 let mut state = self.inner.lock().await;
                 *state = Some(loaded);
             }
@@ -667,6 +700,12 @@ impl rustedclaw_core::provider::Provider for LocalProvider {
         // Local provider is always available (no network needed)
         Ok(true)
     }
+
+    fn token_counter(&self) -> Option<Arc<dyn TokenCounter>> {
+        Some(Arc::new(TokenizerCounter {
+            tokenizer: self.tokenizer.clone(),
+        }))
+    }
 }
 
 #[cfg(test)]
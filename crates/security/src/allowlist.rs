@@ -4,6 +4,7 @@
 //! and for HTTP tools (which endpoints can be accessed).
 
 use rustedclaw_config::ChannelConfig;
+use std::net::{IpAddr, Ipv4Addr};
 
 /// Result of checking a sender against the allowlist.
 #[derive(Debug, Clone, PartialEq)]
@@ -62,19 +63,12 @@ impl AllowlistPolicy {
     /// Check if a URL is allowed by the endpoint allowlist.
     ///
     /// Rules:
+    /// - URLs targeting private/internal addresses are always denied (SSRF)
     /// - If `allowed_endpoints` is empty → allow all (open by default for HTTP tool)
     /// - If `allowed_endpoints` contains `"*"` → allow all
     /// - Otherwise, URL must start with one of the allowed prefixes
     pub fn check_endpoint(url: &str, allowed_endpoints: &[String]) -> SenderCheckResult {
-        if allowed_endpoints.is_empty() {
-            return SenderCheckResult::Allowed;
-        }
-
-        if allowed_endpoints.iter().any(|e| e == "*") {
-            return SenderCheckResult::Allowed;
-        }
-
-        // Block private/internal IPs (SSRF prevention)
+        // Block private/internal IPs (SSRF prevention), even with a wildcard
         if is_private_url(url) {
             return SenderCheckResult::Denied {
                 sender_id: url.into(),
@@ -82,6 +76,10 @@ impl AllowlistPolicy {
             };
         }
 
+        if allowed_endpoints.is_empty() || allowed_endpoints.iter().any(|e| e == "*") {
+            return SenderCheckResult::Allowed;
+        }
+
         if allowed_endpoints.iter().any(|e| url.starts_with(e)) {
             SenderCheckResult::Allowed
         } else {
@@ -95,50 +93,114 @@ impl AllowlistPolicy {
             }
         }
     }
+
+    /// Check if a host name is allowed by a host allowlist.
+    ///
+    /// Rules:
+    /// - If `allowed_hosts` is empty → allow all
+    /// - `"*"` allows all, `"*.example.com"` allows any subdomain of
+    ///   `example.com` (but not `example.com` itself)
+    /// - Otherwise, the host must match an entry exactly (case-insensitive)
+    pub fn check_host(host: &str, allowed_hosts: &[String]) -> SenderCheckResult {
+        let host = host.trim_end_matches('.').to_lowercase();
+        let matches = |pattern: &String| {
+            let pattern = pattern.to_lowercase();
+            match pattern.strip_prefix("*.") {
+                Some(suffix) => host
+                    .strip_suffix(suffix)
+                    .is_some_and(|sub| sub.ends_with('.')),
+                None => pattern == "*" || pattern == host,
+            }
+        };
+
+        if allowed_hosts.is_empty() || allowed_hosts.iter().any(matches) {
+            SenderCheckResult::Allowed
+        } else {
+            SenderCheckResult::Denied {
+                sender_id: host,
+                reason: format!(
+                    "Host not in allowed hosts ({} configured)",
+                    allowed_hosts.len()
+                ),
+            }
+        }
+    }
 }
 
-/// Check if a URL targets a private/internal IP address.
+/// Check if an IP address is private, internal or otherwise not publicly
+/// routable.
 ///
-/// Blocks:
-/// - localhost / 127.x.x.x (loopback)
-/// - 10.x.x.x, 172.16-31.x.x, 192.168.x.x (RFC 1918 private)
-/// - 169.254.x.x (link-local, including cloud metadata at 169.254.169.254)
-/// - fc00::/7, fe80::/10, ::1 (IPv6 private/link-local/loopback)
-/// - 0.0.0.0 (wildcard)
-fn is_private_url(url: &str) -> bool {
-    let lower = url.to_lowercase();
-
-    // Extract host from URL
-    let host = lower
-        .strip_prefix("http://")
-        .or_else(|| lower.strip_prefix("https://"))
-        .unwrap_or(&lower);
+/// Covers loopback, RFC 1918, link-local (including cloud metadata at
+/// 169.254.169.254), carrier-grade NAT (100.64.0.0/10), unspecified,
+/// broadcast, multicast and documentation ranges, IPv6 unique-local
+/// (fc00::/7) and link-local (fe80::/10), and IPv4 addresses embedded in
+/// IPv6 (`::ffff:127.0.0.1`).
+pub fn is_private_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_private_ipv4(v4),
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_private_ipv4(&v4);
+            }
+            let first = v6.segments()[0];
+            v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || (first & 0xfe00) == 0xfc00 // unique local
+                || (first & 0xffc0) == 0xfe80 // link-local
+                // IPv4-compatible (::a.b.c.d), deprecated but still routable
+                || v6.segments()[..6].iter().all(|&s| s == 0)
+        }
+    }
+}
 
-    let host = host.split('/').next().unwrap_or(host);
-    let host = host.split(':').next().unwrap_or(host);
+fn is_private_ipv4(ip: &Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || a == 0
+        || (a == 100 && (64..128).contains(&b)) // carrier-grade NAT
+}
 
-    // Check for private IP ranges and localhost
+/// Check if a host name or IP literal refers to a private/internal target.
+///
+/// Host names only match well-known internal names (`localhost`,
+/// `*.local`, `*.internal`); names that *resolve* to private addresses
+/// must be checked after resolution with [`is_private_ip`].
+pub fn is_private_host(host: &str) -> bool {
+    let host = host.trim_end_matches('.').to_lowercase();
+    let literal = host.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = literal.parse::<IpAddr>() {
+        return is_private_ip(&ip);
+    }
     host == "localhost"
-        || host.starts_with("127.")              // Full 127.0.0.0/8 loopback
-        || host.starts_with("10.")               // 10.0.0.0/8
-        || host.starts_with("192.168.")           // 192.168.0.0/16
-        || host.starts_with("172.16.")
-        || host.starts_with("172.17.")
-        || host.starts_with("172.18.")
-        || host.starts_with("172.19.")
-        || host.starts_with("172.2")              // 172.20-29
-        || host.starts_with("172.30.")
-        || host.starts_with("172.31.")
-        || host.starts_with("169.254.")           // Full 169.254.0.0/16 link-local
-        || host == "[::1]"                        // IPv6 loopback
-        || host.starts_with("[fe80:")             // IPv6 link-local
-        || host.starts_with("[fc")                // IPv6 ULA (fc00::/7)
-        || host.starts_with("[fd")                // IPv6 ULA (fc00::/7)
-        || host == "0.0.0.0"
-        || host == "[::]"
-        || host.ends_with(".internal")            // Common internal DNS suffixes
-        || host.ends_with(".local")
         || host.ends_with(".localhost")
+        || host.ends_with(".local")
+        || host.ends_with(".internal")
+}
+
+/// Check if a URL targets a private/internal address.
+fn is_private_url(url: &str) -> bool {
+    is_private_host(url_host(url))
+}
+
+/// Extract the host of a URL: the authority without userinfo or port.
+fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    if host.starts_with('[') {
+        // IPv6 literal: keep the brackets, drop any port after them
+        return host.find(']').map_or(host, |end| &host[..=end]);
+    }
+    host.split(':').next().unwrap_or(host)
 }
 
 #[cfg(test)]
@@ -247,15 +309,16 @@ mod tests {
 
     #[test]
     fn ssrf_localhost_blocked() {
-        let _allowed: Vec<String> = vec!["*".into()]; // Even with wildcard
-        // But with endpoint list, private IPs should be blocked
         let specific = vec!["https://api.example.com".into()];
-        let result = AllowlistPolicy::check_endpoint("http://127.0.0.1:8080/admin", &specific);
-        match result {
-            SenderCheckResult::Denied { reason, .. } => {
-                assert!(reason.contains("SSRF"));
+        let wildcard = vec!["*".into()];
+        for allowed in [&specific, &wildcard, &vec![]] {
+            let result = AllowlistPolicy::check_endpoint("http://127.0.0.1:8080/admin", allowed);
+            match result {
+                SenderCheckResult::Denied { reason, .. } => {
+                    assert!(reason.contains("SSRF"));
+                }
+                _ => panic!("Expected SSRF block"),
             }
-            _ => panic!("Expected SSRF block"),
         }
     }
 
@@ -279,5 +342,70 @@ mod tests {
         assert!(is_private_url("http://169.254.169.254/meta"));
         assert!(!is_private_url("https://api.example.com/v1"));
         assert!(!is_private_url("https://google.com"));
+        assert!(!is_private_url("http://172.217.0.1/"));
+        assert!(is_private_url("http://172.20.0.1/"));
+        assert!(is_private_url("http://user@127.0.0.1/"));
+        assert!(is_private_url("http://[::ffff:127.0.0.1]:80/"));
+        assert!(is_private_url("http://100.100.100.200/"));
+        assert!(!is_private_url(
+            "https://127.0.0.1.example.com@api.example.com/"
+        ));
+        assert!(is_private_url("http://metadata.google.internal/"));
+    }
+
+    #[test]
+    fn private_ip_ranges() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.31.255.255",
+            "192.168.0.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(is_private_ip(&ip.parse().unwrap()), "{ip}");
+        }
+        for ip in ["8.8.8.8", "172.32.0.1", "100.128.0.1", "2606:4700::1111"] {
+            assert!(!is_private_ip(&ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[test]
+    fn host_allowlist() {
+        let allowed = vec!["api.github.com".into(), "*.example.com".into()];
+        for host in [
+            "api.github.com",
+            "API.GitHub.com",
+            "docs.example.com",
+            "a.b.example.com",
+        ] {
+            assert_eq!(
+                AllowlistPolicy::check_host(host, &allowed),
+                SenderCheckResult::Allowed,
+                "{host}"
+            );
+        }
+        for host in [
+            "github.com",
+            "example.com",
+            "evilexample.com",
+            "example.com.evil.io",
+        ] {
+            assert!(matches!(
+                AllowlistPolicy::check_host(host, &allowed),
+                SenderCheckResult::Denied { .. }
+            ));
+        }
+        assert_eq!(
+            AllowlistPolicy::check_host("anything.io", &[]),
+            SenderCheckResult::Allowed
+        );
     }
 }
@@ -11,7 +11,7 @@ pub mod audit;
 pub mod path;
 pub mod secrets;
 
-pub use allowlist::{AllowlistPolicy, SenderCheckResult};
+pub use allowlist::{AllowlistPolicy, SenderCheckResult, is_private_host, is_private_ip};
 pub use audit::{AuditEntry, AuditEvent, AuditLogger, AuditOutcome, AuditSink, TracingSink};
-pub use path::{PathValidationError, validate_path};
+pub use path::{PathValidationError, expand_tilde, validate_path};
 pub use secrets::{EncryptedValue, SecretsManager};
@@ -138,7 +138,7 @@ pub fn validate_path(
 }
 
 /// Expand ~ to the user's home directory.
-fn expand_tilde(path: &str) -> String {
+pub fn expand_tilde(path: &str) -> String {
     if (path.starts_with("~/") || path == "~")
         && let Ok(home) = home_dir()
     {
@@ -1,20 +1,172 @@
-//! HTTP request tool — stub that returns mock HTTP responses.
+//! HTTP request tool — calls external APIs with `reqwest`.
 //!
-//! In production this would use `reqwest` to make real HTTP calls.
-//! The stub returns realistic mock responses so the agent loop and
-//! routines can be tested end-to-end without network access.
+//! Requests are guarded against SSRF: URLs naming private, loopback or
+//! link-local addresses are rejected up front, host names are resolved
+//! through a resolver that refuses private addresses (so a public name
+//! can't be re-pointed at an internal service), and every redirect hop is
+//! checked again. Hosts can be restricted further with
+//! `[tools.http_request] allowed_hosts`.
+//!
+//! Responses are read up to `max_response_bytes`. Text bodies (HTML, JSON,
+//! XML, ...) are returned up to `max_body_chars` characters; binary bodies
+//! are summarized instead of dumped into the conversation.
 
 use async_trait::async_trait;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::{Url, redirect};
+use rustedclaw_config::HttpRequestToolConfig;
 use rustedclaw_core::error::ToolError;
 use rustedclaw_core::tool::{Tool, ToolResult};
+use rustedclaw_security::{AllowlistPolicy, SenderCheckResult, is_private_ip};
 use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const TOOL_NAME: &str = "http_request";
+
+pub struct HttpRequestTool {
+    config: Arc<HttpRequestToolConfig>,
+    client: reqwest::Client,
+}
 
-pub struct HttpRequestTool;
+impl HttpRequestTool {
+    /// Create the tool with the default `[tools.http_request]` limits.
+    pub fn new() -> Self {
+        Self::from_config(&HttpRequestToolConfig::default())
+    }
+
+    /// Create the tool from `[tools.http_request]`.
+    pub fn from_config(config: &HttpRequestToolConfig) -> Self {
+        Self::with_resolver(config, GuardedResolver::new(config.allow_private_networks))
+    }
+
+    fn with_resolver(config: &HttpRequestToolConfig, resolver: GuardedResolver) -> Self {
+        let config = Arc::new(config.clone());
+        let policy = config.clone();
+        let client = reqwest::Client::builder()
+            // A proxy would resolve host names itself, bypassing the resolver
+            .no_proxy()
+            .dns_resolver(Arc::new(resolver))
+            .redirect(redirect::Policy::custom(move |attempt| {
+                if attempt.previous().len() > policy.max_redirects {
+                    attempt.error(Blocked(format!(
+                        "Too many redirects (limit {})",
+                        policy.max_redirects
+                    )))
+                } else if let Err(blocked) = check_url(&policy, attempt.url()) {
+                    attempt.error(blocked)
+                } else {
+                    attempt.follow()
+                }
+            }))
+            .build()
+            .expect("Failed to create HTTP client");
+        Self { config, client }
+    }
+}
+
+impl Default for HttpRequestTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A response whose body has been read up to `max_response_bytes`.
+pub(crate) struct Fetched {
+    pub status: reqwest::StatusCode,
+    /// URL of the final response, after redirects.
+    pub url: String,
+    pub headers: HashMap<String, String>,
+    pub bytes: Vec<u8>,
+    /// The body was longer than `max_response_bytes`.
+    pub over_limit: bool,
+    pub elapsed_ms: u64,
+}
+
+impl HttpRequestTool {
+    pub(crate) fn config(&self) -> &HttpRequestToolConfig {
+        &self.config
+    }
+
+    /// Send a request under the SSRF policy and read its body. Errors name
+    /// `tool_name`, so tools built on this one report as themselves.
+    pub(crate) async fn fetch(
+        &self,
+        tool_name: &str,
+        method: reqwest::Method,
+        url: &str,
+        headers: &HashMap<String, String>,
+        body: Option<&str>,
+        timeout_secs: u64,
+    ) -> Result<Fetched, ToolError> {
+        // Validate URL format
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(ToolError::InvalidArguments(
+                "URL must start with http:// or https://".into(),
+            ));
+        }
+        let url = Url::parse(url)
+            .map_err(|e| ToolError::InvalidArguments(format!("Invalid URL '{url}': {e}")))?;
+        check_url(&self.config, &url).map_err(|blocked| blocked.into_tool_error(tool_name))?;
+
+        let mut request = self
+            .client
+            .request(method, url)
+            .timeout(Duration::from_secs(timeout_secs));
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        if let Some(body) = body {
+            request = request.body(body.to_string());
+        }
+
+        let started = Instant::now();
+        let mut response = request
+            .send()
+            .await
+            .map_err(|e| request_error(tool_name, e, timeout_secs))?;
+
+        let status = response.status();
+        let url = response.url().to_string();
+        let headers: HashMap<String, String> = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+
+        let max_bytes = self.config.max_response_bytes;
+        let mut bytes = Vec::new();
+        let mut over_limit = false;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| request_error(tool_name, e, timeout_secs))?
+        {
+            let room = max_bytes - bytes.len();
+            if chunk.len() > room {
+                bytes.extend_from_slice(&chunk[..room]);
+                over_limit = true;
+                break;
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        Ok(Fetched {
+            status,
+            url,
+            headers,
+            bytes,
+            over_limit,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+}
 
 #[async_trait]
 impl Tool for HttpRequestTool {
     fn name(&self) -> &str {
-        "http_request"
+        TOOL_NAME
     }
 
     fn description(&self) -> &str {
@@ -47,8 +199,11 @@ impl Tool for HttpRequestTool {
                 },
                 "timeout_secs": {
                     "type": "integer",
-                    "description": "Request timeout in seconds (default 30)",
-                    "default": 30
+                    "description": format!(
+                        "Request timeout in seconds (default {}, max {})",
+                        self.config.timeout_secs, self.config.max_timeout_secs
+                    ),
+                    "default": self.config.timeout_secs
                 }
             },
             "required": ["url"]
@@ -69,24 +224,48 @@ impl Tool for HttpRequestTool {
             )));
         }
 
-        // Validate URL format
-        if !url.starts_with("http://") && !url.starts_with("https://") {
-            return Err(ToolError::InvalidArguments(
-                "URL must start with http:// or https://".into(),
-            ));
-        }
-
         let headers: HashMap<String, String> = arguments
             .get("headers")
             .and_then(|h| serde_json::from_value(h.clone()).ok())
             .unwrap_or_default();
 
-        let body = arguments["body"].as_str().map(|s| s.to_string());
-
-        let _timeout_secs = arguments["timeout_secs"].as_u64().unwrap_or(30);
+        let timeout_secs = arguments["timeout_secs"]
+            .as_u64()
+            .unwrap_or(self.config.timeout_secs)
+            .clamp(1, self.config.max_timeout_secs);
 
-        // Generate mock response based on URL patterns
-        let response = generate_mock_response(url, &method, &headers, body.as_deref());
+        let method =
+            reqwest::Method::from_bytes(method.as_bytes()).expect("method was validated above");
+        let Fetched {
+            status,
+            url,
+            headers: response_headers,
+            bytes,
+            over_limit,
+            elapsed_ms,
+        } = self
+            .fetch(
+                TOOL_NAME,
+                method,
+                url,
+                &headers,
+                arguments["body"].as_str(),
+                timeout_secs,
+            )
+            .await?;
+
+        let content_type = response_headers.get("content-type").map(String::as_str);
+        let (body, truncated) =
+            render_body(&bytes, content_type, self.config.max_body_chars, over_limit);
+        let response = HttpResponse {
+            status_code: status.as_u16(),
+            status_text: status.canonical_reason().unwrap_or_default().into(),
+            url,
+            headers: response_headers,
+            body,
+            truncated,
+            elapsed_ms,
+        };
         let output = serde_json::to_string_pretty(&response).unwrap_or_default();
 
         Ok(ToolResult {
@@ -102,256 +281,579 @@ impl Tool for HttpRequestTool {
 struct HttpResponse {
     status_code: u16,
     status_text: String,
+    /// URL of the final response, after redirects.
+    url: String,
     headers: HashMap<String, String>,
     body: String,
+    truncated: bool,
     elapsed_ms: u64,
 }
 
-fn generate_mock_response(
-    url: &str,
-    method: &str,
-    _request_headers: &HashMap<String, String>,
-    request_body: Option<&str>,
-) -> HttpResponse {
-    let mut response_headers = HashMap::new();
-    response_headers.insert("content-type".into(), "application/json".into());
-    response_headers.insert("x-mock".into(), "true".into());
-
-    // Pattern-match URL to generate context-aware responses
-    let lower_url = url.to_lowercase();
-
-    if lower_url.contains("/health") || lower_url.contains("/healthz") {
-        return HttpResponse {
-            status_code: 200,
-            status_text: "OK".into(),
-            headers: response_headers,
-            body: r#"{"status":"healthy","uptime_secs":86400}"#.into(),
-            elapsed_ms: 12,
-        };
+/// A request refused by policy: a private address, a host outside the
+/// allowlist or too many redirects.
+#[derive(Debug)]
+struct Blocked(String);
+
+impl std::fmt::Display for Blocked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
     }
+}
 
-    if lower_url.contains("/api/") || lower_url.contains("/v1/") {
-        let body = match method {
-            "GET" => {
-                r#"{"data":[{"id":1,"name":"Item 1"},{"id":2,"name":"Item 2"}],"total":2}"#.into()
-            }
-            "POST" => {
-                let id = simple_hash(request_body.unwrap_or("")) % 10000;
-                format!(r#"{{"id":{id},"created":true,"message":"Resource created successfully"}}"#)
-            }
-            "PUT" | "PATCH" => {
-                r#"{"updated":true,"message":"Resource updated successfully"}"#.into()
-            }
-            "DELETE" => r#"{"deleted":true,"message":"Resource deleted successfully"}"#.into(),
-            _ => r#"{"error":"Method not allowed"}"#.into(),
-        };
+impl std::error::Error for Blocked {}
 
-        let status_code = match method {
-            "POST" => 201,
-            "DELETE" => 204,
-            _ => 200,
-        };
+impl Blocked {
+    fn into_tool_error(self, tool_name: &str) -> ToolError {
+        ToolError::PermissionDenied {
+            tool_name: tool_name.into(),
+            reason: self.0,
+        }
+    }
+}
 
-        return HttpResponse {
-            status_code,
-            status_text: status_text_for(status_code).into(),
-            headers: response_headers,
-            body,
-            elapsed_ms: 45,
+/// Check a request or redirect target against the policy.
+fn check_url(config: &HttpRequestToolConfig, url: &Url) -> Result<(), Blocked> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(Blocked(format!("Unsupported URL scheme: {}", url.scheme())));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| Blocked(format!("URL has no host: {url}")))?;
+
+    if !config.allow_private_networks
+        // No endpoint prefixes: only the private-address check applies here
+        && let SenderCheckResult::Denied { reason, .. } =
+            AllowlistPolicy::check_endpoint(url.as_str(), &[])
+    {
+        return Err(Blocked(format!("{reason}: {host}")));
+    }
+
+    match AllowlistPolicy::check_host(host, &config.allowed_hosts) {
+        SenderCheckResult::Denied { .. } => Err(Blocked(format!(
+            "Host '{host}' is not in tools.http_request.allowed_hosts"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Map a `reqwest` failure to a tool error, surfacing policy blocks raised
+/// by the resolver or redirect policy as permission errors.
+fn request_error(tool_name: &str, error: reqwest::Error, timeout_secs: u64) -> ToolError {
+    if error.is_timeout() {
+        return ToolError::Timeout {
+            tool_name: tool_name.into(),
+            timeout_secs,
         };
     }
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(&error);
+    while let Some(err) = source {
+        if let Some(blocked) = err.downcast_ref::<Blocked>() {
+            return Blocked(blocked.0.clone()).into_tool_error(tool_name);
+        }
+        source = err.source();
+    }
 
-    if lower_url.contains("404") || lower_url.contains("notfound") {
-        return HttpResponse {
-            status_code: 404,
-            status_text: "Not Found".into(),
-            headers: response_headers,
-            body: r#"{"error":"Not found"}"#.into(),
-            elapsed_ms: 8,
+    let mut reason = error.to_string();
+    let mut source = std::error::Error::source(&error);
+    while let Some(err) = source {
+        reason.push_str(&format!(": {err}"));
+        source = err.source();
+    }
+    ToolError::ExecutionFailed {
+        tool_name: tool_name.into(),
+        reason,
+    }
+}
+
+/// Turn the bytes read into the body returned to the agent.
+///
+/// Text is cut at a character boundary with a note saying so; binary
+/// content is replaced by a one-line summary.
+pub(crate) fn render_body(
+    bytes: &[u8],
+    content_type: Option<&str>,
+    max_chars: usize,
+    over_limit: bool,
+) -> (String, bool) {
+    // A body cut at the byte limit may end inside a multi-byte character
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => Some(text),
+        Err(e) if over_limit && e.error_len().is_none() => {
+            Some(std::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default())
+        }
+        Err(_) => None,
+    };
+    let is_text = match content_type {
+        Some(content_type) => is_text_content_type(content_type),
+        None => text.is_some(),
+    };
+    if !is_text {
+        let size = if over_limit {
+            format!("over {} bytes", bytes.len())
+        } else {
+            format!("{} bytes", bytes.len())
         };
+        let kind = content_type.unwrap_or("unknown type");
+        return (
+            format!("[binary content omitted: {size} of {kind}]"),
+            over_limit,
+        );
     }
 
-    // Default: return a 200 with an HTML-like response
-    response_headers.insert("content-type".into(), "text/html".into());
-    HttpResponse {
-        status_code: 200,
-        status_text: "OK".into(),
-        headers: response_headers,
-        body: format!(
-            "<html><body><h1>Mock Response</h1><p>Fetched {} {}</p></body></html>",
-            method, url
+    let text = match text {
+        Some(text) => text.into(),
+        None => String::from_utf8_lossy(bytes),
+    };
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => (
+            format!(
+                "{}\n[truncated: showing the first {max_chars} characters]",
+                &text[..end]
+            ),
+            true,
+        ),
+        None if over_limit => (
+            format!(
+                "{text}\n[truncated: response larger than {} bytes]",
+                bytes.len()
+            ),
+            true,
         ),
-        elapsed_ms: 30,
+        None => (text.into_owned(), false),
     }
 }
 
-fn status_text_for(code: u16) -> &'static str {
-    match code {
-        200 => "OK",
-        201 => "Created",
-        204 => "No Content",
-        400 => "Bad Request",
-        404 => "Not Found",
-        500 => "Internal Server Error",
-        _ => "Unknown",
+pub(crate) fn is_text_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    mime.starts_with("text/")
+        || mime.ends_with("+json")
+        || mime.ends_with("+xml")
+        || matches!(
+            mime.as_str(),
+            "application/json"
+                | "application/x-ndjson"
+                | "application/xml"
+                | "application/javascript"
+                | "application/x-www-form-urlencoded"
+                | "application/graphql"
+        )
+}
+
+/// Resolves host names and refuses private addresses, so a public name
+/// pointing (or re-pointed) at an internal service can't be reached.
+struct GuardedResolver {
+    allow_private: bool,
+    /// Fixed answers consulted before DNS.
+    hosts: HashMap<String, Vec<IpAddr>>,
+}
+
+impl GuardedResolver {
+    fn new(allow_private: bool) -> Self {
+        Self {
+            allow_private,
+            hosts: HashMap::new(),
+        }
     }
 }
 
-fn simple_hash(s: &str) -> u64 {
-    s.bytes()
-        .fold(0u64, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u64))
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let allow_private = self.allow_private;
+        let host = name.as_str().to_string();
+        let fixed = self.hosts.get(&host).cloned();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = match fixed {
+                Some(ips) => ips.into_iter().map(|ip| SocketAddr::new(ip, 0)).collect(),
+                None => tokio::net::lookup_host((host.as_str(), 0)).await?.collect(),
+            };
+            if !allow_private && let Some(addr) = addrs.iter().find(|a| is_private_ip(&a.ip())) {
+                return Err(Box::new(Blocked(format!(
+                    "'{host}' resolves to private/internal address {} (SSRF prevention)",
+                    addr.ip()
+                ))) as _);
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use axum::Router;
+    use axum::extract::Path;
+    use axum::http::{HeaderMap, Method, header};
+    use axum::response::{IntoResponse, Redirect};
+    use axum::routing::{any, get};
+
+    /// Serve a stand-in API on an ephemeral loopback port.
+    async fn serve() -> SocketAddr {
+        let app = Router::new()
+            .route(
+                "/json",
+                get(|| async { axum::Json(serde_json::json!({"items": [1, 2, 3]})) }),
+            )
+            .route(
+                "/echo",
+                any(
+                    |method: Method, headers: HeaderMap, body: String| async move {
+                        let token = headers
+                            .get("x-token")
+                            .and_then(|v| v.to_str().ok())
+                            .unwrap_or_default()
+                            .to_string();
+                        (
+                            axum::http::StatusCode::CREATED,
+                            format!("{method} {token} {body}"),
+                        )
+                    },
+                ),
+            )
+            .route(
+                "/redirect/{n}",
+                get(|Path(n): Path<u32>| async move {
+                    if n == 0 {
+                        "arrived".into_response()
+                    } else {
+                        Redirect::temporary(&format!("/redirect/{}", n - 1)).into_response()
+                    }
+                }),
+            )
+            .route(
+                "/bounce",
+                get(|headers: HeaderMap| async move {
+                    // Same server, but under a name the allowlist doesn't cover
+                    let host = headers[header::HOST].to_str().unwrap().to_string();
+                    let port = host.rsplit(':').next().unwrap().to_string();
+                    Redirect::temporary(&format!("http://localhost:{port}/json"))
+                }),
+            )
+            .route("/big", get(|| async { "é".repeat(50_000) }))
+            .route(
+                "/image",
+                get(|| async { ([(header::CONTENT_TYPE, "image/png")], vec![0u8; 2048]) }),
+            )
+            .route(
+                "/missing",
+                get(|| async { (axum::http::StatusCode::NOT_FOUND, "nope") }),
+            )
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    "late"
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        addr
+    }
+
+    /// A tool allowed to reach the loopback stand-in server.
+    fn local_tool() -> HttpRequestTool {
+        HttpRequestTool::from_config(&HttpRequestToolConfig {
+            allow_private_networks: true,
+            ..Default::default()
+        })
+    }
 
     #[test]
     fn tool_definition() {
-        let tool = HttpRequestTool;
+        let tool = HttpRequestTool::new();
         assert_eq!(tool.name(), "http_request");
         let schema = tool.parameters_schema();
         assert_eq!(schema["required"], serde_json::json!(["url"]));
         assert!(schema["properties"]["method"].is_object());
         assert!(schema["properties"]["headers"].is_object());
         assert!(schema["properties"]["body"].is_object());
+        assert_eq!(schema["properties"]["timeout_secs"]["default"], 30);
     }
 
     #[tokio::test]
-    async fn get_request_returns_success() {
-        let tool = HttpRequestTool;
-        let result = tool
-            .execute(serde_json::json!({
-                "url": "https://example.com/page"
-            }))
+    async fn get_returns_status_headers_and_body() {
+        let addr = serve().await;
+        let result = local_tool()
+            .execute(serde_json::json!({ "url": format!("http://{addr}/json") }))
             .await
             .unwrap();
 
         assert!(result.success);
-        assert!(result.output.contains("Mock Response"));
+        let data = result.data.unwrap();
+        assert_eq!(data["status_code"], 200);
+        assert_eq!(data["status_text"], "OK");
+        assert_eq!(data["headers"]["content-type"], "application/json");
+        assert_eq!(data["body"], r#"{"items":[1,2,3]}"#);
+        assert_eq!(data["truncated"], false);
     }
 
     #[tokio::test]
-    async fn health_endpoint_returns_healthy() {
-        let tool = HttpRequestTool;
-        let result = tool
+    async fn sends_method_headers_and_body() {
+        let addr = serve().await;
+        let result = local_tool()
             .execute(serde_json::json!({
-                "url": "https://myapp.com/health"
+                "url": format!("http://{addr}/echo"),
+                "method": "post",
+                "headers": { "X-Token": "secret" },
+                "body": "{\"name\": \"Test Item\"}"
             }))
             .await
             .unwrap();
 
         assert!(result.success);
-        assert!(result.output.contains("healthy"));
+        let data = result.data.unwrap();
+        assert_eq!(data["status_code"], 201);
+        assert_eq!(data["body"], "POST secret {\"name\": \"Test Item\"}");
+    }
+
+    #[tokio::test]
+    async fn error_status_is_not_success() {
+        let addr = serve().await;
+        let result = local_tool()
+            .execute(serde_json::json!({ "url": format!("http://{addr}/missing") }))
+            .await
+            .unwrap();
+
+        assert!(!result.success);
+        assert_eq!(result.data.unwrap()["status_code"], 404);
     }
 
     #[tokio::test]
-    async fn post_to_api_returns_created() {
-        let tool = HttpRequestTool;
+    async fn follows_redirects_up_to_the_limit() {
+        let addr = serve().await;
+        let tool = local_tool();
+
         let result = tool
-            .execute(serde_json::json!({
-                "url": "https://api.example.com/v1/items",
-                "method": "POST",
-                "body": "{\"name\": \"Test Item\"}"
-            }))
+            .execute(serde_json::json!({ "url": format!("http://{addr}/redirect/5") }))
             .await
             .unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data["body"], "arrived");
+        assert_eq!(data["url"], format!("http://{addr}/redirect/0"));
+
+        let result = tool
+            .execute(serde_json::json!({ "url": format!("http://{addr}/redirect/6") }))
+            .await;
+        match result {
+            Err(ToolError::PermissionDenied { reason, .. }) => {
+                assert!(reason.contains("Too many redirects"), "{reason}");
+            }
+            other => panic!("Expected redirect limit, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn redirects_are_checked_against_the_allowlist() {
+        let addr = serve().await;
+        let tool = HttpRequestTool::from_config(&HttpRequestToolConfig {
+            allow_private_networks: true,
+            allowed_hosts: vec!["127.0.0.1".into()],
+            ..Default::default()
+        });
 
+        let result = tool
+            .execute(serde_json::json!({ "url": format!("http://{addr}/json") }))
+            .await
+            .unwrap();
         assert!(result.success);
-        assert!(result.output.contains("created"));
+
+        let result = tool
+            .execute(serde_json::json!({ "url": format!("http://{addr}/bounce") }))
+            .await;
+        match result {
+            Err(ToolError::PermissionDenied { reason, .. }) => {
+                assert!(reason.contains("'localhost'"), "{reason}");
+            }
+            other => panic!("Expected allowlist block, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn text_bodies_are_truncated_at_char_boundaries() {
+        let addr = serve().await;
+        let tool = HttpRequestTool::from_config(&HttpRequestToolConfig {
+            allow_private_networks: true,
+            max_body_chars: 10,
+            ..Default::default()
+        });
+        let result = tool
+            .execute(serde_json::json!({ "url": format!("http://{addr}/big") }))
+            .await
+            .unwrap();
         let data = result.data.unwrap();
-        assert_eq!(data["status_code"], 201);
+        assert_eq!(data["truncated"], true);
+        let body = data["body"].as_str().unwrap();
+        assert!(body.starts_with(&"é".repeat(10)));
+        assert!(body.ends_with("[truncated: showing the first 10 characters]"));
     }
 
     #[tokio::test]
-    async fn delete_api_resource() {
-        let tool = HttpRequestTool;
+    async fn response_size_is_capped() {
+        let addr = serve().await;
+        let tool = HttpRequestTool::from_config(&HttpRequestToolConfig {
+            allow_private_networks: true,
+            max_response_bytes: 1001,
+            ..Default::default()
+        });
         let result = tool
-            .execute(serde_json::json!({
-                "url": "https://api.example.com/v1/items/42",
-                "method": "DELETE"
-            }))
+            .execute(serde_json::json!({ "url": format!("http://{addr}/big") }))
             .await
             .unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data["truncated"], true);
+        let body = data["body"].as_str().unwrap();
+        // 1001 bytes hold 500 two-byte characters; the split one is dropped
+        assert!(body.starts_with(&format!("{}\n", "é".repeat(500))));
+        assert!(body.ends_with("[truncated: response larger than 1001 bytes]"));
+    }
 
-        assert!(result.success);
+    #[tokio::test]
+    async fn binary_bodies_are_summarized() {
+        let addr = serve().await;
+        let result = local_tool()
+            .execute(serde_json::json!({ "url": format!("http://{addr}/image") }))
+            .await
+            .unwrap();
         let data = result.data.unwrap();
-        assert_eq!(data["status_code"], 204);
+        assert_eq!(
+            data["body"],
+            "[binary content omitted: 2048 bytes of image/png]"
+        );
+        assert_eq!(data["truncated"], false);
     }
 
     #[tokio::test]
-    async fn not_found_url_returns_404() {
-        let tool = HttpRequestTool;
-        let result = tool
+    async fn timeout_is_enforced() {
+        let addr = serve().await;
+        let result = local_tool()
             .execute(serde_json::json!({
-                "url": "https://example.com/404"
+                "url": format!("http://{addr}/slow"),
+                "timeout_secs": 1
             }))
+            .await;
+        assert!(matches!(
+            result,
+            Err(ToolError::Timeout {
+                timeout_secs: 1,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn private_addresses_are_blocked_by_default() {
+        let tool = HttpRequestTool::new();
+        for url in [
+            "http://127.0.0.1:8080/admin",
+            "http://localhost/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://[::ffff:127.0.0.1]/",
+            "http://0x7f.1/",
+            "http://user@10.0.0.1/",
+            "http://metadata.google.internal/",
+        ] {
+            let result = tool.execute(serde_json::json!({ "url": url })).await;
+            assert!(
+                matches!(result, Err(ToolError::PermissionDenied { .. })),
+                "{url} was not blocked"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn names_resolving_to_private_addresses_are_blocked() {
+        let addr = serve().await;
+        let resolver = |allow_private| GuardedResolver {
+            hosts: HashMap::from([("rebind.test".into(), vec![addr.ip()])]),
+            ..GuardedResolver::new(allow_private)
+        };
+        let url = format!("http://rebind.test:{}/json", addr.port());
+
+        let tool =
+            HttpRequestTool::with_resolver(&HttpRequestToolConfig::default(), resolver(false));
+        match tool.execute(serde_json::json!({ "url": url })).await {
+            Err(ToolError::PermissionDenied { reason, .. }) => {
+                assert!(reason.contains("resolves to private"), "{reason}");
+            }
+            other => panic!("Expected DNS rebinding block, got {other:?}"),
+        }
+
+        // The same name is reachable once private networks are allowed
+        let config = HttpRequestToolConfig {
+            allow_private_networks: true,
+            ..Default::default()
+        };
+        let tool = HttpRequestTool::with_resolver(&config, resolver(true));
+        let result = tool
+            .execute(serde_json::json!({ "url": url }))
             .await
             .unwrap();
+        assert!(result.success);
+    }
 
-        assert!(!result.success);
-        let data = result.data.unwrap();
-        assert_eq!(data["status_code"], 404);
+    #[tokio::test]
+    async fn hosts_outside_the_allowlist_are_denied() {
+        let tool = HttpRequestTool::from_config(&HttpRequestToolConfig {
+            allowed_hosts: vec!["*.example.com".into()],
+            ..Default::default()
+        });
+        let result = tool
+            .execute(serde_json::json!({ "url": "https://evil.com/steal" }))
+            .await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied { .. })));
+    }
+
+    #[test]
+    fn content_types() {
+        for text in [
+            "text/html; charset=utf-8",
+            "application/json",
+            "application/problem+json",
+            "application/atom+xml",
+        ] {
+            assert!(is_text_content_type(text), "{text}");
+        }
+        for binary in ["image/png", "application/octet-stream", "application/pdf"] {
+            assert!(!is_text_content_type(binary), "{binary}");
+        }
+        assert_eq!(render_body(b"plain", None, 100, false).0, "plain");
+        assert!(
+            render_body(&[0xff, 0xfe], None, 100, false)
+                .0
+                .starts_with("[binary content omitted")
+        );
     }
 
     #[tokio::test]
     async fn missing_url_returns_error() {
-        let tool = HttpRequestTool;
+        let tool = HttpRequestTool::new();
         let result = tool.execute(serde_json::json!({})).await;
         assert!(result.is_err());
     }
 
     #[tokio::test]
     async fn invalid_url_scheme_returns_error() {
-        let tool = HttpRequestTool;
+        let tool = HttpRequestTool::new();
         let result = tool
             .execute(serde_json::json!({ "url": "ftp://files.example.com" }))
             .await;
-        assert!(result.is_err());
+        assert!(matches!(result, Err(ToolError::InvalidArguments(_))));
     }
 
     #[tokio::test]
     async fn invalid_method_returns_error() {
-        let tool = HttpRequestTool;
+        let tool = HttpRequestTool::new();
         let result = tool
             .execute(serde_json::json!({
                 "url": "https://example.com",
                 "method": "TRACE"
             }))
             .await;
-        assert!(result.is_err());
-    }
-
-    #[tokio::test]
-    async fn put_updates_resource() {
-        let tool = HttpRequestTool;
-        let result = tool
-            .execute(serde_json::json!({
-                "url": "https://api.example.com/v1/items/1",
-                "method": "PUT",
-                "body": "{\"name\": \"Updated\"}"
-            }))
-            .await
-            .unwrap();
-
-        assert!(result.success);
-        assert!(result.output.contains("updated"));
-    }
-
-    #[tokio::test]
-    async fn custom_headers_accepted() {
-        let tool = HttpRequestTool;
-        let result = tool
-            .execute(serde_json::json!({
-                "url": "https://api.example.com/v1/data",
-                "headers": {
-                    "Authorization": "Bearer token123",
-                    "Accept": "application/json"
-                }
-            }))
-            .await
-            .unwrap();
-
-        assert!(result.success);
+        assert!(matches!(result, Err(ToolError::InvalidArguments(_))));
     }
 }
@@ -1,14 +1,51 @@
-//! Knowledge base query tool — stub for RAG retrieval.
+//! Knowledge base query tool — retrieval over ingested documents.
 //!
-//! In production this would perform vector similarity search against
-//! a PostgreSQL + pgvector database. The stub returns mock knowledge
-//! chunks so the RAG agent pattern can be tested end-to-end.
+//! Searches the chunks written by `rustedclaw ingest` and `POST
+//! /v1/documents` with hybrid keyword + vector search (see
+//! [`rustedclaw_memory::knowledge`]). Each hit carries the `document_id`,
+//! `chunk_index`, `source` and `similarity` the RAG agent cites.
+//!
+//! Without a knowledge base the tool reports that none is configured
+//! rather than inventing results.
 
 use async_trait::async_trait;
 use rustedclaw_core::error::ToolError;
+use rustedclaw_core::memory::MemoryScope;
 use rustedclaw_core::tool::{Tool, ToolResult};
+use rustedclaw_memory::ingest::metadata_tags;
+use rustedclaw_memory::knowledge::{KnowledgeBase, KnowledgeQuery};
+use std::sync::Arc;
+
+/// Most results a single query may ask for.
+const MAX_TOP_K: u64 = 20;
+
+const NO_KNOWLEDGE_BASE: &str = "No knowledge base is configured. Enable memory and ingest \
+                                 documents with `rustedclaw ingest` to query them.";
+
+/// A tool that retrieves relevant document chunks from the knowledge base.
+pub struct KnowledgeBaseQueryTool {
+    knowledge: Option<Arc<KnowledgeBase>>,
+}
+
+impl KnowledgeBaseQueryTool {
+    /// Create the tool without a knowledge base; every query fails.
+    pub fn new() -> Self {
+        Self { knowledge: None }
+    }
+
+    /// Create the tool backed by `knowledge`.
+    pub fn with_knowledge_base(knowledge: KnowledgeBase) -> Self {
+        Self {
+            knowledge: Some(Arc::new(knowledge)),
+        }
+    }
+}
 
-pub struct KnowledgeBaseQueryTool;
+impl Default for KnowledgeBaseQueryTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 #[async_trait]
 impl Tool for KnowledgeBaseQueryTool {
@@ -35,8 +72,21 @@ impl Tool for KnowledgeBaseQueryTool {
                 },
                 "min_score": {
                     "type": "number",
-                    "description": "Minimum similarity score threshold (0.0-1.0, default 0.5)",
-                    "default": 0.5
+                    "description": "Minimum similarity score threshold (0.0-1.0, default 0.0)",
+                    "default": 0.0
+                },
+                "filters": {
+                    "type": "object",
+                    "description": "Document metadata every result must match, e.g. {\"lang\": \"en\"}"
+                },
+                "document_id": {
+                    "type": "string",
+                    "description": "Only search this document"
+                },
+                "rerank": {
+                    "type": "boolean",
+                    "description": "Re-score results by how well they cover the query (default false)",
+                    "default": false
                 }
             },
             "required": ["query"]
@@ -48,122 +98,47 @@ impl Tool for KnowledgeBaseQueryTool {
             .as_str()
             .ok_or_else(|| ToolError::InvalidArguments("Missing 'query' argument".into()))?;
 
-        let top_k = arguments["top_k"].as_u64().unwrap_or(3).min(10) as usize;
-
-        let chunks = generate_mock_chunks(query, top_k);
-        let output = serde_json::to_string_pretty(&chunks).unwrap_or_default();
-
-        Ok(ToolResult {
-            call_id: String::new(),
-            success: true,
-            output,
-            data: Some(serde_json::to_value(&chunks).unwrap()),
-        })
-    }
-}
-
-#[derive(serde::Serialize)]
-struct KnowledgeResult {
-    document_id: String,
-    chunk_index: usize,
-    content: String,
-    source: String,
-    similarity: f64,
-}
-
-fn generate_mock_chunks(query: &str, top_k: usize) -> Vec<KnowledgeResult> {
-    let q = query.to_lowercase();
-
-    // Topic-specific mock knowledge for realistic agent testing.
-    let knowledge_base: Vec<(&str, Vec<KnowledgeResult>)> = vec![
-        ("rust", vec![
-            KnowledgeResult {
-                document_id: "doc_rust_001".into(),
-                chunk_index: 0,
-                content: "Rust is a multi-paradigm, general-purpose programming language that emphasizes performance, type safety, and concurrency. It enforces memory safety without a garbage collector.".into(),
-                source: "rust_overview.md".into(),
-                similarity: 0.95,
-            },
-            KnowledgeResult {
-                document_id: "doc_rust_001".into(),
-                chunk_index: 1,
-                content: "Rust's ownership system guarantees memory safety and thread safety at compile time, eliminating data races. The borrow checker enforces these rules.".into(),
-                source: "rust_overview.md".into(),
-                similarity: 0.88,
-            },
-            KnowledgeResult {
-                document_id: "doc_rust_002".into(),
-                chunk_index: 0,
-                content: "Cargo is Rust's build system and package manager. It downloads dependencies, compiles packages, and can upload to crates.io.".into(),
-                source: "rust_tooling.md".into(),
-                similarity: 0.75,
-            },
-        ]),
-        ("wasm", vec![
-            KnowledgeResult {
-                document_id: "doc_wasm_001".into(),
-                chunk_index: 0,
-                content: "WebAssembly (WASM) is a binary instruction format for a stack-based virtual machine. It enables high-performance applications on the web and beyond.".into(),
-                source: "wasm_intro.md".into(),
-                similarity: 0.93,
-            },
-            KnowledgeResult {
-                document_id: "doc_wasm_002".into(),
-                chunk_index: 0,
-                content: "The WASM Component Model defines a portable, language-agnostic binary format with a rich type system (WIT). Components can be composed and linked.".into(),
-                source: "component_model.md".into(),
-                similarity: 0.85,
-            },
-        ]),
-        ("agent", vec![
-            KnowledgeResult {
-                document_id: "doc_agent_001".into(),
-                chunk_index: 0,
-                content: "The ReAct pattern combines reasoning and acting in an interleaved manner. The agent thinks (Thought), acts (Action), and observes (Observation) in a loop.".into(),
-                source: "agent_patterns.md".into(),
-                similarity: 0.92,
-            },
-            KnowledgeResult {
-                document_id: "doc_agent_002".into(),
-                chunk_index: 0,
-                content: "RAG (Retrieval-Augmented Generation) grounds LLM responses in factual data by retrieving relevant documents before generating answers.".into(),
-                source: "rag_pattern.md".into(),
-                similarity: 0.87,
-            },
-        ]),
-    ];
+        let Some(knowledge) = &self.knowledge else {
+            return Ok(ToolResult {
+                call_id: String::new(),
+                success: false,
+                output: NO_KNOWLEDGE_BASE.into(),
+                data: None,
+            });
+        };
 
-    // Match topics or return generic results.
-    for (keyword, results) in &knowledge_base {
-        if q.contains(keyword) {
-            return results.iter().take(top_k).cloned().collect();
+        let top_k = arguments["top_k"].as_u64().unwrap_or(3).min(MAX_TOP_K) as usize;
+        let mut search = KnowledgeQuery::new(query)
+            .with_top_k(top_k)
+            .with_min_score(arguments["min_score"].as_f64().unwrap_or(0.0) as f32)
+            .with_rerank(arguments["rerank"].as_bool().unwrap_or(false))
+            // Only what the sender of the current run may see
+            .with_scope(MemoryScope::current());
+        search.filters = metadata_tags(&arguments["filters"]);
+        if let Some(document_id) = arguments["document_id"].as_str() {
+            search = search.with_document(document_id);
         }
-    }
-
-    // Generic fallback with decreasing similarity.
-    (0..top_k)
-        .map(|i| KnowledgeResult {
-            document_id: format!("doc_gen_{:03}", i),
-            chunk_index: 0,
-            content: format!(
-                "Knowledge chunk {} related to '{}'. This is mock content for testing the RAG pipeline.",
-                i + 1,
-                query
-            ),
-            source: format!("knowledge_{}.md", i + 1),
-            similarity: 0.9 - (i as f64 * 0.1),
-        })
-        .collect()
-}
 
-impl Clone for KnowledgeResult {
-    fn clone(&self) -> Self {
-        Self {
-            document_id: self.document_id.clone(),
-            chunk_index: self.chunk_index,
-            content: self.content.clone(),
-            source: self.source.clone(),
-            similarity: self.similarity,
+        match knowledge.search(&search).await {
+            Ok(hits) => {
+                let output = if hits.is_empty() {
+                    format!("No knowledge found for '{query}'.")
+                } else {
+                    serde_json::to_string_pretty(&hits).unwrap_or_default()
+                };
+                Ok(ToolResult {
+                    call_id: String::new(),
+                    success: true,
+                    output,
+                    data: Some(serde_json::to_value(&hits).unwrap()),
+                })
+            }
+            Err(e) => Ok(ToolResult {
+                call_id: String::new(),
+                success: false,
+                output: format!("Knowledge base query failed: {e}"),
+                data: None,
+            }),
         }
     }
 }
@@ -171,55 +146,126 @@ impl Clone for KnowledgeResult {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rustedclaw_core::memory::MemoryBackend;
+    use rustedclaw_memory::InMemoryBackend;
+    use rustedclaw_memory::ingest::{Document, DocumentFormat, DocumentIngestor};
+    use rustedclaw_memory::knowledge::LexicalReranker;
+
+    async fn tool() -> KnowledgeBaseQueryTool {
+        let memory: Arc<dyn MemoryBackend> = Arc::new(InMemoryBackend::new());
+        let ingestor = DocumentIngestor::new(memory.clone());
+        for (id, text, tags) in [
+            (
+                "rust",
+                "# Rust\n\nRust enforces memory safety through ownership.\n\n## Cargo\n\nCargo builds Rust packages.",
+                vec!["lang:en".to_string()],
+            ),
+            (
+                "rust-fr",
+                "# Rust\n\nRust garantit la sécurité mémoire grâce à l'ownership.",
+                vec!["lang:fr".to_string()],
+            ),
+        ] {
+            ingestor
+                .ingest(Document {
+                    id: id.into(),
+                    source: format!("{id}.md"),
+                    format: DocumentFormat::Markdown,
+                    content: text.as_bytes().to_vec(),
+                    tags,
+                    scope: MemoryScope::default(),
+                })
+                .await
+                .unwrap();
+        }
+        KnowledgeBaseQueryTool::with_knowledge_base(
+            KnowledgeBase::new(memory).with_reranker(Arc::new(LexicalReranker)),
+        )
+    }
 
     #[tokio::test]
-    async fn query_returns_results() {
-        let tool = KnowledgeBaseQueryTool;
-        let result = tool
-            .execute(serde_json::json!({"query": "rust ownership"}))
+    async fn query_returns_ingested_chunks() {
+        let result = tool()
+            .await
+            .execute(serde_json::json!({"query": "How does Rust enforce memory safety?"}))
             .await
             .unwrap();
 
         assert!(result.success);
-        assert!(result.output.contains("Rust"));
+        let hits = result.data.unwrap();
+        let first = &hits[0];
+        assert_eq!(first["document_id"], "rust");
+        assert_eq!(first["chunk_index"], 0);
+        assert_eq!(first["source"], "rust.md");
+        assert!(first["content"].as_str().unwrap().contains("ownership"));
+        assert!(first["similarity"].as_f64().unwrap() > 0.0);
         assert!(result.output.contains("similarity"));
     }
 
     #[tokio::test]
-    async fn respects_top_k() {
-        let tool = KnowledgeBaseQueryTool;
+    async fn respects_top_k_filters_and_document() {
+        let tool = tool().await;
+
         let result = tool
-            .execute(serde_json::json!({"query": "test", "top_k": 2}))
+            .execute(serde_json::json!({"query": "rust", "top_k": 1}))
             .await
             .unwrap();
+        assert_eq!(result.data.unwrap().as_array().unwrap().len(), 1);
 
-        let data: Vec<serde_json::Value> = serde_json::from_str(&result.output).unwrap();
-        assert_eq!(data.len(), 2);
+        let result = tool
+            .execute(serde_json::json!({"query": "rust ownership", "filters": {"lang": "fr"}}))
+            .await
+            .unwrap();
+        let hits = result.data.unwrap();
+        assert_eq!(hits.as_array().unwrap().len(), 1);
+        assert_eq!(hits[0]["document_id"], "rust-fr");
+
+        let result = tool
+            .execute(serde_json::json!({"query": "cargo packages", "document_id": "rust-fr"}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(result.output.starts_with("No knowledge found"));
     }
 
     #[tokio::test]
-    async fn generic_query_works() {
-        let tool = KnowledgeBaseQueryTool;
-        let result = tool
-            .execute(serde_json::json!({"query": "some random topic"}))
+    async fn rerank_scores_term_coverage() {
+        let result = tool()
+            .await
+            .execute(serde_json::json!({
+                "query": "cargo packages",
+                "rerank": true,
+                "min_score": 0.9
+            }))
             .await
             .unwrap();
+        let hits = result.data.unwrap();
+        assert_eq!(hits.as_array().unwrap().len(), 1);
+        assert_eq!(hits[0]["chunk_index"], 1);
+        assert_eq!(hits[0]["similarity"], 1.0);
+    }
 
-        assert!(result.success);
-        let data: Vec<serde_json::Value> = serde_json::from_str(&result.output).unwrap();
-        assert_eq!(data.len(), 3); // default top_k
+    #[tokio::test]
+    async fn without_knowledge_base_nothing_is_invented() {
+        let result = KnowledgeBaseQueryTool::new()
+            .execute(serde_json::json!({"query": "rust ownership"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.data.is_none());
+        assert!(result.output.contains("No knowledge base"));
     }
 
     #[tokio::test]
     async fn missing_query_returns_error() {
-        let tool = KnowledgeBaseQueryTool;
+        let tool = KnowledgeBaseQueryTool::new();
         let result = tool.execute(serde_json::json!({})).await;
         assert!(result.is_err());
     }
 
     #[test]
     fn tool_definition() {
-        let tool = KnowledgeBaseQueryTool;
+        let tool = KnowledgeBaseQueryTool::new();
         let def = tool.to_definition();
         assert_eq!(def.name, "knowledge_base_query");
     }
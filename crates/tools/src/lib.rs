@@ -1,7 +1,7 @@
 //! Built-in tool implementations for RustedClaw.
 //!
 //! Tools give the agent the ability to interact with the world:
-//! run shell commands, read/write files, search the web, do math,
+//! run shell commands, read/write files, search and read the web, do math,
 //! check the weather, and query a knowledge base.
 //!
 //! With the `wasm` feature, user-provided WASM modules can also be
@@ -15,56 +15,232 @@ pub mod knowledge_base_query;
 pub mod memory_search;
 pub mod shell;
 pub mod weather_lookup;
+pub mod web_fetch;
 pub mod web_search;
 
 #[cfg(feature = "wasm")]
 pub mod wasm_tool;
 
-use rustedclaw_core::tool::ToolRegistry;
+use rustedclaw_config::{AppConfig, ToolsConfig};
+use rustedclaw_core::memory::MemoryBackend;
+use rustedclaw_core::tool::{Tool, ToolRegistry};
+use rustedclaw_memory::knowledge::{KnowledgeBase, LexicalReranker};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
 
 #[cfg(feature = "wasm")]
 pub use wasm_tool::{
     WasmCapability, WasmPolicy, WasmTool, WasmToolConfig, load_wasm_tools_from_dir,
 };
 
-/// Create a default tool registry with all built-in tools.
+/// Create a tool registry with all built-in tools and default settings.
 ///
 /// Security defaults:
-/// - Shell: only common safe commands (ls, cat, echo, git, pwd, etc.)
+/// - Shell: only the `[autonomy]` default commands (git, cargo, ls, cat, ...)
 /// - File read/write: sensitive paths (~/.ssh, /etc/shadow, etc.) are blocked
+/// - HTTP: private and internal addresses are blocked
 pub fn default_registry() -> ToolRegistry {
-    let mut registry = ToolRegistry::new();
-    // Secure shell allowlist — only common safe commands
-    let safe_commands = vec![
-        "ls".into(),
-        "dir".into(),
-        "cat".into(),
-        "head".into(),
-        "tail".into(),
-        "echo".into(),
-        "pwd".into(),
-        "date".into(),
-        "whoami".into(),
-        "wc".into(),
-        "grep".into(),
-        "find".into(),
-        "which".into(),
-        "git".into(),
-        "cargo".into(),
-        "rustc".into(),
-        "node".into(),
-        "npm".into(),
-        "python".into(),
-        "pip".into(),
+    build_registry(&AppConfig::default(), None)
+}
+
+/// [`default_registry`] with `memory_search` querying `memory` instead of
+/// returning stub results, and `knowledge_base_query` searching the
+/// documents ingested into it.
+pub fn registry_with_memory(memory: Arc<dyn MemoryBackend>) -> ToolRegistry {
+    build_registry(&AppConfig::default(), Some(memory))
+}
+
+/// Build the tool registry described by `config`: the built-ins not listed
+/// in `[tools] disabled`, configured from `[tools]` and `[autonomy]`, plus
+/// any WASM tools in `[tools] wasm_dir`.
+///
+/// This is the one place the gateway, the daemon and `rustedclaw agent`
+/// get their tools from.
+pub fn registry_from_config(config: &AppConfig, memory: Arc<dyn MemoryBackend>) -> ToolRegistry {
+    build_registry(config, Some(memory))
+}
+
+fn build_registry(config: &AppConfig, memory: Option<Arc<dyn MemoryBackend>>) -> ToolRegistry {
+    let tools = &config.tools;
+    let autonomy = &config.autonomy;
+
+    // The tools' own sensitive-path defaults always apply; `[autonomy]`
+    // adds to them rather than replacing them.
+    let forbidden_paths = |defaults: Vec<String>| {
+        let mut paths = defaults;
+        for path in &autonomy.forbidden_paths {
+            if !paths.contains(path) {
+                paths.push(path.clone());
+            }
+        }
+        paths
+    };
+    let file_read = file_read::FileReadTool::new();
+    let file_write = file_write::FileWriteTool::new();
+
+    let (memory_search, knowledge_base_query) = match memory {
+        Some(memory) => (
+            memory_search::MemorySearchTool::with_backend(memory.clone()),
+            knowledge_base_query::KnowledgeBaseQueryTool::with_knowledge_base(
+                KnowledgeBase::new(memory).with_reranker(Arc::new(LexicalReranker)),
+            ),
+        ),
+        None => (
+            memory_search::MemorySearchTool::new(),
+            knowledge_base_query::KnowledgeBaseQueryTool::new(),
+        ),
+    };
+
+    let builtins: Vec<Box<dyn Tool>> = vec![
+        Box::new(
+            shell::ShellTool::new(autonomy.allowed_commands.clone())
+                .with_timeout(Duration::from_secs(tools.shell.timeout_secs)),
+        ),
+        Box::new(file_read::FileReadTool::with_restrictions(
+            autonomy.allowed_roots.clone(),
+            forbidden_paths(file_read.forbidden_paths),
+        )),
+        Box::new(file_write::FileWriteTool::with_restrictions(
+            autonomy.allowed_roots.clone(),
+            forbidden_paths(file_write.forbidden_paths),
+        )),
+        Box::new(web_search::WebSearchTool::from_config(&tools.web_search)),
+        Box::new(web_fetch::WebFetchTool::from_config(&tools.http_request)),
+        Box::new(calculator::CalculatorTool),
+        Box::new(weather_lookup::WeatherLookupTool),
+        Box::new(knowledge_base_query),
+        Box::new(http_request::HttpRequestTool::from_config(
+            &tools.http_request,
+        )),
+        Box::new(memory_search),
     ];
-    registry.register(Box::new(shell::ShellTool::new(safe_commands)));
-    registry.register(Box::new(file_read::FileReadTool::new()));
-    registry.register(Box::new(file_write::FileWriteTool::new()));
-    registry.register(Box::new(web_search::WebSearchTool));
-    registry.register(Box::new(calculator::CalculatorTool));
-    registry.register(Box::new(weather_lookup::WeatherLookupTool));
-    registry.register(Box::new(knowledge_base_query::KnowledgeBaseQueryTool));
-    registry.register(Box::new(http_request::HttpRequestTool));
-    registry.register(Box::new(memory_search::MemorySearchTool::new()));
+
+    let mut registry = ToolRegistry::new();
+    let mut known: Vec<String> = Vec::new();
+    for tool in builtins.into_iter().chain(wasm_tools(tools)) {
+        let name = tool.name().to_string();
+        if known.contains(&name) {
+            warn!(tool = %name, "Skipping WASM tool that shadows a built-in tool");
+            continue;
+        }
+        known.push(name.clone());
+        if tools.is_enabled(&name) {
+            registry.register(tool);
+        }
+    }
+
+    // `delegate` is registered by the gateway and the CLI, on top of this
+    // registry
+    for name in &tools.disabled {
+        if !known.contains(name) && name != "delegate" {
+            warn!(tool = %name, "Unknown tool in [tools] disabled");
+        }
+    }
     registry
 }
+
+/// Load the WASM tools in `[tools] wasm_dir`.
+#[cfg(feature = "wasm")]
+fn wasm_tools(tools: &ToolsConfig) -> Vec<Box<dyn Tool>> {
+    let Some(dir) = &tools.wasm_dir else {
+        return Vec::new();
+    };
+    let dir = rustedclaw_security::expand_tilde(dir);
+    load_wasm_tools_from_dir(std::path::Path::new(&dir))
+        .into_iter()
+        .map(|tool| Box::new(tool) as Box<dyn Tool>)
+        .collect()
+}
+
+#[cfg(not(feature = "wasm"))]
+fn wasm_tools(tools: &ToolsConfig) -> Vec<Box<dyn Tool>> {
+    if let Some(dir) = &tools.wasm_dir {
+        warn!(dir = %dir, "Ignoring [tools] wasm_dir: built without the `wasm` feature");
+    }
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustedclaw_core::error::ToolError;
+    use rustedclaw_memory::InMemoryBackend;
+
+    fn config(toml: &str) -> AppConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn default_registry_has_every_builtin() {
+        let registry = default_registry();
+        let mut names = registry.names();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "calculator",
+                "file_read",
+                "file_write",
+                "http_request",
+                "knowledge_base_query",
+                "memory_search",
+                "shell",
+                "weather_lookup",
+                "web_fetch",
+                "web_search",
+            ]
+        );
+    }
+
+    #[test]
+    fn disabled_tools_are_left_out() {
+        let config = config("[tools]\ndisabled = [\"shell\", \"file_write\", \"delegate\"]");
+        let registry = registry_from_config(&config, Arc::new(InMemoryBackend::new()));
+        assert!(registry.get("shell").is_none());
+        assert!(registry.get("file_write").is_none());
+        assert!(registry.get("file_read").is_some());
+    }
+
+    #[tokio::test]
+    async fn shell_uses_the_autonomy_allowlist() {
+        let config = config("[autonomy]\nallowed_commands = [\"echo\"]");
+        let registry = registry_from_config(&config, Arc::new(InMemoryBackend::new()));
+        let shell = registry.get("shell").unwrap();
+
+        let result = shell
+            .execute(serde_json::json!({"command": "echo configured"}))
+            .await
+            .unwrap();
+        assert_eq!(result.output, "configured");
+
+        // Allowed by the old hard-coded list, but not by this config
+        let result = shell.execute(serde_json::json!({"command": "pwd"})).await;
+        assert!(matches!(result, Err(ToolError::PermissionDenied { .. })));
+    }
+
+    #[tokio::test]
+    async fn file_tools_use_autonomy_paths() {
+        let allowed = tempfile::tempdir().unwrap();
+        let other = tempfile::tempdir().unwrap();
+        let secret = allowed.path().join("secret");
+        std::fs::create_dir(&secret).unwrap();
+        for dir in [allowed.path(), secret.as_path(), other.path()] {
+            std::fs::write(dir.join("notes.txt"), "hello").unwrap();
+        }
+        let config = config(&format!(
+            "[autonomy]\nallowed_roots = [{:?}]\nforbidden_paths = [{:?}]",
+            allowed.path().to_str().unwrap(),
+            secret.to_str().unwrap(),
+        ));
+        let registry = registry_from_config(&config, Arc::new(InMemoryBackend::new()));
+        let file_read = registry.get("file_read").unwrap();
+        let read = |path: std::path::PathBuf| {
+            file_read.execute(serde_json::json!({"path": path.join("notes.txt")}))
+        };
+
+        assert!(read(allowed.path().to_path_buf()).await.unwrap().success);
+        assert!(read(secret.clone()).await.is_err());
+        assert!(read(other.path().to_path_buf()).await.is_err());
+    }
+}
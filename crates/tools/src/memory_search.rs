@@ -9,7 +9,7 @@
 
 use async_trait::async_trait;
 use rustedclaw_core::error::ToolError;
-use rustedclaw_core::memory::{MemoryBackend, MemoryQuery, SearchMode};
+use rustedclaw_core::memory::{MemoryBackend, MemoryQuery, MemoryScope, SearchMode};
 use rustedclaw_core::tool::{Tool, ToolResult};
 use std::sync::Arc;
 
@@ -95,6 +95,9 @@ impl Tool for MemorySearchTool {
                 min_score: 0.0,
                 tags: vec![],
                 mode: SearchMode::Keyword,
+                embedding: None,
+                // Only what the sender of the current run may see
+                scope: MemoryScope::current(),
             };
 
             match backend.search(search_query).await {
@@ -310,8 +313,12 @@ mod tests {
                 source: Some("test".into()),
                 created_at: chrono::Utc::now(),
                 last_accessed: chrono::Utc::now(),
+                access_count: 0,
+                expires_at: None,
                 score: 0.0,
                 embedding: None,
+                embedding_model: None,
+                scope: MemoryScope::default(),
             })
             .await
             .unwrap();
@@ -122,10 +122,18 @@ impl Tool for ShellTool {
 
         debug!(command = %command, timeout = ?self.timeout, "Executing shell command");
 
+        // kill_on_drop: a cancelled run drops this future, which must not
+        // leave the command running in the background.
         let child = if cfg!(target_os = "windows") {
-            Command::new("cmd").args(["/C", command]).output()
+            Command::new("cmd")
+                .args(["/C", command])
+                .kill_on_drop(true)
+                .output()
         } else {
-            Command::new("sh").args(["-c", command]).output()
+            Command::new("sh")
+                .args(["-c", command])
+                .kill_on_drop(true)
+                .output()
         };
 
         // Enforce timeout on command execution
@@ -0,0 +1,329 @@
+//! Web fetch tool — turns a page URL into readable text.
+//!
+//! The companion to `web_search`: fetch a result and read it. Requests go
+//! through the same SSRF policy and size limits as `http_request`
+//! (`[tools.http_request]`). HTML, Markdown, plain text and PDF bodies are
+//! extracted with the document parsers used for ingestion, so scripts,
+//! styles and markup are dropped and headings are kept.
+
+use crate::http_request::{HttpRequestTool, is_text_content_type};
+use async_trait::async_trait;
+use rustedclaw_config::HttpRequestToolConfig;
+use rustedclaw_core::error::ToolError;
+use rustedclaw_core::tool::{Tool, ToolResult};
+use rustedclaw_memory::ingest::{self, DocumentFormat, ParsedDocument};
+use std::collections::HashMap;
+
+const TOOL_NAME: &str = "web_fetch";
+
+pub struct WebFetchTool {
+    http: HttpRequestTool,
+}
+
+impl WebFetchTool {
+    /// Create the tool with the default `[tools.http_request]` limits.
+    pub fn new() -> Self {
+        Self {
+            http: HttpRequestTool::new(),
+        }
+    }
+
+    /// Create the tool from `[tools.http_request]`.
+    pub fn from_config(config: &HttpRequestToolConfig) -> Self {
+        Self {
+            http: HttpRequestTool::from_config(config),
+        }
+    }
+}
+
+impl Default for WebFetchTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for WebFetchTool {
+    fn name(&self) -> &str {
+        TOOL_NAME
+    }
+
+    fn description(&self) -> &str {
+        "Fetch a web page and return its readable text. Use it to read a result found with web_search."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "description": "The page to read"
+                },
+                "max_chars": {
+                    "type": "integer",
+                    "description": format!(
+                        "Most characters of text to return (default {})",
+                        self.http.config().max_body_chars
+                    ),
+                    "default": self.http.config().max_body_chars
+                }
+            },
+            "required": ["url"]
+        })
+    }
+
+    async fn execute(&self, arguments: serde_json::Value) -> Result<ToolResult, ToolError> {
+        let url = arguments["url"]
+            .as_str()
+            .ok_or_else(|| ToolError::InvalidArguments("Missing 'url' argument".into()))?;
+
+        let config = self.http.config();
+        let max_chars = arguments["max_chars"]
+            .as_u64()
+            .map_or(config.max_body_chars, |n| n as usize)
+            .clamp(1, config.max_body_chars);
+
+        let fetched = self
+            .http
+            .fetch(
+                TOOL_NAME,
+                reqwest::Method::GET,
+                url,
+                &HashMap::new(),
+                None,
+                config.timeout_secs,
+            )
+            .await?;
+        let failure = |output: String| {
+            Ok(ToolResult {
+                call_id: String::new(),
+                success: false,
+                output,
+                data: None,
+            })
+        };
+        if !fetched.status.is_success() {
+            return failure(format!("Fetching {} returned HTTP {}", url, fetched.status));
+        }
+
+        let content_type = fetched.headers.get("content-type").map(String::as_str);
+        let Some(format) = document_format(content_type, &fetched.bytes) else {
+            return failure(format!(
+                "Cannot extract text from {} content at {url}",
+                content_type.unwrap_or("binary")
+            ));
+        };
+        let parsed = match ingest::parse(format, &fetched.bytes) {
+            Ok(parsed) => parsed,
+            Err(e) => return failure(format!("Could not extract text from {url}: {e}")),
+        };
+
+        let mut content = readable_text(&parsed);
+        let mut truncated = fetched.over_limit;
+        if let Some((end, _)) = content.char_indices().nth(max_chars) {
+            content.truncate(end);
+            truncated = true;
+        }
+        if truncated {
+            content.push_str("\n[truncated]");
+        }
+
+        let output = match &parsed.title {
+            Some(title) => format!("{title}\n{}\n\n{content}", fetched.url),
+            None => format!("{}\n\n{content}", fetched.url),
+        };
+        Ok(ToolResult {
+            call_id: String::new(),
+            success: true,
+            output,
+            data: Some(serde_json::json!({
+                "url": fetched.url,
+                "title": parsed.title,
+                "content": content,
+                "truncated": truncated,
+            })),
+        })
+    }
+}
+
+/// Pick a parser from the content type, sniffing the body when there is
+/// none. `None` means the content isn't text.
+fn document_format(content_type: Option<&str>, bytes: &[u8]) -> Option<DocumentFormat> {
+    let Some(content_type) = content_type else {
+        let text = std::str::from_utf8(bytes).ok();
+        return if bytes.starts_with(b"%PDF") {
+            Some(DocumentFormat::Pdf)
+        } else if text.is_some_and(|t| t.trim_start().starts_with('<')) {
+            Some(DocumentFormat::Html)
+        } else {
+            text.map(|_| DocumentFormat::Text)
+        };
+    };
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    match mime.as_str() {
+        "text/html" | "application/xhtml+xml" => Some(DocumentFormat::Html),
+        "text/markdown" | "text/x-markdown" => Some(DocumentFormat::Markdown),
+        "application/pdf" => Some(DocumentFormat::Pdf),
+        _ if is_text_content_type(&mime) => Some(DocumentFormat::Text),
+        _ => None,
+    }
+}
+
+/// Render sections as text, with each new heading as a Markdown heading.
+fn readable_text(parsed: &ParsedDocument) -> String {
+    let mut out = String::new();
+    let mut headings: &[String] = &[];
+    for section in &parsed.sections {
+        if section.headings != headings
+            && let Some(heading) = section.headings.last()
+        {
+            out.push_str(&format!(
+                "{} {heading}\n\n",
+                "#".repeat(section.headings.len())
+            ));
+        }
+        headings = &section.headings;
+        if !section.text.is_empty() {
+            out.push_str(&section.text);
+            out.push_str("\n\n");
+        }
+    }
+    out.truncate(out.trim_end().len());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::http::header;
+    use axum::routing::get;
+    use std::net::SocketAddr;
+
+    async fn serve() -> SocketAddr {
+        let app = Router::new()
+            .route(
+                "/article",
+                get(|| async {
+                    axum::response::Html(
+                        "<html><head><title>Ownership</title><style>p{}</style></head><body>\
+                         <nav>Home | Docs</nav><h1>Ownership</h1><p>Each value has an owner.</p>\
+                         <script>track()</script><h2>Borrowing</h2><p>References borrow &amp; \
+                         don't own.</p></body></html>",
+                    )
+                }),
+            )
+            .route("/notes.txt", get(|| async { "plain notes\nline two" }))
+            .route(
+                "/image",
+                get(|| async { ([(header::CONTENT_TYPE, "image/png")], vec![0u8; 16]) }),
+            )
+            .route(
+                "/gone",
+                get(|| async { (axum::http::StatusCode::GONE, "gone") }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        addr
+    }
+
+    fn local_tool() -> WebFetchTool {
+        WebFetchTool::from_config(&HttpRequestToolConfig {
+            allow_private_networks: true,
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn html_pages_become_readable_text() {
+        let addr = serve().await;
+        let result = local_tool()
+            .execute(serde_json::json!({ "url": format!("http://{addr}/article") }))
+            .await
+            .unwrap();
+
+        assert!(result.success, "{}", result.output);
+        let data = result.data.unwrap();
+        assert_eq!(data["title"], "Ownership");
+        assert_eq!(
+            data["content"],
+            "Home | Docs\n\n# Ownership\n\nEach value has an owner.\n\n## Borrowing\n\nReferences borrow & don't own."
+        );
+        assert!(result.output.starts_with("Ownership\nhttp://"));
+    }
+
+    #[tokio::test]
+    async fn long_pages_are_truncated() {
+        let addr = serve().await;
+        let result = local_tool()
+            .execute(serde_json::json!({
+                "url": format!("http://{addr}/notes.txt"),
+                "max_chars": 5
+            }))
+            .await
+            .unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data["content"], "plain\n[truncated]");
+        assert_eq!(data["truncated"], true);
+    }
+
+    #[tokio::test]
+    async fn binary_and_error_responses_fail() {
+        let addr = serve().await;
+        let tool = local_tool();
+
+        let result = tool
+            .execute(serde_json::json!({ "url": format!("http://{addr}/image") }))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("image/png"));
+
+        let result = tool
+            .execute(serde_json::json!({ "url": format!("http://{addr}/gone") }))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("410"));
+    }
+
+    #[tokio::test]
+    async fn private_addresses_are_blocked_by_default() {
+        let result = WebFetchTool::new()
+            .execute(serde_json::json!({ "url": "http://169.254.169.254/latest/meta-data/" }))
+            .await;
+        assert!(matches!(
+            result,
+            Err(ToolError::PermissionDenied { tool_name, .. }) if tool_name == "web_fetch"
+        ));
+    }
+
+    #[test]
+    fn formats_follow_content_type_or_sniffing() {
+        assert_eq!(
+            document_format(Some("text/html; charset=utf-8"), b""),
+            Some(DocumentFormat::Html)
+        );
+        assert_eq!(
+            document_format(Some("application/json"), b"{}"),
+            Some(DocumentFormat::Text)
+        );
+        assert_eq!(document_format(Some("image/png"), b""), None);
+        assert_eq!(
+            document_format(None, b"%PDF-1.4"),
+            Some(DocumentFormat::Pdf)
+        );
+        assert_eq!(
+            document_format(None, b"  <!doctype html>"),
+            Some(DocumentFormat::Html)
+        );
+        assert_eq!(document_format(None, &[0xff, 0x00]), None);
+    }
+}
@@ -0,0 +1,63 @@
+//! Brave Search API backend.
+
+use super::{SearchBackend, SearchResult, collect_results, get_json, http_client};
+use async_trait::async_trait;
+use rustedclaw_core::error::ToolError;
+use std::time::Duration;
+
+const DEFAULT_ENDPOINT: &str = "https://api.search.brave.com/res/v1/web/search";
+
+/// Most results Brave returns per request.
+const MAX_COUNT: usize = 20;
+
+pub struct BraveBackend {
+    api_key: String,
+    endpoint: String,
+    client: reqwest::Client,
+}
+
+impl BraveBackend {
+    /// Query the Brave Search API with the subscription token `api_key`.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            endpoint: DEFAULT_ENDPOINT.into(),
+            client: http_client(Duration::from_secs(15)),
+        }
+    }
+
+    /// Send requests to `endpoint` instead of the public API.
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = http_client(timeout);
+        self
+    }
+}
+
+#[async_trait]
+impl SearchBackend for BraveBackend {
+    fn name(&self) -> &str {
+        "brave"
+    }
+
+    async fn search(&self, query: &str, count: usize) -> Result<Vec<SearchResult>, ToolError> {
+        let count = count.min(MAX_COUNT).to_string();
+        let request = self
+            .client
+            .get(&self.endpoint)
+            .header("Accept", "application/json")
+            .header("X-Subscription-Token", &self.api_key)
+            .query(&[("q", query), ("count", &count)]);
+        let body = get_json(self.name(), request).await?;
+
+        Ok(collect_results(
+            body.pointer("/web/results"),
+            |item, field| item.get(field)?.as_str(),
+            ("title", "url", "description"),
+        ))
+    }
+}
@@ -0,0 +1,95 @@
+//! Generic JSON API backend.
+//!
+//! Sends `GET <endpoint>?<query_param>=<query>` and reads the results from
+//! the response using the dot-separated paths in [`JsonSearchConfig`], so
+//! most search APIs can be used without writing a backend.
+
+use super::{SearchBackend, SearchResult, collect_results, get_json, http_client};
+use async_trait::async_trait;
+use rustedclaw_config::JsonSearchConfig;
+use rustedclaw_core::error::ToolError;
+use std::time::Duration;
+
+pub struct JsonApiBackend {
+    endpoint: String,
+    mapping: JsonSearchConfig,
+    api_key: Option<String>,
+    client: reqwest::Client,
+}
+
+impl JsonApiBackend {
+    /// Query `endpoint`, reading results as described by `mapping`.
+    pub fn new(endpoint: impl Into<String>, mapping: JsonSearchConfig) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            mapping,
+            api_key: None,
+            client: http_client(Duration::from_secs(15)),
+        }
+    }
+
+    /// Send `api_key` in the mapping's `api_key_header`.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = http_client(timeout);
+        self
+    }
+}
+
+#[async_trait]
+impl SearchBackend for JsonApiBackend {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    async fn search(&self, query: &str, count: usize) -> Result<Vec<SearchResult>, ToolError> {
+        let mapping = &self.mapping;
+        let count = count.to_string();
+        let mut params = vec![(mapping.query_param.as_str(), query)];
+        if let Some(count_param) = &mapping.count_param {
+            params.push((count_param, &count));
+        }
+        let mut request = self.client.get(&self.endpoint).query(&params);
+        if let Some(api_key) = &self.api_key {
+            request = request.header(&mapping.api_key_header, api_key);
+        }
+        let body = get_json(self.name(), request).await?;
+
+        Ok(collect_results(
+            at_path(&body, &mapping.results_path),
+            |item, path| at_path(item, path)?.as_str(),
+            (
+                &mapping.title_field,
+                &mapping.url_field,
+                &mapping.snippet_field,
+            ),
+        ))
+    }
+}
+
+/// Follow a dot-separated path of object keys and array indices.
+fn at_path<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    path.split('.')
+        .filter(|key| !key.is_empty())
+        .try_fold(value, |value, key| match key.parse::<usize>() {
+            Ok(index) if value.is_array() => value.get(index),
+            _ => value.get(key),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_walk_objects_and_arrays() {
+        let value = serde_json::json!({"data": {"pages": [{"items": [1]}, {"items": [2]}]}});
+        assert_eq!(at_path(&value, "data.pages.1.items.0"), Some(&2.into()));
+        assert_eq!(at_path(&value, ""), Some(&value));
+        assert!(at_path(&value, "data.missing").is_none());
+    }
+}
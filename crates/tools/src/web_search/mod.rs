@@ -0,0 +1,601 @@
+//! Web search tool — queries a configurable search backend.
+//!
+//! Backends implement [`SearchBackend`]: [`SearxngBackend`] for a SearXNG
+//! instance, [`BraveBackend`] for the Brave Search API and
+//! [`JsonApiBackend`] for any API returning a JSON list of results. Pick
+//! one with `[tools.web_search] backend`. Results are normalized into
+//! [`SearchResult`]s (markup stripped, duplicates and non-web URLs
+//! dropped) and repeated queries are answered from a short-lived cache.
+//!
+//! Without a backend the tool reports that none is configured rather than
+//! inventing results. Pair it with [`web_fetch`](crate::web_fetch) to read
+//! a result's page.
+
+mod brave;
+mod json;
+mod searxng;
+
+pub use brave::BraveBackend;
+pub use json::JsonApiBackend;
+pub use searxng::SearxngBackend;
+
+use async_trait::async_trait;
+use rustedclaw_config::WebSearchToolConfig;
+use rustedclaw_core::error::ToolError;
+use rustedclaw_core::tool::{Tool, ToolResult};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Most results a single query may ask for.
+const MAX_RESULTS: u64 = 10;
+
+const NO_BACKEND: &str = "No web search backend is configured. Set `backend` under \
+                          [tools.web_search] to \"searxng\", \"brave\" or \"json\".";
+
+/// One search hit.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
+
+impl SearchResult {
+    /// Build a result from raw backend fields, stripping markup (such as
+    /// `<strong>` highlights and `&amp;` references) and extra whitespace.
+    pub fn new(title: &str, url: &str, snippet: &str) -> Self {
+        Self {
+            title: plain_text(title),
+            url: url.trim().to_string(),
+            snippet: plain_text(snippet),
+        }
+    }
+}
+
+/// A source of web search results.
+#[async_trait]
+pub trait SearchBackend: Send + Sync {
+    /// Backend name, used in errors and logs.
+    fn name(&self) -> &str;
+
+    /// Results for `query`, best first. Backends that can't limit the
+    /// count may return more than `count`; the tool keeps the first ones.
+    async fn search(&self, query: &str, count: usize) -> Result<Vec<SearchResult>, ToolError>;
+}
+
+/// Build the backend selected by `[tools.web_search]`, if any.
+pub fn backend_from_config(config: &WebSearchToolConfig) -> Option<Arc<dyn SearchBackend>> {
+    let timeout = Duration::from_secs(config.timeout_secs);
+    let endpoint = config.endpoint.clone().unwrap_or_default();
+    let backend: Arc<dyn SearchBackend> = match config.backend.as_deref()? {
+        "searxng" => Arc::new(SearxngBackend::new(endpoint).with_timeout(timeout)),
+        "brave" => {
+            let mut brave =
+                BraveBackend::new(config.api_key.clone().unwrap_or_default()).with_timeout(timeout);
+            if let Some(endpoint) = &config.endpoint {
+                brave = brave.with_endpoint(endpoint);
+            }
+            Arc::new(brave)
+        }
+        "json" => {
+            let mut json = JsonApiBackend::new(endpoint, config.json.clone()).with_timeout(timeout);
+            if let Some(api_key) = &config.api_key {
+                json = json.with_api_key(api_key);
+            }
+            Arc::new(json)
+        }
+        other => {
+            tracing::warn!("Unknown web search backend '{other}'; web_search is disabled");
+            return None;
+        }
+    };
+    Some(backend)
+}
+
+pub struct WebSearchTool {
+    backend: Option<Arc<dyn SearchBackend>>,
+    cache: SearchCache,
+}
+
+impl WebSearchTool {
+    /// Create the tool without a backend; every search fails.
+    pub fn new() -> Self {
+        let defaults = WebSearchToolConfig::default();
+        Self {
+            backend: None,
+            cache: SearchCache::new(
+                Duration::from_secs(defaults.cache_ttl_secs),
+                defaults.cache_capacity,
+            ),
+        }
+    }
+
+    /// Create the tool backed by `backend`.
+    pub fn with_backend(backend: Arc<dyn SearchBackend>) -> Self {
+        Self {
+            backend: Some(backend),
+            ..Self::new()
+        }
+    }
+
+    /// Create the tool from `[tools.web_search]`.
+    pub fn from_config(config: &WebSearchToolConfig) -> Self {
+        Self {
+            backend: backend_from_config(config),
+            cache: SearchCache::new(
+                Duration::from_secs(config.cache_ttl_secs),
+                config.cache_capacity,
+            ),
+        }
+    }
+
+    /// Reuse a query's results for `ttl`, keeping at most `capacity`
+    /// queries. A zero `ttl` disables caching.
+    pub fn with_cache(mut self, ttl: Duration, capacity: usize) -> Self {
+        self.cache = SearchCache::new(ttl, capacity);
+        self
+    }
+}
+
+impl Default for WebSearchTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for WebSearchTool {
+    fn name(&self) -> &str {
+        "web_search"
+    }
+
+    fn description(&self) -> &str {
+        "Search the web for information. Returns a list of relevant results with titles, URLs, and snippets."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "The search query"
+                },
+                "num_results": {
+                    "type": "integer",
+                    "description": "Number of results to return (default 3, max 10)",
+                    "default": 3
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    async fn execute(&self, arguments: serde_json::Value) -> Result<ToolResult, ToolError> {
+        let query = arguments["query"]
+            .as_str()
+            .ok_or_else(|| ToolError::InvalidArguments("Missing 'query' argument".into()))?;
+
+        let Some(backend) = &self.backend else {
+            return Ok(ToolResult {
+                call_id: String::new(),
+                success: false,
+                output: NO_BACKEND.into(),
+                data: None,
+            });
+        };
+
+        let num_results = arguments["num_results"]
+            .as_u64()
+            .unwrap_or(3)
+            .clamp(1, MAX_RESULTS) as usize;
+
+        let results = match self.cache.get(query, num_results) {
+            Some(results) => results,
+            None => match backend.search(query, num_results).await {
+                Ok(results) => {
+                    let results = normalize(results, num_results);
+                    self.cache.put(query, num_results, results.clone());
+                    results
+                }
+                Err(e) => {
+                    return Ok(ToolResult {
+                        call_id: String::new(),
+                        success: false,
+                        output: format!("Web search failed: {e}"),
+                        data: None,
+                    });
+                }
+            },
+        };
+
+        let output = if results.is_empty() {
+            format!("No results found for '{query}'.")
+        } else {
+            serde_json::to_string_pretty(&results).unwrap_or_default()
+        };
+        Ok(ToolResult {
+            call_id: String::new(),
+            success: true,
+            output,
+            data: Some(serde_json::to_value(&results).unwrap()),
+        })
+    }
+}
+
+/// Drop results without a web URL or without any text, and repeats of a
+/// URL already seen; keep at most `count`.
+fn normalize(results: Vec<SearchResult>, count: usize) -> Vec<SearchResult> {
+    let mut seen = std::collections::HashSet::new();
+    results
+        .into_iter()
+        .filter(|r| r.url.starts_with("http://") || r.url.starts_with("https://"))
+        .filter(|r| !r.title.is_empty() || !r.snippet.is_empty())
+        .filter(|r| seen.insert(r.url.split('#').next().unwrap_or_default().to_string()))
+        .take(count)
+        .collect()
+}
+
+/// Strip HTML markup and collapse whitespace.
+fn plain_text(text: &str) -> String {
+    let text = if text.contains(['<', '&']) {
+        let parsed = rustedclaw_memory::ingest::html::parse(text);
+        let sections: Vec<String> = parsed.sections.into_iter().map(|s| s.text).collect();
+        sections.join(" ")
+    } else {
+        text.to_string()
+    };
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Normalized query and result count.
+type CacheKey = (String, usize);
+
+/// Recent results by query and count, expiring after a fixed TTL.
+struct SearchCache {
+    ttl: Duration,
+    capacity: usize,
+    entries: Mutex<HashMap<CacheKey, (Instant, Vec<SearchResult>)>>,
+}
+
+impl SearchCache {
+    fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Queries differing only in case or spacing share an entry.
+    fn key(query: &str, count: usize) -> CacheKey {
+        let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+        (query.to_lowercase(), count)
+    }
+
+    fn get(&self, query: &str, count: usize) -> Option<Vec<SearchResult>> {
+        let entries = self.entries.lock().unwrap();
+        let (stored, results) = entries.get(&Self::key(query, count))?;
+        (stored.elapsed() < self.ttl).then(|| results.clone())
+    }
+
+    fn put(&self, query: &str, count: usize, results: Vec<SearchResult>) {
+        if self.ttl.is_zero() || self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (stored, _)| stored.elapsed() < self.ttl);
+        if entries.len() >= self.capacity
+            && let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, (stored, _))| *stored)
+                .map(|(key, _)| key.clone())
+        {
+            entries.remove(&oldest);
+        }
+        entries.insert(Self::key(query, count), (Instant::now(), results));
+    }
+}
+
+/// HTTP client shared by the backends.
+fn http_client(timeout: Duration) -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .user_agent(concat!("rustedclaw/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .expect("Failed to create HTTP client")
+}
+
+/// Send `request` and parse the JSON body, naming `backend` in errors.
+async fn get_json(
+    backend: &str,
+    request: reqwest::RequestBuilder,
+) -> Result<serde_json::Value, ToolError> {
+    let failed = |reason: String| ToolError::ExecutionFailed {
+        tool_name: "web_search".into(),
+        reason: format!("{backend}: {reason}"),
+    };
+    let response = request.send().await.map_err(|e| failed(e.to_string()))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(failed(format!("HTTP {status}")));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| failed(format!("invalid JSON response: {e}")))
+}
+
+/// Map each object in `items` to a result with the given field readers.
+fn collect_results<'a>(
+    items: Option<&'a serde_json::Value>,
+    field: impl Fn(&'a serde_json::Value, &str) -> Option<&'a str>,
+    (title, url, snippet): (&str, &str, &str),
+) -> Vec<SearchResult> {
+    items
+        .and_then(|items| items.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            Some(SearchResult::new(
+                field(item, title).unwrap_or_default(),
+                field(item, url)?,
+                field(item, snippet).unwrap_or_default(),
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::extract::Query;
+    use axum::http::HeaderMap;
+    use axum::routing::get;
+    use rustedclaw_config::JsonSearchConfig;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    type Params = Query<HashMap<String, String>>;
+
+    /// Stand-ins for SearXNG, Brave and a custom JSON API.
+    async fn serve() -> SocketAddr {
+        let app = Router::new()
+            .route(
+                "/search",
+                get(|Query(params): Params| async move {
+                    assert_eq!(params["format"], "json");
+                    axum::Json(serde_json::json!({
+                        "query": params["q"],
+                        "results": [
+                            {"title": "The Rust Book", "url": "https://doc.rust-lang.org/book/", "content": "Learn <b>Rust</b>"},
+                            {"title": "Rust Book (mirror)", "url": "https://doc.rust-lang.org/book/#intro", "content": "Duplicate"},
+                            {"title": "Magnet", "url": "magnet:?xt=urn:btih:abc", "content": "Not a web page"},
+                            {"title": "Rust by Example", "url": "https://doc.rust-lang.org/rust-by-example/", "content": "Runnable   examples"}
+                        ]
+                    }))
+                }),
+            )
+            .route(
+                "/brave",
+                get(|headers: HeaderMap, Query(params): Params| async move {
+                    if headers["x-subscription-token"] != "brave-key" {
+                        return Err(axum::http::StatusCode::UNAUTHORIZED);
+                    }
+                    let count: usize = params["count"].parse().unwrap();
+                    let results: Vec<_> = (1..=count)
+                        .map(|i| serde_json::json!({
+                            "title": format!("{} &amp; result {i}", params["q"]),
+                            "url": format!("https://example.com/{i}"),
+                            "description": format!("<strong>Snippet</strong> {i}")
+                        }))
+                        .collect();
+                    Ok(axum::Json(serde_json::json!({"web": {"results": results}})))
+                }),
+            )
+            .route(
+                "/api",
+                get(|headers: HeaderMap, Query(params): Params| async move {
+                    axum::Json(serde_json::json!({
+                        "data": {"items": [{
+                            "name": format!("{} ({})", params["query"], params["limit"]),
+                            "link": {"href": "https://api.example.com/item"},
+                            "summary": headers["x-api-key"].to_str().unwrap()
+                        }]}
+                    }))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        addr
+    }
+
+    async fn search(tool: &WebSearchTool, args: serde_json::Value) -> Vec<SearchResult> {
+        let result = tool.execute(args).await.unwrap();
+        assert!(result.success, "{}", result.output);
+        serde_json::from_value(result.data.unwrap()).unwrap()
+    }
+
+    /// Counts calls and answers every query with the same results.
+    struct CountingBackend(AtomicUsize);
+
+    #[async_trait]
+    impl SearchBackend for CountingBackend {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        async fn search(&self, query: &str, count: usize) -> Result<Vec<SearchResult>, ToolError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok((0..count)
+                .map(|i| SearchResult::new(query, &format!("https://example.com/{i}"), ""))
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn searxng_results_are_normalized() {
+        let addr = serve().await;
+        let tool = WebSearchTool::from_config(&WebSearchToolConfig {
+            backend: Some("searxng".into()),
+            endpoint: Some(format!("http://{addr}/")),
+            ..Default::default()
+        });
+
+        let results = search(
+            &tool,
+            serde_json::json!({"query": "rust", "num_results": 5}),
+        )
+        .await;
+        assert_eq!(
+            results,
+            [
+                SearchResult::new(
+                    "The Rust Book",
+                    "https://doc.rust-lang.org/book/",
+                    "Learn Rust"
+                ),
+                SearchResult::new(
+                    "Rust by Example",
+                    "https://doc.rust-lang.org/rust-by-example/",
+                    "Runnable examples"
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn brave_sends_the_token_and_count() {
+        let addr = serve().await;
+        let tool = WebSearchTool::from_config(&WebSearchToolConfig {
+            backend: Some("brave".into()),
+            endpoint: Some(format!("http://{addr}/brave")),
+            api_key: Some("brave-key".into()),
+            ..Default::default()
+        });
+
+        let results = search(
+            &tool,
+            serde_json::json!({"query": "tokio", "num_results": 2}),
+        )
+        .await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].title, "tokio & result 1");
+        assert_eq!(results[0].snippet, "Snippet 1");
+
+        let tool = WebSearchTool::from_config(&WebSearchToolConfig {
+            backend: Some("brave".into()),
+            endpoint: Some(format!("http://{addr}/brave")),
+            api_key: Some("wrong".into()),
+            ..Default::default()
+        });
+        let result = tool
+            .execute(serde_json::json!({"query": "tokio"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("401"), "{}", result.output);
+    }
+
+    #[tokio::test]
+    async fn json_api_follows_configured_paths() {
+        let addr = serve().await;
+        let tool = WebSearchTool::from_config(&WebSearchToolConfig {
+            backend: Some("json".into()),
+            endpoint: Some(format!("http://{addr}/api")),
+            api_key: Some("json-key".into()),
+            json: JsonSearchConfig {
+                query_param: "query".into(),
+                count_param: Some("limit".into()),
+                api_key_header: "X-Api-Key".into(),
+                results_path: "data.items".into(),
+                title_field: "name".into(),
+                url_field: "link.href".into(),
+                snippet_field: "summary".into(),
+            },
+            ..Default::default()
+        });
+
+        let results = search(&tool, serde_json::json!({"query": "widgets"})).await;
+        assert_eq!(
+            results,
+            [SearchResult::new(
+                "widgets (3)",
+                "https://api.example.com/item",
+                "json-key"
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn repeated_queries_hit_the_cache() {
+        let backend = Arc::new(CountingBackend(AtomicUsize::new(0)));
+        let tool = WebSearchTool::with_backend(backend.clone());
+
+        search(&tool, serde_json::json!({"query": "Rust async"})).await;
+        search(&tool, serde_json::json!({"query": "  rust   ASYNC "})).await;
+        assert_eq!(backend.0.load(Ordering::SeqCst), 1);
+
+        // A different count is a different query
+        search(
+            &tool,
+            serde_json::json!({"query": "rust async", "num_results": 1}),
+        )
+        .await;
+        assert_eq!(backend.0.load(Ordering::SeqCst), 2);
+
+        let tool = WebSearchTool::with_backend(backend.clone()).with_cache(Duration::ZERO, 10);
+        search(&tool, serde_json::json!({"query": "rust async"})).await;
+        search(&tool, serde_json::json!({"query": "rust async"})).await;
+        assert_eq!(backend.0.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn cache_evicts_the_oldest_query() {
+        let cache = SearchCache::new(Duration::from_secs(60), 2);
+        for query in ["a", "b", "c"] {
+            cache.put(query, 3, vec![]);
+        }
+        assert!(cache.get("a", 3).is_none());
+        assert!(cache.get("b", 3).is_some());
+        assert!(cache.get("c", 3).is_some());
+    }
+
+    #[tokio::test]
+    async fn without_backend_nothing_is_invented() {
+        let result = WebSearchTool::new()
+            .execute(serde_json::json!({"query": "rust programming"}))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.data.is_none());
+        assert!(result.output.contains("No web search backend"));
+    }
+
+    #[test]
+    fn unknown_backend_is_ignored() {
+        let config = WebSearchToolConfig {
+            backend: Some("google".into()),
+            ..Default::default()
+        };
+        assert!(backend_from_config(&config).is_none());
+    }
+
+    #[tokio::test]
+    async fn missing_query_returns_error() {
+        let tool = WebSearchTool::new();
+        let result = tool.execute(serde_json::json!({})).await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn tool_definition() {
+        let tool = WebSearchTool::new();
+        let def = tool.to_definition();
+        assert_eq!(def.name, "web_search");
+        assert!(!def.description.is_empty());
+    }
+}
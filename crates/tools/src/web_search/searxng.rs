@@ -0,0 +1,53 @@
+//! SearXNG backend.
+//!
+//! Calls `<endpoint>/search?format=json`; the instance must have the JSON
+//! output format enabled under `search.formats`.
+
+use super::{SearchBackend, SearchResult, collect_results, get_json, http_client};
+use async_trait::async_trait;
+use rustedclaw_core::error::ToolError;
+use std::time::Duration;
+
+pub struct SearxngBackend {
+    endpoint: String,
+    client: reqwest::Client,
+}
+
+impl SearxngBackend {
+    /// Query the SearXNG instance at `endpoint`, e.g. `https://searx.example.org`.
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            client: http_client(Duration::from_secs(15)),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = http_client(timeout);
+        self
+    }
+}
+
+#[async_trait]
+impl SearchBackend for SearxngBackend {
+    fn name(&self) -> &str {
+        "searxng"
+    }
+
+    async fn search(&self, query: &str, _count: usize) -> Result<Vec<SearchResult>, ToolError> {
+        let url = format!("{}/search", self.endpoint.trim_end_matches('/'));
+        let request = self
+            .client
+            .get(url)
+            .query(&[("q", query), ("format", "json")]);
+        let body = get_json(self.name(), request).await?;
+
+        // SearXNG has no count parameter: return the whole page and let the
+        // tool keep the first `count` after dropping duplicates
+        Ok(collect_results(
+            body.get("results"),
+            |item, field| item.get(field)?.as_str(),
+            ("title", "url", "content"),
+        ))
+    }
+}